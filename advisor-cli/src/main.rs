@@ -0,0 +1,307 @@
+//! Headless command-line access to the advisor.
+//!
+//! Loads a data file, runs one computation through the same `rust-backend`
+//! code as the desktop app and the web server, and prints the result to
+//! stdout as JSON or CSV. Useful for cron jobs, scripts and attaching a
+//! reproducible result to a bug report without starting the GUI.
+
+use std::env;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use rust_backend::{
+    analysis,
+    currency::ExchangeRates,
+    date,
+    handlers::{self, AppState},
+    migrations,
+    model::Dataset,
+    summary::{self, SummaryRange},
+    JsonRpcRequest, LeanRepl,
+};
+
+const USAGE: &str = "\
+Usage: advisor-cli <command> [options]
+
+Commands:
+  recommend   Today's recommended payments (asks the advisor)
+  summary     Payment totals by month, school, category and child
+  analysis    Deadline conflicts and bank holidays
+
+Options:
+  --data <file>        Data file as saved by the app, or - for stdin
+                       (default: data/data.json)
+  --today <YYYYMMDD>   Day to compute for (default: today in Japan)
+  --from <YYYYMMDD>    First day of the summary
+  --to <YYYYMMDD>      Last day of the summary
+  --format <json|csv>  Output format (default: json)
+  --advisor <path>     Advisor binary (default: from LEAN_BACKEND_PATH)
+  -h, --help           Show this help
+
+CSV output has one row per recommendation, per school of the summary
+or per warning.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Recommend,
+    Summary,
+    Analysis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Debug)]
+struct Options {
+    command: Command,
+    data: String,
+    today: u32,
+    range: SummaryRange,
+    format: Format,
+    advisor: Option<PathBuf>,
+}
+
+/// Parse a YYYYMMDD option value
+fn parse_day(name: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|day| date::parse_day(*day).is_some())
+        .ok_or_else(|| format!("{} must be a date as YYYYMMDD: {}", name, value))
+}
+
+/// Parse the arguments after the program name; `Ok(None)` asks for help
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut command = None;
+    let mut options = Options {
+        command: Command::Recommend,
+        data: "data/data.json".to_string(),
+        today: date::today(),
+        range: SummaryRange::default(),
+        format: Format::Json,
+        advisor: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+        if !arg.starts_with("--") {
+            if command.is_some() {
+                return Err(format!("Unexpected argument: {}", arg));
+            }
+            command = Some(match arg.as_str() {
+                "recommend" => Command::Recommend,
+                "summary" => Command::Summary,
+                "analysis" => Command::Analysis,
+                _ => return Err(format!("Unknown command: {}", arg)),
+            });
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--data" => options.data = value.clone(),
+            "--today" => options.today = parse_day(arg, value)?,
+            "--from" => options.range.from = Some(parse_day(arg, value)?),
+            "--to" => options.range.to = Some(parse_day(arg, value)?),
+            "--format" => {
+                options.format = match value.as_str() {
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    _ => return Err(format!("Unknown format: {}", value)),
+                }
+            }
+            "--advisor" => options.advisor = Some(PathBuf::from(value)),
+            _ => return Err(format!("Unknown option: {}", arg)),
+        }
+    }
+    options.command = command.ok_or("No command given")?;
+    Ok(Some(options))
+}
+
+/// Read the data file and bring it up to the current schema
+fn load_data(path: &str) -> Result<serde_json::Value, String> {
+    let mut text = String::new();
+    if path == "-" {
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Cannot read stdin: {}", e))?;
+    } else {
+        text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    }
+    let mut data: serde_json::Value = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Invalid data file {}: {}", path, e))?;
+    migrations::migrate(&mut data).map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+/// Advisor binary, as the web server finds it unless given
+fn advisor_path(option: Option<PathBuf>) -> PathBuf {
+    option.unwrap_or_else(|| {
+        let lean_backend_path =
+            env::var("LEAN_BACKEND_PATH").unwrap_or_else(|_| "../lean-backend".to_string());
+        PathBuf::from(lean_backend_path)
+            .join(".lake")
+            .join("build")
+            .join("bin")
+            .join("advisor")
+    })
+}
+
+fn to_json<T: serde::Serialize>(value: T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+/// Run the command, returning its JSON result
+async fn run(options: Options) -> Result<serde_json::Value, String> {
+    let data = load_data(&options.data)?;
+    match options.command {
+        Command::Recommend => {
+            let repl = LeanRepl::new(advisor_path(options.advisor));
+            let state = Arc::new(AppState::new(repl));
+            let mut params = handlers::advisor_params(&data);
+            params["today"] = options.today.into();
+            let request =
+                JsonRpcRequest::new("getRecommendation", &params, serde_json::json!("cli"));
+            let response = handlers::send_rpc(state.clone(), request)
+                .await
+                .map_err(|e| e.to_string())?;
+            state.lean_repl.lock().await.stop();
+            match response.error {
+                Some(error) => Err(format!("Advisor error: {}", error.message)),
+                None => Ok(response.result_value().unwrap_or_default()),
+            }
+        }
+        Command::Summary => {
+            let dataset = Dataset::from_value(data).map_err(|e| e.to_string())?;
+            // Only yen amounts are totalled, as no rates are fetched
+            to_json(summary::summarize(
+                &dataset,
+                options.range,
+                &ExchangeRates::default(),
+            ))
+        }
+        Command::Analysis => {
+            let dataset = Dataset::from_value(data).map_err(|e| e.to_string())?;
+            to_json(analysis::analyze(&dataset, options.today))
+        }
+    }
+}
+
+/// Rows of the CSV output of `command`
+fn csv_rows(command: Command, result: &serde_json::Value) -> Vec<serde_json::Value> {
+    let rows = match command {
+        Command::Recommend => result.get("allRecommendations"),
+        Command::Summary => result.get("bySchool"),
+        Command::Analysis => Some(result),
+    };
+    rows.and_then(|r| r.as_array()).cloned().unwrap_or_default()
+}
+
+/// Add the fields of `value` to `out`, nested names joined with `.`
+fn flatten(prefix: &str, value: &serde_json::Value, out: &mut Vec<(String, String)>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields {
+                let name = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(&name, field, out);
+            }
+        }
+        serde_json::Value::String(text) => out.push((prefix.to_string(), text.clone())),
+        serde_json::Value::Null => out.push((prefix.to_string(), String::new())),
+        other => out.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// CSV with a column for every field found in any row
+fn to_csv(rows: &[serde_json::Value]) -> String {
+    let rows: Vec<Vec<(String, String)>> = rows
+        .iter()
+        .map(|row| {
+            let mut fields = Vec::new();
+            flatten("", row, &mut fields);
+            fields
+        })
+        .collect();
+    let mut columns: Vec<&str> = Vec::new();
+    for (name, _) in rows.iter().flatten() {
+        if !columns.contains(&name.as_str()) {
+            columns.push(name);
+        }
+    }
+
+    let mut csv = columns
+        .iter()
+        .map(|c| escape(c))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for row in &rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                row.iter()
+                    .find(|(name, _)| name == column)
+                    .map(|(_, value)| escape(value))
+                    .unwrap_or_default()
+            })
+            .collect();
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let command = options.command;
+    let format = options.format;
+    match run(options).await {
+        Ok(result) => {
+            match format {
+                Format::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&result).unwrap_or_default()
+                ),
+                Format::Csv => print!("{}", to_csv(&csv_rows(command, &result))),
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("advisor-cli: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
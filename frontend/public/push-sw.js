@@ -0,0 +1,30 @@
+/**
+ * プッシュ通知用 Service Worker
+ *
+ * Web サーバーから届く支払い期限の通知を、タブを閉じていても表示する。
+ * 通知の内容は PushMessage（title / body / tag）の JSON。
+ */
+
+self.addEventListener("push", (event) => {
+  const message = event.data ? event.data.json() : {};
+  event.waitUntil(
+    self.registration.showNotification(message.title || "学費支払い管理", {
+      body: message.body || "",
+      tag: message.tag,
+      icon: "/vite.svg",
+    })
+  );
+});
+
+// 通知をクリックしたら、開いているタブを前面に出すか新しく開く
+self.addEventListener("notificationclick", (event) => {
+  event.notification.close();
+  event.waitUntil(
+    self.clients
+      .matchAll({ type: "window", includeUncontrolled: true })
+      .then((clients) => {
+        const client = clients.find((c) => "focus" in c);
+        return client ? client.focus() : self.clients.openWindow("/");
+      })
+  );
+});
@@ -13,6 +13,7 @@ import type {
   GetRecommendationResult,
   GetWeeklyRecommendationsResult,
   JsonRpcResponse,
+  MethodCallStats,
 } from "@/types";
 import { dateToDay } from "@/lib/date-utils";
 
@@ -261,6 +262,22 @@ export async function healthCheck(): Promise<{
   }
 }
 
+/**
+ * アドバイザーのメソッドごとの呼び出し回数・エラー率・遅延（p50/p95）
+ */
+export async function getMethodStats(): Promise<MethodCallStats[]> {
+  if (isTauri()) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke("get_method_stats");
+  } else {
+    const response = await fetch(`${API_BASE_URL}/stats/methods`);
+    if (!response.ok) {
+      throw new Error(`HTTP error: ${response.status}`);
+    }
+    return response.json();
+  }
+}
+
 /**
  * REPL 再起動（Tauri 専用）
  */
@@ -0,0 +1,83 @@
+/**
+ * プッシュ通知の購読 - Web 版のみ
+ *
+ * Service Worker（public/push-sw.js）を登録し、サーバーの VAPID 公開鍵で
+ * 購読してから、購読情報をサーバーに保存する。
+ */
+
+import type { NewPushSubscription, PushSubscription } from "@/types/generated";
+import { isTauri } from "./client";
+
+const API_BASE_URL = import.meta.env.VITE_API_URL || "http://localhost:3001";
+
+/**
+ * このブラウザでプッシュ通知が使えるかどうか
+ */
+export function isPushSupported(): boolean {
+  return (
+    !isTauri() &&
+    typeof navigator !== "undefined" &&
+    "serviceWorker" in navigator &&
+    typeof window !== "undefined" &&
+    "PushManager" in window
+  );
+}
+
+/**
+ * base64url の VAPID 公開鍵を applicationServerKey 用のバイト列に変換
+ */
+function decodeKey(key: string): Uint8Array {
+  const base64 = key.replace(/-/g, "+").replace(/_/g, "/");
+  const padded = base64 + "=".repeat((4 - (base64.length % 4)) % 4);
+  return Uint8Array.from(atob(padded), (c) => c.charCodeAt(0));
+}
+
+/**
+ * 支払い期限の通知をこのブラウザで受け取る
+ * @param user 通知を受け取る人（例: 母）
+ */
+export async function subscribeToPush(user: string): Promise<PushSubscription> {
+  const keyResponse = await fetch(`${API_BASE_URL}/push/key`);
+  if (!keyResponse.ok) {
+    throw new Error(`HTTP error: ${keyResponse.status}`);
+  }
+  const { publicKey } = (await keyResponse.json()) as { publicKey: string };
+
+  const registration = await navigator.serviceWorker.register("/push-sw.js");
+  const subscription = await registration.pushManager.subscribe({
+    userVisibleOnly: true,
+    applicationServerKey: decodeKey(publicKey),
+  });
+
+  const body: NewPushSubscription = {
+    ...(subscription.toJSON() as Omit<NewPushSubscription, "user">),
+    user,
+  };
+  const response = await fetch(`${API_BASE_URL}/push/subscriptions`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify(body),
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP error: ${response.status}`);
+  }
+  return response.json();
+}
+
+/**
+ * このブラウザでの通知をやめる
+ */
+export async function unsubscribeFromPush(): Promise<void> {
+  const registration = await navigator.serviceWorker.getRegistration("/push-sw.js");
+  const subscription = await registration?.pushManager.getSubscription();
+  if (!subscription) {
+    return;
+  }
+  const endpoint = encodeURIComponent(subscription.endpoint);
+  await fetch(`${API_BASE_URL}/push/subscriptions?endpoint=${endpoint}`, {
+    method: "DELETE",
+  });
+  await subscription.unsubscribe();
+}
@@ -5,10 +5,11 @@
  * Web 版では localStorage を使用（フォールバック）。
  */
 
-import type { SchoolWithState } from "@/types";
+import type { BulkOp, ChangeReport, Dataset, SchoolWithState } from "@/types";
 import { isTauri } from "./client";
 
 const STORAGE_KEY = "school-payment-data";
+const API_BASE_URL = import.meta.env.VITE_API_URL || "http://localhost:3001";
 
 /**
  * 学校データを保存
@@ -29,7 +30,7 @@ export async function saveSchools(schools: SchoolWithState[]): Promise<void> {
 export async function loadSchools(): Promise<SchoolWithState[] | null> {
   if (isTauri()) {
     const { invoke } = await import("@tauri-apps/api/core");
-    const data = await invoke<{ schools: SchoolWithState[] } | null>("load_data");
+    const data = await invoke<Dataset | null>("load_data");
     return data?.schools ?? null;
   } else {
     // Web 版: localStorage からフォールバック
@@ -43,3 +44,30 @@ export async function loadSchools(): Promise<SchoolWithState[] | null> {
     }
   }
 }
+
+/**
+ * 複数の学校・支払いの変更をまとめて保存（すべて成功するか、何も変わらないか）
+ * @param ops 変更の一覧
+ * @param expectedRevision 読み込んだときのリビジョン（他で変更されていたら失敗）
+ */
+export async function bulkUpdate(
+  ops: BulkOp[],
+  expectedRevision?: number
+): Promise<ChangeReport<Dataset>> {
+  if (isTauri()) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<ChangeReport<Dataset>>("bulk_update", { ops, expectedRevision });
+  }
+  const query = expectedRevision === undefined ? "" : `?expectedRevision=${expectedRevision}`;
+  const response = await fetch(`${API_BASE_URL}/data/bulk${query}`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify(ops),
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP error: ${response.status}`);
+  }
+  return response.json();
+}
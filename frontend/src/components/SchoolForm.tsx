@@ -132,6 +132,7 @@ export function SchoolForm({
       tuitionDeadline: dateToDay(tuitionDeadline!),
       enrollmentFee: parseInt(enrollmentFee),
       tuition: parseInt(tuition),
+      currency: school?.currency ?? "JPY",
       passStatus: school?.passStatus ?? "notYetAnnounced",
       enrollmentFeePaid: school?.enrollmentFeePaid ?? false,
       tuitionPaid: school?.tuitionPaid ?? false,
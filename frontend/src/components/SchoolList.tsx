@@ -146,6 +146,7 @@ export function SchoolList({
       tuitionDeadline: dateToDay(editData.tuitionDeadline!),
       enrollmentFee: parseInt(editData.enrollmentFee),
       tuition: parseInt(editData.tuition),
+      currency: "JPY",
       passStatus: "notYetAnnounced",
       enrollmentFeePaid: false,
       tuitionPaid: false,
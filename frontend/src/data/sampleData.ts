@@ -32,6 +32,7 @@ export const sampleSchools: SchoolWithState[] = [
     tuitionDeadline: 20260331,       // 3/31 授業料締切（推定）
     enrollmentFee: 282000,           // 入学金 28万2千円
     tuition: 535800,                 // 授業料（年額）53万5800円
+    currency: "JPY",
     passStatus: "notYetAnnounced",
     enrollmentFeePaid: false,
     tuitionPaid: false,
@@ -47,6 +48,7 @@ export const sampleSchools: SchoolWithState[] = [
     tuitionDeadline: 20260324,       // 3/24 第2次振込締切（授業料）
     enrollmentFee: 200000,           // 入学金 20万円
     tuition: 1447000,                // 授業料等（春学期分相当）
+    currency: "JPY",
     passStatus: "notYetAnnounced",
     enrollmentFeePaid: false,
     tuitionPaid: false,
@@ -62,6 +64,7 @@ export const sampleSchools: SchoolWithState[] = [
     tuitionDeadline: 20260324,       // 3/24 授業料支払期限
     enrollmentFee: 200000,           // 入学金 20万円
     tuition: 1480000,                // 授業料等
+    currency: "JPY",
     passStatus: "notYetAnnounced",
     enrollmentFeePaid: false,
     tuitionPaid: false,
@@ -77,6 +80,7 @@ export const sampleSchools: SchoolWithState[] = [
     tuitionDeadline: 20260311,       // 3/11 二次入学手続き締切
     enrollmentFee: 300000,           // 入学金 30万円
     tuition: 1240000,                // 授業料等
+    currency: "JPY",
     passStatus: "notYetAnnounced",
     enrollmentFeePaid: false,
     tuitionPaid: false,
@@ -92,6 +96,7 @@ export const sampleSchools: SchoolWithState[] = [
     tuitionDeadline: 20260325,       // 3/25 延納第二次手続締切
     enrollmentFee: 250000,           // 入学金 25万円
     tuition: 1200000,                // 授業料等
+    currency: "JPY",
     passStatus: "notYetAnnounced",
     enrollmentFeePaid: false,
     tuitionPaid: false,
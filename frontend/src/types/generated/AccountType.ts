@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Kind of Japanese bank account (預金種目)
+ */
+export type AccountType = "ordinary" | "checking" | "savings";
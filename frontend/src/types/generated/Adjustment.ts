@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+
+/**
+ * A scholarship or discount reducing one of a school's fees
+ */
+export type Adjustment = { 
+/**
+ * What the reduction is, such as `特待生奨学金`
+ */
+label: string, fee: FeeKind, amount: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Coarse advisor availability
+ */
+export type AdvisorState = "running" | "starting" | "degraded" | "stopped";
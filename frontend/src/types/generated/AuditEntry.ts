@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuditAction } from "./AuditAction";
+
+/**
+ * One signed entry of the audit log
+ */
+export type AuditEntry = { 
+/**
+ * Position in the log, from 1
+ */
+seq: number, 
+/**
+ * When the entry was recorded (RFC 3339, local time)
+ */
+at: string, action: AuditAction, 
+/**
+ * What changed, such as the payment recorded
+ */
+details: unknown, 
+/**
+ * HMAC of the previous entry; empty for the first
+ */
+prevMac: string, 
+/**
+ * HMAC of this entry's content and `prev_mac`, hex
+ */
+mac: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuditProblemKind } from "./AuditProblemKind";
+
+/**
+ * An entry where verification failed
+ */
+export type AuditProblem = { 
+/**
+ * 1-based position of the entry in the file
+ */
+position: number, kind: AuditProblemKind, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuditProblem } from "./AuditProblem";
+
+/**
+ * Result of verifying the audit log
+ */
+export type AuditVerification = { entries: number, valid: boolean, problems: Array<AuditProblem>, };
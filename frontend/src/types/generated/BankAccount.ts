@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AccountType } from "./AccountType";
+
+/**
+ * A Japanese bank account, as needed for a bank transfer.
+ *
+ * Names are in kana as registered with the bank; see
+ * [`to_bank_kana`](crate::format::to_bank_kana) for what is accepted.
+ */
+export type BankAccount = { 
+/**
+ * 4-digit bank code
+ */
+bankCode: string, bankName: string, 
+/**
+ * 3-digit branch code
+ */
+branchCode: string, branchName: string, accountType: AccountType, 
+/**
+ * Up to 7 digits
+ */
+accountNumber: string, holderName: string, };
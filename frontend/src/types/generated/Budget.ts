@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Spending limits for the season, in yen; absent limits are not checked
+ */
+export type Budget = { total?: number, applicationFees?: number, enrollmentFees?: number, tuition?: number, };
@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentActionType } from "./PaymentActionType";
+import type { PaymentCategory } from "./PaymentCategory";
+
+/**
+ * A recommended payment that would go beyond a limit
+ */
+export type BudgetAlert = { 
+/**
+ * Kind of payment limited, or `None` for the overall budget
+ */
+category: PaymentCategory | null, limit: number, 
+/**
+ * Paid so far, before this payment
+ */
+committed: number, schoolId: number, schoolName: string, actionType: PaymentActionType, 
+/**
+ * Amount of the payment, in yen
+ */
+amount: number, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentCategory } from "./PaymentCategory";
+
+/**
+ * Spend against one limit, in yen
+ */
+export type BudgetLine = { 
+/**
+ * Kind of payment limited, or `None` for the overall budget
+ */
+category: PaymentCategory | null, limit: number, committed: number, projected: number, 
+/**
+ * Limit minus projected spend; negative when over budget
+ */
+remaining: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BudgetLine } from "./BudgetLine";
+
+/**
+ * Spend against each limit that is set, overall first
+ */
+export type BudgetStatus = { lines: Array<BudgetLine>, };
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+import type { PaymentRecord } from "./PaymentRecord";
+import type { School } from "./School";
+
+/**
+ * A change to a school or one of its payments
+ */
+export type BulkOp = { "op": "createSchool", school: School, } | { "op": "updateSchool", schoolId: number, patch: unknown, } | { "op": "deleteSchool", schoolId: number, } | { "op": "recordPayment", payment: PaymentRecord, } | { "op": "deletePayment", schoolId: number, fee: FeeKind, 
+/**
+ * 1-based installment number, for tuition paid in parts
+ */
+installment?: number, };
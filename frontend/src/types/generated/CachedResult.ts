@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A saved advisor result with how old it is
+ */
+export type CachedResult = { result: unknown, 
+/**
+ * When the advisor computed the result (RFC 3339)
+ */
+computedAt: string, 
+/**
+ * Seconds since then
+ */
+ageSecs: number, 
+/**
+ * Parameters the advisor computed the result from
+ */
+params: unknown, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DatasetDiff } from "./DatasetDiff";
+
+/**
+ * What a change to the saved data did or, in a dry run, would do
+ */
+export type ChangeReport<T> = { 
+/**
+ * The operation's result, such as the new data
+ */
+result: T, diff: DatasetDiff, 
+/**
+ * Whether nothing was saved
+ */
+dryRun: boolean, };
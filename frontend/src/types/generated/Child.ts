@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A child applying to schools; siblings applying in the same season
+ * share one dataset, with each school tagged by child
+ */
+export type Child = { id: number, name: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MonthSummary } from "./MonthSummary";
+
+/**
+ * Totals for one child, with its share of each month
+ */
+export type ChildSummary = { 
+/**
+ * `None` for schools not tagged with a child
+ */
+childId: number | null, childName: string | null, total: number, paid: number, byMonth: Array<MonthSummary>, };
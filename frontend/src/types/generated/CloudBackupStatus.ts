@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What the last backups did, for the diagnostics
+ */
+export type CloudBackupStatus = { 
+/**
+ * RFC 3339 time of the last attempt
+ */
+lastAttemptAt: string | null, 
+/**
+ * RFC 3339 time of the last backup the server accepted
+ */
+lastSuccessAt: string | null, 
+/**
+ * Why the last attempt failed, if it did
+ */
+lastError: string | null, 
+/**
+ * RFC 3339 time of the first failure since the last success
+ */
+failingSince: string | null, 
+/**
+ * Day (YYYYMMDD) the failure alert was last sent
+ */
+alertedOn: number | null, };
@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MappingSource } from "./MappingSource";
+
+/**
+ * Which spreadsheet column holds which school field
+ */
+export type ColumnMapping = { name: string, 
+/**
+ * School field (`name`, `examDate`, ...) by column title
+ */
+columns: { [key in string]?: string }, 
+/**
+ * Sheet URL the mapping was last used with
+ */
+url?: string, source: MappingSource, 
+/**
+ * Every column title of the sheet or file the mapping was last used
+ * with, mapped or not; empty until it is first used
+ */
+headers: Array<string>, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OnboardingStep } from "./OnboardingStep";
+
+/**
+ * A step and when it was completed
+ */
+export type CompletedStep = { step: OnboardingStep, 
+/**
+ * RFC 3339 time
+ */
+completedAt: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * ISO 4217 currency of a school's fees
+ */
+export type Currency = "JPY" | "USD" | "EUR" | "GBP" | "AUD" | "CAD" | "NZD" | "CHF" | "SGD" | "HKD" | "CNY" | "KRW";
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GetRecommendationResult } from "./GetRecommendationResult";
+
+/**
+ * Recommendation for one day of a weekly plan
+ */
+export type DailyRecommendation = { day: number, result: GetRecommendationResult, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where the data is kept
+ */
+export type DataLocation = { 
+/**
+ * Directory in use
+ */
+dataDir: string, 
+/**
+ * Directory used unless another was chosen
+ */
+defaultDir: string, 
+/**
+ * Whether another directory was chosen
+ */
+custom: boolean, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Budget } from "./Budget";
+import type { Child } from "./Child";
+import type { MethodFee } from "./MethodFee";
+import type { School } from "./School";
+
+/**
+ * The saved data file
+ */
+export type Dataset = { 
+/**
+ * Format version; missing in files written before versioning
+ */
+schemaVersion: number, 
+/**
+ * Raised by one on every save; see [`crate::revision`]
+ */
+revision?: number, schools: Array<School>, budget?: Budget, children?: Array<Child>, 
+/**
+ * Charges of the payment methods, one rule per method
+ */
+methodFees?: Array<MethodFee>, };
@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SchoolChange } from "./SchoolChange";
+import type { SchoolRef } from "./SchoolRef";
+
+/**
+ * Differences between two versions of the saved dataset
+ */
+export type DatasetDiff = { 
+/**
+ * In the order of the new data
+ */
+added: Array<SchoolRef>, 
+/**
+ * In the order of the old data
+ */
+removed: Array<SchoolRef>, 
+/**
+ * In the order of the new data
+ */
+changed: Array<SchoolChange>, 
+/**
+ * Changed top-level fields other than the schools, such as the budget
+ */
+otherFields: Array<string>, };
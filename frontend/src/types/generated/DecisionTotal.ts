@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Count and amount of recommendations with one decision
+ */
+export type DecisionTotal = { count: number, amount: number, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Code for the user to enter, from the start of the device flow
+ */
+export type DeviceAuthorization = { 
+/**
+ * Passed back to finish signing in
+ */
+deviceCode: string, 
+/**
+ * Code the user enters at `verification_url`
+ */
+userCode: string, verificationUrl: string, expiresIn: number, 
+/**
+ * Seconds to wait between polls
+ */
+interval: number, };
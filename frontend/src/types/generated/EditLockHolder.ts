@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The instance holding the edit lock
+ */
+export type EditLockHolder = { 
+/**
+ * Random id of the holding instance
+ */
+instance: string, 
+/**
+ * Kind of instance, such as `desktop` or `web`
+ */
+label: string, pid: number, 
+/**
+ * When the lock was taken (RFC 3339)
+ */
+acquiredAt: string, 
+/**
+ * When the holder last took it again (RFC 3339)
+ */
+renewedAt: string, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EditLockHolder } from "./EditLockHolder";
+
+/**
+ * The edit lock as seen by one instance
+ */
+export type EditLockStatus = { 
+/**
+ * Whether this instance holds the lock
+ */
+held: boolean, 
+/**
+ * Whether another instance holds it, so this one must not save
+ */
+readOnly: boolean, 
+/**
+ * The holder, if any instance holds the lock
+ */
+holder: EditLockHolder | null, };
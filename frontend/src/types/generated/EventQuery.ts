@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Which events to return
+ */
+export type EventQuery = { 
+/**
+ * Least severe level returned, all levels if unset
+ */
+level?: string, 
+/**
+ * Only events from this module or its submodules
+ */
+module?: string, 
+/**
+ * Most events returned, [`DEFAULT_LIMIT`] if unset
+ */
+limit?: number, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Currency } from "./Currency";
+import type { Rate } from "./Rate";
+
+/**
+ * Known rates, keyed by currency
+ */
+export type ExchangeRates = { rates: { [key in Currency]?: Rate }, };
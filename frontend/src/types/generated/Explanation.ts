@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Why the advisor recommends an action (Lean: Explanation)
+ */
+export type Explanation = { summary: string, 
+/**
+ * Facts the judgement rests on, such as the status of preferred schools
+ */
+factors: Array<string>, };
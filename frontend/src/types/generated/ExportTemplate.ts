@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An export template found in [`TEMPLATES_DIR`]
+ */
+export type ExportTemplate = { 
+/**
+ * Name of the exported file, such as `schools.md`
+ */
+name: string, 
+/**
+ * Extension of the exported file, such as `md`; empty if it has none
+ */
+extension: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A fee due once the results are out
+ */
+export type FeeKind = "enrollmentFee" | "tuition";
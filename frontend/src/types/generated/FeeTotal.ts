@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+
+/**
+ * Total paid in the year for one kind of fee, in yen
+ */
+export type FeeTotal = { fee: FeeKind, count: number, total: number, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One field of a school before and after a change
+ */
+export type FieldChange = { field: string, 
+/**
+ * `null` when the field was added
+ */
+before: unknown, 
+/**
+ * `null` when the field was removed
+ */
+after: unknown, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SensitiveField } from "./SensitiveField";
+
+/**
+ * Whether field encryption is on, for the settings screen
+ */
+export type FieldEncryptionStatus = { 
+/**
+ * Fields encrypted on save
+ */
+fields: Array<SensitiveField>, 
+/**
+ * Whether the key is set up on this device
+ */
+unlocked: boolean, 
+/**
+ * Whether the password is asked for on launch
+ */
+requirePasswordOnLaunch: boolean, };
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentAction } from "./PaymentAction";
+import type { Recommendation } from "./Recommendation";
+import type { StateUpdate } from "./StateUpdate";
+
+/**
+ * Result of `getRecommendation`
+ */
+export type GetRecommendationResult = { action: PaymentAction, reason: string, urgency: number, allRecommendations: Array<Recommendation>, stateUpdates?: Array<StateUpdate>, 
+/**
+ * Id to look up the explanation of the top action with
+ */
+explanationId?: string, };
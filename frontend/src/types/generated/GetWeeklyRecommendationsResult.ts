@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DailyRecommendation } from "./DailyRecommendation";
+import type { UpcomingAnnouncement } from "./UpcomingAnnouncement";
+
+/**
+ * Result of `getWeeklyRecommendations`
+ */
+export type GetWeeklyRecommendationsResult = { startDay: number, recommendations: Array<DailyRecommendation>, upcomingAnnouncements: Array<UpcomingAnnouncement>, note: string | null, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ProfileSyncStatus } from "./ProfileSyncStatus";
+
+/**
+ * Whether sync can be used, and which datasets are synced
+ */
+export type GoogleCalendarStatus = { 
+/**
+ * A `[google_calendar]` client is configured
+ */
+configured: boolean, 
+/**
+ * Signed in to Google
+ */
+connected: boolean, profiles: Array<ProfileSyncStatus>, };
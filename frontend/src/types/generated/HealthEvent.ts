@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AdvisorState } from "./AdvisorState";
+import type { HealthEventKind } from "./HealthEventKind";
+
+/**
+ * One entry of the health history
+ */
+export type HealthEvent = { 
+/**
+ * RFC 3339 time in Japan
+ */
+time: string, kind: HealthEventKind, 
+/**
+ * Status from this event on, for status changes
+ */
+state: AdvisorState | null, reason: string | null, };
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Decision } from "./Decision";
+import type { GetRecommendationResult } from "./GetRecommendationResult";
+import type { PaymentActionType } from "./PaymentActionType";
+
+/**
+ * One recommendation set and the decision taken on it
+ */
+export type HistoryEntry = { id: number, 
+/**
+ * RFC 3339 time the advisor returned the recommendation
+ */
+recordedAt: string, 
+/**
+ * Day the recommendation was for
+ */
+day: number, actionType: PaymentActionType, schoolId: number | null, schoolName: string | null, 
+/**
+ * Amount of the recommended payment, in the school's currency
+ */
+amount: number | null, recommendation: GetRecommendationResult, decision: Decision | null, decidedAt: string | null, note: string | null, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DecisionTotal } from "./DecisionTotal";
+
+/**
+ * Season totals for review
+ */
+export type HistoryStats = { recommendations: number, 
+/**
+ * Recommendations to wait rather than pay
+ */
+waitRecommendations: number, paid: DecisionTotal, skipped: DecisionTotal, deferred: DecisionTotal, undecided: number, 
+/**
+ * Enrollment fees of passed schools whose deadline went by unpaid
+ */
+avoidedEnrollmentFees: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A day banks are closed other than a weekend
+ */
+export type Holiday = { day: number, name: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How to split tuition into installments
+ */
+export type InstallmentPlan = { total: number, 
+/**
+ * Due date of the first payment, normally the tuition deadline
+ */
+firstDue: number, count: number, 
+/**
+ * Months between payments
+ */
+intervalMonths: number, };
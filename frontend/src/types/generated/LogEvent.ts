@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One log event
+ */
+export type LogEvent = { 
+/**
+ * RFC 3339 time in Japan
+ */
+time: string, 
+/**
+ * `TRACE`, `DEBUG`, `INFO`, `WARN` or `ERROR`
+ */
+level: string, 
+/**
+ * Module the event was logged from, such as `rust_backend::handlers`
+ */
+target: string, 
+/**
+ * The message followed by the event's other fields as `name=value`
+ */
+message: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A saved mapping found to fit a header row
+ */
+export type MappingMatch = { name: string, 
+/**
+ * Share of the titles in the mapping and the header that both have,
+ * in percent; 100 for the same layout
+ */
+score: number, };
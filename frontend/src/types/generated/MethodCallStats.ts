@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Statistics of one advisor method
+ */
+export type MethodCallStats = { method: string, 
+/**
+ * Calls since startup
+ */
+calls: number, 
+/**
+ * Calls that failed or were answered with a JSON-RPC error
+ */
+errors: number, 
+/**
+ * `errors` over `calls`, from 0 to 1
+ */
+errorRate: number, 
+/**
+ * Latency percentiles over the latest [`LATENCY_SAMPLES`] calls
+ */
+p50Ms: number, p95Ms: number, };
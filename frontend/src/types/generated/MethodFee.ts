@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentMethod } from "./PaymentMethod";
+
+/**
+ * What paying by one method costs on top of the amount paid, such as a
+ * card surcharge or a convenience store handling fee
+ */
+export type MethodFee = { method: PaymentMethod, 
+/**
+ * Charged on every payment, in yen
+ */
+fixed: number, 
+/**
+ * Share of the amount charged, in basis points (`150` is 1.5%)
+ */
+rateBp: number, 
+/**
+ * Most charged on one payment, in yen; uncapped if absent
+ */
+cap?: number, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PhaseStats } from "./PhaseStats";
+
+/**
+ * Timings of the recent requests of one method
+ */
+export type MethodStats = { method: string, 
+/**
+ * Requests the percentiles are taken over
+ */
+count: number, 
+/**
+ * The whole request
+ */
+total: PhaseStats, 
+/**
+ * Each phase, in [`Phase::ALL`] order
+ */
+phases: Array<PhaseStats>, };
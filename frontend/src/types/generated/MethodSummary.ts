@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentMethod } from "./PaymentMethod";
+
+/**
+ * Totals of the payments made or to be made by one method
+ */
+export type MethodSummary = { method: PaymentMethod, total: number, paid: number, 
+/**
+ * Charges of the method on top of `total`
+ */
+fees: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Totals for one month, with the running total up to its end
+ */
+export type MonthSummary = { 
+/**
+ * YYYYMM
+ */
+month: number, total: number, paid: number, cumulative: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What moving the data directory did
+ */
+export type MovedData = { dataDir: string, files: number, bytes: number, 
+/**
+ * Old files that could not be removed, such as open log files
+ */
+leftBehind: Array<string>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PassStatus } from "./PassStatus";
+
+/**
+ * A change to the dataset
+ */
+export type Mutation = { "type": "decline", schoolId: number, } | { "type": "setResult", schoolId: number, status: PassStatus, } | { "type": "delayResult", schoolId: number, days: number, };
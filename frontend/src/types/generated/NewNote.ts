@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+
+/**
+ * A note to add to a school
+ */
+export type NewNote = { schoolId: number, text: string, 
+/**
+ * Payment the note is about; about the school itself if absent
+ */
+fee?: FeeKind, 
+/**
+ * 1-based installment number, for tuition paid in parts
+ */
+installment?: number, };
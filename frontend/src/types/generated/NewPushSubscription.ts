@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PushKeys } from "./PushKeys";
+
+/**
+ * A subscription as the browser's `PushSubscription.toJSON()` gives it,
+ * with the user it belongs to
+ */
+export type NewPushSubscription = { endpoint: string, keys: PushKeys, 
+/**
+ * Who gets the notifications, such as `母`
+ */
+user: string, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+
+/**
+ * A free-text note on a school or one of its payments
+ */
+export type Note = { text: string, 
+/**
+ * When the note was written (RFC 3339)
+ */
+writtenAt: string, 
+/**
+ * Payment the note is about; about the school itself if absent
+ */
+fee?: FeeKind, 
+/**
+ * 1-based installment number, for a note on tuition paid in parts
+ */
+installment?: number, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CompletedStep } from "./CompletedStep";
+import type { OnboardingStep } from "./OnboardingStep";
+
+/**
+ * How far the setup wizard has got
+ */
+export type OnboardingState = { 
+/**
+ * Steps done, in the order they were completed
+ */
+completed: Array<CompletedStep>, 
+/**
+ * Step to show next; `None` once setup is finished
+ */
+next: OnboardingStep | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Setup steps, in the order the wizard shows them
+ */
+export type OnboardingStep = "locale" | "data" | "reminders" | "advisor";
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExamOutcome } from "./ExamOutcome";
+
+/**
+ * An exam result recorded for a school
+ */
+export type OutcomeEntry = { 
+/**
+ * RFC 3339 time the result was recorded
+ */
+recordedAt: string, schoolId: number, schoolName: string, outcome: ExamOutcome, 
+/**
+ * Schools cancelled by the outcome rules as a result
+ */
+cancelledSchoolIds: Array<number>, };
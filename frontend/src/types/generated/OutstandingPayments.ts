@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Currency } from "./Currency";
+import type { PaymentItem } from "./PaymentItem";
+
+/**
+ * Payments falling in a range, split by whether they were made
+ */
+export type OutstandingPayments = { 
+/**
+ * Unpaid payments of schools still open, earliest first
+ */
+outstanding: Array<PaymentItem>, 
+/**
+ * Payments made, earliest first
+ */
+settled: Array<PaymentItem>, outstandingTotal: number, settledTotal: number, 
+/**
+ * Schools left out of the totals because their currency has no
+ * exchange rate
+ */
+unconvertedSchoolIds: Array<number>, 
+/**
+ * Currencies of the schools whose rate is past its maximum age
+ */
+staleRates: Array<Currency>, };
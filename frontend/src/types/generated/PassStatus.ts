@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result status of a school's exam (Lean: PassStatus)
+ */
+export type PassStatus = "notYetAnnounced" | "passed" | "failed" | "cancelled";
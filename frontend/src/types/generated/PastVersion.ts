@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { VersionSource } from "./VersionSource";
+
+/**
+ * A dataset as it was saved at some point
+ */
+export type PastVersion = { 
+/**
+ * When this version was saved (RFC 3339)
+ */
+savedAt: string, revision: number, source: VersionSource, data: unknown, };
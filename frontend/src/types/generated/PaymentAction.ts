@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentActionType } from "./PaymentActionType";
+
+/**
+ * What the advisor recommends doing (Lean: PaymentAction)
+ */
+export type PaymentAction = { type: PaymentActionType, schoolId?: number, };
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Currency } from "./Currency";
+import type { FeeKind } from "./FeeKind";
+import type { Receipt } from "./Receipt";
+
+/**
+ * One enrollment fee or tuition payment of a school
+ */
+export type PaymentItem = { schoolId: number, schoolName: string, fee: FeeKind, 
+/**
+ * 1-based installment number, for tuition paid in parts
+ */
+installment: number | null, due: number, 
+/**
+ * Amount after adjustments, in the school's currency
+ */
+amount: number, currency: Currency, 
+/**
+ * How the payment was made, if it was recorded
+ */
+receipt: Receipt | null, };
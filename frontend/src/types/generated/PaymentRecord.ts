@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+import type { PaymentMethod } from "./PaymentMethod";
+
+/**
+ * A payment to record as made
+ */
+export type PaymentRecord = { schoolId: number, fee: FeeKind, 
+/**
+ * 1-based installment number, for tuition paid in parts
+ */
+installment?: number, paidOn: number, method: PaymentMethod, reference?: string, attachment?: string, };
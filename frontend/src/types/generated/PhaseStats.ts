@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Phase } from "./Phase";
+
+/**
+ * Percentiles of one phase, in milliseconds
+ */
+export type PhaseStats = { 
+/**
+ * The phase, or none for the whole request
+ */
+phase: Phase | null, meanMs: number, p50Ms: number, p90Ms: number, p99Ms: number, maxMs: number, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentActionType } from "./PaymentActionType";
+
+/**
+ * A payment the advisor would recommend during the plan
+ */
+export type PlannedPayment = { 
+/**
+ * First day the payment is recommended
+ */
+day: number, schoolId: number, schoolName: string, actionType: PaymentActionType, 
+/**
+ * In yen
+ */
+amount: number, };
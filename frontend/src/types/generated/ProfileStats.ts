@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MethodStats } from "./MethodStats";
+
+/**
+ * Profiling state and the timings collected so far
+ */
+export type ProfileStats = { enabled: boolean, 
+/**
+ * Methods in name order
+ */
+methods: Array<MethodStats>, };
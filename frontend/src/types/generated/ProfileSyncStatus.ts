@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Sync settings of one dataset, as shown in the settings screen
+ */
+export type ProfileSyncStatus = { 
+/**
+ * Dataset name, or none for the default dataset
+ */
+profile: string | null, enabled: boolean, 
+/**
+ * Events currently synced
+ */
+eventCount: number, };
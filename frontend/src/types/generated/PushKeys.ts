@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Keys a browser created for a subscription, base64url
+ */
+export type PushKeys = { 
+/**
+ * The browser's P-256 public key
+ */
+p256dh: string, 
+/**
+ * Authentication secret
+ */
+auth: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a notification shows, as the service worker receives it
+ */
+export type PushMessage = { title: string, body: string, 
+/**
+ * Replaces an earlier notification with the same tag
+ */
+tag: string, };
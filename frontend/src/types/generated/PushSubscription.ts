@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PushKeys } from "./PushKeys";
+
+/**
+ * A saved push subscription
+ */
+export type PushSubscription = { endpoint: string, keys: PushKeys, user: string, 
+/**
+ * When the browser subscribed (RFC 3339)
+ */
+createdAt: string, };
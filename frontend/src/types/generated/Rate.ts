@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RateSource } from "./RateSource";
+
+/**
+ * Value of one major unit of a currency in yen
+ */
+export type Rate = { yenPerUnit: number, source: RateSource, 
+/**
+ * RFC 3339 time the rate was set or fetched
+ */
+updatedAt: string, };
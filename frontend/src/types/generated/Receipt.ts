@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+import type { PaymentMethod } from "./PaymentMethod";
+
+/**
+ * Record of a payment made, kept with the school
+ */
+export type Receipt = { fee: FeeKind, 
+/**
+ * 1-based installment number, for tuition paid in parts
+ */
+installment?: number, paidOn: number, method: PaymentMethod, 
+/**
+ * Transfer or receipt number
+ */
+reference?: string, 
+/**
+ * Link to a scan or confirmation of the payment
+ */
+attachment?: string, };
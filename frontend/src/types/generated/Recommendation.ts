@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentAction } from "./PaymentAction";
+
+/**
+ * One recommended action with its reason (Lean: Recommendation)
+ */
+export type Recommendation = { action: PaymentAction, reason: string, 
+/**
+ * Days left until the action is due; 0 means today
+ */
+urgency: number, 
+/**
+ * Id to look up the explanation of this recommendation with
+ */
+explanationId?: string, };
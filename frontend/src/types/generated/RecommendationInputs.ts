@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The exact parameters a recommendation was computed from
+ */
+export type RecommendationInputs = { entryId: number, 
+/**
+ * Day the recommendation was for
+ */
+day: number, 
+/**
+ * RFC 3339 time the advisor returned the recommendation
+ */
+recordedAt: string, 
+/**
+ * `getRecommendation` parameters as sent to the advisor
+ */
+params: unknown, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Dataset } from "./Dataset";
+import type { GetRecommendationResult } from "./GetRecommendationResult";
+import type { OutcomeEntry } from "./OutcomeEntry";
+
+/**
+ * An exam result recorded, with the advisor's recommendation after it
+ */
+export type RecordedOutcome = { entry: OutcomeEntry, dataset: Dataset, 
+/**
+ * Today's recommendation for the updated data; `None` in a dry run or
+ * while the advisor is unavailable
+ */
+recommendation: GetRecommendationResult | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Whether a session is being recorded
+ */
+export type RecordingStatus = { recording: boolean, startedAt: string | null, 
+/**
+ * Requests recorded so far
+ */
+exchanges: number, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A fee due again at a fixed interval, such as monthly tuition or a
+ * facility fee each term
+ */
+export type RecurringFee = { 
+/**
+ * What the fee is, such as `施設設備費`
+ */
+label: string, amount: number, 
+/**
+ * Due date of the first payment
+ */
+firstDue: number, 
+/**
+ * Months between payments: 1 for monthly, 3, 4 or 6 for each term
+ */
+intervalMonths: number, 
+/**
+ * Last day a payment may fall on; open-ended if absent
+ */
+until?: number, };
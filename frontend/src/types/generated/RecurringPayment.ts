@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One payment of a recurring fee, generated from the fee
+ */
+export type RecurringPayment = { 
+/**
+ * Label of the fee it belongs to
+ */
+label: string, due: number, amount: number, paid: boolean, };
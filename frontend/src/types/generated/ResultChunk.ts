@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One part of a result sent in chunks
+ */
+export type ResultChunk = { 
+/**
+ * Position of the chunk, from 0
+ */
+seq: number, 
+/**
+ * The rest of the result besides the chunked items; first chunk only
+ */
+header: unknown, items: Array<unknown>, 
+/**
+ * Number of items in all chunks together
+ */
+total: number, 
+/**
+ * Whether this is the last chunk
+ */
+done: boolean, };
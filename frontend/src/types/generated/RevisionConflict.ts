@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DatasetDiff } from "./DatasetDiff";
+
+/**
+ * A save based on a revision that is no longer the latest
+ */
+export type RevisionConflict = { 
+/**
+ * Revision the client loaded
+ */
+expected: number, 
+/**
+ * Revision saved now
+ */
+current: number, 
+/**
+ * What the stale save would change in the current data
+ */
+diff: DatasetDiff, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Mutation } from "./Mutation";
+
+/**
+ * A named set of changes to compare
+ */
+export type Scenario = { name: string, mutations: Array<Mutation>, };
@@ -0,0 +1,31 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PlannedPayment } from "./PlannedPayment";
+
+/**
+ * How one scenario plays out
+ */
+export type ScenarioOutcome = { name: string, 
+/**
+ * Already paid plus planned payments, in yen
+ */
+totalCost: number, 
+/**
+ * Enrollment fees within the total, which are kept if a place is declined
+ */
+nonRefundableCost: number, 
+/**
+ * Total cost minus the baseline's
+ */
+costDifference: number, plannedPayments: Array<PlannedPayment>, 
+/**
+ * Schools the advisor expects to be cancelled by a missed deadline
+ */
+lapsedSchools: Array<string>, 
+/**
+ * Advisor error, if this scenario could not be planned
+ */
+error: string | null, 
+/**
+ * How long the advisor took to plan this scenario, in milliseconds
+ */
+durationMs: number, };
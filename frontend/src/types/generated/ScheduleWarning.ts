@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentCategory } from "./PaymentCategory";
+
+/**
+ * Something wrong with the schedule
+ */
+export type ScheduleWarning = { "kind": "datesOutOfOrder", schoolId: number, schoolName: string, examDate: number, resultDate: number, } | { "kind": "paymentBeforeResult", schoolId: number, schoolName: string, category: PaymentCategory, deadline: number, resultDate: number, } | { "kind": "overlappingEnrollmentFees", schoolId: number, schoolName: string, deadline: number, 
+/**
+ * Enrollment fee at risk, in the school's currency
+ */
+amount: number, preferredSchoolId: number, preferredSchoolName: string, preferredResultDate: number, } | { "kind": "bankHoliday", schoolId: number, schoolName: string, category: PaymentCategory, deadline: number, 
+/**
+ * Why banks are closed, such as `春分の日` or `土曜日`
+ */
+reason: string, 
+/**
+ * Last day banks are open before the deadline
+ */
+payBy: number, };
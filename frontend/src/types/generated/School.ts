@@ -0,0 +1,67 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Adjustment } from "./Adjustment";
+import type { BankAccount } from "./BankAccount";
+import type { Currency } from "./Currency";
+import type { Installment } from "./Installment";
+import type { Note } from "./Note";
+import type { PassStatus } from "./PassStatus";
+import type { PaymentMethod } from "./PaymentMethod";
+import type { Receipt } from "./Receipt";
+import type { RecurringFee } from "./RecurringFee";
+import type { RecurringPayment } from "./RecurringPayment";
+
+/**
+ * A school with its exam schedule, fees and payment state, as stored
+ */
+export type School = { id: number, name: string, priority: number, examDate: number, resultDate: number, enrollmentFeeDeadline: number, tuitionDeadline: number, enrollmentFee: number, tuition: number, 
+/**
+ * Exam application fee, paid by the exam date; unknown if absent
+ */
+applicationFee?: number, 
+/**
+ * Currency of the fees, in its minor units
+ */
+currency: Currency, passStatus: PassStatus, enrollmentFeePaid: boolean, tuitionPaid: boolean, 
+/**
+ * Account fees are transferred to, for bank transfer files
+ */
+payee?: BankAccount, 
+/**
+ * Tuition paid in parts; the payments replace the single tuition
+ * payment and already account for any tuition adjustments
+ */
+installments?: Array<Installment>, 
+/**
+ * Scholarships and discounts taken off the fees
+ */
+adjustments?: Array<Adjustment>, 
+/**
+ * Child applying to the school, one of the dataset's children
+ */
+childId?: number, 
+/**
+ * Records of the payments made
+ */
+receipts?: Array<Receipt>, 
+/**
+ * Fees due again and again once enrolled, such as monthly tuition
+ */
+recurringFees?: Array<RecurringFee>, 
+/**
+ * Payments generated from `recurring_fees`; see [`expand_recurring`]
+ */
+recurringPayments?: Array<RecurringPayment>, 
+/**
+ * On the waiting list; the pass status stays not yet announced until
+ * the final result
+ */
+waitlisted?: boolean, 
+/**
+ * How the school's fees will be paid, for their method charges;
+ * recorded receipts take precedence for payments already made
+ */
+paymentMethod?: PaymentMethod, 
+/**
+ * Notes on the school and its payments, oldest first
+ */
+notes?: Array<Note>, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FieldChange } from "./FieldChange";
+
+/**
+ * A school kept by a change with some of its fields changed
+ */
+export type SchoolChange = { schoolId: number, 
+/**
+ * Name after the change
+ */
+name: string, 
+/**
+ * Changed fields, by name
+ */
+fields: Array<FieldChange>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A school as sent to the advisor (Lean: SchoolInput)
+ */
+export type SchoolInput = { id: number, name: string, priority: number, examDate: number, resultDate: number, enrollmentFeeDeadline: number, tuitionDeadline: number, enrollmentFee: number, tuition: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A school added or removed, by id and name
+ */
+export type SchoolRef = { 
+/**
+ * `None` for schools without an id
+ */
+id: number | null, name: string, };
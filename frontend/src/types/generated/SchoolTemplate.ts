@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Currency } from "./Currency";
+
+/**
+ * Fees and deadlines of a kind of school
+ */
+export type SchoolTemplate = { id: string, name: string, description: string, 
+/**
+ * Days from the exam to the announcement of results
+ */
+resultDays: number, 
+/**
+ * Days from the exam to the enrollment fee deadline
+ */
+enrollmentFeeDeadlineDays: number, 
+/**
+ * Days from the exam to the tuition deadline
+ */
+tuitionDeadlineDays: number, enrollmentFee: number, tuition: number, applicationFee?: number, currency: Currency, };
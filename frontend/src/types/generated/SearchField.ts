@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a search hit was found, in the order hits are listed
+ */
+export type SearchField = "schoolName" | "note" | "reference" | "adjustment" | "recurringFee";
@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeKind } from "./FeeKind";
+import type { SearchField } from "./SearchField";
+
+/**
+ * A school field matching a search
+ */
+export type SearchHit = { schoolId: number, schoolName: string, field: SearchField, 
+/**
+ * The matching text, as saved
+ */
+text: string, 
+/**
+ * Position in the school's notes, for a note
+ */
+note?: number, 
+/**
+ * Payment the note, receipt or adjustment belongs to
+ */
+fee?: FeeKind, 
+/**
+ * 1-based installment number, for tuition paid in parts
+ */
+installment?: number, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CheckStatus } from "./CheckStatus";
+import type { SelfTestStep } from "./SelfTestStep";
+
+/**
+ * Result of one check
+ */
+export type SelfTestCheck = { step: SelfTestStep, label: string, status: CheckStatus, 
+/**
+ * What was checked, or why it failed or was skipped
+ */
+detail: string, durationMs: number, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SelfTestCheck } from "./SelfTestCheck";
+
+/**
+ * Result of the whole self-test
+ */
+export type SelfTestReport = { 
+/**
+ * Whether no check failed
+ */
+passed: boolean, checks: Array<SelfTestCheck>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Checks, in the order they run
+ */
+export type SelfTestStep = "advisorPing" | "recommendation" | "storage";
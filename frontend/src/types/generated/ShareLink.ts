@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A new share link
+ */
+export type ShareLink = { token: string, 
+/**
+ * RFC 3339 time the link stops working
+ */
+expiresAt: string, };
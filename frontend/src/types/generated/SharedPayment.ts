@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Currency } from "./Currency";
+import type { FeeKind } from "./FeeKind";
+
+/**
+ * One payment of a shared schedule
+ */
+export type SharedPayment = { schoolName: string, fee: FeeKind, installment: number | null, due: number, amount: number, currency: Currency, };
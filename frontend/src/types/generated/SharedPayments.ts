@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SharedPayment } from "./SharedPayment";
+
+/**
+ * Payments of a shared schedule
+ */
+export type SharedPayments = { outstanding: Array<SharedPayment>, settled: Array<SharedPayment>, 
+/**
+ * Totals in yen, leaving out currencies without an exchange rate
+ */
+outstandingTotal: number, settledTotal: number, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SharedPayments } from "./SharedPayments";
+
+/**
+ * The schedule as it was when shared
+ */
+export type SharedSchedule = { 
+/**
+ * RFC 3339 time the link was made
+ */
+createdAt: string, expiresAt: string, 
+/**
+ * Payments still to make and already made, without receipts
+ */
+payments: SharedPayments, };
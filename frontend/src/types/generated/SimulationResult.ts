@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ScenarioOutcome } from "./ScenarioOutcome";
+
+/**
+ * Outcomes of the baseline and each scenario, in request order
+ */
+export type SimulationResult = { baseline: ScenarioOutcome, scenarios: Array<ScenarioOutcome>, 
+/**
+ * How long the whole simulation took, in milliseconds
+ */
+elapsedMs: number, };
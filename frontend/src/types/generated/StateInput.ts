@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PassStatus } from "./PassStatus";
+
+/**
+ * A school's payment state as sent to the advisor (Lean: StateInput)
+ */
+export type StateInput = { schoolId: number, passStatus: PassStatus, enrollmentFeePaid: boolean, tuitionPaid: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A status change the advisor inferred (Lean: StateUpdate)
+ */
+export type StateUpdate = { schoolId: number, schoolName: string, oldStatus: string, newStatus: string, reason: string, };
@@ -0,0 +1,39 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BudgetStatus } from "./BudgetStatus";
+import type { CategorySummary } from "./CategorySummary";
+import type { ChildSummary } from "./ChildSummary";
+import type { Currency } from "./Currency";
+import type { MethodSummary } from "./MethodSummary";
+import type { MonthSummary } from "./MonthSummary";
+import type { SchoolSummary } from "./SchoolSummary";
+
+/**
+ * Payment totals over a range, in yen
+ */
+export type Summary = { currency: Currency, total: number, paid: number, refundable: number, nonRefundable: number, byMonth: Array<MonthSummary>, bySchool: Array<SchoolSummary>, byCategory: Array<CategorySummary>, 
+/**
+ * Totals per payment method, for the payments whose method is known
+ */
+byMethod: Array<MethodSummary>, 
+/**
+ * Charges of the payment methods on top of `total`, so `total` plus
+ * this is what comes out of pocket
+ */
+methodFees: number, 
+/**
+ * Totals per child, empty unless the dataset has children
+ */
+byChild: Array<ChildSummary>, 
+/**
+ * Schools left out because their currency has no exchange rate
+ */
+unconvertedSchoolIds: Array<number>, 
+/**
+ * Currencies of the schools whose rate is past its maximum age, such
+ * as the last known rate when the rate service could not be reached
+ */
+staleRates: Array<Currency>, 
+/**
+ * Season spend against the budget, when one is set
+ */
+budget: BudgetStatus | null, };
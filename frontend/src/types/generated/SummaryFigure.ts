@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentCategory } from "./PaymentCategory";
+import type { PaymentMethod } from "./PaymentMethod";
+
+/**
+ * A figure of a [`Summary`]
+ */
+export type SummaryFigure = { "figure": "total" } | { "figure": "paid" } | { "figure": "refundable" } | { "figure": "nonRefundable" } | { "figure": "methodFees" } | { "figure": "month", month: number, } | { "figure": "school", schoolId: number, } | { "figure": "category", category: PaymentCategory, } | { "figure": "method", method: PaymentMethod, } | { "figure": "child", childId: number | null, };
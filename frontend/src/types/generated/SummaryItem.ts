@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Currency } from "./Currency";
+import type { PaymentCategory } from "./PaymentCategory";
+import type { PaymentMethod } from "./PaymentMethod";
+
+/**
+ * One payment counted in a [`Summary`], in yen
+ */
+export type SummaryItem = { schoolId: number, schoolName: string, childId: number | null, category: PaymentCategory, 
+/**
+ * Due date, or the exam date for application fees
+ */
+day: number, amount: number, paid: boolean, method: PaymentMethod | null, 
+/**
+ * Charge of `method` on top of `amount`
+ */
+methodFee: number, 
+/**
+ * The school's currency, and the amount in it before conversion
+ */
+currency: Currency, originalAmount: number, };
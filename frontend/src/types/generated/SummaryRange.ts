@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Days to include, both ends inclusive and optional
+ */
+export type SummaryRange = { from?: number, to?: number, 
+/**
+ * Only the schools of this child
+ */
+childId?: number, };
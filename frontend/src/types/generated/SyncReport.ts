@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What one sync did
+ */
+export type SyncReport = { 
+/**
+ * Payments recorded as made from Google Calendar
+ */
+completed: number, 
+/**
+ * Deadlines moved from Google Calendar
+ */
+moved: number, created: number, updated: number, deleted: number, };
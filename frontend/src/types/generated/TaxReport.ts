@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeeTotal } from "./FeeTotal";
+import type { TaxReportLine } from "./TaxReportLine";
+
+/**
+ * Payments made in a calendar year
+ */
+export type TaxReport = { year: number, 
+/**
+ * Payments in date order
+ */
+lines: Array<TaxReportLine>, 
+/**
+ * Totals for the kinds of fee paid, enrollment fees first
+ */
+byFee: Array<FeeTotal>, total: number, 
+/**
+ * Payments dated by their deadline because no receipt was recorded
+ */
+withoutReceipt: number, 
+/**
+ * Schools left out of the totals because their currency has no
+ * exchange rate
+ */
+unconvertedSchoolIds: Array<number>, };
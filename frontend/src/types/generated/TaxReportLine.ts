@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PaymentItem } from "./PaymentItem";
+
+/**
+ * One payment made in the year
+ */
+export type TaxReportLine = { 
+/**
+ * Day the payment was made, or its deadline without a receipt
+ */
+paidOn: number, 
+/**
+ * Name of the child the school is for, if tagged
+ */
+childName: string | null, payment: PaymentItem, 
+/**
+ * Amount in yen, `None` if the currency has no exchange rate
+ */
+amountYen: number | null, };
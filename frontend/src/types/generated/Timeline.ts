@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TimelineLane } from "./TimelineLane";
+
+/**
+ * Lanes of all schools in priority order, with the days they cover
+ */
+export type Timeline = { 
+/**
+ * Earliest day in any lane, `None` without schools
+ */
+start: number | null, 
+/**
+ * Latest day in any lane
+ */
+end: number | null, lanes: Array<TimelineLane>, };
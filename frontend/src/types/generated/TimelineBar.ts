@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BarKind } from "./BarKind";
+
+/**
+ * A span of days in a lane, both ends included
+ */
+export type TimelineBar = { kind: BarKind, start: number, end: number, };
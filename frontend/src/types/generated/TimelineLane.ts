@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PassStatus } from "./PassStatus";
+import type { TimelineBar } from "./TimelineBar";
+import type { TimelineMarker } from "./TimelineMarker";
+
+/**
+ * Bars and markers of one school
+ */
+export type TimelineLane = { schoolId: number, schoolName: string, childId: number | null, priority: number, passStatus: PassStatus, bars: Array<TimelineBar>, 
+/**
+ * Markers in date order
+ */
+markers: Array<TimelineMarker>, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MarkerKind } from "./MarkerKind";
+
+/**
+ * A single day in a lane
+ */
+export type TimelineMarker = { kind: MarkerKind, day: number, 
+/**
+ * 1-based installment number, for tuition paid in parts
+ */
+installment: number | null, 
+/**
+ * Whether the payment was made, for deadlines
+ */
+paid: boolean | null, 
+/**
+ * Last day banks are open, for deadlines on days they are closed
+ */
+payBy: number | null, };
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SummaryFigure } from "./SummaryFigure";
+import type { SummaryItem } from "./SummaryItem";
+
+/**
+ * The payments adding up to a figure
+ */
+export type TotalExplanation = { figure: SummaryFigure, 
+/**
+ * The figure, as in the summary: the sum of the items' amounts, or of
+ * their method fees for [`SummaryFigure::MethodFees`]
+ */
+value: number, 
+/**
+ * In date order
+ */
+items: Array<SummaryItem>, 
+/**
+ * Schools left out because their currency has no exchange rate
+ */
+unconvertedSchoolIds: Array<number>, };
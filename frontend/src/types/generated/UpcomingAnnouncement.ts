@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A school whose results are due soon
+ */
+export type UpcomingAnnouncement = { schoolId: number, schoolName: string, resultDay: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a past version of a dataset was found
+ */
+export type VersionSource = "journal" | "backup" | "current";
@@ -0,0 +1,135 @@
+// Re-exports of the definitions generated by ts-rs from rust_backend.
+export type { AccountType } from "./AccountType";
+export type { Adjustment } from "./Adjustment";
+export type { AdvisorState } from "./AdvisorState";
+export type { AuditAction } from "./AuditAction";
+export type { AuditEntry } from "./AuditEntry";
+export type { AuditProblem } from "./AuditProblem";
+export type { AuditProblemKind } from "./AuditProblemKind";
+export type { AuditVerification } from "./AuditVerification";
+export type { BankAccount } from "./BankAccount";
+export type { BarKind } from "./BarKind";
+export type { Budget } from "./Budget";
+export type { BudgetAlert } from "./BudgetAlert";
+export type { BudgetLine } from "./BudgetLine";
+export type { BudgetStatus } from "./BudgetStatus";
+export type { BulkOp } from "./BulkOp";
+export type { CachedResult } from "./CachedResult";
+export type { CategorySummary } from "./CategorySummary";
+export type { ChangeReport } from "./ChangeReport";
+export type { CheckStatus } from "./CheckStatus";
+export type { Child } from "./Child";
+export type { ChildSummary } from "./ChildSummary";
+export type { CloudBackupStatus } from "./CloudBackupStatus";
+export type { ColumnMapping } from "./ColumnMapping";
+export type { CompletedStep } from "./CompletedStep";
+export type { Currency } from "./Currency";
+export type { DailyRecommendation } from "./DailyRecommendation";
+export type { DataLocation } from "./DataLocation";
+export type { Dataset } from "./Dataset";
+export type { DatasetDiff } from "./DatasetDiff";
+export type { Decision } from "./Decision";
+export type { DecisionTotal } from "./DecisionTotal";
+export type { DeviceAuthorization } from "./DeviceAuthorization";
+export type { EditLockHolder } from "./EditLockHolder";
+export type { EditLockStatus } from "./EditLockStatus";
+export type { EventQuery } from "./EventQuery";
+export type { ExamOutcome } from "./ExamOutcome";
+export type { ExchangeRates } from "./ExchangeRates";
+export type { Explanation } from "./Explanation";
+export type { ExportTemplate } from "./ExportTemplate";
+export type { FeeKind } from "./FeeKind";
+export type { FeeTotal } from "./FeeTotal";
+export type { FieldChange } from "./FieldChange";
+export type { FieldEncryptionStatus } from "./FieldEncryptionStatus";
+export type { GetRecommendationResult } from "./GetRecommendationResult";
+export type { GetWeeklyRecommendationsResult } from "./GetWeeklyRecommendationsResult";
+export type { GoogleCalendarStatus } from "./GoogleCalendarStatus";
+export type { HealthEvent } from "./HealthEvent";
+export type { HealthEventKind } from "./HealthEventKind";
+export type { HistoryEntry } from "./HistoryEntry";
+export type { HistoryStats } from "./HistoryStats";
+export type { Holiday } from "./Holiday";
+export type { Installment } from "./Installment";
+export type { InstallmentPlan } from "./InstallmentPlan";
+export type { LogEvent } from "./LogEvent";
+export type { MappingMatch } from "./MappingMatch";
+export type { MappingSource } from "./MappingSource";
+export type { MarkerKind } from "./MarkerKind";
+export type { MethodCallStats } from "./MethodCallStats";
+export type { MethodFee } from "./MethodFee";
+export type { MethodStats } from "./MethodStats";
+export type { MethodSummary } from "./MethodSummary";
+export type { MonthSummary } from "./MonthSummary";
+export type { MovedData } from "./MovedData";
+export type { Mutation } from "./Mutation";
+export type { NewNote } from "./NewNote";
+export type { NewPushSubscription } from "./NewPushSubscription";
+export type { Note } from "./Note";
+export type { OnboardingState } from "./OnboardingState";
+export type { OnboardingStep } from "./OnboardingStep";
+export type { OutcomeEntry } from "./OutcomeEntry";
+export type { OutstandingPayments } from "./OutstandingPayments";
+export type { PassStatus } from "./PassStatus";
+export type { PastVersion } from "./PastVersion";
+export type { PaymentAction } from "./PaymentAction";
+export type { PaymentActionType } from "./PaymentActionType";
+export type { PaymentCategory } from "./PaymentCategory";
+export type { PaymentItem } from "./PaymentItem";
+export type { PaymentMethod } from "./PaymentMethod";
+export type { PaymentRecord } from "./PaymentRecord";
+export type { Phase } from "./Phase";
+export type { PhaseStats } from "./PhaseStats";
+export type { PlannedPayment } from "./PlannedPayment";
+export type { ProfileStats } from "./ProfileStats";
+export type { ProfileSyncStatus } from "./ProfileSyncStatus";
+export type { PushKeys } from "./PushKeys";
+export type { PushMessage } from "./PushMessage";
+export type { PushSubscription } from "./PushSubscription";
+export type { Rate } from "./Rate";
+export type { RateSource } from "./RateSource";
+export type { Receipt } from "./Receipt";
+export type { Recommendation } from "./Recommendation";
+export type { RecommendationInputs } from "./RecommendationInputs";
+export type { RecordedOutcome } from "./RecordedOutcome";
+export type { RecordingStatus } from "./RecordingStatus";
+export type { RecurringFee } from "./RecurringFee";
+export type { RecurringPayment } from "./RecurringPayment";
+export type { ResultChunk } from "./ResultChunk";
+export type { RevisionConflict } from "./RevisionConflict";
+export type { Scenario } from "./Scenario";
+export type { ScenarioOutcome } from "./ScenarioOutcome";
+export type { ScheduleWarning } from "./ScheduleWarning";
+export type { School } from "./School";
+export type { SchoolChange } from "./SchoolChange";
+export type { SchoolInput } from "./SchoolInput";
+export type { SchoolRef } from "./SchoolRef";
+export type { SchoolSummary } from "./SchoolSummary";
+export type { SchoolTemplate } from "./SchoolTemplate";
+export type { SearchField } from "./SearchField";
+export type { SearchHit } from "./SearchHit";
+export type { SelfTestCheck } from "./SelfTestCheck";
+export type { SelfTestReport } from "./SelfTestReport";
+export type { SelfTestStep } from "./SelfTestStep";
+export type { SensitiveField } from "./SensitiveField";
+export type { ShareLink } from "./ShareLink";
+export type { SharedPayment } from "./SharedPayment";
+export type { SharedPayments } from "./SharedPayments";
+export type { SharedSchedule } from "./SharedSchedule";
+export type { SimulationResult } from "./SimulationResult";
+export type { StateInput } from "./StateInput";
+export type { StateUpdate } from "./StateUpdate";
+export type { Summary } from "./Summary";
+export type { SummaryFigure } from "./SummaryFigure";
+export type { SummaryItem } from "./SummaryItem";
+export type { SummaryRange } from "./SummaryRange";
+export type { SyncReport } from "./SyncReport";
+export type { TaxReport } from "./TaxReport";
+export type { TaxReportLine } from "./TaxReportLine";
+export type { Timeline } from "./Timeline";
+export type { TimelineBar } from "./TimelineBar";
+export type { TimelineLane } from "./TimelineLane";
+export type { TimelineMarker } from "./TimelineMarker";
+export type { TotalExplanation } from "./TotalExplanation";
+export type { UpcomingAnnouncement } from "./UpcomingAnnouncement";
+export type { VersionSource } from "./VersionSource";
@@ -2,8 +2,147 @@
  * 型定義 - Leanバックエンドの型と対応
  */
 
-/** 合否状態（Lean: PassStatus） */
-export type PassStatus = "notYetAnnounced" | "passed" | "failed" | "cancelled";
+import type { PassStatus, School } from "./generated";
+
+/**
+ * ドメインモデル - rust_backend から ts-rs で生成（`cargo test` で更新）
+ */
+export type {
+  PassStatus,
+  Currency,
+  AccountType,
+  BankAccount,
+  School,
+  Dataset,
+  SchoolInput,
+  StateInput,
+  PaymentActionType,
+  PaymentAction,
+  Recommendation,
+  StateUpdate,
+  GetRecommendationResult,
+  DailyRecommendation,
+  UpcomingAnnouncement,
+  GetWeeklyRecommendationsResult,
+  ExchangeRates,
+  Rate,
+  RateSource,
+  SummaryRange,
+  Summary,
+  MonthSummary,
+  SchoolSummary,
+  CategorySummary,
+  PaymentCategory,
+  Decision,
+  DecisionTotal,
+  HistoryEntry,
+  HistoryStats,
+  Mutation,
+  Scenario,
+  PlannedPayment,
+  ScenarioOutcome,
+  SimulationResult,
+  Budget,
+  BudgetLine,
+  BudgetStatus,
+  BudgetAlert,
+  SchoolTemplate,
+  ScheduleWarning,
+  Installment,
+  InstallmentPlan,
+  FeeKind,
+  Adjustment,
+  Child,
+  ChildSummary,
+  PaymentMethod,
+  Receipt,
+  PaymentRecord,
+  PaymentItem,
+  OutstandingPayments,
+  Timeline,
+  TimelineLane,
+  TimelineBar,
+  TimelineMarker,
+  BarKind,
+  MarkerKind,
+  TaxReport,
+  TaxReportLine,
+  FeeTotal,
+  Explanation,
+  Holiday,
+  LogEvent,
+  EventQuery,
+  SelfTestStep,
+  CheckStatus,
+  SelfTestCheck,
+  SelfTestReport,
+  Phase,
+  PhaseStats,
+  MethodStats,
+  ProfileStats,
+  AdvisorState,
+  HealthEventKind,
+  HealthEvent,
+  DeviceAuthorization,
+  GoogleCalendarStatus,
+  ProfileSyncStatus,
+  SyncReport,
+  ColumnMapping,
+  SensitiveField,
+  FieldEncryptionStatus,
+  AuditAction,
+  AuditEntry,
+  AuditProblemKind,
+  AuditProblem,
+  AuditVerification,
+  RecordingStatus,
+  ResultChunk,
+  CachedResult,
+  ChangeReport,
+  DatasetDiff,
+  FieldChange,
+  SchoolChange,
+  SchoolRef,
+  EditLockHolder,
+  EditLockStatus,
+  DataLocation,
+  MovedData,
+  ExportTemplate,
+  RecurringFee,
+  RecurringPayment,
+  MethodFee,
+  MethodSummary,
+  ExamOutcome,
+  OutcomeEntry,
+  RecordedOutcome,
+  RecommendationInputs,
+  RevisionConflict,
+  CloudBackupStatus,
+  ShareLink,
+  SharedSchedule,
+  SharedPayments,
+  SharedPayment,
+  OnboardingStep,
+  CompletedStep,
+  OnboardingState,
+  SummaryItem,
+  SummaryFigure,
+  TotalExplanation,
+  MappingMatch,
+  MappingSource,
+  Note,
+  NewNote,
+  SearchField,
+  SearchHit,
+  PushKeys,
+  NewPushSubscription,
+  PushSubscription,
+  PushMessage,
+  VersionSource,
+  PastVersion,
+  BulkOp,
+  MethodCallStats,
+} from "./generated";
 
 /** 合否状態の日本語ラベル */
 export const passStatusLabels: Record<PassStatus, string> = {
@@ -14,96 +153,10 @@ export const passStatusLabels: Record<PassStatus, string> = {
 };
 
 /**
- * 学校の状態を含む情報（フロントエンド内部用）
+ * 学校の状態を含む情報（保存データの学校と同じ）
  * 日付はYYYYMMDD形式の整数（例: 20260225 = 2026年2月25日）
  */
-export interface SchoolWithState {
-  id: number;
-  name: string;
-  priority: number;
-  examDate: number; // YYYYMMDD形式
-  resultDate: number;
-  enrollmentFeeDeadline: number;
-  tuitionDeadline: number;
-  enrollmentFee: number;
-  tuition: number;
-  passStatus: PassStatus;
-  enrollmentFeePaid: boolean;
-  tuitionPaid: boolean;
-}
-
-/** API用学校情報（Lean: SchoolInput） */
-export interface SchoolInput {
-  id: number;
-  name: string;
-  priority: number;
-  examDate: number; // YYYYMMDD形式
-  resultDate: number;
-  enrollmentFeeDeadline: number;
-  tuitionDeadline: number;
-  enrollmentFee: number;
-  tuition: number;
-}
-
-/** API用状態情報（Lean: StateInput） */
-export interface StateInput {
-  schoolId: number;
-  passStatus: string;
-  enrollmentFeePaid: boolean;
-  tuitionPaid: boolean;
-}
-
-/** 支払いアクション（Lean: PaymentAction） */
-export interface PaymentAction {
-  type: "payEnrollmentFee" | "payTuition" | "doNothing";
-  schoolId?: number;
-}
-
-/** 推奨アクション（Lean: Recommendation） */
-export interface Recommendation {
-  action: PaymentAction;
-  reason: string;
-  urgency: number; // 0 = 本日期限
-}
-
-/** 状態更新情報（Lean: StateUpdate） */
-export interface StateUpdate {
-  schoolId: number;
-  schoolName: string;
-  oldStatus: string;
-  newStatus: string;
-  reason: string;
-}
-
-/** getRecommendation レスポンス */
-export interface GetRecommendationResult {
-  action: PaymentAction;
-  reason: string;
-  urgency: number;
-  allRecommendations: Recommendation[];
-  stateUpdates?: StateUpdate[];
-}
-
-/** 1日分の推奨アクション（Lean APIレスポンス） */
-export interface DailyRecommendation {
-  day: number;
-  result: GetRecommendationResult;
-}
-
-/** 発表予定の学校情報（Lean APIレスポンス） */
-export interface UpcomingAnnouncement {
-  schoolId: number;
-  schoolName: string;
-  resultDay: number;
-}
-
-/** getWeeklyRecommendations レスポンス（Lean APIレスポンス） */
-export interface GetWeeklyRecommendationsResult {
-  startDay: number;
-  recommendations: DailyRecommendation[];
-  upcomingAnnouncements: UpcomingAnnouncement[];
-  note: string | null;
-}
+export type SchoolWithState = School;
 
 /** JSON-RPC リクエスト */
 export interface JsonRpcRequest {
@@ -6,6 +6,7 @@
   使用方法:
   - `lake exe advisor` : サンプル実行
   - `lake exe advisor --repl` : REPLモード（JSON-RPC over stdin/stdout）
+  - `lake exe advisor --repl --verbose` : 各リクエストとレスポンスを標準エラーにも出力
 -/
 
 import SchoolPayment
@@ -37,7 +38,7 @@ def runDemo : IO Unit := do
   IO.println response2
 
 /-- REPLモード: 標準入力から1行ずつ読み取り処理 -/
-def runRepl : IO Unit := do
+def runRepl (verbose : Bool) : IO Unit := do
   IO.println "{\"jsonrpc\":\"2.0\",\"result\":\"ready\",\"id\":0}"
   (← IO.getStdout).flush
 
@@ -51,13 +52,17 @@ def runRepl : IO Unit := do
     let trimmed := line.trimAscii.toString
     if trimmed.isEmpty then
       continue
+    if verbose then
+      IO.eprintln s!"[request] {trimmed}"
     let response := processJsonRpc trimmed
+    if verbose then
+      IO.eprintln s!"[response] {response}"
     stdout.putStrLn response
     stdout.flush
 
 /-- メインエントリポイント -/
 def main (args : List String) : IO Unit := do
   if args.contains "--repl" then
-    runRepl
+    runRepl (args.contains "--verbose")
   else
     runDemo
@@ -165,6 +165,20 @@ instance : FromJson PassStatus where
     | "cancelled" => return .Cancelled
     | _ => throw s!"Unknown pass status: {s}"
 
+/--
+  PaymentAction のパース（ToJson と同じタグ付きオブジェクト）
+
+  未知の type が渡された場合はエラーを返す。
+-/
+instance : FromJson PaymentAction where
+  fromJson? j := do
+    let type ← j.getObjValAs? String "type"
+    match type with
+    | "payEnrollmentFee" => return .PayEnrollmentFee (← j.getObjValAs? Nat "schoolId")
+    | "payTuition" => return .PayTuition (← j.getObjValAs? Nat "schoolId")
+    | "doNothing" => return .DoNothing
+    | _ => throw s!"Unknown action type: {type}"
+
 /-! ## PaymentStatus のパース -/
 
 -- mkPaymentStatus は Types.lean で定義
@@ -17,6 +17,7 @@
   【サポートするメソッド】
   - getRecommendation: 支払い推奨アクションを取得
   - ping: 接続確認（"pong"を返す）
+  - getVersion: アドバイザーのバージョンと対応データスキーマ版を返す
 -/
 
 import SchoolPayment.Types
@@ -452,6 +453,130 @@ def executeGetWeeklyRecommendations (params : GetWeeklyRecommendationsParams) :
     note := note
   }
 
+/-! ## 推奨の説明 -/
+
+/--
+  explain メソッドのパラメータ
+  - today, schools, states: getRecommendation と同じ
+  - action: 説明するアクション
+-/
+structure ExplainParams where
+  today : Nat
+  schools : Array SchoolInput
+  states : Array StateInput
+  action : PaymentAction
+deriving Repr
+
+instance : FromJson ExplainParams where
+  fromJson? j := do
+    let today ← j.getObjValAs? Nat "today"
+    let schools ← j.getObjValAs? (Array SchoolInput) "schools"
+    let states ← j.getObjValAs? (Array StateInput) "states"
+    let action ← j.getObjValAs? PaymentAction "action"
+    return ⟨today, schools, states, action⟩
+
+/--
+  explain メソッドのレスポンス
+  - summary: 判断の要約
+  - factors: 判断に使った事実（上位校の状況、期限までの日数など）
+-/
+structure Explanation where
+  summary : String
+  factors : List String
+deriving Repr
+
+instance : ToJson Explanation where
+  toJson e := Json.mkObj [
+    ("summary", toJson e.summary),
+    ("factors", toJson e.factors)
+  ]
+
+/-- 学校の合否と支払いの状況を1行で説明 -/
+def describeSchoolState (s : SchoolState) (today : Date) : String :=
+  match s.passStatus with
+  | .NotYetAnnounced => s!"{s.school.name}: 発表待ち（発表日 {s.school.resultDate.day}）"
+  | .Failed => s!"{s.school.name}: 不合格"
+  | .Cancelled => s!"{s.school.name}: 合格取り消し"
+  | .Passed =>
+    if s.paymentStatus.tuitionPaid then
+      s!"{s.school.name}: 合格・入学確定"
+    else if s.paymentStatus.enrollmentFeePaid then
+      s!"{s.school.name}: 合格・入学金支払済み"
+    else if s.school.enrollmentFeeDeadline.day < today.day then
+      s!"{s.school.name}: 合格・入学金期限切れ"
+    else
+      s!"{s.school.name}: 合格・入学金期限 {s.school.enrollmentFeeDeadline.day}"
+
+/--
+  アクションの判断理由を説明
+
+  【内容】
+  - 対象校の期限までの日数と、入学金の支払い状況（授業料の場合）
+  - 上位校それぞれの状況（判断の根拠）
+  - 何もしない場合は、全ての学校の状況
+-/
+def explainAction (states : List SchoolState) (action : PaymentAction) (today : Date) : Except String Explanation := do
+  let target : Option SchoolState ← match action with
+    | .PayEnrollmentFee schoolId | .PayTuition schoolId =>
+      match states.find? (fun s => s.school.id == schoolId) with
+      | some s => pure (some s)
+      | none => throw s!"School not found: {schoolId}"
+    | .DoNothing => pure none
+  match target with
+  | none =>
+    let top := getTopRecommendation states today
+    return {
+      summary := top.reason,
+      factors := states.map (describeSchoolState · today)
+    }
+  | some target =>
+    let deadline := match action with
+      | .PayTuition _ => target.school.tuitionDeadline
+      | _ => target.school.enrollmentFeeDeadline
+    let higher := states.filter fun s => s.school.priority.higherThan target.school.priority
+    let notRecommended := s!"{target.school.name}は現時点でこの支払いの推奨対象ではありません。"
+    let summary := match getRecommendationForSchool states target today with
+      | some r => if r.action == action then r.reason else notRecommended
+      | none => notRecommended
+    let deadlineFactor := s!"期限 {deadline.day}（あと{daysUntilDeadline deadline today}日）"
+    let enrollmentFactor := match action with
+      | .PayTuition _ =>
+        if target.paymentStatus.enrollmentFeePaid then ["入学金は支払済みです"] else ["入学金が未払いです"]
+      | _ => []
+    let higherFactors := if higher.isEmpty then
+      ["第一志望の学校です"]
+    else
+      higher.map (describeSchoolState · today)
+    return {
+      summary := summary,
+      factors := [describeSchoolState target today, deadlineFactor] ++ enrollmentFactor ++ higherFactors
+    }
+
+/--
+  explain の実行
+
+  getRecommendation と同じく期限切れを反映した状態で判断理由を説明する。
+-/
+def executeExplain (params : ExplainParams) : Except String Explanation := do
+  let today : Date := ⟨params.today⟩
+  let schoolStates ← buildSchoolStates params.schools params.states
+  validatePassStatusTiming schoolStates today
+  let updatedStates := applyDeadlineUpdates schoolStates today
+  explainAction updatedStates params.action today
+
+/-! ## バージョン情報 -/
+
+/-- アドバイザーのバージョン -/
+def advisorVersion : String := "0.4.0"
+
+/--
+  対応するデータスキーマのバージョン
+
+  Rust側の `migrations::CURRENT_SCHEMA_VERSION` と対応する。
+  保存データの形式を変更したら両方を更新すること。
+-/
+def dataSchemaVersion : Nat := 2
+
 /-! ## エラーコード（JSON-RPC 2.0 標準） -/
 
 /-- パースエラー（不正なJSON） -/
@@ -514,8 +639,20 @@ def handleRequest (req : JsonRpcRequest) : JsonRpcResponse :=
         | Except.ok result => { id := req.id, result := some (toJson result) }
         | Except.error msg => { id := req.id, error := some (errorInvalidParams msg) }
       | Except.error msg => { id := req.id, error := some (errorInvalidParams msg) }
+    | "explain" =>
+      match FromJson.fromJson? req.params with
+      | Except.ok (params : ExplainParams) =>
+        match executeExplain params with
+        | Except.ok result => { id := req.id, result := some (toJson result) }
+        | Except.error msg => { id := req.id, error := some (errorInvalidParams msg) }
+      | Except.error msg => { id := req.id, error := some (errorInvalidParams msg) }
     | "ping" =>
       { id := req.id, result := some (toJson "pong") }
+    | "getVersion" =>
+      { id := req.id, result := some (Json.mkObj [
+          ("advisorVersion", toJson advisorVersion),
+          ("schemaVersion", toJson dataSchemaVersion)
+        ]) }
     | method =>
       { id := req.id, error := some (errorMethodNotFound method) }
 
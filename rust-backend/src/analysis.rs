@@ -0,0 +1,222 @@
+//! Schedule checks that need no advisor.
+//!
+//! [`analyze`] looks over the schools for schedules that cannot work or
+//! will cost more than expected: payments due before the results they
+//! depend on, enrollment fees that must be paid before a preferred school
+//! announces its results, and deadlines on days banks are closed. Only
+//! unpaid payments of schools still open, due from today on, are checked.
+//! Siblings' schools are compared only with those of the same child.
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::calendar::{bank_holiday, previous_business_day};
+use crate::model::{Dataset, School};
+use crate::summary::PaymentCategory;
+
+/// Something wrong with the schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+#[ts(export)]
+pub enum ScheduleWarning {
+    /// Results are announced before the exam is taken
+    #[serde(rename_all = "camelCase")]
+    DatesOutOfOrder {
+        #[ts(type = "number")]
+        school_id: u64,
+        school_name: String,
+        exam_date: u32,
+        result_date: u32,
+    },
+    /// A payment is due before the school's own results are out
+    #[serde(rename_all = "camelCase")]
+    PaymentBeforeResult {
+        #[ts(type = "number")]
+        school_id: u64,
+        school_name: String,
+        category: PaymentCategory,
+        deadline: u32,
+        result_date: u32,
+    },
+    /// An enrollment fee is due before a preferred school's results, so
+    /// securing the place may mean paying two non-refundable fees
+    #[serde(rename_all = "camelCase")]
+    OverlappingEnrollmentFees {
+        #[ts(type = "number")]
+        school_id: u64,
+        school_name: String,
+        deadline: u32,
+        /// Enrollment fee at risk, in the school's currency
+        #[ts(type = "number")]
+        amount: u64,
+        #[ts(type = "number")]
+        preferred_school_id: u64,
+        preferred_school_name: String,
+        preferred_result_date: u32,
+    },
+    /// A deadline falls on a day banks are closed
+    #[serde(rename_all = "camelCase")]
+    BankHoliday {
+        #[ts(type = "number")]
+        school_id: u64,
+        school_name: String,
+        category: PaymentCategory,
+        deadline: u32,
+        /// Why banks are closed, such as `春分の日` or `土曜日`
+        reason: String,
+        /// Last day banks are open before the deadline
+        pay_by: u32,
+    },
+}
+
+/// Unpaid enrollment fee and tuition deadlines of `school`, one per
+/// tuition installment
+fn unpaid_deadlines(school: &School) -> Vec<(PaymentCategory, u32)> {
+    let mut deadlines = Vec::new();
+    if !school.enrollment_fee_paid {
+        deadlines.push((
+            PaymentCategory::EnrollmentFee,
+            school.enrollment_fee_deadline,
+        ));
+    }
+    deadlines.extend(
+        school
+            .tuition_payments()
+            .into_iter()
+            .filter(|p| !p.paid)
+            .map(|p| (PaymentCategory::Tuition, p.due)),
+    );
+    deadlines
+}
+
+/// Check the schedule of `dataset` from `today`, school by school in
+/// priority order
+pub fn analyze(dataset: &Dataset, today: u32) -> Vec<ScheduleWarning> {
+    let mut schools: Vec<&School> = dataset
+        .schools
+        .iter()
+        .filter(|s| s.pass_status.is_open())
+        .collect();
+    schools.sort_by_key(|s| (s.priority, s.id));
+
+    let mut warnings = Vec::new();
+    for school in &schools {
+        if school.result_date < school.exam_date {
+            warnings.push(ScheduleWarning::DatesOutOfOrder {
+                school_id: school.id,
+                school_name: school.name.clone(),
+                exam_date: school.exam_date,
+                result_date: school.result_date,
+            });
+        }
+
+        let deadlines = unpaid_deadlines(school);
+        for &(category, deadline) in deadlines.iter().filter(|(_, d)| *d >= today) {
+            if deadline < school.result_date {
+                warnings.push(ScheduleWarning::PaymentBeforeResult {
+                    school_id: school.id,
+                    school_name: school.name.clone(),
+                    category,
+                    deadline,
+                    result_date: school.result_date,
+                });
+            }
+            if let Some(reason) = bank_holiday(deadline) {
+                warnings.push(ScheduleWarning::BankHoliday {
+                    school_id: school.id,
+                    school_name: school.name.clone(),
+                    category,
+                    deadline,
+                    reason,
+                    pay_by: previous_business_day(deadline),
+                });
+            }
+        }
+
+        // Preferred schools still waiting for results when this fee is due
+        let deadline = school.enrollment_fee_deadline;
+        if school.enrollment_fee_paid || deadline < today {
+            continue;
+        }
+        let preferred_schools = schools
+            .iter()
+            .filter(|s| s.child_id == school.child_id)
+            .take_while(|s| s.priority < school.priority);
+        for preferred in preferred_schools {
+            if preferred.enrollment_fee_paid || preferred.result_date <= deadline {
+                continue;
+            }
+            warnings.push(ScheduleWarning::OverlappingEnrollmentFees {
+                school_id: school.id,
+                school_name: school.name.clone(),
+                deadline,
+                amount: school.net_enrollment_fee(),
+                preferred_school_id: preferred.id,
+                preferred_school_name: preferred.name.clone(),
+                preferred_result_date: preferred.result_date,
+            });
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_dataset;
+
+    #[test]
+    fn test_sample_schedule() {
+        let warnings = analyze(&sample_dataset(), 20260301);
+        // Waseda's tuition is due on 3/20, a holiday. Its enrollment fee,
+        // due before Tokyo's results, is already paid.
+        assert_eq!(
+            warnings,
+            vec![ScheduleWarning::BankHoliday {
+                school_id: 2,
+                school_name: "早稲田大学".to_string(),
+                category: PaymentCategory::Tuition,
+                deadline: 20260320,
+                reason: "春分の日".to_string(),
+                pay_by: 20260319,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut dataset = sample_dataset();
+        // Waseda's enrollment fee is now unpaid and due before Tokyo's results
+        dataset.schools[0].enrollment_fee_paid = false;
+        // Tokyo's fee is due before its own results
+        dataset.schools[1].enrollment_fee_deadline = 20260309;
+
+        let warnings = analyze(&dataset, 20260301);
+        assert!(warnings.contains(&ScheduleWarning::PaymentBeforeResult {
+            school_id: 1,
+            school_name: "東京大学".to_string(),
+            category: PaymentCategory::EnrollmentFee,
+            deadline: 20260309,
+            result_date: 20260310,
+        }));
+        assert!(warnings.iter().any(|w| matches!(
+            w,
+            ScheduleWarning::OverlappingEnrollmentFees {
+                school_id: 2,
+                preferred_school_id: 1,
+                amount: 200000,
+                ..
+            }
+        )));
+
+        // Past deadlines are not reported
+        assert!(analyze(&dataset, 20260401).is_empty());
+
+        // Nor are siblings' schools compared
+        dataset.schools[0].child_id = Some(1);
+        dataset.schools[1].child_id = Some(2);
+        assert!(!analyze(&dataset, 20260301)
+            .iter()
+            .any(|w| matches!(w, ScheduleWarning::OverlappingEnrollmentFees { .. })));
+    }
+}
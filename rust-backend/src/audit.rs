@@ -0,0 +1,312 @@
+//! Tamper-evident audit log of changes to the payment journal.
+//!
+//! Each recorded payment, applied import and restored backup appends an
+//! [`AuditEntry`] to [`AUDIT_LOG_FILE`]. Entries are chained: the HMAC of
+//! an entry covers its content and the HMAC of the entry before it, so
+//! editing, removing or reordering an entry breaks every later link.
+//! [`verify`] walks the chain and reports where it breaks, which gives the
+//! journal weight when a school disputes whether a fee was paid on time.
+//!
+//! The HMAC key is kept outside the data directory, in the OS keychain on
+//! the desktop app. Entries are not recorded while no key is set up.
+//! Removing the newest entries leaves a valid, shorter chain; the entry
+//! count shown with each verification lets the user notice it.
+
+use chrono::Local;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use ts_rs::TS;
+
+use crate::field_crypto::{FieldCryptoError, FieldKey, KeyStore};
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding the audit log
+pub const AUDIT_LOG_FILE: &str = "audit_log.json";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What an audit entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum AuditAction {
+    PaymentRecorded,
+    ImportApplied,
+    BackupRestored,
+    SchoolAdded,
+}
+
+/// One signed entry of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct AuditEntry {
+    /// Position in the log, from 1
+    #[ts(type = "number")]
+    pub seq: u64,
+    /// When the entry was recorded (RFC 3339, local time)
+    pub at: String,
+    pub action: AuditAction,
+    /// What changed, such as the payment recorded
+    #[ts(type = "unknown")]
+    pub details: serde_json::Value,
+    /// HMAC of the previous entry; empty for the first
+    pub prev_mac: String,
+    /// HMAC of this entry's content and `prev_mac`, hex
+    pub mac: String,
+}
+
+impl AuditEntry {
+    fn compute_mac(&self, key: &FieldKey) -> HmacSha256 {
+        // Object keys are sorted, so the content hashes the same after a
+        // round trip through the file
+        let content = serde_json::json!({
+            "seq": self.seq,
+            "at": self.at,
+            "action": self.action,
+            "details": self.details,
+        });
+        let mut mac =
+            HmacSha256::new_from_slice(key.bytes()).expect("HMAC accepts keys of any length");
+        mac.update(self.prev_mac.as_bytes());
+        mac.update(content.to_string().as_bytes());
+        mac
+    }
+
+    fn signed(mut self, key: &FieldKey) -> Self {
+        self.mac = hex::encode(self.compute_mac(key).finalize().into_bytes());
+        self
+    }
+
+    fn has_valid_mac(&self, key: &FieldKey) -> bool {
+        hex::decode(&self.mac).is_ok_and(|mac| self.compute_mac(key).verify_slice(&mac).is_ok())
+    }
+}
+
+/// How the chain is broken at an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum AuditProblemKind {
+    /// The entry was changed after it was recorded
+    BadSignature,
+    /// The entry does not follow the one before it; entries were removed,
+    /// inserted or reordered
+    BrokenChain,
+}
+
+/// An entry where verification failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct AuditProblem {
+    /// 1-based position of the entry in the file
+    #[ts(type = "number")]
+    pub position: u64,
+    pub kind: AuditProblemKind,
+}
+
+/// Result of verifying the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct AuditVerification {
+    #[ts(type = "number")]
+    pub entries: u64,
+    pub valid: bool,
+    pub problems: Vec<AuditProblem>,
+}
+
+/// The stored audit log
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(AUDIT_LOG_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(AUDIT_LOG_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Sign and add an entry after the last one
+    pub fn append(
+        &mut self,
+        key: &FieldKey,
+        action: AuditAction,
+        details: serde_json::Value,
+    ) -> &AuditEntry {
+        let last = self.entries.last();
+        let entry = AuditEntry {
+            seq: last.map_or(1, |e| e.seq + 1),
+            at: Local::now().to_rfc3339(),
+            action,
+            details,
+            prev_mac: last.map(|e| e.mac.clone()).unwrap_or_default(),
+            mac: String::new(),
+        }
+        .signed(key);
+        self.entries.push(entry);
+        self.entries.last().expect("just pushed")
+    }
+
+    /// Check every entry's signature and link to the one before it
+    pub fn verify(&self, key: &FieldKey) -> AuditVerification {
+        let mut problems = Vec::new();
+        let mut previous: Option<&AuditEntry> = None;
+        for (i, entry) in self.entries.iter().enumerate() {
+            let position = i as u64 + 1;
+            if !entry.has_valid_mac(key) {
+                problems.push(AuditProblem {
+                    position,
+                    kind: AuditProblemKind::BadSignature,
+                });
+            }
+            let linked = match previous {
+                Some(prev) => entry.prev_mac == prev.mac && entry.seq == prev.seq + 1,
+                None => entry.prev_mac.is_empty() && entry.seq == 1,
+            };
+            if !linked {
+                problems.push(AuditProblem {
+                    position,
+                    kind: AuditProblemKind::BrokenChain,
+                });
+            }
+            previous = Some(entry);
+        }
+        AuditVerification {
+            entries: self.entries.len() as u64,
+            valid: problems.is_empty(),
+            problems,
+        }
+    }
+}
+
+/// The audit or cloud backup key in `keys`, creating one the first time
+pub fn load_or_create_key(keys: &dyn KeyStore) -> Result<FieldKey, FieldCryptoError> {
+    if let Some(key) = keys.load()? {
+        return Ok(key);
+    }
+    let key = FieldKey::generate();
+    keys.save(&key)?;
+    Ok(key)
+}
+
+/// Append an entry to the stored log
+pub fn record(
+    storage: &Storage,
+    key: &FieldKey,
+    action: AuditAction,
+    details: serde_json::Value,
+) -> Result<(), StorageError> {
+    let mut log = AuditLog::load(storage)?;
+    log.append(key, action, details);
+    log.save(storage)
+}
+
+/// Verify the stored log
+pub fn verify(storage: &Storage, key: &FieldKey) -> Result<AuditVerification, StorageError> {
+    Ok(AuditLog::load(storage)?.verify(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_log(key: &FieldKey) -> AuditLog {
+        let mut log = AuditLog::default();
+        for (school_id, paid_on) in [(1, 20260310), (2, 20260312), (1, 20260330)] {
+            log.append(
+                key,
+                AuditAction::PaymentRecorded,
+                json!({ "schoolId": school_id, "fee": "tuition", "paidOn": paid_on }),
+            );
+        }
+        log
+    }
+
+    fn problems(log: &AuditLog, key: &FieldKey) -> Vec<(u64, AuditProblemKind)> {
+        log.verify(key)
+            .problems
+            .into_iter()
+            .map(|p| (p.position, p.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_chain() {
+        let key = FieldKey::generate();
+        let log = sample_log(&key);
+        assert_eq!(log.entries[2].seq, 3);
+        assert_eq!(log.entries[1].prev_mac, log.entries[0].mac);
+        let verification = log.verify(&key);
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 3);
+
+        // Another key cannot have signed the entries
+        assert_eq!(problems(&log, &FieldKey::generate()).len(), 3);
+    }
+
+    #[test]
+    fn test_detects_tampering() {
+        let key = FieldKey::generate();
+
+        let mut edited = sample_log(&key);
+        edited.entries[1].details["paidOn"] = json!(20260301);
+        assert_eq!(
+            problems(&edited, &key),
+            vec![(2, AuditProblemKind::BadSignature)]
+        );
+
+        let mut removed = sample_log(&key);
+        removed.entries.remove(1);
+        assert_eq!(
+            problems(&removed, &key),
+            vec![(2, AuditProblemKind::BrokenChain)]
+        );
+
+        let mut reordered = sample_log(&key);
+        reordered.entries.swap(1, 2);
+        assert_eq!(
+            problems(&reordered, &key),
+            vec![
+                (2, AuditProblemKind::BrokenChain),
+                (3, AuditProblemKind::BrokenChain)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_and_verify_stored_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let key = FieldKey::generate();
+        record(
+            &storage,
+            &key,
+            AuditAction::BackupRestored,
+            json!({ "fileName": "auto-20260301-120000.json" }),
+        )
+        .unwrap();
+        record(
+            &storage,
+            &key,
+            AuditAction::ImportApplied,
+            json!({ "schools": 3 }),
+        )
+        .unwrap();
+
+        let verification = verify(&storage, &key).unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 2);
+    }
+}
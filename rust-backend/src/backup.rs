@@ -0,0 +1,221 @@
+//! Timestamped backups of the stored dataset and unclean-shutdown detection.
+//!
+//! Backups live in `backups/` under the data directory and are named
+//! `<kind>-YYYYMMDD-HHMMSS.json`. A marker file is created at startup and
+//! removed on clean exit, so its presence at the next launch means the app
+//! crashed or was killed.
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::field_crypto;
+use crate::storage::{Storage, StorageError, SCHOOLS_DATA_FILE};
+
+/// Subdirectory of the data directory holding backups
+pub const BACKUP_DIR: &str = "backups";
+
+/// Marker present while the app is running
+pub const RUNNING_MARKER: &str = "running.lock";
+
+/// Backup kind written when the app exits
+pub const EXIT_BACKUP: &str = "exit";
+
+/// Backup kind written on user request
+pub const MANUAL_BACKUP: &str = "manual";
+
+/// Backup kind written before a restore overwrites the data
+pub const PRE_RESTORE_BACKUP: &str = "prerestore";
+
+/// How many backups of each kind are kept
+pub const MAX_BACKUPS_PER_KIND: usize = 5;
+
+/// A backup file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub kind: String,
+    /// `YYYYMMDD-HHMMSS` in local time
+    pub created_at: String,
+}
+
+impl BackupInfo {
+    fn parse(file_name: &str) -> Option<Self> {
+        let stem = file_name.strip_suffix(".json")?;
+        let (kind, created_at) = stem.split_once('-')?;
+        Some(Self {
+            file_name: file_name.to_string(),
+            kind: kind.to_string(),
+            created_at: created_at.to_string(),
+        })
+    }
+}
+
+fn backup_dir(storage: &Storage) -> PathBuf {
+    storage.data_dir().join(BACKUP_DIR)
+}
+
+/// List backups, newest first
+pub fn list_backups(storage: &Storage) -> Result<Vec<BackupInfo>, StorageError> {
+    let dir = backup_dir(storage);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups: Vec<BackupInfo> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| BackupInfo::parse(&entry.file_name().to_string_lossy()))
+        .collect();
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.kind.cmp(&b.kind)));
+    Ok(backups)
+}
+
+/// Copy the current dataset into a new backup of the given kind.
+///
+/// Returns `None` if there is no saved data. Older backups of the same
+/// kind beyond [`MAX_BACKUPS_PER_KIND`] are removed.
+pub fn write_backup(storage: &Storage, kind: &str) -> Result<Option<BackupInfo>, StorageError> {
+    let Some(mut data) = storage.load(SCHOOLS_DATA_FILE)? else {
+        return Ok(None);
+    };
+    let created_at = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let file_name = format!("{}-{}.json", kind, created_at);
+
+    let dir = backup_dir(storage);
+    fs::create_dir_all(&dir)?;
+    field_crypto::encrypt(&mut data);
+    fs::write(dir.join(&file_name), serde_json::to_string_pretty(&data)?)?;
+
+    for old in list_backups(storage)?
+        .into_iter()
+        .filter(|b| b.kind == kind)
+        .skip(MAX_BACKUPS_PER_KIND)
+    {
+        fs::remove_file(dir.join(old.file_name))?;
+    }
+
+    Ok(BackupInfo::parse(&file_name))
+}
+
+/// Backup files as paths relative to the data directory, such as
+/// `backups/manual-20260301-103000.json`
+pub fn backup_files(storage: &Storage) -> Result<Vec<String>, StorageError> {
+    Ok(list_backups(storage)?
+        .into_iter()
+        .map(|b| format!("{}/{}", BACKUP_DIR, b.file_name))
+        .collect())
+}
+
+/// Whether `file_name`, relative to the data directory, is a backup
+pub fn is_backup_file(file_name: &str) -> bool {
+    file_name
+        .strip_prefix(BACKUP_DIR)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .is_some_and(|name| !name.contains(['/', '\\']) && BackupInfo::parse(name).is_some())
+}
+
+/// Resolve a backup file name to its path, if such a backup exists.
+///
+/// Names that are not backup file names (including any path) resolve to `None`.
+pub fn backup_path(storage: &Storage, file_name: &str) -> Option<PathBuf> {
+    if BackupInfo::parse(file_name).is_none() || file_name.contains(['/', '\\']) {
+        return None;
+    }
+    let path = backup_dir(storage).join(file_name);
+    path.exists().then_some(path)
+}
+
+/// Load a backup by file name
+pub fn load_backup(
+    storage: &Storage,
+    file_name: &str,
+) -> Result<Option<serde_json::Value>, StorageError> {
+    let Some(path) = backup_path(storage, file_name) else {
+        return Ok(None);
+    };
+    let mut data = serde_json::from_str(&fs::read_to_string(path)?)?;
+    field_crypto::decrypt(&mut data)?;
+    Ok(Some(data))
+}
+
+/// Create the running marker, returning whether the previous run ended uncleanly
+pub fn mark_running(storage: &Storage) -> Result<bool, StorageError> {
+    let unclean = storage.exists(RUNNING_MARKER);
+    storage.save(
+        RUNNING_MARKER,
+        &serde_json::json!({ "pid": std::process::id() }),
+    )?;
+    Ok(unclean)
+}
+
+/// Remove the running marker on clean exit
+pub fn clear_running(storage: &Storage) -> Result<(), StorageError> {
+    storage.delete(RUNNING_MARKER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        assert_eq!(write_backup(&storage, EXIT_BACKUP).unwrap(), None);
+
+        let data = serde_json::json!({"schools": [{"id": 1}]});
+        storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+        let backup = write_backup(&storage, EXIT_BACKUP).unwrap().unwrap();
+        assert_eq!(backup.kind, EXIT_BACKUP);
+
+        assert_eq!(list_backups(&storage).unwrap(), vec![backup.clone()]);
+        assert_eq!(
+            load_backup(&storage, &backup.file_name).unwrap(),
+            Some(data)
+        );
+        assert_eq!(load_backup(&storage, "../data.json").unwrap(), None);
+        assert!(backup_path(&storage, &backup.file_name).is_some());
+        assert_eq!(backup_path(&storage, "exit-19990101-000000.json"), None);
+    }
+
+    #[test]
+    fn test_old_backups_pruned() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let backups = dir.path().join(BACKUP_DIR);
+        fs::create_dir_all(&backups).unwrap();
+        for i in 0..MAX_BACKUPS_PER_KIND {
+            fs::write(backups.join(format!("exit-20200101-00000{}.json", i)), "{}").unwrap();
+        }
+        fs::write(backups.join("manual-20200101-000000.json"), "{}").unwrap();
+
+        storage
+            .save(SCHOOLS_DATA_FILE, &serde_json::json!({}))
+            .unwrap();
+        write_backup(&storage, EXIT_BACKUP).unwrap();
+
+        let all = list_backups(&storage).unwrap();
+        assert_eq!(
+            all.iter().filter(|b| b.kind == EXIT_BACKUP).count(),
+            MAX_BACKUPS_PER_KIND
+        );
+        assert!(!all
+            .iter()
+            .any(|b| b.file_name == "exit-20200101-000000.json"));
+        assert!(all.iter().any(|b| b.kind == "manual"));
+    }
+
+    #[test]
+    fn test_running_marker() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+
+        assert!(!mark_running(&storage).unwrap());
+        // Not cleared: simulates a crash
+        assert!(mark_running(&storage).unwrap());
+        clear_running(&storage).unwrap();
+        assert!(!mark_running(&storage).unwrap());
+    }
+}
@@ -0,0 +1,236 @@
+//! Season budget and alerts for payments that would go beyond it.
+//!
+//! The [`Budget`] saved with the dataset sets an overall limit and optional
+//! limits per kind of payment, in yen. Spend is counted as in
+//! [`summary`](crate::summary): committed spend is what has been paid, and
+//! projected spend adds the unpaid fees of schools that are still open.
+//! When the advisor recommends a payment that would take committed spend
+//! past a limit, [`check_action`] returns a [`BudgetAlert`] to send through
+//! the notification pipeline.
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::currency::ExchangeRates;
+use crate::model::{Budget, Currency, Dataset, PaymentAction, PaymentActionType};
+use crate::summary::{self, PaymentCategory, Summary, SummaryRange};
+
+/// Spend against one limit, in yen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BudgetLine {
+    /// Kind of payment limited, or `None` for the overall budget
+    pub category: Option<PaymentCategory>,
+    #[ts(type = "number")]
+    pub limit: u64,
+    #[ts(type = "number")]
+    pub committed: u64,
+    #[ts(type = "number")]
+    pub projected: u64,
+    /// Limit minus projected spend; negative when over budget
+    #[ts(type = "number")]
+    pub remaining: i64,
+}
+
+/// Spend against each limit that is set, overall first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BudgetStatus {
+    pub lines: Vec<BudgetLine>,
+}
+
+impl BudgetStatus {
+    /// Whether projected spend is over any limit
+    pub fn is_over(&self) -> bool {
+        self.lines.iter().any(|line| line.remaining < 0)
+    }
+}
+
+/// A recommended payment that would go beyond a limit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BudgetAlert {
+    /// Kind of payment limited, or `None` for the overall budget
+    pub category: Option<PaymentCategory>,
+    #[ts(type = "number")]
+    pub limit: u64,
+    /// Paid so far, before this payment
+    #[ts(type = "number")]
+    pub committed: u64,
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    pub action_type: PaymentActionType,
+    /// Amount of the payment, in yen
+    #[ts(type = "number")]
+    pub amount: u64,
+}
+
+impl BudgetAlert {
+    /// How far the payment would go beyond the limit
+    pub fn excess(&self) -> u64 {
+        (self.committed + self.amount).saturating_sub(self.limit)
+    }
+}
+
+/// Limit for `category`, or the overall limit for `None`
+fn limit(budget: &Budget, category: Option<PaymentCategory>) -> Option<u64> {
+    match category {
+        None => budget.total,
+        Some(PaymentCategory::ApplicationFee) => budget.application_fees,
+        Some(PaymentCategory::EnrollmentFee) => budget.enrollment_fees,
+        Some(PaymentCategory::Tuition) => budget.tuition,
+        // Counted in the total only
+        Some(PaymentCategory::Recurring) => None,
+    }
+}
+
+/// Committed and projected spend for `category`, or overall for `None`
+fn spend(summary: &Summary, category: Option<PaymentCategory>) -> (u64, u64) {
+    match category {
+        None => (summary.paid, summary.total),
+        Some(category) => summary
+            .by_category
+            .iter()
+            .find(|c| c.category == category)
+            .map_or((0, 0), |c| (c.paid, c.total)),
+    }
+}
+
+const CATEGORIES: [Option<PaymentCategory>; 4] = [
+    None,
+    Some(PaymentCategory::ApplicationFee),
+    Some(PaymentCategory::EnrollmentFee),
+    Some(PaymentCategory::Tuition),
+];
+
+/// Spend against the dataset's budget, or `None` if no budget is set
+pub fn status(dataset: &Dataset, rates: &ExchangeRates) -> Option<BudgetStatus> {
+    let budget = dataset.budget.as_ref()?;
+    let summary = summary::summarize(dataset, SummaryRange::default(), rates);
+    let lines = CATEGORIES
+        .into_iter()
+        .filter_map(|category| {
+            let limit = limit(budget, category)?;
+            let (committed, projected) = spend(&summary, category);
+            Some(BudgetLine {
+                category,
+                limit,
+                committed,
+                projected,
+                remaining: limit as i64 - projected as i64,
+            })
+        })
+        .collect();
+    Some(BudgetStatus { lines })
+}
+
+/// Limits that paying `action` would go beyond
+pub fn check_action(
+    dataset: &Dataset,
+    rates: &ExchangeRates,
+    action: &PaymentAction,
+) -> Vec<BudgetAlert> {
+    let Some(budget) = &dataset.budget else {
+        return Vec::new();
+    };
+    let Some(school) = action
+        .school_id
+        .and_then(|id| dataset.schools.iter().find(|s| s.id == id))
+    else {
+        return Vec::new();
+    };
+    let (category, amount) = match action.action_type {
+        PaymentActionType::PayEnrollmentFee => {
+            (PaymentCategory::EnrollmentFee, school.net_enrollment_fee())
+        }
+        PaymentActionType::PayTuition => (PaymentCategory::Tuition, school.securing_tuition()),
+        PaymentActionType::DoNothing => return Vec::new(),
+    };
+    let Some(amount) = rates.convert(amount, school.currency, Currency::Jpy) else {
+        return Vec::new();
+    };
+
+    let summary = summary::summarize(dataset, SummaryRange::default(), rates);
+    [None, Some(category)]
+        .into_iter()
+        .filter_map(|category| {
+            let limit = limit(budget, category)?;
+            let (committed, _) = spend(&summary, category);
+            (committed + amount > limit).then(|| BudgetAlert {
+                category,
+                limit,
+                committed,
+                school_id: school.id,
+                school_name: school.name.clone(),
+                action_type: action.action_type,
+                amount,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(budget: Budget) -> Dataset {
+        let mut dataset = crate::export::sample_dataset();
+        dataset.budget = Some(budget);
+        dataset
+    }
+
+    #[test]
+    fn test_status() {
+        let dataset = dataset(Budget {
+            total: Some(1500000),
+            tuition: Some(1000000),
+            ..Budget::default()
+        });
+        let budget = status(&dataset, &ExchangeRates::default()).unwrap();
+
+        assert_eq!(budget.lines.len(), 2);
+        // Waseda's enrollment fee is paid; everything else is still to come
+        assert_eq!(budget.lines[0].committed, 200000);
+        assert_eq!(budget.lines[0].projected, 200000 + 800000 + 282000 + 535800);
+        assert_eq!(budget.lines[0].remaining, -317800);
+        assert_eq!(budget.lines[1].category, Some(PaymentCategory::Tuition));
+        assert_eq!(budget.lines[1].projected, 1335800);
+        assert!(budget.is_over());
+
+        let mut unbudgeted = dataset.clone();
+        unbudgeted.budget = None;
+        assert_eq!(status(&unbudgeted, &ExchangeRates::default()), None);
+    }
+
+    #[test]
+    fn test_check_action() {
+        let dataset = dataset(Budget {
+            total: Some(900000),
+            enrollment_fees: Some(500000),
+            ..Budget::default()
+        });
+        let rates = ExchangeRates::default();
+        let action = |action_type, school_id| PaymentAction {
+            action_type,
+            school_id: Some(school_id),
+        };
+
+        // ¥200,000 paid plus Waseda's ¥800,000 tuition
+        let alerts = check_action(&dataset, &rates, &action(PaymentActionType::PayTuition, 2));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].category, None);
+        assert_eq!(alerts[0].excess(), 100000);
+
+        // ¥482,000 in enrollment fees stays within both limits
+        let alerts = check_action(
+            &dataset,
+            &rates,
+            &action(PaymentActionType::PayEnrollmentFee, 1),
+        );
+        assert!(alerts.is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+//! Several changes to schools and payments applied as one.
+//!
+//! [`apply`] runs a list of [`BulkOp`]s in order against one copy of the
+//! dataset. If any of them fails, the copy is thrown away and the error
+//! says which one, so the saved data is never left half-updated; otherwise
+//! the caller saves it once, with a single revision.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::model::{Dataset, FeeKind, School};
+use crate::payments::{self, PaymentError, PaymentRecord};
+use crate::revision;
+
+/// Most operations applied in one request
+pub const MAX_BULK_OPS: usize = 500;
+
+/// Errors from a single bulk operation
+#[derive(Debug, Error)]
+pub enum BulkOpError {
+    #[error("School not found: {0}")]
+    UnknownSchool(u64),
+
+    #[error("A school with id {0} already exists")]
+    DuplicateSchool(u64),
+
+    #[error("Invalid school update: {0}")]
+    InvalidUpdate(String),
+
+    #[error(transparent)]
+    Payment(#[from] PaymentError),
+
+    #[error("School {school_id} has no recorded payment to delete")]
+    NotPaid { school_id: u64 },
+}
+
+/// Errors that can occur when applying bulk operations
+#[derive(Debug, Error)]
+pub enum BulkError {
+    #[error("At most {MAX_BULK_OPS} operations can be applied at once")]
+    TooMany,
+
+    /// `index` is the failing operation's position in the list, from 0
+    #[error("Operation {} failed, nothing was changed: {error}", .index + 1)]
+    Failed { index: usize, error: BulkOpError },
+}
+
+/// A change to a school or one of its payments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(tag = "op", rename_all = "camelCase")]
+#[ts(export)]
+pub enum BulkOp {
+    /// Add a school; an id of 0 is replaced by the next free id
+    CreateSchool { school: Box<School> },
+    /// Change fields of a school with a JSON merge patch (RFC 7386)
+    #[serde(rename_all = "camelCase")]
+    UpdateSchool {
+        #[ts(type = "number")]
+        school_id: u64,
+        #[ts(type = "unknown")]
+        patch: serde_json::Value,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeleteSchool {
+        #[ts(type = "number")]
+        school_id: u64,
+    },
+    /// Record a payment as made, replacing any receipt already kept for it
+    RecordPayment { payment: PaymentRecord },
+    /// Undo a recorded payment and drop its receipt
+    #[serde(rename_all = "camelCase")]
+    DeletePayment {
+        #[ts(type = "number")]
+        school_id: u64,
+        fee: FeeKind,
+        /// 1-based installment number, for tuition paid in parts
+        #[serde(default)]
+        #[ts(optional)]
+        installment: Option<u32>,
+    },
+}
+
+/// Apply `ops` to `dataset` in order; on failure `dataset` is unchanged
+pub fn apply(dataset: &mut Dataset, ops: Vec<BulkOp>) -> Result<(), BulkError> {
+    if ops.len() > MAX_BULK_OPS {
+        return Err(BulkError::TooMany);
+    }
+    let mut working = dataset.clone();
+    for (index, op) in ops.into_iter().enumerate() {
+        apply_one(&mut working, op).map_err(|error| BulkError::Failed { index, error })?;
+    }
+    *dataset = working;
+    Ok(())
+}
+
+fn apply_one(dataset: &mut Dataset, op: BulkOp) -> Result<(), BulkOpError> {
+    match op {
+        BulkOp::CreateSchool { school } => {
+            let mut school = *school;
+            if school.id == 0 {
+                school.id = dataset.schools.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+            } else if dataset.schools.iter().any(|s| s.id == school.id) {
+                return Err(BulkOpError::DuplicateSchool(school.id));
+            }
+            dataset.schools.push(school);
+        }
+        BulkOp::UpdateSchool { school_id, patch } => {
+            let school = school_mut(dataset, school_id)?;
+            let mut value = serde_json::to_value(&*school)
+                .map_err(|e| BulkOpError::InvalidUpdate(e.to_string()))?;
+            revision::merge_patch(&mut value, &patch);
+            let updated: School = serde_json::from_value(value)
+                .map_err(|e| BulkOpError::InvalidUpdate(e.to_string()))?;
+            if updated.id != school_id {
+                return Err(BulkOpError::InvalidUpdate(
+                    "the id of a school cannot be changed".to_string(),
+                ));
+            }
+            *school = updated;
+        }
+        BulkOp::DeleteSchool { school_id } => {
+            school_mut(dataset, school_id)?;
+            dataset.schools.retain(|s| s.id != school_id);
+        }
+        BulkOp::RecordPayment { payment } => payments::mark_paid(dataset, payment)?,
+        BulkOp::DeletePayment {
+            school_id,
+            fee,
+            installment,
+        } => unmark_paid(school_mut(dataset, school_id)?, fee, installment)?,
+    }
+    Ok(())
+}
+
+fn school_mut(dataset: &mut Dataset, school_id: u64) -> Result<&mut School, BulkOpError> {
+    dataset
+        .schools
+        .iter_mut()
+        .find(|s| s.id == school_id)
+        .ok_or(BulkOpError::UnknownSchool(school_id))
+}
+
+/// Undo what [`payments::mark_paid`] records for a payment
+fn unmark_paid(
+    school: &mut School,
+    fee: FeeKind,
+    installment: Option<u32>,
+) -> Result<(), BulkOpError> {
+    let not_paid = BulkOpError::NotPaid {
+        school_id: school.id,
+    };
+    let installment = match fee {
+        FeeKind::EnrollmentFee if school.enrollment_fee_paid => {
+            school.enrollment_fee_paid = false;
+            None
+        }
+        FeeKind::Tuition if school.installments.is_empty() && school.tuition_paid => {
+            school.tuition_paid = false;
+            None
+        }
+        FeeKind::Tuition if !school.installments.is_empty() => {
+            let number = installment.unwrap_or(1);
+            let part = number
+                .checked_sub(1)
+                .and_then(|i| school.installments.get_mut(i as usize))
+                .ok_or(PaymentError::UnknownInstallment {
+                    school_id: school.id,
+                    installment: number,
+                })?;
+            if !part.paid {
+                return Err(not_paid);
+            }
+            part.paid = false;
+            if number == 1 {
+                school.tuition_paid = false;
+            }
+            Some(number)
+        }
+        _ => return Err(not_paid),
+    };
+    school
+        .receipts
+        .retain(|r| !(r.fee == fee && r.installment == installment));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset() -> Dataset {
+        Dataset::from_value(serde_json::json!({
+            "schools": [{
+                "id": 1,
+                "name": "早稲田大学",
+                "examDate": 20260212,
+                "resultDate": 20260220,
+                "enrollmentFeeDeadline": 20260227,
+                "tuitionDeadline": 20260313,
+                "enrollmentFee": 200000,
+                "tuition": 800000,
+                "installments": [
+                    { "due": 20260313, "amount": 400000 },
+                    { "due": 20260930, "amount": 400000 }
+                ]
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn parse_ops(value: serde_json::Value) -> Vec<BulkOp> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_ops_apply_in_order() {
+        let mut dataset = dataset();
+        let mut new_school = serde_json::to_value(&dataset.schools[0]).unwrap();
+        new_school["id"] = 0.into();
+        new_school["name"] = "慶應義塾大学".into();
+        let ops = parse_ops(serde_json::json!([
+            { "op": "createSchool", "school": new_school },
+            { "op": "updateSchool", "schoolId": 2, "patch": { "priority": 1 } },
+            {
+                "op": "recordPayment",
+                "payment": {
+                    "schoolId": 1, "fee": "tuition", "installment": 1,
+                    "paidOn": 20260310, "method": "bankTransfer"
+                }
+            },
+            {
+                "op": "recordPayment",
+                "payment": {
+                    "schoolId": 1, "fee": "enrollmentFee",
+                    "paidOn": 20260225, "method": "bankTransfer"
+                }
+            },
+            { "op": "deletePayment", "schoolId": 1, "fee": "enrollmentFee" }
+        ]));
+        apply(&mut dataset, ops).unwrap();
+
+        let created = &dataset.schools[1];
+        assert_eq!((created.id, created.priority), (2, 1));
+        let school = &dataset.schools[0];
+        assert!(school.installments[0].paid && school.tuition_paid);
+        assert!(!school.enrollment_fee_paid);
+        assert_eq!(school.receipts.len(), 1);
+        assert_eq!(school.receipts[0].fee, FeeKind::Tuition);
+    }
+
+    #[test]
+    fn test_a_failing_op_changes_nothing() {
+        let mut dataset = dataset();
+        let before = dataset.clone();
+        let ops = parse_ops(serde_json::json!([
+            { "op": "updateSchool", "schoolId": 1, "patch": { "name": "早大" } },
+            { "op": "deleteSchool", "schoolId": 1 },
+            { "op": "deletePayment", "schoolId": 1, "fee": "tuition" }
+        ]));
+        let error = apply(&mut dataset, ops).unwrap_err();
+        assert!(matches!(
+            error,
+            BulkError::Failed {
+                index: 2,
+                error: BulkOpError::UnknownSchool(1)
+            }
+        ));
+        assert_eq!(
+            error.to_string().split(',').next(),
+            Some("Operation 3 failed")
+        );
+        assert_eq!(dataset, before);
+
+        let ops = parse_ops(serde_json::json!([
+            { "op": "updateSchool", "schoolId": 1, "patch": { "id": 5 } }
+        ]));
+        assert!(matches!(
+            apply(&mut dataset, ops),
+            Err(BulkError::Failed {
+                error: BulkOpError::InvalidUpdate(_),
+                ..
+            })
+        ));
+        let ops = parse_ops(serde_json::json!([
+            { "op": "deletePayment", "schoolId": 1, "fee": "tuition", "installment": 2 }
+        ]));
+        assert!(matches!(
+            apply(&mut dataset, ops),
+            Err(BulkError::Failed {
+                error: BulkOpError::NotPaid { school_id: 1 },
+                ..
+            })
+        ));
+    }
+}
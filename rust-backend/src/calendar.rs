@@ -0,0 +1,354 @@
+//! Days banks are closed in Japan, for payments made by bank transfer.
+//!
+//! Banks are closed on weekends, on national holidays and over the New
+//! Year break, so a deadline falling on one of those days has to be paid
+//! by the last business day before it. The national holidays are
+//! announced a year or two ahead, so they are kept in a data file bundled
+//! with the app rather than in code, and users can import a newer file in
+//! the same JSON format. Imported files are saved to [`HOLIDAYS_FILE`] and
+//! their days replace the bundled days of the same date.
+//!
+//! The calendar in use is shared by the whole process: it starts out as
+//! the bundled one and [`install`] replaces it once updates are loaded.
+
+use std::sync::{Arc, RwLock};
+
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::date::{parse_day, to_day};
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding imported holidays
+pub const HOLIDAYS_FILE: &str = "holidays.json";
+
+/// Calendar bundled with the app
+const BUNDLED_CALENDAR: &str = include_str!("../calendar/japan.json");
+
+/// Errors that can occur with the holiday calendar
+#[derive(Debug, Error)]
+pub enum CalendarError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error("Invalid holiday calendar: {0}")]
+    InvalidCalendar(String),
+}
+
+/// A day banks are closed other than a weekend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Holiday {
+    pub day: u32,
+    pub name: String,
+}
+
+/// A day banks are closed every year, such as December 31
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YearlyClosure {
+    pub month: u32,
+    pub day: u32,
+    pub name: String,
+}
+
+/// National holidays and the days banks close every year
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Calendar {
+    /// Public holidays, including substitute holidays (振替休日) and
+    /// citizens' holidays (国民の休日)
+    #[serde(default)]
+    pub holidays: Vec<Holiday>,
+    #[serde(default)]
+    pub yearly_closures: Vec<YearlyClosure>,
+}
+
+impl Calendar {
+    /// Parse and check a calendar file
+    pub fn parse(text: &str) -> Result<Self, CalendarError> {
+        let calendar: Calendar = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+            .map_err(|e| CalendarError::InvalidCalendar(e.to_string()))?;
+        if let Some(holiday) = calendar
+            .holidays
+            .iter()
+            .find(|h| parse_day(h.day).is_none() || h.name.trim().is_empty())
+        {
+            return Err(CalendarError::InvalidCalendar(format!(
+                "{}: a valid day and a name are required",
+                holiday.day
+            )));
+        }
+        // February 29 is allowed, as it is a valid day in leap years
+        if let Some(closure) = calendar.yearly_closures.iter().find(|c| {
+            chrono::NaiveDate::from_ymd_opt(2028, c.month, c.day).is_none()
+                || c.name.trim().is_empty()
+        }) {
+            return Err(CalendarError::InvalidCalendar(format!(
+                "{}/{}: a valid month and day and a name are required",
+                closure.month, closure.day
+            )));
+        }
+        Ok(calendar)
+    }
+
+    /// Calendar bundled with the app
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_CALENDAR).expect("bundled calendar is valid")
+    }
+
+    /// Add the days of `other`, replacing those on the same date
+    pub fn merge(&mut self, other: Calendar) {
+        for holiday in other.holidays {
+            match self.holidays.iter_mut().find(|h| h.day == holiday.day) {
+                Some(existing) => *existing = holiday,
+                None => self.holidays.push(holiday),
+            }
+        }
+        self.holidays.sort_by_key(|h| h.day);
+        for closure in other.yearly_closures {
+            match self
+                .yearly_closures
+                .iter_mut()
+                .find(|c| (c.month, c.day) == (closure.month, closure.day))
+            {
+                Some(existing) => *existing = closure,
+                None => self.yearly_closures.push(closure),
+            }
+        }
+    }
+
+    /// Name of the public holiday on `day`, if it is one
+    pub fn holiday_name(&self, day: u32) -> Option<&str> {
+        self.holidays
+            .iter()
+            .find(|h| h.day == day)
+            .map(|h| h.name.as_str())
+    }
+
+    /// Why banks are closed on `day`, if they are
+    pub fn bank_holiday(&self, day: u32) -> Option<&str> {
+        let date = parse_day(day)?;
+        if let Some(name) = self.holiday_name(day) {
+            return Some(name);
+        }
+        if let Some(closure) = self
+            .yearly_closures
+            .iter()
+            .find(|c| (c.month, c.day) == (date.month(), date.day()))
+        {
+            return Some(&closure.name);
+        }
+        match date.weekday() {
+            chrono::Weekday::Sat => Some("土曜日"),
+            chrono::Weekday::Sun => Some("日曜日"),
+            _ => None,
+        }
+    }
+
+    /// Last day on or before `day` that banks are open
+    pub fn previous_business_day(&self, day: u32) -> u32 {
+        self.business_day(day, chrono::NaiveDate::pred_opt)
+    }
+
+    /// First day on or after `day` that banks are open
+    pub fn next_business_day(&self, day: u32) -> u32 {
+        self.business_day(day, chrono::NaiveDate::succ_opt)
+    }
+
+    fn business_day(
+        &self,
+        day: u32,
+        step: fn(&chrono::NaiveDate) -> Option<chrono::NaiveDate>,
+    ) -> u32 {
+        let mut date = match parse_day(day) {
+            Some(date) => date,
+            None => return day,
+        };
+        while self.bank_holiday(to_day(date)).is_some() {
+            match step(&date) {
+                Some(other) => date = other,
+                None => break,
+            }
+        }
+        to_day(date)
+    }
+
+    /// Days banks are closed in `year` other than weekends, in date order
+    pub fn closures_in(&self, year: u32) -> Vec<Holiday> {
+        let mut days: Vec<Holiday> = self
+            .holidays
+            .iter()
+            .filter(|h| h.day / 10000 == year)
+            .cloned()
+            .collect();
+        for closure in &self.yearly_closures {
+            let day = year * 10000 + closure.month * 100 + closure.day;
+            if parse_day(day).is_some() && !days.iter().any(|h| h.day == day) {
+                days.push(Holiday {
+                    day,
+                    name: closure.name.clone(),
+                });
+            }
+        }
+        days.sort_by_key(|h| h.day);
+        days
+    }
+}
+
+/// Imported holidays, or none
+fn load_imported(storage: &Storage) -> Result<Calendar, StorageError> {
+    match storage.load(HOLIDAYS_FILE)? {
+        Some(value) => Ok(serde_json::from_value(value)?),
+        None => Ok(Calendar::default()),
+    }
+}
+
+/// Bundled calendar with the imported holidays merged in
+pub fn load(storage: Option<&Storage>) -> Result<Calendar, StorageError> {
+    let mut calendar = Calendar::bundled();
+    if let Some(storage) = storage {
+        calendar.merge(load_imported(storage)?);
+    }
+    Ok(calendar)
+}
+
+/// Add the days of a calendar file to the imported ones, replacing those
+/// on the same date. Returns the calendar with the import merged in.
+pub fn import(storage: &Storage, text: &str) -> Result<Calendar, CalendarError> {
+    let added = Calendar::parse(text)?;
+    let mut imported = load_imported(storage)?;
+    imported.merge(added);
+    storage.save(
+        HOLIDAYS_FILE,
+        &serde_json::to_value(&imported).map_err(StorageError::from)?,
+    )?;
+    Ok(load(Some(storage))?)
+}
+
+static CURRENT: RwLock<Option<Arc<Calendar>>> = RwLock::new(None);
+
+/// Calendar in use
+pub fn current() -> Arc<Calendar> {
+    if let Some(calendar) = CURRENT.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return calendar.clone();
+    }
+    CURRENT
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(|| Arc::new(Calendar::bundled()))
+        .clone()
+}
+
+/// Use `calendar` from now on
+pub fn install(calendar: Calendar) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(calendar));
+}
+
+/// Name of the public holiday on `day`, if it is one
+pub fn holiday_name(day: u32) -> Option<String> {
+    current().holiday_name(day).map(str::to_string)
+}
+
+/// Why banks are closed on `day`, if they are: weekends, public holidays
+/// and the year-end break from December 31 to January 3
+pub fn bank_holiday(day: u32) -> Option<String> {
+    current().bank_holiday(day).map(str::to_string)
+}
+
+/// Whether banks are closed on `day`
+pub fn is_bank_holiday(day: u32) -> bool {
+    current().bank_holiday(day).is_some()
+}
+
+/// Last day on or before `day` that banks are open
+pub fn previous_business_day(day: u32) -> u32 {
+    current().previous_business_day(day)
+}
+
+/// First day on or after `day` that banks are open
+pub fn next_business_day(day: u32) -> u32 {
+    current().next_business_day(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::weekday_label;
+
+    #[test]
+    fn test_bundled_holidays_match_weekdays() {
+        let calendar = Calendar::bundled();
+        assert_eq!(calendar.holidays.len(), 70);
+        for holiday in &calendar.holidays {
+            // Substitute holidays always fall on a weekday
+            if holiday.name == "振替休日" {
+                assert_ne!(weekday_label(holiday.day), Some("日"), "{}", holiday.day);
+            }
+        }
+        // Mondays of the Happy Monday system
+        for day in [20260112, 20260720, 20260921, 20261012, 20280918] {
+            assert_eq!(weekday_label(day), Some("月"), "{}", day);
+        }
+    }
+
+    #[test]
+    fn test_bank_holidays() {
+        let calendar = Calendar::bundled();
+        assert_eq!(calendar.bank_holiday(20260320), Some("春分の日"));
+        assert_eq!(calendar.bank_holiday(20260103), Some("年始休業"));
+        assert_eq!(calendar.bank_holiday(20260307), Some("土曜日"));
+        assert_eq!(calendar.bank_holiday(20260306), None);
+        assert!(is_bank_holiday(20251231));
+        // Golden Week runs from Sunday 5/3 through Wednesday 5/6
+        assert_eq!(calendar.previous_business_day(20260506), 20260501);
+        assert_eq!(calendar.next_business_day(20260502), 20260507);
+        assert_eq!(calendar.previous_business_day(20260306), 20260306);
+        assert_eq!(calendar.next_business_day(20251229), 20251229);
+        assert_eq!(calendar.next_business_day(20251231), 20260105);
+    }
+
+    #[test]
+    fn test_closures_in_year() {
+        let closures = Calendar::bundled().closures_in(2026);
+        assert_eq!(closures.first().unwrap().day, 20260101);
+        assert_eq!(closures[1].name, "年始休業");
+        assert_eq!(closures.last().unwrap().day, 20261231);
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let calendar = import(
+            &storage,
+            r#"{"holidays": [
+                {"day": 20290101, "name": "元日"},
+                {"day": 20260320, "name": "春分の日（更新）"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(calendar.holiday_name(20290101), Some("元日"));
+        assert_eq!(calendar.holiday_name(20260320), Some("春分の日（更新）"));
+        assert_eq!(calendar.holidays.len(), 71);
+        assert_eq!(load(Some(&storage)).unwrap(), calendar);
+
+        assert!(matches!(
+            import(
+                &storage,
+                r#"{"holidays": [{"day": 20290230, "name": "x"}]}"#
+            ),
+            Err(CalendarError::InvalidCalendar(_))
+        ));
+        assert!(matches!(
+            import(
+                &storage,
+                r#"{"yearlyClosures": [{"month": 13, "day": 1, "name": "x"}]}"#
+            ),
+            Err(CalendarError::InvalidCalendar(_))
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+//! Call counts, error rates and latency of each advisor method.
+//!
+//! Unlike [`crate::profiling`], which breaks requests down by phase and is
+//! switched on to investigate, these statistics are always kept and cheap:
+//! a count of calls and errors since startup and the latency of the most
+//! recent calls of each method. The frontend shows them so that the
+//! methods which are slow on users' machines get optimized first.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::profiling::percentile;
+
+/// Latencies kept per method for the percentiles
+pub const LATENCY_SAMPLES: usize = 200;
+
+/// Statistics of one advisor method
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MethodCallStats {
+    pub method: String,
+    /// Calls since startup
+    #[ts(type = "number")]
+    pub calls: u64,
+    /// Calls that failed or were answered with a JSON-RPC error
+    #[ts(type = "number")]
+    pub errors: u64,
+    /// `errors` over `calls`, from 0 to 1
+    pub error_rate: f64,
+    /// Latency percentiles over the latest [`LATENCY_SAMPLES`] calls
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    calls: u64,
+    errors: u64,
+    latencies: VecDeque<Duration>,
+}
+
+/// Counts the calls of each advisor method
+#[derive(Debug, Default)]
+pub struct CallStats {
+    methods: Mutex<HashMap<String, Counters>>,
+}
+
+impl CallStats {
+    /// Count a call of `method` that took `latency`
+    pub fn record(&self, method: &str, latency: Duration, failed: bool) {
+        let mut methods = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = methods.entry(method.to_string()).or_default();
+        counters.calls += 1;
+        if failed {
+            counters.errors += 1;
+        }
+        if counters.latencies.len() == LATENCY_SAMPLES {
+            counters.latencies.pop_front();
+        }
+        counters.latencies.push_back(latency);
+    }
+
+    /// Statistics of every method called so far, in name order
+    pub fn snapshot(&self) -> Vec<MethodCallStats> {
+        let methods = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut stats: Vec<MethodCallStats> = methods
+            .iter()
+            .map(|(method, counters)| {
+                let mut latencies: Vec<Duration> = counters.latencies.iter().copied().collect();
+                latencies.sort();
+                MethodCallStats {
+                    method: method.clone(),
+                    calls: counters.calls,
+                    errors: counters.errors,
+                    error_rate: counters.errors as f64 / counters.calls.max(1) as f64,
+                    p50_ms: ms(percentile(&latencies, 50)),
+                    p95_ms: ms(percentile(&latencies, 95)),
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.method.cmp(&b.method));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_errors_and_latency() {
+        let stats = CallStats::default();
+        for ms in 1..=20 {
+            stats.record("getRecommendation", Duration::from_millis(ms), ms % 10 == 0);
+        }
+        stats.record("ping", Duration::from_millis(3), false);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        let recommendation = &snapshot[0];
+        assert_eq!(recommendation.method, "getRecommendation");
+        assert_eq!((recommendation.calls, recommendation.errors), (20, 2));
+        assert_eq!(recommendation.error_rate, 0.1);
+        assert_eq!(recommendation.p50_ms, 10.0);
+        assert_eq!(recommendation.p95_ms, 19.0);
+        assert_eq!(snapshot[1].error_rate, 0.0);
+
+        // Counts cover every call; latencies only the latest
+        for _ in 0..LATENCY_SAMPLES {
+            stats.record("ping", Duration::from_millis(1), false);
+        }
+        let ping = &stats.snapshot()[1];
+        assert_eq!(ping.calls, LATENCY_SAMPLES as u64 + 1);
+        assert_eq!(ping.p95_ms, 1.0);
+    }
+}
@@ -0,0 +1,536 @@
+//! Scheduled encrypted backups pushed to the sync server.
+//!
+//! With [`CloudBackupSettings::enabled`], [`run_scheduler`] seals the saved
+//! dataset with the backup key ([`transfer::seal`]) and uploads it to
+//! [`UPLOAD_PATH`] on the web server configured for sync whenever the
+//! cron-like [`Schedule`] comes due, so the server only ever holds
+//! ciphertext. The server takes uploads only with its admin token
+//! ([`CloudBackupSettings::server_token`]) or from its own machine. Each attempt is recorded in [`CLOUD_BACKUP_FILE`] and shown
+//! in the diagnostics. Once backups have failed for
+//! [`CloudBackupSettings::alert_after_days`] days, a
+//! [`WebhookEvent::BackupFailing`] goes out through the notifiers, at most
+//! once a day, until one succeeds again.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, TimeDelta, Timelike};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::backup::BACKUP_DIR;
+use crate::date;
+use crate::field_crypto::FieldKey;
+use crate::handlers::AppState;
+use crate::storage::{Storage, StorageError, SCHOOLS_DATA_FILE};
+use crate::transfer;
+use crate::webhook::WebhookEvent;
+
+/// File in the data directory recording the backup attempts
+pub const CLOUD_BACKUP_FILE: &str = "cloud_backup.json";
+
+/// Path on the sync server receiving the backups
+pub const UPLOAD_PATH: &str = "/backups/cloud";
+
+/// Subdirectory of the server's backup directory holding received backups
+pub const CLOUD_BACKUP_DIR: &str = "cloud";
+
+/// Backups kept by the server; the oldest are removed first
+pub const MAX_CLOUD_BACKUPS: usize = 30;
+
+/// How often the scheduler wakes to see whether a backup is due
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Errors that can occur while backing up
+#[derive(Debug, Error)]
+pub enum CloudBackupError {
+    #[error("Invalid backup schedule: {0}")]
+    InvalidSchedule(String),
+
+    #[error("No sync server is configured")]
+    NoServer,
+
+    #[error("The backup key is not set up")]
+    NoKey,
+
+    #[error("No saved data to back up")]
+    NoData,
+
+    #[error("Upload failed: {0}")]
+    Upload(String),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// When and whether backups are pushed to the sync server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CloudBackupSettings {
+    pub enabled: bool,
+    /// Cron-like schedule in Japan time: minute, hour, day of month, month
+    /// and day of week, such as `30 3 * * *` for 3:30 every night
+    pub schedule: String,
+    /// Days of failed backups before the notifiers are alerted
+    pub alert_after_days: u32,
+    /// `admin_token` of the sync server, which only accepts uploads with
+    /// it or from its own machine
+    pub server_token: Option<String>,
+}
+
+impl Default for CloudBackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: "0 3 * * *".to_string(),
+            alert_after_days: 3,
+            server_token: None,
+        }
+    }
+}
+
+/// A parsed cron-like schedule, one bit per allowed value of each field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Whether the day of month or of week was left as `*`; when both are
+    /// given, either one matching is enough, as in cron
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// Bits of the values `field` allows between `min` and `max`
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|&s| s > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+                None => {
+                    let value = range.parse().ok()?;
+                    (value, if step > 1 { max } else { value })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Some(bits)
+}
+
+impl Schedule {
+    /// Parse five space-separated fields: minute (0-59), hour (0-23), day of
+    /// month (1-31), month (1-12) and day of week (0-7, Sunday being 0 or 7).
+    /// Each takes `*`, numbers, ranges like `1-5`, lists and steps like `*/15`.
+    pub fn parse(text: &str) -> Result<Self, CloudBackupError> {
+        let invalid = || CloudBackupError::InvalidSchedule(text.to_string());
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid());
+        };
+        let weekdays = parse_field(weekday, 0, 7).ok_or_else(invalid)?;
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).ok_or_else(invalid)?,
+            hours: parse_field(hour, 0, 23).ok_or_else(invalid)? as u32,
+            days: parse_field(day, 1, 31).ok_or_else(invalid)? as u32,
+            months: parse_field(month, 1, 12).ok_or_else(invalid)? as u16,
+            weekdays: ((weekdays | weekdays >> 7) & 0x7f) as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        day_matches && self.months & (1 << time.month()) != 0
+    }
+
+    /// The first scheduled minute after `after`, looking up to a year ahead
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let mut time = start;
+        while time - start <= TimeDelta::days(366) {
+            if !self.matches_day(&time) {
+                time = time.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + TimeDelta::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += TimeDelta::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+/// What the last backups did, for the diagnostics
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CloudBackupStatus {
+    /// RFC 3339 time of the last attempt
+    pub last_attempt_at: Option<String>,
+    /// RFC 3339 time of the last backup the server accepted
+    pub last_success_at: Option<String>,
+    /// Why the last attempt failed, if it did
+    pub last_error: Option<String>,
+    /// RFC 3339 time of the first failure since the last success
+    pub failing_since: Option<String>,
+    /// Day (YYYYMMDD) the failure alert was last sent
+    pub alerted_on: Option<u32>,
+}
+
+fn parse_time(time: &Option<String>) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(time.as_deref()?).ok()
+}
+
+impl CloudBackupStatus {
+    /// Load the recorded status, or an empty one
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(CLOUD_BACKUP_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(CLOUD_BACKUP_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Whether `schedule` has come due since the last attempt, or within
+    /// the last day if there has been none
+    pub fn is_due(&self, schedule: &Schedule, now: DateTime<FixedOffset>) -> bool {
+        let since = parse_time(&self.last_attempt_at).unwrap_or(now - TimeDelta::days(1));
+        schedule
+            .next_after(since.naive_local())
+            .is_some_and(|next| next <= now.naive_local())
+    }
+
+    /// Record a backup accepted at `now`
+    pub fn record_success(&mut self, now: DateTime<FixedOffset>) {
+        let now = now.to_rfc3339();
+        self.last_attempt_at = Some(now.clone());
+        self.last_success_at = Some(now);
+        self.last_error = None;
+        self.failing_since = None;
+        self.alerted_on = None;
+    }
+
+    /// Record a failure at `now`, returning the alert to send if backups
+    /// have now failed for `alert_after_days` days and none was sent today
+    pub fn record_failure(
+        &mut self,
+        now: DateTime<FixedOffset>,
+        error: String,
+        alert_after_days: u32,
+    ) -> Option<WebhookEvent> {
+        self.last_attempt_at = Some(now.to_rfc3339());
+        self.last_error = Some(error.clone());
+        let since = match parse_time(&self.failing_since) {
+            Some(since) => since,
+            None => {
+                self.failing_since = Some(now.to_rfc3339());
+                now
+            }
+        };
+        let days = (now - since).num_days();
+        let today = date::day_of(&now);
+        if days < i64::from(alert_after_days) || self.alerted_on == Some(today) {
+            return None;
+        }
+        self.alerted_on = Some(today);
+        Some(WebhookEvent::BackupFailing {
+            since: since.to_rfc3339(),
+            last_success: self.last_success_at.clone(),
+            days: days as u32,
+            error,
+        })
+    }
+}
+
+/// Seal the saved dataset with `key`
+pub fn seal_dataset(storage: &Storage, key: &FieldKey) -> Result<Vec<u8>, CloudBackupError> {
+    let data = storage
+        .load(SCHOOLS_DATA_FILE)?
+        .ok_or(CloudBackupError::NoData)?;
+    let json = serde_json::to_vec(&data).map_err(StorageError::from)?;
+    Ok(transfer::seal(key.bytes(), &json))
+}
+
+/// Read a backup sealed by [`seal_dataset`]
+pub fn open_backup(key: &FieldKey, sealed: &[u8]) -> Result<serde_json::Value, CloudBackupError> {
+    let json = transfer::open(key.bytes(), sealed).map_err(|_| {
+        CloudBackupError::Upload("the backup cannot be opened with this key".into())
+    })?;
+    Ok(serde_json::from_slice(&json).map_err(StorageError::from)?)
+}
+
+/// Upload `sealed` to the sync server at `server_url`, with its admin
+/// `token` if there is one
+async fn upload(
+    server_url: &str,
+    token: Option<&str>,
+    sealed: Vec<u8>,
+) -> Result<(), CloudBackupError> {
+    let url = format!("{}{}", server_url.trim_end_matches('/'), UPLOAD_PATH);
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .timeout(UPLOAD_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream");
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request
+        .body(sealed)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| CloudBackupError::Upload(e.without_url().to_string()))?;
+    Ok(())
+}
+
+/// Back up the saved data now, record the outcome and return the status
+/// with the failure alert due, if any. Only recording the outcome can fail.
+pub async fn attempt(
+    state: &AppState,
+    now: DateTime<FixedOffset>,
+) -> Result<(CloudBackupStatus, Option<WebhookEvent>), StorageError> {
+    let storage = state.storage.as_ref().ok_or(StorageError::NoDataDir)?;
+    let (server_url, token, alert_after_days) = {
+        let settings = state.settings.lock().await;
+        (
+            settings.sync.server_url.clone(),
+            settings.cloud_backup.server_token.clone(),
+            settings.cloud_backup.alert_after_days,
+        )
+    };
+    let pushed = async {
+        let server_url = server_url.ok_or(CloudBackupError::NoServer)?;
+        let key = state.backup_key().ok_or(CloudBackupError::NoKey)?;
+        upload(&server_url, token.as_deref(), seal_dataset(storage, key)?).await
+    }
+    .await;
+
+    let mut status = CloudBackupStatus::load(storage)?;
+    let alert = match pushed {
+        Ok(()) => {
+            tracing::info!("Backed up the data to the sync server");
+            status.record_success(now);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Cloud backup failed: {}", e);
+            status.record_failure(now, e.to_string(), alert_after_days)
+        }
+    };
+    status.save(storage)?;
+    Ok((status, alert))
+}
+
+/// Back up on the schedule in the settings forever, sending failure alerts
+/// to the notifiers and to `notify`.
+///
+/// Wakes every [`POLL_INTERVAL`]; a backup missed while the computer slept
+/// runs on waking. Settings changes apply from the next wake.
+pub async fn run_scheduler<F>(state: Arc<AppState>, notify: F)
+where
+    F: Fn(&WebhookEvent) + Send + 'static,
+{
+    loop {
+        let settings = state.settings.lock().await.cloud_backup.clone();
+        if let (true, Some(storage)) = (settings.enabled, &state.storage) {
+            let now = date::now();
+            let due = Schedule::parse(&settings.schedule).is_ok_and(|schedule| {
+                CloudBackupStatus::load(storage).is_ok_and(|s| s.is_due(&schedule, now))
+            });
+            if due {
+                match attempt(&state, now).await {
+                    Ok((_, Some(alert))) => {
+                        notify(&alert);
+                        state.notify(alert);
+                    }
+                    Ok((_, None)) => {}
+                    Err(e) => tracing::warn!("Failed to record the cloud backup: {}", e),
+                }
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn cloud_backup_dir(storage: &Storage) -> PathBuf {
+    storage.data_dir().join(BACKUP_DIR).join(CLOUD_BACKUP_DIR)
+}
+
+/// Keep a backup received from a client, named by the time it arrived,
+/// removing the oldest beyond [`MAX_CLOUD_BACKUPS`]. Returns the file name.
+pub fn store_received(
+    storage: &Storage,
+    sealed: &[u8],
+    received_at: DateTime<FixedOffset>,
+) -> Result<String, StorageError> {
+    let dir = cloud_backup_dir(storage);
+    fs::create_dir_all(&dir)?;
+    let file_name = format!("cloud-{}.bin", received_at.format("%Y%m%d-%H%M%S"));
+    fs::write(dir.join(&file_name), sealed)?;
+
+    let mut names: Vec<String> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("cloud-") && name.ends_with(".bin"))
+        .collect();
+    names.sort();
+    let excess = names.len().saturating_sub(MAX_CLOUD_BACKUPS);
+    for old in &names[..excess] {
+        fs::remove_file(dir.join(old))?;
+    }
+    Ok(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    fn naive(text: &str) -> NaiveDateTime {
+        at(text).naive_local()
+    }
+
+    #[test]
+    fn test_schedule() {
+        let nightly = Schedule::parse("30 3 * * *").unwrap();
+        assert_eq!(
+            nightly.next_after(naive("2026-03-01T03:30:00+09:00")),
+            Some(naive("2026-03-02T03:30:00+09:00"))
+        );
+        assert_eq!(
+            nightly.next_after(naive("2026-03-01T01:10:20+09:00")),
+            Some(naive("2026-03-01T03:30:00+09:00"))
+        );
+
+        // Weekdays at 22:00 and 22:30; 2026-03-07 is a Saturday
+        let weekdays = Schedule::parse("0,30 22 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next_after(naive("2026-03-06T22:40:00+09:00")),
+            Some(naive("2026-03-09T22:00:00+09:00"))
+        );
+        // Sunday as 7, every six hours
+        let sundays = Schedule::parse("0 */6 * * 7").unwrap();
+        assert_eq!(
+            sundays.next_after(naive("2026-03-01T07:00:00+09:00")),
+            Some(naive("2026-03-01T12:00:00+09:00"))
+        );
+        // The 1st of each month or any Monday
+        let either = Schedule::parse("0 0 1 * 1").unwrap();
+        assert_eq!(
+            either.next_after(naive("2026-03-01T00:00:00+09:00")),
+            Some(naive("2026-03-02T00:00:00+09:00"))
+        );
+        assert_eq!(
+            Schedule::parse("0 0 31 2 *")
+                .unwrap()
+                .next_after(naive("2026-03-01T00:00:00+09:00")),
+            None
+        );
+
+        for invalid in [
+            "",
+            "0 3 * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(Schedule::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_failures_alert_once_a_day_after_the_limit() {
+        let schedule = Schedule::parse("0 3 * * *").unwrap();
+        let mut status = CloudBackupStatus::default();
+        assert!(status.is_due(&schedule, at("2026-03-01T09:00:00+09:00")));
+
+        let first = at("2026-03-01T03:00:00+09:00");
+        assert!(status.record_failure(first, "timeout".into(), 2).is_none());
+        assert!(!status.is_due(&schedule, at("2026-03-01T09:00:00+09:00")));
+        assert!(status.is_due(&schedule, at("2026-03-02T03:00:00+09:00")));
+        assert!(status
+            .record_failure(at("2026-03-02T03:00:00+09:00"), "timeout".into(), 2)
+            .is_none());
+
+        let alert = status.record_failure(at("2026-03-03T03:00:00+09:00"), "503".into(), 2);
+        match alert {
+            Some(WebhookEvent::BackupFailing { since, days, .. }) => {
+                assert_eq!(at(&since), first);
+                assert_eq!(days, 2);
+            }
+            other => panic!("expected an alert, got {:?}", other),
+        }
+        // Retried the same day: no second alert
+        assert!(status
+            .record_failure(at("2026-03-03T09:00:00+09:00"), "503".into(), 2)
+            .is_none());
+
+        status.record_success(at("2026-03-04T03:00:00+09:00"));
+        assert_eq!(status.failing_since, None);
+        assert_eq!(status.last_error, None);
+        assert!(status.last_success_at.is_some());
+    }
+
+    #[test]
+    fn test_sealed_backups_are_kept_by_the_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let key = FieldKey::generate();
+        assert!(matches!(
+            seal_dataset(&storage, &key),
+            Err(CloudBackupError::NoData)
+        ));
+        let data = serde_json::json!({ "schools": [{ "id": 1, "name": "早稲田大学" }] });
+        storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+
+        let sealed = seal_dataset(&storage, &key).unwrap();
+        assert!(!String::from_utf8_lossy(&sealed).contains("早稲田"));
+        assert_eq!(open_backup(&key, &sealed).unwrap(), data);
+        assert!(open_backup(&FieldKey::generate(), &sealed).is_err());
+
+        let mut received = at("2026-03-01T03:00:00+09:00");
+        for _ in 0..MAX_CLOUD_BACKUPS + 2 {
+            store_received(&storage, &sealed, received).unwrap();
+            received += TimeDelta::days(1);
+        }
+        let kept = fs::read_dir(cloud_backup_dir(&storage)).unwrap().count();
+        assert_eq!(kept, MAX_CLOUD_BACKUPS);
+        assert!(!cloud_backup_dir(&storage)
+            .join("cloud-20260301-030000.bin")
+            .exists());
+    }
+}
@@ -0,0 +1,249 @@
+//! Application configuration loaded from `config.toml`.
+//!
+//! The file is optional: a missing file yields the default configuration,
+//! so both the desktop app and the web server run without one.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::currency::ExchangeRateConfig;
+use crate::email::EmailConfig;
+use crate::google_calendar::GoogleCalendarConfig;
+use crate::logs::LogConfig;
+use crate::notifier::NotifierConfig;
+use crate::profiling::ProfilingConfig;
+use crate::reminders::ReminderConfig;
+use crate::web_push::WebPushConfig;
+use crate::webhook::WebhookConfig;
+
+/// Default configuration filename
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Errors that can occur while loading configuration
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid config file: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// Top-level application configuration
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Run on sample data with the built-in mock advisor, as with `--demo`
+    pub demo: bool,
+    /// Outgoing webhook targets
+    pub webhooks: Vec<WebhookConfig>,
+    /// Chat notification targets (LINE, Slack, Discord)
+    pub notifiers: Vec<NotifierConfig>,
+    /// Deadline reminder settings
+    pub reminders: ReminderConfig,
+    /// SMTP digest settings; email is disabled when absent
+    pub email: Option<EmailConfig>,
+    /// Google Calendar OAuth client; sync is unavailable when absent
+    pub google_calendar: Option<GoogleCalendarConfig>,
+    /// Exchange rate service settings
+    pub exchange_rates: ExchangeRateConfig,
+    /// Log file location and retention
+    pub logging: LogConfig,
+    /// Latency profiling of advisor requests
+    pub profiling: ProfilingConfig,
+    /// Web server settings
+    pub web: WebConfig,
+    /// Desktop app settings
+    pub desktop: DesktopConfig,
+}
+
+/// Settings that only apply to the desktop app
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DesktopConfig {
+    /// Hide to the system tray instead of quitting when the window is
+    /// closed, until changed in the settings
+    pub close_to_tray: bool,
+}
+
+/// Settings that only apply to the web server
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WebConfig {
+    /// Require a double-submit CSRF token on mutating requests that carry cookies
+    pub csrf: bool,
+    /// File holding the key of encrypted fields; keep it outside the data
+    /// directory. Encrypted data cannot be read when absent.
+    pub field_key_file: Option<PathBuf>,
+    /// File holding the key signing the audit log, created if missing;
+    /// keep it outside the data directory. Nothing is audited when absent.
+    pub audit_key_file: Option<PathBuf>,
+    /// File holding the key signing share links, created if missing. When
+    /// absent, the key is kept in `share.key` in the data directory.
+    pub share_key_file: Option<PathBuf>,
+    /// VAPID keys for Web Push; browsers cannot subscribe when absent
+    pub push: Option<WebPushConfig>,
+    /// Origins browsers may call the API from, such as
+    /// `https://school.example.com`; when empty, only the frontend dev
+    /// server on this machine
+    pub allowed_origins: Vec<String>,
+    /// Bearer token for the `/admin` routes; without it they only answer
+    /// requests from this machine
+    pub admin_token: Option<String>,
+}
+
+impl AppConfig {
+    /// Load configuration from a TOML file, falling back to defaults if it does not exist
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Parse configuration from a TOML string
+    pub fn parse(content: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webhook::WebhookEventKind;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_missing_file() {
+        let dir = tempdir().unwrap();
+        let config = AppConfig::load(&dir.path().join(CONFIG_FILE)).unwrap();
+        assert!(config.webhooks.is_empty());
+    }
+
+    #[test]
+    fn test_parse_webhooks() {
+        let config = AppConfig::parse(
+            r#"
+            [[webhooks]]
+            url = "http://homeassistant.local/api/webhook/payments"
+            secret = "s3cret"
+            events = ["deadlineApproaching", "replCrashed"]
+
+            [[webhooks]]
+            url = "http://n8n.local/webhook/all"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.webhooks.len(), 2);
+        assert_eq!(
+            config.webhooks[0].events,
+            vec![
+                WebhookEventKind::DeadlineApproaching,
+                WebhookEventKind::ReplCrashed
+            ]
+        );
+        assert!(config.webhooks[1].events.is_empty());
+        assert_eq!(config.webhooks[1].max_retries, 3);
+        assert_eq!(config.reminders.lead_days, 3);
+        assert!(config.reminders.desktop_notifications);
+    }
+
+    #[test]
+    fn test_parse_reminders() {
+        let config =
+            AppConfig::parse("[reminders]\nlead_days = 7\ndesktop_notifications = false\n")
+                .unwrap();
+        assert_eq!(config.reminders.lead_days, 7);
+        assert!(!config.reminders.desktop_notifications);
+        assert_eq!(config.reminders.check_interval_minutes, 60);
+    }
+
+    #[test]
+    fn test_parse_web() {
+        let config = AppConfig::parse(
+            "[web]\ncsrf = true\nfield_key_file = \"/etc/school-payment/field.key\"\n",
+        )
+        .unwrap();
+        assert!(config.web.csrf);
+        assert_eq!(
+            config.web.field_key_file,
+            Some(PathBuf::from("/etc/school-payment/field.key"))
+        );
+        assert!(!AppConfig::default().web.csrf);
+        assert!(AppConfig::default().web.allowed_origins.is_empty());
+        assert!(AppConfig::default().web.admin_token.is_none());
+    }
+
+    #[test]
+    fn test_parse_profiling() {
+        let config = AppConfig::parse("[profiling]\nenabled = true\n").unwrap();
+        assert!(config.profiling.enabled);
+        assert_eq!(config.profiling.samples, 500);
+        assert!(!AppConfig::default().profiling.enabled);
+    }
+
+    #[test]
+    fn test_parse_demo() {
+        let config = AppConfig::parse("demo = true\n[web]\ncsrf = true\n").unwrap();
+        assert!(config.demo);
+        assert!(!AppConfig::default().demo);
+    }
+
+    #[test]
+    fn test_parse_google_calendar() {
+        let config = AppConfig::parse(
+            "[google_calendar]\nclient_id = \"id.apps.googleusercontent.com\"\nclient_secret = \"s\"\n",
+        )
+        .unwrap();
+        let google = config.google_calendar.unwrap();
+        assert_eq!(google.calendar_name, "学費の支払い期限");
+        assert_eq!(google.sync_interval_minutes, 30);
+        assert!(AppConfig::default().google_calendar.is_none());
+    }
+
+    #[test]
+    fn test_parse_desktop() {
+        let config = AppConfig::parse("[desktop]\nclose_to_tray = true\n").unwrap();
+        assert!(config.desktop.close_to_tray);
+        assert!(!AppConfig::default().desktop.close_to_tray);
+    }
+
+    #[test]
+    fn test_parse_exchange_rates() {
+        let config = AppConfig::parse(
+            "[exchange_rates]\nauto_refresh = true\nprovider_url = \"http://rates.local/JPY\"\n",
+        )
+        .unwrap();
+        assert!(config.exchange_rates.auto_refresh);
+        assert_eq!(config.exchange_rates.provider_url, "http://rates.local/JPY");
+        assert_eq!(config.exchange_rates.max_age_hours, 24);
+        assert!(!AppConfig::default().exchange_rates.auto_refresh);
+    }
+
+    #[test]
+    fn test_parse_logging() {
+        let config =
+            AppConfig::parse("[logging]\ndir = \"/var/log/school-payment\"\nmax_age_days = 30\n")
+                .unwrap();
+        assert_eq!(
+            config.logging.dir(Path::new("data")),
+            Path::new("/var/log/school-payment")
+        );
+        assert_eq!(config.logging.max_age_days, 30);
+        assert_eq!(config.logging.max_total_size_mb, 50);
+        assert_eq!(
+            AppConfig::default().logging.dir(Path::new("data")),
+            Path::new("data/logs")
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(AppConfig::parse("webhooks = 1").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Live countdown to payment deadlines, for badges.
+//!
+//! A [`Countdown`] sorts the unpaid deadlines by how soon they are due:
+//! overdue, today, within 3 days and within 7 days. [`run_countdown`]
+//! recomputes it on a timer and just after midnight in Japan, when every
+//! deadline moves a day closer, and publishes it on a watch channel held
+//! by `AppState` whenever it changes. The desktop app forwards it to the
+//! frontend as an event and the web server streams it as server-sent
+//! events, so badge counts stay right in a window left open overnight.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use serde::Serialize;
+
+use crate::date;
+use crate::handlers::{AppState, HandlerError};
+use crate::reminders::{unpaid_deadlines, UpcomingDeadline};
+
+/// How often the countdown is recomputed, to pick up changed data
+pub const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Unpaid deadlines by how soon they are due
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Countdown {
+    /// Day the countdown was computed for
+    pub today: u32,
+    /// Past their deadline, most overdue first
+    pub overdue: Vec<UpcomingDeadline>,
+    pub due_today: Vec<UpcomingDeadline>,
+    /// Due in 1 to 3 days
+    pub within_3_days: Vec<UpcomingDeadline>,
+    /// Due in 4 to 7 days
+    pub within_7_days: Vec<UpcomingDeadline>,
+}
+
+impl Countdown {
+    /// Sort the unpaid deadlines in `data` as of `today`
+    pub fn compute(data: &serde_json::Value, today: u32) -> Self {
+        let mut countdown = Self {
+            today,
+            ..Self::default()
+        };
+        for deadline in unpaid_deadlines(data, today) {
+            let bucket = match deadline.days_left {
+                ..0 => &mut countdown.overdue,
+                0 => &mut countdown.due_today,
+                1..=3 => &mut countdown.within_3_days,
+                4..=7 => &mut countdown.within_7_days,
+                _ => continue,
+            };
+            bucket.push(deadline);
+        }
+        countdown
+    }
+
+    /// Deadlines overdue or due within a week, for a badge
+    pub fn badge_count(&self) -> usize {
+        self.overdue.len()
+            + self.due_today.len()
+            + self.within_3_days.len()
+            + self.within_7_days.len()
+    }
+}
+
+/// Time from `now` until the next midnight in Japan
+pub fn until_midnight(now: DateTime<FixedOffset>) -> Duration {
+    let now = now.with_timezone(&date::jst());
+    let midnight = (now.date_naive() + TimeDelta::days(1))
+        .and_hms_opt(0, 0, 0)
+        .expect("valid time");
+    (midnight - now.naive_local()).to_std().unwrap_or_default()
+}
+
+/// Compute the countdown for the saved data now and publish it
+pub fn refresh(state: &AppState) -> Result<Countdown, HandlerError> {
+    let today = date::today();
+    let countdown = match state.load_dataset() {
+        Ok(data) => Countdown::compute(&data, today),
+        Err(HandlerError::NoData) | Err(HandlerError::NoStorage) => Countdown {
+            today,
+            ..Countdown::default()
+        },
+        Err(e) => return Err(e),
+    };
+    state.set_countdown(countdown.clone());
+    Ok(countdown)
+}
+
+/// Refresh the countdown forever, every `interval` and just after midnight
+pub async fn run_countdown(state: Arc<AppState>, interval: Duration) {
+    loop {
+        if let Err(e) = refresh(&state) {
+            tracing::warn!("Deadline countdown could not read data: {}", e);
+        }
+        // A second late, so the new day has begun
+        let midnight = until_midnight(date::now()) + Duration::from_secs(1);
+        tokio::time::sleep(interval.min(midnight)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        let data = serde_json::json!({
+            "schools": [{
+                "id": 1,
+                "name": "東京大学",
+                "enrollmentFeeDeadline": 20260305,
+                "enrollmentFee": 282000,
+                "tuitionDeadline": 20260331,
+                "tuition": 535800,
+                "passStatus": "passed"
+            }, {
+                "id": 2,
+                "name": "早稲田大学",
+                "enrollmentFeeDeadline": 20260303,
+                "enrollmentFee": 200000,
+                "tuitionDeadline": 20260310,
+                "tuition": 800000,
+                "passStatus": "passed"
+            }]
+        });
+
+        let countdown = Countdown::compute(&data, 20260303);
+        assert!(countdown.overdue.is_empty());
+        assert_eq!(countdown.due_today[0].school_id, 2);
+        assert_eq!(countdown.within_3_days[0].deadline, 20260305);
+        assert_eq!(countdown.within_7_days[0].deadline, 20260310);
+        assert_eq!(countdown.badge_count(), 3);
+
+        // The next day, the enrollment fee due yesterday is overdue
+        let countdown = Countdown::compute(&data, 20260304);
+        assert_eq!(countdown.overdue[0].days_left, -1);
+        assert!(countdown.due_today.is_empty());
+        assert_eq!(countdown.badge_count(), 3);
+    }
+
+    #[test]
+    fn test_until_midnight_in_japan() {
+        let late = DateTime::parse_from_rfc3339("2026-03-03T23:59:30+09:00").unwrap();
+        assert_eq!(until_midnight(late), Duration::from_secs(30));
+
+        // 15:00 UTC is midnight in Japan
+        let utc = DateTime::parse_from_rfc3339("2026-03-03T14:00:00+00:00").unwrap();
+        assert_eq!(until_midnight(utc), Duration::from_secs(3600));
+    }
+}
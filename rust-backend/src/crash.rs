@@ -0,0 +1,166 @@
+//! Crash reports written by a panic hook.
+//!
+//! [`install_panic_hook`] records each panic as a JSON report in
+//! `crashes/` under the data directory, together with the most recent log
+//! lines and the app and advisor versions, and points [`CRASH_MARKER`] at
+//! it. On the next launch [`take_last_crash`] returns that report once, so
+//! the user can be offered to send it.
+
+use std::backtrace::Backtrace;
+use std::fs;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::logs::{self, LogLevel};
+use crate::support;
+
+/// Subdirectory of the data directory holding crash reports
+pub const CRASH_DIR: &str = "crashes";
+
+/// File naming the report of a crash not yet seen by the user
+pub const CRASH_MARKER: &str = "crash.marker";
+
+/// Log lines included in a report
+pub const CRASH_LOG_LINES: usize = 100;
+
+/// What the panic hook needs to know about the running app
+#[derive(Debug, Clone)]
+pub struct CrashContext {
+    pub app_version: String,
+    pub data_dir: PathBuf,
+    /// Directory the log files are written to
+    pub log_dir: PathBuf,
+    pub advisor_path: PathBuf,
+}
+
+/// One crash, as saved in `crashes/`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub file_name: String,
+    pub created_at: String,
+    pub app_version: String,
+    pub advisor_path: String,
+    pub advisor_sha256: Option<String>,
+    pub thread: String,
+    pub message: String,
+    /// `file:line:column` of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub recent_logs: Vec<String>,
+}
+
+impl CrashReport {
+    fn from_panic(context: &CrashContext, info: &PanicHookInfo<'_>) -> Self {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let now = chrono::Local::now();
+        Self {
+            file_name: format!("crash-{}.json", now.format("%Y%m%d-%H%M%S")),
+            created_at: now.to_rfc3339(),
+            app_version: context.app_version.clone(),
+            advisor_path: context.advisor_path.display().to_string(),
+            advisor_sha256: support::file_sha256(&context.advisor_path).ok(),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("<unnamed>")
+                .to_string(),
+            message,
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            backtrace: Backtrace::force_capture().to_string(),
+            recent_logs: logs::recent_lines(&context.log_dir, CRASH_LOG_LINES, LogLevel::Trace)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Save a report and mark it as the last crash
+pub fn write_report(data_dir: &Path, report: &CrashReport) -> io::Result<PathBuf> {
+    let dir = data_dir.join(CRASH_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(&report.file_name);
+    fs::write(&path, serde_json::to_vec_pretty(report)?)?;
+    fs::write(data_dir.join(CRASH_MARKER), &report.file_name)?;
+    Ok(path)
+}
+
+/// The report of the last crash, if it has not been taken yet.
+///
+/// Clears the marker, so each crash is offered only once; the report
+/// itself stays in `crashes/`.
+pub fn take_last_crash(data_dir: &Path) -> Option<CrashReport> {
+    let marker = data_dir.join(CRASH_MARKER);
+    let file_name = fs::read_to_string(&marker).ok()?;
+    if let Err(e) = fs::remove_file(&marker) {
+        tracing::warn!("Could not clear crash marker: {}", e);
+    }
+    let path = data_dir.join(CRASH_DIR).join(file_name.trim());
+    let bytes = fs::read(&path)
+        .inspect_err(|e| tracing::warn!("Cannot read crash report {:?}: {}", path, e))
+        .ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Write a crash report on every panic, then run the previous hook
+pub fn install_panic_hook(context: CrashContext) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::from_panic(&context, info);
+        match write_report(&context.data_dir, &report) {
+            Ok(path) => tracing::error!(
+                "Panic: {}; crash report written to {:?}",
+                report.message,
+                path
+            ),
+            Err(e) => tracing::error!(
+                "Panic: {}; could not write crash report: {}",
+                report.message,
+                e
+            ),
+        }
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_report() -> CrashReport {
+        CrashReport {
+            file_name: "crash-20260301-120000.json".to_string(),
+            created_at: "2026-03-01T12:00:00+09:00".to_string(),
+            app_version: "1.0.0".to_string(),
+            advisor_path: "/opt/advisor".to_string(),
+            advisor_sha256: None,
+            thread: "main".to_string(),
+            message: "index out of bounds".to_string(),
+            location: Some("src/lib.rs:1:1".to_string()),
+            backtrace: String::new(),
+            recent_logs: vec!["last line".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_last_crash_is_taken_once() {
+        let dir = tempdir().unwrap();
+        assert_eq!(take_last_crash(dir.path()), None);
+
+        let path = write_report(dir.path(), &sample_report()).unwrap();
+        assert!(path.starts_with(dir.path().join(CRASH_DIR)));
+
+        assert_eq!(take_last_crash(dir.path()), Some(sample_report()));
+        assert_eq!(take_last_crash(dir.path()), None);
+        assert!(path.exists());
+    }
+}
@@ -0,0 +1,332 @@
+//! Exchange rates for comparing fees in different currencies.
+//!
+//! Rates are stored as yen per major unit of each currency in
+//! [`EXCHANGE_RATES_FILE`]. They are either entered by the user or fetched
+//! from the provider set in [`ExchangeRateConfig`]; a fetch never
+//! overwrites a manual rate, so a family can fix the rate they budget with.
+//!
+//! Fetched rates older than the configured maximum age are stale. With
+//! automatic refresh on, stale or missing rates are fetched again before
+//! they are used; when the provider cannot be reached, the last known rates
+//! are used and reported as stale.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::model::Currency;
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding the rates
+pub const EXCHANGE_RATES_FILE: &str = "exchange-rates.json";
+
+/// Free rate service quoting every currency per yen
+pub const DEFAULT_RATES_URL: &str = "https://open.er-api.com/v6/latest/JPY";
+
+fn default_provider_url() -> String {
+    DEFAULT_RATES_URL.to_string()
+}
+
+fn default_max_age_hours() -> u64 {
+    24
+}
+
+/// Rate service settings from `config.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExchangeRateConfig {
+    /// Fetch stale or missing rates before they are used
+    #[serde(default)]
+    pub auto_refresh: bool,
+    /// Service quoting every currency per yen, shaped like [`DEFAULT_RATES_URL`]
+    #[serde(default = "default_provider_url")]
+    pub provider_url: String,
+    /// How long a fetched rate stays current
+    #[serde(default = "default_max_age_hours")]
+    pub max_age_hours: u64,
+}
+
+impl Default for ExchangeRateConfig {
+    fn default() -> Self {
+        Self {
+            auto_refresh: false,
+            provider_url: default_provider_url(),
+            max_age_hours: default_max_age_hours(),
+        }
+    }
+}
+
+impl ExchangeRateConfig {
+    pub fn max_age(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.max_age_hours.min(i64::MAX as u64 / 3600) as i64)
+    }
+}
+
+/// Errors that can occur while updating rates
+#[derive(Debug, Error)]
+pub enum CurrencyError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error("Invalid exchange rate for {0}")]
+    InvalidRate(&'static str),
+
+    #[error("Unexpected response from the rate service")]
+    BadResponse,
+}
+
+/// Where a rate came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum RateSource {
+    Manual,
+    Fetched,
+}
+
+/// Value of one major unit of a currency in yen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Rate {
+    pub yen_per_unit: f64,
+    pub source: RateSource,
+    /// RFC 3339 time the rate was set or fetched
+    pub updated_at: String,
+}
+
+/// Known rates, keyed by currency
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ExchangeRates {
+    pub rates: BTreeMap<Currency, Rate>,
+}
+
+/// Response of the rate service
+#[derive(Deserialize)]
+struct RatesResponse {
+    result: String,
+    rates: HashMap<String, f64>,
+}
+
+impl ExchangeRates {
+    /// Load saved rates, or none if nothing has been saved
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(EXCHANGE_RATES_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(EXCHANGE_RATES_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Yen per major unit of `currency`, if known
+    pub fn yen_per_unit(&self, currency: Currency) -> Option<f64> {
+        match currency {
+            Currency::Jpy => Some(1.0),
+            _ => self.rates.get(&currency).map(|r| r.yen_per_unit),
+        }
+    }
+
+    /// Set a rate by hand, or with `None` remove it
+    pub fn set_manual(
+        &mut self,
+        currency: Currency,
+        yen_per_unit: Option<f64>,
+    ) -> Result<(), CurrencyError> {
+        let Some(yen_per_unit) = yen_per_unit else {
+            self.rates.remove(&currency);
+            return Ok(());
+        };
+        if currency == Currency::Jpy || !(yen_per_unit.is_finite() && yen_per_unit > 0.0) {
+            return Err(CurrencyError::InvalidRate(currency.code()));
+        }
+        self.rates.insert(
+            currency,
+            Rate {
+                yen_per_unit,
+                source: RateSource::Manual,
+                updated_at: crate::date::now().to_rfc3339(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Take fetched quotes (units of each currency per yen), keeping manual rates
+    pub fn apply_quotes(&mut self, quotes: &HashMap<String, f64>, updated_at: &str) {
+        for currency in Currency::ALL {
+            let Some(&per_yen) = quotes.get(currency.code()) else {
+                continue;
+            };
+            let manual = matches!(
+                self.rates.get(&currency),
+                Some(Rate {
+                    source: RateSource::Manual,
+                    ..
+                })
+            );
+            if currency == Currency::Jpy || manual || !(per_yen.is_finite() && per_yen > 0.0) {
+                continue;
+            }
+            self.rates.insert(
+                currency,
+                Rate {
+                    yen_per_unit: 1.0 / per_yen,
+                    source: RateSource::Fetched,
+                    updated_at: updated_at.to_string(),
+                },
+            );
+        }
+    }
+
+    /// Currencies among `currencies` whose rate was fetched more than
+    /// `max_age` before `now`. Manual rates are never stale.
+    pub fn stale<Tz: TimeZone>(
+        &self,
+        currencies: impl IntoIterator<Item = Currency>,
+        now: &DateTime<Tz>,
+        max_age: chrono::Duration,
+    ) -> Vec<Currency> {
+        let mut stale: Vec<Currency> = currencies
+            .into_iter()
+            .filter(|currency| match self.rates.get(currency) {
+                Some(rate) if rate.source == RateSource::Fetched => {
+                    DateTime::parse_from_rfc3339(&rate.updated_at)
+                        .map_or(true, |updated| now.fixed_offset() - updated > max_age)
+                }
+                _ => false,
+            })
+            .collect();
+        stale.sort();
+        stale.dedup();
+        stale
+    }
+
+    /// Whether any of `currencies` has a stale rate or none at all
+    pub fn needs_refresh<Tz: TimeZone>(
+        &self,
+        currencies: impl IntoIterator<Item = Currency> + Clone,
+        now: &DateTime<Tz>,
+        max_age: chrono::Duration,
+    ) -> bool {
+        currencies
+            .clone()
+            .into_iter()
+            .any(|currency| self.yen_per_unit(currency).is_none())
+            || !self.stale(currencies, now, max_age).is_empty()
+    }
+
+    /// Convert an amount in `from`'s minor units to `to`'s, rounding to
+    /// the nearest unit. `None` if either rate is unknown.
+    pub fn convert(&self, amount: u64, from: Currency, to: Currency) -> Option<u64> {
+        if from == to {
+            return Some(amount);
+        }
+        let major = amount as f64 / 10f64.powi(from.minor_digits() as i32);
+        let yen = major * self.yen_per_unit(from)?;
+        let converted = yen / self.yen_per_unit(to)? * 10f64.powi(to.minor_digits() as i32);
+        Some(converted.round() as u64)
+    }
+}
+
+/// Fetch current quotes from a service shaped like [`DEFAULT_RATES_URL`]
+pub async fn fetch_quotes(url: &str) -> Result<HashMap<String, f64>, CurrencyError> {
+    let response: RatesResponse = reqwest::Client::new()
+        .get(url)
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if response.result != "success" {
+        return Err(CurrencyError::BadResponse);
+    }
+    Ok(response.rates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let mut rates = ExchangeRates::default();
+        rates.set_manual(Currency::Usd, Some(150.0)).unwrap();
+        rates.set_manual(Currency::Eur, Some(160.0)).unwrap();
+
+        // US$100.00 in cents
+        assert_eq!(
+            rates.convert(10000, Currency::Usd, Currency::Jpy),
+            Some(15000)
+        );
+        assert_eq!(
+            rates.convert(15000, Currency::Jpy, Currency::Usd),
+            Some(10000)
+        );
+        assert_eq!(
+            rates.convert(16000, Currency::Usd, Currency::Eur),
+            Some(15000)
+        );
+        assert_eq!(rates.convert(100, Currency::Gbp, Currency::Jpy), None);
+        assert_eq!(rates.convert(100, Currency::Gbp, Currency::Gbp), Some(100));
+    }
+
+    #[test]
+    fn test_fetched_quotes_keep_manual_rates() {
+        let mut rates = ExchangeRates::default();
+        rates.set_manual(Currency::Usd, Some(140.0)).unwrap();
+        let quotes = HashMap::from([
+            ("USD".to_string(), 0.0066),
+            ("EUR".to_string(), 0.00625),
+            ("JPY".to_string(), 1.0),
+            ("XYZ".to_string(), 2.0),
+        ]);
+        rates.apply_quotes(&quotes, "2026-03-01T00:00:00+09:00");
+
+        assert_eq!(rates.yen_per_unit(Currency::Usd), Some(140.0));
+        assert_eq!(rates.yen_per_unit(Currency::Eur), Some(160.0));
+        assert_eq!(rates.rates[&Currency::Eur].source, RateSource::Fetched);
+        assert!(!rates.rates.contains_key(&Currency::Jpy));
+    }
+
+    #[test]
+    fn test_stale_rates() {
+        let mut rates = ExchangeRates::default();
+        rates.set_manual(Currency::Usd, Some(150.0)).unwrap();
+        let quotes = HashMap::from([("EUR".to_string(), 0.00625), ("GBP".to_string(), 0.005)]);
+        rates.apply_quotes(&quotes, "2026-03-01T00:00:00+09:00");
+        let now = DateTime::parse_from_rfc3339("2026-03-02T12:00:00+09:00").unwrap();
+        let max_age = ExchangeRateConfig::default().max_age();
+
+        // Manual rates never go stale; Jpy needs no rate
+        let used = [Currency::Jpy, Currency::Usd, Currency::Eur];
+        assert_eq!(rates.stale(used, &now, max_age), vec![Currency::Eur]);
+        assert!(rates
+            .stale(used, &now, chrono::Duration::hours(48))
+            .is_empty());
+        assert!(rates.needs_refresh(used, &now, max_age));
+        assert!(!rates.needs_refresh([Currency::Usd], &now, max_age));
+        assert!(rates.needs_refresh([Currency::Aud], &now, max_age));
+    }
+
+    #[test]
+    fn test_invalid_manual_rates() {
+        let mut rates = ExchangeRates::default();
+        assert!(rates.set_manual(Currency::Usd, Some(0.0)).is_err());
+        assert!(rates.set_manual(Currency::Usd, Some(f64::NAN)).is_err());
+        assert!(rates.set_manual(Currency::Jpy, Some(1.0)).is_err());
+        rates.set_manual(Currency::Usd, Some(150.0)).unwrap();
+        rates.set_manual(Currency::Usd, None).unwrap();
+        assert_eq!(rates.yen_per_unit(Currency::Usd), None);
+    }
+}
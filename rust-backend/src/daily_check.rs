@@ -0,0 +1,261 @@
+//! Once-a-day recommendation check for desktop notifications.
+//!
+//! While the desktop app is running, even minimized to the tray,
+//! [`run_daily_check`] asks the advisor for today's recommendation once
+//! per day and compares it with the previous run, recorded in
+//! `daily-check.json` so restarts do not repeat notifications. A change of
+//! action, or an unpaid deadline newly inside the reminder window, is
+//! reported as [`WebhookEvent`]s for the caller to show.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::budget::BudgetAlert;
+use crate::date;
+use crate::handlers::{self, AppState, HandlerError};
+use crate::json_rpc::JsonRpcRequest;
+use crate::reminders::{self, UpcomingDeadline};
+use crate::webhook::WebhookEvent;
+
+/// File recording the previous check in the data directory
+pub const DAILY_CHECK_FILE: &str = "daily-check.json";
+
+/// How often the scheduler wakes to see whether today's check has run
+pub const CHECK_POLL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// What one check saw
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckRecord {
+    pub day: u32,
+    /// Recommended action, if the advisor returned one
+    pub action: Option<serde_json::Value>,
+    /// Unpaid deadlines inside the reminder window
+    pub deadlines: Vec<UpcomingDeadline>,
+}
+
+/// Differences between a check and the one before it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckOutcome {
+    /// Previous and current action, when the action changed to something to do
+    pub action_changed: Option<(serde_json::Value, serde_json::Value)>,
+    /// Deadlines that were not in the window last time
+    pub new_deadlines: Vec<UpcomingDeadline>,
+    /// Budget limits the changed action would go beyond
+    pub budget_alerts: Vec<BudgetAlert>,
+}
+
+impl CheckOutcome {
+    /// Check whether there is anything worth notifying
+    pub fn is_empty(&self) -> bool {
+        self.action_changed.is_none()
+            && self.new_deadlines.is_empty()
+            && self.budget_alerts.is_empty()
+    }
+
+    /// The outcome as events, in the order they should be shown
+    pub fn events(&self) -> Vec<WebhookEvent> {
+        let mut events: Vec<WebhookEvent> = self
+            .action_changed
+            .iter()
+            .map(|(previous, current)| WebhookEvent::RecommendationChanged {
+                previous: previous.clone(),
+                current: current.clone(),
+            })
+            .collect();
+        events.extend(
+            self.new_deadlines
+                .iter()
+                .cloned()
+                .map(WebhookEvent::DeadlineApproaching),
+        );
+        events.extend(
+            self.budget_alerts
+                .iter()
+                .cloned()
+                .map(WebhookEvent::BudgetExceeded),
+        );
+        events
+    }
+}
+
+fn is_do_nothing(action: &serde_json::Value) -> bool {
+    action.get("type").and_then(|t| t.as_str()) == Some("doNothing")
+}
+
+/// Compare a check with the previous one, if any
+pub fn compare(previous: Option<&CheckRecord>, current: &CheckRecord) -> CheckOutcome {
+    let previous_action = previous.and_then(|p| p.action.clone());
+    let action_changed = match &current.action {
+        Some(action) if !is_do_nothing(action) && previous_action.as_ref() != Some(action) => {
+            Some((previous_action.unwrap_or_default(), action.clone()))
+        }
+        _ => None,
+    };
+
+    let new_deadlines = current
+        .deadlines
+        .iter()
+        .filter(|d| {
+            !previous.is_some_and(|p| {
+                p.deadlines.iter().any(|old| {
+                    (old.school_id, old.kind, old.deadline) == (d.school_id, d.kind, d.deadline)
+                })
+            })
+        })
+        .cloned()
+        .collect();
+
+    CheckOutcome {
+        action_changed,
+        new_deadlines,
+        budget_alerts: Vec::new(),
+    }
+}
+
+/// The previous check, if one was recorded
+fn last_record(state: &AppState) -> Option<CheckRecord> {
+    let value = state.storage.as_ref()?.load(DAILY_CHECK_FILE).ok()??;
+    serde_json::from_value(value).ok()
+}
+
+/// Run the check for `today`, record it, and return what changed
+pub async fn check(state: Arc<AppState>, today: u32) -> Result<CheckOutcome, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let data = match state.load_dataset() {
+        Ok(data) => data,
+        Err(HandlerError::NoData) => return Ok(CheckOutcome::default()),
+        Err(e) => return Err(e),
+    };
+
+    let mut params = handlers::advisor_params(&data);
+    params["today"] = today.into();
+    let request = JsonRpcRequest::new(
+        "getRecommendation",
+        &params,
+        serde_json::json!("daily-check"),
+    );
+    let response = handlers::send_rpc(state.clone(), request).await?;
+    if let Some(error) = response.error {
+        return Err(HandlerError::Advisor(error.message));
+    }
+
+    let lead_days = i64::from(state.settings.lock().await.reminder_lead_days);
+    let current = CheckRecord {
+        day: today,
+        action: response
+            .result_value()
+            .and_then(|r| r.get("action").cloned()),
+        deadlines: reminders::upcoming_deadlines(&data, today, lead_days),
+    };
+    let mut outcome = compare(last_record(&state).as_ref(), &current);
+    if let Some((_, action)) = &outcome.action_changed {
+        outcome.budget_alerts = handlers::budget_alerts(&state, action);
+    }
+    storage.save(
+        DAILY_CHECK_FILE,
+        &serde_json::to_value(&current).unwrap_or_default(),
+    )?;
+    Ok(outcome)
+}
+
+/// Run [`check`] once per day forever, calling `notify` for each event.
+///
+/// Wakes every [`CHECK_POLL_INTERVAL`] and runs the check if none has been
+/// recorded for today, so a day missed while the computer slept is caught
+/// up on waking.
+pub async fn run_daily_check<F>(state: Arc<AppState>, notify: F)
+where
+    F: Fn(&WebhookEvent) + Send + 'static,
+{
+    loop {
+        let today = date::today();
+        if last_record(&state).is_none_or(|record| record.day != today) {
+            match check(state.clone(), today).await {
+                Ok(outcome) => {
+                    tracing::info!(
+                        "Daily check: action changed {}, {} new deadlines, {} budget alerts",
+                        outcome.action_changed.is_some(),
+                        outcome.new_deadlines.len(),
+                        outcome.budget_alerts.len()
+                    );
+                    for event in outcome.events() {
+                        notify(&event);
+                    }
+                }
+                Err(e) => tracing::warn!("Daily recommendation check failed: {}", e),
+            }
+        }
+        tokio::time::sleep(CHECK_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reminders::DeadlineKind;
+
+    fn deadline(school_id: u64, deadline: u32) -> UpcomingDeadline {
+        UpcomingDeadline {
+            school_id,
+            school_name: format!("学校{}", school_id),
+            kind: DeadlineKind::EnrollmentFee,
+            deadline,
+            amount: 200000,
+            days_left: 2,
+            pay_by: deadline,
+            label: None,
+        }
+    }
+
+    fn record(
+        day: u32,
+        action: serde_json::Value,
+        deadlines: Vec<UpcomingDeadline>,
+    ) -> CheckRecord {
+        CheckRecord {
+            day,
+            action: Some(action),
+            deadlines,
+        }
+    }
+
+    #[test]
+    fn test_first_check_reports_everything() {
+        let pay = serde_json::json!({"type": "payEnrollmentFee", "schoolId": 1});
+        let current = record(20260301, pay.clone(), vec![deadline(1, 20260303)]);
+
+        let outcome = compare(None, &current);
+        assert_eq!(outcome.action_changed, Some((serde_json::Value::Null, pay)));
+        assert_eq!(outcome.new_deadlines.len(), 1);
+        assert_eq!(outcome.events().len(), 2);
+    }
+
+    #[test]
+    fn test_unchanged_check_is_empty() {
+        let pay = serde_json::json!({"type": "payEnrollmentFee", "schoolId": 1});
+        let previous = record(20260301, pay.clone(), vec![deadline(1, 20260303)]);
+        let mut current = previous.clone();
+        current.day = 20260302;
+
+        assert!(compare(Some(&previous), &current).is_empty());
+    }
+
+    #[test]
+    fn test_new_deadline_and_do_nothing() {
+        let pay = serde_json::json!({"type": "payEnrollmentFee", "schoolId": 1});
+        let nothing = serde_json::json!({"type": "doNothing"});
+        let previous = record(20260301, pay, vec![deadline(1, 20260303)]);
+        let current = record(
+            20260302,
+            nothing,
+            vec![deadline(1, 20260303), deadline(2, 20260305)],
+        );
+
+        let outcome = compare(Some(&previous), &current);
+        assert_eq!(outcome.action_changed, None);
+        assert_eq!(outcome.new_deadlines, vec![deadline(2, 20260305)]);
+    }
+}
@@ -0,0 +1,325 @@
+//! Keeping the data somewhere other than the default data directory.
+//!
+//! Users who want their data in a Dropbox or NAS folder can move the data
+//! directory there. The settings file lives in the data directory itself,
+//! so the chosen location is kept in [`DATA_LOCATION_FILE`] in the default
+//! directory instead, and [`resolve`] follows it at startup.
+//!
+//! [`move_data`] never leaves the data half-moved: every file is copied to
+//! a staging directory beside the new location and checked against its
+//! original by SHA-256 before anything is swapped in. Only once the new
+//! location is complete and recorded are the old files removed.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use ts_rs::TS;
+
+/// File in the default data directory naming the directory in use
+pub const DATA_LOCATION_FILE: &str = "data_location.json";
+
+/// Errors moving the data directory
+#[derive(Debug, Error)]
+pub enum DataDirError {
+    #[error("Not an absolute path: {0}")]
+    NotAbsolute(PathBuf),
+
+    #[error("The folder is not empty: {0}")]
+    NotEmpty(PathBuf),
+
+    #[error("The new folder cannot be inside the current one or contain it: {0}")]
+    Nested(PathBuf),
+
+    #[error("The data is already in {0}")]
+    SameDir(PathBuf),
+
+    #[error("Copy of {0} does not match the original")]
+    ChecksumMismatch(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocationFile {
+    data_dir: PathBuf,
+}
+
+/// Where the data is kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DataLocation {
+    /// Directory in use
+    #[ts(type = "string")]
+    pub data_dir: PathBuf,
+    /// Directory used unless another was chosen
+    #[ts(type = "string")]
+    pub default_dir: PathBuf,
+    /// Whether another directory was chosen
+    pub custom: bool,
+}
+
+/// What moving the data directory did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MovedData {
+    #[ts(type = "string")]
+    pub data_dir: PathBuf,
+    pub files: u32,
+    #[ts(type = "number")]
+    pub bytes: u64,
+    /// Old files that could not be removed, such as open log files
+    pub left_behind: Vec<String>,
+}
+
+/// The data directory chosen for `default_dir`, or `default_dir` itself.
+///
+/// A chosen directory that has gone missing, such as an unmounted NAS
+/// folder, is still returned, so the app does not quietly start over with
+/// empty data in the default directory.
+pub fn resolve(default_dir: &Path) -> PathBuf {
+    let chosen = fs::read_to_string(default_dir.join(DATA_LOCATION_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str::<LocationFile>(&text).ok());
+    match chosen {
+        Some(location) => {
+            if !location.data_dir.is_dir() {
+                tracing::warn!("Data directory {:?} is missing", location.data_dir);
+            }
+            location.data_dir
+        }
+        None => default_dir.to_path_buf(),
+    }
+}
+
+/// Where the data of `default_dir` is kept
+pub fn location(default_dir: &Path) -> DataLocation {
+    let data_dir = resolve(default_dir);
+    DataLocation {
+        custom: data_dir != default_dir,
+        data_dir,
+        default_dir: default_dir.to_path_buf(),
+    }
+}
+
+/// Files under `dir`, relative to it, except the location file of `skip`
+fn files_under(dir: &Path, skip: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        for entry in fs::read_dir(dir.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else if dir.join(&path) != skip {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `dir` is missing, empty, or holds only the location file
+fn is_empty(dir: &Path) -> io::Result<bool> {
+    if !dir.exists() {
+        return Ok(true);
+    }
+    Ok(fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .all(|entry| entry.file_name() == DATA_LOCATION_FILE))
+}
+
+/// `path` made absolute through its nearest existing ancestor, so paths
+/// to folders not created yet can be compared
+fn canonical(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    while let Some(parent) = existing.parent().filter(|_| !existing.exists()) {
+        missing.push(existing.file_name().unwrap_or_default().to_owned());
+        existing = parent;
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(missing.into_iter().rev());
+    resolved
+}
+
+/// Move the data from `from` to `to` and record `to` as the data
+/// directory of `default_dir`.
+///
+/// `to` must be empty or not exist yet. Moving back to `default_dir`
+/// forgets the chosen directory.
+pub fn move_data(from: &Path, to: &Path, default_dir: &Path) -> Result<MovedData, DataDirError> {
+    if !to.is_absolute() {
+        return Err(DataDirError::NotAbsolute(to.to_path_buf()));
+    }
+    let (source, target) = (canonical(from), canonical(to));
+    if source == target {
+        return Err(DataDirError::SameDir(to.to_path_buf()));
+    }
+    if target.starts_with(&source) || source.starts_with(&target) {
+        return Err(DataDirError::Nested(to.to_path_buf()));
+    }
+    if !is_empty(to)? {
+        return Err(DataDirError::NotEmpty(to.to_path_buf()));
+    }
+
+    // Copy into a staging directory beside the target and verify
+    let files = if from.exists() {
+        files_under(from, &default_dir.join(DATA_LOCATION_FILE))?
+    } else {
+        Vec::new()
+    };
+    let name = to.file_name().unwrap_or_default().to_string_lossy();
+    let staging = to.with_file_name(format!(".{}.moving", name));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let mut bytes = 0;
+    let copied = files.iter().try_for_each(|file| {
+        let copy = staging.join(file);
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Checked against the bytes read, as files like the log may grow
+        let original = fs::read(from.join(file))?;
+        fs::write(&copy, &original)?;
+        bytes += original.len() as u64;
+        if Sha256::digest(&original) != Sha256::digest(fs::read(&copy)?) {
+            return Err(DataDirError::ChecksumMismatch(file.display().to_string()));
+        }
+        Ok(())
+    });
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    // Swap the verified copy in and record where it is
+    fs::create_dir_all(to)?;
+    for file in &files {
+        let moved = to.join(file);
+        if let Some(parent) = moved.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(staging.join(file), moved)?;
+    }
+    let _ = fs::remove_dir_all(&staging);
+    let location = default_dir.join(DATA_LOCATION_FILE);
+    if target == canonical(default_dir) {
+        if location.exists() {
+            fs::remove_file(location)?;
+        }
+    } else {
+        fs::create_dir_all(default_dir)?;
+        let chosen = LocationFile {
+            data_dir: to.to_path_buf(),
+        };
+        fs::write(location, serde_json::to_string_pretty(&chosen)?)?;
+    }
+
+    // Clean up the old location
+    let left_behind = files
+        .iter()
+        .filter(|file| fs::remove_file(from.join(file)).is_err())
+        .map(|file| file.display().to_string())
+        .collect();
+    remove_empty_dirs(from);
+
+    Ok(MovedData {
+        data_dir: to.to_path_buf(),
+        files: files.len() as u32,
+        bytes,
+        left_behind,
+    })
+}
+
+/// Remove the directories under `dir` left empty, and `dir` if it is too
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(Result::ok) {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+    // Fails unless empty
+    let _ = fs::remove_dir(dir);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Storage, SCHOOLS_DATA_FILE};
+
+    #[test]
+    fn test_move_and_move_back() {
+        let root = tempfile::tempdir().unwrap();
+        let default_dir = root.path().join("app");
+        let storage = Storage::new(default_dir.clone());
+        let data = serde_json::json!({ "schools": [{ "id": 1, "name": "A" }] });
+        storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+        storage
+            .save("backups/manual-20260301-090000.json", &data)
+            .unwrap();
+        assert!(!location(&default_dir).custom);
+
+        let dropbox = root.path().join("Dropbox").join("school-payment");
+        let moved = move_data(&default_dir, &dropbox, &default_dir).unwrap();
+        assert_eq!(moved.files, 2);
+        assert!(moved.left_behind.is_empty());
+        assert_eq!(resolve(&default_dir), dropbox);
+        assert!(location(&default_dir).custom);
+        assert!(!default_dir.join(SCHOOLS_DATA_FILE).exists());
+        assert!(!dropbox.with_file_name(".school-payment.moving").exists());
+        let moved_storage = Storage::new(dropbox.clone());
+        assert_eq!(
+            moved_storage.load(SCHOOLS_DATA_FILE).unwrap(),
+            Some(data.clone())
+        );
+        assert!(moved_storage.exists("backups/manual-20260301-090000.json"));
+
+        // Back to the default directory, which holds only the location file
+        move_data(&dropbox, &default_dir, &default_dir).unwrap();
+        assert_eq!(resolve(&default_dir), default_dir);
+        assert_eq!(storage.load(SCHOOLS_DATA_FILE).unwrap(), Some(data));
+        assert!(!dropbox.exists());
+    }
+
+    #[test]
+    fn test_unsafe_targets_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let default_dir = root.path().join("app");
+        Storage::new(default_dir.clone())
+            .save(SCHOOLS_DATA_FILE, &serde_json::json!({}))
+            .unwrap();
+
+        let busy = root.path().join("busy");
+        fs::create_dir_all(&busy).unwrap();
+        fs::write(busy.join("other.txt"), "x").unwrap();
+        let cases = [
+            (PathBuf::from("relative"), "NotAbsolute"),
+            (busy, "NotEmpty"),
+            (default_dir.join("inside"), "Nested"),
+            (default_dir.clone(), "SameDir"),
+        ];
+        for (to, expected) in cases {
+            let e = move_data(&default_dir, &to, &default_dir).unwrap_err();
+            assert!(format!("{:?}", e).starts_with(expected), "{:?}", e);
+        }
+        assert!(default_dir.join(SCHOOLS_DATA_FILE).exists());
+    }
+}
@@ -0,0 +1,209 @@
+//! Calendar days as used by Japanese schools.
+//!
+//! Days are YYYYMMDD integers, matching the Lean advisor. Deadlines are
+//! Japanese calendar days, so "today" and the day of a timestamp are taken
+//! in Japan Standard Time whatever the machine's timezone is set to.
+//! Otherwise, on a laptop set to UTC, today would only change at 9:00 in
+//! the morning, and a deadline sent as midnight JST
+//! (`2026-03-05T15:00:00Z`) would land a day early.
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Utc};
+
+/// Japan Standard Time, which has no daylight saving
+pub fn jst() -> FixedOffset {
+    FixedOffset::east_opt(9 * 3600).expect("valid offset")
+}
+
+/// Current time in Japan
+pub fn now() -> DateTime<FixedOffset> {
+    Utc::now().with_timezone(&jst())
+}
+
+/// Today's date in Japan as a YYYYMMDD integer
+pub fn today() -> u32 {
+    day_of(&Utc::now())
+}
+
+/// Day in Japan at `time`
+pub fn day_of<Tz: TimeZone>(time: &DateTime<Tz>) -> u32 {
+    to_day(time.with_timezone(&jst()).date_naive())
+}
+
+/// Convert a YYYYMMDD integer to a date
+pub fn parse_day(day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt((day / 10000) as i32, (day / 100) % 100, day % 100)
+}
+
+/// Convert a date to a YYYYMMDD integer
+pub fn to_day(date: NaiveDate) -> u32 {
+    date.year() as u32 * 10000 + date.month() * 100 + date.day()
+}
+
+/// Moment a deadline ends: midnight in Japan at the end of `day`
+pub fn deadline_end(day: u32) -> Option<DateTime<FixedOffset>> {
+    let next = parse_day(day)?.succ_opt()?;
+    next.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(jst())
+        .single()
+}
+
+/// Whether the deadline on `day` has passed at `time`
+pub fn is_past_deadline<Tz: TimeZone>(day: u32, time: &DateTime<Tz>) -> bool {
+    deadline_end(day).is_some_and(|end| *time >= end)
+}
+
+/// Japanese era (元号)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Era {
+    Showa,
+    Heisei,
+    Reiwa,
+}
+
+impl Era {
+    /// Newest first
+    const ALL: [Era; 3] = [Era::Reiwa, Era::Heisei, Era::Showa];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Showa => "昭和",
+            Self::Heisei => "平成",
+            Self::Reiwa => "令和",
+        }
+    }
+
+    /// First day of the era
+    pub fn start(self) -> u32 {
+        match self {
+            Self::Showa => 19261225,
+            Self::Heisei => 19890108,
+            Self::Reiwa => 20190501,
+        }
+    }
+}
+
+/// Era and year within it for `day`, or `None` before Showa
+pub fn era_year(day: u32) -> Option<(Era, u32)> {
+    let era = Era::ALL.into_iter().find(|era| day >= era.start())?;
+    Some((era, day / 10000 - era.start() / 10000 + 1))
+}
+
+/// Format a day in the Japanese calendar, as in `令和8年3月6日`.
+///
+/// The first year of an era is written 元年. Days before Showa fall back
+/// to [`format_day`](crate::format::format_day).
+pub fn format_wareki(day: u32) -> String {
+    match era_year(day).filter(|_| parse_day(day).is_some()) {
+        Some((era, year)) => {
+            let year = match year {
+                1 => "元".to_string(),
+                n => n.to_string(),
+            };
+            format!(
+                "{}{}年{}月{}日",
+                era.name(),
+                year,
+                (day / 100) % 100,
+                day % 100
+            )
+        }
+        None => crate::format::format_day(day),
+    }
+}
+
+/// Japanese weekday of `day`: `月` to `日`
+pub fn weekday_label(day: u32) -> Option<&'static str> {
+    const LABELS: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+    parse_day(day).map(|date| LABELS[date.weekday().num_days_from_monday() as usize])
+}
+
+/// Format a day with its weekday, as in `2026/03/06(金)`
+pub fn format_day_with_weekday(day: u32) -> String {
+    let date = crate::format::format_day(day);
+    match weekday_label(day) {
+        Some(weekday) => format!("{}({})", date, weekday),
+        None => date,
+    }
+}
+
+/// Parse a date written as text into a day.
+///
+/// Accepts `20260306`, `2026-03-06`, `2026/03/06`, the Japanese calendar
+/// (`令和8年3月6日`) and RFC 3339 timestamps, which are taken as the day
+/// in Japan.
+pub fn parse_date_text(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(day_of(&time));
+    }
+    if let Some(day) = parse_wareki(text) {
+        return Some(day);
+    }
+    ["%Y%m%d", "%Y-%m-%d", "%Y/%m/%d"]
+        .into_iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+        .map(to_day)
+}
+
+fn parse_wareki(text: &str) -> Option<u32> {
+    let (era, rest) = Era::ALL
+        .into_iter()
+        .find_map(|era| Some((era, text.strip_prefix(era.name())?)))?;
+    let (year, rest) = rest.split_once('年')?;
+    let (month, rest) = rest.split_once('月')?;
+    let day = rest.strip_suffix('日')?;
+    let year: u32 = match year {
+        "元" => 1,
+        year => year.parse().ok()?,
+    };
+    let date = NaiveDate::from_ymd_opt(
+        (era.start() / 10000 + year - 1) as i32,
+        month.parse().ok()?,
+        day.parse().ok()?,
+    )?;
+    // Reject days the era does not cover, such as 令和元年4月1日
+    let day = to_day(date);
+    (era_year(day)? == (era, year)).then_some(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_in_japan() {
+        // 23:30 UTC on the 5th is already the 6th in Japan
+        let time = Utc.with_ymd_and_hms(2026, 3, 5, 23, 30, 0).unwrap();
+        assert_eq!(day_of(&time), 20260306);
+
+        assert!(!is_past_deadline(20260306, &time));
+        let late = Utc.with_ymd_and_hms(2026, 3, 6, 15, 0, 0).unwrap();
+        assert!(is_past_deadline(20260306, &late));
+    }
+
+    #[test]
+    fn test_wareki() {
+        assert_eq!(format_wareki(20260306), "令和8年3月6日");
+        assert_eq!(format_wareki(20190501), "令和元年5月1日");
+        assert_eq!(format_wareki(20190430), "平成31年4月30日");
+        assert_eq!(format_wareki(19000101), "1900/01/01");
+        assert_eq!(weekday_label(20260306), Some("金"));
+        assert_eq!(format_day_with_weekday(20260301), "2026/03/01(日)");
+    }
+
+    #[test]
+    fn test_parse_date_text() {
+        for text in [
+            "20260306",
+            "2026-03-06",
+            "2026/3/6",
+            "令和8年3月6日",
+            "2026-03-05T15:00:00.000Z",
+        ] {
+            assert_eq!(parse_date_text(text), Some(20260306), "{}", text);
+        }
+        assert_eq!(parse_date_text("令和元年5月1日"), Some(20190501));
+        assert_eq!(parse_date_text("令和元年4月1日"), None);
+        assert_eq!(parse_date_text("2026-02-30"), None);
+    }
+}
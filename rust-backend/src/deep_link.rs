@@ -0,0 +1,117 @@
+//! Parsing of `school-payment://` deep links.
+//!
+//! Reminder emails and notifications link into the desktop app with URLs
+//! such as `school-payment://school/12` or
+//! `school-payment://import?src=https://example.com/data.json`. Parsing
+//! turns them into a [`DeepLink`] route the frontend can navigate to.
+
+use serde::Serialize;
+use thiserror::Error;
+use url::Url;
+
+/// URL scheme registered by the desktop app
+pub const SCHEME: &str = "school-payment";
+
+/// Errors that can occur when parsing a deep link
+#[derive(Debug, Error)]
+pub enum DeepLinkError {
+    #[error("Invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+
+    #[error("Not a {SCHEME}:// link: {0}")]
+    WrongScheme(String),
+
+    #[error("Unknown deep link: {0}")]
+    Unknown(String),
+}
+
+/// Screen a deep link points at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "route", rename_all = "camelCase")]
+pub enum DeepLink {
+    /// `school-payment://` or `school-payment://home`
+    Home,
+    /// `school-payment://school/<id>`
+    #[serde(rename_all = "camelCase")]
+    School { school_id: u64 },
+    /// `school-payment://calendar`
+    Calendar,
+    /// `school-payment://import?src=<url or path>`
+    Import { src: String },
+}
+
+/// Parse a deep link URL
+pub fn parse(link: &str) -> Result<DeepLink, DeepLinkError> {
+    let url = Url::parse(link)?;
+    if url.scheme() != SCHEME {
+        return Err(DeepLinkError::WrongScheme(link.to_string()));
+    }
+
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let unknown = || DeepLinkError::Unknown(link.to_string());
+
+    match (url.host_str().unwrap_or_default(), segments.as_slice()) {
+        ("" | "home", []) => Ok(DeepLink::Home),
+        ("calendar", []) => Ok(DeepLink::Calendar),
+        ("school", [id]) => Ok(DeepLink::School {
+            school_id: id.parse().map_err(|_| unknown())?,
+        }),
+        ("import", []) => url
+            .query_pairs()
+            .find(|(key, _)| key == "src")
+            .map(|(_, src)| DeepLink::Import {
+                src: src.into_owned(),
+            })
+            .ok_or_else(unknown),
+        _ => Err(unknown()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_routes() {
+        assert_eq!(parse("school-payment://").unwrap(), DeepLink::Home);
+        assert_eq!(
+            parse("school-payment://school/12").unwrap(),
+            DeepLink::School { school_id: 12 }
+        );
+        assert_eq!(
+            parse("school-payment://calendar/").unwrap(),
+            DeepLink::Calendar
+        );
+        assert_eq!(
+            parse("school-payment://import?src=https%3A%2F%2Fexample.com%2Fdata.json").unwrap(),
+            DeepLink::Import {
+                src: "https://example.com/data.json".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(matches!(
+            parse("https://school/12"),
+            Err(DeepLinkError::WrongScheme(_))
+        ));
+        assert!(matches!(
+            parse("school-payment://school/abc"),
+            Err(DeepLinkError::Unknown(_))
+        ));
+        assert!(matches!(
+            parse("school-payment://import"),
+            Err(DeepLinkError::Unknown(_))
+        ));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let json = serde_json::to_value(DeepLink::School { school_id: 3 }).unwrap();
+        assert_eq!(json, serde_json::json!({"route": "school", "schoolId": 3}));
+    }
+}
@@ -0,0 +1,71 @@
+//! Demo mode: sample data answered by the built-in mock advisor.
+//!
+//! Turned on with [`DEMO_ARG`] or `demo = true` in `config.toml`. The app
+//! then runs on a separate [`DEMO_DATA_DIR`] inside the data directory,
+//! seeded with generated sample schools, and asks a
+//! [`MockAdvisor`](crate::mock_advisor::MockAdvisor) instead of the Lean
+//! advisor. Everything works without the advisor binary, so the app can be
+//! explored and screenshotted anywhere and frontend CI can drive the real
+//! commands, while the user's own data is never touched.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+use crate::sample::{self, SampleProfile};
+use crate::storage::{Storage, StorageError, SCHOOLS_DATA_FILE};
+
+/// Command-line flag that turns on demo mode
+pub const DEMO_ARG: &str = "--demo";
+
+/// Directory inside the data directory used in demo mode
+pub const DEMO_DATA_DIR: &str = "demo";
+
+/// Whether demo mode is on, by `demo_arg` or the config
+pub fn is_enabled(config: &AppConfig, demo_arg: bool) -> bool {
+    demo_arg || config.demo
+}
+
+/// Data directory to use in demo mode
+pub fn data_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(DEMO_DATA_DIR)
+}
+
+/// Save sample schools dated around `today` unless demo data already
+/// exists, so changes made while exploring survive a restart. Returns
+/// whether data was written.
+pub fn seed(storage: &Storage, today: u32) -> Result<bool, StorageError> {
+    if storage.exists(SCHOOLS_DATA_FILE) {
+        return Ok(false);
+    }
+    let dataset = sample::sample_dataset(SampleProfile::University, today);
+    storage.save(SCHOOLS_DATA_FILE, &dataset.to_value())?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::handlers::{self, AppState};
+    use crate::json_rpc::JsonRpcRequest;
+    use crate::lean_repl::LeanRepl;
+
+    #[tokio::test]
+    async fn test_seeded_data_gets_recommendations() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(data_dir(dir.path()));
+        assert!(seed(&storage, 20260201).unwrap());
+        assert!(!seed(&storage, 20260201).unwrap());
+
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        let data = state.load_dataset().unwrap();
+        let mut params = handlers::advisor_params(&data);
+        params["today"] = 20260201.into();
+        let request = JsonRpcRequest::new("getRecommendation", &params, serde_json::json!(1));
+        let response = handlers::send_rpc(state.clone(), request).await.unwrap();
+        assert!(response.result_value().unwrap()["action"]["type"].is_string());
+        assert_eq!(handlers::health_check(state).await.lean_repl, "demo");
+        assert!(!dir.path().join(SCHOOLS_DATA_FILE).exists());
+    }
+}
@@ -0,0 +1,236 @@
+//! What a change to the saved data does to it.
+//!
+//! Importing a file, recording a payment, adding a school from a template
+//! and restoring a backup all rewrite the saved dataset. Each can be run
+//! as a dry run, which validates everything the real run does but saves
+//! nothing, so the frontend can show exactly which schools and fields
+//! would change before the user commits. Both runs report the change as a
+//! [`DatasetDiff`] between the data before and after.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use ts_rs::TS;
+
+use crate::revision::REVISION_KEY;
+
+/// A school added or removed, by id and name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SchoolRef {
+    /// `None` for schools without an id
+    #[ts(type = "number | null")]
+    pub id: Option<u64>,
+    pub name: String,
+}
+
+/// One field of a school before and after a change
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct FieldChange {
+    pub field: String,
+    /// `null` when the field was added
+    #[ts(type = "unknown")]
+    pub before: Value,
+    /// `null` when the field was removed
+    #[ts(type = "unknown")]
+    pub after: Value,
+}
+
+/// A school kept by a change with some of its fields changed
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SchoolChange {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    /// Name after the change
+    pub name: String,
+    /// Changed fields, by name
+    pub fields: Vec<FieldChange>,
+}
+
+/// Differences between two versions of the saved dataset
+#[derive(Debug, Clone, Default, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DatasetDiff {
+    /// In the order of the new data
+    pub added: Vec<SchoolRef>,
+    /// In the order of the old data
+    pub removed: Vec<SchoolRef>,
+    /// In the order of the new data
+    pub changed: Vec<SchoolChange>,
+    /// Changed top-level fields other than the schools, such as the budget
+    pub other_fields: Vec<String>,
+}
+
+impl DatasetDiff {
+    /// Whether the change leaves the data as it was
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.other_fields.is_empty()
+    }
+}
+
+/// What a change to the saved data did or, in a dry run, would do
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ChangeReport<T> {
+    /// The operation's result, such as the new data
+    pub result: T,
+    pub diff: DatasetDiff,
+    /// Whether nothing was saved
+    pub dry_run: bool,
+}
+
+fn id_of(school: &Value) -> Option<u64> {
+    school.get("id")?.as_u64()
+}
+
+fn school_ref(school: &Value) -> SchoolRef {
+    SchoolRef {
+        id: id_of(school),
+        name: school
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+fn find(schools: &[Value], id: Option<u64>) -> Option<&Value> {
+    schools.iter().find(|s| id.is_some() && id_of(s) == id)
+}
+
+fn schools_of(data: Option<&Value>) -> &[Value] {
+    match data.and_then(|d| d.get("schools")) {
+        Some(Value::Array(schools)) => schools,
+        _ => &[],
+    }
+}
+
+/// Fields present in either object whose values differ, by name
+pub fn field_changes(before: &Map<String, Value>, after: &Map<String, Value>) -> Vec<FieldChange> {
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let (old, new) = (before.get(field), after.get(field));
+            (old != new).then(|| FieldChange {
+                field: field.clone(),
+                before: old.cloned().unwrap_or(Value::Null),
+                after: new.cloned().unwrap_or(Value::Null),
+            })
+        })
+        .collect()
+}
+
+/// Differences from `before`, or no data at all, to `after`.
+///
+/// Schools are matched by id; those without one count as added or removed.
+pub fn diff(before: Option<&Value>, after: &Value) -> DatasetDiff {
+    let (old, new) = (schools_of(before), schools_of(Some(after)));
+    let mut diff = DatasetDiff::default();
+    for school in new {
+        let id = id_of(school);
+        match (find(old, id), id) {
+            (Some(previous), Some(school_id)) => {
+                let fields = match (previous.as_object(), school.as_object()) {
+                    (Some(previous), Some(current)) => field_changes(previous, current),
+                    _ => Vec::new(),
+                };
+                if !fields.is_empty() {
+                    diff.changed.push(SchoolChange {
+                        school_id,
+                        name: school_ref(school).name,
+                        fields,
+                    });
+                }
+            }
+            _ => diff.added.push(school_ref(school)),
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|s| find(new, id_of(s)).is_none())
+        .map(school_ref)
+        .collect();
+
+    let top = |data: Option<&Value>| data.and_then(Value::as_object).cloned().unwrap_or_default();
+    diff.other_fields = field_changes(&top(before), &top(Some(after)))
+        .into_iter()
+        .map(|change| change.field)
+        .filter(|field| field != "schools" && field != REVISION_KEY)
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_schools_are_matched_by_id() {
+        let before = json!({
+            "schools": [
+                { "id": 1, "name": "A", "tuitionPaid": false },
+                { "id": 2, "name": "B" },
+            ],
+        });
+        let after = json!({
+            "schools": [
+                { "id": 1, "name": "A", "tuitionPaid": true, "note": "paid" },
+                { "id": 3, "name": "C" },
+            ],
+            "budget": { "limit": 1000000 },
+        });
+        let diff = diff(Some(&before), &after);
+
+        assert_eq!(
+            diff.added,
+            vec![SchoolRef {
+                id: Some(3),
+                name: "C".to_string()
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![SchoolRef {
+                id: Some(2),
+                name: "B".to_string()
+            }]
+        );
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].school_id, 1);
+        let fields: Vec<&str> = diff.changed[0]
+            .fields
+            .iter()
+            .map(|f| f.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["note", "tuitionPaid"]);
+        assert_eq!(diff.changed[0].fields[0].before, Value::Null);
+        assert_eq!(diff.other_fields, vec!["budget".to_string()]);
+    }
+
+    #[test]
+    fn test_no_data_before_adds_everything() {
+        let after = json!({ "schools": [{ "id": 1, "name": "A" }, { "name": "no id" }] });
+        let diff = diff(None, &after);
+        assert_eq!(diff.added.len(), 2);
+        assert_eq!(diff.added[1].id, None);
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+
+        let same = json!({ "schools": [{ "id": 1, "name": "A" }] });
+        assert!(super::diff(Some(&same), &same).is_empty());
+        // Without an id a school cannot be matched
+        assert_eq!(super::diff(Some(&after), &after).removed.len(), 1);
+    }
+}
@@ -0,0 +1,201 @@
+//! Which app instance may change the saved data.
+//!
+//! The desktop app and the web server serving the PWA can share a data
+//! directory, and when both wrote the dataset the last save silently won.
+//! An instance that takes the edit lock writes [`EDIT_LOCK_FILE`] naming
+//! itself; every other instance sees the file, reports itself read-only to
+//! its frontend and refuses to save until the lock is released. A holder
+//! that exits without releasing leaves a lock that nobody renews, which
+//! other instances may take over after [`STALE_AFTER_SECS`].
+//!
+//! While no instance holds the lock, every instance may write, as before.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::storage::{Storage, StorageError};
+use crate::token::random_token;
+
+/// File in the data directory naming the holder of the edit lock
+pub const EDIT_LOCK_FILE: &str = "edit.lock";
+
+/// Seconds after its last renewal that a lock may be taken over
+pub const STALE_AFTER_SECS: i64 = 90;
+
+/// The instance holding the edit lock
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct EditLockHolder {
+    /// Random id of the holding instance
+    pub instance: String,
+    /// Kind of instance, such as `desktop` or `web`
+    pub label: String,
+    pub pid: u32,
+    /// When the lock was taken (RFC 3339)
+    pub acquired_at: String,
+    /// When the holder last took it again (RFC 3339)
+    pub renewed_at: String,
+}
+
+impl EditLockHolder {
+    /// Whether the holder stopped renewing the lock before `now` (RFC 3339)
+    fn is_stale(&self, now: &str) -> bool {
+        match (
+            DateTime::parse_from_rfc3339(&self.renewed_at),
+            DateTime::parse_from_rfc3339(now),
+        ) {
+            (Ok(renewed), Ok(now)) => (now - renewed).num_seconds() >= STALE_AFTER_SECS,
+            _ => true,
+        }
+    }
+}
+
+/// The edit lock as seen by one instance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct EditLockStatus {
+    /// Whether this instance holds the lock
+    pub held: bool,
+    /// Whether another instance holds it, so this one must not save
+    pub read_only: bool,
+    /// The holder, if any instance holds the lock
+    pub holder: Option<EditLockHolder>,
+}
+
+/// This instance's side of the edit lock
+#[derive(Debug, Clone)]
+pub struct EditLock {
+    instance: String,
+    label: String,
+}
+
+impl Default for EditLock {
+    fn default() -> Self {
+        Self::new("app")
+    }
+}
+
+impl EditLock {
+    /// A new instance of kind `label`, not holding the lock
+    pub fn new(label: &str) -> Self {
+        Self {
+            instance: random_token(8),
+            label: label.to_string(),
+        }
+    }
+
+    /// The current holder, unless the lock is free or stale as of `now`
+    fn holder(&self, storage: &Storage, now: &str) -> Result<Option<EditLockHolder>, StorageError> {
+        let Some(value) = storage.load(EDIT_LOCK_FILE)? else {
+            return Ok(None);
+        };
+        let holder: EditLockHolder = match serde_json::from_value(value) {
+            Ok(holder) => holder,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable edit lock: {}", e);
+                return Ok(None);
+            }
+        };
+        Ok((!holder.is_stale(now)).then_some(holder))
+    }
+
+    fn status_of(&self, holder: Option<EditLockHolder>) -> EditLockStatus {
+        let held = holder.as_ref().is_some_and(|h| h.instance == self.instance);
+        EditLockStatus {
+            held,
+            read_only: holder.is_some() && !held,
+            holder,
+        }
+    }
+
+    /// The lock as of `now` (RFC 3339)
+    pub fn status(&self, storage: &Storage, now: &str) -> Result<EditLockStatus, StorageError> {
+        Ok(self.status_of(self.holder(storage, now)?))
+    }
+
+    /// Take the lock unless another instance holds it, or renew it if this
+    /// one does. Returns the lock as it is afterwards, read-only if it was
+    /// held elsewhere.
+    pub fn acquire(&self, storage: &Storage, now: &str) -> Result<EditLockStatus, StorageError> {
+        let holder = match self.holder(storage, now)? {
+            Some(holder) if holder.instance != self.instance => {
+                return Ok(self.status_of(Some(holder)));
+            }
+            Some(holder) => EditLockHolder {
+                renewed_at: now.to_string(),
+                ..holder
+            },
+            None => EditLockHolder {
+                instance: self.instance.clone(),
+                label: self.label.clone(),
+                pid: std::process::id(),
+                acquired_at: now.to_string(),
+                renewed_at: now.to_string(),
+            },
+        };
+        storage.save(EDIT_LOCK_FILE, &serde_json::to_value(&holder)?)?;
+        Ok(self.status_of(Some(holder)))
+    }
+
+    /// Give up the lock if this instance holds it
+    pub fn release(&self, storage: &Storage, now: &str) -> Result<EditLockStatus, StorageError> {
+        let holder = self.holder(storage, now)?;
+        if holder.as_ref().is_some_and(|h| h.instance == self.instance) {
+            storage.delete(EDIT_LOCK_FILE)?;
+            return Ok(self.status_of(None));
+        }
+        Ok(self.status_of(holder))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: &str = "2026-03-01T09:00:00+09:00";
+
+    #[test]
+    fn test_one_instance_edits_at_a_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let (desktop, web) = (EditLock::new("desktop"), EditLock::new("web"));
+        assert!(!web.status(&storage, NOW).unwrap().read_only);
+
+        assert!(desktop.acquire(&storage, NOW).unwrap().held);
+        let status = web.acquire(&storage, NOW).unwrap();
+        assert!(!status.held && status.read_only);
+        assert_eq!(status.holder.unwrap().label, "desktop");
+
+        // Only the holder can release it
+        assert!(web.release(&storage, NOW).unwrap().read_only);
+        assert!(!desktop.release(&storage, NOW).unwrap().read_only);
+        assert!(web.acquire(&storage, NOW).unwrap().held);
+        assert!(desktop.status(&storage, NOW).unwrap().read_only);
+    }
+
+    #[test]
+    fn test_unrenewed_lock_can_be_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let (desktop, web) = (EditLock::new("desktop"), EditLock::new("web"));
+        desktop.acquire(&storage, NOW).unwrap();
+
+        // Renewing keeps it
+        let renewed = desktop
+            .acquire(&storage, "2026-03-01T09:01:00+09:00")
+            .unwrap();
+        assert_eq!(renewed.holder.as_ref().unwrap().acquired_at, NOW);
+        assert!(
+            web.acquire(&storage, "2026-03-01T09:02:00+09:00")
+                .unwrap()
+                .read_only
+        );
+
+        let later = "2026-03-01T09:02:30+09:00";
+        assert!(web.acquire(&storage, later).unwrap().held);
+        assert!(desktop.status(&storage, later).unwrap().read_only);
+    }
+}
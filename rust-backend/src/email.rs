@@ -0,0 +1,286 @@
+//! SMTP email digest of upcoming payment deadlines.
+//!
+//! Configured under `[email]` in `config.toml`. Once a day at `send_hour`
+//! (local time) the reminder engine builds a digest, and if it contains any
+//! upcoming deadlines or a newly recommended action it is mailed to every
+//! address in `to`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::date;
+use crate::format::{format_day, format_yen};
+use crate::handlers::AppState;
+use crate::reminders::Digest;
+use crate::storage::{Storage, SCHOOLS_DATA_FILE};
+
+/// Errors that can occur when sending email
+#[derive(Debug, Error)]
+pub enum EmailError {
+    #[error("Invalid email address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+
+    #[error("Failed to build email: {0}")]
+    Build(#[from] lettre::error::Error),
+
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+}
+
+/// Connection security for the SMTP server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection with STARTTLS (port 587)
+    #[default]
+    Starttls,
+    /// Implicit TLS (port 465)
+    Tls,
+    /// Unencrypted, for local relays only
+    None,
+}
+
+fn default_send_hour() -> u32 {
+    7
+}
+
+/// Email settings from `config.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    #[serde(default)]
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Local hour of day (0-23) at which the digest is sent
+    #[serde(default = "default_send_hour")]
+    pub send_hour: u32,
+}
+
+/// Sends reminder digests over SMTP
+pub struct EmailNotifier {
+    config: EmailConfig,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl EmailNotifier {
+    /// Create a notifier from configuration
+    pub fn new(config: EmailConfig) -> Result<Self, EmailError> {
+        let mut builder = match config.security {
+            SmtpSecurity::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)?,
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+            }
+        };
+        if let Some(port) = config.smtp_port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            config,
+        })
+    }
+
+    /// Send a digest to all configured recipients
+    pub async fn send_digest(&self, digest: &Digest) -> Result<(), EmailError> {
+        let (subject, body) = render_digest(digest);
+        let from: Mailbox = self.config.from.parse()?;
+
+        for to in &self.config.to {
+            let message = Message::builder()
+                .from(from.clone())
+                .to(to.parse()?)
+                .subject(subject.clone())
+                .body(body.clone())?;
+            self.transport.send(message).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Render a digest as an email subject and plain-text body
+pub fn render_digest(digest: &Digest) -> (String, String) {
+    let subject = format!(
+        "【志望校支払いアドバイザー】{} の支払い予定（期限{}件）",
+        format_day(digest.today),
+        digest.deadlines.len()
+    );
+
+    let mut body = String::new();
+    if let Some(recommendation) = &digest.new_recommendation {
+        let reason = recommendation
+            .get("reason")
+            .and_then(|r| r.as_str())
+            .unwrap_or_default();
+        body.push_str("■ 新しい推奨アクション\n");
+        body.push_str(&format!("{}\n\n", reason));
+    }
+
+    if digest.deadlines.is_empty() {
+        body.push_str("■ 近日中の支払い期限はありません\n");
+    } else {
+        body.push_str("■ 近日中の支払い期限\n");
+        for deadline in &digest.deadlines {
+            let kind = deadline.fee_name();
+            let remaining = if deadline.days_left == 0 {
+                "本日期限".to_string()
+            } else {
+                format!("あと{}日", deadline.days_left)
+            };
+            body.push_str(&format!(
+                "- {} {} {} {}（{}）\n",
+                date::format_day_with_weekday(deadline.deadline),
+                deadline.school_name,
+                kind,
+                format_yen(deadline.amount),
+                remaining
+            ));
+        }
+    }
+
+    (subject, body)
+}
+
+/// Time to wait from `now` until the next occurrence of `hour:00`
+pub fn duration_until_hour(now: NaiveDateTime, hour: u32) -> Duration {
+    let today_at = now.date().and_hms_opt(hour.min(23), 0, 0).unwrap_or(now);
+    let next = if today_at > now {
+        today_at
+    } else {
+        today_at + chrono::Duration::days(1)
+    };
+    (next - now).to_std().unwrap_or_default()
+}
+
+/// Send the daily digest forever.
+///
+/// `fallback` supplies the stored dataset when no advisor request has been
+/// made since startup. Returns immediately if email is not configured.
+pub async fn run_daily_digest(state: Arc<AppState>, fallback: Option<Storage>) {
+    let Some(config) = state.config.email.clone() else {
+        return;
+    };
+    let send_hour = config.send_hour;
+    let notifier = match EmailNotifier::new(config) {
+        Ok(notifier) => notifier,
+        Err(e) => {
+            tracing::error!("Email digest disabled: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        let now = chrono::Local::now().naive_local();
+        tokio::time::sleep(duration_until_hour(now, send_hour)).await;
+
+        let stored = fallback
+            .as_ref()
+            .and_then(|storage| storage.load(SCHOOLS_DATA_FILE).ok().flatten());
+        let digest = state.reminders.lock().await.take_digest(
+            date::today(),
+            state.config.reminders.lead_days,
+            stored.as_ref(),
+        );
+        if digest.is_empty() {
+            tracing::debug!("Nothing to report in email digest");
+            continue;
+        }
+
+        match notifier.send_digest(&digest).await {
+            Ok(()) => tracing::info!(
+                "Sent email digest with {} deadlines",
+                digest.deadlines.len()
+            ),
+            Err(e) => tracing::error!("Failed to send email digest: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reminders::{DeadlineKind, UpcomingDeadline};
+
+    #[test]
+    fn test_render_digest() {
+        let digest = Digest {
+            today: 20260304,
+            deadlines: vec![UpcomingDeadline {
+                school_id: 2,
+                school_name: "早稲田大学".to_string(),
+                kind: DeadlineKind::EnrollmentFee,
+                deadline: 20260306,
+                amount: 200000,
+                days_left: 2,
+                pay_by: 20260306,
+                label: None,
+            }],
+            new_recommendation: Some(
+                serde_json::json!({"reason": "早稲田大学の入学金を支払ってください"}),
+            ),
+        };
+
+        let (subject, body) = render_digest(&digest);
+        assert!(subject.contains("2026/03/04"));
+        assert!(subject.contains("期限1件"));
+        assert!(body.contains("早稲田大学の入学金を支払ってください"));
+        assert!(body.contains("2026/03/06(金) 早稲田大学 入学金 ¥200,000（あと2日）"));
+    }
+
+    #[test]
+    fn test_duration_until_hour() {
+        let at = |h, m| {
+            chrono::NaiveDate::from_ymd_opt(2026, 3, 1)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        assert_eq!(
+            duration_until_hour(at(6, 30), 7),
+            Duration::from_secs(30 * 60)
+        );
+        assert_eq!(
+            duration_until_hour(at(7, 0), 7),
+            Duration::from_secs(24 * 3600)
+        );
+        assert_eq!(
+            duration_until_hour(at(8, 0), 7),
+            Duration::from_secs(23 * 3600)
+        );
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config: EmailConfig = toml::from_str(
+            r#"
+            smtp_host = "smtp.example.com"
+            from = "Advisor <advisor@example.com>"
+            to = ["parent@example.com"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.security, SmtpSecurity::Starttls);
+        assert_eq!(config.send_hour, 7);
+        assert!(EmailNotifier::new(config).is_ok());
+    }
+}
@@ -0,0 +1,233 @@
+//! Events the desktop app sends to its frontend.
+//!
+//! Every event the frontend can listen for is an [`Event`] here: its name,
+//! the window it goes to, and the payload type it carries. The desktop app
+//! sends them through a single helper, so a name cannot be misspelled or
+//! paired with the wrong payload.
+//!
+//! | Event                | Payload                | Sent to               |
+//! |----------------------|------------------------|-----------------------|
+//! | `advisor-status`     | [`AdvisorStatus`]      | all windows           |
+//! | `startup-progress`   | [`StartupProgress`]    | all windows           |
+//! | `data://changed`     | [`DataChanged`]        | all windows           |
+//! | `reminder`           | [`UpcomingDeadline`]   | all windows           |
+//! | `import-preview`     | [`DroppedFile`]        | the window dropped on |
+//! | `result-chunk`       | [`StreamedChunk`]      | all windows           |
+//! | `menu://action`      | [`MenuAction`]         | main window           |
+//! | `deep-link://open`   | [`DeepLink`]           | main window           |
+//! | `tray://recompute`   | [`RecomputeRequested`] | all windows           |
+//! | `advisor-stderr`     | [`AdvisorStderrLine`]  | all windows           |
+//! | `deadline-countdown` | [`Countdown`]          | all windows           |
+
+use serde::Serialize;
+
+use crate::countdown::Countdown;
+use crate::deep_link::DeepLink;
+use crate::i18n::LocalizedError;
+use crate::import::ImportPreview;
+use crate::reminders::UpcomingDeadline;
+use crate::startup::StartupProgress;
+use crate::streaming::ResultChunk;
+use crate::supervisor::AdvisorStatus;
+
+/// Label of the main window
+pub const MAIN_WINDOW: &str = "main";
+
+/// A message to the frontend and the payload it carries
+pub trait Event: Serialize {
+    /// Name the frontend listens for
+    const NAME: &'static str;
+
+    /// Label of the window to deliver to; `None` sends to every window
+    fn target(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Advisor availability changed
+impl Event for AdvisorStatus {
+    const NAME: &'static str = "advisor-status";
+}
+
+/// An advisor warm-up stage started or failed
+impl Event for StartupProgress {
+    const NAME: &'static str = "startup-progress";
+}
+
+/// The saved data changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataChanged {
+    /// Label of the window that made the change, if any, so it can skip
+    /// reloading
+    pub source: Option<String>,
+}
+
+impl Event for DataChanged {
+    const NAME: &'static str = "data://changed";
+}
+
+/// A payment deadline entered the reminder window, alongside the desktop
+/// notification
+impl Event for UpcomingDeadline {
+    const NAME: &'static str = "reminder";
+}
+
+/// A file dropped on a window, validated like the import dialog
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedFile {
+    pub file_name: String,
+    /// Validation result, when the file could be read and parsed
+    pub preview: Option<ImportPreview>,
+    /// Why the file cannot be imported
+    pub error: Option<LocalizedError>,
+    /// Label of the window it was dropped on
+    #[serde(skip)]
+    pub window: String,
+}
+
+impl Event for DroppedFile {
+    const NAME: &'static str = "import-preview";
+
+    fn target(&self) -> Option<&str> {
+        Some(&self.window)
+    }
+}
+
+/// One chunk of a result streamed to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamedChunk<'a> {
+    /// Id the frontend chose for the stream
+    pub stream_id: &'a str,
+    #[serde(flatten)]
+    pub chunk: ResultChunk,
+}
+
+impl Event for StreamedChunk<'_> {
+    const NAME: &'static str = "result-chunk";
+}
+
+/// A menu action for the frontend to carry out, or the result of one
+/// handled in Rust
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuAction {
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+}
+
+impl Event for MenuAction {
+    const NAME: &'static str = "menu://action";
+
+    fn target(&self) -> Option<&str> {
+        Some(MAIN_WINDOW)
+    }
+}
+
+/// A `school-payment://` link was opened
+impl Event for DeepLink {
+    const NAME: &'static str = "deep-link://open";
+
+    fn target(&self) -> Option<&str> {
+        Some(MAIN_WINDOW)
+    }
+}
+
+/// "Recompute recommendations" was chosen in the tray menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RecomputeRequested;
+
+impl Event for RecomputeRequested {
+    const NAME: &'static str = "tray://recompute";
+}
+
+/// A line the advisor wrote to stderr, while debugging it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct AdvisorStderrLine(pub String);
+
+impl Event for AdvisorStderrLine {
+    const NAME: &'static str = "advisor-stderr";
+}
+
+/// The deadline countdown changed, see [`crate::countdown`]
+impl Event for Countdown {
+    const NAME: &'static str = "deadline-countdown";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_payloads() {
+        let changed = DataChanged {
+            source: Some("calendar".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&changed).unwrap(),
+            json!({ "source": "calendar" })
+        );
+        assert_eq!(changed.target(), None);
+
+        let dropped = DroppedFile {
+            file_name: "schools.csv".to_string(),
+            preview: None,
+            error: None,
+            window: "school-3".to_string(),
+        };
+        assert_eq!(dropped.target(), Some("school-3"));
+        assert_eq!(
+            serde_json::to_value(&dropped).unwrap(),
+            json!({ "fileName": "schools.csv", "preview": null, "error": null })
+        );
+
+        let chunk = StreamedChunk {
+            stream_id: "s1",
+            chunk: ResultChunk {
+                seq: 0,
+                header: None,
+                items: vec![json!(1)],
+                total: 1,
+                done: true,
+            },
+        };
+        let value = serde_json::to_value(&chunk).unwrap();
+        assert_eq!(value["streamId"], "s1");
+        assert_eq!(value["items"], json!([1]));
+
+        assert_eq!(DeepLink::Home.target(), Some(MAIN_WINDOW));
+        assert_eq!(
+            serde_json::to_value(RecomputeRequested).unwrap(),
+            json!(null)
+        );
+        assert_eq!(
+            serde_json::to_value(AdvisorStderrLine("warning".to_string())).unwrap(),
+            json!("warning")
+        );
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        let mut names = vec![
+            AdvisorStatus::NAME,
+            StartupProgress::NAME,
+            DataChanged::NAME,
+            UpcomingDeadline::NAME,
+            DroppedFile::NAME,
+            StreamedChunk::NAME,
+            MenuAction::NAME,
+            DeepLink::NAME,
+            RecomputeRequested::NAME,
+            AdvisorStderrLine::NAME,
+            Countdown::NAME,
+        ];
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 11);
+    }
+}
@@ -0,0 +1,192 @@
+//! Explanations of the advisor's recommendations, fetched ahead of time.
+//!
+//! Asking the advisor why it recommends something takes as long as the
+//! recommendation itself. So when a `getRecommendation` result comes back,
+//! each recommended action is given an id, a hash of the inputs it was
+//! judged from and the action, and its explanation is fetched from the
+//! advisor's `explain` method in the background. The explanations are kept
+//! in [`EXPLANATIONS_FILE`], most recent last, up to [`MAX_ENTRIES`].
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ts_rs::TS;
+
+use crate::json_rpc::JsonRpcRequest;
+use crate::model::PaymentAction;
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding the explanations
+pub const EXPLANATIONS_FILE: &str = "explanations.json";
+
+/// Explanations kept; the oldest are dropped first
+pub const MAX_ENTRIES: usize = 200;
+
+/// Why the advisor recommends an action (Lean: Explanation)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Explanation {
+    pub summary: String,
+    /// Facts the judgement rests on, such as the status of preferred schools
+    pub factors: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedExplanation {
+    id: String,
+    explanation: Explanation,
+}
+
+/// Stored explanations, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplanationCache {
+    entries: Vec<CachedExplanation>,
+}
+
+impl ExplanationCache {
+    /// Load saved explanations, or none if nothing has been saved
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(EXPLANATIONS_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(EXPLANATIONS_FILE, &serde_json::to_value(self)?)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Explanation> {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| &e.explanation)
+    }
+
+    /// Keep `explanation` under `id`, dropping the oldest beyond [`MAX_ENTRIES`]
+    pub fn insert(&mut self, id: String, explanation: Explanation) {
+        self.entries.retain(|e| e.id != id);
+        self.entries.push(CachedExplanation { id, explanation });
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+    }
+}
+
+/// Inputs of `getRecommendation` params that the advice depends on
+fn explain_params(params: &serde_json::Value, action: &PaymentAction) -> serde_json::Value {
+    serde_json::json!({
+        "today": params.get("today"),
+        "schools": params.get("schools"),
+        "states": params.get("states"),
+        "action": action,
+    })
+}
+
+/// Id of the recommendation of `action` for `getRecommendation` params
+pub fn explanation_id(params: &serde_json::Value, action: &PaymentAction) -> String {
+    let digest = Sha256::digest(explain_params(params, action).to_string());
+    hex::encode(&digest[..16])
+}
+
+/// `explain` request for the recommendation of `action`
+pub fn explain_request(params: &serde_json::Value, action: &PaymentAction) -> JsonRpcRequest {
+    JsonRpcRequest::new(
+        "explain",
+        &explain_params(params, action),
+        serde_json::json!("explain"),
+    )
+}
+
+/// Give the actions in a `getRecommendation` result their explanation ids.
+///
+/// Returns the ids with the `explain` request for each, top action first.
+pub fn annotate(
+    params: &serde_json::Value,
+    result: &mut serde_json::Value,
+) -> Vec<(String, JsonRpcRequest)> {
+    let mut requests: Vec<(String, JsonRpcRequest)> = Vec::new();
+    let mut tag = |recommendation: &mut serde_json::Value| {
+        let Some(action) = recommendation
+            .get("action")
+            .and_then(|a| serde_json::from_value::<PaymentAction>(a.clone()).ok())
+        else {
+            return;
+        };
+        let id = explanation_id(params, &action);
+        recommendation["explanationId"] = id.clone().into();
+        if !requests.iter().any(|(known, _)| *known == id) {
+            requests.push((id, explain_request(params, &action)));
+        }
+    };
+    if !result.is_object() {
+        return requests;
+    }
+    tag(result);
+    if let Some(all) = result
+        .get_mut("allRecommendations")
+        .and_then(|a| a.as_array_mut())
+    {
+        all.iter_mut().for_each(&mut tag);
+    }
+    requests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::PaymentActionType;
+
+    fn explanation(summary: &str) -> Explanation {
+        Explanation {
+            summary: summary.to_string(),
+            factors: vec!["東京大学: 発表待ち（発表日 20260310）".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_annotate() {
+        let params = serde_json::json!({"today": 20260301, "schools": [], "states": []});
+        let mut result = serde_json::json!({
+            "action": {"type": "payTuition", "schoolId": 2},
+            "reason": "",
+            "urgency": 3,
+            "allRecommendations": [
+                {"action": {"type": "payTuition", "schoolId": 2}, "reason": "", "urgency": 3},
+                {"action": {"type": "payEnrollmentFee", "schoolId": 1}, "reason": "", "urgency": 5}
+            ]
+        });
+        let requests = annotate(&params, &mut result);
+
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].1.method, "explain");
+        assert_eq!(requests[0].1.params_value()["action"]["schoolId"], 2);
+        let id = result["explanationId"].as_str().unwrap();
+        assert_eq!(id.len(), 32);
+        assert_eq!(result["allRecommendations"][0]["explanationId"], id);
+        assert_ne!(result["allRecommendations"][1]["explanationId"], id);
+
+        // The same inputs and action always get the same id
+        let action = PaymentAction {
+            action_type: PaymentActionType::PayTuition,
+            school_id: Some(2),
+        };
+        assert_eq!(explanation_id(&params, &action), id);
+    }
+
+    #[test]
+    fn test_cache_keeps_the_latest() {
+        let mut cache = ExplanationCache::default();
+        for i in 0..MAX_ENTRIES + 5 {
+            cache.insert(i.to_string(), explanation(&i.to_string()));
+        }
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert_eq!(cache.get("0"), None);
+        assert_eq!(cache.get("10").unwrap().summary, "10");
+
+        cache.insert("10".to_string(), explanation("new"));
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert_eq!(cache.get("10").unwrap().summary, "new");
+    }
+}
@@ -0,0 +1,410 @@
+//! Export of the stored school schedule in several file formats.
+//!
+//! [`ExportFormat`] handles content negotiation (`Accept` header or an
+//! explicit format name); the per-format writers live in submodules.
+//! Printable PDF reports are selected with [`ReportType`]. Users can add
+//! their own text formats as templates, see [`template`].
+
+mod csv;
+mod ics;
+mod pdf;
+mod plan;
+mod tax;
+pub mod template;
+mod text;
+mod xlsx;
+mod zengin;
+
+pub use template::{ExportTemplate, TemplateExport, TEMPLATES_DIR};
+pub use text::TextFormat;
+pub use zengin::ZenginLayout;
+
+use serde::{Deserialize, Serialize};
+
+use crate::currency::ExchangeRates;
+use crate::history::HistoryEntry;
+use crate::model::{Currency, Dataset, School};
+use crate::settings::BankTransferSettings;
+use crate::summary::SummaryRange;
+use crate::tax_report::TaxReport;
+use thiserror::Error;
+
+/// Errors that can occur during export
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("Invalid school data: {0}")]
+    InvalidData(#[from] serde_json::Error),
+
+    #[error("Spreadsheet error: {0}")]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+
+    #[error("Export format '{0}' is not supported yet")]
+    Unsupported(&'static str),
+
+    #[error("Bank transfer settings are not set")]
+    NoBankTransferSettings,
+
+    #[error("Invalid bank account for {school}: {field}")]
+    InvalidAccount { school: String, field: &'static str },
+
+    #[error("Export template not found: {0}")]
+    UnknownTemplate(String),
+
+    #[error("Template error: {0}")]
+    Template(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Xlsx,
+    Ics,
+    Pdf,
+}
+
+impl ExportFormat {
+    const ALL: [ExportFormat; 5] = [
+        ExportFormat::Json,
+        ExportFormat::Csv,
+        ExportFormat::Xlsx,
+        ExportFormat::Ics,
+        ExportFormat::Pdf,
+    ];
+
+    /// Look up a format by its short name (`json`, `csv`, `xlsx`, `ics`, `pdf`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.extension().eq_ignore_ascii_case(name))
+    }
+
+    /// Pick the most preferred supported format from an `Accept` header.
+    ///
+    /// Wildcards resolve to JSON. Returns `None` if nothing acceptable matches.
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, usize, Self)> = Vec::new();
+        for (index, entry) in accept.split(',').enumerate() {
+            let mut parts = entry.split(';');
+            let media_type = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality <= 0.0 {
+                continue;
+            }
+
+            let format = match media_type.as_str() {
+                "*/*" | "application/*" => Some(Self::Json),
+                "text/*" => Some(Self::Csv),
+                other => Self::ALL.into_iter().find(|f| f.content_type() == other),
+            };
+            if let Some(format) = format {
+                candidates.push((quality, index, format));
+            }
+        }
+
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        candidates.first().map(|c| c.2)
+    }
+
+    /// MIME type for the `Content-Type` header
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            Self::Ics => "text/calendar",
+            Self::Pdf => "application/pdf",
+        }
+    }
+
+    /// File extension without the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+            Self::Ics => "ics",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// Read the `schools` array of a stored dataset
+pub fn schedule_entries(data: &serde_json::Value) -> Result<Vec<School>, ExportError> {
+    let schools = data
+        .get("schools")
+        .cloned()
+        .unwrap_or(serde_json::Value::Array(Vec::new()));
+    let mut entries: Vec<School> = serde_json::from_value(schools)?;
+    entries.sort_by_key(|e| (e.priority, e.id));
+    Ok(entries)
+}
+
+/// Render a dataset in the given format
+pub fn export(data: &serde_json::Value, format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(data)?),
+        ExportFormat::Csv => Ok(csv::render(&schedule_entries(data)?).into_bytes()),
+        ExportFormat::Xlsx => xlsx::render(&schedule_entries(data)?),
+        ExportFormat::Ics => Ok(ics::render(&schedule_entries(data)?).into_bytes()),
+        ExportFormat::Pdf => schedule_pdf(data, crate::date::today()),
+    }
+}
+
+/// Render recommendation history as JSON or CSV
+pub fn history(entries: &[HistoryEntry], format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(entries)?),
+        ExportFormat::Csv => Ok(csv::render_history(entries).into_bytes()),
+        other => Err(ExportError::Unsupported(other.extension())),
+    }
+}
+
+/// Render a year-end report as JSON, CSV or PDF
+pub fn tax_report(report: &TaxReport, format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(report)?),
+        ExportFormat::Csv => Ok(csv::render_tax_report(report).into_bytes()),
+        ExportFormat::Pdf => Ok(tax::render(report)),
+        other => Err(ExportError::Unsupported(other.extension())),
+    }
+}
+
+/// Printable reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportType {
+    /// Every school's dates, amounts, and payment status
+    Schedule,
+    /// The advisor's recommended action for each day of the coming week
+    Weekly,
+    /// Summary, per-school payments, deadline calendar and the advisor's advice
+    Plan,
+}
+
+/// Render the payment schedule of a dataset as PDF
+pub fn schedule_pdf(data: &serde_json::Value, today: u32) -> Result<Vec<u8>, ExportError> {
+    Ok(pdf::render_schedule(&schedule_entries(data)?, today))
+}
+
+/// Render a `getWeeklyRecommendations` result for a dataset as PDF
+pub fn weekly_pdf(
+    data: &serde_json::Value,
+    recommendations: &serde_json::Value,
+    today: u32,
+) -> Result<Vec<u8>, ExportError> {
+    let report = serde_json::from_value(recommendations.clone())?;
+    Ok(pdf::render_weekly(&report, &schedule_entries(data)?, today))
+}
+
+/// Render the payment-plan report of a dataset as PDF.
+///
+/// `advice` is a `getRecommendation` result for `today`; totals are in yen
+/// using `rates`.
+pub fn plan_pdf(
+    data: &serde_json::Value,
+    rates: &ExchangeRates,
+    advice: &serde_json::Value,
+    today: u32,
+) -> Result<Vec<u8>, ExportError> {
+    let dataset = Dataset {
+        schools: schedule_entries(data)?,
+        method_fees: data
+            .get("methodFees")
+            .map(|fees| serde_json::from_value(fees.clone()))
+            .transpose()?
+            .unwrap_or_default(),
+        ..Dataset::default()
+    };
+    let summary = crate::summary::summarize(&dataset, SummaryRange::default(), rates);
+    let advice = serde_json::from_value(advice.clone())?;
+    Ok(plan::render(&dataset.schools, &summary, &advice, today))
+}
+
+/// Days covered by the upcoming-payments summary
+pub const SUMMARY_DAYS: i64 = 7;
+
+/// A bank transfer file and what went into it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZenginExport {
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+    pub transfers: usize,
+    pub total: u64,
+    /// Schools with payments due but no payee account, or not in yen
+    pub skipped: Vec<String>,
+}
+
+/// Bank transfers, dated `today`, for the unpaid payments due in the week
+/// starting `today`
+pub fn zengin_transfers(
+    data: &serde_json::Value,
+    requester: &BankTransferSettings,
+    today: u32,
+    layout: ZenginLayout,
+) -> Result<ZenginExport, ExportError> {
+    let entries = schedule_entries(data)?;
+    let mut transfers = Vec::new();
+    let mut skipped = Vec::new();
+    for deadline in crate::reminders::upcoming_deadlines(data, today, SUMMARY_DAYS - 1) {
+        let Some(school) = entries.iter().find(|s| s.id == deadline.school_id) else {
+            continue;
+        };
+        let payee = match &school.payee {
+            Some(payee) if school.currency == Currency::Jpy => payee,
+            _ => {
+                if !skipped.contains(&school.name) {
+                    skipped.push(school.name.clone());
+                }
+                continue;
+            }
+        };
+        if let Some(field) = payee.invalid_field() {
+            return Err(ExportError::InvalidAccount {
+                school: school.name.clone(),
+                field,
+            });
+        }
+        transfers.push(zengin::Transfer {
+            payee,
+            amount: deadline.amount,
+            customer_code: school.id.to_string(),
+        });
+    }
+
+    Ok(ZenginExport {
+        bytes: zengin::render(requester, today, &transfers, layout),
+        transfers: transfers.len(),
+        total: transfers.iter().map(|t| t.amount).sum(),
+        skipped,
+    })
+}
+
+/// Summarize unpaid deadlines in the week starting `today` for pasting
+pub fn upcoming_payments_text(data: &serde_json::Value, today: u32, format: TextFormat) -> String {
+    let deadlines = crate::reminders::upcoming_deadlines(data, today, SUMMARY_DAYS - 1);
+    text::render(&deadlines, today, SUMMARY_DAYS, format)
+}
+
+#[cfg(test)]
+pub(crate) fn sample_data() -> serde_json::Value {
+    serde_json::json!({
+        "schools": [
+            {
+                "id": 2, "name": "早稲田大学", "priority": 2,
+                "examDate": 20260215, "resultDate": 20260301,
+                "enrollmentFeeDeadline": 20260306, "tuitionDeadline": 20260320,
+                "enrollmentFee": 200000, "tuition": 800000,
+                "passStatus": "passed", "enrollmentFeePaid": true, "tuitionPaid": false
+            },
+            {
+                "id": 1, "name": "東京大学", "priority": 1,
+                "examDate": 20260225, "resultDate": 20260310,
+                "enrollmentFeeDeadline": 20260317, "tuitionDeadline": 20260331,
+                "enrollmentFee": 282000, "tuition": 535800,
+                "passStatus": "notYetAnnounced", "enrollmentFeePaid": false, "tuitionPaid": false
+            }
+        ]
+    })
+}
+
+/// [`sample_data`] as a dataset, the fixture most module tests start from
+#[cfg(test)]
+pub(crate) fn sample_dataset() -> crate::model::Dataset {
+    crate::model::Dataset::from_value(sample_data()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(ExportFormat::from_name("CSV"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::from_name("ics"), Some(ExportFormat::Ics));
+        assert_eq!(ExportFormat::from_name("doc"), None);
+    }
+
+    #[test]
+    fn test_from_accept() {
+        assert_eq!(
+            ExportFormat::from_accept("text/csv"),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(
+            ExportFormat::from_accept("text/calendar;q=0.5, application/json;q=0.9"),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(
+            ExportFormat::from_accept("text/html, */*;q=0.8"),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(ExportFormat::from_accept("text/csv;q=0"), None);
+        assert_eq!(ExportFormat::from_accept("image/png"), None);
+    }
+
+    #[test]
+    fn test_schedule_entries_sorted_by_priority() {
+        let entries = schedule_entries(&sample_data()).unwrap();
+        assert_eq!(entries[0].name, "東京大学");
+        assert!(entries[1].enrollment_fee_paid);
+    }
+
+    #[test]
+    fn test_export_all_formats() {
+        let data = sample_data();
+        assert!(!export(&data, ExportFormat::Json).unwrap().is_empty());
+        assert!(!export(&data, ExportFormat::Csv).unwrap().is_empty());
+        assert!(!export(&data, ExportFormat::Ics).unwrap().is_empty());
+        // XLSX files are zip archives
+        assert!(export(&data, ExportFormat::Xlsx)
+            .unwrap()
+            .starts_with(b"PK"));
+        assert!(export(&data, ExportFormat::Pdf)
+            .unwrap()
+            .starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn test_export_invalid_data() {
+        let data = serde_json::json!({"schools": [{"id": 1}]});
+        assert!(export(&data, ExportFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn test_zengin_transfers_for_the_week() {
+        let mut data = sample_data();
+        let mut payee = serde_json::json!({
+            "bankCode": "0001", "bankName": "ミズホ",
+            "branchCode": "002", "branchName": "ワセダ",
+            "accountNumber": "7654321", "holderName": "ガク)ワセダダイガク"
+        });
+        data["schools"][0]["payee"] = payee.clone();
+        let requester = BankTransferSettings {
+            requester_code: "0000012345".to_string(),
+            account: serde_json::from_value(payee.clone()).unwrap(),
+        };
+
+        let export =
+            zengin_transfers(&data, &requester, 20260315, ZenginLayout::Fixed).unwrap();
+        assert_eq!(export.transfers, 1);
+        assert_eq!(export.total, 800000);
+        assert_eq!(export.skipped, vec!["東京大学"]);
+        assert_eq!(export.bytes.len(), 4 * 122);
+
+        payee["holderName"] = "早稲田大学".into();
+        data["schools"][0]["payee"] = payee;
+        assert!(matches!(
+            zengin_transfers(&data, &requester, 20260315, ZenginLayout::Fixed),
+            Err(ExportError::InvalidAccount { field: "holderName", .. })
+        ));
+    }
+}
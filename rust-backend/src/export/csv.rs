@@ -0,0 +1,216 @@
+//! CSV writer: one row per school.
+//!
+//! Installments and adjustments are written as JSON arrays in their cells,
+//! empty when a school has none.
+
+use crate::history::HistoryEntry;
+use crate::model::School;
+use crate::tax_report::TaxReport;
+
+/// Column headers, matching the stored JSON field names
+pub(super) const COLUMNS: [&str; 16] = [
+    "id",
+    "name",
+    "priority",
+    "passStatus",
+    "examDate",
+    "resultDate",
+    "enrollmentFeeDeadline",
+    "enrollmentFee",
+    "enrollmentFeePaid",
+    "tuitionDeadline",
+    "tuition",
+    "tuitionPaid",
+    "currency",
+    "childId",
+    "installments",
+    "adjustments",
+];
+
+/// Quote a field if it contains a delimiter, quote, or line break
+pub(super) fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `items` as JSON text, or an empty string for none
+pub(super) fn json_cell<T: serde::Serialize>(items: &[T]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    serde_json::to_string(items).unwrap_or_default()
+}
+
+/// Render entries as CSV with a header row
+pub(super) fn render(entries: &[School]) -> String {
+    let mut out = COLUMNS.join(",");
+    out.push_str("\r\n");
+    for e in entries {
+        let fields = [
+            e.id.to_string(),
+            escape(&e.name),
+            e.priority.to_string(),
+            escape(e.pass_status.as_str()),
+            e.exam_date.to_string(),
+            e.result_date.to_string(),
+            e.enrollment_fee_deadline.to_string(),
+            e.enrollment_fee.to_string(),
+            e.enrollment_fee_paid.to_string(),
+            e.tuition_deadline.to_string(),
+            e.tuition.to_string(),
+            e.tuition_paid.to_string(),
+            e.currency.code().to_string(),
+            e.child_id.map(|id| id.to_string()).unwrap_or_default(),
+            escape(&json_cell(&e.installments)),
+            escape(&json_cell(&e.adjustments)),
+        ];
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Column headers of the history export
+const HISTORY_COLUMNS: [&str; 11] = [
+    "id",
+    "recordedAt",
+    "day",
+    "actionType",
+    "schoolId",
+    "schoolName",
+    "amount",
+    "reason",
+    "decision",
+    "decidedAt",
+    "note",
+];
+
+/// Render recommendation history as CSV with a header row
+pub(super) fn render_history(entries: &[HistoryEntry]) -> String {
+    let mut out = HISTORY_COLUMNS.join(",");
+    out.push_str("\r\n");
+    for e in entries {
+        let fields = [
+            e.id.to_string(),
+            escape(&e.recorded_at),
+            e.day.to_string(),
+            e.action_type.as_str().to_string(),
+            e.school_id.map(|id| id.to_string()).unwrap_or_default(),
+            escape(e.school_name.as_deref().unwrap_or_default()),
+            e.amount.map(|a| a.to_string()).unwrap_or_default(),
+            escape(&e.recommendation.reason),
+            e.decision.map(|d| d.as_str()).unwrap_or_default().to_string(),
+            escape(e.decided_at.as_deref().unwrap_or_default()),
+            escape(e.note.as_deref().unwrap_or_default()),
+        ];
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Column headers of the year-end report
+const TAX_REPORT_COLUMNS: [&str; 11] = [
+    "paidOn",
+    "schoolId",
+    "schoolName",
+    "childName",
+    "fee",
+    "installment",
+    "amount",
+    "currency",
+    "amountYen",
+    "method",
+    "reference",
+];
+
+/// Render the payments of a year-end report as CSV with a header row.
+/// Method and reference are empty for payments without a receipt.
+pub(super) fn render_tax_report(report: &TaxReport) -> String {
+    let mut out = TAX_REPORT_COLUMNS.join(",");
+    out.push_str("\r\n");
+    for line in &report.lines {
+        let p = &line.payment;
+        let receipt = p.receipt.as_ref();
+        let fields = [
+            line.paid_on.to_string(),
+            p.school_id.to_string(),
+            escape(&p.school_name),
+            escape(line.child_name.as_deref().unwrap_or_default()),
+            p.fee.as_str().to_string(),
+            p.installment.map(|i| i.to_string()).unwrap_or_default(),
+            p.amount.to_string(),
+            p.currency.code().to_string(),
+            line.amount_yen.map(|a| a.to_string()).unwrap_or_default(),
+            receipt
+                .map(|r| r.method.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            escape(
+                receipt
+                    .and_then(|r| r.reference.as_deref())
+                    .unwrap_or_default(),
+            ),
+        ];
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{sample_data, schedule_entries};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_render() {
+        let csv = render(&schedule_entries(&sample_data()).unwrap());
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("id,name,priority"));
+        assert_eq!(
+            lines[1],
+            "1,東京大学,1,notYetAnnounced,20260225,20260310,20260317,282000,false,20260331,535800,false,JPY,,,"
+        );
+    }
+
+    #[test]
+    fn test_render_installments() {
+        let mut entries = schedule_entries(&sample_data()).unwrap();
+        entries[1].installments = vec![crate::model::Installment {
+            due: 20260320,
+            amount: 800000,
+            paid: false,
+        }];
+        let csv = render(&entries);
+        assert!(csv.contains(r#",JPY,,"[{""due"":20260320,""amount"":800000,""paid"":false}]","#));
+    }
+
+    #[test]
+    fn test_render_history() {
+        let mut history = crate::history::History::default();
+        let recommendation = serde_json::from_value(serde_json::json!({
+            "action": {"type": "doNothing"},
+            "reason": "発表待ち, 3日後",
+            "urgency": 3,
+            "allRecommendations": []
+        }))
+        .unwrap();
+        history.record(20260301, &serde_json::json!({}), recommendation, "2026-03-01");
+
+        let csv = render_history(&history.entries);
+        let row = csv.lines().nth(1).unwrap();
+        assert_eq!(row, "1,2026-03-01,20260301,doNothing,,,,\"発表待ち, 3日後\",,,");
+    }
+}
@@ -0,0 +1,198 @@
+//! iCalendar writer: all-day events for exams, results, and payment deadlines.
+//!
+//! Unpaid recurring payments get an event each, identified by the position
+//! of their fee and their due date.
+
+use crate::model::School;
+use crate::format::format_yen;
+use crate::date::{parse_day, to_day};
+
+/// Escape text per RFC 5545 section 3.3.11
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line to at most 75 octets, continuing with a leading space
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn event(out: &mut String, uid: &str, stamp: &str, day: u32, summary: &str) {
+    let Some(date) = parse_day(day) else {
+        return;
+    };
+    let end = to_day(date + chrono::Duration::days(1));
+    out.push_str("BEGIN:VEVENT\r\n");
+    out.push_str(&fold(&format!("UID:{}@school-payment", uid)));
+    out.push_str(&format!("DTSTAMP:{}\r\n", stamp));
+    out.push_str(&format!("DTSTART;VALUE=DATE:{}\r\n", day));
+    out.push_str(&format!("DTEND;VALUE=DATE:{}\r\n", end));
+    out.push_str(&fold(&format!("SUMMARY:{}", escape(summary))));
+    out.push_str("END:VEVENT\r\n");
+}
+
+/// Render entries as an iCalendar document
+pub(super) fn render(entries: &[School]) -> String {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut out = String::from(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//school-payment//advisor//JA\r\nCALSCALE:GREGORIAN\r\n",
+    );
+
+    for e in entries {
+        event(
+            &mut out,
+            &format!("{}-exam", e.id),
+            &stamp,
+            e.exam_date,
+            &format!("{} 受験日", e.name),
+        );
+        event(
+            &mut out,
+            &format!("{}-result", e.id),
+            &stamp,
+            e.result_date,
+            &format!("{} 合格発表", e.name),
+        );
+        if !e.enrollment_fee_paid {
+            event(
+                &mut out,
+                &format!("{}-enrollment-fee", e.id),
+                &stamp,
+                e.enrollment_fee_deadline,
+                &format!(
+                    "{} 入学金期限 {}",
+                    e.name,
+                    format_yen(e.net_enrollment_fee())
+                ),
+            );
+        }
+        let tuition = e.tuition_payments();
+        let count = tuition.len();
+        for (i, payment) in tuition.into_iter().enumerate().filter(|(_, p)| !p.paid) {
+            let (uid, label) = if count == 1 {
+                (format!("{}-tuition", e.id), "授業料期限".to_string())
+            } else {
+                (
+                    format!("{}-tuition-{}", e.id, i + 1),
+                    format!("授業料期限 ({}/{}回)", i + 1, count),
+                )
+            };
+            event(
+                &mut out,
+                &uid,
+                &stamp,
+                payment.due,
+                &format!("{} {} {}", e.name, label, format_yen(payment.amount)),
+            );
+        }
+        for payment in e.recurring_payments.iter().filter(|p| !p.paid) {
+            let fee = e
+                .recurring_fees
+                .iter()
+                .position(|f| f.label == payment.label)
+                .unwrap_or(e.recurring_fees.len());
+            event(
+                &mut out,
+                &format!("{}-recurring-{}-{}", e.id, fee, payment.due),
+                &stamp,
+                payment.due,
+                &format!(
+                    "{} {}期限 {}",
+                    e.name,
+                    payment.label,
+                    format_yen(payment.amount)
+                ),
+            );
+        }
+    }
+
+    out.push_str("END:VCALENDAR\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{sample_data, schedule_entries};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a,b;c\\d"), "a\\,b\\;c\\\\d");
+    }
+
+    #[test]
+    fn test_fold_long_line() {
+        let folded = fold(&"あ".repeat(40));
+        for line in folded.split("\r\n") {
+            assert!(line.len() <= 75);
+        }
+        assert_eq!(folded.replace("\r\n ", "").trim_end(), "あ".repeat(40));
+    }
+
+    #[test]
+    fn test_render() {
+        let ics = render(&schedule_entries(&sample_data()).unwrap());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        // Tokyo: 4 events; Waseda: enrollment fee already paid, so 3
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 7);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20260317\r\nDTEND;VALUE=DATE:20260318"));
+        assert!(ics.contains("SUMMARY:東京大学 入学金期限 ¥282\\,000"));
+    }
+
+    #[test]
+    fn test_render_installments() {
+        let mut entries = schedule_entries(&sample_data()).unwrap();
+        let waseda = entries.iter_mut().find(|e| e.id == 2).unwrap();
+        waseda.installments = crate::model::InstallmentPlan {
+            total: 800000,
+            first_due: 20260320,
+            count: 2,
+            interval_months: 6,
+        }
+        .schedule()
+        .unwrap();
+        let ics = render(&entries);
+
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 8);
+        assert!(ics.contains("UID:2-tuition-2@school-payment"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20260920"));
+        assert!(ics.contains("SUMMARY:早稲田大学 授業料期限 (2/2回) ¥400\\,000"));
+    }
+
+    #[test]
+    fn test_render_recurring_payments() {
+        let mut entries = schedule_entries(&sample_data()).unwrap();
+        let waseda = entries.iter_mut().find(|e| e.id == 2).unwrap();
+        waseda.recurring_fees = vec![crate::model::RecurringFee {
+            label: "施設費".to_string(),
+            amount: 30000,
+            first_due: 20260430,
+            interval_months: 1,
+            until: Some(20260630),
+        }];
+        waseda.regenerate_recurring(20260301);
+        waseda.recurring_payments[0].paid = true;
+        let ics = render(&entries);
+
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 9);
+        assert!(!ics.contains("UID:2-recurring-0-20260430@school-payment"));
+        assert!(ics.contains("UID:2-recurring-0-20260530@school-payment"));
+        assert!(ics.contains("SUMMARY:早稲田大学 施設費期限 ¥30\\,000"));
+    }
+}
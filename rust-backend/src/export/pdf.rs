@@ -0,0 +1,556 @@
+//! PDF writer for printable reports.
+//!
+//! Reports are plain text laid out on landscape A4 pages. Text is set in the
+//! standard Adobe-Japan1 font `KozMinPr6N-Regular`, which is referenced but not
+//! embedded: PDF viewers substitute their own Japanese Mincho face, so the
+//! files stay small and no font has to ship with the app.
+
+use serde::Deserialize;
+
+use crate::date::format_wareki;
+use crate::format::{format_day, format_yen};
+use crate::model::{PassStatus, School};
+
+const PAGE_WIDTH: f32 = 842.0;
+const PAGE_HEIGHT: f32 = 595.0;
+const MARGIN: f32 = 40.0;
+
+const TITLE_SIZE: f32 = 16.0;
+const TEXT_SIZE: f32 = 9.5;
+const LINE_HEIGHT: f32 = 1.6;
+
+/// A string drawn at a fixed position
+struct TextRun {
+    x: f32,
+    y: f32,
+    size: f32,
+    text: String,
+}
+
+/// Approximate advance width of `c` in text space units (1000 per em)
+fn char_width(c: char) -> f32 {
+    // The half-width CMap maps ASCII and half-width katakana to 500-unit glyphs
+    if c.is_ascii() || ('\u{FF61}'..='\u{FF9F}').contains(&c) {
+        500.0
+    } else {
+        1000.0
+    }
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().map(char_width).sum::<f32>() * size / 1000.0
+}
+
+/// Cut `text` to fit in `width`, marking the cut with an ellipsis
+fn truncate(text: &str, width: f32, size: f32) -> String {
+    if text_width(text, size) <= width {
+        return text.to_string();
+    }
+    let limit = width - text_width("…", size);
+    let mut out = String::new();
+    let mut used = 0.0;
+    for c in text.chars() {
+        used += char_width(c) * size / 1000.0;
+        if used > limit {
+            break;
+        }
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+/// Split `text` into lines no wider than `width`
+fn wrap(text: &str, width: f32, size: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut used = 0.0;
+        for c in paragraph.chars() {
+            let advance = char_width(c) * size / 1000.0;
+            if used + advance > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                used = 0.0;
+            }
+            line.push(c);
+            used += advance;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Encode text for the UCS-2 CMap as a PDF hex string
+fn hex_string(text: &str) -> String {
+    let mut out = String::from("<");
+    for c in text.chars() {
+        let code = u32::from(c);
+        // Characters outside the BMP have no UCS-2 code
+        let code = if code > 0xFFFF { u32::from('?') } else { code };
+        out.push_str(&format!("{:04X}", code));
+    }
+    out.push('>');
+    out
+}
+
+/// A text-only document that starts a new page when the current one fills up
+pub(super) struct Document {
+    title: String,
+    pages: Vec<Vec<TextRun>>,
+    /// Baseline of the next line on the current page
+    y: f32,
+}
+
+impl Document {
+    pub(super) fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            pages: vec![Vec::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Move down by one line of `size`, breaking the page if needed
+    fn advance(&mut self, size: f32) -> f32 {
+        let height = size * LINE_HEIGHT;
+        if self.y - height < MARGIN {
+            self.pages.push(Vec::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= height;
+        self.y
+    }
+
+    fn draw(&mut self, x: f32, y: f32, size: f32, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        self.pages
+            .last_mut()
+            .expect("document has a page")
+            .push(TextRun { x, y, size, text });
+    }
+
+    /// Continue on a new page unless the current one is still empty
+    pub(super) fn new_page(&mut self) {
+        if self.pages.last().is_some_and(|page| !page.is_empty()) {
+            self.pages.push(Vec::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    /// Add the report title or a section heading
+    pub(super) fn heading(&mut self, text: &str) {
+        let y = self.advance(TITLE_SIZE);
+        self.draw(MARGIN, y, TITLE_SIZE, text.to_string());
+    }
+
+    /// Add a paragraph, wrapped to the page width
+    pub(super) fn paragraph(&mut self, text: &str, indent: f32) {
+        for line in wrap(text, PAGE_WIDTH - 2.0 * MARGIN - indent, TEXT_SIZE) {
+            let y = self.advance(TEXT_SIZE);
+            self.draw(MARGIN + indent, y, TEXT_SIZE, line);
+        }
+    }
+
+    /// Add a table row; `columns` gives each cell's width in points
+    pub(super) fn row(&mut self, columns: &[f32], cells: &[String]) {
+        let y = self.advance(TEXT_SIZE);
+        let mut x = MARGIN;
+        for (width, cell) in columns.iter().zip(cells) {
+            self.draw(x, y, TEXT_SIZE, truncate(cell, width - 4.0, TEXT_SIZE));
+            x += width;
+        }
+    }
+
+    /// Add vertical space
+    pub(super) fn gap(&mut self) {
+        self.advance(TEXT_SIZE / 2.0);
+    }
+
+    /// Serialize the document
+    pub(super) fn finish(self) -> Vec<u8> {
+        let mut objects: Vec<String> = Vec::new();
+        let page_count = self.pages.len();
+        // Fixed objects: 1 catalog, 2 page tree, 3-5 font, 6 info; pages follow
+        let page_ids: Vec<usize> = (0..page_count).map(|i| 7 + 2 * i).collect();
+
+        objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+        objects.push(format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{} 0 R", id))
+                .collect::<Vec<_>>()
+                .join(" "),
+            page_count
+        ));
+        objects.push(
+            "<< /Type /Font /Subtype /Type0 /BaseFont /KozMinPr6N-Regular \
+             /Encoding /UniJIS-UCS2-HW-H /DescendantFonts [4 0 R] >>"
+                .to_string(),
+        );
+        objects.push(
+            "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /KozMinPr6N-Regular \
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 6 >> \
+             /FontDescriptor 5 0 R /DW 1000 /W [1 230 500 327 389 500] >>"
+                .to_string(),
+        );
+        objects.push(
+            "<< /Type /FontDescriptor /FontName /KozMinPr6N-Regular /Flags 6 \
+             /FontBBox [-437 -340 1147 1317] /ItalicAngle 0 /Ascent 880 \
+             /Descent -120 /CapHeight 742 /StemV 80 >>"
+                .to_string(),
+        );
+        objects.push(format!(
+            "<< /Title <FEFF{}> /Producer (school-payment) >>",
+            hex_string(&self.title).trim_matches(['<', '>'])
+        ));
+
+        for (index, runs) in self.pages.iter().enumerate() {
+            let mut content = String::new();
+            for run in runs {
+                content.push_str(&format!(
+                    "BT /F1 {:.1} Tf {:.1} {:.1} Td {} Tj ET\n",
+                    run.size,
+                    run.x,
+                    run.y,
+                    hex_string(&run.text)
+                ));
+            }
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                page_ids[index] + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.push_str(&format!("{} 0 obj\n{}\nendobj\n", index + 1, object));
+        }
+        let xref = out.len();
+        out.push_str(&format!("xref\n0 {}\n", objects.len() + 1));
+        out.push_str("0000000000 65535 f \n");
+        for offset in offsets {
+            out.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        out.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 6 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        ));
+        out.into_bytes()
+    }
+}
+
+pub(super) fn pass_status_label(status: PassStatus) -> &'static str {
+    match status {
+        PassStatus::NotYetAnnounced => "未発表",
+        PassStatus::Passed => "合格",
+        PassStatus::Failed => "不合格",
+        PassStatus::Cancelled => "取消",
+    }
+}
+
+pub(super) fn paid_label(paid: bool) -> &'static str {
+    if paid {
+        "済"
+    } else {
+        "未"
+    }
+}
+
+/// Paid state of a school's tuition, counting installments when paid in parts
+fn tuition_label(school: &School) -> String {
+    let payments = school.tuition_payments();
+    if payments.len() == 1 {
+        return paid_label(payments[0].paid).to_string();
+    }
+    let paid = payments.iter().filter(|p| p.paid).count();
+    format!("{}/{}回済", paid, payments.len())
+}
+
+/// Render the payment schedule: one row per school plus paid/unpaid totals
+pub(super) fn render_schedule(entries: &[School], today: u32) -> Vec<u8> {
+    const COLUMNS: [f32; 10] = [36.0, 170.0, 50.0, 62.0, 62.0, 62.0, 80.0, 62.0, 80.0, 96.0];
+
+    let mut doc = Document::new("支払いスケジュール");
+    doc.heading("支払いスケジュール");
+    doc.paragraph(
+        &format!("作成日: {}（{}）", format_day(today), format_wareki(today)),
+        0.0,
+    );
+    doc.gap();
+    doc.row(
+        &COLUMNS,
+        &[
+            "順位",
+            "学校名",
+            "合否",
+            "試験日",
+            "発表日",
+            "入学金期限",
+            "入学金",
+            "授業料期限",
+            "授業料",
+            "支払状況",
+        ]
+        .map(String::from),
+    );
+
+    let mut paid = 0;
+    let mut unpaid = 0;
+    for e in entries {
+        doc.row(
+            &COLUMNS,
+            &[
+                e.priority.to_string(),
+                e.name.clone(),
+                pass_status_label(e.pass_status).to_string(),
+                format_day(e.exam_date),
+                format_day(e.result_date),
+                format_day(e.enrollment_fee_deadline),
+                format_yen(e.net_enrollment_fee()),
+                format_day(e.tuition_deadline),
+                format_yen(e.net_tuition()),
+                format!(
+                    "入学金{} / 授業料{}",
+                    paid_label(e.enrollment_fee_paid),
+                    tuition_label(e)
+                ),
+            ],
+        );
+
+        let open = e.pass_status.is_open();
+        let payments = std::iter::once((e.net_enrollment_fee(), e.enrollment_fee_paid))
+            .chain(e.tuition_payments().into_iter().map(|p| (p.amount, p.paid)));
+        for (amount, is_paid) in payments {
+            if is_paid {
+                paid += amount;
+            } else if open {
+                unpaid += amount;
+            }
+        }
+    }
+
+    doc.gap();
+    doc.paragraph(
+        &format!(
+            "支払済: {}　未払い（不合格・取消を除く）: {}",
+            format_yen(paid),
+            format_yen(unpaid)
+        ),
+        0.0,
+    );
+    doc.finish()
+}
+
+/// `getWeeklyRecommendations` result
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct WeeklyReport {
+    start_day: u32,
+    recommendations: Vec<DailyRecommendation>,
+    #[serde(default)]
+    upcoming_announcements: Vec<UpcomingAnnouncement>,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DailyRecommendation {
+    day: u32,
+    result: Recommendation,
+}
+
+#[derive(Debug, Deserialize)]
+struct Recommendation {
+    action: serde_json::Value,
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpcomingAnnouncement {
+    school_name: String,
+    result_day: u32,
+}
+
+/// Describe an advisor action, e.g. `入学金を支払う: 東京大学 ¥282,000`
+pub(super) fn describe_action(action: &serde_json::Value, entries: &[School]) -> String {
+    let school = action
+        .get("schoolId")
+        .and_then(|id| id.as_u64())
+        .and_then(|id| entries.iter().find(|e| e.id == id));
+    match (action.get("type").and_then(|t| t.as_str()), school) {
+        (Some("payEnrollmentFee"), Some(s)) => {
+            format!(
+                "入学金を支払う: {} {}",
+                s.name,
+                format_yen(s.net_enrollment_fee())
+            )
+        }
+        (Some("payTuition"), Some(s)) => {
+            format!(
+                "授業料を支払う: {} {}",
+                s.name,
+                format_yen(s.securing_tuition())
+            )
+        }
+        (Some("payEnrollmentFee"), None) => "入学金を支払う".to_string(),
+        (Some("payTuition"), None) => "授業料を支払う".to_string(),
+        _ => "支払いなし（待機）".to_string(),
+    }
+}
+
+/// Render a weekly recommendation report: the action for each day with its reason
+pub(super) fn render_weekly(report: &WeeklyReport, entries: &[School], today: u32) -> Vec<u8> {
+    let mut doc = Document::new("週間推奨レポート");
+    doc.heading("週間推奨レポート");
+    let end_day = report
+        .recommendations
+        .last()
+        .map_or(report.start_day, |r| r.day);
+    doc.paragraph(
+        &format!(
+            "期間: {} 〜 {}　作成日: {}",
+            format_day(report.start_day),
+            format_day(end_day),
+            format_day(today)
+        ),
+        0.0,
+    );
+
+    for daily in &report.recommendations {
+        doc.gap();
+        doc.paragraph(
+            &format!(
+                "{}　{}",
+                format_day(daily.day),
+                describe_action(&daily.result.action, entries)
+            ),
+            0.0,
+        );
+        if !daily.result.reason.is_empty() {
+            doc.paragraph(&daily.result.reason, 16.0);
+        }
+    }
+
+    if !report.upcoming_announcements.is_empty() {
+        doc.gap();
+        doc.paragraph("■ 今後の合格発表", 0.0);
+        for announcement in &report.upcoming_announcements {
+            doc.paragraph(
+                &format!(
+                    "{}　{}",
+                    format_day(announcement.result_day),
+                    announcement.school_name
+                ),
+                16.0,
+            );
+        }
+    }
+
+    if let Some(note) = report.note.as_deref().filter(|n| !n.is_empty()) {
+        doc.gap();
+        doc.paragraph(&format!("※ {}", note), 0.0);
+    }
+    doc.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{sample_data, schedule_entries};
+
+    #[test]
+    fn test_hex_string() {
+        assert_eq!(hex_string("A東"), "<00416771>");
+        assert_eq!(hex_string("😀"), "<003F>");
+    }
+
+    #[test]
+    fn test_truncate_and_wrap() {
+        assert_eq!(truncate("abc", 100.0, 10.0), "abc");
+        assert_eq!(truncate("東京大学大学院", 40.0, 10.0), "東京大…");
+        assert_eq!(wrap("東京大学", 20.0, 10.0), vec!["東京", "大学"]);
+        assert_eq!(wrap("", 20.0, 10.0), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_xref_offsets() {
+        let pdf = render_schedule(&schedule_entries(&sample_data()).unwrap(), 20260301);
+        let text = String::from_utf8(pdf).unwrap();
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        assert!(text[startxref..].starts_with("xref\n"));
+        // Every object offset in the table points at that object
+        for (index, entry) in text[startxref..].lines().skip(3).enumerate() {
+            let Some(offset) = entry.strip_suffix(" 00000 n ") else {
+                break;
+            };
+            let offset: usize = offset.parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", index + 1)));
+        }
+    }
+
+    #[test]
+    fn test_long_schedule_paginates() {
+        let mut data = sample_data();
+        let school = data["schools"][0].clone();
+        let schools = data["schools"].as_array_mut().unwrap();
+        for id in 10..100 {
+            let mut s = school.clone();
+            s["id"] = serde_json::json!(id);
+            schools.push(s);
+        }
+        let pdf = render_schedule(&schedule_entries(&data).unwrap(), 20260301);
+        let text = String::from_utf8(pdf).unwrap();
+        assert!(text.matches("/Type /Page ").count() > 1);
+    }
+
+    #[test]
+    fn test_render_weekly() {
+        let entries = schedule_entries(&sample_data()).unwrap();
+        let report: WeeklyReport = serde_json::from_value(serde_json::json!({
+            "startDay": 20260301,
+            "recommendations": [
+                {"day": 20260301, "result": {
+                    "action": {"type": "payEnrollmentFee", "schoolId": 2},
+                    "reason": "期限が近いため"
+                }},
+                {"day": 20260302, "result": {"action": {"type": "doNothing"}, "reason": ""}}
+            ],
+            "upcomingAnnouncements": [
+                {"schoolId": 1, "schoolName": "東京大学", "resultDay": 20260310}
+            ],
+            "note": null
+        }))
+        .unwrap();
+
+        assert_eq!(
+            describe_action(&report.recommendations[0].result.action, &entries),
+            "入学金を支払う: 早稲田大学 ¥200,000"
+        );
+        let pdf = render_weekly(&report, &entries, 20260301);
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+}
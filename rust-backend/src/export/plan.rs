@@ -0,0 +1,390 @@
+//! Payment-plan report, for sharing the plan with family or a financial
+//! advisor.
+//!
+//! The report has four sections, each starting on a new page: a cover with
+//! the totals in yen, a payment table per school, a calendar of upcoming
+//! dates, and the advisor's recommended actions with their reasons. When
+//! the schools say how they will be paid, the cover also totals each
+//! payment method with its charges.
+
+use std::collections::BTreeMap;
+
+use super::pdf::{describe_action, paid_label, pass_status_label, Document};
+use super::tax::method_label;
+use crate::date::format_wareki;
+use crate::format::{format_day, format_yen};
+use crate::i18n::Locale;
+use crate::model::{Currency, FeeKind, GetRecommendationResult, PaymentAction, School};
+use crate::summary::{PaymentCategory, Summary};
+
+/// Amount in the school's currency, symbol first as in the other reports
+fn money(amount: u64, currency: Currency) -> String {
+    crate::format::format_money(amount, currency, Locale::En)
+}
+
+fn category_label(category: PaymentCategory) -> &'static str {
+    match category {
+        PaymentCategory::ApplicationFee => "受験料",
+        PaymentCategory::EnrollmentFee => "入学金",
+        PaymentCategory::Tuition => "授業料",
+        PaymentCategory::Recurring => "定期納付金",
+    }
+}
+
+fn cover(doc: &mut Document, schools: &[School], summary: &Summary, today: u32) {
+    const COLUMNS: [f32; 4] = [100.0, 120.0, 120.0, 120.0];
+
+    doc.heading("支払い計画レポート");
+    doc.paragraph(
+        &format!(
+            "作成日: {}（{}）　対象校: {}校",
+            format_day(today),
+            format_wareki(today),
+            schools.len()
+        ),
+        0.0,
+    );
+    doc.gap();
+    doc.paragraph(
+        &format!(
+            "支払総額: {}　うち支払済: {}　残り: {}",
+            format_yen(summary.total),
+            format_yen(summary.paid),
+            format_yen(summary.total - summary.paid)
+        ),
+        0.0,
+    );
+    doc.paragraph(
+        &format!(
+            "辞退時に返金される額（授業料）: {}　返金されない額: {}",
+            format_yen(summary.refundable),
+            format_yen(summary.non_refundable)
+        ),
+        0.0,
+    );
+    if summary.method_fees > 0 {
+        doc.paragraph(
+            &format!(
+                "支払方法の手数料: {}　手数料込みの負担額: {}",
+                format_yen(summary.method_fees),
+                format_yen(summary.total + summary.method_fees)
+            ),
+            0.0,
+        );
+    }
+    doc.paragraph("※ 不合格・取消の学校の未払い分は含みません。", 0.0);
+
+    doc.gap();
+    doc.paragraph("■ 月別", 0.0);
+    doc.row(
+        &COLUMNS,
+        &["月", "金額", "支払済", "累計"].map(String::from),
+    );
+    for month in &summary.by_month {
+        doc.row(
+            &COLUMNS,
+            &[
+                format!("{}年{}月", month.month / 100, month.month % 100),
+                format_yen(month.total),
+                format_yen(month.paid),
+                format_yen(month.cumulative),
+            ],
+        );
+    }
+
+    doc.gap();
+    doc.paragraph("■ 費目別", 0.0);
+    doc.row(&COLUMNS, &["費目", "金額", "支払済"].map(String::from));
+    for category in &summary.by_category {
+        doc.row(
+            &COLUMNS,
+            &[
+                category_label(category.category).to_string(),
+                format_yen(category.total),
+                format_yen(category.paid),
+            ],
+        );
+    }
+
+    if !summary.by_method.is_empty() {
+        doc.gap();
+        doc.paragraph("■ 支払方法別", 0.0);
+        doc.row(
+            &COLUMNS,
+            &["支払方法", "金額", "支払済", "手数料"].map(String::from),
+        );
+        for method in &summary.by_method {
+            doc.row(
+                &COLUMNS,
+                &[
+                    method_label(method.method).to_string(),
+                    format_yen(method.total),
+                    format_yen(method.paid),
+                    format_yen(method.fees),
+                ],
+            );
+        }
+    }
+
+    let unconverted: Vec<&str> = schools
+        .iter()
+        .filter(|s| summary.unconverted_school_ids.contains(&s.id))
+        .map(|s| s.name.as_str())
+        .collect();
+    if !unconverted.is_empty() {
+        doc.gap();
+        doc.paragraph(
+            &format!(
+                "※ 為替レート未設定のため合計に含まれていない学校: {}",
+                unconverted.join("、")
+            ),
+            0.0,
+        );
+    }
+}
+
+fn school_tables(doc: &mut Document, schools: &[School]) {
+    const COLUMNS: [f32; 4] = [100.0, 100.0, 140.0, 100.0];
+
+    doc.heading("学校別の支払い");
+    for school in schools {
+        doc.gap();
+        doc.paragraph(
+            &format!(
+                "{}. {}（{}・発表 {}）",
+                school.priority,
+                school.name,
+                pass_status_label(school.pass_status),
+                format_day(school.result_date)
+            ),
+            0.0,
+        );
+        doc.row(
+            &COLUMNS,
+            &["費目", "期限", "金額", "状況"].map(String::from),
+        );
+
+        let mut payments = vec![(
+            category_label(PaymentCategory::EnrollmentFee).to_string(),
+            school.enrollment_fee_deadline,
+            school.net_enrollment_fee(),
+            school.enrollment_fee_paid,
+        )];
+        let tuition = school.tuition_payments();
+        let count = tuition.len();
+        payments.extend(tuition.into_iter().enumerate().map(|(i, p)| {
+            let label = category_label(PaymentCategory::Tuition);
+            let label = if count == 1 {
+                label.to_string()
+            } else {
+                format!("{}（{}/{}回）", label, i + 1, count)
+            };
+            (label, p.due, p.amount, p.paid)
+        }));
+        if let Some(fee) = school.application_fee {
+            payments.insert(
+                0,
+                (
+                    category_label(PaymentCategory::ApplicationFee).to_string(),
+                    school.exam_date,
+                    fee,
+                    true,
+                ),
+            );
+        }
+        for (label, day, amount, paid) in payments {
+            let status = if !paid && !school.pass_status.is_open() {
+                "不要"
+            } else {
+                paid_label(paid)
+            };
+            doc.row(
+                &COLUMNS,
+                &[
+                    label,
+                    format_day(day),
+                    money(amount, school.currency),
+                    status.to_string(),
+                ],
+            );
+        }
+        for adjustment in &school.adjustments {
+            let fee = match adjustment.fee {
+                FeeKind::EnrollmentFee => PaymentCategory::EnrollmentFee,
+                FeeKind::Tuition => PaymentCategory::Tuition,
+            };
+            doc.paragraph(
+                &format!(
+                    "※ {}: {}から {} 減額",
+                    adjustment.label,
+                    category_label(fee),
+                    money(adjustment.amount, school.currency)
+                ),
+                0.0,
+            );
+        }
+    }
+}
+
+/// Dates from `today` on: exams, announcements and unpaid deadlines of
+/// schools that are still open, grouped by month
+fn calendar(doc: &mut Document, schools: &[School], today: u32) {
+    const COLUMNS: [f32; 3] = [100.0, 200.0, 300.0];
+
+    let mut months: BTreeMap<u32, Vec<(u32, &str, String)>> = BTreeMap::new();
+    for school in schools.iter().filter(|s| s.pass_status.is_open()) {
+        let mut events = vec![
+            (school.exam_date, "試験".to_string()),
+            (school.result_date, "合格発表".to_string()),
+        ];
+        if !school.enrollment_fee_paid {
+            events.push((
+                school.enrollment_fee_deadline,
+                format!(
+                    "入学金 {} 支払期限",
+                    money(school.net_enrollment_fee(), school.currency)
+                ),
+            ));
+        }
+        for payment in school.tuition_payments().into_iter().filter(|p| !p.paid) {
+            events.push((
+                payment.due,
+                format!("授業料 {} 支払期限", money(payment.amount, school.currency)),
+            ));
+        }
+        for (day, event) in events.into_iter().filter(|(day, _)| *day >= today) {
+            months
+                .entry(day / 100)
+                .or_default()
+                .push((day, &school.name, event));
+        }
+    }
+
+    doc.heading("期限カレンダー");
+    if months.is_empty() {
+        doc.paragraph("今後の予定はありません。", 0.0);
+    }
+    for (month, mut events) in months {
+        events.sort_by_key(|(day, _, _)| *day);
+        doc.gap();
+        doc.paragraph(&format!("■ {}年{}月", month / 100, month % 100), 0.0);
+        for (day, name, event) in events {
+            doc.row(&COLUMNS, &[format_day(day), name.to_string(), event]);
+        }
+    }
+}
+
+fn urgency_label(urgency: u32) -> String {
+    match urgency {
+        0 => "本日中".to_string(),
+        n => format!("あと{}日", n),
+    }
+}
+
+fn recommended_actions(doc: &mut Document, schools: &[School], advice: &GetRecommendationResult) {
+    let describe = |action: &PaymentAction| {
+        describe_action(&serde_json::to_value(action).unwrap_or_default(), schools)
+    };
+
+    doc.heading("推奨アクション");
+    doc.paragraph(
+        &format!(
+            "{}（{}）",
+            describe(&advice.action),
+            urgency_label(advice.urgency)
+        ),
+        0.0,
+    );
+    if !advice.reason.is_empty() {
+        doc.paragraph(&advice.reason, 16.0);
+    }
+
+    let others: Vec<_> = advice
+        .all_recommendations
+        .iter()
+        .filter(|r| r.action != advice.action)
+        .collect();
+    if !others.is_empty() {
+        doc.gap();
+        doc.paragraph("■ その他の検討事項", 0.0);
+        for recommendation in others {
+            doc.paragraph(
+                &format!(
+                    "{}（{}）",
+                    describe(&recommendation.action),
+                    urgency_label(recommendation.urgency)
+                ),
+                16.0,
+            );
+            if !recommendation.reason.is_empty() {
+                doc.paragraph(&recommendation.reason, 32.0);
+            }
+        }
+    }
+
+    if let Some(updates) = advice.state_updates.as_ref().filter(|u| !u.is_empty()) {
+        doc.gap();
+        doc.paragraph("■ 合否状況の更新", 0.0);
+        for update in updates {
+            doc.paragraph(
+                &format!(
+                    "{}: {} → {}　{}",
+                    update.school_name, update.old_status, update.new_status, update.reason
+                ),
+                16.0,
+            );
+        }
+    }
+}
+
+/// Render the plan for `schools`, sorted by priority
+pub(super) fn render(
+    schools: &[School],
+    summary: &Summary,
+    recommendation: &GetRecommendationResult,
+    today: u32,
+) -> Vec<u8> {
+    let mut doc = Document::new("支払い計画レポート");
+    cover(&mut doc, schools, summary, today);
+    doc.new_page();
+    school_tables(&mut doc, schools);
+    doc.new_page();
+    calendar(&mut doc, schools, today);
+    doc.new_page();
+    recommended_actions(&mut doc, schools, recommendation);
+    doc.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::ExchangeRates;
+    use crate::export::{plan_pdf, sample_data};
+
+    #[test]
+    fn test_plan_has_a_page_per_section() {
+        let advice = serde_json::json!({
+            "action": {"type": "payTuition", "schoolId": 2},
+            "reason": "授業料の期限が近いため",
+            "urgency": 3,
+            "allRecommendations": [
+                {"action": {"type": "payTuition", "schoolId": 2}, "reason": "", "urgency": 3},
+                {"action": {"type": "doNothing"}, "reason": "東京大学の発表待ち", "urgency": 9}
+            ]
+        });
+        let pdf = plan_pdf(&sample_data(), &ExchangeRates::default(), &advice, 20260301).unwrap();
+        let text = String::from_utf8(pdf).unwrap();
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert_eq!(text.matches("/Type /Page ").count(), 4);
+    }
+
+    #[test]
+    fn test_calendar_skips_past_and_closed_dates() {
+        let mut doc = Document::new("test");
+        let schools = crate::export::schedule_entries(&sample_data()).unwrap();
+        calendar(&mut doc, &schools, 20270101);
+        // Only the heading and the empty-calendar note
+        let text = String::from_utf8(doc.finish()).unwrap();
+        assert_eq!(text.matches(" Tj ").count(), 2);
+    }
+}
@@ -0,0 +1,154 @@
+//! Year-end report of school payments, for tax filing and applications to
+//! education-support programs.
+//!
+//! A single section: totals by kind of fee, then every payment with how it
+//! was made and its receipt number.
+
+use super::pdf::Document;
+use crate::format::{format_day, format_money, format_yen};
+use crate::i18n::Locale;
+use crate::model::{FeeKind, PaymentMethod};
+use crate::tax_report::{TaxReport, TaxReportLine};
+
+fn fee_label(fee: FeeKind) -> &'static str {
+    match fee {
+        FeeKind::EnrollmentFee => "入学金",
+        FeeKind::Tuition => "授業料",
+    }
+}
+
+pub(super) fn method_label(method: PaymentMethod) -> &'static str {
+    match method {
+        PaymentMethod::BankTransfer => "銀行振込",
+        PaymentMethod::CreditCard => "クレジットカード",
+        PaymentMethod::ConvenienceStore => "コンビニ払い",
+        PaymentMethod::PayEasy => "ペイジー",
+        PaymentMethod::Other => "その他",
+    }
+}
+
+fn payment_row(line: &TaxReportLine) -> [String; 7] {
+    let p = &line.payment;
+    let fee = match p.installment {
+        Some(i) => format!("{}（{}回目）", fee_label(p.fee), i),
+        None => fee_label(p.fee).to_string(),
+    };
+    let school = match &line.child_name {
+        Some(child) => format!("{}（{}）", p.school_name, child),
+        None => p.school_name.clone(),
+    };
+    let receipt = p.receipt.as_ref();
+    [
+        format_day(line.paid_on),
+        school,
+        fee,
+        format_money(p.amount, p.currency, Locale::En),
+        line.amount_yen
+            .map(format_yen)
+            .unwrap_or_else(|| "—".to_string()),
+        receipt
+            .map_or("領収記録なし", |r| method_label(r.method))
+            .to_string(),
+        receipt
+            .and_then(|r| r.reference.clone())
+            .unwrap_or_default(),
+    ]
+}
+
+/// Render `report` as a PDF
+pub(super) fn render(report: &TaxReport) -> Vec<u8> {
+    const TOTAL_COLUMNS: [f32; 3] = [100.0, 60.0, 120.0];
+    const COLUMNS: [f32; 7] = [80.0, 180.0, 100.0, 90.0, 90.0, 100.0, 120.0];
+
+    let title = format!("{}年 教育費支払いレポート", report.year);
+    let mut doc = Document::new(&title);
+    doc.heading(&title);
+    doc.paragraph(
+        &format!(
+            "{}年1月1日から12月31日までに支払った入学金・授業料　合計: {}",
+            report.year,
+            format_yen(report.total)
+        ),
+        0.0,
+    );
+    doc.gap();
+    doc.row(&TOTAL_COLUMNS, &["費目", "件数", "金額"].map(String::from));
+    for total in &report.by_fee {
+        doc.row(
+            &TOTAL_COLUMNS,
+            &[
+                fee_label(total.fee).to_string(),
+                format!("{}件", total.count),
+                format_yen(total.total),
+            ],
+        );
+    }
+
+    doc.gap();
+    doc.row(
+        &COLUMNS,
+        &[
+            "支払日",
+            "学校",
+            "費目",
+            "金額",
+            "円換算",
+            "支払方法",
+            "控え番号",
+        ]
+        .map(String::from),
+    );
+    for line in &report.lines {
+        doc.row(&COLUMNS, &payment_row(line));
+    }
+    if report.lines.is_empty() {
+        doc.paragraph("この年に支払った記録はありません。", 0.0);
+    }
+
+    doc.gap();
+    if report.without_receipt > 0 {
+        doc.paragraph(
+            &format!(
+                "※ 領収記録のない{}件は支払期限の日付で集計しています。",
+                report.without_receipt
+            ),
+            0.0,
+        );
+    }
+    if !report.unconverted_school_ids.is_empty() {
+        doc.paragraph(
+            "※ 為替レートが未設定の外貨建ての支払いは合計に含まれていません。",
+            0.0,
+        );
+    }
+    doc.paragraph(
+        "※ 受験料は支払い状況を記録していないため含まれていません。申告や申請には学校発行の領収書を添付してください。",
+        0.0,
+    );
+    doc.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::currency::ExchangeRates;
+    use crate::export::{sample_data, tax_report, ExportFormat};
+    use crate::model::Dataset;
+
+    #[test]
+    fn test_render_tax_report() {
+        let dataset = Dataset::from_value(sample_data()).unwrap();
+        let report = crate::tax_report::tax_report(&dataset, 2026, &ExchangeRates::default());
+
+        let pdf = tax_report(&report, ExportFormat::Pdf).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+
+        let csv = String::from_utf8(tax_report(&report, ExportFormat::Csv).unwrap()).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            "20260306,2,早稲田大学,,enrollmentFee,,200000,JPY,200000,,"
+        );
+        assert!(tax_report(&report, ExportFormat::Ics).is_err());
+    }
+}
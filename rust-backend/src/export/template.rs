@@ -0,0 +1,221 @@
+//! Exports rendered with templates written by the user.
+//!
+//! Handlebars templates put in [`TEMPLATES_DIR`] in the data directory
+//! render the dataset, and optionally the advisor's result for a report, to
+//! any text format. A template's file name is the exported file's name with
+//! `.hbs` added, so `schools.md.hbs` exports `schools.md`.
+//!
+//! Values are written as they are, without HTML escaping; the `csv` and
+//! `tex` helpers escape them for those formats, and `day` and `yen` format
+//! days and amounts as the built-in exports do.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use handlebars::{handlebars_helper, no_escape, Handlebars};
+use serde::Serialize;
+use ts_rs::TS;
+
+use super::{csv, schedule_entries, ExportError};
+use crate::format::{format_day, format_yen};
+
+/// Directory in the data directory holding export templates
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Suffix of template files
+const TEMPLATE_SUFFIX: &str = ".hbs";
+
+/// An export template found in [`TEMPLATES_DIR`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ExportTemplate {
+    /// Name of the exported file, such as `schools.md`
+    pub name: String,
+    /// Extension of the exported file, such as `md`; empty if it has none
+    pub extension: String,
+}
+
+impl ExportTemplate {
+    /// The template stored as `file_name`, if it is a template file
+    fn from_file_name(file_name: &str) -> Option<Self> {
+        let name = file_name.strip_suffix(TEMPLATE_SUFFIX)?;
+        if name.is_empty() || name.starts_with('.') {
+            return None;
+        }
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_string())
+            .unwrap_or_default();
+        Some(Self {
+            name: name.to_string(),
+            extension,
+        })
+    }
+
+    /// MIME type of the exported file, from its extension
+    pub fn content_type(&self) -> &'static str {
+        match self.extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => "text/markdown; charset=utf-8",
+            "csv" => "text/csv; charset=utf-8",
+            "tex" => "application/x-tex",
+            "html" | "htm" => "text/html; charset=utf-8",
+            "json" => "application/json",
+            _ => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// A dataset rendered with an export template
+#[derive(Debug, Clone)]
+pub struct TemplateExport {
+    pub template: ExportTemplate,
+    pub text: String,
+}
+
+/// Templates in `dir`, by name; none if it does not exist
+pub fn list(dir: &Path) -> io::Result<Vec<ExportTemplate>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut templates: Vec<ExportTemplate> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|entry| ExportTemplate::from_file_name(&entry.file_name().to_string_lossy()))
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+handlebars_helper!(day_helper: |day: u64| format_day(day as u32));
+handlebars_helper!(yen_helper: |amount: u64| format_yen(amount));
+handlebars_helper!(csv_helper: |value: Json| csv::escape(&plain_text(value)));
+handlebars_helper!(tex_helper: |value: Json| escape_tex(&plain_text(value)));
+
+/// A string as it is, and any other value as JSON
+fn plain_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Escape the characters LaTeX treats specially
+fn escape_tex(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render `template` with `data` as `data`, its schools as `schools`,
+/// `today` and, for a report, the advisor's result as `report`
+pub fn render(
+    template: &str,
+    data: &serde_json::Value,
+    report: Option<&serde_json::Value>,
+    today: u32,
+) -> Result<String, ExportError> {
+    let context = serde_json::json!({
+        "data": data,
+        "schools": schedule_entries(data)?,
+        "today": today,
+        "report": report,
+    });
+    let mut registry = Handlebars::new();
+    registry.register_escape_fn(no_escape);
+    registry.register_helper("day", Box::new(day_helper));
+    registry.register_helper("yen", Box::new(yen_helper));
+    registry.register_helper("csv", Box::new(csv_helper));
+    registry.register_helper("tex", Box::new(tex_helper));
+    registry
+        .render_template(template, &context)
+        .map_err(|e| ExportError::Template(e.to_string()))
+}
+
+/// Render `data` with the template called `name` in `dir`
+pub fn render_named(
+    dir: &Path,
+    name: &str,
+    data: &serde_json::Value,
+    report: Option<&serde_json::Value>,
+    today: u32,
+) -> Result<TemplateExport, ExportError> {
+    // Looked up among the listed templates, so `name` cannot leave `dir`
+    let template = list(dir)?
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| ExportError::UnknownTemplate(name.to_string()))?;
+    let source = fs::read_to_string(dir.join(format!("{}{}", name, TEMPLATE_SUFFIX)))?;
+    let text = render(&source, data, report, today)?;
+    Ok(TemplateExport { template, text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_data;
+
+    #[test]
+    fn test_templates_are_listed_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list(&dir.path().join("missing")).unwrap().is_empty());
+        for file in ["schools.md.hbs", "notes.txt", ".hidden.hbs", "plain.hbs"] {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+        let templates = list(dir.path()).unwrap();
+        let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["plain", "schools.md"]);
+        assert_eq!(templates[1].extension, "md");
+        assert_eq!(templates[1].content_type(), "text/markdown; charset=utf-8");
+        assert_eq!(templates[0].content_type(), "text/plain; charset=utf-8");
+    }
+
+    #[test]
+    fn test_render_with_helpers() {
+        let dir = tempfile::tempdir().unwrap();
+        let template =
+            "{{#each schools}}| {{name}} | {{day tuitionDeadline}} | {{yen tuition}} |\n{{/each}}";
+        fs::write(dir.path().join("schools.md.hbs"), template).unwrap();
+        let data = sample_data();
+
+        let export = render_named(dir.path(), "schools.md", &data, None, 20260301).unwrap();
+        assert_eq!(
+            export.text,
+            "| 東京大学 | 2026/03/31 | ¥535,800 |\n| 早稲田大学 | 2026/03/20 | ¥800,000 |\n"
+        );
+
+        let report = serde_json::json!({ "note": "A & B" });
+        let text = render(
+            "{{csv report.note}},{{tex report.note}},{{today}}",
+            &data,
+            Some(&report),
+            20260301,
+        )
+        .unwrap();
+        assert_eq!(text, "A & B,A \\& B,20260301");
+        assert_eq!(escape_tex("50%_#"), "50\\%\\_\\#");
+        assert_eq!(csv::escape("a,b"), "\"a,b\"");
+
+        assert!(matches!(
+            render_named(dir.path(), "../schools.md", &data, None, 20260301),
+            Err(ExportError::UnknownTemplate(_))
+        ));
+        assert!(matches!(
+            render("{{#each schools}}", &data, None, 20260301),
+            Err(ExportError::Template(_))
+        ));
+    }
+}
@@ -0,0 +1,136 @@
+//! Plain-text and Markdown summaries of upcoming payments for pasting into
+//! chat messages and emails.
+
+use crate::date::{format_day_with_weekday, parse_day, to_day};
+use crate::format::{format_day, format_yen};
+use crate::reminders::UpcomingDeadline;
+
+/// Layout of a text summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    /// Bulleted lines, readable in any messenger
+    Plain,
+    /// A Markdown table
+    Markdown,
+}
+
+impl TextFormat {
+    /// Look up a format by name (`text`/`plain` or `markdown`/`md`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" | "plain" => Some(Self::Plain),
+            "markdown" | "md" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+fn remaining(days_left: i64) -> String {
+    if days_left == 0 {
+        "本日期限".to_string()
+    } else {
+        format!("あと{}日", days_left)
+    }
+}
+
+/// Render deadlines from `today` through the last day of the window
+pub(super) fn render(
+    deadlines: &[UpcomingDeadline],
+    today: u32,
+    days: i64,
+    format: TextFormat,
+) -> String {
+    let last_day = parse_day(today)
+        .map(|date| to_day(date + chrono::Duration::days(days - 1)))
+        .unwrap_or(today);
+    let title = format!(
+        "支払い予定（{}〜{}）",
+        format_day(today),
+        format_day(last_day)
+    );
+    if deadlines.is_empty() {
+        return format!("{}\n支払い予定はありません\n", title);
+    }
+    let total: u64 = deadlines.iter().map(|d| d.amount).sum();
+
+    let mut out = String::new();
+    match format {
+        TextFormat::Plain => {
+            out.push_str(&format!("{}\n", title));
+            for d in deadlines {
+                out.push_str(&format!(
+                    "・{}（{}） {} {} {}\n",
+                    format_day_with_weekday(d.deadline),
+                    remaining(d.days_left),
+                    d.school_name,
+                    d.fee_name(),
+                    format_yen(d.amount)
+                ));
+            }
+            out.push_str(&format!("合計 {}\n", format_yen(total)));
+        }
+        TextFormat::Markdown => {
+            out.push_str(&format!("### {}\n\n", title));
+            out.push_str("| 期限 | 学校 | 種別 | 金額 |\n");
+            out.push_str("| --- | --- | --- | ---: |\n");
+            for d in deadlines {
+                out.push_str(&format!(
+                    "| {}（{}） | {} | {} | {} |\n",
+                    format_day_with_weekday(d.deadline),
+                    remaining(d.days_left),
+                    d.school_name.replace('|', "\\|"),
+                    d.fee_name().replace('|', "\\|"),
+                    format_yen(d.amount)
+                ));
+            }
+            out.push_str(&format!("| **合計** | | | **{}** |\n", format_yen(total)));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_data;
+    use crate::reminders::upcoming_deadlines;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(TextFormat::from_name("MD"), Some(TextFormat::Markdown));
+        assert_eq!(TextFormat::from_name("text"), Some(TextFormat::Plain));
+        assert_eq!(TextFormat::from_name("html"), None);
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let deadlines = upcoming_deadlines(&sample_data(), 20260315, 6);
+        let text = render(&deadlines, 20260315, 7, TextFormat::Plain);
+        assert_eq!(
+            text,
+            "支払い予定（2026/03/15〜2026/03/21）\n\
+             ・2026/03/17(火)（あと2日） 東京大学 入学金 ¥282,000\n\
+             ・2026/03/20(金)（あと5日） 早稲田大学 授業料 ¥800,000\n\
+             合計 ¥1,082,000\n"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let deadlines = upcoming_deadlines(&sample_data(), 20260315, 6);
+        let text = render(&deadlines, 20260315, 7, TextFormat::Markdown);
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "### 支払い予定（2026/03/15〜2026/03/21）");
+        assert_eq!(
+            lines[4],
+            "| 2026/03/17(火)（あと2日） | 東京大学 | 入学金 | ¥282,000 |"
+        );
+        assert_eq!(lines.last(), Some(&"| **合計** | | | **¥1,082,000** |"));
+    }
+
+    #[test]
+    fn test_render_empty() {
+        let text = render(&[], 20260101, 7, TextFormat::Markdown);
+        assert!(text.ends_with("支払い予定はありません\n"));
+    }
+}
@@ -0,0 +1,51 @@
+//! Excel workbook writer: a single sheet mirroring the CSV layout.
+
+use rust_xlsxwriter::{Format, Workbook};
+
+use super::csv::{json_cell, COLUMNS};
+use super::ExportError;
+use crate::model::{Currency, School};
+
+/// Render entries as an XLSX workbook
+pub(super) fn render(entries: &[School]) -> Result<Vec<u8>, ExportError> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Schedule")?;
+
+    let header = Format::new().set_bold();
+    let yen = Format::new().set_num_format("¥#,##0");
+    let minor_units = Format::new().set_num_format("#,##0");
+    for (col, name) in COLUMNS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, &header)?;
+    }
+
+    for (i, e) in entries.iter().enumerate() {
+        let row = i as u32 + 1;
+        let amount = if e.currency == Currency::Jpy {
+            &yen
+        } else {
+            &minor_units
+        };
+        sheet.write_number(row, 0, e.id as f64)?;
+        sheet.write_string(row, 1, &e.name)?;
+        sheet.write_number(row, 2, e.priority as f64)?;
+        sheet.write_string(row, 3, e.pass_status.as_str())?;
+        sheet.write_number(row, 4, e.exam_date as f64)?;
+        sheet.write_number(row, 5, e.result_date as f64)?;
+        sheet.write_number(row, 6, e.enrollment_fee_deadline as f64)?;
+        sheet.write_number_with_format(row, 7, e.enrollment_fee as f64, amount)?;
+        sheet.write_boolean(row, 8, e.enrollment_fee_paid)?;
+        sheet.write_number(row, 9, e.tuition_deadline as f64)?;
+        sheet.write_number_with_format(row, 10, e.tuition as f64, amount)?;
+        sheet.write_string(row, 12, e.currency.code())?;
+        sheet.write_boolean(row, 11, e.tuition_paid)?;
+        if let Some(child_id) = e.child_id {
+            sheet.write_number(row, 13, child_id as f64)?;
+        }
+        sheet.write_string(row, 14, json_cell(&e.installments))?;
+        sheet.write_string(row, 15, json_cell(&e.adjustments))?;
+    }
+    sheet.set_column_width(1, 24)?;
+
+    Ok(workbook.save_to_buffer()?)
+}
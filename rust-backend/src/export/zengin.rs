@@ -0,0 +1,244 @@
+//! Zengin-format (全銀フォーマット) bulk transfer files for online banking.
+//!
+//! A file holds a header record with the paying account and transfer date,
+//! one data record per transfer, a trailer with the count and total, and an
+//! end record. In the fixed layout every record is 120 bytes of Shift_JIS
+//! followed by CRLF; the CSV layout has the same fields separated by commas,
+//! with text left unpadded. Text is limited to what
+//! [`to_bank_kana`](crate::format::to_bank_kana) produces, which is all
+//! single-byte in Shift_JIS.
+
+use crate::format::to_bank_kana;
+use crate::model::BankAccount;
+use crate::settings::BankTransferSettings;
+
+/// Record layout of a transfer file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZenginLayout {
+    /// 120-byte fixed-width records
+    #[default]
+    Fixed,
+    /// Comma-separated records
+    Csv,
+}
+
+impl ZenginLayout {
+    /// Look up a layout by name (`fixed`/`txt` or `csv`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "fixed" | "txt" => Some(Self::Fixed),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Fixed => "txt",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// One transfer to a payee
+pub(super) struct Transfer<'a> {
+    pub payee: &'a BankAccount,
+    pub amount: u64,
+    /// Shown on the bank's statement to match the transfer to a school
+    pub customer_code: String,
+}
+
+/// A field of a record with its width in bytes
+enum Field {
+    /// Digits, zero-padded on the left
+    Number(String, usize),
+    /// An amount, zero-padded on the left in the fixed layout only
+    Amount(u64, usize),
+    /// Bank kana, space-padded on the right and cut to fit
+    Text(String, usize),
+    /// Unused space
+    Blank(usize),
+}
+
+fn render_record(fields: &[Field], layout: ZenginLayout) -> String {
+    let cells: Vec<String> = fields
+        .iter()
+        .map(|field| match (field, layout) {
+            (Field::Number(digits, width), _) => format!("{:0>width$}", digits, width = width),
+            (Field::Amount(amount, width), ZenginLayout::Fixed) => {
+                format!("{:0width$}", amount, width = width)
+            }
+            (Field::Amount(amount, _), ZenginLayout::Csv) => amount.to_string(),
+            (Field::Text(text, width), ZenginLayout::Fixed) => {
+                let text: String = text.chars().take(*width).collect();
+                format!("{:<width$}", text, width = width)
+            }
+            (Field::Text(text, width), ZenginLayout::Csv) => text
+                .chars()
+                .take(*width)
+                .collect::<String>()
+                .trim_end()
+                .to_string(),
+            (Field::Blank(width), ZenginLayout::Fixed) => " ".repeat(*width),
+            (Field::Blank(_), ZenginLayout::Csv) => String::new(),
+        })
+        .collect();
+    match layout {
+        ZenginLayout::Fixed => cells.concat(),
+        ZenginLayout::Csv => cells.join(","),
+    }
+}
+
+/// Bank form of a name; accounts are checked with
+/// [`BankAccount::invalid_field`] before rendering
+fn kana(text: &str) -> String {
+    to_bank_kana(text).unwrap_or_default()
+}
+
+/// Encode bank kana text as Shift_JIS
+fn shift_jis(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            // Half-width katakana occupy 0xA1-0xDF
+            '\u{FF61}'..='\u{FF9F}' => (u32::from(c) - 0xFF61 + 0xA1) as u8,
+            _ => c as u8,
+        })
+        .collect()
+}
+
+/// Render a general transfer (総合振込) file dated `transfer_day`
+pub(super) fn render(
+    requester: &BankTransferSettings,
+    transfer_day: u32,
+    transfers: &[Transfer],
+    layout: ZenginLayout,
+) -> Vec<u8> {
+    let from = &requester.account;
+    let mut records = vec![vec![
+        Field::Number("1".to_string(), 1),
+        // 21: general transfer, 0: Shift_JIS
+        Field::Number("21".to_string(), 2),
+        Field::Number("0".to_string(), 1),
+        Field::Number(requester.requester_code.clone(), 10),
+        Field::Text(kana(&from.holder_name), 40),
+        Field::Number((transfer_day % 10000).to_string(), 4),
+        Field::Number(from.bank_code.clone(), 4),
+        Field::Text(kana(&from.bank_name), 15),
+        Field::Number(from.branch_code.clone(), 3),
+        Field::Text(kana(&from.branch_name), 15),
+        Field::Number(from.account_type.zengin_code().to_string(), 1),
+        Field::Number(from.account_number.clone(), 7),
+        Field::Blank(17),
+    ]];
+
+    for transfer in transfers {
+        let to = transfer.payee;
+        records.push(vec![
+            Field::Number("2".to_string(), 1),
+            Field::Number(to.bank_code.clone(), 4),
+            Field::Text(kana(&to.bank_name), 15),
+            Field::Number(to.branch_code.clone(), 3),
+            Field::Text(kana(&to.branch_name), 15),
+            // Clearing house number, unused for electronic transfers
+            Field::Blank(4),
+            Field::Number(to.account_type.zengin_code().to_string(), 1),
+            Field::Number(to.account_number.clone(), 7),
+            Field::Text(kana(&to.holder_name), 30),
+            Field::Amount(transfer.amount, 10),
+            // 0: not a new payee
+            Field::Number("0".to_string(), 1),
+            Field::Text(transfer.customer_code.clone(), 10),
+            Field::Blank(10),
+            Field::Blank(1),
+            Field::Blank(1),
+            Field::Blank(7),
+        ]);
+    }
+
+    records.push(vec![
+        Field::Number("8".to_string(), 1),
+        Field::Number(transfers.len().to_string(), 6),
+        Field::Amount(transfers.iter().map(|t| t.amount).sum(), 12),
+        Field::Blank(101),
+    ]);
+    records.push(vec![Field::Number("9".to_string(), 1), Field::Blank(119)]);
+
+    let mut out = Vec::new();
+    for fields in records {
+        out.extend(shift_jis(&render_record(&fields, layout)));
+        out.extend(b"\r\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::AccountType;
+
+    fn account(holder_name: &str) -> BankAccount {
+        BankAccount {
+            bank_code: "0005".to_string(),
+            bank_name: "ミツビシユーエフジエイ".to_string(),
+            branch_code: "001".to_string(),
+            branch_name: "ホンテン".to_string(),
+            account_type: AccountType::Ordinary,
+            account_number: "12345".to_string(),
+            holder_name: holder_name.to_string(),
+        }
+    }
+
+    fn requester() -> BankTransferSettings {
+        BankTransferSettings {
+            requester_code: "0000012345".to_string(),
+            account: account("ヤマダ タロウ"),
+        }
+    }
+
+    #[test]
+    fn test_fixed_records_are_120_bytes() {
+        let payee = account("ガク)ワセダダイガク");
+        let transfers = [Transfer {
+            payee: &payee,
+            amount: 800000,
+            customer_code: "2".to_string(),
+        }];
+        let file = render(&requester(), 20260306, &transfers, ZenginLayout::Fixed);
+
+        let records: Vec<&[u8]> = file
+            .split(|&b| b == b'\n')
+            .filter(|r| !r.is_empty())
+            .collect();
+        assert_eq!(records.len(), 4);
+        for record in &records {
+            assert_eq!(record.len(), 121, "120 bytes plus CR");
+        }
+        assert!(records[0].starts_with(b"12100000012345\xD4\xCF\xC0\xDE \xC0\xDB\xB3"));
+        assert_eq!(&records[0][54..58], b"0306");
+        // Account number, holder and amount of the data record
+        assert_eq!(&records[1][43..50], b"0012345");
+        assert_eq!(&records[1][80..90], b"0000800000");
+        assert_eq!(&records[2][..19], b"8000001000000800000");
+    }
+
+    #[test]
+    fn test_csv_layout() {
+        let payee = account("ワセダダイガク");
+        let transfers = [Transfer {
+            payee: &payee,
+            amount: 200000,
+            customer_code: "2".to_string(),
+        }];
+        let file = render(&requester(), 20260306, &transfers, ZenginLayout::Csv);
+        let lines: Vec<&[u8]> = file.split(|&b| b == b'\n').collect();
+        assert!(lines[2].starts_with(b"8,000001,200000,"));
+        assert!(lines[3].starts_with(b"9,"));
+    }
+
+    #[test]
+    fn test_layout_names() {
+        assert_eq!(ZenginLayout::from_name("CSV"), Some(ZenginLayout::Csv));
+        assert_eq!(ZenginLayout::from_name("txt"), Some(ZenginLayout::Fixed));
+        assert_eq!(ZenginLayout::from_name("xml"), None);
+    }
+}
@@ -0,0 +1,851 @@
+//! Encryption of selected fields of the stored JSON.
+//!
+//! The data directory can be kept in a cloud-synced folder. With field
+//! encryption turned on, the values of the chosen [`SensitiveField`]s (bank
+//! account numbers, memos, amounts) are replaced by sealed strings in the
+//! data files, settings and backups, while keys, dates and statuses stay
+//! readable. The sync provider sees which schools are planned but not the
+//! account numbers or how much is paid.
+//!
+//! Values are sealed like a device transfer bundle (see
+//! [`transfer::seal`]), each with its own nonce, and written as
+//! [`ENCRYPTED_PREFIX`] followed by hex. The key never enters the data
+//! directory: the desktop app keeps it in the OS keychain, and it is moved
+//! to another device as a [`FieldKey::code`].
+//!
+//! Like the holiday calendar, the cipher in use is installed once for the
+//! process; [`Storage`] seals values on save and opens them on load.
+//!
+//! At launch the desktop app reads the key from the keychain
+//! ([`unlock_with_keychain`]), so encrypted data opens without typing
+//! anything. With [`require_password_on_launch`] on, the key is taken out
+//! of the keychain and kept only sealed with a password in
+//! [`PASSWORD_KEY_FILE`]; the data stays locked until
+//! [`unlock_with_password`].
+//!
+//! [`require_password_on_launch`]: FieldEncryptionSettings::require_password_on_launch
+
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use ring::pbkdf2;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::audit::AUDIT_LOG_FILE;
+use crate::backup;
+use crate::history::HISTORY_INPUTS_FILE;
+use crate::journal;
+use crate::migrations;
+use crate::result_cache::RESULT_CACHE_FILE;
+use crate::settings::SETTINGS_FILE;
+use crate::share::SHARES_FILE;
+use crate::storage::{Storage, StorageError, DATASETS_DIR, SCHOOLS_DATA_FILE};
+use crate::token::random_token;
+use crate::transfer;
+
+/// File in the data directory listing the encrypted fields
+pub const FIELD_ENCRYPTION_FILE: &str = "field_encryption.json";
+
+/// File in the data directory holding the key sealed with the launch
+/// password
+pub const PASSWORD_KEY_FILE: &str = "field_key.json";
+
+/// Start of an encrypted value in the stored JSON
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Shortest launch password accepted
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// PBKDF2-HMAC-SHA256 rounds deriving the key that seals the field key;
+/// the count is saved with the sealed key, so tests can use fewer
+const PASSWORD_ITERATIONS: u32 = if cfg!(test) { 1_000 } else { 310_000 };
+
+const KEY_LEN: usize = transfer::KEY_LEN;
+
+/// Errors that can occur while encrypting or decrypting fields
+#[derive(Debug, Error)]
+pub enum FieldCryptoError {
+    #[error("The data has encrypted values but the encryption key is not set up")]
+    Locked,
+
+    #[error("Encrypted values could not be decrypted with this key")]
+    WrongKey,
+
+    #[error("Invalid encryption key code")]
+    InvalidKey,
+
+    #[error("The password is not correct")]
+    WrongPassword,
+
+    #[error("The password must be at least {MIN_PASSWORD_LEN} characters")]
+    WeakPassword,
+
+    #[error("Keychain error: {0}")]
+    Keychain(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Kind of value that can be encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SensitiveField {
+    /// Account numbers and holder names
+    BankAccount,
+    /// Transfer numbers, attachments and notes
+    Memo,
+    /// Fees, installments, adjustments and budgets
+    Amount,
+}
+
+impl SensitiveField {
+    /// Keys of the stored JSON holding this kind of value
+    pub fn keys(self) -> &'static [&'static str] {
+        match self {
+            Self::BankAccount => &["accountNumber", "holderName", "requesterCode"],
+            Self::Memo => &["reference", "attachment", "note", "memo", "text"],
+            Self::Amount => &[
+                "enrollmentFee",
+                "tuition",
+                "applicationFee",
+                "amount",
+                "total",
+                "enrollmentFees",
+                "applicationFees",
+            ],
+        }
+    }
+}
+
+/// Secret key of the encrypted fields
+#[derive(Clone, PartialEq, Eq)]
+pub struct FieldKey([u8; KEY_LEN]);
+
+impl FieldKey {
+    /// A new random key
+    pub fn generate() -> Self {
+        Self::from_code(&random_token(KEY_LEN)).expect("random_token returns hex")
+    }
+
+    /// Read a key from its [`code`](Self::code)
+    pub fn from_code(code: &str) -> Result<Self, FieldCryptoError> {
+        let bytes = hex::decode(code.trim()).map_err(|_| FieldCryptoError::InvalidKey)?;
+        bytes
+            .try_into()
+            .map(Self)
+            .map_err(|_| FieldCryptoError::InvalidKey)
+    }
+
+    /// The key as text, to keep in a keychain or enter on another device
+    pub fn code(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for FieldKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FieldKey(..)")
+    }
+}
+
+/// Where the key is kept, outside the data directory
+pub trait KeyStore: Send + Sync {
+    fn load(&self) -> Result<Option<FieldKey>, FieldCryptoError>;
+    fn save(&self, key: &FieldKey) -> Result<(), FieldCryptoError>;
+    fn delete(&self) -> Result<(), FieldCryptoError>;
+}
+
+/// Key kept in a file, for the web server
+pub struct FileKeyStore {
+    path: PathBuf,
+}
+
+impl FileKeyStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn load(&self) -> Result<Option<FieldKey>, FieldCryptoError> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(code) => FieldKey::from_code(&code).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, key: &FieldKey) -> Result<(), FieldCryptoError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // Readable by the owner only
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path)?;
+        Ok(std::io::Write::write_all(&mut file, key.code().as_bytes())?)
+    }
+
+    fn delete(&self) -> Result<(), FieldCryptoError> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Fields to encrypt, as saved in [`FIELD_ENCRYPTION_FILE`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FieldEncryptionSettings {
+    pub fields: Vec<SensitiveField>,
+    /// Keep the key only sealed with a password, asked for on each launch,
+    /// instead of in the keychain
+    pub require_password_on_launch: bool,
+}
+
+impl FieldEncryptionSettings {
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(FIELD_ENCRYPTION_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(FIELD_ENCRYPTION_FILE, &serde_json::to_value(self)?)
+    }
+}
+
+/// Whether field encryption is on, for the settings screen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct FieldEncryptionStatus {
+    /// Fields encrypted on save
+    pub fields: Vec<SensitiveField>,
+    /// Whether the key is set up on this device
+    pub unlocked: bool,
+    /// Whether the password is asked for on launch
+    pub require_password_on_launch: bool,
+}
+
+/// The field key sealed with a password, as saved in [`PASSWORD_KEY_FILE`].
+///
+/// The sealed key is useless without the password, so unlike the key
+/// itself it can be kept in the data directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordSealedKey {
+    salt: String,
+    iterations: u32,
+    sealed: String,
+}
+
+impl PasswordSealedKey {
+    /// Seal `key` with `password`
+    pub fn seal(key: &FieldKey, password: &str) -> Result<Self, FieldCryptoError> {
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(FieldCryptoError::WeakPassword);
+        }
+        let salt = random_token(16);
+        let wrapping = Self::derive(password, &salt, PASSWORD_ITERATIONS);
+        Ok(Self {
+            sealed: hex::encode(transfer::seal(&wrapping, key.bytes())),
+            salt,
+            iterations: PASSWORD_ITERATIONS,
+        })
+    }
+
+    /// The key, if `password` is the one it was sealed with
+    pub fn open(&self, password: &str) -> Result<FieldKey, FieldCryptoError> {
+        let wrapping = Self::derive(password, &self.salt, self.iterations);
+        let sealed = hex::decode(&self.sealed).map_err(|_| FieldCryptoError::WrongPassword)?;
+        let bytes =
+            transfer::open(&wrapping, &sealed).map_err(|_| FieldCryptoError::WrongPassword)?;
+        bytes
+            .try_into()
+            .map(FieldKey)
+            .map_err(|_| FieldCryptoError::WrongPassword)
+    }
+
+    fn derive(password: &str, salt: &str, iterations: u32) -> [u8; KEY_LEN] {
+        let mut key = [0u8; KEY_LEN];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(iterations).unwrap_or(NonZeroU32::MIN),
+            salt.as_bytes(),
+            password.as_bytes(),
+            &mut key,
+        );
+        key
+    }
+
+    pub fn load(storage: &Storage) -> Result<Option<Self>, StorageError> {
+        match storage.load(PASSWORD_KEY_FILE)? {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(PASSWORD_KEY_FILE, &serde_json::to_value(self)?)
+    }
+}
+
+/// Key and fields in use
+#[derive(Debug, Clone)]
+pub struct FieldCipher {
+    key: FieldKey,
+    fields: Vec<SensitiveField>,
+}
+
+impl FieldCipher {
+    pub fn new(key: FieldKey, fields: Vec<SensitiveField>) -> Self {
+        Self { key, fields }
+    }
+
+    fn is_sensitive(&self, key: &str) -> bool {
+        self.fields.iter().any(|f| f.keys().contains(&key))
+    }
+
+    fn seal(&self, value: &serde_json::Value) -> serde_json::Value {
+        let plaintext = value.to_string();
+        let sealed = transfer::seal(&self.key.0, plaintext.as_bytes());
+        format!("{}{}", ENCRYPTED_PREFIX, hex::encode(sealed)).into()
+    }
+
+    fn open(&self, sealed: &str) -> Result<serde_json::Value, FieldCryptoError> {
+        let bytes = hex::decode(sealed).map_err(|_| FieldCryptoError::WrongKey)?;
+        let plaintext =
+            transfer::open(&self.key.0, &bytes).map_err(|_| FieldCryptoError::WrongKey)?;
+        serde_json::from_slice(&plaintext).map_err(|_| FieldCryptoError::WrongKey)
+    }
+
+    /// Seal the values of the encrypted fields anywhere in `value`
+    pub fn encrypt(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if self.is_sensitive(key) && !field.is_null() && !is_encrypted(field) {
+                        *field = self.seal(field);
+                    } else {
+                        self.encrypt(field);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.encrypt(v)),
+            _ => {}
+        }
+    }
+
+    /// Open every sealed value in `value`, whichever field it is in
+    pub fn decrypt(&self, value: &mut serde_json::Value) -> Result<(), FieldCryptoError> {
+        match value {
+            serde_json::Value::String(s) => {
+                if let Some(sealed) = s.strip_prefix(ENCRYPTED_PREFIX) {
+                    *value = self.open(sealed)?;
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for field in map.values_mut() {
+                    self.decrypt(field)?;
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    self.decrypt(item)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn is_encrypted(value: &serde_json::Value) -> bool {
+    value
+        .as_str()
+        .is_some_and(|s| s.starts_with(ENCRYPTED_PREFIX))
+}
+
+/// Whether `value` holds any sealed value
+pub fn has_encrypted(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Object(map) => map.values().any(has_encrypted),
+        serde_json::Value::Array(items) => items.iter().any(has_encrypted),
+        other => is_encrypted(other),
+    }
+}
+
+static CURRENT: RwLock<Option<Arc<FieldCipher>>> = RwLock::new(None);
+
+/// Cipher in use, if the key is set up
+pub fn current() -> Option<Arc<FieldCipher>> {
+    CURRENT.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Use `cipher` from now on; `None` leaves sealed values unreadable
+pub fn install(cipher: Option<FieldCipher>) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = cipher.map(Arc::new);
+}
+
+/// Whether values are sealed when `file_name` is saved: the datasets and
+/// the copies kept before migrating them, the settings and the audit log.
+/// Backups and journal versions are sealed when they are written, and
+/// covered here so that [`configure`] rewrites them too.
+pub fn covers(file_name: &str) -> bool {
+    file_name == SCHOOLS_DATA_FILE
+        || migrations::is_migration_copy(file_name)
+        || backup::is_backup_file(file_name)
+        || journal::is_journal_file(file_name)
+        || file_name == SETTINGS_FILE
+        || file_name == AUDIT_LOG_FILE
+        || file_name == HISTORY_INPUTS_FILE
+        || file_name == RESULT_CACHE_FILE
+        || file_name == SHARES_FILE
+        || file_name.starts_with(&format!("{}/", DATASETS_DIR))
+}
+
+/// Seal the encrypted fields of `value` with the cipher in use
+pub fn encrypt(value: &mut serde_json::Value) {
+    if let Some(cipher) = current() {
+        cipher.encrypt(value);
+    }
+}
+
+/// Open the sealed values of `value` with the cipher in use
+pub fn decrypt(value: &mut serde_json::Value) -> Result<(), FieldCryptoError> {
+    match current() {
+        Some(cipher) => cipher.decrypt(value),
+        None if has_encrypted(value) => Err(FieldCryptoError::Locked),
+        None => Ok(()),
+    }
+}
+
+/// Install the cipher from the saved settings and the key in `keys`.
+///
+/// Returns whether a key was found. Without one, data written with
+/// encryption on cannot be loaded until the key is imported.
+pub fn unlock(storage: &Storage, keys: &dyn KeyStore) -> Result<bool, StorageError> {
+    let Some(key) = keys.load()? else {
+        return Ok(false);
+    };
+    let settings = FieldEncryptionSettings::load(storage)?;
+    install(Some(FieldCipher::new(key, settings.fields)));
+    Ok(true)
+}
+
+/// Install the cipher at launch from the key in the keychain `keys`.
+///
+/// Returns `false` without reading the keychain when a password is
+/// required on launch; the data then stays locked until
+/// [`unlock_with_password`].
+pub fn unlock_with_keychain(storage: &Storage, keys: &dyn KeyStore) -> Result<bool, StorageError> {
+    if FieldEncryptionSettings::load(storage)?.require_password_on_launch {
+        return Ok(false);
+    }
+    unlock(storage, keys)
+}
+
+/// Install the cipher from the key sealed with the launch password
+pub fn unlock_with_password(
+    storage: &Storage,
+    password: &str,
+) -> Result<FieldEncryptionStatus, StorageError> {
+    let sealed = PasswordSealedKey::load(storage)?.ok_or(FieldCryptoError::Locked)?;
+    let key = sealed.open(password)?;
+    let settings = FieldEncryptionSettings::load(storage)?;
+    install(Some(FieldCipher::new(key, settings.fields)));
+    status(storage)
+}
+
+/// Ask for `password` on each launch, taking the key out of the keychain
+/// `keys`; with `None`, put the key back and unlock from the keychain again.
+///
+/// The data must be unlocked, so the key in use is the one sealed.
+pub fn set_launch_password(
+    storage: &Storage,
+    keys: &dyn KeyStore,
+    password: Option<&str>,
+) -> Result<FieldEncryptionStatus, StorageError> {
+    let key = current()
+        .map(|c| c.key.clone())
+        .ok_or(FieldCryptoError::Locked)?;
+    let mut settings = FieldEncryptionSettings::load(storage)?;
+    match password {
+        Some(password) => {
+            PasswordSealedKey::seal(&key, password)?.save(storage)?;
+            keys.delete()?;
+            settings.require_password_on_launch = true;
+        }
+        None => {
+            keys.save(&key)?;
+            storage.delete(PASSWORD_KEY_FILE)?;
+            settings.require_password_on_launch = false;
+        }
+    }
+    settings.save(storage)?;
+    status(storage)
+}
+
+/// Current fields and whether the key is set up
+pub fn status(storage: &Storage) -> Result<FieldEncryptionStatus, StorageError> {
+    let settings = FieldEncryptionSettings::load(storage)?;
+    Ok(FieldEncryptionStatus {
+        fields: settings.fields,
+        unlocked: current().is_some(),
+        require_password_on_launch: settings.require_password_on_launch,
+    })
+}
+
+/// Files whose values are sealed, that exist
+fn covered_files(storage: &Storage) -> Result<Vec<String>, StorageError> {
+    let mut files = vec![
+        SCHOOLS_DATA_FILE.to_string(),
+        SETTINGS_FILE.to_string(),
+        AUDIT_LOG_FILE.to_string(),
+        HISTORY_INPUTS_FILE.to_string(),
+        RESULT_CACHE_FILE.to_string(),
+        SHARES_FILE.to_string(),
+    ];
+    for name in storage.list_datasets()? {
+        files.push(crate::storage::dataset_file(Some(&name))?);
+    }
+    if storage.data_dir().exists() {
+        for entry in std::fs::read_dir(storage.data_dir())? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if migrations::is_migration_copy(&name) {
+                files.push(name);
+            }
+        }
+    }
+    files.extend(backup::backup_files(storage)?);
+    files.extend(journal::journal_files(storage)?);
+    files.retain(|f| storage.exists(f));
+    Ok(files)
+}
+
+/// Encrypt `fields` from now on and rewrite the data files, their
+/// migration copies, the backups and the journal to match.
+///
+/// Turning encryption on for the first time creates a key and keeps it in
+/// `keys`. With no fields the files are rewritten in the clear; the key is
+/// kept so backups taken with encryption on can still be restored.
+/// While a launch password is required, the data must be unlocked first.
+pub fn configure(
+    storage: &Storage,
+    keys: &dyn KeyStore,
+    fields: Vec<SensitiveField>,
+) -> Result<FieldEncryptionStatus, StorageError> {
+    let mut settings = FieldEncryptionSettings::load(storage)?;
+    if current().is_none() && !unlock_with_keychain(storage, keys)? {
+        if settings.require_password_on_launch {
+            return Err(FieldCryptoError::Locked.into());
+        }
+        if fields.is_empty() {
+            FieldEncryptionSettings::default().save(storage)?;
+            return status(storage);
+        }
+        let key = FieldKey::generate();
+        keys.save(&key)?;
+        install(Some(FieldCipher::new(key, Vec::new())));
+    }
+    let key = current()
+        .map(|c| c.key.clone())
+        .expect("cipher installed above");
+
+    // Read everything with the current fields before switching
+    let files = covered_files(storage)?;
+    let contents = files
+        .iter()
+        .map(|f| storage.load(f))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut fields = fields;
+    fields.sort();
+    fields.dedup();
+    install(Some(FieldCipher::new(key, fields.clone())));
+    for (file, content) in files.iter().zip(contents) {
+        if let Some(content) = content {
+            storage.save(file, &content)?;
+        }
+    }
+    settings.fields = fields;
+    settings.save(storage)?;
+    status(storage)
+}
+
+/// Set up the key from another device, checking that it opens the data.
+///
+/// While a launch password is required the key is not kept in `keys`;
+/// the password-sealed copy in the data directory is used at launch.
+pub fn import_key(
+    storage: &Storage,
+    keys: &dyn KeyStore,
+    code: &str,
+) -> Result<FieldEncryptionStatus, StorageError> {
+    let key = FieldKey::from_code(code)?;
+    let settings = FieldEncryptionSettings::load(storage)?;
+    let previous = current();
+    install(Some(FieldCipher::new(key.clone(), settings.fields)));
+    for file in covered_files(storage)? {
+        if storage.load(&file).is_err() {
+            install(previous.as_deref().cloned());
+            return Err(FieldCryptoError::WrongKey.into());
+        }
+    }
+    if !settings.require_password_on_launch {
+        keys.save(&key)?;
+    }
+    status(storage)
+}
+
+/// The key in use, or else the one in `keys`, to set up another device with
+pub fn export_key(keys: &dyn KeyStore) -> Result<Option<FieldKey>, FieldCryptoError> {
+    match current() {
+        Some(cipher) => Ok(Some(cipher.key.clone())),
+        None => keys.load(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cipher(fields: Vec<SensitiveField>) -> FieldCipher {
+        FieldCipher::new(FieldKey::generate(), fields)
+    }
+
+    #[test]
+    fn test_encrypt_keeps_structure() {
+        let cipher = cipher(vec![SensitiveField::BankAccount, SensitiveField::Amount]);
+        let original = json!({
+            "schools": [{
+                "name": "東京大学",
+                "examDate": 20260225,
+                "enrollmentFee": 282000,
+                "payee": { "bankCode": "0001", "accountNumber": "1234567" },
+                "receipts": [{ "fee": "tuition", "reference": "R-1" }]
+            }],
+            "budget": { "total": 3000000 }
+        });
+
+        let mut value = original.clone();
+        cipher.encrypt(&mut value);
+        let school = &value["schools"][0];
+        assert_eq!(school["name"], "東京大学");
+        assert_eq!(school["examDate"], 20260225);
+        assert_eq!(school["payee"]["bankCode"], "0001");
+        assert_eq!(school["receipts"][0]["reference"], "R-1");
+        assert_eq!(school["receipts"][0]["fee"], "tuition");
+        for sealed in [
+            &school["enrollmentFee"],
+            &school["payee"]["accountNumber"],
+            &value["budget"]["total"],
+        ] {
+            assert!(sealed.as_str().unwrap().starts_with(ENCRYPTED_PREFIX));
+        }
+        assert!(!value.to_string().contains("1234567"));
+
+        // Sealing again leaves sealed values alone
+        let sealed = value.clone();
+        cipher.encrypt(&mut value);
+        assert_eq!(value, sealed);
+
+        cipher.decrypt(&mut value).unwrap();
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn test_memo_covers_school_notes() {
+        let cipher = cipher(vec![SensitiveField::Memo]);
+        let original = json!({
+            "schools": [{
+                "name": "東京大学",
+                "notes": [{
+                    "text": "振込は窓口で確認済み",
+                    "writtenAt": "2026-02-20T10:00:00+09:00",
+                    "fee": "tuition"
+                }]
+            }]
+        });
+
+        let mut value = original.clone();
+        cipher.encrypt(&mut value);
+        let note = &value["schools"][0]["notes"][0];
+        assert!(note["text"].as_str().unwrap().starts_with(ENCRYPTED_PREFIX));
+        assert_eq!(note["fee"], "tuition");
+        assert!(!value.to_string().contains("窓口"));
+
+        cipher.decrypt(&mut value).unwrap();
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn test_decrypt_needs_the_key() {
+        let mut value = json!({ "memo": "第一志望" });
+        cipher(vec![SensitiveField::Memo]).encrypt(&mut value);
+        assert!(has_encrypted(&value));
+        assert!(matches!(
+            cipher(vec![]).decrypt(&mut value.clone()),
+            Err(FieldCryptoError::WrongKey)
+        ));
+    }
+
+    #[test]
+    fn test_key_code() {
+        let key = FieldKey::generate();
+        assert_eq!(FieldKey::from_code(&key.code()).unwrap(), key);
+        assert!(FieldKey::from_code("abcd").is_err());
+        assert!(FieldKey::from_code("not hex").is_err());
+    }
+
+    #[test]
+    fn test_file_key_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileKeyStore::new(dir.path().join("keys").join("field.key"));
+        assert!(store.load().unwrap().is_none());
+        let key = FieldKey::generate();
+        store.save(&key).unwrap();
+        assert_eq!(store.load().unwrap(), Some(key));
+        store.delete().unwrap();
+        store.delete().unwrap();
+        assert!(store.load().unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_key_store_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("share.key");
+        FileKeyStore::new(path.clone()).save(&FieldKey::generate()).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_password_sealed_key() {
+        let key = FieldKey::generate();
+        let sealed = PasswordSealedKey::seal(&key, "いつもの合言葉です").unwrap();
+        assert!(!serde_json::to_string(&sealed)
+            .unwrap()
+            .contains(&key.code()));
+        assert_eq!(sealed.open("いつもの合言葉です").unwrap(), key);
+        assert!(matches!(
+            sealed.open("ちがう合言葉です"),
+            Err(FieldCryptoError::WrongPassword)
+        ));
+        assert!(matches!(
+            PasswordSealedKey::seal(&key, "short"),
+            Err(FieldCryptoError::WeakPassword)
+        ));
+    }
+
+    #[test]
+    fn test_password_on_launch() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().join("data"));
+        let keys = FileKeyStore::new(dir.path().join("keys").join("field.key"));
+        let key = FieldKey::generate();
+        keys.save(&key).unwrap();
+
+        // Setting a password takes the key out of the keychain
+        install(Some(FieldCipher::new(key.clone(), Vec::new())));
+        let status = set_launch_password(&storage, &keys, Some("correct horse")).unwrap();
+        assert!(status.require_password_on_launch);
+        assert!(keys.load().unwrap().is_none());
+        assert!(storage.exists(PASSWORD_KEY_FILE));
+
+        // The next launch stays locked until the password is given
+        install(None);
+        assert!(!unlock_with_keychain(&storage, &keys).unwrap());
+        assert!(current().is_none());
+        assert!(matches!(
+            unlock_with_password(&storage, "wrong horse"),
+            Err(StorageError::FieldCrypto(FieldCryptoError::WrongPassword))
+        ));
+        assert!(
+            unlock_with_password(&storage, "correct horse")
+                .unwrap()
+                .unlocked
+        );
+        assert_eq!(export_key(&keys).unwrap(), Some(key.clone()));
+
+        // Without the password the key goes back to the keychain
+        let status = set_launch_password(&storage, &keys, None).unwrap();
+        assert!(!status.require_password_on_launch);
+        assert_eq!(keys.load().unwrap(), Some(key));
+        assert!(!storage.exists(PASSWORD_KEY_FILE));
+        install(None);
+        assert!(unlock_with_keychain(&storage, &keys).unwrap());
+        install(None);
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers(SCHOOLS_DATA_FILE));
+        assert!(covers(SETTINGS_FILE));
+        assert!(covers(HISTORY_INPUTS_FILE));
+        assert!(covers(RESULT_CACHE_FILE));
+        assert!(covers(SHARES_FILE));
+        assert!(covers(
+            &crate::storage::dataset_file(Some("併願案")).unwrap()
+        ));
+        assert!(!covers(FIELD_ENCRYPTION_FILE));
+        assert!(!covers(PASSWORD_KEY_FILE));
+        assert!(covers("data.v1.json"));
+        assert!(covers("backups/manual-20260301-103000.json"));
+        assert!(!covers("backups/../data.json"));
+    }
+
+    #[test]
+    fn test_covered_files_include_copies_backups_and_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage.save(SCHOOLS_DATA_FILE, &json!({})).unwrap();
+        storage
+            .save(&migrations::migration_copy_file(1), &json!({}))
+            .unwrap();
+        let backup = backup::write_backup(&storage, backup::MANUAL_BACKUP)
+            .unwrap()
+            .unwrap();
+
+        let at = chrono::DateTime::parse_from_rfc3339("2026-03-01T10:15:00+09:00").unwrap();
+        journal::record(&storage, SCHOOLS_DATA_FILE, &json!({ "revision": 3 }), at).unwrap();
+        journal::record(
+            &storage,
+            "datasets/sibling.json",
+            &json!({ "revision": 1 }),
+            at,
+        )
+        .unwrap();
+
+        let files = covered_files(&storage).unwrap();
+        assert!(files.contains(&"data.v1.json".to_string()));
+        assert!(files.contains(&format!("backups/{}", backup.file_name)));
+        assert!(files.contains(&"journal/data/20260301-101500-00000003.json".to_string()));
+        let sibling = "journal/datasets/sibling/20260301-101500-00000001.json";
+        assert!(files.contains(&sibling.to_string()));
+        assert!(files.iter().all(|f| covers(f)));
+        assert!(!covers("journal/data/notes.json"));
+        assert!(!covers("journal/20260301-101500-00000003.json"));
+        assert!(!covers("journal/../20260301-101500-00000003.json"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Display formatting shared by notifications and exports.
+
+use crate::i18n::Locale;
+use crate::model::Currency;
+
+/// Format a YYYYMMDD integer as `YYYY/MM/DD`
+pub fn format_day(day: u32) -> String {
+    format!("{}/{:02}/{:02}", day / 10000, (day / 100) % 100, day % 100)
+}
+
+/// Insert thousands separators into an integer
+fn group_thousands(amount: u64) -> String {
+    let digits = amount.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Format a yen amount with thousands separators
+pub fn format_yen(amount: u64) -> String {
+    format!("¥{}", group_thousands(amount))
+}
+
+/// Format a yen amount in 万/億 notation, as in `28万2000円`
+pub fn format_man_yen(amount: u64) -> String {
+    if amount == 0 {
+        return "0円".to_string();
+    }
+    let units = [
+        (amount / 100_000_000, "億"),
+        (amount / 10_000 % 10_000, "万"),
+        (amount % 10_000, ""),
+    ];
+    let mut out: String = units
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect();
+    out.push('円');
+    out
+}
+
+/// Format an amount in `currency`'s minor units for `locale`.
+///
+/// Yen is written `282,000円` in Japanese and `¥282,000` in English; other
+/// currencies use their symbol and decimals, as in `US$12,500.00`.
+pub fn format_money(amount: u64, currency: Currency, locale: Locale) -> String {
+    if currency == Currency::Jpy && locale == Locale::Ja {
+        return format!("{}円", group_thousands(amount));
+    }
+    let scale = 10u64.pow(currency.minor_digits());
+    let major = group_thousands(amount / scale);
+    match currency.minor_digits() {
+        0 => format!("{}{}", currency.symbol(), major),
+        digits => format!(
+            "{}{}.{:0width$}",
+            currency.symbol(),
+            major,
+            amount % scale,
+            width = digits as usize
+        ),
+    }
+}
+
+/// Full-width katakana in gojūon order and their half-width forms
+const FULL_KANA: &str =
+    "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワヲン";
+const HALF_KANA: &str = "ｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜｦﾝ";
+
+/// Half-width form of a full-width katakana, splitting off voicing marks
+fn half_kana(c: char) -> Option<String> {
+    let half = |base: char| {
+        FULL_KANA
+            .chars()
+            .position(|k| k == base)
+            .and_then(|i| HALF_KANA.chars().nth(i))
+    };
+    if let Some(h) = half(c) {
+        return Some(h.to_string());
+    }
+    let prev = |n: u32| char::from_u32(u32::from(c) - n);
+    if "ガギグゲゴザジズゼゾダヂヅデドバビブベボ".contains(c) {
+        return prev(1).and_then(half).map(|h| format!("{}ﾞ", h));
+    }
+    if "パピプペポ".contains(c) {
+        return prev(2).and_then(half).map(|h| format!("{}ﾟ", h));
+    }
+    match c {
+        // Banks do not take small kana; they are sent as their full size
+        'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ッ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ' => {
+            char::from_u32(u32::from(c) + 1)
+                .and_then(half)
+                .map(String::from)
+        }
+        'ヴ' => Some("ｳﾞ".to_string()),
+        'ヰ' => Some("ｲ".to_string()),
+        'ヱ' => Some("ｴ".to_string()),
+        _ => None,
+    }
+}
+
+/// Convert a name to the half-width kana accepted in bank transfer files.
+///
+/// Hiragana and full-width characters are converted, letters upper-cased,
+/// small kana enlarged and `ー` written as `-`. Fails with the first
+/// character that has no bank form, such as a kanji.
+pub fn to_bank_kana(text: &str) -> Result<String, char> {
+    let mut out = String::new();
+    for c in text.chars() {
+        // Full-width ASCII and the ideographic space
+        let c = match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(u32::from(c) - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            _ => c,
+        };
+        // Hiragana to katakana
+        let c = match c {
+            '\u{3041}'..='\u{3096}' => char::from_u32(u32::from(c) + 0x60).unwrap_or(c),
+            _ => c,
+        };
+        match c {
+            '0'..='9' | 'A'..='Z' | ' ' | '(' | ')' | '-' | '.' | '/' => out.push(c),
+            'a'..='z' => out.push(c.to_ascii_uppercase()),
+            'ー' | 'ｰ' => out.push('-'),
+            'ｱ'..='ﾝ' | 'ﾞ' | 'ﾟ' | '｢' | '｣' => out.push(c),
+            'ｧ'..='ｯ' => out.extend(
+                "ｱｲｳｴｵﾔﾕﾖﾂ"
+                    .chars()
+                    .nth((u32::from(c) - u32::from('ｧ')) as usize),
+            ),
+            _ => out.push_str(&half_kana(c).ok_or(c)?),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_day() {
+        assert_eq!(format_day(20260306), "2026/03/06");
+    }
+
+    #[test]
+    fn test_format_yen() {
+        assert_eq!(format_yen(0), "¥0");
+        assert_eq!(format_yen(999), "¥999");
+        assert_eq!(format_yen(282000), "¥282,000");
+        assert_eq!(format_yen(1535800), "¥1,535,800");
+    }
+
+    #[test]
+    fn test_format_man_yen() {
+        assert_eq!(format_man_yen(0), "0円");
+        assert_eq!(format_man_yen(500), "500円");
+        assert_eq!(format_man_yen(282000), "28万2000円");
+        assert_eq!(format_man_yen(1000000), "100万円");
+        assert_eq!(format_man_yen(123450000), "1億2345万円");
+    }
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(format_money(282000, Currency::Jpy, Locale::Ja), "282,000円");
+        assert_eq!(format_money(282000, Currency::Jpy, Locale::En), "¥282,000");
+        assert_eq!(
+            format_money(1250005, Currency::Usd, Locale::En),
+            "US$12,500.05"
+        );
+        assert_eq!(format_money(99, Currency::Eur, Locale::Ja), "€0.99");
+        assert_eq!(format_money(50000, Currency::Krw, Locale::Ja), "₩50,000");
+    }
+
+    #[test]
+    fn test_to_bank_kana() {
+        assert_eq!(to_bank_kana("ワセダダイガク").unwrap(), "ﾜｾﾀﾞﾀﾞｲｶﾞｸ");
+        assert_eq!(to_bank_kana("がっこうほうじん").unwrap(), "ｶﾞﾂｺｳﾎｳｼﾞﾝ");
+        assert_eq!(to_bank_kana("パーク　Ａｂｃ").unwrap(), "ﾊﾟ-ｸ ABC");
+        assert_eq!(to_bank_kana("ｷｮｳｲｸ(ｶ").unwrap(), "ｷﾖｳｲｸ(ｶ");
+        assert_eq!(to_bank_kana("ヴァイオリン").unwrap(), "ｳﾞｱｲｵﾘﾝ");
+        assert_eq!(to_bank_kana("東京"), Err('東'));
+    }
+}
@@ -0,0 +1,1149 @@
+//! Two-way sync of payment deadlines with Google Calendar.
+//!
+//! Each payment of a synced dataset becomes an all-day event in a calendar
+//! of its own, created on the first sync, so the deadlines can be removed
+//! again by deleting that one calendar. Events carry the payment's key in
+//! a private extended property. A sync first pulls changes made in Google
+//! Calendar back into the data:
+//!
+//! - an event whose title was marked with [`DONE_MARK`] records the
+//!   payment as made, and
+//! - an event moved to another day moves the deadline, unless the deadline
+//!   was also changed in the app, in which case the app wins.
+//!
+//! It then pushes new, changed and paid payments as events. What was last
+//! pushed is kept per dataset in [`GOOGLE_CALENDAR_FILE`], so each dataset
+//! ("profile") can be synced to its own calendar or not at all.
+//!
+//! The app signs in with the OAuth device flow: the user enters a short
+//! code on another device, so no browser redirect back to the app is
+//! needed. The tokens are kept by a [`TokenStore`], which the desktop app
+//! backs with the OS keychain.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::date::{self, parse_day, to_day};
+use crate::format::format_yen;
+use crate::model::{Dataset, FeeKind, PaymentMethod, School};
+use crate::payments::{self, PaymentError, PaymentRecord};
+use crate::storage::{dataset_file, Storage, StorageError};
+
+/// File in the data directory holding the sync state of each dataset
+pub const GOOGLE_CALENDAR_FILE: &str = "google_calendar.json";
+
+/// Title of the calendar created for the deadlines
+pub const DEFAULT_CALENDAR_NAME: &str = "学費の支払い期限";
+
+/// Title prefix marking a payment as made
+pub const DONE_MARK: &str = "✅";
+
+/// Private extended property holding the payment key of an event
+const KEY_PROPERTY: &str = "schoolPaymentKey";
+
+const DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const CALENDAR_API: &str = "https://www.googleapis.com/calendar/v3";
+const CALENDAR_SCOPE: &str = "https://www.googleapis.com/auth/calendar";
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Seconds before expiry at which an access token is refreshed
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+/// Times pulled changes are saved before giving up on data that keeps
+/// changing
+const SAVE_ATTEMPTS: usize = 3;
+
+fn default_calendar_name() -> String {
+    DEFAULT_CALENDAR_NAME.to_string()
+}
+
+fn default_sync_interval() -> u64 {
+    30
+}
+
+/// `[google_calendar]` section of `config.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GoogleCalendarConfig {
+    /// OAuth client of type "TVs and Limited Input devices"
+    pub client_id: String,
+    pub client_secret: String,
+    /// Title of the calendar created for each synced dataset
+    #[serde(default = "default_calendar_name")]
+    pub calendar_name: String,
+    /// Minutes between background syncs; 0 syncs only on request
+    #[serde(default = "default_sync_interval")]
+    pub sync_interval_minutes: u64,
+}
+
+/// Errors that can occur while syncing with Google Calendar
+#[derive(Debug, Error)]
+pub enum GoogleCalendarError {
+    #[error("Google Calendar is not configured")]
+    NotConfigured,
+
+    #[error("Not signed in to Google")]
+    NotConnected,
+
+    #[error("Waiting for the code to be entered")]
+    AuthorizationPending,
+
+    #[error("Sign-in was denied")]
+    AccessDenied,
+
+    #[error("The sign-in code has expired")]
+    Expired,
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Google API error {status}: {message}")]
+    Api { status: u16, message: String },
+
+    #[error("Keychain error: {0}")]
+    Keychain(String),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Invalid data: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Payment(#[from] PaymentError),
+}
+
+/// OAuth tokens of the signed-in Google account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix time the access token expires at
+    pub expires_at: i64,
+}
+
+impl OAuthToken {
+    fn is_expiring(&self, now: i64) -> bool {
+        now + REFRESH_MARGIN_SECS >= self.expires_at
+    }
+}
+
+/// Secure storage for the OAuth tokens
+pub trait TokenStore: Send + Sync {
+    fn load(&self) -> Result<Option<OAuthToken>, GoogleCalendarError>;
+    fn save(&self, token: &OAuthToken) -> Result<(), GoogleCalendarError>;
+    fn delete(&self) -> Result<(), GoogleCalendarError>;
+}
+
+/// Code for the user to enter, from the start of the device flow
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DeviceAuthorization {
+    /// Passed back to finish signing in
+    pub device_code: String,
+    /// Code the user enters at `verification_url`
+    pub user_code: String,
+    pub verification_url: String,
+    #[ts(type = "number")]
+    pub expires_in: u64,
+    /// Seconds to wait between polls
+    #[ts(type = "number")]
+    pub interval: u64,
+}
+
+/// What was last pushed for one payment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedEvent {
+    pub event_id: String,
+    pub day: u32,
+    pub summary: String,
+}
+
+/// Sync state of one dataset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSync {
+    pub enabled: bool,
+    /// Calendar created for the dataset, once synced
+    pub calendar_id: Option<String>,
+    /// Pushed events by payment key
+    pub events: BTreeMap<String, SyncedEvent>,
+}
+
+/// Sync state of every dataset, keyed by dataset name ("" for the default)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleCalendarState {
+    pub profiles: BTreeMap<String, ProfileSync>,
+}
+
+impl GoogleCalendarState {
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(GOOGLE_CALENDAR_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(GOOGLE_CALENDAR_FILE, &serde_json::to_value(self)?)
+    }
+}
+
+fn profile_key(profile: Option<&str>) -> String {
+    profile.unwrap_or_default().to_string()
+}
+
+/// Sync settings of one dataset, as shown in the settings screen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileSyncStatus {
+    /// Dataset name, or none for the default dataset
+    pub profile: Option<String>,
+    pub enabled: bool,
+    /// Events currently synced
+    pub event_count: usize,
+}
+
+/// Whether sync can be used, and which datasets are synced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct GoogleCalendarStatus {
+    /// A `[google_calendar]` client is configured
+    pub configured: bool,
+    /// Signed in to Google
+    pub connected: bool,
+    pub profiles: Vec<ProfileSyncStatus>,
+}
+
+/// Sync status of the datasets with sync settings
+pub fn status(
+    config: Option<&GoogleCalendarConfig>,
+    store: &dyn TokenStore,
+    storage: &Storage,
+) -> Result<GoogleCalendarStatus, GoogleCalendarError> {
+    let state = GoogleCalendarState::load(storage)?;
+    Ok(GoogleCalendarStatus {
+        configured: config.is_some(),
+        connected: store.load()?.is_some(),
+        profiles: state
+            .profiles
+            .iter()
+            .map(|(name, sync)| ProfileSyncStatus {
+                profile: (!name.is_empty()).then(|| name.clone()),
+                enabled: sync.enabled,
+                event_count: sync.events.len(),
+            })
+            .collect(),
+    })
+}
+
+/// Turn sync of the dataset `profile` on or off. Turning it off keeps the
+/// calendar and its events, so turning it back on carries on from there.
+pub fn set_enabled(
+    storage: &Storage,
+    profile: Option<&str>,
+    enabled: bool,
+) -> Result<(), GoogleCalendarError> {
+    dataset_file(profile)?;
+    let mut state = GoogleCalendarState::load(storage)?;
+    state
+        .profiles
+        .entry(profile_key(profile))
+        .or_default()
+        .enabled = enabled;
+    state.save(storage)?;
+    Ok(())
+}
+
+/// One payment as an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalEvent {
+    pub key: String,
+    pub school_id: u64,
+    pub fee: FeeKind,
+    pub installment: Option<u32>,
+    pub day: u32,
+    pub summary: String,
+    pub description: String,
+    pub paid: bool,
+}
+
+/// Key identifying a payment across syncs
+fn event_key(school_id: u64, fee: FeeKind, installment: Option<u32>) -> String {
+    match installment {
+        Some(number) => format!("{}:{}:{}", school_id, fee.as_str(), number),
+        None => format!("{}:{}", school_id, fee.as_str()),
+    }
+}
+
+fn payment_label(fee: FeeKind, installment: Option<u32>) -> String {
+    match (fee, installment) {
+        (FeeKind::EnrollmentFee, _) => "入学金".to_string(),
+        (FeeKind::Tuition, None) => "授業料".to_string(),
+        (FeeKind::Tuition, Some(number)) => format!("授業料（第{}回）", number),
+    }
+}
+
+/// The payments of `dataset` as events: every payment made, and the
+/// unpaid ones of schools still open
+pub fn local_events(dataset: &Dataset) -> Vec<LocalEvent> {
+    let mut events = Vec::new();
+    for school in &dataset.schools {
+        for (item, paid) in payments::items(school) {
+            if !(paid || school.pass_status.is_open()) {
+                continue;
+            }
+            let title = format!(
+                "{}の{}支払期限",
+                school.name,
+                payment_label(item.fee, item.installment)
+            );
+            events.push(LocalEvent {
+                key: event_key(school.id, item.fee, item.installment),
+                school_id: school.id,
+                fee: item.fee,
+                installment: item.installment,
+                day: item.due,
+                summary: if paid {
+                    format!("{} {}", DONE_MARK, title)
+                } else {
+                    title
+                },
+                description: format!(
+                    "金額: {}\n支払いを済ませたらタイトルの先頭に {} を付けてください。",
+                    format_yen(item.amount),
+                    DONE_MARK
+                ),
+                paid,
+            });
+        }
+    }
+    events
+}
+
+/// An event as found in Google Calendar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteEvent {
+    pub id: String,
+    /// Payment key, for events created by the app
+    pub key: Option<String>,
+    pub summary: String,
+    /// Day of an all-day event
+    pub day: Option<u32>,
+    pub cancelled: bool,
+}
+
+/// A change made in Google Calendar to bring into the data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PulledChange {
+    /// The event was marked done
+    Completed {
+        school_id: u64,
+        fee: FeeKind,
+        installment: Option<u32>,
+    },
+    /// The event was moved to `day`
+    Moved {
+        school_id: u64,
+        fee: FeeKind,
+        installment: Option<u32>,
+        day: u32,
+    },
+}
+
+/// Changes made in Google Calendar since the last push
+pub fn pull_changes(
+    locals: &[LocalEvent],
+    sync: &ProfileSync,
+    remote: &[RemoteEvent],
+) -> Vec<PulledChange> {
+    let mut changes = Vec::new();
+    for event in remote.iter().filter(|e| !e.cancelled) {
+        let Some(key) = &event.key else { continue };
+        let (Some(local), Some(synced)) =
+            (locals.iter().find(|l| &l.key == key), sync.events.get(key))
+        else {
+            continue;
+        };
+        if event.summary.trim_start().starts_with(DONE_MARK) && !local.paid {
+            changes.push(PulledChange::Completed {
+                school_id: local.school_id,
+                fee: local.fee,
+                installment: local.installment,
+            });
+        }
+        let moved = event.day.filter(|day| *day != synced.day);
+        if let Some(day) = moved {
+            // The app wins when the deadline was changed on both sides
+            if local.day == synced.day {
+                changes.push(PulledChange::Moved {
+                    school_id: local.school_id,
+                    fee: local.fee,
+                    installment: local.installment,
+                    day,
+                });
+            }
+        }
+    }
+    changes
+}
+
+fn set_deadline(school: &mut School, fee: FeeKind, installment: Option<u32>, day: u32) {
+    match (fee, installment) {
+        (FeeKind::EnrollmentFee, _) => school.enrollment_fee_deadline = day,
+        (FeeKind::Tuition, None) => school.tuition_deadline = day,
+        (FeeKind::Tuition, Some(number)) => {
+            if let Some(payment) = number
+                .checked_sub(1)
+                .and_then(|i| school.installments.get_mut(i as usize))
+            {
+                payment.due = day;
+            }
+        }
+    }
+}
+
+/// Apply pulled `changes` to `dataset`, recording completions as paid
+/// on `today`
+pub fn apply_pulled(
+    dataset: &mut Dataset,
+    changes: &[PulledChange],
+    today: u32,
+) -> Result<(), GoogleCalendarError> {
+    for change in changes {
+        match *change {
+            PulledChange::Completed {
+                school_id,
+                fee,
+                installment,
+            } => payments::mark_paid(
+                dataset,
+                PaymentRecord {
+                    school_id,
+                    fee,
+                    installment,
+                    paid_on: today,
+                    method: PaymentMethod::Other,
+                    reference: Some("Google Calendar".to_string()),
+                    attachment: None,
+                },
+            )?,
+            PulledChange::Moved {
+                school_id,
+                fee,
+                installment,
+                day,
+            } => {
+                if let Some(school) = dataset.schools.iter_mut().find(|s| s.id == school_id) {
+                    set_deadline(school, fee, installment, day);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply pulled `changes` to `dataset`, read from `file`, and save it.
+///
+/// Reading Google Calendar takes a while, so the data may have been saved
+/// meanwhile; the changes are then applied again to the saved data rather
+/// than overwriting it, up to [`SAVE_ATTEMPTS`] times.
+fn save_pulled(
+    storage: &Storage,
+    file: &str,
+    mut dataset: Dataset,
+    changes: &[PulledChange],
+    today: u32,
+) -> Result<Dataset, GoogleCalendarError> {
+    let mut attempts = 1;
+    loop {
+        apply_pulled(&mut dataset, changes, today)?;
+        let mut data = dataset.to_value();
+        match storage.save_dataset(file, &mut data, Some(dataset.revision)) {
+            Ok(revision) => {
+                dataset.revision = revision;
+                return Ok(dataset);
+            }
+            Err(StorageError::Conflict(conflict)) if attempts < SAVE_ATTEMPTS => {
+                let Some(data) = storage.load(file)? else {
+                    return Err(StorageError::Conflict(conflict).into());
+                };
+                dataset = Dataset::from_value(data)?;
+                attempts += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// A write to Google Calendar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventWrite {
+    Insert(LocalEvent),
+    Update {
+        event_id: String,
+        event: LocalEvent,
+    },
+    /// Remove the event of a payment no longer in the data
+    Delete {
+        key: String,
+        event_id: String,
+    },
+}
+
+/// Writes bringing Google Calendar up to date with `locals`. Events
+/// deleted in Google Calendar are created again.
+pub fn push_writes(
+    locals: &[LocalEvent],
+    sync: &ProfileSync,
+    remote: &[RemoteEvent],
+) -> Vec<EventWrite> {
+    let exists = |id: &str| remote.iter().any(|e| e.id == id && !e.cancelled);
+    let mut writes = Vec::new();
+    for local in locals {
+        match sync.events.get(&local.key) {
+            Some(synced) if exists(&synced.event_id) => {
+                if synced.day != local.day || synced.summary != local.summary {
+                    writes.push(EventWrite::Update {
+                        event_id: synced.event_id.clone(),
+                        event: local.clone(),
+                    });
+                }
+            }
+            _ => writes.push(EventWrite::Insert(local.clone())),
+        }
+    }
+    for (key, synced) in &sync.events {
+        if !locals.iter().any(|l| &l.key == key) && exists(&synced.event_id) {
+            writes.push(EventWrite::Delete {
+                key: key.clone(),
+                event_id: synced.event_id.clone(),
+            });
+        }
+    }
+    writes
+}
+
+/// What one sync did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncReport {
+    /// Payments recorded as made from Google Calendar
+    pub completed: usize,
+    /// Deadlines moved from Google Calendar
+    pub moved: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+impl SyncReport {
+    /// Whether the data itself was changed
+    pub fn changed_data(&self) -> bool {
+        self.completed > 0 || self.moved > 0
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthErrorResponse {
+    error: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventList {
+    #[serde(default)]
+    items: Vec<serde_json::Value>,
+    next_page_token: Option<String>,
+}
+
+fn date_string(day: u32) -> String {
+    parse_day(day)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn parse_date_string(text: &str) -> Option<u32> {
+    chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .map(to_day)
+}
+
+/// Body of an all-day event for `event`
+fn event_body(event: &LocalEvent) -> serde_json::Value {
+    let next_day = parse_day(event.day)
+        .and_then(|d| d.succ_opt())
+        .map(to_day)
+        .unwrap_or(event.day);
+    serde_json::json!({
+        "summary": event.summary,
+        "description": event.description,
+        "start": { "date": date_string(event.day) },
+        "end": { "date": date_string(next_day) },
+        "extendedProperties": { "private": { KEY_PROPERTY: event.key } },
+    })
+}
+
+fn remote_event(item: &serde_json::Value) -> Option<RemoteEvent> {
+    Some(RemoteEvent {
+        id: item.get("id")?.as_str()?.to_string(),
+        key: item
+            .pointer(&format!("/extendedProperties/private/{}", KEY_PROPERTY))
+            .and_then(|k| k.as_str())
+            .map(str::to_string),
+        summary: item
+            .get("summary")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        day: item
+            .pointer("/start/date")
+            .and_then(|d| d.as_str())
+            .and_then(parse_date_string),
+        cancelled: item.get("status").and_then(|s| s.as_str()) == Some("cancelled"),
+    })
+}
+
+/// Turn a non-2xx response into an error
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, GoogleCalendarError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let message = response.text().await.unwrap_or_default();
+    Err(GoogleCalendarError::Api {
+        status: status.as_u16(),
+        message,
+    })
+}
+
+/// Client for the Google OAuth and Calendar APIs
+pub struct GoogleClient {
+    client: reqwest::Client,
+    config: GoogleCalendarConfig,
+}
+
+impl GoogleClient {
+    pub fn new(config: GoogleCalendarConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    /// Start signing in, returning the code to show the user
+    pub async fn start_device_flow(&self) -> Result<DeviceAuthorization, GoogleCalendarError> {
+        let response = self
+            .client
+            .post(DEVICE_CODE_URL)
+            .form(&[
+                ("client_id", self.config.client_id.as_str()),
+                ("scope", CALENDAR_SCOPE),
+            ])
+            .send()
+            .await?;
+        Ok(check(response).await?.json().await?)
+    }
+
+    async fn request_token(
+        &self,
+        params: &[(&str, &str)],
+    ) -> Result<TokenResponse, GoogleCalendarError> {
+        let response = self.client.post(TOKEN_URL).form(params).send().await?;
+        if response.status().is_success() {
+            return Ok(response.json().await?);
+        }
+        let status = response.status().as_u16();
+        let text = response.text().await.unwrap_or_default();
+        let error = serde_json::from_str::<OAuthErrorResponse>(&text)
+            .map(|e| e.error)
+            .unwrap_or_default();
+        Err(match error.as_str() {
+            "authorization_pending" | "slow_down" => GoogleCalendarError::AuthorizationPending,
+            "access_denied" => GoogleCalendarError::AccessDenied,
+            "expired_token" => GoogleCalendarError::Expired,
+            "invalid_grant" => GoogleCalendarError::NotConnected,
+            _ => GoogleCalendarError::Api {
+                status,
+                message: text,
+            },
+        })
+    }
+
+    /// Check once whether the user has entered the code of `device_code`
+    pub async fn poll_token(&self, device_code: &str) -> Result<OAuthToken, GoogleCalendarError> {
+        let token = self
+            .request_token(&[
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("device_code", device_code),
+                ("grant_type", DEVICE_GRANT_TYPE),
+            ])
+            .await?;
+        Ok(OAuthToken {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token.unwrap_or_default(),
+            expires_at: date::now().timestamp() + token.expires_in,
+        })
+    }
+
+    /// Wait for the user to enter the code of `authorization` and keep
+    /// the tokens in `store`
+    pub async fn finish_device_flow(
+        &self,
+        authorization: &DeviceAuthorization,
+        store: &dyn TokenStore,
+    ) -> Result<(), GoogleCalendarError> {
+        let deadline = date::now().timestamp() + authorization.expires_in as i64;
+        let interval = Duration::from_secs(authorization.interval.max(1));
+        loop {
+            match self.poll_token(&authorization.device_code).await {
+                Ok(token) => return store.save(&token),
+                Err(GoogleCalendarError::AuthorizationPending) => {}
+                Err(e) => return Err(e),
+            }
+            if date::now().timestamp() >= deadline {
+                return Err(GoogleCalendarError::Expired);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// A valid access token, refreshed and saved if it was about to expire
+    async fn access_token(&self, store: &dyn TokenStore) -> Result<String, GoogleCalendarError> {
+        let token = store.load()?.ok_or(GoogleCalendarError::NotConnected)?;
+        if !token.is_expiring(date::now().timestamp()) {
+            return Ok(token.access_token);
+        }
+        let refreshed = self
+            .request_token(&[
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("refresh_token", &token.refresh_token),
+                ("grant_type", "refresh_token"),
+            ])
+            .await?;
+        let token = OAuthToken {
+            access_token: refreshed.access_token,
+            refresh_token: refreshed.refresh_token.unwrap_or(token.refresh_token),
+            expires_at: date::now().timestamp() + refreshed.expires_in,
+        };
+        store.save(&token)?;
+        Ok(token.access_token)
+    }
+
+    /// URL of an API path, with each segment escaped
+    fn url(&self, segments: &[&str]) -> url::Url {
+        let mut url = url::Url::parse(CALENDAR_API).expect("valid API URL");
+        url.path_segments_mut()
+            .expect("API URL has a path")
+            .extend(segments);
+        url
+    }
+
+    async fn create_calendar(&self, token: &str) -> Result<String, GoogleCalendarError> {
+        let response = self
+            .client
+            .post(self.url(&["calendars"]))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "summary": self.config.calendar_name }))
+            .send()
+            .await?;
+        let calendar: serde_json::Value = check(response).await?.json().await?;
+        calendar
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| GoogleCalendarError::Api {
+                status: 200,
+                message: "Calendar has no id".to_string(),
+            })
+    }
+
+    async fn list_events(
+        &self,
+        token: &str,
+        calendar_id: &str,
+    ) -> Result<Vec<RemoteEvent>, GoogleCalendarError> {
+        let mut events = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .client
+                .get(self.url(&["calendars", calendar_id, "events"]))
+                .bearer_auth(token)
+                .query(&[("showDeleted", "true"), ("maxResults", "2500")]);
+            if let Some(page) = &page_token {
+                request = request.query(&[("pageToken", page)]);
+            }
+            let list: EventList = check(request.send().await?).await?.json().await?;
+            events.extend(list.items.iter().filter_map(remote_event));
+            match list.next_page_token {
+                Some(next) => page_token = Some(next),
+                None => return Ok(events),
+            }
+        }
+    }
+
+    async fn write_event(
+        &self,
+        token: &str,
+        calendar_id: &str,
+        write: &EventWrite,
+    ) -> Result<Option<String>, GoogleCalendarError> {
+        let request = match write {
+            EventWrite::Insert(event) => self
+                .client
+                .post(self.url(&["calendars", calendar_id, "events"]))
+                .json(&event_body(event)),
+            EventWrite::Update { event_id, event } => self
+                .client
+                .patch(self.url(&["calendars", calendar_id, "events", event_id]))
+                .json(&event_body(event)),
+            EventWrite::Delete { event_id, .. } => {
+                self.client
+                    .delete(self.url(&["calendars", calendar_id, "events", event_id]))
+            }
+        };
+        let response = check(request.bearer_auth(token).send().await?).await?;
+        if let EventWrite::Delete { .. } = write {
+            return Ok(None);
+        }
+        let event: serde_json::Value = response.json().await?;
+        Ok(event
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string))
+    }
+
+    /// Sync the dataset `profile` both ways, as of `today`
+    pub async fn sync(
+        &self,
+        store: &dyn TokenStore,
+        storage: &Storage,
+        profile: Option<&str>,
+        today: u32,
+    ) -> Result<SyncReport, GoogleCalendarError> {
+        let file = dataset_file(profile)?;
+        let Some(data) = storage.load(&file)? else {
+            return Ok(SyncReport::default());
+        };
+        let mut dataset = Dataset::from_value(data)?;
+        let mut state = GoogleCalendarState::load(storage)?;
+        let mut sync = state
+            .profiles
+            .get(&profile_key(profile))
+            .cloned()
+            .unwrap_or_default();
+
+        let token = self.access_token(store).await?;
+        let calendar_id = match &sync.calendar_id {
+            Some(id) => id.clone(),
+            None => {
+                let id = self.create_calendar(&token).await?;
+                sync.calendar_id = Some(id.clone());
+                id
+            }
+        };
+        let remote = self.list_events(&token, &calendar_id).await?;
+
+        let mut report = SyncReport::default();
+        let pulled = pull_changes(&local_events(&dataset), &sync, &remote);
+        if !pulled.is_empty() {
+            dataset = save_pulled(storage, &file, dataset, &pulled, today)?;
+        }
+        for change in &pulled {
+            match change {
+                PulledChange::Completed { .. } => report.completed += 1,
+                PulledChange::Moved { .. } => report.moved += 1,
+            }
+        }
+
+        // Record each write as it succeeds, so a failure part way through
+        // does not create duplicates on the next sync
+        let result = async {
+            for write in push_writes(&local_events(&dataset), &sync, &remote) {
+                let event_id = self.write_event(&token, &calendar_id, &write).await?;
+                match (write, event_id) {
+                    (EventWrite::Delete { key, .. }, _) => {
+                        sync.events.remove(&key);
+                        report.deleted += 1;
+                    }
+                    (EventWrite::Insert(event), Some(event_id)) => {
+                        sync.events
+                            .insert(event.key, synced(event_id, event.day, event.summary));
+                        report.created += 1;
+                    }
+                    (EventWrite::Update { event, .. }, Some(event_id)) => {
+                        sync.events
+                            .insert(event.key, synced(event_id, event.day, event.summary));
+                        report.updated += 1;
+                    }
+                    _ => {}
+                }
+            }
+            Ok::<(), GoogleCalendarError>(())
+        }
+        .await;
+
+        state.profiles.insert(profile_key(profile), sync);
+        state.save(storage)?;
+        result.map(|()| report)
+    }
+}
+
+fn synced(event_id: String, day: u32, summary: String) -> SyncedEvent {
+    SyncedEvent {
+        event_id,
+        day,
+        summary,
+    }
+}
+
+/// Sync every enabled dataset every `interval`, until the process exits
+pub async fn run_periodic_sync<F>(
+    client: GoogleClient,
+    store: std::sync::Arc<dyn TokenStore>,
+    storage: Storage,
+    interval: Duration,
+    on_data_changed: F,
+) where
+    F: Fn(Option<&str>) + Send + Sync + 'static,
+{
+    loop {
+        tokio::time::sleep(interval).await;
+        if !matches!(store.load(), Ok(Some(_))) {
+            continue;
+        }
+        let profiles: Vec<String> = match GoogleCalendarState::load(&storage) {
+            Ok(state) => state
+                .profiles
+                .into_iter()
+                .filter(|(_, sync)| sync.enabled)
+                .map(|(name, _)| name)
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Cannot read Google Calendar sync state: {}", e);
+                continue;
+            }
+        };
+        for name in profiles {
+            let profile = (!name.is_empty()).then_some(name.as_str());
+            match client
+                .sync(store.as_ref(), &storage, profile, date::today())
+                .await
+            {
+                Ok(report) => {
+                    tracing::debug!("Google Calendar sync of {:?}: {:?}", profile, report);
+                    if report.changed_data() {
+                        on_data_changed(profile);
+                    }
+                }
+                Err(e) => tracing::warn!("Google Calendar sync of {:?} failed: {}", profile, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_dataset;
+
+    /// State after pushing `locals` unchanged
+    fn pushed(locals: &[LocalEvent]) -> (ProfileSync, Vec<RemoteEvent>) {
+        let mut sync = ProfileSync {
+            enabled: true,
+            calendar_id: Some("cal".to_string()),
+            events: BTreeMap::new(),
+        };
+        let mut remote = Vec::new();
+        for (i, local) in locals.iter().enumerate() {
+            let id = format!("ev{}", i);
+            sync.events.insert(
+                local.key.clone(),
+                synced(id.clone(), local.day, local.summary.clone()),
+            );
+            remote.push(RemoteEvent {
+                id,
+                key: Some(local.key.clone()),
+                summary: local.summary.clone(),
+                day: Some(local.day),
+                cancelled: false,
+            });
+        }
+        (sync, remote)
+    }
+
+    #[test]
+    fn test_local_events() {
+        let events = local_events(&sample_dataset());
+        let tokyo_fee = events.iter().find(|e| e.key == "1:enrollmentFee").unwrap();
+        assert!(tokyo_fee.summary.ends_with("の入学金支払期限"));
+        assert!(tokyo_fee.description.contains("¥"));
+        assert!(!tokyo_fee.paid);
+        assert_eq!(
+            event_body(tokyo_fee)["start"]["date"]
+                .as_str()
+                .unwrap()
+                .len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_first_sync_inserts_everything() {
+        let locals = local_events(&sample_dataset());
+        let writes = push_writes(&locals, &ProfileSync::default(), &[]);
+        assert_eq!(writes.len(), locals.len());
+        assert!(writes.iter().all(|w| matches!(w, EventWrite::Insert(_))));
+
+        let (sync, remote) = pushed(&locals);
+        assert!(push_writes(&locals, &sync, &remote).is_empty());
+        assert!(pull_changes(&locals, &sync, &remote).is_empty());
+    }
+
+    #[test]
+    fn test_pulls_completion_and_move() {
+        let mut dataset = sample_dataset();
+        let locals = local_events(&dataset);
+        let (sync, mut remote) = pushed(&locals);
+        let fee = remote
+            .iter_mut()
+            .find(|e| e.key.as_deref() == Some("1:enrollmentFee"))
+            .unwrap();
+        fee.summary = format!("{} {}", DONE_MARK, fee.summary);
+        let tuition = remote
+            .iter_mut()
+            .find(|e| e.key.as_deref() == Some("1:tuition"))
+            .unwrap();
+        tuition.day = Some(20260401);
+
+        let changes = pull_changes(&locals, &sync, &remote);
+        assert_eq!(changes.len(), 2);
+        apply_pulled(&mut dataset, &changes, 20260310).unwrap();
+        let tokyo = dataset.schools.iter().find(|s| s.id == 1).unwrap();
+        assert!(tokyo.enrollment_fee_paid);
+        assert_eq!(tokyo.receipts[0].paid_on, 20260310);
+        assert_eq!(tokyo.tuition_deadline, 20260401);
+
+        // Pushing afterwards records the new day and the done title
+        let locals = local_events(&dataset);
+        let writes = push_writes(&locals, &sync, &remote);
+        assert_eq!(writes.len(), 2);
+        assert!(writes
+            .iter()
+            .all(|w| matches!(w, EventWrite::Update { .. })));
+    }
+
+    #[test]
+    fn test_pulled_changes_keep_edits_saved_meanwhile() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let file = dataset_file(None).unwrap();
+        let mut saved = sample_dataset();
+        saved.revision = storage
+            .save_dataset(&file, &mut saved.to_value(), None)
+            .unwrap();
+        let loaded = saved.clone();
+
+        // The user renames a school while Google Calendar is being read
+        saved.schools[0].name = "改名した学校".to_string();
+        storage
+            .save_dataset(&file, &mut saved.to_value(), Some(saved.revision))
+            .unwrap();
+
+        let pulled = [PulledChange::Completed {
+            school_id: 1,
+            fee: FeeKind::EnrollmentFee,
+            installment: None,
+        }];
+        let synced = save_pulled(&storage, &file, loaded, &pulled, 20260310).unwrap();
+        assert_eq!(synced.revision, 3);
+        let stored = Dataset::from_value(storage.load(&file).unwrap().unwrap()).unwrap();
+        assert_eq!(stored.schools[0].name, "改名した学校");
+        let tokyo = stored.schools.iter().find(|s| s.id == 1).unwrap();
+        assert!(tokyo.enrollment_fee_paid);
+    }
+
+    #[test]
+    fn test_app_wins_when_both_moved() {
+        let mut dataset = sample_dataset();
+        let (sync, mut remote) = pushed(&local_events(&dataset));
+        dataset.schools[0].enrollment_fee_deadline = 20260320;
+        let key = event_key(dataset.schools[0].id, FeeKind::EnrollmentFee, None);
+        remote
+            .iter_mut()
+            .find(|e| e.key.as_ref() == Some(&key))
+            .unwrap()
+            .day = Some(20260321);
+
+        let locals = local_events(&dataset);
+        assert!(pull_changes(&locals, &sync, &remote).is_empty());
+        let writes = push_writes(&locals, &sync, &remote);
+        assert!(matches!(&writes[..], [EventWrite::Update { event, .. }] if event.day == 20260320));
+    }
+
+    #[test]
+    fn test_recreates_deleted_and_removes_stale() {
+        let mut dataset = sample_dataset();
+        let locals = local_events(&dataset);
+        let (sync, mut remote) = pushed(&locals);
+        remote[0].cancelled = true;
+        let removed = dataset.schools.pop().unwrap();
+        let writes = push_writes(&local_events(&dataset), &sync, &remote);
+        assert!(writes
+            .iter()
+            .any(|w| matches!(w, EventWrite::Insert(e) if e.key == locals[0].key)));
+        assert!(writes.iter().any(
+            |w| matches!(w, EventWrite::Delete { key, .. } if key.starts_with(&format!("{}:", removed.id)))
+        ));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        set_enabled(&storage, None, true).unwrap();
+        set_enabled(&storage, Some("次男"), false).unwrap();
+        assert!(set_enabled(&storage, Some("../x"), true).is_err());
+
+        let state = GoogleCalendarState::load(&storage).unwrap();
+        assert!(state.profiles[""].enabled);
+        assert!(!state.profiles["次男"].enabled);
+    }
+
+    #[test]
+    fn test_remote_event() {
+        let item = serde_json::json!({
+            "id": "abc",
+            "status": "confirmed",
+            "summary": "✅ 東京大学の入学金支払期限",
+            "start": { "date": "2026-03-17" },
+            "extendedProperties": { "private": { KEY_PROPERTY: "1:enrollmentFee" } },
+        });
+        let event = remote_event(&item).unwrap();
+        assert_eq!(event.day, Some(20260317));
+        assert_eq!(event.key.as_deref(), Some("1:enrollmentFee"));
+        assert!(!event.cancelled);
+    }
+}
@@ -2,44 +2,3047 @@
 //!
 //! These handlers are used by both Tauri commands and Axum HTTP endpoints.
 
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{watch, Mutex, Semaphore};
+use tokio::task::JoinSet;
 
-use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::analysis::{self, ScheduleWarning};
+use crate::audit::{self, AuditAction, AuditEntry, AuditLog, AuditVerification};
+use crate::backup::{self, BackupInfo};
+use crate::budget::{self, BudgetAlert};
+use crate::bulk::{self, BulkError, BulkOp};
+use crate::call_stats::{CallStats, MethodCallStats};
+use crate::calendar::{self, CalendarError, Holiday};
+use crate::cloud_backup::{self, CloudBackupStatus};
+use crate::config::AppConfig;
+use crate::crash::CrashReport;
+use crate::countdown::{self, Countdown};
+use crate::currency::{self, CurrencyError, ExchangeRates};
+use crate::data_location::{self, DataDirError, MovedData};
+use crate::date;
+use crate::diff::{self, ChangeReport};
+use crate::edit_lock::{EditLock, EditLockStatus};
+use crate::explanations::{self, Explanation, ExplanationCache};
+use crate::export::{
+    self, ExportError, ExportFormat, ExportTemplate, ReportType, TemplateExport, ZenginExport,
+    ZenginLayout, TEMPLATES_DIR,
+};
+use crate::field_crypto::{
+    self, FieldCryptoError, FieldEncryptionStatus, FieldKey, KeyStore, SensitiveField,
+};
+use crate::health_history::{HealthEvent, HealthEventKind, HealthHistory};
+use crate::history::{
+    self, Decision, History, HistoryEntry, HistoryError, HistoryInputs, HistoryStats,
+    RecommendationInputs,
+};
+use crate::i18n::{Locale, LocalizedError, Message};
+use crate::import::{self, ImportError, ImportPreview, MergeAction, ParsedImport, PendingImport};
+use crate::journal::{self, PastVersion};
+use crate::json_rpc::{self, JsonRpcRequest, JsonRpcResponse};
+use crate::legacy_import::{self, LegacyImportPreview};
 use crate::lean_repl::{LeanRepl, LeanReplError};
+use crate::log_buffer::{EventQuery, LogBuffer, LogEvent};
+use crate::logs::{LogError, LogLevelHandle};
+use crate::migrations::{self, MigrationError, CURRENT_SCHEMA_VERSION};
+use crate::model::{
+    expand_recurring, method_charge, recurring_horizon, Currency, Dataset, GetRecommendationResult,
+    Installment, InstallmentPlan, MethodFee, PaymentMethod, RecurringFee, RecurringPayment, School,
+};
+use crate::notes::{self, NewNote, NoteError};
+use crate::notifier::NotifierSet;
+use crate::onboarding::{OnboardingError, OnboardingState, OnboardingStep};
+use crate::os_reminders::{self, OsReminderError};
+use crate::outcome::{self, ExamOutcome, OutcomeEntry, OutcomeError, RecordedOutcome};
+use crate::payments::{self, OutstandingPayments, PaymentError, PaymentRecord};
+use crate::preload::PreloadCache;
+use crate::profiling::{Phase, PhaseTimings, ProfileStats, Profiler};
+use crate::query::{ListQuery, Page};
+use crate::recording::{RecordingError, RecordingStatus, Session};
+use crate::repl_pool::{self, PoolLimits, ReplPool};
+use crate::result_cache::{CachedResult, ResultCache};
+use crate::revision::{self, RevisionConflict};
+use crate::reminders::{self, ReminderState};
+use crate::sample::{self, SampleProfile};
+use crate::search::{self, SearchHit};
+use crate::self_test::{self, SelfTestReport};
+use crate::settings::{Settings, SettingsError};
+use crate::share::{ShareError, ShareLink, SharedSchedule, Shares, MAX_SHARE_DAYS};
+use crate::sheet_import::{
+    self, ColumnMapping, MappingCatalog, MappingMatch, MappingSource, SheetImportError,
+};
+use crate::simulation::{self, Scenario, SimulationError, SimulationResult};
+use crate::storage::{dataset_file, Storage, StorageError, SCHOOLS_DATA_FILE};
+use crate::streaming::Chunks;
+use crate::summary::{self, Summary, SummaryFigure, SummaryRange, TotalExplanation};
+use crate::supervisor::{self, AdvisorState, AdvisorStatus};
+use crate::tax_report::{self, TaxReport};
+use crate::templates::{self, SchoolTemplate, TemplateError};
+use crate::timeline::{self, Timeline};
+use crate::support::{self, AdvisorInfo, SupportError, SupportReport, SystemInfo};
+use crate::token::random_token;
+use crate::transfer::{self, PendingTransfer, TransferError, TransferOffer};
+use crate::web_push::{
+    NewPushSubscription, PushMessage, PushSubscription, PushSubscriptions, WebPush, WebPushError,
+};
+use crate::webhook::{WebhookDispatcher, WebhookEvent};
+
+/// Errors returned by handlers that do more than forward to the REPL
+#[derive(Debug, Error)]
+pub enum HandlerError {
+    #[error(transparent)]
+    Repl(#[from] LeanReplError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error(transparent)]
+    Export(#[from] ExportError),
+
+    #[error(transparent)]
+    Import(#[from] ImportError),
+
+    #[error(transparent)]
+    Migration(#[from] MigrationError),
+
+    #[error(transparent)]
+    SheetImport(#[from] SheetImportError),
+
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+
+    #[error(transparent)]
+    Support(#[from] SupportError),
+
+    #[error(transparent)]
+    Transfer(#[from] TransferError),
+
+    #[error(transparent)]
+    OsReminders(#[from] OsReminderError),
+
+    #[error(transparent)]
+    Currency(#[from] CurrencyError),
+
+    #[error(transparent)]
+    History(#[from] HistoryError),
+
+    #[error(transparent)]
+    Simulation(#[from] SimulationError),
+
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+
+    #[error(transparent)]
+    Payment(#[from] PaymentError),
+
+    #[error(transparent)]
+    Calendar(#[from] CalendarError),
+
+    #[error(transparent)]
+    DataDir(#[from] DataDirError),
+
+    #[error("Data storage is not configured")]
+    NoStorage,
+
+    #[error("No saved data found")]
+    NoData,
+
+    #[error("Import has expired or was already applied")]
+    UnknownImport,
+
+    #[error("Backup not found: {0}")]
+    UnknownBackup(String),
+
+    #[error("Invalid installment plan")]
+    InvalidInstallmentPlan,
+
+    #[error("Invalid recurring fee: {0}")]
+    InvalidRecurringFee(String),
+
+    #[error("Invalid data patch: {0}")]
+    InvalidPatch(String),
+
+    #[error(transparent)]
+    Outcome(#[from] OutcomeError),
+
+    #[error("Explanation not found: {0}")]
+    UnknownExplanation(String),
+
+    #[error(transparent)]
+    Log(#[from] LogError),
+
+    #[error("The log level cannot be changed while running")]
+    LogLevelUnavailable,
+
+    #[error("Advisor error: {0}")]
+    Advisor(String),
+
+    #[error("The audit log key is not set up")]
+    AuditUnavailable,
+
+    #[error(transparent)]
+    Recording(#[from] RecordingError),
+
+    #[error("No session is being recorded")]
+    NotRecording,
+
+    #[error("The data is being edited in another window ({0})")]
+    ReadOnly(String),
+
+    #[error("The uploaded backup is empty")]
+    EmptyBackup,
+
+    #[error(transparent)]
+    Share(#[from] ShareError),
+
+    #[error("Sharing is not set up")]
+    SharingUnavailable,
+
+    #[error(transparent)]
+    Onboarding(#[from] OnboardingError),
+
+    #[error(transparent)]
+    Note(#[from] NoteError),
+
+    #[error(transparent)]
+    WebPush(#[from] WebPushError),
+
+    #[error("Web Push is not set up")]
+    PushUnavailable,
+
+    #[error("Invalid point in time: {0}")]
+    InvalidAsOf(String),
+
+    #[error("No data had been saved by {0}")]
+    NoDataAsOf(String),
+
+    #[error(transparent)]
+    Bulk(#[from] BulkError),
+}
+
+impl HandlerError {
+    /// Catalog message describing this error to the user
+    pub fn message(&self) -> Message {
+        match self {
+            Self::Repl(e) => match e {
+                LeanReplError::StartFailed(detail) => {
+                    Message::new("error.advisorStartFailed").arg("detail", detail)
+                }
+                LeanReplError::NotRunning => Message::new("error.advisorNotRunning"),
+                LeanReplError::Timeout => Message::new("error.advisorTimeout"),
+                _ => Message::new("error.advisorFailed").arg("detail", e),
+            },
+            Self::Storage(StorageError::InvalidName(name)) => {
+                Message::new("error.invalidDatasetName").arg("name", name)
+            }
+            Self::Storage(StorageError::FieldCrypto(e)) => match e {
+                FieldCryptoError::Locked => Message::new("error.fieldsLocked"),
+                FieldCryptoError::WrongKey => Message::new("error.fieldKeyMismatch"),
+                FieldCryptoError::InvalidKey => Message::new("error.invalidFieldKey"),
+                FieldCryptoError::WrongPassword => Message::new("error.wrongPassword"),
+                FieldCryptoError::WeakPassword => {
+                    Message::new("error.weakPassword").arg("min", field_crypto::MIN_PASSWORD_LEN)
+                }
+                _ => Message::new("error.fieldEncryption").arg("detail", e),
+            },
+            Self::Storage(StorageError::Conflict(conflict)) => Message::new("error.dataConflict")
+                .arg("expected", conflict.expected)
+                .arg("current", conflict.current),
+            Self::Storage(e) => Message::new("error.storage").arg("detail", e),
+            Self::Export(e) => Message::new("error.export").arg("detail", e),
+            Self::Import(e) => match e {
+                ImportError::Json(detail) => Message::new("error.importJson").arg("detail", detail),
+                ImportError::Csv(detail) => Message::new("error.importCsv").arg("detail", detail),
+                ImportError::UnsupportedFile(file) => {
+                    Message::new("error.importUnsupported").arg("file", file)
+                }
+                ImportError::NotLegacyExport => Message::new("error.importNotLegacy"),
+            },
+            Self::SheetImport(e) => match e {
+                SheetImportError::InvalidUrl(_) => Message::new("error.sheetInvalidUrl"),
+                SheetImportError::TooLarge => Message::new("error.importTooLarge")
+                    .arg("limit", sheet_import::MAX_CSV_BYTES / (1024 * 1024)),
+                SheetImportError::NotCsv => Message::new("error.sheetNotShared"),
+                SheetImportError::UnknownMapping(name) => {
+                    Message::new("error.unknownColumnMapping").arg("name", name)
+                }
+                SheetImportError::Storage(e) => Message::new("error.storage").arg("detail", e),
+                SheetImportError::Http(e) => Message::new("error.sheetFetch").arg("detail", e),
+            },
+            Self::Migration(MigrationError::TooNew { found, supported }) => {
+                Message::new("error.dataTooNew")
+                    .arg("found", found)
+                    .arg("supported", supported)
+            }
+            Self::Migration(MigrationError::IncompatibleAdvisor { advisor, required }) => {
+                Message::new("error.advisorIncompatible")
+                    .arg("advisor", advisor)
+                    .arg("required", required)
+            }
+            Self::Settings(SettingsError::Invalid { field, reason }) => {
+                Message::new("error.invalidSetting")
+                    .arg("field", field)
+                    .arg("reason", reason)
+            }
+            Self::Settings(SettingsError::Storage(e)) => {
+                Message::new("error.storage").arg("detail", e)
+            }
+            Self::Support(e) => Message::new("error.supportBundle").arg("detail", e),
+            Self::Transfer(e) => match e {
+                TransferError::InvalidCode => Message::new("error.transferInvalidCode"),
+                TransferError::Corrupt => Message::new("error.transferCorrupt"),
+                _ => Message::new("error.transfer").arg("detail", e),
+            },
+            Self::OsReminders(OsReminderError::Unsupported) => {
+                Message::new("error.osRemindersUnsupported")
+            }
+            Self::OsReminders(e) => Message::new("error.osReminders").arg("detail", e),
+            Self::Currency(CurrencyError::InvalidRate(code)) => {
+                Message::new("error.invalidRate").arg("currency", code)
+            }
+            Self::Currency(e) => Message::new("error.exchangeRates").arg("detail", e),
+            Self::History(HistoryError::UnknownEntry(id)) => {
+                Message::new("error.unknownHistoryEntry").arg("id", id)
+            }
+            Self::History(HistoryError::NoInputs(id)) => {
+                Message::new("error.noRecommendationInputs").arg("id", id)
+            }
+            Self::History(HistoryError::Storage(e)) => {
+                Message::new("error.storage").arg("detail", e)
+            }
+            Self::Simulation(SimulationError::UnknownSchool {
+                scenario,
+                school_id,
+            }) => Message::new("error.simulationUnknownSchool")
+                .arg("scenario", scenario)
+                .arg("id", school_id),
+            Self::Simulation(SimulationError::TooManyScenarios) => {
+                Message::new("error.tooManyScenarios").arg("max", simulation::MAX_SCENARIOS)
+            }
+            Self::Template(e) => match e {
+                TemplateError::Storage(e) => Message::new("error.storage").arg("detail", e),
+                TemplateError::UnknownTemplate(id) => {
+                    Message::new("error.unknownTemplate").arg("id", id)
+                }
+                TemplateError::InvalidExamDate(day) => {
+                    Message::new("error.invalidExamDate").arg("day", day)
+                }
+                TemplateError::InvalidCatalog(detail) => {
+                    Message::new("error.invalidTemplateCatalog").arg("detail", detail)
+                }
+            },
+            Self::Calendar(e) => match e {
+                CalendarError::Storage(e) => Message::new("error.storage").arg("detail", e),
+                CalendarError::InvalidCalendar(detail) => {
+                    Message::new("error.invalidHolidayCalendar").arg("detail", detail)
+                }
+            },
+            Self::Payment(e) => match e {
+                PaymentError::UnknownSchool(id) => {
+                    Message::new("error.paymentUnknownSchool").arg("id", id)
+                }
+                PaymentError::UnknownInstallment {
+                    school_id,
+                    installment,
+                } => Message::new("error.unknownInstallment")
+                    .arg("id", school_id)
+                    .arg("installment", installment),
+                PaymentError::InvalidDate(day) => {
+                    Message::new("error.invalidPaymentDate").arg("day", day)
+                }
+            },
+            Self::DataDir(e) => match e {
+                DataDirError::NotEmpty(path) => {
+                    Message::new("error.dataDirNotEmpty").arg("path", path.display())
+                }
+                DataDirError::NotAbsolute(path)
+                | DataDirError::Nested(path)
+                | DataDirError::SameDir(path) => {
+                    Message::new("error.dataDirInvalid").arg("path", path.display())
+                }
+                _ => Message::new("error.dataDirMove").arg("detail", e),
+            },
+            Self::NoStorage => Message::new("error.noStorage"),
+            Self::NoData => Message::new("error.noData"),
+            Self::UnknownImport => Message::new("error.unknownImport"),
+            Self::InvalidInstallmentPlan => {
+                Message::new("error.invalidInstallmentPlan").arg("max", InstallmentPlan::MAX_COUNT)
+            }
+            Self::Outcome(OutcomeError::UnknownSchool(id)) => {
+                Message::new("error.outcomeUnknownSchool").arg("id", id)
+            }
+            Self::InvalidRecurringFee(label) => {
+                Message::new("error.invalidRecurringFee").arg("label", label)
+            }
+            Self::InvalidPatch(detail) => Message::new("error.invalidPatch").arg("detail", detail),
+            Self::UnknownBackup(name) => Message::new("error.unknownBackup").arg("name", name),
+            Self::UnknownExplanation(_) => Message::new("error.unknownExplanation"),
+            Self::Log(LogError::UnknownLevel(name)) => {
+                Message::new("error.unknownLogLevel").arg("name", name)
+            }
+            Self::Log(LogError::InvalidFilter(detail)) => {
+                Message::new("error.invalidLogFilter").arg("detail", detail)
+            }
+            Self::Log(e) => Message::new("error.unexpected").arg("detail", e),
+            Self::LogLevelUnavailable => Message::new("error.logLevelUnavailable"),
+            Self::Advisor(detail) => Message::new("error.advisor").arg("detail", detail),
+            Self::AuditUnavailable => Message::new("error.auditUnavailable"),
+            Self::Recording(e) => Message::new("error.recording").arg("detail", e),
+            Self::NotRecording => Message::new("error.notRecording"),
+            Self::ReadOnly(holder) => Message::new("error.readOnly").arg("holder", holder),
+            Self::EmptyBackup => Message::new("error.emptyBackup"),
+            Self::Share(ShareError::InvalidDays(days)) => Message::new("error.invalidShareDays")
+                .arg("days", days)
+                .arg("max", MAX_SHARE_DAYS),
+            Self::Share(ShareError::InvalidToken) => Message::new("error.invalidShareLink"),
+            Self::Share(ShareError::Expired(at)) => {
+                Message::new("error.shareExpired").arg("at", at)
+            }
+            Self::Share(ShareError::Storage(e)) => Message::new("error.storage").arg("detail", e),
+            Self::SharingUnavailable => Message::new("error.sharingUnavailable"),
+            Self::Onboarding(OnboardingError::OutOfOrder(step)) => {
+                Message::new("error.onboardingOutOfOrder").arg("step", step)
+            }
+            Self::Note(e) => match e {
+                NoteError::UnknownSchool(id) => {
+                    Message::new("error.noteUnknownSchool").arg("id", id)
+                }
+                NoteError::UnknownInstallment {
+                    school_id,
+                    installment,
+                } => Message::new("error.unknownInstallment")
+                    .arg("id", school_id)
+                    .arg("installment", installment),
+                NoteError::UnknownNote { .. } => Message::new("error.unknownNote"),
+                NoteError::Empty => Message::new("error.emptyNote"),
+                NoteError::TooLong => {
+                    Message::new("error.noteTooLong").arg("max", notes::MAX_NOTE_CHARS)
+                }
+            },
+            Self::WebPush(WebPushError::InvalidSubscription(detail)) => {
+                Message::new("error.invalidPushSubscription").arg("detail", detail)
+            }
+            Self::WebPush(WebPushError::Storage(e)) => {
+                Message::new("error.storage").arg("detail", e)
+            }
+            Self::WebPush(e) => Message::new("error.push").arg("detail", e),
+            Self::PushUnavailable => Message::new("error.pushUnavailable"),
+            Self::InvalidAsOf(at) => Message::new("error.invalidAsOf").arg("at", at),
+            Self::NoDataAsOf(at) => Message::new("error.noDataAsOf").arg("at", at),
+            Self::Bulk(BulkError::TooMany) => {
+                Message::new("error.bulkTooMany").arg("max", bulk::MAX_BULK_OPS)
+            }
+            Self::Bulk(BulkError::Failed { index, error }) => Message::new("error.bulkFailed")
+                .arg("position", index + 1)
+                .arg("detail", error),
+        }
+    }
+
+    /// This error as shown to a user of `locale`
+    pub fn localize(&self, locale: Locale) -> LocalizedError {
+        let error = LocalizedError::new(&self.message(), locale, self);
+        match self {
+            Self::Storage(StorageError::Conflict(conflict)) => {
+                error.with_data(serde_json::to_value(conflict).unwrap_or_default())
+            }
+            _ => error,
+        }
+    }
+}
 
 /// Shared state for the application
 pub struct AppState {
     pub lean_repl: Mutex<LeanRepl>,
+    pub config: AppConfig,
+    pub webhooks: WebhookDispatcher,
+    pub notifiers: NotifierSet,
+    pub reminders: Mutex<ReminderState>,
+    /// User settings from `settings.json`
+    pub settings: Mutex<Settings>,
+    pub storage: Option<Storage>,
+    /// Previewed imports awaiting confirmation, keyed by token
+    pub pending_imports: Mutex<HashMap<String, PendingImport>>,
+    /// Fetched explanations of recommendations
+    explanations: Mutex<ExplanationCache>,
+    /// Advisor results kept across launches
+    result_cache: Mutex<ResultCache>,
+    /// `explain` requests for recommendations not explained yet, by id
+    pending_explanations: Mutex<HashMap<String, JsonRpcRequest>>,
+    /// When rates were last fetched automatically
+    rates_refreshed_at: Mutex<Option<Instant>>,
+    /// Changes the log level when the settings do
+    log_level: Option<LogLevelHandle>,
+    /// Recent log events for the diagnostics screen
+    log_buffer: LogBuffer,
+    /// Phase timings of advisor requests, while profiling
+    pub profiler: Profiler,
+    /// Calls, errors and latency of each advisor method
+    call_stats: CallStats,
+    /// Advisor status changes, crashes and restarts
+    health_history: std::sync::Mutex<HealthHistory>,
+    advisor_status: watch::Sender<AdvisorStatus>,
+    countdown: watch::Sender<Countdown>,
+    /// Signs audit log entries; nothing is audited without it
+    audit_key: Option<FieldKey>,
+    /// Seals the scheduled cloud backups; none are pushed without it
+    backup_key: Option<FieldKey>,
+    /// Signs share links; nothing can be shared without it
+    share_key: Option<FieldKey>,
+    /// Sends Web Push messages; browsers cannot subscribe without it
+    pub web_push: Option<Arc<WebPush>>,
+    /// Session being recorded for a bug report
+    recording: std::sync::Mutex<Option<Session>>,
+    /// Responses preloaded at startup
+    pub preload: PreloadCache,
+    /// Extra advisor processes for busy times, started when needed
+    repl_pool: std::sync::Mutex<Arc<ReplPool>>,
+    /// This instance's side of the lock shared with other instances
+    edit_lock: EditLock,
+}
+
+impl AppState {
+    pub fn new(lean_repl: LeanRepl) -> Self {
+        let limits = Settings::default().advisor.pool_limits();
+        let repl_pool = Arc::new(ReplPool::like(&lean_repl, limits));
+        Self {
+            lean_repl: Mutex::new(lean_repl),
+            config: AppConfig::default(),
+            webhooks: WebhookDispatcher::default(),
+            notifiers: NotifierSet::default(),
+            reminders: Mutex::new(ReminderState::default()),
+            settings: Mutex::new(Settings::default()),
+            storage: None,
+            pending_imports: Mutex::new(HashMap::new()),
+            explanations: Mutex::new(ExplanationCache::default()),
+            result_cache: Mutex::new(ResultCache::default()),
+            pending_explanations: Mutex::new(HashMap::new()),
+            rates_refreshed_at: Mutex::new(None),
+            log_level: None,
+            log_buffer: LogBuffer::default(),
+            profiler: Profiler::default(),
+            call_stats: CallStats::default(),
+            health_history: std::sync::Mutex::new(HealthHistory::default()),
+            advisor_status: watch::Sender::new(AdvisorStatus::new(
+                AdvisorState::Starting,
+                "Waiting for first advisor response",
+            )),
+            countdown: watch::Sender::new(Countdown::default()),
+            audit_key: None,
+            backup_key: None,
+            share_key: None,
+            web_push: None,
+            recording: std::sync::Mutex::new(None),
+            preload: PreloadCache::default(),
+            repl_pool: std::sync::Mutex::new(repl_pool),
+            edit_lock: EditLock::default(),
+        }
+    }
+
+    /// Apply configuration loaded from `config.toml`
+    pub fn with_config(mut self, config: AppConfig) -> Self {
+        self.webhooks = WebhookDispatcher::new(config.webhooks.clone());
+        self.notifiers = NotifierSet::new(&config.notifiers);
+        self.profiler = Profiler::new(&config.profiling);
+        self.config = config;
+        self
+    }
+
+    /// Apply saved user settings
+    pub fn with_settings(self, settings: Settings) -> Self {
+        let mut repl = self.lean_repl.try_lock().expect("state is not shared yet");
+        repl.set_request_timeout(settings.advisor.request_timeout());
+        self.rebuild_repl_pool(&repl, settings.advisor.pool_limits());
+        drop(repl);
+        if let Some(handle) = &self.log_level {
+            handle.set_level(settings.log_level());
+        }
+        if settings.record_sessions {
+            *self.recording.lock().unwrap_or_else(|e| e.into_inner()) = Some(Session::start());
+        }
+        *self.settings.try_lock().expect("state is not shared yet") = settings;
+        self
+    }
+
+    /// Sign audit log entries with `key`
+    pub fn with_audit_key(mut self, key: FieldKey) -> Self {
+        self.audit_key = Some(key);
+        self
+    }
+
+    /// Seal cloud backups with `key`
+    pub fn with_backup_key(mut self, key: FieldKey) -> Self {
+        self.backup_key = Some(key);
+        self
+    }
+
+    pub(crate) fn backup_key(&self) -> Option<&FieldKey> {
+        self.backup_key.as_ref()
+    }
+
+    /// Sign share links with `key`
+    pub fn with_share_key(mut self, key: FieldKey) -> Self {
+        self.share_key = Some(key);
+        self
+    }
+
+    /// Send Web Push messages with `push`
+    pub fn with_web_push(mut self, push: WebPush) -> Self {
+        self.web_push = Some(Arc::new(push));
+        self
+    }
+
+    /// Name this instance `label` to other instances sharing its data
+    pub fn with_edit_lock(mut self, label: &str) -> Self {
+        self.edit_lock = EditLock::new(label);
+        self
+    }
+
+    /// Fail if another instance holds the edit lock on the persisted data
+    pub fn check_writable(&self) -> Result<(), HandlerError> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        let status = self.edit_lock.status(storage, &date::now().to_rfc3339())?;
+        match status.holder {
+            Some(holder) if status.read_only => Err(HandlerError::ReadOnly(holder.label)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Append an entry to the audit log, if it is set up.
+    ///
+    /// The change being audited has already been saved, so a failure is
+    /// logged rather than returned.
+    fn audit(&self, action: AuditAction, details: serde_json::Value) {
+        let (Some(storage), Some(key)) = (&self.storage, &self.audit_key) else {
+            return;
+        };
+        if let Err(e) = audit::record(storage, key, action, details) {
+            tracing::warn!("Failed to write audit log entry: {}", e);
+        }
+    }
+
+    /// Add an exchange to the session being recorded, if any
+    fn record_exchange(
+        &self,
+        request: &JsonRpcRequest,
+        result: &Result<JsonRpcResponse, LeanReplError>,
+    ) {
+        let mut recording = self.recording.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(session) = recording.as_mut() else {
+            return;
+        };
+        let data = self
+            .load_dataset_if_saved()
+            .inspect_err(|e| tracing::warn!("Recording without the saved data: {}", e))
+            .ok()
+            .flatten();
+        session.record(request, result.as_ref().map_err(|e| e.to_string()), data.as_ref());
+    }
+
+    fn repl_pool(&self) -> Arc<ReplPool> {
+        self.repl_pool.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Start pooled processes like `repl` from now on, within `limits`;
+    /// those of the previous pool stop once they have answered
+    fn rebuild_repl_pool(&self, repl: &LeanRepl, limits: PoolLimits) {
+        *self.repl_pool.lock().unwrap_or_else(|e| e.into_inner()) =
+            Arc::new(ReplPool::like(repl, limits));
+    }
+
+    /// Serve recent log events from `buffer`, the layer given to tracing
+    pub fn with_log_buffer(mut self, buffer: LogBuffer) -> Self {
+        self.log_buffer = buffer;
+        self
+    }
+
+    /// Apply the log level of the settings now and whenever they change
+    pub fn with_log_level(mut self, handle: LogLevelHandle) -> Self {
+        handle.set_level(
+            self.settings
+                .try_lock()
+                .expect("state is not shared yet")
+                .log_level(),
+        );
+        self.log_level = Some(handle);
+        self
+    }
+
+    /// Locale for user-facing messages, from the settings
+    pub async fn locale(&self) -> Locale {
+        self.settings.lock().await.locale()
+    }
+
+    /// Use the given storage for the persisted dataset
+    pub fn with_storage(mut self, storage: Storage) -> Self {
+        match calendar::load(Some(&storage)) {
+            Ok(loaded) => calendar::install(loaded),
+            Err(e) => tracing::warn!("Failed to load imported holidays: {}", e),
+        }
+        match ExplanationCache::load(&storage) {
+            Ok(cache) => *self.explanations.get_mut() = cache,
+            Err(e) => tracing::warn!("Failed to load recommendation explanations: {}", e),
+        }
+        match ResultCache::load(&storage) {
+            Ok(cache) => *self.result_cache.get_mut() = cache,
+            Err(e) => tracing::warn!("Failed to load saved advisor results: {}", e),
+        }
+        match HealthHistory::load(&storage) {
+            Ok(history) => {
+                *self
+                    .health_history
+                    .get_mut()
+                    .unwrap_or_else(|e| e.into_inner()) = history
+            }
+            Err(e) => tracing::warn!("Failed to load the health history: {}", e),
+        }
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Load the persisted dataset
+    pub fn load_dataset(&self) -> Result<serde_json::Value, HandlerError> {
+        let storage = self.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+        storage.load(SCHOOLS_DATA_FILE)?.ok_or(HandlerError::NoData)
+    }
+
+    /// Load the persisted dataset, treating a missing file as no data
+    fn load_dataset_if_saved(&self) -> Result<Option<serde_json::Value>, HandlerError> {
+        match self.load_dataset() {
+            Ok(data) => Ok(Some(data)),
+            Err(HandlerError::NoData) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Current advisor availability
+    pub fn advisor_status(&self) -> AdvisorStatus {
+        self.advisor_status.borrow().clone()
+    }
+
+    /// Record advisor availability, waking subscribers only if it changed
+    pub fn set_advisor_status(&self, status: AdvisorStatus) {
+        let event = HealthEvent::status_changed(&status);
+        let changed = self.advisor_status.send_if_modified(|current| {
+            if *current == status {
+                return false;
+            }
+            tracing::info!("Advisor status: {:?} {:?}", status.state, status.reason);
+            *current = status;
+            true
+        });
+        if changed {
+            self.record_health(event);
+        }
+    }
+
+    /// Add `event` to the health history and save it
+    pub fn record_health(&self, event: HealthEvent) {
+        let mut history = self
+            .health_history
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        history.record(event);
+        if let Some(storage) = &self.storage {
+            if let Err(e) = history.save(storage) {
+                tracing::warn!("Failed to save the health history: {}", e);
+            }
+        }
+    }
+
+    /// Record that the advisor crashed and tell webhooks and notifiers
+    fn advisor_crashed(&self, reason: String) {
+        self.record_health(HealthEvent::new(
+            HealthEventKind::Crashed,
+            None,
+            Some(reason.clone()),
+        ));
+        self.notify(WebhookEvent::ReplCrashed { reason });
+    }
+
+    /// Watch advisor availability changes
+    pub fn subscribe_advisor_status(&self) -> watch::Receiver<AdvisorStatus> {
+        self.advisor_status.subscribe()
+    }
+
+    /// Latest deadline countdown, empty until first computed
+    pub fn countdown(&self) -> Countdown {
+        self.countdown.borrow().clone()
+    }
+
+    /// Publish the deadline countdown, waking subscribers only if it changed
+    pub fn set_countdown(&self, countdown: Countdown) {
+        self.countdown.send_if_modified(|current| {
+            if *current == countdown {
+                return false;
+            }
+            *current = countdown;
+            true
+        });
+    }
+
+    /// Watch deadline countdown changes
+    pub fn subscribe_countdown(&self) -> watch::Receiver<Countdown> {
+        self.countdown.subscribe()
+    }
+
+    /// Check whether any webhook or chat target is configured
+    pub fn has_notification_targets(&self) -> bool {
+        !self.webhooks.is_empty() || !self.notifiers.is_empty()
+    }
+
+    /// Deliver an event to webhooks and chat notifiers
+    pub fn notify(&self, event: WebhookEvent) {
+        self.notifiers.dispatch(&event);
+        self.webhooks.dispatch(event);
+    }
+}
+
+/// Send an RPC request to the Lean REPL
+pub async fn send_rpc(
+    state: Arc<AppState>,
+    request: JsonRpcRequest,
+) -> Result<JsonRpcResponse, LeanReplError> {
+    state.preload.cancel();
+    let mut timings = PhaseTimings::default();
+    let started = Instant::now();
+    if let Some(response) = state.preload.take(&request) {
+        timings.add(Phase::Cache, started.elapsed());
+        tracing::debug!("Answering {} from the preload", request.method);
+        return finish_rpc(state, request, Ok(response), timings).await;
+    }
+    // While the main advisor is busy, a free pooled one answers instead
+    if state.lean_repl.try_lock().is_err() {
+        if let Some(pooled) = state.repl_pool().try_send(&request).await {
+            let waited = started.elapsed().saturating_sub(pooled.timings.total());
+            timings.add(Phase::Queueing, waited);
+            for phase in Phase::ALL {
+                timings.add(phase, pooled.timings.get(phase));
+            }
+            return finish_rpc(state, request, pooled.result, timings).await;
+        }
+    }
+    let mut repl = state.lean_repl.lock().await;
+    timings.add(Phase::Queueing, started.elapsed());
+
+    // Log for debugging; the params are only parsed when logged
+    if request.method == "getWeeklyRecommendations" && tracing::enabled!(tracing::Level::INFO) {
+        tracing::info!("=== Weekly Recommendations Request ===");
+        let params = request.params_value();
+        if let Some(start_day) = params.get("startDay") {
+            tracing::info!("startDay: {}", start_day);
+        }
+        if let Some(states) = params.get("states") {
+            tracing::info!("states: {}", serde_json::to_string_pretty(states).unwrap_or_default());
+        }
+    }
+
+    if repl.has_exited() {
+        state.advisor_crashed("Advisor process exited unexpectedly".to_string());
+    }
+
+    let result = repl.send_request_timed(&request, &mut timings);
+    drop(repl);
+    finish_rpc(state, request, result, timings).await
+}
+
+/// Send a what-if request to the advisor, like [`send_rpc`] but without
+/// taking the answer for the user's plan.
+///
+/// The preload is neither used nor cancelled, and the response is not seen
+/// by the reminder engine, the result cache, the history or the
+/// notifications. Only the recording, the advisor status and the call
+/// statistics see the exchange. Simulations go through here, so a
+/// scenario's schools never reach deadline alerts or the cached plan.
+pub async fn send_rpc_isolated(
+    state: &AppState,
+    request: JsonRpcRequest,
+) -> Result<JsonRpcResponse, LeanReplError> {
+    let mut timings = PhaseTimings::default();
+    let started = Instant::now();
+    if state.lean_repl.try_lock().is_err() {
+        if let Some(pooled) = state.repl_pool().try_send(&request).await {
+            let waited = started.elapsed().saturating_sub(pooled.timings.total());
+            timings.add(Phase::Queueing, waited);
+            for phase in Phase::ALL {
+                timings.add(phase, pooled.timings.get(phase));
+            }
+            return settle_rpc(state, &request, pooled.result, timings);
+        }
+    }
+    let mut repl = state.lean_repl.lock().await;
+    timings.add(Phase::Queueing, started.elapsed());
+    if repl.has_exited() {
+        state.advisor_crashed("Advisor process exited unexpectedly".to_string());
+    }
+    let result = repl.send_request_timed(&request, &mut timings);
+    drop(repl);
+    settle_rpc(state, &request, result, timings)
+}
+
+/// Bookkeeping every advisor exchange gets, isolated or not
+fn settle_rpc(
+    state: &AppState,
+    request: &JsonRpcRequest,
+    result: Result<JsonRpcResponse, LeanReplError>,
+    timings: PhaseTimings,
+) -> Result<JsonRpcResponse, LeanReplError> {
+    state.record_exchange(request, &result);
+    update_advisor_status(state, &result);
+    count_call(state, request, &result, timings);
+    result
+}
+
+/// Follow the advisor's availability from the outcome of an exchange
+fn update_advisor_status(state: &AppState, result: &Result<JsonRpcResponse, LeanReplError>) {
+    match result {
+        Ok(_) => state.set_advisor_status(AdvisorStatus::running()),
+        Err(e) => {
+            state.set_advisor_status(AdvisorStatus::from_error(e));
+            if let LeanReplError::ReceiveFailed(reason) = e {
+                state.advisor_crashed(reason.clone());
+            }
+        }
+    }
+}
+
+/// Add an exchange to the call statistics and the profile
+fn count_call(
+    state: &AppState,
+    request: &JsonRpcRequest,
+    result: &Result<JsonRpcResponse, LeanReplError>,
+    timings: PhaseTimings,
+) {
+    let failed = result.as_ref().map_or(true, |r| r.error.is_some());
+    state
+        .call_stats
+        .record(&request.method, timings.total(), failed);
+    state.profiler.record(&request.method, timings);
+}
+
+/// Update the advisor status and observe the response to `request`
+async fn finish_rpc(
+    state: Arc<AppState>,
+    request: JsonRpcRequest,
+    mut result: Result<JsonRpcResponse, LeanReplError>,
+    mut timings: PhaseTimings,
+) -> Result<JsonRpcResponse, LeanReplError> {
+    // Before the response is amended below, so a replay can amend it again
+    state.record_exchange(&request, &result);
+    update_advisor_status(&state, &result);
+
+    if let Ok(response) = &mut result {
+        if request.method == "getRecommendation" {
+            let started = Instant::now();
+            prefetch_explanations(&state, &request, response).await;
+            timings.add(Phase::Cache, started.elapsed());
+        }
+        observe_response(&state, &request, response).await;
+        save_result(&state, &request, response).await;
+    }
+    count_call(&state, &request, &result, timings);
+
+    result
+}
+
+/// Keep a successful result to show on the next launch, see
+/// [`result_cache`](crate::result_cache)
+pub(crate) async fn save_result(
+    state: &AppState,
+    request: &JsonRpcRequest,
+    response: &JsonRpcResponse,
+) {
+    if response.error.is_some() {
+        return;
+    }
+    let Some(result) = response.result_value() else {
+        return;
+    };
+    let mut cache = state.result_cache.lock().await;
+    if !cache.insert(request, result, &date::now().to_rfc3339()) {
+        return;
+    }
+    if let Some(storage) = &state.storage {
+        if let Err(e) = cache.save(storage) {
+            tracing::warn!("Failed to save the advisor result: {}", e);
+        }
+    }
+}
+
+/// The result last computed for `request`, with how long ago, to show
+/// while the advisor computes it again
+pub async fn cached_result(
+    state: Arc<AppState>,
+    request: &JsonRpcRequest,
+) -> Option<CachedResult> {
+    state
+        .result_cache
+        .lock()
+        .await
+        .get(request, &date::now().to_rfc3339())
+}
+
+/// Give the actions in a `getRecommendation` response their explanation
+/// ids, and fetch the explanations not cached yet in the background
+async fn prefetch_explanations(
+    state: &Arc<AppState>,
+    request: &JsonRpcRequest,
+    response: &mut JsonRpcResponse,
+) {
+    let Some(mut result) = response.result_value() else {
+        return;
+    };
+    let params = request.params_value();
+    let mut missing = Vec::new();
+    {
+        let cache = state.explanations.lock().await;
+        let mut pending = state.pending_explanations.lock().await;
+        if pending.len() >= explanations::MAX_ENTRIES {
+            pending.clear();
+        }
+        for (id, explain) in explanations::annotate(&params, &mut result) {
+            if cache.get(&id).is_none() && !pending.contains_key(&id) {
+                pending.insert(id.clone(), explain);
+                missing.push(id);
+            }
+        }
+    }
+    response.set_result(&result);
+    if missing.is_empty() {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        for id in missing {
+            if let Err(e) = explanation(&state, &id).await {
+                tracing::warn!("Failed to fetch the explanation of {}: {}", id, e);
+            }
+        }
+    });
+}
+
+/// Explanation `id`, from the cache or else asked of the advisor
+async fn explanation(state: &AppState, id: &str) -> Result<Explanation, HandlerError> {
+    if let Some(explanation) = state.explanations.lock().await.get(id) {
+        return Ok(explanation.clone());
+    }
+    let request = state
+        .pending_explanations
+        .lock()
+        .await
+        .get(id)
+        .cloned()
+        .ok_or_else(|| HandlerError::UnknownExplanation(id.to_string()))?;
+    let response = send_rpc_isolated(state, request).await?;
+    if let Some(error) = response.error {
+        return Err(HandlerError::Advisor(error.message));
+    }
+    let explanation: Explanation = response
+        .parse_result()
+        .map_err(|e| HandlerError::Advisor(e.to_string()))?;
+
+    state.pending_explanations.lock().await.remove(id);
+    let mut cache = state.explanations.lock().await;
+    cache.insert(id.to_string(), explanation.clone());
+    if let Some(storage) = &state.storage {
+        cache.save(storage)?;
+    }
+    Ok(explanation)
+}
+
+/// Why the advisor recommends the action with explanation id `id`.
+///
+/// Explanations are fetched when the recommendation is made, so this is
+/// normally served from the cache.
+pub async fn get_explanation(
+    state: Arc<AppState>,
+    id: String,
+) -> Result<Explanation, HandlerError> {
+    explanation(&state, &id).await
+}
+
+/// The day a `getRecommendation` or `getWeeklyRecommendations` request is for
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestDay {
+    today: Option<u64>,
+    start_day: Option<u64>,
+}
+
+/// Feed a completed advisor exchange to the reminder engine and notifiers
+async fn observe_response(state: &AppState, request: &JsonRpcRequest, response: &JsonRpcResponse) {
+    let today = request
+        .parse_params::<RequestDay>()
+        .ok()
+        .and_then(|day| day.today.or(day.start_day));
+
+    let lead_days = i64::from(state.settings.lock().await.reminder_lead_days);
+    let (changed, deadlines) = {
+        let mut reminders = state.reminders.lock().await;
+        let changed = reminders.observe(request, response);
+        let deadlines = match today {
+            Some(today) if state.has_notification_targets() => {
+                reminders.fresh_deadlines(reminders::upcoming_deadlines(
+                    &request.params_value(),
+                    today as u32,
+                    lead_days,
+                ))
+            }
+            _ => Vec::new(),
+        };
+        (changed, deadlines)
+    };
+
+    if let (Some(today), "getRecommendation") = (today, request.method.as_str()) {
+        record_history(state, today as u32, request, response);
+    }
+
+    for deadline in deadlines {
+        state.notify(WebhookEvent::DeadlineApproaching(deadline));
+    }
+    if let Some((previous, current)) = changed {
+        let alerts = if state.has_notification_targets() {
+            budget_alerts(state, &current)
+        } else {
+            Vec::new()
+        };
+        state.notify(WebhookEvent::RecommendationChanged { previous, current });
+        for alert in alerts {
+            state.notify(WebhookEvent::BudgetExceeded(alert));
+        }
+    }
+}
+
+/// Budget limits the recommended `action` would go beyond, judged against
+/// the persisted dataset; failures are only logged
+pub(crate) fn budget_alerts(state: &AppState, action: &serde_json::Value) -> Vec<BudgetAlert> {
+    let Ok(action) = serde_json::from_value(action.clone()) else {
+        return Vec::new();
+    };
+    let checked = state.load_dataset_if_saved().and_then(|data| {
+        let Some(dataset) = data.and_then(|data| Dataset::from_value(data).ok()) else {
+            return Ok(Vec::new());
+        };
+        let rates = match &state.storage {
+            Some(storage) => ExchangeRates::load(storage)?,
+            None => ExchangeRates::default(),
+        };
+        Ok(budget::check_action(&dataset, &rates, &action))
+    });
+    checked.unwrap_or_else(|e| {
+        tracing::warn!("Failed to check the budget: {}", e);
+        Vec::new()
+    })
+}
+
+/// Append a recommendation to the history; failures are only logged
+fn record_history(
+    state: &AppState,
+    today: u32,
+    request: &JsonRpcRequest,
+    response: &JsonRpcResponse,
+) {
+    let Some(storage) = &state.storage else {
+        return;
+    };
+    let Ok(recommendation) = response.parse_result::<GetRecommendationResult>() else {
+        return;
+    };
+    let recorded = History::load(storage).and_then(|mut history| {
+        let now = date::now().to_rfc3339();
+        let params = request.params_value();
+        if history.record(today, &params, recommendation, &now) {
+            history.save(storage)?;
+            let mut inputs = HistoryInputs::load(storage)?;
+            let id = history.next_id;
+            inputs.insert(id, params, &history);
+            inputs.save(storage)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record recommendation history: {}", e);
+    }
+}
+
+/// Default number of batch entries dispatched concurrently
+pub const DEFAULT_BATCH_PARALLELISM: usize = 4;
+
+/// Most requests accepted in one batch
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Send several RPC requests and return their responses in request order.
+///
+/// Entries are dispatched with at most `max_parallel` in flight. A failing
+/// entry does not abort the batch; it is reported as an internal error
+/// response carrying that entry's id. A batch of more than
+/// [`MAX_BATCH_SIZE`] requests is not run at all and is answered with a
+/// single "Invalid Request" error.
+pub async fn send_rpc_batch(
+    state: Arc<AppState>,
+    requests: Vec<JsonRpcRequest>,
+    max_parallel: usize,
+) -> Vec<JsonRpcResponse> {
+    if requests.len() > MAX_BATCH_SIZE {
+        return vec![JsonRpcResponse::error(
+            serde_json::Value::Null,
+            -32600,
+            format!(
+                "Batch of {} requests exceeds the limit of {}",
+                requests.len(),
+                MAX_BATCH_SIZE
+            ),
+        )];
+    }
+    let semaphore = Arc::new(Semaphore::new(max_parallel.max(1)));
+    let mut tasks = JoinSet::new();
+
+    for (index, request) in requests.iter().cloned().enumerate() {
+        let state = state.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let id = request.id.clone();
+            let response = send_rpc(state, request)
+                .await
+                .unwrap_or_else(|e| JsonRpcResponse::internal_error(id, e.to_string()));
+            (index, response)
+        });
+    }
+
+    let mut responses: Vec<Option<JsonRpcResponse>> = vec![None; requests.len()];
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, response)) => responses[index] = Some(response),
+            Err(e) => tracing::error!("Batch task failed: {}", e),
+        }
+    }
+
+    responses
+        .into_iter()
+        .zip(requests)
+        .map(|(response, request)| {
+            response.unwrap_or_else(|| {
+                JsonRpcResponse::internal_error(request.id, "Batch task aborted".to_string())
+            })
+        })
+        .collect()
+}
+
+/// Send an RPC request and split the result into chunks of `chunk_size`
+/// items, see [`streaming`](crate::streaming)
+pub async fn stream_rpc(
+    state: Arc<AppState>,
+    request: JsonRpcRequest,
+    chunk_size: usize,
+) -> Result<Chunks, HandlerError> {
+    let method = request.method.clone();
+    let response = send_rpc(state, request).await?;
+    if let Some(error) = response.error {
+        return Err(HandlerError::Advisor(error.message));
+    }
+    let result = response.result_value().unwrap_or_default();
+    Ok(Chunks::from_result(&method, result, chunk_size))
+}
+
+/// Export a dataset in the given format.
+///
+/// Uses `data` when supplied, otherwise the persisted dataset.
+pub async fn export_data(
+    state: Arc<AppState>,
+    format: ExportFormat,
+    data: Option<serde_json::Value>,
+) -> Result<Vec<u8>, HandlerError> {
+    let data = match data {
+        Some(data) => data,
+        None => state.load_dataset()?,
+    };
+    Ok(export::export(&data, format)?)
+}
+
+/// Bank transfer file for the payments due this week, paid on `today`.
+///
+/// Needs the bank transfer settings; schools without a payee account are
+/// listed in the result instead.
+pub async fn export_zengin(
+    state: Arc<AppState>,
+    layout: ZenginLayout,
+    today: u32,
+) -> Result<ZenginExport, HandlerError> {
+    let requester = state
+        .settings
+        .lock()
+        .await
+        .bank_transfer
+        .clone()
+        .ok_or(ExportError::NoBankTransferSettings)?;
+    let data = state.load_dataset()?;
+    Ok(export::zengin_transfers(&data, &requester, today, layout)?)
+}
+
+/// Number of days covered by the weekly report
+pub const WEEKLY_REPORT_DAYS: u32 = 7;
+
+/// Add the charge of `school`'s payment method to each of its fees, so the
+/// advisor weighs what will actually be paid. Only fees in yen are changed,
+/// since the charges are in yen.
+fn add_method_charges(school: &mut serde_json::Value, fees: &[MethodFee]) {
+    let method = school
+        .get("paymentMethod")
+        .and_then(|m| serde_json::from_value::<PaymentMethod>(m.clone()).ok());
+    let in_yen = school.get("currency").is_none_or(|c| c == "JPY");
+    let Some(method) = method.filter(|_| in_yen) else {
+        return;
+    };
+    let add = |amount: &mut serde_json::Value| {
+        if let Some(value) = amount.as_u64() {
+            *amount = (value + method_charge(fees, method, value)).into();
+        }
+    };
+    for key in ["enrollmentFee", "tuition"] {
+        if let Some(amount) = school.get_mut(key) {
+            add(amount);
+        }
+    }
+    if let Some(installments) = school.get_mut("installments").and_then(|i| i.as_array_mut()) {
+        for installment in installments {
+            if let Some(amount) = installment.get_mut("amount") {
+                add(amount);
+            }
+        }
+    }
+}
+
+/// Build advisor parameters (`schools` plus parallel `states`) from a stored
+/// dataset, with the charges of each school's payment method in its fees
+pub fn advisor_params(data: &serde_json::Value) -> serde_json::Value {
+    let mut schools = data
+        .get("schools")
+        .and_then(|s| s.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let method_fees: Vec<MethodFee> = data
+        .get("methodFees")
+        .and_then(|f| serde_json::from_value(f.clone()).ok())
+        .unwrap_or_default();
+    if !method_fees.is_empty() {
+        for school in &mut schools {
+            add_method_charges(school, &method_fees);
+        }
+    }
+    // Notes mean nothing to the advisor and would only miss its cache
+    for school in &mut schools {
+        if let Some(school) = school.as_object_mut() {
+            school.remove("notes");
+        }
+    }
+    let states: Vec<serde_json::Value> = schools
+        .iter()
+        .map(|school| {
+            let field = |key: &str| school.get(key).cloned();
+            serde_json::json!({
+                "schoolId": field("id"),
+                "passStatus": field("passStatus").unwrap_or_else(|| "notYetAnnounced".into()),
+                "enrollmentFeePaid": field("enrollmentFeePaid").unwrap_or(false.into()),
+                "tuitionPaid": field("tuitionPaid").unwrap_or(false.into()),
+            })
+        })
+        .collect();
+    serde_json::json!({ "schools": schools, "states": states })
+}
+
+/// The advisor's result behind `report` as of `today`: the week's
+/// recommendations for the weekly report and today's recommendation for the
+/// plan report. The schedule report needs none.
+async fn report_advice(
+    state: Arc<AppState>,
+    data: &serde_json::Value,
+    report: ReportType,
+    today: u32,
+) -> Result<Option<serde_json::Value>, HandlerError> {
+    let mut params = advisor_params(data);
+    let request = match report {
+        ReportType::Schedule => return Ok(None),
+        ReportType::Weekly => {
+            params["startDay"] = today.into();
+            params["days"] = WEEKLY_REPORT_DAYS.into();
+            JsonRpcRequest::new(
+                "getWeeklyRecommendations",
+                &params,
+                serde_json::json!("weekly-report"),
+            )
+        }
+        ReportType::Plan => {
+            params["today"] = today.into();
+            JsonRpcRequest::new("getRecommendation", &params, serde_json::json!("plan-report"))
+        }
+    };
+    let response = send_rpc(state, request).await?;
+    if let Some(error) = response.error {
+        return Err(HandlerError::Advisor(error.message));
+    }
+    Ok(Some(response.result_value().unwrap_or_default()))
+}
+
+/// Render a printable PDF report of the persisted dataset.
+///
+/// The weekly report asks the advisor for recommendations starting `today`;
+/// the plan report asks for today's recommendation.
+pub async fn export_report(
+    state: Arc<AppState>,
+    report: ReportType,
+    today: u32,
+) -> Result<Vec<u8>, HandlerError> {
+    let data = state.load_dataset()?;
+    let advice = report_advice(state.clone(), &data, report, today)
+        .await?
+        .unwrap_or_default();
+    match report {
+        ReportType::Schedule => Ok(export::schedule_pdf(&data, today)?),
+        ReportType::Weekly => Ok(export::weekly_pdf(&data, &advice, today)?),
+        ReportType::Plan => {
+            let rates = match &state.storage {
+                Some(storage) => ExchangeRates::load(storage)?,
+                None => ExchangeRates::default(),
+            };
+            Ok(export::plan_pdf(&data, &rates, &advice, today)?)
+        }
+    }
+}
+
+/// Export templates the user has put in the data directory
+pub async fn list_export_templates(
+    state: Arc<AppState>,
+) -> Result<Vec<ExportTemplate>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let dir = storage.data_dir().join(TEMPLATES_DIR);
+    Ok(export::template::list(&dir).map_err(ExportError::from)?)
+}
+
+/// Render the persisted dataset with the export template `name`.
+///
+/// With `report`, the template also gets the advisor's result for that
+/// report as of `today`, as [`export_report`] would render it.
+pub async fn export_with_template(
+    state: Arc<AppState>,
+    name: &str,
+    report: Option<ReportType>,
+    today: u32,
+) -> Result<TemplateExport, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let dir = storage.data_dir().join(TEMPLATES_DIR);
+    let data = state.load_dataset()?;
+    let advice = match report {
+        Some(report) => report_advice(state.clone(), &data, report, today).await?,
+        None => None,
+    };
+    Ok(export::template::render_named(&dir, name, &data, advice.as_ref(), today)?)
+}
+
+/// Generate a sample dataset dated around today, for onboarding and demos.
+///
+/// Nothing is saved; the caller decides whether to keep it.
+pub fn generate_sample_data(profile: SampleProfile) -> Dataset {
+    sample::sample_dataset(profile, date::today())
+}
+
+/// Parse and validate an import file without applying it.
+///
+/// The returned preview carries a token for [`confirm_import`].
+pub async fn preview_import(
+    state: Arc<AppState>,
+    file_name: &str,
+    bytes: &[u8],
+) -> Result<ImportPreview, HandlerError> {
+    preview_import_with_mapping(state, file_name, bytes, None).await
+}
+
+/// Preview an import file, reading a CSV file's columns through the saved
+/// column mapping called `mapping`.
+///
+/// Without `mapping`, a CSV file is read through the saved CSV mapping that
+/// best fits its header, if any, and otherwise with the standard column
+/// titles.
+pub async fn preview_import_with_mapping(
+    state: Arc<AppState>,
+    file_name: &str,
+    bytes: &[u8],
+    mapping: Option<&str>,
+) -> Result<ImportPreview, HandlerError> {
+    if !file_name.to_ascii_lowercase().ends_with(".csv") {
+        return preview_parsed(state, import::parse_file(file_name, bytes)?).await;
+    }
+    let text = String::from_utf8_lossy(bytes);
+    preview_csv(state, &text, MappingSource::Csv, mapping, None).await
+}
+
+/// Preview the school list at `url`, such as a shared Google Sheet.
+///
+/// With `mapping`, the columns are read through the saved column mapping
+/// of that name; otherwise through the saved sheet mapping that best fits
+/// the sheet's header, or with the standard column titles if none does.
+/// The preview is applied with [`confirm_import`] like an imported file.
+pub async fn preview_url_import(
+    state: Arc<AppState>,
+    url: &str,
+    mapping: Option<&str>,
+) -> Result<ImportPreview, HandlerError> {
+    if let Some(name) = mapping {
+        // Fail on an unknown mapping before fetching
+        let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+        MappingCatalog::load(storage)?.get(name)?;
+    }
+    let text = sheet_import::fetch_csv(url).await?;
+    preview_csv(state, &text, MappingSource::Sheet, mapping, Some(url)).await
+}
+
+/// Preview CSV text read through the mapping called `mapping`, or the one
+/// detected from its header, remembering the header with the mapping used
+async fn preview_csv(
+    state: Arc<AppState>,
+    text: &str,
+    source: MappingSource,
+    mapping: Option<&str>,
+    url: Option<&str>,
+) -> Result<ImportPreview, HandlerError> {
+    let header = import::csv_header(text)?;
+    let mut catalog = match &state.storage {
+        Some(storage) => MappingCatalog::load(storage)?,
+        None if mapping.is_some() => return Err(HandlerError::NoStorage),
+        None => MappingCatalog::default(),
+    };
+    let name = match mapping {
+        Some(name) => Some(name.to_string()),
+        None => catalog.detect(source, &header).map(|m| m.name),
+    };
+    let parsed = match &name {
+        Some(name) => {
+            let parsed = import::parse_csv_mapped(text, &catalog.get(name)?.columns)?;
+            if let Some(storage) = &state.storage {
+                if catalog.record_use(name, &header, url) {
+                    catalog.save(storage)?;
+                }
+            }
+            parsed
+        }
+        None => import::parse_file("import.csv", text.as_bytes())?,
+    };
+    let mut preview = preview_parsed(state, parsed).await?;
+    preview.mapping = name;
+    preview.header = header;
+    Ok(preview)
+}
+
+/// Preview an export of the browser version's data, with a report of how
+/// it was converted. Applied with [`confirm_import`].
+pub async fn preview_legacy_import(
+    state: Arc<AppState>,
+    bytes: &[u8],
+) -> Result<LegacyImportPreview, HandlerError> {
+    let (parsed, report) = legacy_import::parse(bytes)?;
+    Ok(LegacyImportPreview {
+        preview: preview_parsed(state, parsed).await?,
+        report,
+    })
+}
+
+/// Localize the issues of `parsed` and keep it until it is confirmed
+async fn preview_parsed(
+    state: Arc<AppState>,
+    mut parsed: ParsedImport,
+) -> Result<ImportPreview, HandlerError> {
+    let locale = state.locale().await;
+    for issue in &mut parsed.issues {
+        issue.localize(locale);
+    }
+    let existing = state.load_dataset_if_saved()?;
+    let conflicts = import::find_conflicts(existing.as_ref(), &parsed.schools);
+    let duplicates = import::find_duplicates(existing.as_ref(), &parsed.schools);
+
+    let token = random_token(16);
+    let pending = PendingImport {
+        schools: parsed.schools.clone(),
+        duplicates: duplicates.clone(),
+    };
+    state.pending_imports.lock().await.insert(token.clone(), pending);
+
+    Ok(ImportPreview {
+        token,
+        schools: parsed.schools,
+        issues: parsed.issues,
+        conflicts,
+        duplicates,
+        mapping: None,
+        header: Vec::new(),
+    })
+}
+
+/// Saved column mappings for importing spreadsheets
+pub async fn list_column_mappings(
+    state: Arc<AppState>,
+) -> Result<Vec<ColumnMapping>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(MappingCatalog::load(storage)?.mappings)
+}
+
+/// Save `mapping`, replacing the one with the same name
+pub async fn save_column_mapping(
+    state: Arc<AppState>,
+    mapping: ColumnMapping,
+) -> Result<Vec<ColumnMapping>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let mut catalog = MappingCatalog::load(storage)?;
+    catalog.upsert(mapping);
+    catalog.save(storage)?;
+    Ok(catalog.mappings)
+}
+
+/// Saved mapping for `source` that best fits the column titles `header`,
+/// if any
+pub async fn detect_column_mapping(
+    state: Arc<AppState>,
+    source: MappingSource,
+    header: &[String],
+) -> Result<Option<MappingMatch>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(MappingCatalog::load(storage)?.detect(source, header))
+}
+
+/// Delete the column mapping called `name`
+pub async fn delete_column_mapping(
+    state: Arc<AppState>,
+    name: &str,
+) -> Result<Vec<ColumnMapping>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let mut catalog = MappingCatalog::load(storage)?;
+    if !catalog.remove(name) {
+        return Err(SheetImportError::UnknownMapping(name.to_string()).into());
+    }
+    catalog.save(storage)?;
+    Ok(catalog.mappings)
+}
+
+/// Apply a previewed import to the persisted dataset and return the result.
+///
+/// Each probable duplicate in the preview is kept, replaced or merged as
+/// chosen in `choices`, keyed by its position, or else as suggested. A dry
+/// run saves nothing and keeps the preview, so it can still be applied
+/// afterwards.
+pub async fn confirm_import(
+    state: Arc<AppState>,
+    token: &str,
+    choices: &BTreeMap<usize, MergeAction>,
+    dry_run: bool,
+) -> Result<ChangeReport<serde_json::Value>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let pending = {
+        let mut pending = state.pending_imports.lock().await;
+        if dry_run {
+            pending.get(token).cloned()
+        } else {
+            pending.remove(token)
+        }
+    }
+    .ok_or(HandlerError::UnknownImport)?;
+
+    let existing = state.load_dataset_if_saved()?;
+    let expected = existing.as_ref().map_or(0, revision::of);
+    let schools = import::resolve_duplicates(
+        existing.as_ref(),
+        pending.schools,
+        &pending.duplicates,
+        choices,
+    );
+    let count = schools.len();
+    let mut data = import::merge(existing.clone(), schools);
+    let diff = diff::diff(existing.as_ref(), &data);
+    if !dry_run {
+        storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(expected))?;
+        state.audit(
+            AuditAction::ImportApplied,
+            serde_json::json!({ "schools": count }),
+        );
+    }
+    Ok(ChangeReport {
+        result: data,
+        diff,
+        dry_run,
+    })
+}
+
+/// Offer the persisted dataset to another device.
+///
+/// The dataset is served once, encrypted, from a local endpoint that closes
+/// after [`transfer::TRANSFER_TTL`]; the returned code is shown as a QR code.
+pub async fn start_transfer(state: Arc<AppState>) -> Result<TransferOffer, HandlerError> {
+    let data = state.load_dataset()?;
+    let pending = PendingTransfer::bind(transfer::local_ip(), data.to_string().as_bytes()).await?;
+    let offer = pending.offer.clone();
+    tokio::spawn(pending.serve(transfer::TRANSFER_TTL));
+    Ok(offer)
+}
+
+/// Fetch a dataset offered by another device and preview it for import.
+///
+/// Nothing is applied until the preview is passed to [`confirm_import`].
+pub async fn receive_transfer(
+    state: Arc<AppState>,
+    code: &str,
+) -> Result<ImportPreview, HandlerError> {
+    let bytes = transfer::receive(code).await?;
+    preview_import(state, "transfer.json", &bytes).await
+}
+
+/// Schedule OS reminders for the unpaid deadlines in the persisted dataset.
+///
+/// Replaces any reminders registered before and returns how many were added.
+pub async fn register_os_reminders(state: Arc<AppState>) -> Result<usize, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let data = state.load_dataset()?;
+    let lead_days = state.settings.lock().await.reminder_lead_days;
+    let tasks = os_reminders::reminder_tasks(&data, date::today(), lead_days);
+    Ok(os_reminders::register(storage, &tasks)?)
+}
+
+/// Remove all OS reminders and return how many there were
+pub async fn unregister_os_reminders(state: Arc<AppState>) -> Result<usize, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(os_reminders::unregister(storage)?)
+}
+
+/// Unpaid deadlines by how soon they are due, recomputed from the persisted
+/// dataset and published to countdown subscribers
+pub async fn deadline_countdown(state: Arc<AppState>) -> Result<Countdown, HandlerError> {
+    countdown::refresh(&state)
+}
+
+/// Payment totals by month, school and category for the persisted dataset.
+///
+/// Computed locally without the advisor; empty if nothing has been saved.
+pub async fn get_summary(
+    state: Arc<AppState>,
+    range: SummaryRange,
+) -> Result<Summary, HandlerError> {
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    let rates = current_rates(&state, &dataset).await?;
+    let mut summary = summary::summarize(&dataset, range, &rates);
+    summary.budget = budget::status(&dataset, &rates);
+    summary.stale_rates = stale_rates(&state, &dataset, &rates);
+    Ok(summary)
+}
+
+/// The payments adding up to `figure` of the summary over `range`, each
+/// linked to its school, computed without the advisor
+pub async fn explain_totals(
+    state: Arc<AppState>,
+    range: SummaryRange,
+    figure: SummaryFigure,
+) -> Result<TotalExplanation, HandlerError> {
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    let rates = current_rates(&state, &dataset).await?;
+    Ok(summary::explain(&dataset, range, &rates, figure))
+}
+
+/// Payments of the persisted dataset made in `year`, for tax filing
+pub async fn get_tax_report(state: Arc<AppState>, year: u32) -> Result<TaxReport, HandlerError> {
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    let rates = current_rates(&state, &dataset).await?;
+    Ok(tax_report::tax_report(&dataset, year, &rates))
+}
+
+/// Render the year-end report for `year` as JSON, CSV or PDF
+pub async fn export_tax_report(
+    state: Arc<AppState>,
+    year: u32,
+    format: ExportFormat,
+) -> Result<Vec<u8>, HandlerError> {
+    let report = get_tax_report(state, year).await?;
+    Ok(export::tax_report(&report, format)?)
+}
+
+/// Dataset `name`, or the default dataset, with its revision
+pub async fn load_data(state: Arc<AppState>, name: Option<&str>) -> Result<Dataset, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let data = storage.load(&dataset_file(name)?)?.ok_or(HandlerError::NoData)?;
+    Ok(Dataset::from_value(data).map_err(StorageError::from)?)
+}
+
+/// Dataset `name`, or the default dataset, as it was at `at`: RFC 3339,
+/// or a date for the end of that day. Saved versions come from the journal,
+/// and for the default dataset also from backups.
+pub async fn get_data_as_of(
+    state: Arc<AppState>,
+    name: Option<&str>,
+    at: &str,
+) -> Result<PastVersion, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let time = journal::parse_time(at).ok_or_else(|| HandlerError::InvalidAsOf(at.to_string()))?;
+    journal::as_of(storage, &dataset_file(name)?, time)?
+        .ok_or_else(|| HandlerError::NoDataAsOf(at.to_string()))
+}
+
+/// Save `data` as dataset `name`, or the default dataset, as of `today`.
+///
+/// With an expected revision the save only goes through if the saved data
+/// is still at it; otherwise it fails with a [`RevisionConflict`] so that
+/// edits made meanwhile in another window or device are not overwritten.
+/// Returns the saved data with its new revision.
+pub async fn save_data(
+    state: Arc<AppState>,
+    name: Option<&str>,
+    mut data: Dataset,
+    expected_revision: Option<u64>,
+    today: u32,
+) -> Result<Dataset, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    state.check_writable()?;
+    let file = dataset_file(name)?;
+    data.schema_version = CURRENT_SCHEMA_VERSION;
+    data.regenerate_recurring(today);
+    data.revision = storage.save_dataset(&file, &mut data.to_value(), expected_revision)?;
+    Ok(data)
+}
+
+/// Apply a JSON merge patch (RFC 7386) to dataset `name`, or the default
+/// dataset, as of `today`, checking the expected revision like
+/// [`save_data`]. Without one, the save still fails if the data changed
+/// after it was read for patching. A dry run reports the change and saves
+/// nothing.
+pub async fn patch_data(
+    state: Arc<AppState>,
+    name: Option<&str>,
+    patch: &serde_json::Value,
+    expected_revision: Option<u64>,
+    dry_run: bool,
+    today: u32,
+) -> Result<ChangeReport<Dataset>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let file = dataset_file(name)?;
+    let before = storage
+        .load(&file)?
+        .unwrap_or_else(|| serde_json::json!({}));
+    let mut patched = before.clone();
+    revision::merge_patch(&mut patched, patch);
+    let mut dataset =
+        Dataset::from_value(patched).map_err(|e| HandlerError::InvalidPatch(e.to_string()))?;
+    dataset.revision = revision::of(&before);
+    dataset.schema_version = CURRENT_SCHEMA_VERSION;
+    dataset.regenerate_recurring(today);
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    if dry_run {
+        if let Some(expected) = expected_revision.filter(|&e| e != dataset.revision) {
+            let conflict = RevisionConflict::new(expected, &before, &data);
+            return Err(StorageError::Conflict(Box::new(conflict)).into());
+        }
+    } else {
+        let expected = expected_revision.or(Some(revision::of(&before)));
+        dataset.revision = storage.save_dataset(&file, &mut data, expected)?;
+    }
+    Ok(ChangeReport {
+        result: dataset,
+        diff,
+        dry_run,
+    })
+}
+
+/// Apply `ops` to dataset `name`, or the default dataset, as of `today`,
+/// all or none.
+///
+/// The operations are saved together as one revision, checked against
+/// the expected revision like [`save_data`], or without one against the
+/// revision they were applied to, so a client never leaves the data
+/// half-updated. A dry run reports the change and saves nothing.
+pub async fn bulk_update(
+    state: Arc<AppState>,
+    name: Option<&str>,
+    ops: Vec<BulkOp>,
+    expected_revision: Option<u64>,
+    dry_run: bool,
+    today: u32,
+) -> Result<ChangeReport<Dataset>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let file = dataset_file(name)?;
+    let before = storage
+        .load(&file)?
+        .unwrap_or_else(|| serde_json::json!({}));
+    let mut dataset = Dataset::from_value(before.clone()).map_err(StorageError::from)?;
+    let payments: Vec<PaymentRecord> = ops
+        .iter()
+        .filter_map(|op| match op {
+            BulkOp::RecordPayment { payment } => Some(payment.clone()),
+            _ => None,
+        })
+        .collect();
+    bulk::apply(&mut dataset, ops)?;
+    dataset.schema_version = CURRENT_SCHEMA_VERSION;
+    dataset.regenerate_recurring(today);
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    if dry_run {
+        if let Some(expected) = expected_revision.filter(|&e| e != dataset.revision) {
+            let conflict = RevisionConflict::new(expected, &before, &data);
+            return Err(StorageError::Conflict(Box::new(conflict)).into());
+        }
+    } else {
+        let expected = expected_revision.or(Some(revision::of(&before)));
+        dataset.revision = storage.save_dataset(&file, &mut data, expected)?;
+        if name.is_none() {
+            for payment in payments {
+                let details = serde_json::to_value(&payment).map_err(StorageError::from)?;
+                state.audit(AuditAction::PaymentRecorded, details);
+            }
+            state.reminders.lock().await.last_dataset =
+                Some(json_rpc::to_raw(&advisor_params(&data)));
+        }
+    }
+    Ok(ChangeReport {
+        result: dataset,
+        diff,
+        dry_run,
+    })
+}
+
+/// Record a payment as made in the persisted dataset and return the result.
+///
+/// The reminder engine is handed the updated data at once, so the payment
+/// stops generating alerts without waiting for the next advisor request.
+/// A dry run checks the payment but saves nothing.
+pub async fn mark_paid(
+    state: Arc<AppState>,
+    record: PaymentRecord,
+    dry_run: bool,
+) -> Result<ChangeReport<Dataset>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let mut dataset = Dataset::from_value(state.load_dataset()?).map_err(StorageError::from)?;
+    let details = serde_json::to_value(&record).map_err(StorageError::from)?;
+    let before = dataset.to_value();
+    payments::mark_paid(&mut dataset, record)?;
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    if !dry_run {
+        dataset.revision =
+            storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(dataset.revision))?;
+        state.audit(AuditAction::PaymentRecorded, details);
+        state.reminders.lock().await.last_dataset =
+            Some(json_rpc::to_raw(&advisor_params(&data)));
+    }
+    Ok(ChangeReport {
+        result: dataset,
+        diff,
+        dry_run,
+    })
+}
+
+/// Today's recommendation for `data`, which also records it in the history
+async fn recompute_recommendation(
+    state: Arc<AppState>,
+    data: &serde_json::Value,
+    today: u32,
+) -> Option<GetRecommendationResult> {
+    let mut params = advisor_params(data);
+    params["today"] = today.into();
+    let request = JsonRpcRequest::new("getRecommendation", &params, serde_json::json!("outcome"));
+    let recomputed = send_rpc(state, request)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|response| match response.error {
+            Some(error) => Err(error.message),
+            None => response.parse_result().map_err(|e| e.to_string()),
+        });
+    recomputed
+        .inspect_err(|e| tracing::warn!("Failed to recompute the recommendation: {}", e))
+        .ok()
+}
+
+/// Record the result announced by school `school_id` in the persisted
+/// dataset, as of `today`.
+///
+/// The outcome rules in the settings say what else changes, such as
+/// cancelling the schools ranked below an accepted one. The result is kept
+/// in the history and the recommendation recomputed for the new data. A
+/// dry run shows the changes but saves and recomputes nothing.
+pub async fn record_outcome(
+    state: Arc<AppState>,
+    school_id: u64,
+    outcome: ExamOutcome,
+    dry_run: bool,
+    today: u32,
+) -> Result<ChangeReport<RecordedOutcome>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let mut dataset = Dataset::from_value(state.load_dataset()?).map_err(StorageError::from)?;
+    let rules = state.settings.lock().await.outcome_rules.clone();
+    let before = dataset.to_value();
+    let cancelled_school_ids = outcome::apply(&mut dataset, school_id, outcome, &rules, today)?;
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    let entry = OutcomeEntry {
+        recorded_at: date::now().to_rfc3339(),
+        school_id,
+        school_name: dataset
+            .schools
+            .iter()
+            .find(|s| s.id == school_id)
+            .map(|s| s.name.clone())
+            .unwrap_or_default(),
+        outcome,
+        cancelled_school_ids,
+    };
+
+    let mut recommendation = None;
+    if !dry_run {
+        dataset.revision =
+            storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(dataset.revision))?;
+        state.reminders.lock().await.last_dataset =
+            Some(json_rpc::to_raw(&advisor_params(&data)));
+        let mut history = History::load(storage)?;
+        history.record_outcome(entry.clone());
+        history.save(storage)?;
+        recommendation = recompute_recommendation(state.clone(), &data, today).await;
+    }
+    Ok(ChangeReport {
+        result: RecordedOutcome {
+            entry,
+            dataset,
+            recommendation,
+        },
+        diff,
+        dry_run,
+    })
+}
+
+/// Outstanding and settled payments of the persisted dataset within `range`
+pub async fn get_outstanding_payments(
+    state: Arc<AppState>,
+    range: SummaryRange,
+) -> Result<OutstandingPayments, HandlerError> {
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    let rates = current_rates(&state, &dataset).await?;
+    let mut payments = payments::outstanding(&dataset, range, &rates);
+    payments.stale_rates = stale_rates(&state, &dataset, &rates);
+    Ok(payments)
+}
+
+/// Add a note to a school of the persisted dataset, or with `dry_run` only
+/// show the change
+pub async fn add_note(
+    state: Arc<AppState>,
+    note: NewNote,
+    dry_run: bool,
+) -> Result<ChangeReport<Dataset>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let mut dataset = Dataset::from_value(state.load_dataset()?).map_err(StorageError::from)?;
+    let before = dataset.to_value();
+    notes::add(&mut dataset, note, date::now())?;
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    if !dry_run {
+        dataset.revision =
+            storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(dataset.revision))?;
+    }
+    Ok(ChangeReport {
+        result: dataset,
+        diff,
+        dry_run,
+    })
+}
+
+/// Delete the note at `index` of school `school_id` in the persisted
+/// dataset, or with `dry_run` only show the change
+pub async fn delete_note(
+    state: Arc<AppState>,
+    school_id: u64,
+    index: usize,
+    dry_run: bool,
+) -> Result<ChangeReport<Dataset>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let mut dataset = Dataset::from_value(state.load_dataset()?).map_err(StorageError::from)?;
+    let before = dataset.to_value();
+    notes::remove(&mut dataset, school_id, index)?;
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    if !dry_run {
+        dataset.revision =
+            storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(dataset.revision))?;
+    }
+    Ok(ChangeReport {
+        result: dataset,
+        diff,
+        dry_run,
+    })
+}
+
+/// School names, notes and payment memos of the persisted dataset matching
+/// every word of `query`
+pub async fn search(state: Arc<AppState>, query: &str) -> Result<Vec<SearchHit>, HandlerError> {
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    Ok(search::search(&dataset, query))
+}
+
+/// Public VAPID key browsers subscribe with
+pub async fn push_public_key(state: Arc<AppState>) -> Result<String, HandlerError> {
+    let push = state.web_push.as_ref().ok_or(HandlerError::PushUnavailable)?;
+    Ok(push.public_key().to_string())
+}
+
+/// Save a browser's push subscription, replacing any for the same endpoint
+pub async fn subscribe_push(
+    state: Arc<AppState>,
+    subscription: NewPushSubscription,
+) -> Result<PushSubscription, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if state.web_push.is_none() {
+        return Err(HandlerError::PushUnavailable);
+    }
+    let mut subscriptions = PushSubscriptions::load(storage)?;
+    let saved = subscriptions.subscribe(subscription, date::now())?.clone();
+    subscriptions.save(storage)?;
+    tracing::info!("Saved a push subscription for {:?}", saved.user);
+    Ok(saved)
+}
+
+/// Forget the push subscription for `endpoint`, returning whether it existed
+pub async fn unsubscribe_push(state: Arc<AppState>, endpoint: &str) -> Result<bool, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let mut subscriptions = PushSubscriptions::load(storage)?;
+    let removed = subscriptions.unsubscribe(endpoint);
+    if removed {
+        subscriptions.save(storage)?;
+    }
+    Ok(removed)
+}
+
+/// Saved push subscriptions of `user`, or everyone's
+pub async fn list_push_subscriptions(
+    state: Arc<AppState>,
+    user: Option<&str>,
+) -> Result<Vec<PushSubscription>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let subscriptions = PushSubscriptions::load(storage)?;
+    Ok(subscriptions.of_user(user).cloned().collect())
+}
+
+/// Push a test notification to `user`, or everyone, returning how many
+/// browsers it reached
+pub async fn send_test_push(
+    state: Arc<AppState>,
+    user: Option<&str>,
+) -> Result<usize, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let push = state.web_push.as_ref().ok_or(HandlerError::PushUnavailable)?;
+    let locale = state.locale().await;
+    let message = PushMessage {
+        title: Message::new("push.testTitle").render(locale),
+        body: Message::new("push.testBody").render(locale),
+        tag: "test".to_string(),
+    };
+    Ok(push.send_all(storage, user, &message).await?)
+}
+
+/// Share a read-only snapshot of the persisted payment schedule for `days`
+pub async fn create_share(state: Arc<AppState>, days: u32) -> Result<ShareLink, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let key = state
+        .share_key
+        .as_ref()
+        .ok_or(HandlerError::SharingUnavailable)?;
+    let payments = get_outstanding_payments(state.clone(), SummaryRange::default()).await?;
+    let mut shares = Shares::load(storage)?;
+    let link = shares.share(key, payments.into(), days, date::now())?;
+    shares.save(storage)?;
+    tracing::info!("Shared the payment schedule until {}", link.expires_at);
+    Ok(link)
+}
+
+/// The snapshot a share link points to
+pub async fn shared_schedule(
+    state: Arc<AppState>,
+    token: &str,
+) -> Result<SharedSchedule, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let key = state
+        .share_key
+        .as_ref()
+        .ok_or(HandlerError::SharingUnavailable)?;
+    Ok(Shares::load(storage)?.open(key, token, date::now())?.clone())
+}
+
+/// How far the first-run setup wizard has got
+pub async fn onboarding_state(state: Arc<AppState>) -> Result<OnboardingState, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(OnboardingState::load(storage)?)
+}
+
+/// Mark `step` of the setup wizard done.
+///
+/// The data step needs saved data and the advisor step a response from the
+/// advisor, so neither can be skipped past.
+pub async fn complete_onboarding_step(
+    state: Arc<AppState>,
+    step: OnboardingStep,
+) -> Result<OnboardingState, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    match step {
+        OnboardingStep::Data => {
+            state.load_dataset()?;
+        }
+        OnboardingStep::Advisor => {
+            if let Some(error) = ping(state.clone()).await?.error {
+                return Err(HandlerError::Advisor(error.message));
+            }
+        }
+        OnboardingStep::Locale | OnboardingStep::Reminders => {}
+    }
+    let mut onboarding = OnboardingState::load(storage)?;
+    if onboarding.complete(step, date::now())? {
+        onboarding.save(storage)?;
+        tracing::info!("Completed the {} setup step", step);
+    }
+    Ok(onboarding)
+}
+
+/// Schedule conflicts in the persisted dataset from `today`
+pub async fn analyze_schedule(
+    state: Arc<AppState>,
+    today: u32,
+) -> Result<Vec<ScheduleWarning>, HandlerError> {
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    Ok(analysis::analyze(&dataset, today))
+}
+
+/// Timeline lanes of the schools in the persisted dataset
+pub async fn get_timeline(state: Arc<AppState>) -> Result<Timeline, HandlerError> {
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    Ok(timeline::timeline(&dataset))
+}
+
+/// Compare what-if scenarios against the persisted dataset from `today`.
+///
+/// Each scenario's mutations are applied to a copy of the dataset, and the
+/// copies are planned in parallel by the advisor pool, or one after another
+/// by the main advisor when there is no pool.
+pub async fn simulate(
+    state: Arc<AppState>,
+    scenarios: Vec<Scenario>,
+    today: u32,
+) -> Result<SimulationResult, HandlerError> {
+    if scenarios.len() > simulation::MAX_SCENARIOS {
+        return Err(SimulationError::TooManyScenarios.into());
+    }
+    let dataset = Dataset::from_value(state.load_dataset()?).map_err(StorageError::from)?;
+    let mut variants = vec![dataset.clone()];
+    for scenario in &scenarios {
+        variants.push(simulation::apply(&dataset, scenario)?);
+    }
+    let rates = current_rates(&state, &dataset).await?;
+
+    let requests: Vec<JsonRpcRequest> = variants
+        .iter()
+        .enumerate()
+        .map(|(i, variant)| simulation::request(variant, today, i))
+        .collect();
+    let started = Instant::now();
+    let (responses, durations) = plan_variants(&state, requests).await;
+    Ok(simulation::compare(&scenarios, &variants, &responses, &rates)
+        .with_timings(&durations, started.elapsed()))
+}
+
+/// Answer independent simulation requests, returning each response with the
+/// time the advisor spent on it.
+///
+/// The main advisor and the pool take requests from one queue, so the pool
+/// grows by up to one process per request beyond the first. Both are asked
+/// as [`send_rpc_isolated`] asks, leaving the user's plan alone.
+async fn plan_variants(
+    state: &Arc<AppState>,
+    requests: Vec<JsonRpcRequest>,
+) -> (Vec<JsonRpcResponse>, Vec<Duration>) {
+    let pool = state.repl_pool();
+    let queue = repl_pool::work_queue(&requests);
+    let main = async {
+        let mut answered = Vec::new();
+        while let Some((index, request)) = repl_pool::next(&queue) {
+            let id = request.id.clone();
+            let started = Instant::now();
+            let response = send_rpc_isolated(state, request)
+                .await
+                .unwrap_or_else(|e| JsonRpcResponse::internal_error(id, e.to_string()));
+            answered.push((index, response, started.elapsed()));
+        }
+        answered
+    };
+    // The pool goes first so its processes have started before the main
+    // advisor blocks this task
+    let (pooled, main) = tokio::join!(pool.drain(&queue, requests.len().saturating_sub(1)), main);
+
+    let mut answers: Vec<Option<(JsonRpcResponse, Duration)>> = vec![None; requests.len()];
+    for (index, pooled) in pooled {
+        let request = &requests[index];
+        let duration = pooled.timings.total();
+        let response = settle_rpc(state, request, pooled.result, pooled.timings)
+            .unwrap_or_else(|e| JsonRpcResponse::internal_error(request.id.clone(), e.to_string()));
+        answers[index] = Some((response, duration));
+    }
+    for (index, response, duration) in main {
+        answers[index] = Some((response, duration));
+    }
+    answers
+        .into_iter()
+        .zip(requests)
+        .map(|(answer, request)| {
+            answer.unwrap_or_else(|| {
+                let reason = "Advisor pool worker failed".to_string();
+                (JsonRpcResponse::internal_error(request.id, reason), Duration::ZERO)
+            })
+        })
+        .unzip()
+}
+
+/// Bundled school templates and those imported by the user
+pub async fn list_templates(state: Arc<AppState>) -> Result<Vec<SchoolTemplate>, HandlerError> {
+    Ok(templates::catalog(state.storage.as_ref())?)
+}
+
+/// A new school from template `template_id` with its exam on `exam_date`,
+/// with an id and priority following the persisted schools.
+///
+/// A dry run returns the school for the user to review without saving it;
+/// otherwise it is added to the persisted dataset.
+pub async fn apply_template(
+    state: Arc<AppState>,
+    template_id: &str,
+    exam_date: u32,
+    name: Option<&str>,
+    dry_run: bool,
+) -> Result<ChangeReport<School>, HandlerError> {
+    let template = templates::catalog(state.storage.as_ref())?
+        .into_iter()
+        .find(|t| t.id == template_id)
+        .ok_or_else(|| TemplateError::UnknownTemplate(template_id.to_string()))?;
+    let mut dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    let school = templates::apply(&template, exam_date, name, &dataset)?;
+    let before = dataset.to_value();
+    dataset.schema_version = CURRENT_SCHEMA_VERSION;
+    dataset.schools.push(school.clone());
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    if !dry_run {
+        let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+        state.check_writable()?;
+        storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(dataset.revision))?;
+        state.audit(
+            AuditAction::SchoolAdded,
+            serde_json::json!({ "schoolId": school.id, "templateId": template_id }),
+        );
+    }
+    Ok(ChangeReport {
+        result: school,
+        diff,
+        dry_run,
+    })
+}
+
+/// Add the templates of a catalog file to the user's templates
+pub async fn import_templates(
+    state: Arc<AppState>,
+    bytes: &[u8],
+) -> Result<Vec<SchoolTemplate>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(templates::import_catalog(
+        storage,
+        &String::from_utf8_lossy(bytes),
+    )?)
+}
+
+/// Days banks are closed in `year` other than weekends
+pub async fn list_holidays(
+    state: Arc<AppState>,
+    year: u32,
+) -> Result<Vec<Holiday>, HandlerError> {
+    Ok(calendar::load(state.storage.as_ref())?.closures_in(year))
+}
+
+/// Add the days of a holiday calendar file to the user's calendar and use
+/// it from now on. Returns the days banks are closed in `year`.
+pub async fn import_holidays(
+    state: Arc<AppState>,
+    bytes: &[u8],
+    year: u32,
+) -> Result<Vec<Holiday>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let updated = calendar::import(storage, &String::from_utf8_lossy(bytes))?;
+    let holidays = updated.closures_in(year);
+    calendar::install(updated);
+    Ok(holidays)
+}
+
+/// Split tuition into installments following `plan`
+pub async fn plan_installments(plan: InstallmentPlan) -> Result<Vec<Installment>, HandlerError> {
+    plan.schedule().ok_or(HandlerError::InvalidInstallmentPlan)
+}
+
+/// Payments of `fees` for the coming [`crate::model::RECURRING_HORIZON_MONTHS`] months
+/// from `today`, to preview the rules before saving them
+pub async fn expand_recurring_fees(
+    fees: Vec<RecurringFee>,
+    today: u32,
+) -> Result<Vec<RecurringPayment>, HandlerError> {
+    if let Some(fee) = fees.iter().find(|fee| fee.dues(today).is_none()) {
+        return Err(HandlerError::InvalidRecurringFee(fee.label.clone()));
+    }
+    Ok(expand_recurring(&fees, &[], recurring_horizon(today)))
+}
+
+/// Generate the recurring payments of every school in the persisted
+/// dataset up to [`crate::model::RECURRING_HORIZON_MONTHS`] months after `today`.
+///
+/// Run as days pass so the next payments appear in summaries, reminders
+/// and the calendar. Payments already made are kept; the data is saved
+/// only if something changed.
+pub async fn refresh_recurring_payments(
+    state: Arc<AppState>,
+    today: u32,
+) -> Result<ChangeReport<Dataset>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let mut dataset = Dataset::from_value(state.load_dataset()?).map_err(StorageError::from)?;
+    let before = dataset.to_value();
+    let changed = dataset.regenerate_recurring(today);
+    let mut data = dataset.to_value();
+    let diff = diff::diff(Some(&before), &data);
+    if changed {
+        state.check_writable()?;
+        dataset.revision =
+            storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(dataset.revision))?;
+        state.reminders.lock().await.last_dataset =
+            Some(json_rpc::to_raw(&advisor_params(&data)));
+    }
+    Ok(ChangeReport {
+        result: dataset,
+        diff,
+        dry_run: false,
+    })
+}
+
+/// How long to wait before fetching rates again after an automatic refresh
+const RATE_RETRY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Saved exchange rates to convert the fees of `dataset` with.
+///
+/// With automatic refresh on, stale or missing rates are fetched first, at
+/// most once per [`RATE_RETRY_INTERVAL`]. When the rate service cannot be
+/// reached the last known rates are used.
+async fn current_rates(state: &AppState, dataset: &Dataset) -> Result<ExchangeRates, HandlerError> {
+    let Some(storage) = &state.storage else {
+        return Ok(ExchangeRates::default());
+    };
+    let mut rates = ExchangeRates::load(storage)?;
+    let config = &state.config.exchange_rates;
+    let currencies = dataset.schools.iter().map(|s| s.currency);
+    if !config.auto_refresh || !rates.needs_refresh(currencies, &date::now(), config.max_age()) {
+        return Ok(rates);
+    }
+    {
+        let mut last_attempt = state.rates_refreshed_at.lock().await;
+        if last_attempt.is_some_and(|at| at.elapsed() < RATE_RETRY_INTERVAL) {
+            return Ok(rates);
+        }
+        *last_attempt = Some(Instant::now());
+    }
+    match currency::fetch_quotes(&config.provider_url).await {
+        Ok(quotes) => {
+            rates.apply_quotes(&quotes, &date::now().to_rfc3339());
+            rates.save(storage)?;
+        }
+        Err(e) => tracing::warn!(
+            "Failed to refresh exchange rates, using the last known: {}",
+            e
+        ),
+    }
+    Ok(rates)
+}
+
+/// Currencies of `dataset` whose rate in `rates` is past its maximum age
+fn stale_rates(state: &AppState, dataset: &Dataset, rates: &ExchangeRates) -> Vec<Currency> {
+    rates.stale(
+        dataset.schools.iter().map(|s| s.currency),
+        &date::now(),
+        state.config.exchange_rates.max_age(),
+    )
+}
+
+/// Saved exchange rates
+pub async fn get_exchange_rates(state: Arc<AppState>) -> Result<ExchangeRates, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(ExchangeRates::load(storage)?)
+}
+
+/// Set the rate for `currency` by hand (yen per unit), or clear it with `None`
+pub async fn set_exchange_rate(
+    state: Arc<AppState>,
+    currency: Currency,
+    yen_per_unit: Option<f64>,
+) -> Result<ExchangeRates, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let mut rates = ExchangeRates::load(storage)?;
+    rates.set_manual(currency, yen_per_unit)?;
+    rates.save(storage)?;
+    Ok(rates)
+}
+
+/// Update the fetched exchange rates from the configured rate service;
+/// rates set by hand are kept
+pub async fn refresh_exchange_rates(state: Arc<AppState>) -> Result<ExchangeRates, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let quotes = currency::fetch_quotes(&state.config.exchange_rates.provider_url).await?;
+    let mut rates = ExchangeRates::load(storage)?;
+    rates.apply_quotes(&quotes, &date::now().to_rfc3339());
+    rates.save(storage)?;
+    Ok(rates)
+}
+
+/// List recorded recommendations, filtered and sorted like schools
+pub async fn list_history(
+    state: Arc<AppState>,
+    query: &ListQuery,
+) -> Result<Page<serde_json::Value>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let entries = History::load(storage)?
+        .entries
+        .into_iter()
+        .rev()
+        .filter_map(|entry| serde_json::to_value(entry).ok())
+        .collect();
+    Ok(query.apply(entries))
+}
+
+/// Recorded recommendations matching `query`, newest first, in chunks of
+/// `chunk_size`; the query's page is ignored
+pub async fn stream_history(
+    state: Arc<AppState>,
+    query: &ListQuery,
+    chunk_size: usize,
+) -> Result<Chunks, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let entries = History::load(storage)?
+        .entries
+        .into_iter()
+        .rev()
+        .filter_map(|entry| serde_json::to_value(entry).ok())
+        .collect();
+    Ok(Chunks::new(None, query.select(entries), chunk_size))
+}
+
+/// Record what the user did about a recommendation
+pub async fn decide_recommendation(
+    state: Arc<AppState>,
+    id: u64,
+    decision: Option<Decision>,
+    note: Option<String>,
+) -> Result<HistoryEntry, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let mut history = History::load(storage)?;
+    let entry = history
+        .decide(id, decision, note, &date::now().to_rfc3339())?
+        .clone();
+    history.save(storage)?;
+    Ok(entry)
+}
+
+/// The exact advisor parameters history entry `id` was computed from
+pub async fn get_recommendation_inputs(
+    state: Arc<AppState>,
+    id: u64,
+) -> Result<RecommendationInputs, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let history = History::load(storage)?;
+    Ok(HistoryInputs::load(storage)?.get(&history, id)?)
+}
+
+/// Season totals of the recommendation history
+pub async fn history_stats(state: Arc<AppState>, today: u32) -> Result<HistoryStats, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let dataset = match state.load_dataset_if_saved()? {
+        Some(data) => Dataset::from_value(data).map_err(StorageError::from)?,
+        None => Dataset::default(),
+    };
+    Ok(history::stats(&History::load(storage)?, &dataset, today))
+}
+
+/// Export the recommendation history as JSON or CSV, oldest first
+pub async fn export_history(
+    state: Arc<AppState>,
+    format: ExportFormat,
+) -> Result<Vec<u8>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(export::history(&History::load(storage)?.entries, format)?)
+}
+
+/// List backups of the persisted dataset, newest first
+pub async fn list_backups(state: Arc<AppState>) -> Result<Vec<BackupInfo>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(backup::list_backups(storage)?)
+}
+
+/// Replace the persisted dataset with a backup and return the restored data.
+///
+/// The current data is itself backed up first so a restore can be undone.
+/// A dry run reads the backup but changes nothing.
+pub async fn restore_backup(
+    state: Arc<AppState>,
+    file_name: &str,
+    dry_run: bool,
+) -> Result<ChangeReport<serde_json::Value>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if !dry_run {
+        state.check_writable()?;
+    }
+    let mut data = backup::load_backup(storage, file_name)?
+        .ok_or_else(|| HandlerError::UnknownBackup(file_name.to_string()))?;
+    let current = state.load_dataset_if_saved()?;
+    let diff = diff::diff(current.as_ref(), &data);
+
+    if !dry_run {
+        let expected = current.as_ref().map_or(0, revision::of);
+        backup::write_backup(storage, backup::PRE_RESTORE_BACKUP)?;
+        storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(expected))?;
+        state.audit(
+            AuditAction::BackupRestored,
+            serde_json::json!({ "fileName": file_name }),
+        );
+    }
+    Ok(ChangeReport {
+        result: data,
+        diff,
+        dry_run,
+    })
+}
+
+/// What the scheduled backups to the sync server last did
+pub async fn cloud_backup_status(state: Arc<AppState>) -> Result<CloudBackupStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(CloudBackupStatus::load(storage)?)
+}
+
+/// Back up to the sync server now, without waiting for the schedule.
+///
+/// A failed upload is recorded in the returned status rather than returned
+/// as an error, and alerts like a scheduled one would.
+pub async fn run_cloud_backup(state: Arc<AppState>) -> Result<CloudBackupStatus, HandlerError> {
+    let (status, alert) = cloud_backup::attempt(&state, date::now()).await?;
+    if let Some(alert) = alert {
+        state.notify(alert);
+    }
+    Ok(status)
+}
+
+/// Keep a sealed backup uploaded by a client, returning its file name
+pub async fn receive_cloud_backup(
+    state: Arc<AppState>,
+    sealed: &[u8],
+) -> Result<String, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    if sealed.is_empty() {
+        return Err(HandlerError::EmptyBackup);
+    }
+    Ok(cloud_backup::store_received(storage, sealed, date::now())?)
+}
+
+/// Move the data files to `new_dir` and keep them there from now on.
+///
+/// `default_dir` is where the app keeps its data unless moved, and where
+/// the new location is recorded. The files are copied and verified before
+/// the old ones are removed; reading and writing continue in `new_dir`
+/// without a restart.
+pub async fn move_data_dir(
+    state: Arc<AppState>,
+    default_dir: &Path,
+    new_dir: &Path,
+) -> Result<MovedData, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    state.check_writable()?;
+    let moved = data_location::move_data(&storage.data_dir(), new_dir, default_dir)?;
+    storage.relocate(new_dir.to_path_buf());
+    tracing::info!(
+        "Moved {} data files ({} bytes) to {:?}",
+        moved.files,
+        moved.bytes,
+        new_dir
+    );
+    if !moved.left_behind.is_empty() {
+        tracing::warn!("Could not remove old files: {:?}", moved.left_behind);
+    }
+    Ok(moved)
+}
+
+/// Whether this instance or another holds the edit lock
+pub async fn edit_lock_status(state: Arc<AppState>) -> Result<EditLockStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(state.edit_lock.status(storage, &date::now().to_rfc3339())?)
+}
+
+/// Take the edit lock so other instances sharing the data are read-only,
+/// or renew it; the frontend renews it well within
+/// [`crate::edit_lock::STALE_AFTER_SECS`] while it is open. If another
+/// instance holds the lock, the returned status is read-only.
+pub async fn acquire_edit_lock(state: Arc<AppState>) -> Result<EditLockStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(state.edit_lock.acquire(storage, &date::now().to_rfc3339())?)
+}
+
+/// Give up the edit lock, if this instance holds it
+pub async fn release_edit_lock(state: Arc<AppState>) -> Result<EditLockStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(state.edit_lock.release(storage, &date::now().to_rfc3339())?)
 }
 
-impl AppState {
-    pub fn new(lean_repl: LeanRepl) -> Self {
-        Self {
-            lean_repl: Mutex::new(lean_repl),
-        }
+/// Entries of the audit log, oldest first
+pub async fn list_audit_log(state: Arc<AppState>) -> Result<Vec<AuditEntry>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(AuditLog::load(storage)?.entries)
+}
+
+/// Check that no audit log entry was changed, removed or reordered
+pub async fn verify_audit_log(state: Arc<AppState>) -> Result<AuditVerification, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let key = state
+        .audit_key
+        .as_ref()
+        .ok_or(HandlerError::AuditUnavailable)?;
+    Ok(audit::verify(storage, key)?)
+}
+
+/// Which fields are encrypted in the data files
+pub async fn field_encryption_status(
+    state: Arc<AppState>,
+) -> Result<FieldEncryptionStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(field_crypto::status(storage)?)
+}
+
+/// Encrypt `fields` from now on, rewriting the data files to match.
+///
+/// The first time encryption is turned on a key is created and kept in
+/// `keys`.
+pub async fn set_encrypted_fields(
+    state: Arc<AppState>,
+    keys: &dyn KeyStore,
+    fields: Vec<SensitiveField>,
+) -> Result<FieldEncryptionStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(field_crypto::configure(storage, keys, fields)?)
+}
+
+/// Code of the encryption key, to set up another device with
+pub async fn export_field_key(keys: &dyn KeyStore) -> Result<Option<String>, HandlerError> {
+    Ok(keys.load().map_err(StorageError::from)?.map(|key| key.code()))
+}
+
+/// Code of the field encryption key in use, or else the one in `keys`;
+/// with a launch password the key is only known once unlocked
+pub async fn export_encryption_key(keys: &dyn KeyStore) -> Result<Option<String>, HandlerError> {
+    let key = field_crypto::export_key(keys).map_err(StorageError::from)?;
+    Ok(key.map(|key| key.code()))
+}
+
+/// Unlock the encrypted fields with the launch password
+pub async fn unlock_with_password(
+    state: Arc<AppState>,
+    password: &str,
+) -> Result<FieldEncryptionStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(field_crypto::unlock_with_password(storage, password)?)
+}
+
+/// Ask for `password` on each launch instead of unlocking from the
+/// keychain `keys`; `None` goes back to the keychain
+pub async fn set_launch_password(
+    state: Arc<AppState>,
+    keys: &dyn KeyStore,
+    password: Option<&str>,
+) -> Result<FieldEncryptionStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(field_crypto::set_launch_password(storage, keys, password)?)
+}
+
+/// Set up the encryption key from another device's code
+pub async fn import_field_key(
+    state: Arc<AppState>,
+    keys: &dyn KeyStore,
+    code: &str,
+) -> Result<FieldEncryptionStatus, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    Ok(field_crypto::import_key(storage, keys, code)?)
+}
+
+/// List the persisted schools with filtering, sorting, and pagination.
+///
+/// Returns an empty page if nothing has been saved yet.
+pub async fn list_schools(
+    state: Arc<AppState>,
+    query: &ListQuery,
+) -> Result<Page<serde_json::Value>, HandlerError> {
+    let schools = match state.load_dataset_if_saved()? {
+        Some(mut data) => match data.get_mut("schools").map(serde_json::Value::take) {
+            Some(serde_json::Value::Array(schools)) => schools,
+            _ => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+    Ok(query.apply(schools))
+}
+
+/// Bring the persisted dataset up to the current schema version.
+///
+/// Before rewriting, the original file is kept as `data.v<N>.json`.
+/// Returns the version migrated from, or `None` if nothing needed doing.
+pub async fn migrate_dataset(state: Arc<AppState>) -> Result<Option<u32>, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let Some(mut data) = state.load_dataset_if_saved()? else {
+        return Ok(None);
+    };
+    let original = data.clone();
+    let expected = revision::of(&original);
+    let from = migrations::migrate(&mut data)?;
+    if from == CURRENT_SCHEMA_VERSION {
+        return Ok(None);
     }
+
+    storage.save(&migrations::migration_copy_file(from), &original)?;
+    storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(expected))?;
+    tracing::info!(
+        "Migrated data from schema version {} to {}",
+        from,
+        CURRENT_SCHEMA_VERSION
+    );
+    Ok(Some(from))
 }
 
-/// Send an RPC request to the Lean REPL
-pub async fn send_rpc(
+/// Ask the advisor which data schema version it supports.
+///
+/// Advisors that predate `getVersion` understand schema version 1.
+pub async fn advisor_schema_version(state: Arc<AppState>) -> Result<u32, HandlerError> {
+    let request =
+        JsonRpcRequest::new("getVersion", &serde_json::json!({}), serde_json::json!("version"));
+    let response = send_rpc(state, request).await?;
+    Ok(response
+        .result_value()
+        .as_ref()
+        .and_then(|r| r.get("schemaVersion"))
+        .and_then(|v| v.as_u64())
+        .map_or(1, |v| v as u32))
+}
+
+/// Verify the running advisor can handle the persisted data
+pub async fn check_advisor_compat(state: Arc<AppState>) -> Result<(), HandlerError> {
+    let data_schema = state
+        .load_dataset_if_saved()?
+        .map_or(CURRENT_SCHEMA_VERSION, |data| migrations::schema_version(&data));
+    let advisor_schema = advisor_schema_version(state).await?;
+    Ok(migrations::check_advisor(advisor_schema, data_schema)?)
+}
+
+/// Current user settings
+pub async fn get_settings(state: Arc<AppState>) -> Settings {
+    state.settings.lock().await.clone()
+}
+
+/// Validate, persist, and apply new user settings
+pub async fn update_settings(
     state: Arc<AppState>,
-    request: JsonRpcRequest,
-) -> Result<JsonRpcResponse, LeanReplError> {
+    settings: Settings,
+) -> Result<Settings, HandlerError> {
+    match &state.storage {
+        Some(storage) => settings.save(storage)?,
+        None => settings.validate()?,
+    }
     let mut repl = state.lean_repl.lock().await;
-
-    // Log for debugging
-    if request.method == "getWeeklyRecommendations" {
-        tracing::info!("=== Weekly Recommendations Request ===");
-        if let Some(start_day) = request.params.get("startDay") {
-            tracing::info!("startDay: {}", start_day);
-        }
-        if let Some(states) = request.params.get("states") {
-            tracing::info!("states: {}", serde_json::to_string_pretty(states).unwrap_or_default());
+    repl.set_request_timeout(settings.advisor.request_timeout());
+    state.rebuild_repl_pool(&repl, settings.advisor.pool_limits());
+    drop(repl);
+    if let Some(handle) = &state.log_level {
+        handle.set_level(settings.log_level());
+    }
+    let previous = std::mem::replace(&mut *state.settings.lock().await, settings.clone());
+    if settings.record_sessions && !previous.record_sessions {
+        start_recording(state.clone()).await;
+    } else if !settings.record_sessions && previous.record_sessions {
+        match stop_recording(state.clone()).await {
+            Ok(path) => tracing::info!("Recorded session saved to {}", path),
+            Err(HandlerError::NotRecording) => {}
+            Err(e) => tracing::warn!("Could not save the recorded session: {}", e),
         }
     }
+    Ok(settings)
+}
+
+/// Start recording advisor requests and responses, unless already
+/// recording
+pub async fn start_recording(state: Arc<AppState>) -> RecordingStatus {
+    let mut recording = state.recording.lock().unwrap_or_else(|e| e.into_inner());
+    let session = recording.get_or_insert_with(Session::start);
+    RecordingStatus::of(Some(session))
+}
+
+/// Stop recording and save the session, returning the path of the file to
+/// attach to a bug report
+pub async fn stop_recording(state: Arc<AppState>) -> Result<String, HandlerError> {
+    let storage = state.storage.as_ref().ok_or(HandlerError::NoStorage)?;
+    let session = state
+        .recording
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or(HandlerError::NotRecording)?;
+    let path = session.finish(&storage.data_dir())?;
+    Ok(path.display().to_string())
+}
+
+/// Whether a session is being recorded
+pub async fn recording_status(state: Arc<AppState>) -> RecordingStatus {
+    RecordingStatus::of(state.recording.lock().unwrap_or_else(|e| e.into_inner()).as_ref())
+}
+
+/// Replace the log filter until the app restarts or the log level setting
+/// changes, returning the filter now in effect.
+///
+/// Lets a user switch to `trace` logging while a problem is still on screen.
+pub async fn set_log_filter(state: Arc<AppState>, filter: &str) -> Result<String, HandlerError> {
+    let handle = state
+        .log_level
+        .as_ref()
+        .ok_or(HandlerError::LogLevelUnavailable)?;
+    Ok(handle.set_filter(filter)?)
+}
+
+/// The most recent log events matching `query`, oldest first
+pub async fn recent_events(
+    state: Arc<AppState>,
+    query: EventQuery,
+) -> Result<Vec<LogEvent>, HandlerError> {
+    Ok(state.log_buffer.query(&query)?)
+}
+
+/// Calls, error rate and latency of each advisor method since startup
+pub async fn get_method_stats(state: Arc<AppState>) -> Vec<MethodCallStats> {
+    state.call_stats.snapshot()
+}
+
+/// Phase timings of recent advisor requests, by method
+pub async fn request_stats(state: Arc<AppState>) -> ProfileStats {
+    state.profiler.stats()
+}
+
+/// Switch latency profiling on or off, returning the timings so far.
+///
+/// Switching it on discards earlier timings.
+pub async fn set_profiling(state: Arc<AppState>, enabled: bool) -> ProfileStats {
+    state.profiler.set_enabled(enabled);
+    tracing::info!("Request profiling {}", if enabled { "on" } else { "off" });
+    state.profiler.stats()
+}
+
+/// Run the end-to-end self-test of the advisor and the data directory
+pub async fn run_self_test(state: Arc<AppState>) -> SelfTestReport {
+    self_test::run(state).await
+}
+
+/// Collect the diagnostics report for a support bundle
+pub async fn support_report(state: Arc<AppState>, app_version: &str) -> SupportReport {
+    let path = state.lean_repl.lock().await.advisor_path().to_path_buf();
+    let sha256 = support::file_sha256(&path)
+        .inspect_err(|e| tracing::warn!("Cannot hash advisor binary {:?}: {}", path, e))
+        .ok();
+    let schema_version = advisor_schema_version(state.clone()).await.ok();
+    let school_count = state
+        .load_dataset_if_saved()
+        .ok()
+        .flatten()
+        .and_then(|data| data.get("schools")?.as_array().map(Vec::len))
+        .unwrap_or(0);
+    let cloud_backup = state
+        .storage
+        .as_ref()
+        .and_then(|storage| CloudBackupStatus::load(storage).ok())
+        .unwrap_or_default();
+
+    SupportReport {
+        app_version: app_version.to_string(),
+        created_at: date::now().to_rfc3339(),
+        system: SystemInfo::current(),
+        advisor: AdvisorInfo {
+            path: path.display().to_string(),
+            sha256,
+            schema_version,
+            status: state.advisor_status(),
+        },
+        health: health_check(state.clone()).await,
+        health_history: health_history(state.clone()).await,
+        settings: support::redact_settings(&*state.settings.lock().await),
+        school_count,
+        cloud_backup,
+    }
+}
 
-    repl.send_request(&request)
+/// Build a support bundle with the report, anonymized data, logs in
+/// `log_dir` and `crash` if given
+pub async fn create_support_bundle(
+    state: Arc<AppState>,
+    app_version: &str,
+    log_dir: Option<&std::path::Path>,
+    crash: Option<&CrashReport>,
+) -> Result<Vec<u8>, HandlerError> {
+    let report = support_report(state.clone(), app_version).await;
+    let data = state.load_dataset_if_saved()?;
+    Ok(support::build_bundle(&report, data.as_ref(), log_dir, crash)?)
 }
 
 /// Health check response
@@ -47,6 +3050,9 @@ pub async fn send_rpc(
 pub struct HealthResponse {
     pub status: String,
     pub lean_repl: String,
+    /// Whether the advisor answered in time, when the check waited for it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ready: Option<bool>,
 }
 
 /// Check the health of the application
@@ -55,31 +3061,1257 @@ pub async fn health_check(state: Arc<AppState>) -> HealthResponse {
 
     HealthResponse {
         status: "ok".to_string(),
-        lean_repl: if repl.is_running() {
+        lean_repl: if repl.is_replay() {
+            "replay".to_string()
+        } else if repl.is_demo() {
+            "demo".to_string()
+        } else if repl.is_running() {
             "running".to_string()
         } else {
             "stopped".to_string()
         },
+        ready: None,
+    }
+}
+
+/// Check the health of the application once the advisor answers a ping,
+/// waiting up to `timeout` (at most [`supervisor::MAX_WAIT_READY`]) for it to start
+pub async fn health_check_ready(state: Arc<AppState>, timeout: Duration) -> HealthResponse {
+    let ready = supervisor::wait_ready(state.clone(), timeout).await;
+    let health = health_check(state).await;
+    HealthResponse {
+        status: if ready { health.status } else { "unavailable".to_string() },
+        ready: Some(ready),
+        ..health
     }
 }
 
+/// Advisor status changes, crashes and restarts, oldest first
+pub async fn health_history(state: Arc<AppState>) -> Vec<HealthEvent> {
+    state
+        .health_history
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .events
+        .clone()
+}
+
 /// Restart the Lean REPL
 pub async fn restart_repl(state: Arc<AppState>) -> Result<(), LeanReplError> {
+    state.record_health(HealthEvent::new(HealthEventKind::Restarted, None, None));
+    state.set_advisor_status(AdvisorStatus::new(
+        AdvisorState::Starting,
+        "Restarting advisor",
+    ));
+    let limits = state.settings.lock().await.advisor.pool_limits();
     let mut repl = state.lean_repl.lock().await;
-    repl.restart()
+    let result = repl.restart();
+    state.rebuild_repl_pool(&repl, limits);
+    drop(repl);
+
+    state.set_advisor_status(match &result {
+        Ok(()) => AdvisorStatus::running(),
+        Err(e) => AdvisorStatus::from_error(e),
+    });
+    result
+}
+
+/// Restart the advisor with verbose stderr tracing on or off
+pub async fn set_advisor_debug(state: Arc<AppState>, enable: bool) -> Result<(), LeanReplError> {
+    tracing::info!("Advisor debug tracing {}", if enable { "on" } else { "off" });
+    state.lean_repl.lock().await.set_verbose(enable);
+    restart_repl(state).await
 }
 
 /// Send a ping request to verify REPL connectivity
 pub async fn ping(state: Arc<AppState>) -> Result<JsonRpcResponse, LeanReplError> {
-    let request = JsonRpcRequest {
-        jsonrpc: "2.0".to_string(),
-        method: "ping".to_string(),
-        params: serde_json::json!({}),
-        id: serde_json::json!(std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64),
-    };
+    let id = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let request = JsonRpcRequest::new("ping", &serde_json::json!({}), serde_json::json!(id));
 
     send_rpc(state, request).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn request(id: u64) -> JsonRpcRequest {
+        JsonRpcRequest::new("ping", &serde_json::json!({}), serde_json::json!(id))
+    }
+
+    #[tokio::test]
+    async fn test_batch_preserves_order_on_failure() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl));
+
+        let responses = send_rpc_batch(state, vec![request(1), request(2), request(3)], 2).await;
+
+        assert_eq!(responses.len(), 3);
+        for (i, response) in responses.iter().enumerate() {
+            assert_eq!(response.id, serde_json::json!(i as u64 + 1));
+            assert_eq!(response.error.as_ref().unwrap().code, -32603);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_preview_and_confirm() {
+        let dir = tempfile::tempdir().unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(
+            AppState::new(repl).with_storage(Storage::new(dir.path().to_path_buf())),
+        );
+
+        let bytes = serde_json::to_vec(&crate::export::sample_data()).unwrap();
+        let preview = preview_import(state.clone(), "data.json", &bytes).await.unwrap();
+        assert_eq!(preview.schools.len(), 2);
+        assert!(preview.conflicts.is_empty());
+
+        let data = confirm_import(state.clone(), &preview.token, &BTreeMap::new(), false)
+            .await
+            .unwrap()
+            .result;
+        assert_eq!(data["schools"].as_array().unwrap().len(), 2);
+        assert_eq!(state.load_dataset().unwrap(), data);
+
+        assert!(matches!(
+            confirm_import(state, &preview.token, &BTreeMap::new(), false).await,
+            Err(HandlerError::UnknownImport)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_import_duplicates_follow_the_chosen_action() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let saved = crate::export::sample_data();
+        storage.save(SCHOOLS_DATA_FILE, &saved).unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+
+        // Typed in again on another device, under a new id
+        let mut school = saved["schools"][0].clone();
+        school["id"] = serde_json::json!(9);
+        school["name"] = serde_json::json!("早稲田 大学");
+        school["tuition"] = serde_json::json!(810000);
+        let bytes = serde_json::to_vec(&serde_json::json!([school])).unwrap();
+        let preview = preview_import(state.clone(), "list.json", &bytes).await.unwrap();
+        assert_eq!(preview.duplicates.len(), 1);
+        assert_eq!(preview.duplicates[0].existing_id, 2);
+        assert_eq!(preview.duplicates[0].suggestion, MergeAction::Replace);
+
+        let merged = confirm_import(state.clone(), &preview.token, &BTreeMap::new(), true)
+            .await
+            .unwrap();
+        assert!(merged.diff.added.is_empty());
+        assert_eq!(merged.diff.changed[0].school_id, 2);
+
+        let choices = BTreeMap::from([(0, MergeAction::Keep)]);
+        let kept = confirm_import(state.clone(), &preview.token, &choices, false)
+            .await
+            .unwrap();
+        assert!(kept.diff.is_empty());
+        assert_eq!(kept.result["schools"], saved["schools"]);
+        assert_eq!(state.load_dataset().unwrap(), kept.result);
+    }
+
+    #[tokio::test]
+    async fn test_csv_import_detects_saved_mapping() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+        let mapping = ColumnMapping {
+            name: "塾の表".to_string(),
+            columns: [
+                ("番号", "id"),
+                ("学校名", "name"),
+                ("受験日", "examDate"),
+                ("発表日", "resultDate"),
+                ("入学金期限", "enrollmentFeeDeadline"),
+                ("授業料期限", "tuitionDeadline"),
+                ("入学金", "enrollmentFee"),
+                ("授業料", "tuition"),
+            ]
+            .into_iter()
+                .map(|(t, f)| (t.to_string(), f.to_string()))
+                .collect(),
+            url: None,
+            source: MappingSource::Csv,
+            headers: Vec::new(),
+        };
+        save_column_mapping(state.clone(), mapping).await.unwrap();
+
+        let csv = "番号,学校名,受験日,発表日,入学金期限,授業料期限,入学金,授業料,備考\n\
+                   1,東京大学,2026/02/25,2026/03/10,2026/03/17,2026/03/31,282000,535800,\n";
+        let preview = preview_import(state.clone(), "3月.csv", csv.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(preview.mapping.as_deref(), Some("塾の表"));
+        assert!(preview.issues.is_empty());
+        assert_eq!(preview.schools[0]["name"], "東京大学");
+        assert_eq!(preview.schools[0]["tuitionDeadline"], 20260331);
+
+        // The full header is remembered for the next detection
+        let header = import::csv_header(csv).unwrap();
+        assert_eq!(list_column_mappings(state.clone()).await.unwrap()[0].headers, header);
+        let found = detect_column_mapping(state.clone(), MappingSource::Csv, &header)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.score, 100);
+        assert!(
+            detect_column_mapping(state.clone(), MappingSource::Sheet, &header)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // A file with the standard titles is read as before
+        let csv = "id,name,備考\n1,東京大学,\n";
+        let preview = preview_import(state, "list.csv", csv.as_bytes()).await.unwrap();
+        assert!(preview.mapping.is_none());
+        assert_eq!(preview.header, ["id", "name", "備考"]);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_dataset_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let legacy = serde_json::json!({"schools": [{"id": 1, "name": "東京大学"}]});
+        storage.save(SCHOOLS_DATA_FILE, &legacy).unwrap();
+
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+
+        assert_eq!(migrate_dataset(state.clone()).await.unwrap(), Some(0));
+        assert_eq!(migrate_dataset(state.clone()).await.unwrap(), None);
+
+        let data = state.load_dataset().unwrap();
+        assert_eq!(migrations::schema_version(&data), CURRENT_SCHEMA_VERSION);
+        let backup = Storage::new(dir.path().to_path_buf())
+            .load("data.v0.json")
+            .unwrap();
+        assert_eq!(backup, Some(legacy));
+    }
+
+    #[test]
+    fn test_advisor_params_from_dataset() {
+        let params = advisor_params(&serde_json::json!({
+            "schools": [{"id": 1, "name": "東京大学", "tuitionPaid": true}]
+        }));
+        assert_eq!(params["schools"][0]["name"], "東京大学");
+        assert_eq!(
+            params["states"][0],
+            serde_json::json!({
+                "schoolId": 1, "passStatus": "notYetAnnounced",
+                "enrollmentFeePaid": false, "tuitionPaid": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_advisor_params_include_method_charges() {
+        let params = advisor_params(&serde_json::json!({
+            "schools": [
+                {"id": 1, "enrollmentFee": 200000, "tuition": 500000, "paymentMethod": "creditCard"},
+                {"id": 2, "enrollmentFee": 200000, "paymentMethod": "bankTransfer"},
+                {"id": 3, "enrollmentFee": 2000, "currency": "USD", "paymentMethod": "creditCard"}
+            ],
+            "methodFees": [{"method": "creditCard", "fixed": 0, "rateBp": 200, "cap": 8000}]
+        }));
+        assert_eq!(params["schools"][0]["enrollmentFee"], 204000);
+        assert_eq!(params["schools"][0]["tuition"], 508000);
+        assert_eq!(params["schools"][1]["enrollmentFee"], 200000);
+        assert_eq!(params["schools"][2]["enrollmentFee"], 2000);
+    }
+
+    #[tokio::test]
+    async fn test_export_with_user_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let templates = dir.path().join(TEMPLATES_DIR);
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(
+            templates.join("count.txt.hbs"),
+            "{{#each schools}}{{id}} {{/each}}{{#if report}}with advice{{/if}}",
+        )
+        .unwrap();
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+
+        let listed = list_export_templates(state.clone()).await.unwrap();
+        assert_eq!(listed[0].name, "count.txt");
+        let export = export_with_template(state.clone(), "count.txt", None, 20260301)
+            .await
+            .unwrap();
+        assert_eq!(export.text, "1 2 ");
+        let weekly = Some(ReportType::Weekly);
+        let export = export_with_template(state.clone(), "count.txt", weekly, 20260301)
+            .await
+            .unwrap();
+        assert_eq!(export.text, "1 2 with advice");
+        assert!(matches!(
+            export_with_template(state, "missing.txt", None, 20260301).await,
+            Err(HandlerError::Export(ExportError::UnknownTemplate(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_schedule_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+
+        let pdf = export_report(state.clone(), ReportType::Schedule, 20260301)
+            .await
+            .unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+        assert!(matches!(
+            export_report(state.clone(), ReportType::Weekly, 20260301).await,
+            Err(HandlerError::Repl(_))
+        ));
+        assert!(matches!(
+            export_report(state, ReportType::Plan, 20260301).await,
+            Err(HandlerError::Repl(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mark_paid_stops_reminders() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+
+        let record: PaymentRecord = serde_json::from_value(serde_json::json!({
+            "schoolId": 1, "fee": "enrollmentFee", "paidOn": 20260312, "method": "bankTransfer"
+        }))
+        .unwrap();
+        mark_paid(state.clone(), record, false).await.unwrap();
+
+        let digest = state.reminders.lock().await.take_digest(20260315, 3, None);
+        assert!(digest.deadlines.is_empty());
+        let payments = get_outstanding_payments(state, SummaryRange::default())
+            .await
+            .unwrap();
+        assert_eq!(payments.settled.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_recurring_payments() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let mut data = crate::export::sample_data();
+        data["schools"][0]["recurringFees"] = serde_json::json!([{
+            "label": "施設設備費", "amount": 30000, "firstDue": 20260410, "intervalMonths": 6
+        }]);
+        storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+
+        let report = refresh_recurring_payments(state.clone(), 20260401)
+            .await
+            .unwrap();
+        assert_eq!(report.diff.changed.len(), 1);
+        let saved = state.load_dataset().unwrap();
+        let dues: Vec<_> = saved["schools"][0]["recurringPayments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["due"].as_u64().unwrap())
+            .collect();
+        assert_eq!(dues, vec![20260410, 20261010]);
+
+        // Nothing changes until the horizon reaches the next payment
+        let again = refresh_recurring_payments(state.clone(), 20260402)
+            .await
+            .unwrap();
+        assert!(again.diff.is_empty());
+
+        let fees: Vec<RecurringFee> = serde_json::from_value(serde_json::json!([{
+            "label": "", "amount": 1000, "firstDue": 20260410, "intervalMonths": 1
+        }]))
+        .unwrap();
+        assert!(matches!(
+            expand_recurring_fees(fees, 20260401).await,
+            Err(HandlerError::InvalidRecurringFee(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stale_saves_conflict_instead_of_overwriting() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        let data = crate::export::sample_dataset();
+
+        // The desktop app and the web app both load revision 1
+        let loaded = save_data(state.clone(), None, data, None, 20260301)
+            .await
+            .unwrap();
+        assert_eq!(loaded.revision, 1);
+        let patch = serde_json::json!({ "budget": { "total": 3000000 } });
+        let patched = patch_data(state.clone(), None, &patch, Some(1), false, 20260301)
+            .await
+            .unwrap();
+        assert_eq!(patched.result.revision, 2);
+        assert_eq!(patched.diff.other_fields, vec!["budget"]);
+
+        let mut stale = loaded.clone();
+        stale.schools.remove(0);
+        let error = save_data(state.clone(), None, stale.clone(), Some(1), 20260301)
+            .await
+            .unwrap_err();
+        let HandlerError::Storage(StorageError::Conflict(conflict)) = &error else {
+            panic!("expected a conflict, got {:?}", error);
+        };
+        assert_eq!((conflict.expected, conflict.current), (1, 2));
+        assert_eq!(conflict.diff.removed.len(), 1);
+        let localized = error.localize(Locale::En);
+        assert_eq!(localized.key, "error.dataConflict");
+        assert_eq!(localized.data.unwrap()["current"], 2);
+        assert!(matches!(
+            patch_data(state.clone(), None, &patch, Some(1), true, 20260301).await,
+            Err(HandlerError::Storage(StorageError::Conflict(_)))
+        ));
+        assert_eq!(state.load_dataset().unwrap()["revision"], 2);
+
+        // Saving again from the latest revision goes through
+        let saved = save_data(state.clone(), None, stale, Some(2), 20260301)
+            .await
+            .unwrap();
+        assert_eq!(saved.revision, 3);
+        assert!(matches!(
+            patch_data(state, None, &serde_json::json!({ "schools": 1 }), None, true, 20260301)
+                .await,
+            Err(HandlerError::InvalidPatch(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 3)]
+    async fn test_concurrent_mark_paid_never_loses_a_payment() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage.clone()));
+        let unpaid = [(1, "enrollmentFee"), (1, "tuition"), (2, "tuition")];
+
+        for _ in 0..20 {
+            storage
+                .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+                .unwrap();
+            let tasks: Vec<_> = unpaid
+                .iter()
+                .map(|&(school_id, fee)| {
+                    let record: PaymentRecord = serde_json::from_value(serde_json::json!({
+                        "schoolId": school_id, "fee": fee,
+                        "paidOn": 20260312, "method": "bankTransfer"
+                    }))
+                    .unwrap();
+                    tokio::spawn(mark_paid(state.clone(), record, false))
+                })
+                .collect();
+
+            let mut results = Vec::new();
+            for task in tasks {
+                results.push(task.await.unwrap());
+            }
+
+            let data = state.load_dataset().unwrap();
+            let mut saved = 0;
+            for (result, (school_id, fee)) in results.into_iter().zip(unpaid) {
+                let school = data["schools"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .find(|s| s["id"] == school_id)
+                    .unwrap();
+                match result {
+                    Ok(_) => {
+                        saved += 1;
+                        assert_eq!(school[format!("{}Paid", fee)], true);
+                    }
+                    // Saved from data another payment had changed meanwhile
+                    Err(e) => assert!(
+                        matches!(e, HandlerError::Storage(StorageError::Conflict(_))),
+                        "expected a conflict, got {:?}",
+                        e
+                    ),
+                }
+            }
+            assert!(saved > 0);
+            assert_eq!(revision::of(&data), saved);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_patch_without_revision_never_overwrites_a_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        let budget = serde_json::json!({ "budget": { "total": 3000000 } });
+        let no_schools = serde_json::json!({ "schools": [] });
+
+        for _ in 0..20 {
+            save_data(state.clone(), None, crate::export::sample_dataset(), None, 20260301)
+                .await
+                .unwrap();
+            let patches = [budget.clone(), no_schools.clone()].map(|patch| {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    patch_data(state, None, &patch, None, false, 20260301).await
+                })
+            });
+            let mut results = Vec::new();
+            for patch in patches {
+                results.push(patch.await.unwrap());
+            }
+
+            // Each patch is either in the saved data or refused, never lost
+            let data = state.load_dataset().unwrap();
+            let applied = [
+                data["budget"]["total"] == 3000000,
+                data["schools"] == serde_json::json!([]),
+            ];
+            for (result, applied) in results.iter().zip(applied) {
+                match result {
+                    Ok(_) => assert!(applied),
+                    Err(e) => assert!(
+                        matches!(e, HandlerError::Storage(StorageError::Conflict(_))),
+                        "expected a conflict, got {:?}",
+                        e
+                    ),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_data_as_of_comes_from_the_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        let mut data = crate::export::sample_dataset();
+        save_data(state.clone(), None, data.clone(), None, 20260301)
+            .await
+            .unwrap();
+        data.schools.remove(0);
+        save_data(state.clone(), None, data, Some(1), 20260301)
+            .await
+            .unwrap();
+
+        let now = date::now().to_rfc3339();
+        let version = get_data_as_of(state.clone(), None, &now).await.unwrap();
+        assert_eq!(version.revision, 2);
+        assert_eq!(version.source, journal::VersionSource::Journal);
+        assert_eq!(version.data["schools"].as_array().unwrap().len(), 1);
+        assert!(get_data_as_of(state.clone(), Some("next"), &now)
+            .await
+            .is_err());
+
+        assert!(matches!(
+            get_data_as_of(state.clone(), None, "2000-01-01").await,
+            Err(HandlerError::NoDataAsOf(_))
+        ));
+        let error = get_data_as_of(state, None, "先週").await.unwrap_err();
+        assert!(matches!(error, HandlerError::InvalidAsOf(_)));
+        assert_eq!(error.localize(Locale::Ja).key, "error.invalidAsOf");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_saves_once_or_not_at_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        let data = crate::export::sample_dataset();
+        save_data(state.clone(), None, data, None, 20260301)
+            .await
+            .unwrap();
+
+        let ops: Vec<BulkOp> = serde_json::from_value(serde_json::json!([
+            { "op": "updateSchool", "schoolId": 1, "patch": { "priority": 5 } },
+            {
+                "op": "recordPayment",
+                "payment": {
+                    "schoolId": 1, "fee": "enrollmentFee",
+                    "paidOn": 20260225, "method": "bankTransfer"
+                }
+            },
+            { "op": "deleteSchool", "schoolId": 2 }
+        ]))
+        .unwrap();
+        let report = bulk_update(state.clone(), None, ops.clone(), Some(1), true, 20260301)
+            .await
+            .unwrap();
+        assert_eq!(report.diff.removed.len(), 1);
+        assert_eq!(state.load_dataset().unwrap()["revision"], 1);
+
+        let report = bulk_update(state.clone(), None, ops, Some(1), false, 20260301)
+            .await
+            .unwrap();
+        assert_eq!(report.result.revision, 2);
+        assert_eq!(report.result.schools.len(), 1);
+        assert!(report.result.schools[0].enrollment_fee_paid);
+
+        // A failing operation leaves the saved data as it was
+        let ops: Vec<BulkOp> = serde_json::from_value(serde_json::json!([
+            { "op": "updateSchool", "schoolId": 1, "patch": { "priority": 9 } },
+            { "op": "deleteSchool", "schoolId": 2 }
+        ]))
+        .unwrap();
+        let error = bulk_update(state.clone(), None, ops, Some(2), false, 20260301)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            HandlerError::Bulk(BulkError::Failed { index: 1, .. })
+        ));
+        let localized = error.localize(Locale::Ja);
+        assert!(localized.message.starts_with("2件目の操作に失敗"));
+        let saved = state.load_dataset().unwrap();
+        assert_eq!(saved["revision"], 2);
+        assert_eq!(saved["schools"][0]["priority"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_record_outcome_saves_logs_and_recomputes() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        // 早稲田大学, ranked second, with nothing paid yet
+        let mut data = crate::export::sample_data();
+        data["schools"][0]["enrollmentFeePaid"] = false.into();
+        storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage.clone()));
+        state.settings.lock().await.outcome_rules.cancel_lower_choices = true;
+
+        let preview = record_outcome(state.clone(), 1, ExamOutcome::Accepted, true, 20260301)
+            .await
+            .unwrap();
+        assert!(preview.dry_run && preview.result.recommendation.is_none());
+        assert_eq!(preview.result.entry.cancelled_school_ids, vec![2]);
+        assert_eq!(state.load_dataset().unwrap(), data);
+
+        let recorded = record_outcome(state.clone(), 1, ExamOutcome::Accepted, false, 20260301)
+            .await
+            .unwrap();
+        assert!(recorded.result.recommendation.is_some());
+        let saved = state.load_dataset().unwrap();
+        assert_eq!(saved["schools"][0]["passStatus"], "cancelled");
+        assert_eq!(saved["schools"][1]["passStatus"], "passed");
+        let history = History::load(&storage).unwrap();
+        assert_eq!(history.outcomes.len(), 1);
+        assert_eq!(history.outcomes[0].school_name, "東京大学");
+        assert_eq!(history.entries.len(), 1);
+        let inputs = get_recommendation_inputs(state.clone(), history.entries[0].id)
+            .await
+            .unwrap();
+        assert_eq!(inputs.params["today"], 20260301);
+        assert_eq!(inputs.params["schools"], advisor_params(&saved)["schools"]);
+        assert!(matches!(
+            get_recommendation_inputs(state.clone(), 9).await,
+            Err(HandlerError::History(HistoryError::UnknownEntry(9)))
+        ));
+
+        assert!(matches!(
+            record_outcome(state, 9, ExamOutcome::Rejected, false, 20260301).await,
+            Err(HandlerError::Outcome(OutcomeError::UnknownSchool(9)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dry_runs_report_the_diff_and_save_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let backup = backup::write_backup(&storage, backup::MANUAL_BACKUP)
+            .unwrap()
+            .unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+        let saved = state.load_dataset().unwrap();
+
+        let record: PaymentRecord = serde_json::from_value(serde_json::json!({
+            "schoolId": 1, "fee": "enrollmentFee", "paidOn": 20260312, "method": "bankTransfer"
+        }))
+        .unwrap();
+        let paid = mark_paid(state.clone(), record, true).await.unwrap();
+        assert!(paid.dry_run);
+        assert_eq!(paid.diff.changed.len(), 1);
+        assert_eq!(paid.diff.changed[0].school_id, 1);
+        assert!(paid.diff.changed[0]
+            .fields
+            .iter()
+            .any(|f| f.field == "enrollmentFeePaid" && f.after == true));
+
+        let template = templates::bundled().remove(0);
+        let added = apply_template(state.clone(), &template.id, 20260310, None, true)
+            .await
+            .unwrap();
+        assert_eq!(added.diff.added.len(), 1);
+        assert_eq!(added.diff.added[0].id, Some(added.result.id));
+
+        let mut data = saved.clone();
+        data["schools"][0]["name"] = serde_json::json!("Renamed");
+        let bytes = serde_json::to_vec(&data).unwrap();
+        let preview = preview_import(state.clone(), "data.json", &bytes).await.unwrap();
+        let imported = confirm_import(state.clone(), &preview.token, &BTreeMap::new(), true)
+            .await
+            .unwrap();
+        assert_eq!(imported.diff.changed[0].fields[0].field, "name");
+
+        let restored = restore_backup(state.clone(), &backup.file_name, true)
+            .await
+            .unwrap();
+        assert!(restored.diff.is_empty());
+
+        assert_eq!(state.load_dataset().unwrap(), saved);
+        let storage = state.storage.as_ref().unwrap();
+        assert_eq!(backup::list_backups(storage).unwrap().len(), 1);
+
+        // The previewed import can still be applied for real
+        let applied = confirm_import(state.clone(), &preview.token, &BTreeMap::new(), false)
+            .await
+            .unwrap();
+        assert_eq!(applied.diff, imported.diff);
+        assert_eq!(state.load_dataset().unwrap(), applied.result);
+    }
+
+    #[tokio::test]
+    async fn test_applied_template_saves_the_current_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(
+            AppState::new(repl).with_storage(Storage::new(dir.path().to_path_buf())),
+        );
+
+        let template = templates::bundled().remove(0);
+        apply_template(state.clone(), &template.id, 20260310, None, false)
+            .await
+            .unwrap();
+        let data = state.load_dataset().unwrap();
+        assert_eq!(migrations::schema_version(&data), CURRENT_SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_other_instance_holding_the_edit_lock_makes_data_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage).with_edit_lock("web"));
+        let desktop = EditLock::new("desktop");
+        let shared = Storage::new(dir.path().to_path_buf());
+        desktop.acquire(&shared, &date::now().to_rfc3339()).unwrap();
+
+        let status = acquire_edit_lock(state.clone()).await.unwrap();
+        assert!(status.read_only);
+        let record: PaymentRecord = serde_json::from_value(serde_json::json!({
+            "schoolId": 1, "fee": "enrollmentFee", "paidOn": 20260312, "method": "bankTransfer"
+        }))
+        .unwrap();
+        assert!(mark_paid(state.clone(), record.clone(), true).await.is_ok());
+        assert!(matches!(
+            mark_paid(state.clone(), record.clone(), false).await,
+            Err(HandlerError::ReadOnly(holder)) if holder == "desktop"
+        ));
+
+        desktop.release(&shared, &date::now().to_rfc3339()).unwrap();
+        assert!(acquire_edit_lock(state.clone()).await.unwrap().held);
+        mark_paid(state.clone(), record, false).await.unwrap();
+        assert!(!release_edit_lock(state).await.unwrap().held);
+    }
+
+    #[tokio::test]
+    async fn test_moved_data_dir_is_used_at_once() {
+        let root = tempfile::tempdir().unwrap();
+        let default_dir = root.path().join("app");
+        let storage = Storage::new(default_dir.clone());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage.clone()));
+
+        let nas = root.path().join("nas");
+        let moved = move_data_dir(state.clone(), &default_dir, &nas).await.unwrap();
+        assert_eq!(moved.files, 1);
+        assert_eq!(storage.data_dir(), nas);
+        assert_eq!(data_location::resolve(&default_dir), nas);
+        assert_eq!(state.load_dataset().unwrap(), crate::export::sample_data());
+
+        let record: PaymentRecord = serde_json::from_value(serde_json::json!({
+            "schoolId": 1, "fee": "enrollmentFee", "paidOn": 20260312, "method": "bankTransfer"
+        }))
+        .unwrap();
+        mark_paid(state, record, false).await.unwrap();
+        assert!(nas.join(SCHOOLS_DATA_FILE).exists());
+        assert!(!default_dir.join(SCHOOLS_DATA_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_mark_paid_is_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(
+            AppState::new(repl)
+                .with_storage(storage)
+                .with_audit_key(FieldKey::generate()),
+        );
+
+        let record: PaymentRecord = serde_json::from_value(serde_json::json!({
+            "schoolId": 1, "fee": "enrollmentFee", "paidOn": 20260312, "method": "bankTransfer"
+        }))
+        .unwrap();
+        mark_paid(state.clone(), record, false).await.unwrap();
+
+        let entries = list_audit_log(state.clone()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::PaymentRecorded);
+        assert_eq!(entries[0].details["paidOn"], 20260312);
+        assert!(verify_audit_log(state).await.unwrap().valid);
+    }
+
+    #[tokio::test]
+    async fn test_notes_are_saved_and_found_but_kept_from_the_advisor() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+
+        let note: NewNote = serde_json::from_value(serde_json::json!({
+            "schoolId": 1, "text": "入学手続きの書類は郵送", "fee": "enrollmentFee"
+        }))
+        .unwrap();
+        let report = add_note(state.clone(), note, false).await.unwrap();
+        assert_eq!(report.result.schools[1].notes.len(), 1);
+
+        let hits = search(state.clone(), "書類 郵送").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].school_id, hits[0].note), (1, Some(0)));
+
+        let data = state.load_dataset().unwrap();
+        assert!(data["schools"][1].get("notes").is_some());
+        assert!(advisor_params(&data)["schools"][1].get("notes").is_none());
+
+        delete_note(state.clone(), 1, 0, false).await.unwrap();
+        assert!(search(state.clone(), "郵送").await.unwrap().is_empty());
+        assert!(matches!(
+            delete_note(state, 1, 0, false).await,
+            Err(HandlerError::Note(NoteError::UnknownNote { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_push_subscriptions_need_vapid_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let subscription: NewPushSubscription = serde_json::from_value(serde_json::json!({
+            "endpoint": "https://fcm.googleapis.com/fcm/send/abc",
+            "keys": {
+                "p256dh": "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4",
+                "auth": "BTBZMqHH6r4Tts7J_aSIgg"
+            },
+            "user": "母"
+        }))
+        .unwrap();
+
+        let state = Arc::new(AppState::new(repl).with_storage(storage.clone()));
+        assert!(matches!(
+            subscribe_push(state, subscription.clone()).await,
+            Err(HandlerError::PushUnavailable)
+        ));
+
+        let config = crate::web_push::WebPushConfig {
+            vapid_public_key: "BGSOZmm1CqAPoAOFyD70i6o2NUi5bL5uJKgtjON9tkH2PPPRhQ_YlQzkpjepbw3fZQXjYfVvk2Y0HR8j2Lt6cnk"
+                .to_string(),
+            vapid_private_key: "WDZVjfvcqkP4QkUixi3iMM8RbGH0MV8Fn0eZpcsGZlY".to_string(),
+            subject: "mailto:admin@example.com".to_string(),
+        };
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(
+            AppState::new(repl)
+                .with_storage(storage)
+                .with_web_push(WebPush::new(&config).unwrap()),
+        );
+        assert_eq!(
+            push_public_key(state.clone()).await.unwrap(),
+            config.vapid_public_key
+        );
+        subscribe_push(state.clone(), subscription.clone())
+            .await
+            .unwrap();
+        let saved = list_push_subscriptions(state.clone(), Some("母"))
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 1);
+        assert!(list_push_subscriptions(state.clone(), Some("父"))
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(unsubscribe_push(state.clone(), &subscription.endpoint)
+            .await
+            .unwrap());
+        assert!(!unsubscribe_push(state, &subscription.endpoint)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_explanations_are_served_from_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let mut cache = ExplanationCache::default();
+        cache.insert(
+            "abc".to_string(),
+            Explanation {
+                summary: "東京大学の発表待ちです".to_string(),
+                factors: Vec::new(),
+            },
+        );
+        cache.save(&storage).unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage));
+
+        let explanation = get_explanation(state.clone(), "abc".to_string())
+            .await
+            .unwrap();
+        assert_eq!(explanation.summary, "東京大学の発表待ちです");
+        assert!(matches!(
+            get_explanation(state, "missing".to_string()).await,
+            Err(HandlerError::UnknownExplanation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_explanation_fetches_are_counted() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl));
+        let request =
+            JsonRpcRequest::new("explain", &serde_json::json!({}), serde_json::json!("explain"));
+        state
+            .pending_explanations
+            .lock()
+            .await
+            .insert("abc".to_string(), request);
+
+        assert!(get_explanation(state.clone(), "abc".to_string()).await.is_err());
+        let stats = get_method_stats(state).await;
+        let explain = stats.iter().find(|s| s.method == "explain").unwrap();
+        assert_eq!((explain.calls, explain.errors), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_update_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(
+            AppState::new(repl).with_storage(Storage::new(dir.path().to_path_buf())),
+        );
+
+        let mut settings = get_settings(state.clone()).await;
+        settings.reminder_lead_days = 10;
+        update_settings(state.clone(), settings.clone()).await.unwrap();
+        assert_eq!(get_settings(state.clone()).await, settings);
+
+        settings.reminder_lead_days = 99;
+        assert!(matches!(
+            update_settings(state.clone(), settings).await,
+            Err(HandlerError::Settings(_))
+        ));
+        assert_eq!(get_settings(state.clone()).await.reminder_lead_days, 10);
+
+        let events = recent_events(state.clone(), EventQuery::default()).await;
+        assert!(events.unwrap().is_empty());
+
+        // Tracing is not set up by the tests, so there is no filter to change
+        assert!(matches!(
+            set_log_filter(state, "trace").await,
+            Err(HandlerError::LogLevelUnavailable)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_localized_error_follows_settings() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let settings = Settings {
+            locale: "en".to_string(),
+            ..Settings::default()
+        };
+        let state = AppState::new(repl).with_settings(settings);
+
+        let error = HandlerError::UnknownBackup("exit-1.json".to_string());
+        let localized = error.localize(state.locale().await);
+        assert_eq!(localized.key, "error.unknownBackup");
+        assert_eq!(localized.message, "Backup exit-1.json was not found");
+        assert_eq!(localized.detail, "Backup not found: exit-1.json");
+
+        let localized = HandlerError::NoData.localize(Locale::Ja);
+        assert_eq!(localized.message, "保存されたデータがありません");
+    }
+
+    #[tokio::test]
+    async fn test_recorded_session_replays() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        assert!(matches!(
+            stop_recording(state.clone()).await,
+            Err(HandlerError::NotRecording)
+        ));
+
+        start_recording(state.clone()).await;
+        let version = JsonRpcRequest {
+            method: "getVersion".to_string(),
+            ..request(1)
+        };
+        let recorded = send_rpc(state.clone(), version.clone()).await.unwrap();
+        assert_eq!(recording_status(state.clone()).await.exchanges, 1);
+        let path = stop_recording(state.clone()).await.unwrap();
+        assert!(!recording_status(state).await.recording);
+
+        let session = Session::load(std::path::Path::new(&path)).unwrap();
+        assert_eq!(session.revisions[0].data, crate::export::sample_data());
+        let replayed = LeanRepl::replay(crate::recording::ReplayAdvisor::new(session))
+            .send_request(&version)
+            .unwrap();
+        assert_eq!(replayed.result_value(), recorded.result_value());
+    }
+
+    #[tokio::test]
+    async fn test_stream_rpc_chunks_a_year_of_recommendations() {
+        let state = Arc::new(AppState::new(LeanRepl::demo()));
+        let mut params = advisor_params(&crate::export::sample_data());
+        params["startDay"] = 20260101.into();
+        params["days"] = 365.into();
+        let request =
+            JsonRpcRequest::new("getWeeklyRecommendations", &params, serde_json::json!(1));
+
+        let chunks: Vec<_> = stream_rpc(state, request, 100).await.unwrap().collect();
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[0].total, 365);
+        assert!(chunks[0].header.is_some());
+        assert_eq!(chunks[3].items.len(), 65);
+        assert!(chunks[3].done);
+    }
+
+    #[tokio::test]
+    async fn test_method_stats_count_calls_and_errors() {
+        let state = Arc::new(AppState::new(LeanRepl::demo()));
+        let version = JsonRpcRequest {
+            method: "getVersion".to_string(),
+            ..request(1)
+        };
+        send_rpc(state.clone(), version.clone()).await.unwrap();
+        send_rpc(state.clone(), version).await.unwrap();
+        let unknown = JsonRpcRequest {
+            method: "noSuchMethod".to_string(),
+            ..request(2)
+        };
+        let response = send_rpc(state.clone(), unknown).await.unwrap();
+        assert!(response.error.is_some());
+
+        let stats = get_method_stats(state).await;
+        let found: Vec<_> = stats
+            .iter()
+            .map(|s| (s.method.as_str(), s.calls, s.errors))
+            .collect();
+        assert_eq!(found, [("getVersion", 2, 0), ("noSuchMethod", 1, 1)]);
+        assert_eq!(stats[1].error_rate, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_busy_advisor_overflows_to_pool() {
+        let state = Arc::new(AppState::new(LeanRepl::demo()));
+        let version = JsonRpcRequest {
+            method: "getVersion".to_string(),
+            ..request(1)
+        };
+        send_rpc(state.clone(), version.clone()).await.unwrap();
+        assert_eq!(state.repl_pool().size(), 0);
+
+        let busy = state.lean_repl.lock().await;
+        let response = send_rpc(state.clone(), version).await.unwrap();
+        drop(busy);
+        assert!(response.result.is_some());
+        assert_eq!(state.repl_pool().size(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pooled_simulation_matches_main_advisor() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        let scenarios: Vec<Scenario> = serde_json::from_value(serde_json::json!([
+            { "name": "decline 1", "mutations": [{ "type": "decline", "schoolId": 1 }] },
+            { "name": "decline 2", "mutations": [{ "type": "decline", "schoolId": 2 }] },
+            {
+                "name": "late result",
+                "mutations": [{ "type": "delayResult", "schoolId": 1, "days": 7 }]
+            },
+        ]))
+        .unwrap();
+
+        let mut settings = Settings::default();
+        settings.advisor.pool_max = 3;
+        update_settings(state.clone(), settings.clone()).await.unwrap();
+        let pooled = simulate(state.clone(), scenarios.clone(), 20260301).await.unwrap();
+        assert!(state.repl_pool().size() <= 3);
+        settings.advisor.pool_max = 0;
+        update_settings(state.clone(), settings).await.unwrap();
+        let sequential = simulate(state.clone(), scenarios, 20260301).await.unwrap();
+        assert_eq!(state.repl_pool().size(), 0);
+
+        let without_timings = |result: SimulationResult| SimulationResult {
+            elapsed_ms: 0,
+            ..result.with_timings(&[Duration::ZERO; 4], Duration::ZERO)
+        };
+        assert_eq!(without_timings(pooled), without_timings(sequential));
+    }
+
+    #[tokio::test]
+    async fn test_simulation_leaves_the_plan_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let data = crate::export::sample_data();
+        storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage));
+        let dataset = Dataset::from_value(data).unwrap();
+        let weekly = simulation::request(&dataset, 20260301, 0);
+        state
+            .preload
+            .insert(&weekly, JsonRpcResponse::success(0.into(), serde_json::json!({})));
+        let scenarios: Vec<Scenario> = serde_json::from_value(serde_json::json!([
+            { "name": "decline 1", "mutations": [{ "type": "decline", "schoolId": 1 }] }
+        ]))
+        .unwrap();
+
+        // Planned by the main advisor alone, then with the pool
+        for pool_max in [0, 2] {
+            let mut settings = Settings::default();
+            settings.advisor.pool_max = pool_max;
+            update_settings(state.clone(), settings).await.unwrap();
+            simulate(state.clone(), scenarios.clone(), 20260301).await.unwrap();
+        }
+
+        assert!(state.reminders.lock().await.last_dataset.is_none());
+        assert!(cached_result(state.clone(), &weekly).await.is_none());
+        assert!(state.preload.take(&weekly).is_some());
+        let calls = get_method_stats(state).await;
+        assert_eq!(calls[0].method, "getWeeklyRecommendations");
+        assert_eq!(calls[0].calls, 4);
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_updates_status() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl));
+        let mut status = state.subscribe_advisor_status();
+        assert_eq!(state.advisor_status().state, AdvisorState::Starting);
+
+        assert!(send_rpc(state.clone(), request(1)).await.is_err());
+        assert!(status.has_changed().unwrap());
+        assert_eq!(status.borrow_and_update().state, AdvisorState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_restart_is_recorded_in_health_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state =
+            Arc::new(AppState::new(repl).with_storage(Storage::new(dir.path().to_path_buf())));
+
+        assert!(restart_repl(state.clone()).await.is_err());
+        let events = health_history(state).await;
+        let kinds: Vec<HealthEventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                HealthEventKind::Restarted,
+                HealthEventKind::StatusChanged,
+                HealthEventKind::StatusChanged
+            ]
+        );
+        assert_eq!(events[2].state, Some(AdvisorState::Stopped));
+        assert_eq!(HealthHistory::load(&storage).unwrap().events, events);
+    }
+
+    #[tokio::test]
+    async fn test_batch_empty() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl));
+
+        let responses = send_rpc_batch(state, Vec::new(), 0).await;
+        assert!(responses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_too_large_is_rejected() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl));
+
+        let requests = (0..=MAX_BATCH_SIZE as u64).map(request).collect();
+        let responses = send_rpc_batch(state, requests, 2).await;
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].id, serde_json::Value::Null);
+        assert_eq!(responses[0].error.as_ref().unwrap().code, -32600);
+    }
+
+    #[tokio::test]
+    async fn test_onboarding_steps_are_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage.clone()));
+
+        complete_onboarding_step(state.clone(), OnboardingStep::Locale)
+            .await
+            .unwrap();
+        assert!(matches!(
+            complete_onboarding_step(state.clone(), OnboardingStep::Data).await,
+            Err(HandlerError::NoData)
+        ));
+
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        for step in [
+            OnboardingStep::Data,
+            OnboardingStep::Reminders,
+            OnboardingStep::Advisor,
+        ] {
+            complete_onboarding_step(state.clone(), step).await.unwrap();
+        }
+        assert!(onboarding_state(state).await.unwrap().is_finished());
+    }
+}
@@ -0,0 +1,134 @@
+//! Persisted timeline of advisor health.
+//!
+//! Every change of the advisor status, every crash and every restart is
+//! recorded with its time in [`HEALTH_HISTORY_FILE`], so a report such as
+//! "it keeps dying every evening" can be checked against real timestamps,
+//! including those from earlier sessions. Only the latest [`MAX_EVENTS`]
+//! are kept.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::storage::{Storage, StorageError};
+use crate::supervisor::{AdvisorState, AdvisorStatus};
+
+/// File in the data directory holding the health history
+pub const HEALTH_HISTORY_FILE: &str = "health_history.json";
+
+/// Events kept; the oldest are dropped first
+pub const MAX_EVENTS: usize = 500;
+
+/// What happened to the advisor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum HealthEventKind {
+    /// The advisor status changed
+    StatusChanged,
+    /// The advisor process exited or stopped answering
+    Crashed,
+    /// The advisor was restarted, by the user or the watchdog
+    Restarted,
+}
+
+/// One entry of the health history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HealthEvent {
+    /// RFC 3339 time in Japan
+    pub time: String,
+    pub kind: HealthEventKind,
+    /// Status from this event on, for status changes
+    pub state: Option<AdvisorState>,
+    pub reason: Option<String>,
+}
+
+impl HealthEvent {
+    /// Event happening now
+    pub fn new(kind: HealthEventKind, state: Option<AdvisorState>, reason: Option<String>) -> Self {
+        Self {
+            time: crate::date::now().to_rfc3339(),
+            kind,
+            state,
+            reason,
+        }
+    }
+
+    /// The status changing to `status`
+    pub fn status_changed(status: &AdvisorStatus) -> Self {
+        Self::new(
+            HealthEventKind::StatusChanged,
+            Some(status.state),
+            status.reason.clone(),
+        )
+    }
+}
+
+/// Recorded health events, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthHistory {
+    pub events: Vec<HealthEvent>,
+}
+
+impl HealthHistory {
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(HEALTH_HISTORY_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(HEALTH_HISTORY_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Add `event`, dropping the oldest beyond [`MAX_EVENTS`]
+    pub fn record(&mut self, event: HealthEvent) {
+        self.events.push(event);
+        if self.events.len() > MAX_EVENTS {
+            let excess = self.events.len() - MAX_EVENTS;
+            self.events.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        assert!(HealthHistory::load(&storage).unwrap().events.is_empty());
+
+        let mut history = HealthHistory::default();
+        history.record(HealthEvent::status_changed(&AdvisorStatus::new(
+            AdvisorState::Stopped,
+            "exited",
+        )));
+        history.record(HealthEvent::new(HealthEventKind::Restarted, None, None));
+        history.save(&storage).unwrap();
+
+        let loaded = HealthHistory::load(&storage).unwrap();
+        assert_eq!(loaded, history);
+        assert_eq!(loaded.events[0].state, Some(AdvisorState::Stopped));
+        assert_eq!(loaded.events[0].reason.as_deref(), Some("exited"));
+    }
+
+    #[test]
+    fn test_keeps_the_latest() {
+        let mut history = HealthHistory::default();
+        for i in 0..MAX_EVENTS + 3 {
+            history.record(HealthEvent::new(
+                HealthEventKind::Crashed,
+                None,
+                Some(i.to_string()),
+            ));
+        }
+        assert_eq!(history.events.len(), MAX_EVENTS);
+        assert_eq!(history.events[0].reason.as_deref(), Some("3"));
+    }
+}
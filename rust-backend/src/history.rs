@@ -0,0 +1,468 @@
+//! History of the advisor's recommendations and what the family did.
+//!
+//! Every distinct `getRecommendation` result is appended to
+//! [`HISTORY_FILE`] as a [`HistoryEntry`]; the user then marks each one as
+//! paid, skipped or deferred. Entries are plain JSON objects with the
+//! recommended action flattened to top-level fields, so they can be listed
+//! with [`ListQuery`](crate::query::ListQuery) like schools are. At the end
+//! of the season [`stats`] totals the decisions and the enrollment fees
+//! that never had to be paid.
+//!
+//! Exam results recorded as they are announced are kept alongside, as
+//! [`OutcomeEntry`] items.
+//!
+//! The exact advisor parameters each recommendation was computed from are
+//! kept apart in [`HISTORY_INPUTS_FILE`], for the most recent
+//! [`MAX_HISTORY_INPUTS`] entries, so the history itself stays small to
+//! list while "why did it say this?" can still be answered.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::model::{Dataset, GetRecommendationResult, PassStatus, PaymentActionType};
+use crate::outcome::OutcomeEntry;
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding the history
+pub const HISTORY_FILE: &str = "history.json";
+
+/// Oldest entries are dropped beyond this many
+pub const MAX_HISTORY_ENTRIES: usize = 5000;
+
+/// File in the data directory holding the advisor inputs of the entries
+pub const HISTORY_INPUTS_FILE: &str = "history_inputs.json";
+
+/// Inputs of older entries are dropped beyond this many
+pub const MAX_HISTORY_INPUTS: usize = 200;
+
+/// Errors that can occur while updating the history
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error("History entry not found: {0}")]
+    UnknownEntry(u64),
+
+    #[error("No advisor inputs kept for history entry {0}")]
+    NoInputs(u64),
+}
+
+/// What the user did about a recommendation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum Decision {
+    Paid,
+    Skipped,
+    Deferred,
+}
+
+impl Decision {
+    /// Name used in stored data
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Paid => "paid",
+            Self::Skipped => "skipped",
+            Self::Deferred => "deferred",
+        }
+    }
+}
+
+/// One recommendation set and the decision taken on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HistoryEntry {
+    #[ts(type = "number")]
+    pub id: u64,
+    /// RFC 3339 time the advisor returned the recommendation
+    pub recorded_at: String,
+    /// Day the recommendation was for
+    pub day: u32,
+    pub action_type: PaymentActionType,
+    #[ts(type = "number | null")]
+    pub school_id: Option<u64>,
+    pub school_name: Option<String>,
+    /// Amount of the recommended payment, in the school's currency
+    #[ts(type = "number | null")]
+    pub amount: Option<u64>,
+    pub recommendation: GetRecommendationResult,
+    pub decision: Option<Decision>,
+    pub decided_at: Option<String>,
+    pub note: Option<String>,
+}
+
+/// The stored history
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct History {
+    pub next_id: u64,
+    pub entries: Vec<HistoryEntry>,
+    /// Exam results, oldest first; missing in files written before them
+    #[serde(default)]
+    pub outcomes: Vec<OutcomeEntry>,
+}
+
+/// Advisor parameters of history entries, by entry id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryInputs {
+    inputs: BTreeMap<u64, serde_json::Value>,
+}
+
+/// The exact parameters a recommendation was computed from
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RecommendationInputs {
+    #[ts(type = "number")]
+    pub entry_id: u64,
+    /// Day the recommendation was for
+    pub day: u32,
+    /// RFC 3339 time the advisor returned the recommendation
+    pub recorded_at: String,
+    /// `getRecommendation` parameters as sent to the advisor
+    #[ts(type = "unknown")]
+    pub params: serde_json::Value,
+}
+
+/// Count and amount of recommendations with one decision
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DecisionTotal {
+    pub count: usize,
+    #[ts(type = "number")]
+    pub amount: u64,
+}
+
+/// Season totals for review
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HistoryStats {
+    pub recommendations: usize,
+    /// Recommendations to wait rather than pay
+    pub wait_recommendations: usize,
+    pub paid: DecisionTotal,
+    pub skipped: DecisionTotal,
+    pub deferred: DecisionTotal,
+    pub undecided: usize,
+    /// Enrollment fees of passed schools whose deadline went by unpaid
+    #[ts(type = "number")]
+    pub avoided_enrollment_fees: u64,
+}
+
+impl History {
+    /// Load the saved history, or an empty one
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(HISTORY_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(HISTORY_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Append a recommendation for `day`, unless it repeats the latest entry.
+    ///
+    /// `params` are the advisor parameters it was computed from, used to
+    /// name the school and amount. Returns whether an entry was added.
+    pub fn record(
+        &mut self,
+        day: u32,
+        params: &serde_json::Value,
+        recommendation: GetRecommendationResult,
+        recorded_at: &str,
+    ) -> bool {
+        if self
+            .entries
+            .last()
+            .is_some_and(|last| last.day == day && last.recommendation == recommendation)
+        {
+            return false;
+        }
+
+        let action = &recommendation.action;
+        let school = action.school_id.and_then(|id| {
+            params
+                .get("schools")?
+                .as_array()?
+                .iter()
+                .find(|s| s.get("id").and_then(|v| v.as_u64()) == Some(id))
+        });
+        let amount_key = match action.action_type {
+            PaymentActionType::PayEnrollmentFee => Some("enrollmentFee"),
+            PaymentActionType::PayTuition => Some("tuition"),
+            PaymentActionType::DoNothing => None,
+        };
+
+        self.next_id += 1;
+        self.entries.push(HistoryEntry {
+            id: self.next_id,
+            recorded_at: recorded_at.to_string(),
+            day,
+            action_type: action.action_type,
+            school_id: action.school_id,
+            school_name: school
+                .and_then(|s| s.get("name")?.as_str())
+                .map(String::from),
+            amount: school
+                .zip(amount_key)
+                .and_then(|(s, key)| s.get(key)?.as_u64()),
+            recommendation,
+            decision: None,
+            decided_at: None,
+            note: None,
+        });
+        if self.entries.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.entries.len() - MAX_HISTORY_ENTRIES;
+            self.entries.drain(..excess);
+        }
+        true
+    }
+
+    /// Append an exam result, dropping the oldest beyond the entry limit
+    pub fn record_outcome(&mut self, entry: OutcomeEntry) {
+        self.outcomes.push(entry);
+        if self.outcomes.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.outcomes.len() - MAX_HISTORY_ENTRIES;
+            self.outcomes.drain(..excess);
+        }
+    }
+
+    /// Record what the user did about entry `id`; `None` clears the decision
+    pub fn decide(
+        &mut self,
+        id: u64,
+        decision: Option<Decision>,
+        note: Option<String>,
+        decided_at: &str,
+    ) -> Result<&HistoryEntry, HistoryError> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or(HistoryError::UnknownEntry(id))?;
+        entry.decision = decision;
+        entry.decided_at = decision.map(|_| decided_at.to_string());
+        entry.note = note.filter(|n| !n.trim().is_empty());
+        Ok(entry)
+    }
+}
+
+impl HistoryInputs {
+    /// Load the saved inputs, or none
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(HISTORY_INPUTS_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(HISTORY_INPUTS_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Keep `params` for entry `id`, dropping the inputs of entries no
+    /// longer in `history` and the oldest beyond [`MAX_HISTORY_INPUTS`]
+    pub fn insert(&mut self, id: u64, params: serde_json::Value, history: &History) {
+        self.inputs.insert(id, params);
+        self.inputs
+            .retain(|id, _| history.entries.iter().any(|e| e.id == *id));
+        while self.inputs.len() > MAX_HISTORY_INPUTS {
+            self.inputs.pop_first();
+        }
+    }
+
+    /// Inputs of entry `id` in `history`
+    pub fn get(&self, history: &History, id: u64) -> Result<RecommendationInputs, HistoryError> {
+        let entry = history
+            .entries
+            .iter()
+            .find(|e| e.id == id)
+            .ok_or(HistoryError::UnknownEntry(id))?;
+        let params = self.inputs.get(&id).ok_or(HistoryError::NoInputs(id))?;
+        Ok(RecommendationInputs {
+            entry_id: id,
+            day: entry.day,
+            recorded_at: entry.recorded_at.clone(),
+            params: params.clone(),
+        })
+    }
+}
+
+/// Totals of `history`, with fees avoided judged against `dataset` on `today`
+pub fn stats(history: &History, dataset: &Dataset, today: u32) -> HistoryStats {
+    let mut stats = HistoryStats {
+        recommendations: history.entries.len(),
+        ..HistoryStats::default()
+    };
+    for entry in &history.entries {
+        if entry.action_type == PaymentActionType::DoNothing {
+            stats.wait_recommendations += 1;
+        }
+        let total = match entry.decision {
+            Some(Decision::Paid) => &mut stats.paid,
+            Some(Decision::Skipped) => &mut stats.skipped,
+            Some(Decision::Deferred) => &mut stats.deferred,
+            None => {
+                stats.undecided += 1;
+                continue;
+            }
+        };
+        total.count += 1;
+        total.amount += entry.amount.unwrap_or(0);
+    }
+    stats.avoided_enrollment_fees = dataset
+        .schools
+        .iter()
+        .filter(|s| {
+            s.pass_status == PassStatus::Passed
+                && !s.enrollment_fee_paid
+                && s.enrollment_fee_deadline < today
+        })
+        .map(|s| s.net_enrollment_fee())
+        .sum();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recommendation(action: serde_json::Value) -> GetRecommendationResult {
+        serde_json::from_value(serde_json::json!({
+            "action": action,
+            "reason": "",
+            "urgency": 1,
+            "allRecommendations": []
+        }))
+        .unwrap()
+    }
+
+    fn params() -> serde_json::Value {
+        serde_json::json!({
+            "schools": [{"id": 2, "name": "早稲田大学", "enrollmentFee": 200000, "tuition": 800000}]
+        })
+    }
+
+    #[test]
+    fn test_record_skips_repeats() {
+        let mut history = History::default();
+        let pay = recommendation(serde_json::json!({"type": "payTuition", "schoolId": 2}));
+        let at = "2026-03-01T09:00:00+09:00";
+
+        assert!(history.record(20260301, &params(), pay.clone(), at));
+        assert!(!history.record(20260301, &params(), pay.clone(), at));
+        assert!(history.record(20260302, &params(), pay, at));
+
+        let entry = &history.entries[0];
+        assert_eq!(entry.school_name.as_deref(), Some("早稲田大学"));
+        assert_eq!(entry.amount, Some(800000));
+        assert_eq!(history.entries[1].id, 2);
+
+        // Entries list by their flattened fields
+        let json = serde_json::to_value(entry).unwrap();
+        assert_eq!(json["actionType"], "payTuition");
+        assert_eq!(json["schoolId"], 2);
+    }
+
+    #[test]
+    fn test_decisions_and_stats() {
+        let mut history = History::default();
+        let at = "2026-03-01T09:00:00+09:00";
+        for (day, action) in [
+            (20260301, serde_json::json!({"type": "doNothing"})),
+            (
+                20260302,
+                serde_json::json!({"type": "payEnrollmentFee", "schoolId": 2}),
+            ),
+            (
+                20260303,
+                serde_json::json!({"type": "payTuition", "schoolId": 2}),
+            ),
+        ] {
+            history.record(day, &params(), recommendation(action), at);
+        }
+        history
+            .decide(2, Some(Decision::Paid), Some(" ".to_string()), at)
+            .unwrap();
+        let entry = history
+            .decide(
+                3,
+                Some(Decision::Deferred),
+                Some("ボーナス待ち".to_string()),
+                at,
+            )
+            .unwrap();
+        assert_eq!(entry.note.as_deref(), Some("ボーナス待ち"));
+        assert!(matches!(
+            history.decide(9, None, None, at),
+            Err(HistoryError::UnknownEntry(9))
+        ));
+
+        let dataset: Dataset = serde_json::from_value(serde_json::json!({
+            "schools": [{
+                "id": 5, "name": "明治大学",
+                "examDate": 20260201, "resultDate": 20260210,
+                "enrollmentFeeDeadline": 20260220, "tuitionDeadline": 20260315,
+                "enrollmentFee": 250000, "tuition": 600000, "passStatus": "passed"
+            }]
+        }))
+        .unwrap();
+        let stats = stats(&history, &dataset, 20260310);
+        assert_eq!(stats.recommendations, 3);
+        assert_eq!(stats.wait_recommendations, 1);
+        assert_eq!(
+            stats.paid,
+            DecisionTotal {
+                count: 1,
+                amount: 200000
+            }
+        );
+        assert_eq!(stats.deferred.amount, 800000);
+        assert_eq!(stats.undecided, 1);
+        assert_eq!(stats.avoided_enrollment_fees, 250000);
+    }
+
+    #[test]
+    fn test_inputs_follow_the_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let mut history = History::default();
+        let mut inputs = HistoryInputs::default();
+        let at = "2026-03-01T09:00:00+09:00";
+        let wait = recommendation(serde_json::json!({"type": "doNothing"}));
+
+        history.record(20260301, &params(), wait.clone(), at);
+        inputs.insert(1, params(), &history);
+        history.record(20260302, &serde_json::Value::Null, wait, at);
+        inputs.save(&storage).unwrap();
+
+        let inputs = HistoryInputs::load(&storage).unwrap();
+        let kept = inputs.get(&history, 1).unwrap();
+        assert_eq!(kept.day, 20260301);
+        assert_eq!(kept.params, params());
+        assert!(matches!(
+            inputs.get(&history, 2),
+            Err(HistoryError::NoInputs(2))
+        ));
+        assert!(matches!(
+            inputs.get(&history, 9),
+            Err(HistoryError::UnknownEntry(9))
+        ));
+
+        // Inputs of entries dropped from the history go with them
+        let mut inputs = inputs;
+        history.entries.remove(0);
+        inputs.insert(2, serde_json::Value::Null, &history);
+        assert_eq!(inputs.inputs.keys().copied().collect::<Vec<_>>(), vec![2]);
+    }
+}
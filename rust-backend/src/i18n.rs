@@ -0,0 +1,514 @@
+//! Localized user-facing messages.
+//!
+//! Messages are identified by a stable key (`error.noData`,
+//! `import.invalidDate`, ...) with named arguments, and rendered from a
+//! Japanese or English catalog. The frontend receives both the key, for
+//! its own handling, and the rendered text in the locale from the user's
+//! settings.
+
+use serde::Serialize;
+
+/// Languages with a message catalog
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Locale {
+    /// Locale for a language tag such as `ja`, `en` or `en-US`, defaulting to Japanese
+    pub fn from_tag(tag: &str) -> Self {
+        if tag.to_ascii_lowercase().starts_with("en") {
+            Self::En
+        } else {
+            Self::Ja
+        }
+    }
+}
+
+/// Catalog entry for `key` as (Japanese, English)
+fn templates(key: &str) -> Option<(&'static str, &'static str)> {
+    Some(match key {
+        "error.advisorStartFailed" => (
+            "アドバイザーを起動できませんでした: {detail}",
+            "Could not start the advisor: {detail}",
+        ),
+        "error.advisorNotRunning" => (
+            "アドバイザーが動いていません",
+            "The advisor is not running",
+        ),
+        "error.advisorTimeout" => (
+            "アドバイザーの応答がありません。しばらくしてから再度お試しください",
+            "The advisor did not respond. Please try again shortly",
+        ),
+        "error.advisorFailed" => (
+            "アドバイザーとの通信に失敗しました: {detail}",
+            "Communication with the advisor failed: {detail}",
+        ),
+        "error.advisor" => (
+            "アドバイザーがエラーを返しました: {detail}",
+            "The advisor returned an error: {detail}",
+        ),
+        "error.storage" => (
+            "データの読み書きに失敗しました: {detail}",
+            "Could not read or write data: {detail}",
+        ),
+        "error.invalidDatasetName" => (
+            "プラン名「{name}」は使用できません",
+            "\"{name}\" cannot be used as a plan name",
+        ),
+        "error.fieldsLocked" => (
+            "データの一部が暗号化されています。暗号化キーを設定してください",
+            "Some of the data is encrypted; set up the encryption key",
+        ),
+        "error.fieldKeyMismatch" => (
+            "暗号化キーが一致しないため、データを復号できません",
+            "The data could not be decrypted; the encryption key does not match",
+        ),
+        "error.invalidFieldKey" => (
+            "暗号化キーのコードが正しくありません",
+            "The encryption key code is not valid",
+        ),
+        "error.wrongPassword" => (
+            "パスワードが正しくありません",
+            "The password is not correct",
+        ),
+        "error.weakPassword" => (
+            "パスワードは{min}文字以上にしてください",
+            "The password must be at least {min} characters",
+        ),
+        "error.fieldEncryption" => (
+            "暗号化キーを読み書きできませんでした: {detail}",
+            "Could not read or store the encryption key: {detail}",
+        ),
+        "error.auditUnavailable" => (
+            "監査ログの署名キーが設定されていません",
+            "The audit log signing key is not set up",
+        ),
+        "error.notRecording" => (
+            "記録中のセッションはありません",
+            "No session is being recorded",
+        ),
+        "error.dataDirNotEmpty" => (
+            "移動先のフォルダーが空ではありません: {path}",
+            "The destination folder is not empty: {path}",
+        ),
+        "error.dataDirInvalid" => (
+            "このフォルダーにはデータを移動できません: {path}",
+            "The data cannot be moved to this folder: {path}",
+        ),
+        "error.dataDirMove" => (
+            "データフォルダーを移動できませんでした: {detail}",
+            "Could not move the data folder: {detail}",
+        ),
+        "error.readOnly" => (
+            "データは別の画面（{holder}）で編集中のため、変更できません",
+            "The data is being edited in another window ({holder}) and cannot be changed",
+        ),
+        "error.recording" => (
+            "セッションの記録を保存できませんでした: {detail}",
+            "Could not save the session recording: {detail}",
+        ),
+        "error.export" => (
+            "エクスポートに失敗しました: {detail}",
+            "Export failed: {detail}",
+        ),
+        "error.importJson" => (
+            "JSONファイルを読み込めません: {detail}",
+            "Could not read the JSON file: {detail}",
+        ),
+        "error.importCsv" => (
+            "CSVファイルを読み込めません: {detail}",
+            "Could not read the CSV file: {detail}",
+        ),
+        "error.importUnsupported" => (
+            "{file} は取り込めない形式です（JSON または CSV のみ）",
+            "{file} cannot be imported (only JSON or CSV)",
+        ),
+        "error.importNotLegacy" => (
+            "ブラウザ版から書き出したデータとして読み込めません",
+            "This is not data exported from the browser version",
+        ),
+        "error.importFolder" => ("フォルダは取り込めません", "Folders cannot be imported"),
+        "error.importTooLarge" => (
+            "ファイルが大きすぎます（{limit}MBまで）",
+            "The file is too large (up to {limit} MB)",
+        ),
+        "error.sheetInvalidUrl" => (
+            "URLが正しくありません（http または https のURLを指定してください）",
+            "The URL is not valid (use an http or https URL)",
+        ),
+        "error.sheetFetch" => (
+            "スプレッドシートを取得できませんでした: {detail}",
+            "Could not download the spreadsheet: {detail}",
+        ),
+        "error.sheetNotShared" => (
+            "CSVを取得できませんでした。シートを「リンクを知っている全員」に共有するか、ウェブに公開してください",
+            "The URL did not return CSV. Share the sheet with anyone with the link or publish it to the web",
+        ),
+        "error.unknownColumnMapping" => (
+            "列の対応「{name}」が見つかりません",
+            "Column mapping \"{name}\" was not found",
+        ),
+        "error.dataTooNew" => (
+            "データ形式 v{found} はこのバージョンでは扱えません（v{supported} まで）。アプリを更新してください",
+            "Data format v{found} is newer than this version supports (v{supported}). Please update the app",
+        ),
+        "error.advisorIncompatible" => (
+            "アドバイザーはデータ形式 v{advisor} までしか扱えません（必要: v{required}）",
+            "The advisor supports data format v{advisor}, but v{required} is needed",
+        ),
+        "error.invalidSetting" => (
+            "設定 {field} が不正です: {reason}",
+            "Invalid setting {field}: {reason}",
+        ),
+        "error.supportBundle" => (
+            "サポート用ファイルを作成できませんでした: {detail}",
+            "Could not create the support bundle: {detail}",
+        ),
+        "error.transfer" => (
+            "端末間の移行に失敗しました: {detail}",
+            "Device transfer failed: {detail}",
+        ),
+        "error.transferInvalidCode" => (
+            "移行用のコードが正しくありません",
+            "The transfer code is not valid",
+        ),
+        "error.transferCorrupt" => (
+            "移行データが壊れているか、コードが一致しません",
+            "The transferred data is damaged or does not match the code",
+        ),
+        "error.osReminders" => (
+            "OSのリマインダーを設定できませんでした: {detail}",
+            "Could not schedule system reminders: {detail}",
+        ),
+        "error.osRemindersUnsupported" => (
+            "このOSではリマインダーを予約できません",
+            "System reminders are not supported on this OS",
+        ),
+        "error.invalidRate" => (
+            "{currency} の為替レートが正しくありません",
+            "Invalid exchange rate for {currency}",
+        ),
+        "error.exchangeRates" => (
+            "為替レートを更新できませんでした: {detail}",
+            "Could not update exchange rates: {detail}",
+        ),
+        "error.unknownHistoryEntry" => (
+            "推奨履歴が見つかりません: {id}",
+            "Recommendation history entry not found: {id}",
+        ),
+        "error.noRecommendationInputs" => (
+            "この推奨の入力データは保存されていません: {id}",
+            "The advisor inputs of this recommendation were not kept: {id}",
+        ),
+        "error.simulationUnknownSchool" => (
+            "シナリオ「{scenario}」の学校が見つかりません: {id}",
+            "Scenario '{scenario}' refers to an unknown school: {id}",
+        ),
+        "error.tooManyScenarios" => (
+            "比較できるシナリオは{max}件までです",
+            "At most {max} scenarios can be compared",
+        ),
+        "error.unknownTemplate" => (
+            "テンプレートが見つかりません: {id}",
+            "Template not found: {id}",
+        ),
+        "error.invalidExamDate" => (
+            "試験日 {day} が不正です",
+            "Invalid exam date {day}",
+        ),
+        "error.invalidTemplateCatalog" => (
+            "テンプレートファイルを読み込めませんでした: {detail}",
+            "Could not read the template file: {detail}",
+        ),
+        "error.invalidHolidayCalendar" => (
+            "祝日ファイルを読み込めませんでした: {detail}",
+            "Could not read the holiday file: {detail}",
+        ),
+        "error.invalidInstallmentPlan" => (
+            "分割払いの設定が正しくありません（回数は1〜{max}回、初回期限は有効な日付）",
+            "Invalid installment plan (1 to {max} payments, with a valid first due date)",
+        ),
+        "error.invalidRecurringFee" => (
+            "定期納付金「{label}」の設定が正しくありません（名前、間隔、有効な日付が必要です）",
+            "Invalid recurring fee \"{label}\" (it needs a name, an interval and valid dates)",
+        ),
+        "error.dataConflict" => (
+            "データは別の画面または端末で更新されています（読み込んだ版 {expected}、現在の版 {current}）。最新のデータを読み込んでから保存し直してください",
+            "The data was changed in another window or device (loaded revision {expected}, now {current}). Reload it before saving again",
+        ),
+        "error.invalidPatch" => (
+            "データの部分更新が正しくありません: {detail}",
+            "Invalid data patch: {detail}",
+        ),
+        "error.emptyBackup" => (
+            "アップロードされたバックアップが空です",
+            "The uploaded backup is empty",
+        ),
+        "error.invalidShareDays" => (
+            "共有リンクの有効期間は1〜{max}日です（指定: {days}日）",
+            "A share link can last 1 to {max} days, not {days}",
+        ),
+        "error.invalidShareLink" => (
+            "共有リンクが正しくありません",
+            "The share link is not valid",
+        ),
+        "error.shareExpired" => (
+            "共有リンクの有効期限（{at}）が過ぎています",
+            "The share link expired at {at}",
+        ),
+        "error.sharingUnavailable" => (
+            "共有リンクの署名キーが設定されていません",
+            "Sharing is not set up",
+        ),
+        "error.onboardingOutOfOrder" => (
+            "先に初期設定の「{step}」を完了してください",
+            "Complete the {step} setup step first",
+        ),
+        "error.paymentUnknownSchool" => (
+            "支払いを記録する学校（ID: {id}）が見つかりません",
+            "School {id} to record the payment for was not found",
+        ),
+        "error.noteUnknownSchool" => (
+            "メモを付ける学校（ID: {id}）が見つかりません",
+            "School {id} to add the note to was not found",
+        ),
+        "error.unknownNote" => (
+            "メモが見つかりません。すでに削除された可能性があります",
+            "The note was not found; it may already have been deleted",
+        ),
+        "error.emptyNote" => (
+            "メモが空です",
+            "The note is empty",
+        ),
+        "error.noteTooLong" => (
+            "メモは{max}文字までです",
+            "Notes are limited to {max} characters",
+        ),
+        "error.invalidAsOf" => (
+            "日時が正しくありません: {at}",
+            "Invalid point in time: {at}",
+        ),
+        "error.noDataAsOf" => (
+            "{at} の時点では保存されたデータがありません",
+            "No data had been saved by {at}",
+        ),
+        "error.bulkTooMany" => (
+            "一度に行える操作は{max}件までです",
+            "At most {max} operations can be applied at once",
+        ),
+        "error.bulkFailed" => (
+            "{position}件目の操作に失敗したため、何も変更されていません: {detail}",
+            "Operation {position} failed, nothing was changed: {detail}",
+        ),
+        "error.pushUnavailable" => (
+            "プッシュ通知が設定されていません。config.toml の [web.push] に VAPID 鍵を設定してください",
+            "Web Push is not set up; add VAPID keys under [web.push] in config.toml",
+        ),
+        "error.invalidPushSubscription" => (
+            "プッシュ通知の登録内容が正しくありません: {detail}",
+            "Invalid push subscription: {detail}",
+        ),
+        "error.push" => (
+            "プッシュ通知を送れませんでした: {detail}",
+            "Could not send the push notification: {detail}",
+        ),
+        "error.outcomeUnknownSchool" => (
+            "結果を記録する学校（ID: {id}）が見つかりません",
+            "School {id} to record the result for was not found",
+        ),
+        "error.unknownInstallment" => (
+            "学校（ID: {id}）に第{installment}回の分割払いはありません",
+            "School {id} has no installment {installment}",
+        ),
+        "error.invalidPaymentDate" => (
+            "支払日が正しくありません: {day}",
+            "Invalid payment date: {day}",
+        ),
+        "error.noStorage" => (
+            "データの保存先が設定されていません",
+            "Data storage is not configured",
+        ),
+        "error.noData" => (
+            "保存されたデータがありません",
+            "No saved data found",
+        ),
+        "error.unknownImport" => (
+            "取り込みの有効期限が切れたか、すでに適用されています",
+            "The import has expired or was already applied",
+        ),
+        "error.unknownBackup" => (
+            "バックアップ {name} が見つかりません",
+            "Backup {name} was not found",
+        ),
+        "error.unknownExplanation" => (
+            "この推奨の説明が見つかりません。推奨をもう一度取得してください",
+            "No explanation was found for this recommendation; fetch the recommendation again",
+        ),
+        "error.unknownLogLevel" => (
+            "不明なログレベルです: {name}",
+            "Unknown log level: {name}",
+        ),
+        "error.invalidLogFilter" => (
+            "ログの出力設定が正しくありません: {detail}",
+            "Invalid log filter: {detail}",
+        ),
+        "error.logLevelUnavailable" => (
+            "実行中にログレベルを変更できません",
+            "The log level cannot be changed while running",
+        ),
+        "error.unexpected" => (
+            "予期しないエラーが発生しました: {detail}",
+            "An unexpected error occurred: {detail}",
+        ),
+        "import.invalidFormat" => ("形式が不正です: {detail}", "Invalid format: {detail}"),
+        "import.emptyName" => ("学校名が空です", "School name is empty"),
+        "import.invalidDate" => (
+            "{field} の日付 {day} が不正です",
+            "{field} has an invalid date {day}",
+        ),
+        "import.unknownPassStatus" => (
+            "合否状態 {status} は不明です",
+            "Unknown pass status {status}",
+        ),
+        "import.duplicateId" => (
+            "ファイル内で学校IDが重複しています",
+            "The school ID appears more than once in the file",
+        ),
+        "push.testTitle" => ("テスト通知", "Test notification"),
+        "push.testBody" => (
+            "支払い期限の通知はこのように届きます",
+            "Payment deadline notifications will arrive like this",
+        ),
+        "notification.deadlineApproaching" => {
+            ("支払い期限が近づいています", "A payment deadline is coming up")
+        }
+        "notification.recommendationChanged" => {
+            ("推奨アクションが変わりました", "The recommended action has changed")
+        }
+        "notification.budgetExceeded" => {
+            ("予算を超える支払いがあります", "Some payments exceed the budget")
+        }
+        "notification.backupFailing" => ("バックアップに失敗しています", "Backups are failing"),
+        _ => return None,
+    })
+}
+
+/// A catalog message with its arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub key: &'static str,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Message {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    /// Add a named argument
+    pub fn arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    /// Render in `locale`; unknown keys render as the key itself
+    pub fn render(&self, locale: Locale) -> String {
+        let Some((ja, en)) = templates(self.key) else {
+            return self.key.to_string();
+        };
+        let mut text = match locale {
+            Locale::Ja => ja,
+            Locale::En => en,
+        }
+        .to_string();
+        for (name, value) in &self.args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
+
+/// An error as returned to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocalizedError {
+    /// Catalog key identifying the kind of error
+    pub key: String,
+    /// Text for the user, in their locale
+    pub message: String,
+    /// Untranslated technical detail, for logs and bug reports
+    pub detail: String,
+    /// Structured detail for errors the frontend resolves itself, such as
+    /// a save conflict
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl LocalizedError {
+    pub fn new(message: &Message, locale: Locale, detail: impl ToString) -> Self {
+        Self {
+            key: message.key.to_string(),
+            message: message.render(locale),
+            detail: detail.to_string(),
+            data: None,
+        }
+    }
+
+    /// Attach structured detail for the frontend
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Wrap an error that has no catalog entry of its own
+    pub fn unexpected(detail: impl ToString, locale: Locale) -> Self {
+        let detail = detail.to_string();
+        Self::new(
+            &Message::new("error.unexpected").arg("detail", &detail),
+            locale,
+            detail,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("en-US"), Locale::En);
+        assert_eq!(Locale::from_tag("ja"), Locale::Ja);
+        assert_eq!(Locale::from_tag(""), Locale::Ja);
+    }
+
+    #[test]
+    fn test_render() {
+        let message = Message::new("error.unknownBackup").arg("name", "exit-1.json");
+        assert_eq!(
+            message.render(Locale::Ja),
+            "バックアップ exit-1.json が見つかりません"
+        );
+        assert_eq!(
+            message.render(Locale::En),
+            "Backup exit-1.json was not found"
+        );
+        assert_eq!(
+            Message::new("no.such.key").render(Locale::En),
+            "no.such.key"
+        );
+    }
+
+    #[test]
+    fn test_unexpected() {
+        let error = LocalizedError::unexpected("disk full", Locale::En);
+        assert_eq!(error.key, "error.unexpected");
+        assert_eq!(error.message, "An unexpected error occurred: disk full");
+        assert_eq!(error.detail, "disk full");
+    }
+}
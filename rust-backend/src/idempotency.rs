@@ -0,0 +1,215 @@
+//! Idempotency keys for retried requests.
+//!
+//! A client that may retry a mutating request, such as recording a payment
+//! over a flaky mobile connection, sends the same `Idempotency-Key` header
+//! with each attempt. The first attempt runs and its response is kept for
+//! [`IDEMPOTENCY_TTL`]; later attempts with the key get that response back
+//! instead of running again. A key reused for a different request is
+//! refused, and so is a retry arriving while the first attempt still runs.
+//! Server errors are not kept, so a retry after one runs again.
+//!
+//! Responses are kept in memory, so a restart forgets them.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+/// Header carrying the client's key
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+
+/// Header marking a response as the one kept from an earlier attempt
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long responses are kept
+pub const IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest key accepted
+pub const MAX_KEY_LEN: usize = 255;
+
+/// Keys kept at most; the oldest are forgotten first
+const MAX_ENTRIES: usize = 1000;
+
+/// A response kept for replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// What to do with a request carrying a key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Claim {
+    /// First attempt: run it, then [`IdempotencyStore::complete`] or
+    /// [`IdempotencyStore::release`] the key
+    New,
+    /// Already done: send this response again
+    Replay(StoredResponse),
+    /// An earlier attempt has not finished yet
+    InProgress,
+    /// The key was used for a different request
+    Mismatch,
+}
+
+#[derive(Debug)]
+struct Entry {
+    fingerprint: String,
+    claimed_at: Instant,
+    response: Option<StoredResponse>,
+}
+
+/// Requests seen by key
+#[derive(Debug, Default)]
+pub struct IdempotencyStore {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+/// Identify a request by its method, path with query and body
+pub fn fingerprint(method: &str, uri: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(uri.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Whether `key` can be used: printable ASCII up to [`MAX_KEY_LEN`]
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+impl IdempotencyStore {
+    /// Claim `key` for the request with `fingerprint`
+    pub fn claim(&self, key: &str, fingerprint: &str, now: Instant) -> Claim {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| now.duration_since(entry.claimed_at) < IDEMPOTENCY_TTL);
+
+        match entries.get(key) {
+            Some(entry) if entry.fingerprint != fingerprint => return Claim::Mismatch,
+            Some(Entry {
+                response: Some(response),
+                ..
+            }) => return Claim::Replay(response.clone()),
+            Some(_) => return Claim::InProgress,
+            None => {}
+        }
+        if entries.len() >= MAX_ENTRIES {
+            // Finished requests go first; attempts still running only when
+            // every entry is one, so abandoned claims cannot fill the store
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| (entry.response.is_none(), entry.claimed_at))
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key.to_string(),
+            Entry {
+                fingerprint: fingerprint.to_string(),
+                claimed_at: now,
+                response: None,
+            },
+        );
+        Claim::New
+    }
+
+    /// Keep the response to the attempt that claimed `key`
+    pub fn complete(&self, key: &str, response: StoredResponse) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(key) {
+            entry.response = Some(response);
+        }
+    }
+
+    /// Forget `key` so a retry runs again, after a server error
+    pub fn release(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn created() -> StoredResponse {
+        StoredResponse {
+            status: 200,
+            content_type: Some("application/json".to_string()),
+            body: br#"{"paid":true}"#.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_retries_replay_the_first_response() {
+        let store = IdempotencyStore::default();
+        let now = Instant::now();
+        let paid = fingerprint("POST", "/payments/paid", br#"{"schoolId":1}"#);
+
+        assert_eq!(store.claim("k1", &paid, now), Claim::New);
+        assert_eq!(store.claim("k1", &paid, now), Claim::InProgress);
+        store.complete("k1", created());
+        assert_eq!(store.claim("k1", &paid, now), Claim::Replay(created()));
+
+        // The same key for another body is refused
+        let other = fingerprint("POST", "/payments/paid", br#"{"schoolId":2}"#);
+        assert_eq!(store.claim("k1", &other, now), Claim::Mismatch);
+
+        // Released after a server error, a retry runs again
+        assert_eq!(store.claim("k2", &paid, now), Claim::New);
+        store.release("k2");
+        assert_eq!(store.claim("k2", &paid, now), Claim::New);
+
+        // Forgotten after the window
+        let later = now + IDEMPOTENCY_TTL;
+        assert_eq!(store.claim("k1", &paid, later), Claim::New);
+    }
+
+    #[test]
+    fn test_oldest_responses_are_forgotten_first() {
+        let store = IdempotencyStore::default();
+        let start = Instant::now();
+        for i in 0..MAX_ENTRIES {
+            let key = format!("k{}", i);
+            let now = start + Duration::from_millis(i as u64);
+            assert_eq!(store.claim(&key, "f", now), Claim::New);
+            store.complete(&key, created());
+        }
+        let now = start + Duration::from_secs(1);
+        assert_eq!(store.claim("new", "f", now), Claim::New);
+        assert_eq!(store.claim("k1", "f", now), Claim::Replay(created()));
+        // k0 made room for the new key, and now k1 for k0
+        assert_eq!(store.claim("k0", "f", now), Claim::New);
+        assert_eq!(store.claim("k2", "f", now), Claim::Replay(created()));
+        assert_eq!(store.claim("k1", "f", now), Claim::New);
+    }
+
+    #[test]
+    fn test_running_attempts_do_not_fill_the_store() {
+        let store = IdempotencyStore::default();
+        let start = Instant::now();
+        for i in 0..MAX_ENTRIES {
+            let now = start + Duration::from_millis(i as u64);
+            assert_eq!(store.claim(&format!("k{}", i), "f", now), Claim::New);
+        }
+        let now = start + Duration::from_secs(1);
+        assert_eq!(store.claim("new", "f", now), Claim::New);
+        assert_eq!(store.entries.lock().unwrap().len(), MAX_ENTRIES);
+        assert_eq!(store.claim("k0", "f", now), Claim::New);
+        assert_eq!(store.claim("k2", "f", now), Claim::InProgress);
+    }
+
+    #[test]
+    fn test_valid_keys() {
+        assert!(is_valid_key("3f2b8c1e-7a4d-4c1b-9e0f-2d6a5b7c8e91"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("has space"));
+        assert!(!is_valid_key(&"k".repeat(MAX_KEY_LEN + 1)));
+    }
+}
@@ -0,0 +1,840 @@
+//! Import of school data from JSON or CSV files.
+//!
+//! Importing is a two-step process: [`parse_file`], [`find_conflicts`] and
+//! [`find_duplicates`] build a preview for the user to confirm, and
+//! [`merge`] applies the confirmed schools to the stored dataset. Rows that
+//! fail validation are reported as issues and left out of the import.
+//!
+//! A school exported from one device and typed in again on another gets a
+//! new id, so matching by id alone imported it twice. Imported schools whose
+//! name is close to an existing school's, with a date in common, are
+//! reported as probable duplicates with a suggested [`MergeAction`], which
+//! [`resolve_duplicates`] applies unless the user chooses another.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::date::{parse_date_text, parse_day};
+use crate::diff::{field_changes, FieldChange};
+use crate::i18n::{Locale, Message};
+use crate::model::{PassStatus, School};
+
+/// CSV columns holding integers
+const NUMBER_COLUMNS: [&str; 9] = [
+    "id",
+    "priority",
+    "examDate",
+    "resultDate",
+    "enrollmentFeeDeadline",
+    "tuitionDeadline",
+    "enrollmentFee",
+    "tuition",
+    "childId",
+];
+
+/// Columns holding YYYYMMDD days, which may also be written as text
+const DATE_COLUMNS: [&str; 4] = [
+    "examDate",
+    "resultDate",
+    "enrollmentFeeDeadline",
+    "tuitionDeadline",
+];
+
+/// CSV columns holding JSON arrays
+const LIST_COLUMNS: [&str; 2] = ["installments", "adjustments"];
+
+/// CSV columns left empty when a school has no value
+const OPTIONAL_COLUMNS: [&str; 3] = ["childId", "installments", "adjustments"];
+
+/// CSV columns holding booleans
+const BOOL_COLUMNS: [&str; 2] = ["enrollmentFeePaid", "tuitionPaid"];
+
+/// Errors that make a whole file unreadable
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid CSV: {0}")]
+    Csv(String),
+
+    #[error("Unsupported import file: {0}")]
+    UnsupportedFile(String),
+
+    #[error("Not an export of the browser version")]
+    NotLegacyExport,
+}
+
+/// A problem with one imported school
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportIssue {
+    /// 1-based position of the school in the file
+    pub row: usize,
+    pub school_id: Option<u64>,
+    /// Catalog key identifying the problem
+    pub key: &'static str,
+    pub message: String,
+    #[serde(skip)]
+    source: Message,
+}
+
+impl ImportIssue {
+    fn new(row: usize, school_id: Option<u64>, source: Message) -> Self {
+        Self {
+            row,
+            school_id,
+            key: source.key,
+            message: source.render(Locale::default()),
+            source,
+        }
+    }
+
+    /// Render `message` in `locale`
+    pub fn localize(&mut self, locale: Locale) {
+        self.message = self.source.render(locale);
+    }
+}
+
+/// An imported school whose id already exists with different content
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportConflict {
+    pub school_id: u64,
+    pub existing_name: String,
+    pub imported_name: String,
+    pub changed_fields: Vec<String>,
+}
+
+/// How to apply an imported school that duplicates an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeAction {
+    /// Keep the existing school and leave the imported one out
+    Keep,
+    /// Replace the existing school with the imported one
+    Replace,
+    /// Keep the existing school with the imported fields written over it
+    MergeFields,
+}
+
+/// An imported school that is probably an existing school under another id
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportDuplicate {
+    /// Position of the imported school in the preview's `schools`
+    pub index: usize,
+    pub existing_id: u64,
+    pub existing_name: String,
+    pub imported_name: String,
+    /// How alike the names are, from 0 to 1
+    pub similarity: f64,
+    /// Fields of the existing school that merging would change
+    pub changed_fields: Vec<FieldChange>,
+    /// Action taken unless the user chooses another
+    pub suggestion: MergeAction,
+}
+
+/// Result of parsing an import file
+#[derive(Debug, Clone, Default)]
+pub struct ParsedImport {
+    pub schools: Vec<serde_json::Value>,
+    pub issues: Vec<ImportIssue>,
+}
+
+/// What an import would do, shown to the user before confirming
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    /// Pass to `confirm_import` to apply
+    pub token: String,
+    pub schools: Vec<serde_json::Value>,
+    pub issues: Vec<ImportIssue>,
+    pub conflicts: Vec<ImportConflict>,
+    pub duplicates: Vec<ImportDuplicate>,
+    /// Saved column mapping the columns were read through
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<String>,
+    /// Column titles of a CSV or sheet, to build a column mapping from
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub header: Vec<String>,
+}
+
+/// A previewed import awaiting confirmation
+#[derive(Debug, Clone, Default)]
+pub struct PendingImport {
+    pub schools: Vec<serde_json::Value>,
+    pub duplicates: Vec<ImportDuplicate>,
+}
+
+/// Parse an import file, choosing the format from its extension
+pub fn parse_file(file_name: &str, bytes: &[u8]) -> Result<ParsedImport, ImportError> {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_start_matches('\u{feff}');
+
+    let rows = match extension.as_str() {
+        "json" => json_rows(serde_json::from_str(text)?),
+        "csv" => csv_rows(text)?,
+        _ => return Err(ImportError::UnsupportedFile(file_name.to_string())),
+    };
+    Ok(validate(rows))
+}
+
+/// Parse CSV text whose header row is renamed by `columns`, from the
+/// sheet's column titles to school fields. Columns not in `columns` are
+/// left out.
+pub fn parse_csv_mapped(
+    text: &str,
+    columns: &BTreeMap<String, String>,
+) -> Result<ParsedImport, ImportError> {
+    let mut records = parse_csv(text.trim_start_matches('\u{feff}'))?;
+    if let Some(header) = records.first_mut() {
+        for column in header.iter_mut() {
+            *column = columns.get(column.trim()).cloned().unwrap_or_default();
+        }
+    }
+    Ok(validate(records_to_rows(records)?))
+}
+
+/// Accept either a stored dataset (`{"schools": [...]}`) or a bare array
+fn json_rows(data: serde_json::Value) -> Vec<serde_json::Value> {
+    match data {
+        serde_json::Value::Array(rows) => rows,
+        serde_json::Value::Object(mut map) => match map.remove("schools") {
+            Some(serde_json::Value::Array(rows)) => rows,
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Column titles in the header row of CSV text
+pub fn csv_header(text: &str) -> Result<Vec<String>, ImportError> {
+    let records = parse_csv(text.trim_start_matches('\u{feff}'))?;
+    let header = records
+        .into_iter()
+        .next()
+        .ok_or_else(|| ImportError::Csv("missing header row".to_string()))?;
+    Ok(header.into_iter().map(|c| c.trim().to_string()).collect())
+}
+
+/// Split CSV text into records, handling quoted fields
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, ImportError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ImportError::Csv("unterminated quoted field".to_string()));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| !(r.len() == 1 && r[0].trim().is_empty()));
+    Ok(records)
+}
+
+/// Turn CSV text into JSON objects keyed by the header row
+fn csv_rows(text: &str) -> Result<Vec<serde_json::Value>, ImportError> {
+    records_to_rows(parse_csv(text)?)
+}
+
+/// Turn CSV records into JSON objects keyed by the header row, skipping
+/// columns with an empty header
+fn records_to_rows(records: Vec<Vec<String>>) -> Result<Vec<serde_json::Value>, ImportError> {
+    let mut records = records.into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| ImportError::Csv("missing header row".to_string()))?;
+
+    Ok(records
+        .map(|record| {
+            let object = header
+                .iter()
+                .zip(record)
+                .filter(|(column, _)| !column.trim().is_empty())
+                .filter(|(column, value)| {
+                    !(OPTIONAL_COLUMNS.contains(&column.trim()) && value.trim().is_empty())
+                })
+                .map(|(column, value)| {
+                    let column = column.trim();
+                    let value = value.trim();
+                    let json = if NUMBER_COLUMNS.contains(&column) {
+                        value
+                            .parse::<u64>()
+                            .map(serde_json::Value::from)
+                            .unwrap_or_else(|_| value.into())
+                    } else if BOOL_COLUMNS.contains(&column) {
+                        match value.to_ascii_lowercase().as_str() {
+                            "true" | "1" => true.into(),
+                            "false" | "0" | "" => false.into(),
+                            _ => value.into(),
+                        }
+                    } else if LIST_COLUMNS.contains(&column) {
+                        serde_json::from_str(value).unwrap_or_else(|_| value.into())
+                    } else {
+                        value.into()
+                    };
+                    (column.to_string(), json)
+                })
+                .collect::<serde_json::Map<_, _>>();
+            serde_json::Value::Object(object)
+        })
+        .collect())
+}
+
+/// Convert dates written as text (`2026-03-06`, `令和8年3月6日`, ...) to days
+fn normalize_dates(school: &mut serde_json::Value) {
+    for column in DATE_COLUMNS {
+        let Some(value) = school.get_mut(column) else {
+            continue;
+        };
+        if let Some(day) = value.as_str().and_then(parse_date_text) {
+            *value = day.into();
+        }
+    }
+}
+
+/// Check a school, returning a message describing the first problem found
+fn check_school(school: &serde_json::Value) -> Result<(), Message> {
+    for column in DATE_COLUMNS {
+        if let Some(text) = school.get(column).and_then(|v| v.as_str()) {
+            return Err(Message::new("import.invalidDate")
+                .arg("field", column)
+                .arg("day", text));
+        }
+    }
+    if let Some(status) = school.get("passStatus").and_then(|v| v.as_str()) {
+        let known = status.is_empty() || PassStatus::ALL.iter().any(|s| s.as_str() == status);
+        if !known {
+            return Err(Message::new("import.unknownPassStatus").arg("status", status));
+        }
+    }
+    let entry: School = serde_json::from_value(school.clone())
+        .map_err(|e| Message::new("import.invalidFormat").arg("detail", e))?;
+
+    if entry.name.trim().is_empty() {
+        return Err(Message::new("import.emptyName"));
+    }
+    let dates = [
+        ("examDate", entry.exam_date),
+        ("resultDate", entry.result_date),
+        ("enrollmentFeeDeadline", entry.enrollment_fee_deadline),
+        ("tuitionDeadline", entry.tuition_deadline),
+    ];
+    for (field, day) in dates {
+        if parse_day(day).is_none() {
+            return Err(Message::new("import.invalidDate")
+                .arg("field", field)
+                .arg("day", day));
+        }
+    }
+    Ok(())
+}
+
+/// Validate rows, keeping valid ones and reporting the rest
+pub(crate) fn validate(rows: Vec<serde_json::Value>) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+    for (index, mut school) in rows.into_iter().enumerate() {
+        normalize_dates(&mut school);
+        let school_id = school.get("id").and_then(|id| id.as_u64());
+        let duplicate = school_id.is_some()
+            && parsed
+                .schools
+                .iter()
+                .any(|s| s.get("id").and_then(|id| id.as_u64()) == school_id);
+
+        let result = if duplicate {
+            Err(Message::new("import.duplicateId"))
+        } else {
+            check_school(&school)
+        };
+        match result {
+            Ok(()) => parsed.schools.push(school),
+            Err(message) => parsed
+                .issues
+                .push(ImportIssue::new(index + 1, school_id, message)),
+        }
+    }
+    parsed
+}
+
+fn existing_schools(existing: Option<&serde_json::Value>) -> &[serde_json::Value] {
+    existing
+        .and_then(|data| data.get("schools"))
+        .and_then(|s| s.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn name_of(school: &serde_json::Value) -> String {
+    school
+        .get("name")
+        .and_then(|n| n.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Find imported schools that would overwrite different existing data
+pub fn find_conflicts(
+    existing: Option<&serde_json::Value>,
+    imported: &[serde_json::Value],
+) -> Vec<ImportConflict> {
+    let existing = existing_schools(existing);
+    imported
+        .iter()
+        .filter_map(|school| {
+            let id = school.get("id")?.as_u64()?;
+            let current = existing
+                .iter()
+                .find(|s| s.get("id").and_then(|i| i.as_u64()) == Some(id))?;
+            let (current_map, imported_map) = (current.as_object()?, school.as_object()?);
+
+            let mut changed_fields: Vec<String> = imported_map
+                .iter()
+                .filter(|(key, value)| current_map.get(*key) != Some(value))
+                .map(|(key, _)| key.clone())
+                .collect();
+            changed_fields.sort();
+            if changed_fields.is_empty() {
+                return None;
+            }
+            Some(ImportConflict {
+                school_id: id,
+                existing_name: name_of(current),
+                imported_name: name_of(school),
+                changed_fields,
+            })
+        })
+        .collect()
+}
+
+/// Least name similarity, from 0 to 1, of a probable duplicate
+pub const DUPLICATE_SIMILARITY: f64 = 0.8;
+
+fn id_of(school: &serde_json::Value) -> Option<u64> {
+    school.get("id")?.as_u64()
+}
+
+/// `name` without case, spacing, punctuation or full-width letters
+pub(crate) fn normalize_name(name: &str) -> Vec<char> {
+    name.chars()
+        .map(|c| match c {
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            _ => c,
+        })
+        .filter(|c| {
+            !c.is_whitespace() && !c.is_ascii_punctuation() && !matches!(c, '・' | '、' | '。')
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Characters to insert, delete or replace to turn `a` into `b`
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replaced = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// How alike two school names are, from 0 for nothing in common to 1 for
+/// the same name written differently
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_name(a), normalize_name(b));
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
+
+/// Whether two schools share an exam, result or deadline day. Schools
+/// without any day are not told apart by their dates.
+fn dates_overlap(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    let days = |school: &serde_json::Value| -> Vec<u64> {
+        DATE_COLUMNS
+            .iter()
+            .filter_map(|column| school.get(*column)?.as_u64())
+            .filter(|day| *day != 0)
+            .collect()
+    };
+    let (a, b) = (days(a), days(b));
+    a.is_empty() || b.is_empty() || a.iter().any(|day| b.contains(day))
+}
+
+/// `existing` with every field of `imported` but its id written over it
+fn merge_fields(
+    existing: &serde_json::Map<String, serde_json::Value>,
+    imported: &serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut merged = existing.clone();
+    for (field, value) in imported.iter().filter(|(field, _)| *field != "id") {
+        merged.insert(field.clone(), value.clone());
+    }
+    merged
+}
+
+/// Keep when the imported school adds nothing, replace when it has every
+/// field of the existing one, and merge the fields otherwise
+fn suggest(
+    existing: &serde_json::Map<String, serde_json::Value>,
+    imported: &serde_json::Map<String, serde_json::Value>,
+) -> MergeAction {
+    if merge_fields(existing, imported) == *existing {
+        MergeAction::Keep
+    } else if existing
+        .keys()
+        .all(|field| field == "id" || imported.contains_key(field))
+    {
+        MergeAction::Replace
+    } else {
+        MergeAction::MergeFields
+    }
+}
+
+/// Find imported schools that are probably existing schools under another
+/// id: their names are at least [`DUPLICATE_SIMILARITY`] alike and their
+/// dates overlap. Each is matched with the most alike existing school.
+pub fn find_duplicates(
+    existing: Option<&serde_json::Value>,
+    imported: &[serde_json::Value],
+) -> Vec<ImportDuplicate> {
+    let existing = existing_schools(existing);
+    imported
+        .iter()
+        .enumerate()
+        .filter_map(|(index, school)| {
+            // Schools with an existing id are conflicts, not duplicates
+            if id_of(school).is_some_and(|id| existing.iter().any(|s| id_of(s) == Some(id))) {
+                return None;
+            }
+            let imported_name = name_of(school);
+            let (current, similarity) = existing
+                .iter()
+                .filter(|s| dates_overlap(s, school))
+                .map(|s| (s, name_similarity(&name_of(s), &imported_name)))
+                .filter(|(_, similarity)| *similarity >= DUPLICATE_SIMILARITY)
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            let (current_map, imported_map) = (current.as_object()?, school.as_object()?);
+            Some(ImportDuplicate {
+                index,
+                existing_id: id_of(current)?,
+                existing_name: name_of(current),
+                imported_name,
+                similarity,
+                changed_fields: field_changes(
+                    current_map,
+                    &merge_fields(current_map, imported_map),
+                ),
+                suggestion: suggest(current_map, imported_map),
+            })
+        })
+        .collect()
+}
+
+/// Apply the action chosen in `choices`, by position, or else the suggested
+/// one to each imported school in `duplicates`, ready for [`merge`].
+///
+/// Kept schools are left out; replaced and merged ones take the id of the
+/// existing school, so [`merge`] puts them in its place.
+pub fn resolve_duplicates(
+    existing: Option<&serde_json::Value>,
+    imported: Vec<serde_json::Value>,
+    duplicates: &[ImportDuplicate],
+    choices: &BTreeMap<usize, MergeAction>,
+) -> Vec<serde_json::Value> {
+    let existing = existing_schools(existing);
+    imported
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, mut school)| {
+            let Some(duplicate) = duplicates.iter().find(|d| d.index == index) else {
+                return Some(school);
+            };
+            let action = choices.get(&index).copied().unwrap_or(duplicate.suggestion);
+            let current = existing
+                .iter()
+                .find(|s| id_of(s) == Some(duplicate.existing_id))
+                .and_then(serde_json::Value::as_object);
+            if action == MergeAction::Keep {
+                return None;
+            }
+            if let (MergeAction::MergeFields, Some(current), Some(imported)) =
+                (action, current, school.as_object())
+            {
+                return Some(serde_json::Value::Object(merge_fields(current, imported)));
+            }
+            if let Some(imported) = school.as_object_mut() {
+                imported.insert("id".to_string(), duplicate.existing_id.into());
+            }
+            Some(school)
+        })
+        .collect()
+}
+
+/// Apply imported schools to a dataset.
+///
+/// Schools with an existing id are replaced in place; new ones are appended.
+pub fn merge(
+    existing: Option<serde_json::Value>,
+    imported: Vec<serde_json::Value>,
+) -> serde_json::Value {
+    let mut data = match existing {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let mut schools = match data.remove("schools") {
+        Some(serde_json::Value::Array(schools)) => schools,
+        _ => Vec::new(),
+    };
+
+    for school in imported {
+        let id = school.get("id").and_then(|id| id.as_u64());
+        match schools
+            .iter_mut()
+            .find(|s| id.is_some() && s.get("id").and_then(|i| i.as_u64()) == id)
+        {
+            Some(slot) => *slot = school,
+            None => schools.push(school),
+        }
+    }
+
+    data.insert("schools".to_string(), serde_json::Value::Array(schools));
+    serde_json::Value::Object(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{self, sample_data, ExportFormat};
+
+    #[test]
+    fn test_parse_json_dataset() {
+        let bytes = serde_json::to_vec(&sample_data()).unwrap();
+        let parsed = parse_file("backup.json", &bytes).unwrap();
+        assert_eq!(parsed.schools.len(), 2);
+        assert!(parsed.issues.is_empty());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = export::export(&sample_data(), ExportFormat::Csv).unwrap();
+        let parsed = parse_file("schools.CSV", &csv).unwrap();
+        assert!(parsed.issues.is_empty());
+        assert_eq!(parsed.schools.len(), 2);
+        assert_eq!(parsed.schools[0]["name"], "東京大学");
+        assert_eq!(parsed.schools[0]["enrollmentFee"], 282000);
+        assert_eq!(parsed.schools[1]["enrollmentFeePaid"], true);
+        assert!(parsed.schools[0].get("installments").is_none());
+        assert!(parsed.schools[0].get("childId").is_none());
+
+        let mut data = sample_data();
+        data["schools"][0]["childId"] = 2.into();
+        data["schools"][0]["installments"] = serde_json::json!([
+            {"due": 20260320, "amount": 400000},
+            {"due": 20260920, "amount": 400000}
+        ]);
+        let csv = export::export(&data, ExportFormat::Csv).unwrap();
+        let parsed = parse_file("schools.csv", &csv).unwrap();
+        assert!(parsed.issues.is_empty());
+        assert_eq!(parsed.schools[1]["installments"][1]["due"], 20260920);
+        assert_eq!(parsed.schools[1]["childId"], 2);
+    }
+
+    #[test]
+    fn test_parse_csv_quotes() {
+        let records = parse_csv("a,b\r\n\"x, \"\"y\"\"\",2\r\n").unwrap();
+        assert_eq!(records[1], vec!["x, \"y\"", "2"]);
+        assert!(parse_csv("a\n\"open").is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_mapped() {
+        let columns: BTreeMap<String, String> = [
+            ("学校名", "name"),
+            ("受験日", "examDate"),
+            ("発表日", "resultDate"),
+            ("入学金期限", "enrollmentFeeDeadline"),
+            ("授業料期限", "tuitionDeadline"),
+            ("入学金", "enrollmentFee"),
+            ("授業料", "tuition"),
+            ("ID", "id"),
+        ]
+        .into_iter()
+        .map(|(title, field)| (title.to_string(), field.to_string()))
+        .collect();
+        let csv = "\u{feff}ID,学校名,受験日,発表日,入学金期限,授業料期限,入学金,授業料,備考\n\
+                   1,東京大学,2026/02/25,2026-03-10,20260317,令和8年3月31日,282000,535800,第一志望\n";
+
+        assert_eq!(csv_header(csv).unwrap()[..2], ["ID", "学校名"]);
+
+        let parsed = parse_csv_mapped(csv, &columns).unwrap();
+        assert!(parsed.issues.is_empty());
+        let school = &parsed.schools[0];
+        assert_eq!(school["name"], "東京大学");
+        assert_eq!(school["examDate"], 20260225);
+        assert!(school.get("備考").is_none());
+    }
+
+    #[test]
+    fn test_validation_issues() {
+        let mut data = sample_data();
+        data["schools"][1]["examDate"] = 20261340.into();
+        let duplicate = data["schools"][0].clone();
+        data["schools"].as_array_mut().unwrap().push(duplicate);
+        let parsed = parse_file("x.json", &serde_json::to_vec(&data).unwrap()).unwrap();
+
+        assert_eq!(parsed.schools.len(), 1);
+        assert_eq!(parsed.issues.len(), 2);
+        assert_eq!(parsed.issues[0].row, 2);
+        assert!(parsed.issues[0].message.contains("examDate"));
+        assert_eq!(parsed.issues[1].school_id, Some(2));
+        assert!(parsed.issues[1].message.contains("重複"));
+        assert_eq!(parsed.issues[1].key, "import.duplicateId");
+
+        let mut issue = parsed.issues[0].clone();
+        issue.localize(Locale::En);
+        assert_eq!(issue.message, "examDate has an invalid date 20261340");
+    }
+
+    #[test]
+    fn test_dates_written_as_text() {
+        let mut data = sample_data();
+        data["schools"][0]["examDate"] = "令和8年2月15日".into();
+        data["schools"][0]["tuitionDeadline"] = "2026-03-19T15:00:00.000Z".into();
+        data["schools"][1]["resultDate"] = "来週".into();
+        let parsed = parse_file("x.json", &serde_json::to_vec(&data).unwrap()).unwrap();
+
+        assert_eq!(parsed.schools.len(), 1);
+        assert_eq!(parsed.schools[0]["examDate"], 20260215);
+        // Midnight in Japan, sent as UTC
+        assert_eq!(parsed.schools[0]["tuitionDeadline"], 20260320);
+        assert_eq!(parsed.issues[0].key, "import.invalidDate");
+    }
+
+    #[test]
+    fn test_unsupported_file() {
+        assert!(matches!(
+            parse_file("data.xlsx", b""),
+            Err(ImportError::UnsupportedFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_conflicts_and_merge() {
+        let existing = sample_data();
+        let mut changed = existing["schools"][0].clone();
+        changed["tuition"] = 900000.into();
+        let unchanged = existing["schools"][1].clone();
+        let new = serde_json::json!({"id": 3, "name": "慶應義塾大学"});
+
+        let imported = vec![changed, unchanged, new];
+        let conflicts = find_conflicts(Some(&existing), &imported);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].school_id, 2);
+        assert_eq!(conflicts[0].changed_fields, vec!["tuition"]);
+
+        let merged = merge(Some(existing), imported);
+        let schools = merged["schools"].as_array().unwrap();
+        assert_eq!(schools.len(), 3);
+        assert_eq!(schools[0]["tuition"], 900000);
+        assert_eq!(schools[2]["id"], 3);
+    }
+
+    #[test]
+    fn test_name_similarity() {
+        assert_eq!(name_similarity("早稲田大学", "早稲田 大学"), 1.0);
+        assert_eq!(name_similarity("ＡＢＣ高校", "abc高校"), 1.0);
+        assert!(name_similarity("早稲田大学", "早稲田大") >= DUPLICATE_SIMILARITY);
+        assert!(name_similarity("早稲田大学", "東京大学") < DUPLICATE_SIMILARITY);
+        assert_eq!(name_similarity("", ""), 0.0);
+    }
+
+    #[test]
+    fn test_duplicates_are_suggested_and_resolved() {
+        let existing = sample_data();
+        let imported = vec![
+            // Same school under a new id, with a fee filled in differently
+            serde_json::json!({"id": 7, "name": "早稲田 大学", "examDate": 20260215, "tuition": 810000}),
+            // Same name but no date in common
+            serde_json::json!({"id": 8, "name": "東京大学", "examDate": 20270225}),
+            // Nothing new
+            serde_json::json!({"name": "東京大学", "examDate": 20260225, "tuition": 535800}),
+        ];
+        let duplicates = find_duplicates(Some(&existing), &imported);
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].index, 0);
+        assert_eq!(duplicates[0].existing_id, 2);
+        assert_eq!(duplicates[0].suggestion, MergeAction::MergeFields);
+        let fields: Vec<&str> = duplicates[0]
+            .changed_fields
+            .iter()
+            .map(|f| f.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["name", "tuition"]);
+        assert_eq!(duplicates[1].index, 2);
+        assert_eq!(duplicates[1].suggestion, MergeAction::Keep);
+        assert!(find_conflicts(Some(&existing), &imported).is_empty());
+
+        let suggested = resolve_duplicates(
+            Some(&existing),
+            imported.clone(),
+            &duplicates,
+            &BTreeMap::new(),
+        );
+        let merged = merge(Some(existing.clone()), suggested);
+        let schools = merged["schools"].as_array().unwrap();
+        assert_eq!(schools.len(), 3);
+        assert_eq!(schools[0]["tuition"], 810000);
+        assert_eq!(schools[0]["passStatus"], "passed");
+        assert_eq!(schools[2]["id"], 8);
+
+        let choices = BTreeMap::from([(0, MergeAction::Replace), (2, MergeAction::MergeFields)]);
+        let chosen = resolve_duplicates(Some(&existing), imported, &duplicates, &choices);
+        let merged = merge(Some(existing), chosen);
+        let schools = merged["schools"].as_array().unwrap();
+        assert_eq!(schools.len(), 3);
+        assert_eq!(schools[0]["id"], 2);
+        assert!(schools[0].get("passStatus").is_none());
+    }
+}
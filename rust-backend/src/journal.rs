@@ -0,0 +1,335 @@
+//! Journal of every saved version of the datasets, for looking back.
+//!
+//! Each save through [`Storage::save_dataset`] also writes the saved
+//! version to `journal/<dataset>/<YYYYMMDD-HHMMSS>-<revision>.json`, the
+//! time being when it was saved in Japan. [`as_of`] finds the version that
+//! was current at a past point in time, so the plan can be seen as it was
+//! before a school was added or an import applied.
+//!
+//! Data saved before the journal existed is still found: the main dataset's
+//! backups are snapshots too, and the saved file itself counts from the
+//! time it was last written.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::backup;
+use crate::date;
+use crate::field_crypto;
+use crate::revision;
+use crate::storage::{Storage, StorageError, SCHOOLS_DATA_FILE};
+
+/// Subdirectory of the data directory holding the journal
+pub const JOURNAL_DIR: &str = "journal";
+
+/// How many versions of each dataset are kept
+pub const MAX_JOURNAL_VERSIONS: usize = 500;
+
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Where a past version of a dataset was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum VersionSource {
+    Journal,
+    Backup,
+    /// The dataset as saved now, unchanged since then
+    Current,
+}
+
+/// A dataset as it was saved at some point
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PastVersion {
+    /// When this version was saved (RFC 3339)
+    pub saved_at: String,
+    #[ts(type = "number")]
+    pub revision: u64,
+    pub source: VersionSource,
+    #[ts(type = "unknown")]
+    pub data: serde_json::Value,
+}
+
+/// Journal directory of dataset file `filename`, such as `journal/data`
+fn journal_dir(storage: &Storage, filename: &str) -> PathBuf {
+    let stem = filename.strip_suffix(".json").unwrap_or(filename);
+    storage.data_dir().join(JOURNAL_DIR).join(stem)
+}
+
+/// Journal file names of `filename`, oldest first
+fn versions(storage: &Storage, filename: &str) -> Result<Vec<String>, StorageError> {
+    let dir = journal_dir(storage, filename);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| parse_version(name).is_some())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Journal files of every dataset, relative to the data directory, such
+/// as `journal/data/20260301-101500-00000003.json`
+pub fn journal_files(storage: &Storage) -> Result<Vec<String>, StorageError> {
+    let mut files = Vec::new();
+    let mut dirs = vec![JOURNAL_DIR.to_string()];
+    while let Some(dir) = dirs.pop() {
+        let path = storage.data_dir().join(&dir);
+        if !path.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let name = format!("{}/{}", dir, entry.file_name().to_string_lossy());
+            if entry.file_type()?.is_dir() {
+                dirs.push(name);
+            } else if is_journal_file(&name) {
+                files.push(name);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `file_name`, relative to the data directory, is a journal file
+pub fn is_journal_file(file_name: &str) -> bool {
+    file_name
+        .strip_prefix(JOURNAL_DIR)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .and_then(|rest| rest.rsplit_once('/'))
+        .is_some_and(|(dataset, name)| {
+            !dataset.is_empty()
+                && !dataset
+                    .split('/')
+                    .any(|part| part.is_empty() || part == "..")
+                && !file_name.contains('\\')
+                && parse_version(name).is_some()
+        })
+}
+
+/// Save time and revision of a journal file name
+fn parse_version(file_name: &str) -> Option<(DateTime<FixedOffset>, u64)> {
+    let stem = file_name.strip_suffix(".json")?;
+    let (time, revision) = stem.rsplit_once('-')?;
+    let time = NaiveDateTime::parse_from_str(time, TIME_FORMAT).ok()?;
+    let time = date::jst().from_local_datetime(&time).single()?;
+    Some((time, revision.parse().ok()?))
+}
+
+/// Add `data`, just saved as dataset file `filename`, to the journal as
+/// saved at `at`, dropping the oldest versions beyond
+/// [`MAX_JOURNAL_VERSIONS`]
+pub fn record(
+    storage: &Storage,
+    filename: &str,
+    data: &serde_json::Value,
+    at: DateTime<FixedOffset>,
+) -> Result<(), StorageError> {
+    let dir = journal_dir(storage, filename);
+    fs::create_dir_all(&dir)?;
+    let file_name = format!(
+        "{}-{:08}.json",
+        at.with_timezone(&date::jst()).format(TIME_FORMAT),
+        revision::of(data)
+    );
+    let mut data = data.clone();
+    field_crypto::encrypt(&mut data);
+    fs::write(dir.join(file_name), serde_json::to_string_pretty(&data)?)?;
+
+    let names = versions(storage, filename)?;
+    let excess = names.len().saturating_sub(MAX_JOURNAL_VERSIONS);
+    for old in &names[..excess] {
+        fs::remove_file(dir.join(old))?;
+    }
+    Ok(())
+}
+
+fn read(path: PathBuf) -> Result<serde_json::Value, StorageError> {
+    let mut data = serde_json::from_str(&fs::read_to_string(path)?)?;
+    field_crypto::decrypt(&mut data)?;
+    Ok(data)
+}
+
+/// Dataset file `filename` as it was at `at`: the latest version saved by
+/// then, or `None` if nothing had been saved yet
+pub fn as_of(
+    storage: &Storage,
+    filename: &str,
+    at: DateTime<FixedOffset>,
+) -> Result<Option<PastVersion>, StorageError> {
+    let mut candidates: Vec<(DateTime<FixedOffset>, VersionSource, PathBuf)> = Vec::new();
+
+    if let Some((time, name)) = versions(storage, filename)?
+        .into_iter()
+        .filter_map(|name| Some((parse_version(&name)?.0, name)))
+        .rfind(|(time, _)| *time <= at)
+    {
+        candidates.push((
+            time,
+            VersionSource::Journal,
+            journal_dir(storage, filename).join(name),
+        ));
+    }
+    if filename == SCHOOLS_DATA_FILE {
+        for info in backup::list_backups(storage)? {
+            let time = NaiveDateTime::parse_from_str(&info.created_at, TIME_FORMAT)
+                .ok()
+                .and_then(|time| Local.from_local_datetime(&time).single());
+            if let (Some(time), Some(path)) = (time, backup::backup_path(storage, &info.file_name))
+            {
+                if time <= at {
+                    candidates.push((time.fixed_offset(), VersionSource::Backup, path));
+                }
+            }
+        }
+    }
+    let current = storage.data_dir().join(filename);
+    if let Ok(modified) = fs::metadata(&current).and_then(|m| m.modified()) {
+        // To the second, like journal file names, so the journal entry of
+        // the same save wins the tie
+        let modified = DateTime::<chrono::Utc>::from(modified)
+            .fixed_offset()
+            .with_nanosecond(0)
+            .unwrap_or(at);
+        if modified <= at {
+            candidates.push((modified, VersionSource::Current, current));
+        }
+    }
+
+    // The latest wins; on a tie the journal, which records every save
+    let Some((time, source, path)) = candidates
+        .into_iter()
+        .min_by_key(|(time, source, _)| (std::cmp::Reverse(*time), *source as u8))
+    else {
+        return Ok(None);
+    };
+    let data = read(path)?;
+    Ok(Some(PastVersion {
+        saved_at: time.with_timezone(&date::jst()).to_rfc3339(),
+        revision: revision::of(&data),
+        source,
+        data,
+    }))
+}
+
+/// Parse a point in time given as RFC 3339, or as a date meaning the end
+/// of that day in Japan
+pub fn parse_time(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time);
+    }
+    let day = date::parse_day(date::parse_date_text(text)?)?;
+    let end = day.and_time(NaiveTime::from_hms_opt(23, 59, 59)?);
+    date::jst().from_local_datetime(&end).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    fn school(name: &str) -> serde_json::Value {
+        serde_json::json!({ "name": name })
+    }
+
+    #[test]
+    fn test_versions_as_of() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let save = |schools: Vec<serde_json::Value>, revision: u64, at: &str| {
+            let data = serde_json::json!({ "schools": schools, "revision": revision });
+            record(&storage, SCHOOLS_DATA_FILE, &data, time(at)).unwrap();
+        };
+        save(vec![school("A")], 1, "2026-02-01T10:00:00+09:00");
+        save(
+            vec![school("A"), school("B")],
+            2,
+            "2026-02-10T10:00:00+09:00",
+        );
+        save(
+            vec![school("A"), school("B"), school("C")],
+            3,
+            "2026-02-10T10:00:00+09:00",
+        );
+
+        assert!(as_of(
+            &storage,
+            SCHOOLS_DATA_FILE,
+            time("2026-01-31T00:00:00+09:00")
+        )
+        .unwrap()
+        .is_none());
+        let version = as_of(
+            &storage,
+            SCHOOLS_DATA_FILE,
+            time("2026-02-09T23:00:00+09:00"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(version.revision, 1);
+        assert_eq!(version.source, VersionSource::Journal);
+        assert_eq!(version.saved_at, "2026-02-01T10:00:00+09:00");
+
+        // Two saves in the same second: the later revision counts
+        let version = as_of(&storage, SCHOOLS_DATA_FILE, time("2026-02-10T01:00:00Z"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(version.revision, 3);
+        assert_eq!(version.data["schools"].as_array().unwrap().len(), 3);
+
+        // Each dataset has its own journal
+        assert!(as_of(
+            &storage,
+            "plans/next.json",
+            time("2026-03-01T00:00:00+09:00")
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn test_old_versions_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let start = time("2026-02-01T00:00:00+09:00");
+        for revision in 1..=MAX_JOURNAL_VERSIONS as u64 + 2 {
+            let data = serde_json::json!({ "revision": revision });
+            let at = start + chrono::Duration::minutes(revision as i64);
+            record(&storage, SCHOOLS_DATA_FILE, &data, at).unwrap();
+        }
+        let names = versions(&storage, SCHOOLS_DATA_FILE).unwrap();
+        assert_eq!(names.len(), MAX_JOURNAL_VERSIONS);
+        assert_eq!(parse_version(&names[0]).unwrap().1, 3);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("2026-02-10T10:00:00+09:00"),
+            Some(time("2026-02-10T10:00:00+09:00"))
+        );
+        assert_eq!(
+            parse_time("2026-02-10"),
+            Some(time("2026-02-10T23:59:59+09:00"))
+        );
+        assert_eq!(
+            parse_time("20260210"),
+            Some(time("2026-02-10T23:59:59+09:00"))
+        );
+        assert_eq!(parse_time("先週"), None);
+    }
+}
@@ -1,23 +1,72 @@
 //! JSON-RPC type definitions for communication with Lean REPL.
+//!
+//! `params` and `result` are kept as the JSON text they arrived as. Most
+//! of them are only forwarded between the frontend and the advisor, and
+//! datasets run to hundreds of kilobytes, so they are parsed only where
+//! the backend reads them, with [`JsonRpcRequest::params_value`],
+//! [`JsonRpcRequest::parse_params`] and [`JsonRpcResponse::result_value`].
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+/// Raw JSON of `value`
+pub fn to_raw(value: &serde_json::Value) -> Box<RawValue> {
+    serde_json::value::to_raw_value(value).expect("a Value always serializes")
+}
+
+/// Parse raw JSON already checked to be valid
+fn parse_raw(raw: &RawValue) -> serde_json::Value {
+    serde_json::from_str(raw.get()).expect("RawValue holds valid JSON")
+}
+
+fn null_params() -> Box<RawValue> {
+    RawValue::NULL.to_owned()
+}
 
 /// JSON-RPC 2.0 request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
     pub method: String,
-    #[serde(default)]
-    pub params: serde_json::Value,
+    #[serde(default = "null_params")]
+    pub params: Box<RawValue>,
     pub id: serde_json::Value,
 }
 
+impl JsonRpcRequest {
+    /// A JSON-RPC 2.0 request for `method`
+    pub fn new(
+        method: impl Into<String>,
+        params: &serde_json::Value,
+        id: serde_json::Value,
+    ) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.into(),
+            params: to_raw(params),
+            id,
+        }
+    }
+
+    /// The params, parsed
+    pub fn params_value(&self) -> serde_json::Value {
+        parse_raw(&self.params)
+    }
+
+    /// Read the params into `T`, skipping what it does not name without
+    /// building a tree of the rest
+    pub fn parse_params<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(self.params.get())
+    }
+}
+
 /// JSON-RPC 2.0 response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcResponse {
     pub jsonrpc: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub result: Option<serde_json::Value>,
+    pub result: Option<Box<RawValue>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<JsonRpcError>,
     pub id: serde_json::Value,
@@ -37,12 +86,27 @@ impl JsonRpcResponse {
     pub fn success(id: serde_json::Value, result: serde_json::Value) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
-            result: Some(result),
+            result: Some(to_raw(&result)),
             error: None,
             id,
         }
     }
 
+    /// The result, parsed
+    pub fn result_value(&self) -> Option<serde_json::Value> {
+        self.result.as_deref().map(parse_raw)
+    }
+
+    /// Read the result into `T`; a missing result reads as `null`
+    pub fn parse_result<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(self.result.as_deref().unwrap_or(RawValue::NULL).get())
+    }
+
+    /// Replace the result
+    pub fn set_result(&mut self, result: &serde_json::Value) {
+        self.result = Some(to_raw(result));
+    }
+
     /// Create an error response
     pub fn error(id: serde_json::Value, code: i32, message: String) -> Self {
         Self {
@@ -69,36 +133,54 @@ mod tests {
 
     #[test]
     fn test_request_serialization() {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            method: "ping".to_string(),
-            params: serde_json::json!({}),
-            id: serde_json::json!(1),
-        };
+        let request = JsonRpcRequest::new("ping", &serde_json::json!({}), serde_json::json!(1));
 
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains("\"jsonrpc\":\"2.0\""));
         assert!(json.contains("\"method\":\"ping\""));
     }
 
+    #[test]
+    fn test_params_are_forwarded_verbatim() {
+        let body = r#"{"jsonrpc":"2.0","method":"getRecommendation","params":{"today": 20260301,"schools":[ ]},"id":1}"#;
+        let request: JsonRpcRequest = serde_json::from_str(body).unwrap();
+        assert_eq!(request.params.get(), r#"{"today": 20260301,"schools":[ ]}"#);
+        assert!(serde_json::to_string(&request)
+            .unwrap()
+            .contains(request.params.get()));
+        assert_eq!(request.params_value()["today"], 20260301);
+
+        #[derive(Deserialize)]
+        struct Today {
+            today: u32,
+        }
+        assert_eq!(request.parse_params::<Today>().unwrap().today, 20260301);
+
+        // Tauri hands commands their arguments as a Value
+        let from_value: JsonRpcRequest = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "method": "getRecommendation", "params": { "today": 20260302 }, "id": 2
+        }))
+        .unwrap();
+        assert_eq!(from_value.parse_params::<Today>().unwrap().today, 20260302);
+
+        let without_params: JsonRpcRequest =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","method":"ping","id":3}"#).unwrap();
+        assert_eq!(without_params.params_value(), serde_json::Value::Null);
+    }
+
     #[test]
     fn test_response_success() {
-        let response = JsonRpcResponse::success(
-            serde_json::json!(1),
-            serde_json::json!({"status": "ok"}),
-        );
+        let response =
+            JsonRpcResponse::success(serde_json::json!(1), serde_json::json!({"status": "ok"}));
 
-        assert!(response.result.is_some());
+        assert_eq!(response.result_value().unwrap()["status"], "ok");
         assert!(response.error.is_none());
     }
 
     #[test]
     fn test_response_error() {
-        let response = JsonRpcResponse::error(
-            serde_json::json!(1),
-            -32603,
-            "Internal error".to_string(),
-        );
+        let response =
+            JsonRpcResponse::error(serde_json::json!(1), -32603, "Internal error".to_string());
 
         assert!(response.result.is_none());
         assert!(response.error.is_some());
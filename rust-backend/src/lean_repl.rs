@@ -3,18 +3,22 @@
 //! Handles spawning, communication, and lifecycle of the Lean advisor REPL process.
 
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
 use thiserror::Error;
+use tokio::sync::broadcast;
 
 use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::mock_advisor::MockAdvisor;
+use crate::profiling::{Phase, PhaseTimings};
+use crate::recording::ReplayAdvisor;
 
 /// Errors that can occur when interacting with the Lean REPL
 #[derive(Debug, Error)]
@@ -41,12 +45,26 @@ pub enum LeanReplError {
     Io(#[from] std::io::Error),
 }
 
+/// How long to wait for a response unless configured otherwise
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Stderr lines buffered for slow subscribers before the oldest are dropped
+const STDERR_BUFFER: usize = 256;
+
 /// Manages a Lean REPL process
 pub struct LeanRepl {
     process: Option<Child>,
     advisor_path: PathBuf,
     response_rx: Option<Receiver<String>>,
     stdin_tx: Option<Sender<String>>,
+    request_timeout: Duration,
+    /// Start the advisor with `--verbose`, tracing each request on stderr
+    verbose: bool,
+    stderr_tx: broadcast::Sender<String>,
+    /// Answer from a [`MockAdvisor`] instead of a process, in demo mode
+    mock: Option<MockAdvisor>,
+    /// Answer from a recorded session instead of a process
+    replay: Option<ReplayAdvisor>,
 }
 
 impl LeanRepl {
@@ -57,11 +75,79 @@ impl LeanRepl {
             advisor_path,
             response_rx: None,
             stdin_tx: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            verbose: false,
+            stderr_tx: broadcast::channel(STDERR_BUFFER).0,
+            mock: None,
+            replay: None,
+        }
+    }
+
+    /// Create a LeanRepl answered by the built-in [`MockAdvisor`], for demo
+    /// mode; no process is ever started
+    pub fn demo() -> Self {
+        let mut repl = Self::new(PathBuf::from("demo"));
+        repl.mock = Some(MockAdvisor);
+        repl
+    }
+
+    /// Create a LeanRepl answering from a recorded session; no process is
+    /// ever started
+    pub fn replay(advisor: ReplayAdvisor) -> Self {
+        let mut repl = Self::new(PathBuf::from("replay"));
+        repl.replay = Some(advisor);
+        repl
+    }
+
+    /// A new LeanRepl answering like this one, with its own process, or
+    /// `None` when answering from a recorded session, which has a single
+    /// order of answers
+    pub fn sibling(&self) -> Option<Self> {
+        if self.is_replay() {
+            return None;
         }
+        let mut repl = Self::new(self.advisor_path.clone());
+        repl.request_timeout = self.request_timeout;
+        repl.verbose = self.verbose;
+        repl.mock = self.mock;
+        Some(repl)
+    }
+
+    /// Whether requests are answered without a process, by the mock advisor
+    /// or from a recorded session
+    pub fn is_demo(&self) -> bool {
+        self.mock.is_some() || self.is_replay()
+    }
+
+    /// Whether requests are answered from a recorded session
+    pub fn is_replay(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Start the advisor with verbose tracing from the next (re)start on
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    pub fn is_verbose(&self) -> bool {
+        self.verbose
+    }
+
+    /// Receive each line the advisor writes to stderr
+    pub fn subscribe_stderr(&self) -> broadcast::Receiver<String> {
+        self.stderr_tx.subscribe()
+    }
+
+    /// Set how long to wait for a response to each request
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = timeout;
     }
 
     /// Check if the REPL process is running
     pub fn is_running(&mut self) -> bool {
+        if self.is_demo() {
+            return true;
+        }
         if let Some(ref mut process) = self.process {
             match process.try_wait() {
                 Ok(None) => true, // Still running
@@ -75,17 +161,42 @@ impl LeanRepl {
         }
     }
 
+    /// Check if a previously started REPL process has exited on its own
+    pub fn has_exited(&mut self) -> bool {
+        match self.process {
+            Some(ref mut process) => !matches!(process.try_wait(), Ok(None)),
+            None => false,
+        }
+    }
+
+    /// Path of the advisor binary
+    pub fn advisor_path(&self) -> &Path {
+        &self.advisor_path
+    }
+
     /// Start the Lean REPL process
     pub fn start(&mut self) -> Result<(), LeanReplError> {
         if self.is_running() {
             return Ok(());
         }
+        self.spawn()?;
+        self.wait_ready();
+        Ok(())
+    }
 
+    /// Spawn the REPL process and its I/O threads without waiting for it to initialize
+    pub fn spawn(&mut self) -> Result<(), LeanReplError> {
+        if self.is_demo() {
+            return Ok(());
+        }
         tracing::info!("Starting Lean REPL: {:?}", self.advisor_path);
 
         let mut cmd = Command::new(&self.advisor_path);
-        cmd.arg("--repl")
-            .stdin(Stdio::piped())
+        cmd.arg("--repl");
+        if self.verbose {
+            cmd.arg("--verbose");
+        }
+        cmd.stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
@@ -146,15 +257,16 @@ impl LeanRepl {
             }
         });
 
-        // Set up stderr reader thread (for logging)
+        // Set up stderr reader thread (for logging and the debug console)
         let stderr = process.stderr.take();
         if let Some(stderr) = stderr {
+            let stderr_tx = self.stderr_tx.clone();
             thread::spawn(move || {
                 let reader = BufReader::new(stderr);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        tracing::debug!("Lean REPL stderr: {}", line);
-                    }
+                for line in reader.lines().map_while(Result::ok) {
+                    tracing::debug!("Lean REPL stderr: {}", line);
+                    // No subscribers is the normal case
+                    let _ = stderr_tx.send(line);
                 }
             });
         }
@@ -162,7 +274,14 @@ impl LeanRepl {
         self.process = Some(process);
         self.response_rx = Some(response_rx);
         self.stdin_tx = Some(stdin_tx);
+        Ok(())
+    }
 
+    /// Give a freshly spawned REPL time to initialize and discard its startup output
+    pub fn wait_ready(&mut self) {
+        if self.is_demo() {
+            return;
+        }
         // Wait a bit for the REPL to initialize
         thread::sleep(Duration::from_millis(500));
 
@@ -172,44 +291,73 @@ impl LeanRepl {
         }
 
         tracing::info!("Lean REPL started successfully");
-        Ok(())
     }
 
     /// Send a request to the Lean REPL and wait for a response
     pub fn send_request(&mut self, request: &JsonRpcRequest) -> Result<JsonRpcResponse, LeanReplError> {
+        self.send_request_timed(request, &mut PhaseTimings::default())
+    }
+
+    /// Send a request like [`send_request`](Self::send_request), adding the
+    /// time spent in each phase to `timings`
+    pub fn send_request_timed(
+        &mut self,
+        request: &JsonRpcRequest,
+        timings: &mut PhaseTimings,
+    ) -> Result<JsonRpcResponse, LeanReplError> {
+        if let Some(mock) = self.mock {
+            let started = Instant::now();
+            let response = mock.handle(request);
+            timings.add(Phase::Compute, started.elapsed());
+            return Ok(response);
+        }
+        if let Some(replay) = &mut self.replay {
+            let started = Instant::now();
+            let response = replay.handle(request);
+            timings.add(Phase::Compute, started.elapsed());
+            return Ok(response);
+        }
         if !self.is_running() {
-            self.start()?;
+            let started = Instant::now();
+            let result = self.start();
+            timings.add(Phase::Spawn, started.elapsed());
+            result?;
         }
 
         let stdin_tx = self.stdin_tx.as_ref().ok_or(LeanReplError::NotRunning)?;
         let response_rx = self.response_rx.as_ref().ok_or(LeanReplError::NotRunning)?;
 
         // Serialize and send request
+        let started = Instant::now();
         let request_str = serde_json::to_string(request)
             .map_err(|e| LeanReplError::SendFailed(e.to_string()))?;
+        timings.add(Phase::Serialization, started.elapsed());
 
         tracing::debug!("Sending to Lean REPL: {}", request_str);
 
+        let started = Instant::now();
         stdin_tx
             .send(format!("{}\n", request_str))
             .map_err(|e| LeanReplError::SendFailed(e.to_string()))?;
 
         // Wait for response with timeout
-        let timeout = Duration::from_secs(30);
         let response_str = response_rx
-            .recv_timeout(timeout)
+            .recv_timeout(self.request_timeout)
             .map_err(|e| match e {
                 mpsc::RecvTimeoutError::Timeout => LeanReplError::Timeout,
                 mpsc::RecvTimeoutError::Disconnected => {
                     LeanReplError::ReceiveFailed("REPL disconnected".to_string())
                 }
             })?;
+        timings.add(Phase::Compute, started.elapsed());
 
         tracing::debug!("Received from Lean REPL: {}", response_str);
 
         // Parse response
+        let started = Instant::now();
         let response: JsonRpcResponse = serde_json::from_str(&response_str)
             .map_err(|e| LeanReplError::InvalidJson(e.to_string()))?;
+        timings.add(Phase::Parsing, started.elapsed());
 
         Ok(response)
     }
@@ -328,4 +476,23 @@ mod tests {
         let json = extract_json(&mut buffer);
         assert!(json.is_none());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verbose_stderr_is_broadcast() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("advisor");
+        std::fs::write(&script, "#!/bin/sh\necho \"args: $*\" >&2\nsleep 5\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut repl = LeanRepl::new(script);
+        repl.set_verbose(true);
+        let mut stderr = repl.subscribe_stderr();
+        repl.spawn().unwrap();
+
+        assert_eq!(stderr.blocking_recv().unwrap(), "args: --repl --verbose");
+        repl.stop();
+    }
 }
@@ -0,0 +1,399 @@
+//! Import of data exported from the old browser-only version (PWA).
+//!
+//! The PWA kept everything in `localStorage` under [`LEGACY_STORAGE_KEY`]
+//! as a JSON string, with one flat object per school and its own field
+//! names. Its export is either the `localStorage` dump (keys to JSON
+//! strings) or the decoded value itself, `{"schools": [...]}` or a bare
+//! array. Each school is converted to the current field names and then
+//! validated like any imported file; the [`ConversionReport`] tells the
+//! user what was renamed, assembled, numbered or left out.
+//!
+//! | PWA field | Current field |
+//! |---|---|
+//! | `schoolName` | `name` |
+//! | `rank` | `priority` |
+//! | `examDay`, `resultDay` | `examDate`, `resultDate` |
+//! | `admissionDeadline` | `enrollmentFeeDeadline` |
+//! | `admissionFee`, `tuitionFee` | `enrollmentFee`, `tuition` |
+//! | `result` (`pending`, `pass`, `fail`, `withdrawn`) | `passStatus` |
+//! | `admissionPaid` | `enrollmentFeePaid` |
+//! | `bankCode` ... `accountHolder` | `payee` |
+//!
+//! Days may be ISO strings, any text [`parse_date_text`] reads, or
+//! JavaScript timestamps in milliseconds. Amounts may be text with commas.
+//!
+//! [`parse_date_text`]: crate::date::parse_date_text
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::date;
+use crate::import::{self, ImportError, ImportPreview, ParsedImport};
+
+/// `localStorage` key the PWA saved its data under
+pub const LEGACY_STORAGE_KEY: &str = "school-payment-data";
+
+/// PWA fields renamed to a current field
+const RENAMED_FIELDS: [(&str, &str); 9] = [
+    ("schoolName", "name"),
+    ("rank", "priority"),
+    ("examDay", "examDate"),
+    ("resultDay", "resultDate"),
+    ("admissionDeadline", "enrollmentFeeDeadline"),
+    ("admissionFee", "enrollmentFee"),
+    ("tuitionFee", "tuition"),
+    ("result", "passStatus"),
+    ("admissionPaid", "enrollmentFeePaid"),
+];
+
+/// PWA fields kept under the same name
+const KEPT_FIELDS: [&str; 5] = ["id", "name", "tuitionDeadline", "tuitionPaid", "childId"];
+
+/// PWA bank account fields and the [`BankAccount`] field each fills
+///
+/// [`BankAccount`]: crate::model::BankAccount
+const PAYEE_FIELDS: [(&str, &str); 7] = [
+    ("bankCode", "bankCode"),
+    ("bankName", "bankName"),
+    ("branchCode", "branchCode"),
+    ("branchName", "branchName"),
+    ("accountType", "accountType"),
+    ("accountNumber", "accountNumber"),
+    ("accountHolder", "holderName"),
+];
+
+/// Fields holding days
+const DAY_FIELDS: [&str; 4] = [
+    "examDate",
+    "resultDate",
+    "enrollmentFeeDeadline",
+    "tuitionDeadline",
+];
+
+/// Fields holding amounts or numbers
+const NUMBER_FIELDS: [&str; 4] = ["enrollmentFee", "tuition", "priority", "childId"];
+
+/// How many schools had a PWA field renamed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedField {
+    pub legacy: String,
+    pub field: String,
+    pub count: usize,
+}
+
+/// A PWA field with no counterpart, and how many schools had it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedField {
+    pub legacy: String,
+    pub count: usize,
+}
+
+/// What the conversion from the PWA format did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionReport {
+    /// Schools found in the export
+    pub schools: usize,
+    pub renamed: Vec<RenamedField>,
+    /// Fields left out because the app has no place for them
+    pub dropped: Vec<DroppedField>,
+    /// Schools whose bank fields were combined into a payee
+    pub payees: usize,
+    /// Schools without an id that were numbered after the others
+    pub ids_assigned: usize,
+    /// Days given as timestamps and converted to the day in Japan
+    pub timestamps_converted: usize,
+}
+
+/// Preview of a PWA import with its conversion report
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyImportPreview {
+    #[serde(flatten)]
+    pub preview: ImportPreview,
+    pub report: ConversionReport,
+}
+
+/// Schools of a PWA export, whichever form it was saved in
+fn legacy_schools(data: serde_json::Value) -> Option<Vec<serde_json::Value>> {
+    match data {
+        serde_json::Value::Array(schools) => Some(schools),
+        serde_json::Value::Object(mut map) => {
+            if let Some(serde_json::Value::String(stored)) = map.remove(LEGACY_STORAGE_KEY) {
+                return legacy_schools(serde_json::from_str(&stored).ok()?);
+            }
+            match map.remove("schools")? {
+                serde_json::Value::Array(schools) => Some(schools),
+                // Saved twice encoded by some versions
+                serde_json::Value::String(stored) => {
+                    legacy_schools(serde_json::from_str(&stored).ok()?)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The day in Japan of a PWA timestamp; `None` for other values, which
+/// are left for import validation to read
+fn convert_timestamp(value: &serde_json::Value) -> Option<serde_json::Value> {
+    let number = value.as_u64()?;
+    // Days are 8 digits; JavaScript timestamps are milliseconds
+    if number < 100_000_000 {
+        return None;
+    }
+    let time = chrono::DateTime::from_timestamp_millis(number as i64)?;
+    Some(date::day_of(&time.with_timezone(&date::jst())).into())
+}
+
+fn convert_number(value: &serde_json::Value) -> Option<serde_json::Value> {
+    let text = value.as_str()?;
+    let digits: String = text
+        .trim()
+        .trim_start_matches(['¥', '￥'])
+        .trim_end_matches('円')
+        .chars()
+        .filter(|c| !matches!(c, ',' | '，'))
+        .collect();
+    digits.parse::<u64>().ok().map(Into::into)
+}
+
+fn convert_status(value: &serde_json::Value) -> serde_json::Value {
+    let status = match value.as_str().unwrap_or_default() {
+        "pass" | "passed" => "passed",
+        "fail" | "failed" => "failed",
+        "withdrawn" | "cancelled" => "cancelled",
+        "pending" | "" => "notYetAnnounced",
+        // Left for validation to report
+        other => other,
+    };
+    status.into()
+}
+
+fn convert_account_type(value: &serde_json::Value) -> serde_json::Value {
+    let account_type = match value.as_str().unwrap_or_default() {
+        "当座" | "checking" => "checking",
+        "貯蓄" | "savings" => "savings",
+        _ => "ordinary",
+    };
+    account_type.into()
+}
+
+/// Convert PWA schools to current rows, counting what was done
+fn convert(schools: Vec<serde_json::Value>) -> (Vec<serde_json::Value>, ConversionReport) {
+    let mut report = ConversionReport {
+        schools: schools.len(),
+        ..Default::default()
+    };
+    let mut renamed: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    let mut dropped: BTreeMap<String, usize> = BTreeMap::new();
+    let mut next_id = schools
+        .iter()
+        .filter_map(|s| s.get("id").and_then(|id| id.as_u64()))
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let rows = schools
+        .into_iter()
+        .map(|school| {
+            let serde_json::Value::Object(fields) = school else {
+                // Left for validation to report
+                return school;
+            };
+            let mut row = serde_json::Map::new();
+            let mut payee = serde_json::Map::new();
+            for (legacy, value) in fields {
+                if KEPT_FIELDS.contains(&legacy.as_str()) {
+                    row.insert(legacy, value);
+                } else if let Some(&(from, to)) =
+                    RENAMED_FIELDS.iter().find(|(from, _)| *from == legacy)
+                {
+                    *renamed.entry((from, to)).or_default() += 1;
+                    row.insert(to.to_string(), value);
+                } else if let Some(&(_, to)) = PAYEE_FIELDS.iter().find(|(f, _)| *f == legacy) {
+                    payee.insert(to.to_string(), value);
+                } else {
+                    *dropped.entry(legacy).or_default() += 1;
+                }
+            }
+
+            for field in DAY_FIELDS {
+                if let Some(day) = row.get(field).and_then(convert_timestamp) {
+                    row.insert(field.to_string(), day);
+                    report.timestamps_converted += 1;
+                }
+            }
+            for field in NUMBER_FIELDS {
+                if let Some(number) = row.get(field).and_then(convert_number) {
+                    row.insert(field.to_string(), number);
+                }
+            }
+            if let Some(status) = row.get("passStatus") {
+                row.insert("passStatus".to_string(), convert_status(status));
+            }
+            if !payee.is_empty() {
+                let account_type = payee.get("accountType").map(convert_account_type);
+                payee.insert(
+                    "accountType".to_string(),
+                    account_type.unwrap_or_else(|| "ordinary".into()),
+                );
+                for (_, field) in PAYEE_FIELDS {
+                    payee.entry(field).or_insert_with(|| "".into());
+                }
+                row.insert("payee".to_string(), payee.into());
+                report.payees += 1;
+            }
+            if !row.contains_key("id") {
+                row.insert("id".to_string(), next_id.into());
+                next_id += 1;
+                report.ids_assigned += 1;
+            }
+            row.into()
+        })
+        .collect();
+
+    report.renamed = renamed
+        .into_iter()
+        .map(|((legacy, field), count)| RenamedField {
+            legacy: legacy.to_string(),
+            field: field.to_string(),
+            count,
+        })
+        .collect();
+    report.dropped = dropped
+        .into_iter()
+        .map(|(legacy, count)| DroppedField { legacy, count })
+        .collect();
+    (rows, report)
+}
+
+/// Parse a PWA export, returning the valid schools with a report
+pub fn parse(bytes: &[u8]) -> Result<(ParsedImport, ConversionReport), ImportError> {
+    let text = String::from_utf8_lossy(bytes);
+    let data: serde_json::Value = serde_json::from_str(text.trim_start_matches('\u{feff}'))?;
+    let schools = legacy_schools(data).ok_or(ImportError::NotLegacyExport)?;
+    let (rows, report) = convert(schools);
+    Ok((import::validate(rows), report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn legacy_export() -> serde_json::Value {
+        let schools = json!({
+            "schools": [
+                {
+                    "id": 3,
+                    "schoolName": "東京大学",
+                    "rank": 1,
+                    "examDay": "2026-02-25",
+                    "resultDay": "2026-03-10",
+                    "admissionDeadline": "2026-03-15",
+                    "tuitionDeadline": "2026-03-31",
+                    "admissionFee": "282,000",
+                    "tuitionFee": 535800,
+                    "result": "pass",
+                    "admissionPaid": true,
+                    "tuitionPaid": false,
+                    "bankCode": "0001",
+                    "bankName": "ミズホ",
+                    "branchCode": "001",
+                    "branchName": "トウキヨウ",
+                    "accountType": "普通",
+                    "accountNumber": "1234567",
+                    "accountHolder": "トウキヨウダイガク",
+                    "color": "#ff0000"
+                },
+                {
+                    "schoolName": "早稲田大学",
+                    "examDay": 1770346800000u64,
+                    "resultDay": "2026/02/20",
+                    "admissionDeadline": "令和8年2月27日",
+                    "tuitionDeadline": "2026-03-27",
+                    "admissionFee": 200000,
+                    "tuitionFee": 600000,
+                    "result": "pending",
+                    "color": "#00ff00"
+                },
+                { "schoolName": "", "examDay": "2026-02-01" }
+            ]
+        });
+        json!({
+            LEGACY_STORAGE_KEY: schools.to_string(),
+            "theme": "dark"
+        })
+    }
+
+    #[test]
+    fn test_parse_localstorage_dump() {
+        let bytes = legacy_export().to_string().into_bytes();
+        let (parsed, report) = parse(&bytes).unwrap();
+
+        assert_eq!(parsed.schools.len(), 2);
+        assert_eq!(parsed.issues.len(), 1);
+        assert_eq!(parsed.issues[0].row, 3);
+
+        let todai = &parsed.schools[0];
+        assert_eq!(todai["name"], "東京大学");
+        assert_eq!(todai["priority"], 1);
+        assert_eq!(todai["examDate"], 20260225);
+        assert_eq!(todai["enrollmentFee"], 282000);
+        assert_eq!(todai["passStatus"], "passed");
+        assert_eq!(todai["enrollmentFeePaid"], true);
+        assert_eq!(todai["payee"]["holderName"], "トウキヨウダイガク");
+        assert_eq!(todai["payee"]["accountType"], "ordinary");
+        assert!(todai.get("color").is_none());
+
+        let waseda = &parsed.schools[1];
+        assert_eq!(waseda["id"], 4);
+        assert_eq!(waseda["examDate"], 20260206);
+        assert_eq!(waseda["enrollmentFeeDeadline"], 20260227);
+        assert_eq!(waseda["passStatus"], "notYetAnnounced");
+
+        assert_eq!(report.schools, 3);
+        assert_eq!(report.payees, 1);
+        assert_eq!(report.ids_assigned, 2);
+        assert_eq!(report.timestamps_converted, 1);
+        assert_eq!(
+            report.dropped,
+            vec![DroppedField {
+                legacy: "color".to_string(),
+                count: 2
+            }]
+        );
+        assert!(report.renamed.contains(&RenamedField {
+            legacy: "schoolName".to_string(),
+            field: "name".to_string(),
+            count: 3
+        }));
+    }
+
+    #[test]
+    fn test_parse_decoded_forms() {
+        let schools = json!([{
+            "schoolName": "慶應義塾大学",
+            "examDay": "2026-02-14",
+            "resultDay": "2026-02-21",
+            "admissionDeadline": "2026-03-02",
+            "tuitionDeadline": "2026-03-02",
+            "admissionFee": 200000,
+            "tuitionFee": 1000000
+        }]);
+        let (parsed, _) = parse(schools.to_string().as_bytes()).unwrap();
+        assert_eq!(parsed.schools.len(), 1);
+        let (parsed, _) = parse(json!({ "schools": schools }).to_string().as_bytes()).unwrap();
+        assert_eq!(parsed.schools[0]["id"], 1);
+
+        assert!(matches!(
+            parse(b"{\"other\": 1}"),
+            Err(ImportError::NotLegacyExport)
+        ));
+    }
+}
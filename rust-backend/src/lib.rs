@@ -2,12 +2,84 @@
 //!
 //! This library provides common functionality for both Tauri desktop and Axum web server.
 
+pub mod analysis;
+pub mod audit;
+pub mod backup;
+pub mod budget;
+pub mod bulk;
+pub mod call_stats;
+pub mod calendar;
+pub mod cloud_backup;
+pub mod config;
+pub mod countdown;
+pub mod crash;
+pub mod currency;
+pub mod daily_check;
+pub mod data_location;
+pub mod date;
+pub mod deep_link;
+pub mod demo;
+pub mod diff;
+pub mod edit_lock;
+pub mod email;
+pub mod events;
+pub mod explanations;
+pub mod export;
+pub mod field_crypto;
+pub mod format;
+pub mod google_calendar;
+pub mod health_history;
+pub mod history;
+pub mod idempotency;
+pub mod journal;
 pub mod json_rpc;
 pub mod lean_repl;
+pub mod legacy_import;
+pub mod log_buffer;
+pub mod logs;
+pub mod migrations;
+pub mod mock_advisor;
+pub mod model;
+pub mod notes;
+pub mod notifier;
+pub mod onboarding;
+pub mod os_reminders;
+pub mod outcome;
+pub mod payments;
+pub mod preload;
+pub mod profiling;
+pub mod query;
+pub mod recording;
+pub mod repl_pool;
+pub mod result_cache;
+pub mod revision;
 pub mod handlers;
+pub mod i18n;
+pub mod import;
+pub mod reminders;
+pub mod sample;
+pub mod search;
+pub mod self_test;
+pub mod settings;
+pub mod share;
+pub mod sheet_import;
+pub mod simulation;
+pub mod startup;
 pub mod storage;
+pub mod streaming;
+pub mod summary;
+pub mod supervisor;
+pub mod support;
+pub mod tax_report;
+pub mod templates;
+pub mod timeline;
+pub mod token;
+pub mod transfer;
+pub mod web_push;
+pub mod webhook;
 
 pub use json_rpc::{JsonRpcRequest, JsonRpcResponse, JsonRpcError};
 pub use lean_repl::LeanRepl;
-pub use handlers::{send_rpc, health_check, restart_repl};
+pub use handlers::{send_rpc, send_rpc_batch, health_check, restart_repl, HandlerError};
 pub use storage::Storage;
+pub use config::AppConfig;
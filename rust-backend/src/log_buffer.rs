@@ -0,0 +1,233 @@
+//! The most recent log events, kept in memory.
+//!
+//! [`LogBuffer`] is a tracing layer holding the last [`CAPACITY`] events
+//! that pass the log filter. The diagnostics screen queries it with
+//! [`LogBuffer::query`] instead of reading the log files, which may not be
+//! open yet, or be somewhere else entirely when configured so.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+use ts_rs::TS;
+
+use crate::logs::{LogError, LogLevel};
+
+/// Events kept; the oldest are dropped first
+pub const CAPACITY: usize = 1000;
+
+/// Events returned when the query does not say
+pub const DEFAULT_LIMIT: usize = 200;
+
+/// One log event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct LogEvent {
+    /// RFC 3339 time in Japan
+    pub time: String,
+    /// `TRACE`, `DEBUG`, `INFO`, `WARN` or `ERROR`
+    pub level: String,
+    /// Module the event was logged from, such as `rust_backend::handlers`
+    pub target: String,
+    /// The message followed by the event's other fields as `name=value`
+    pub message: String,
+}
+
+/// Which events to return
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct EventQuery {
+    /// Least severe level returned, all levels if unset
+    #[ts(optional)]
+    pub level: Option<String>,
+    /// Only events from this module or its submodules
+    #[ts(optional)]
+    pub module: Option<String>,
+    /// Most events returned, [`DEFAULT_LIMIT`] if unset
+    #[ts(optional)]
+    pub limit: Option<usize>,
+}
+
+/// Collects the message and fields of an event
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Shared buffer of recent events, usable as a tracing layer
+#[derive(Debug, Clone, Default)]
+pub struct LogBuffer {
+    events: Arc<Mutex<VecDeque<LogEvent>>>,
+}
+
+impl LogBuffer {
+    /// Keep `event`, dropping the oldest beyond [`CAPACITY`]
+    pub fn push(&self, event: LogEvent) {
+        if let Ok(mut events) = self.events.lock() {
+            if events.len() == CAPACITY {
+                events.pop_front();
+            }
+            events.push_back(event);
+        }
+    }
+
+    /// The most recent events matching `query`, oldest first
+    pub fn query(&self, query: &EventQuery) -> Result<Vec<LogEvent>, LogError> {
+        let min_level = match query.level.as_deref() {
+            Some(name) => {
+                LogLevel::from_name(name).ok_or_else(|| LogError::UnknownLevel(name.to_string()))?
+            }
+            None => LogLevel::Trace,
+        };
+        let module = query.module.as_deref().filter(|m| !m.is_empty());
+        let in_module = |target: &str| {
+            module.is_none_or(|m| {
+                target == m
+                    || target
+                        .strip_prefix(m)
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+        };
+        let Ok(events) = self.events.lock() else {
+            return Ok(Vec::new());
+        };
+        let mut matched: Vec<LogEvent> = events
+            .iter()
+            .rev()
+            .filter(|e| {
+                LogLevel::from_name(&e.level).is_some_and(|level| level >= min_level)
+                    && in_module(&e.target)
+            })
+            .take(query.limit.unwrap_or(DEFAULT_LIMIT))
+            .cloned()
+            .collect();
+        matched.reverse();
+        Ok(matched)
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for LogBuffer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        self.push(LogEvent {
+            time: crate::date::now().to_rfc3339(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn event(level: &str, target: &str, message: &str) -> LogEvent {
+        LogEvent {
+            time: "2026-03-01T09:00:00+09:00".to_string(),
+            level: level.to_string(),
+            target: target.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_captures_events() {
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(buffer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(school_id = 2, "Marked paid: {}", "早稲田大学");
+        });
+
+        let events = buffer.query(&EventQuery::default()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].level, "INFO");
+        assert_eq!(events[0].target, "rust_backend::log_buffer::tests");
+        assert_eq!(events[0].message, "Marked paid: 早稲田大学 school_id=2");
+    }
+
+    #[test]
+    fn test_query() {
+        let buffer = LogBuffer::default();
+        buffer.push(event("DEBUG", "rust_backend::handlers", "states"));
+        buffer.push(event("WARN", "rust_backend::lean_repl", "slow response"));
+        buffer.push(event("ERROR", "rust_backend::handlers", "failed"));
+        buffer.push(event("INFO", "rust_backend_extra", "other crate"));
+
+        let query = |level: Option<&str>, module: Option<&str>, limit| EventQuery {
+            level: level.map(str::to_string),
+            module: module.map(str::to_string),
+            limit,
+        };
+        let messages = |q: EventQuery| -> Vec<String> {
+            buffer
+                .query(&q)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.message)
+                .collect()
+        };
+        assert_eq!(
+            messages(query(Some("warn"), None, None)),
+            vec!["slow response", "failed"]
+        );
+        assert_eq!(
+            messages(query(None, Some("rust_backend::handlers"), None)),
+            vec!["states", "failed"]
+        );
+        // A module matches its submodules but not crates sharing its prefix
+        assert_eq!(messages(query(None, Some("rust_backend"), None)).len(), 3);
+        assert_eq!(
+            messages(query(None, None, Some(2))),
+            vec!["failed", "other crate"]
+        );
+        assert!(matches!(
+            buffer.query(&query(Some("loud"), None, None)),
+            Err(LogError::UnknownLevel(_))
+        ));
+    }
+
+    #[test]
+    fn test_keeps_the_latest() {
+        let buffer = LogBuffer::default();
+        for i in 0..CAPACITY + 5 {
+            buffer.push(event("INFO", "rust_backend", &i.to_string()));
+        }
+        let events = buffer
+            .query(&EventQuery {
+                limit: Some(CAPACITY * 2),
+                ..EventQuery::default()
+            })
+            .unwrap();
+        assert_eq!(events.len(), CAPACITY);
+        assert_eq!(events[0].message, "5");
+    }
+}
@@ -0,0 +1,584 @@
+//! Log files, rotated daily and pruned by age and total size.
+//!
+//! [`LogWriter`] is handed to the tracing subscriber before the log
+//! directory is known; writes are dropped until [`LogWriter::open`] is
+//! called. Each day is written to its own file, `app.YYYY-MM-DD.log`, by
+//! a `tracing-appender` rolling appender. Files older than
+//! [`LogConfig::max_age_days`] are deleted, and then the oldest files
+//! until the rest fit in [`LogConfig::max_total_size_mb`]; this is done
+//! when the log is opened and again each day.
+//!
+//! [`init_tracing`] sets up the subscriber for both the desktop app and
+//! the web server, and returns a [`LogLevelHandle`] for applying the level
+//! chosen in the settings.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+use thiserror::Error;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::log_buffer::LogBuffer;
+
+/// Subdirectory of the data directory holding log files
+pub const LOG_DIR: &str = "logs";
+
+/// Name of the log file written before logs were rotated daily; it and
+/// its numbered predecessors are read and pruned like the daily files
+pub const LOG_FILE: &str = "app.log";
+
+/// Prefix of the daily log file names
+pub const LOG_FILE_PREFIX: &str = "app";
+
+/// Extension of the daily log file names
+pub const LOG_FILE_SUFFIX: &str = "log";
+
+/// Errors that can occur while reading or packaging logs
+#[derive(Debug, Error)]
+pub enum LogError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Unknown log level: {0}")]
+    UnknownLevel(String),
+
+    #[error("Invalid log filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Could not change the log filter: {0}")]
+    Reload(#[from] reload::Error),
+}
+
+/// Where logs are written and how much is kept, from `[logging]` in
+/// `config.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Directory for the log files, `logs` in the data directory if unset
+    pub dir: Option<PathBuf>,
+    /// Days a log file is kept
+    pub max_age_days: u32,
+    /// Total size of the log files kept, in megabytes
+    pub max_total_size_mb: u64,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_age_days: 14,
+            max_total_size_mb: 50,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Directory for the log files of the app storing its data in `data_dir`
+    pub fn dir(&self, data_dir: &Path) -> PathBuf {
+        self.dir.clone().unwrap_or_else(|| data_dir.join(LOG_DIR))
+    }
+
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(u64::from(self.max_age_days) * 24 * 3600)
+    }
+
+    pub fn max_total_size(&self) -> u64 {
+        self.max_total_size_mb * 1024 * 1024
+    }
+}
+
+/// Log severity, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Look up a level by name, case-insensitively
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" | "WARNING" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    /// Name as used in tracing filter directives
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Trace => "trace",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+
+    /// Level of a formatted log line (`<timestamp>  INFO target: message`).
+    ///
+    /// Returns `None` for continuation lines of multi-line messages.
+    fn of_line(line: &str) -> Option<Self> {
+        line.split_whitespace().nth(1).and_then(Self::from_name)
+    }
+}
+
+/// Handle for changing the level of the app's own log events after
+/// tracing is initialized
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+    targets: Vec<&'static str>,
+    /// Set when `RUST_LOG` chose the filter, which then stays as it is
+    from_env: bool,
+}
+
+impl LogLevelHandle {
+    /// Log events of the app's targets at `level` and above
+    pub fn set_level(&self, level: LogLevel) {
+        if self.from_env {
+            return;
+        }
+        if let Err(e) = self.handle.reload(directives(&self.targets, level)) {
+            tracing::warn!("Could not change the log level: {}", e);
+        }
+    }
+
+    /// Replace the filter with `filter`, in `RUST_LOG` syntax such as
+    /// `rust_backend=trace,info`, even if `RUST_LOG` chose the current one.
+    ///
+    /// Lasts until the process exits or the level setting is changed.
+    /// Returns the filter now in effect.
+    pub fn set_filter(&self, filter: &str) -> Result<String, LogError> {
+        let filter = EnvFilter::try_new(filter.trim())
+            .map_err(|e| LogError::InvalidFilter(e.to_string()))?;
+        self.handle.reload(filter)?;
+        let current = self.current();
+        tracing::info!("Log filter set to {}", current);
+        Ok(current)
+    }
+
+    /// Filter in effect, in `RUST_LOG` syntax
+    pub fn current(&self) -> String {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .unwrap_or_default()
+    }
+}
+
+fn directives(targets: &[&'static str], level: LogLevel) -> EnvFilter {
+    let directives: Vec<String> = targets
+        .iter()
+        .map(|target| format!("{}={}", target, level.as_str()))
+        .collect();
+    EnvFilter::new(directives.join(","))
+}
+
+/// Install the global tracing subscriber, logging to stdout, `file` and
+/// `buffer`.
+///
+/// Events of `targets` are logged at `level` and above unless `RUST_LOG`
+/// is set, in which case it decides.
+pub fn init_tracing(
+    targets: &[&'static str],
+    level: LogLevel,
+    file: LogWriter,
+    buffer: LogBuffer,
+) -> LogLevelHandle {
+    let (filter, from_env) = match EnvFilter::try_from_default_env() {
+        Ok(filter) => (filter, true),
+        Err(_) => (directives(targets, level), false),
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(buffer)
+        .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || file.clone()),
+        )
+        .init();
+    LogLevelHandle {
+        handle,
+        targets: targets.to_vec(),
+        from_env,
+    }
+}
+
+struct OpenLog {
+    dir: PathBuf,
+    appender: RollingFileAppender,
+    config: LogConfig,
+    /// Day the directory was last pruned
+    pruned_on: u32,
+}
+
+impl OpenLog {
+    fn prune_daily(&mut self) {
+        let today = crate::date::today();
+        if self.pruned_on == today {
+            return;
+        }
+        self.pruned_on = today;
+        // Failing to delete old logs must not stop logging
+        let _ = prune(
+            &self.dir,
+            self.config.max_age(),
+            self.config.max_total_size(),
+        );
+    }
+}
+
+/// Shared handle to the log files, usable as a tracing writer
+#[derive(Clone, Default)]
+pub struct LogWriter {
+    file: Arc<Mutex<Option<OpenLog>>>,
+}
+
+impl LogWriter {
+    /// Start writing to `dir`, creating it if needed, and prune it
+    /// following `config`
+    pub fn open(&self, dir: &Path, config: &LogConfig) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .build(dir)
+            .map_err(io::Error::other)?;
+        let mut log = OpenLog {
+            dir: dir.to_path_buf(),
+            appender,
+            config: config.clone(),
+            pruned_on: 0,
+        };
+        log.prune_daily();
+        if let Ok(mut current) = self.file.lock() {
+            *current = Some(log);
+        }
+        Ok(())
+    }
+
+    /// Directory being written to, once opened
+    pub fn dir(&self) -> Option<PathBuf> {
+        let current = self.file.lock().ok()?;
+        current.as_ref().map(|log| log.dir.clone())
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.file.lock() {
+            Ok(mut current) => match current.as_mut() {
+                Some(log) => {
+                    log.prune_daily();
+                    log.appender.write(buf)
+                }
+                None => Ok(buf.len()),
+            },
+            Err(_) => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.lock() {
+            Ok(mut current) => current.as_mut().map_or(Ok(()), |log| log.appender.flush()),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// Position of a log file name in age order, `None` if it is not a log file
+fn file_order(name: &str) -> Option<(u8, u64, String)> {
+    if name == LOG_FILE {
+        return Some((1, 0, String::new()));
+    }
+    if let Some(index) = name
+        .strip_prefix(LOG_FILE)
+        .and_then(|n| n.strip_prefix('.'))
+    {
+        // app.log.1 is newer than app.log.2
+        return index
+            .parse::<u64>()
+            .ok()
+            .map(|i| (0, u64::MAX - i, String::new()));
+    }
+    let date = name
+        .strip_prefix(LOG_FILE_PREFIX)?
+        .strip_prefix('.')?
+        .strip_suffix(LOG_FILE_SUFFIX)?
+        .strip_suffix('.')?;
+    (!date.is_empty()).then(|| (2, 0, date.to_string()))
+}
+
+/// Existing log files, oldest first
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let order = file_order(path.file_name()?.to_str()?)?;
+            path.is_file().then_some((order, path))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Delete log files last written more than `max_age` ago, then the oldest
+/// files until the rest take up at most `max_total_size` bytes. The newest
+/// file, which is being written to, is always kept. Returns how many files
+/// were deleted.
+pub fn prune(dir: &Path, max_age: Duration, max_total_size: u64) -> io::Result<usize> {
+    let mut files = log_files(dir);
+    files.pop();
+    let now = SystemTime::now();
+    let mut kept = Vec::new();
+    let mut removed = 0;
+    for path in files {
+        let metadata = fs::metadata(&path)?;
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if age > max_age {
+            fs::remove_file(&path)?;
+            removed += 1;
+        } else {
+            kept.push((path, metadata.len()));
+        }
+    }
+
+    let newest_size = log_files(dir)
+        .last()
+        .and_then(|path| fs::metadata(path).ok())
+        .map_or(0, |m| m.len());
+    let mut total = newest_size + kept.iter().map(|(_, size)| size).sum::<u64>();
+    for (path, size) in kept {
+        if total <= max_total_size {
+            break;
+        }
+        fs::remove_file(&path)?;
+        total -= size;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// The last `lines` log lines at or above `min_level`, oldest first.
+///
+/// Continuation lines of a multi-line message follow their first line.
+pub fn recent_lines(
+    dir: &Path,
+    lines: usize,
+    min_level: LogLevel,
+) -> Result<Vec<String>, LogError> {
+    let mut matched = Vec::new();
+    let mut keep = false;
+    for path in log_files(dir) {
+        let content = fs::read_to_string(path)?;
+        for line in content.lines() {
+            if let Some(level) = LogLevel::of_line(line) {
+                keep = level >= min_level;
+            }
+            if keep {
+                matched.push(line.to_string());
+            }
+        }
+    }
+    let skip = matched.len().saturating_sub(lines);
+    Ok(matched.split_off(skip))
+}
+
+/// Package all log files into a zip archive
+pub fn zip_logs(dir: &Path) -> Result<Vec<u8>, LogError> {
+    let mut archive = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for path in log_files(dir) {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        archive.start_file(name, options)?;
+        archive.write_all(&fs::read(&path)?)?;
+    }
+    Ok(archive.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const SAMPLE: &str = "\
+2026-03-01T00:00:00.000000Z  INFO school_payment: started
+2026-03-01T00:00:01.000000Z DEBUG rust_backend::handlers: states: [
+  {\"schoolId\": 1}
+]
+2026-03-01T00:00:02.000000Z  WARN rust_backend::lean_repl: slow response
+2026-03-01T00:00:03.000000Z ERROR rust_backend::handlers: failed
+";
+
+    #[test]
+    fn test_recent_lines_filters_by_level() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(LOG_FILE), SAMPLE).unwrap();
+
+        let warn = recent_lines(dir.path(), 100, LogLevel::Warn).unwrap();
+        assert_eq!(warn.len(), 2);
+        assert!(warn[0].ends_with("slow response"));
+
+        let debug = recent_lines(dir.path(), 100, LogLevel::Debug).unwrap();
+        assert_eq!(debug.len(), 6);
+        assert_eq!(debug[2], "  {\"schoolId\": 1}");
+
+        let last = recent_lines(dir.path(), 1, LogLevel::Trace).unwrap();
+        assert_eq!(last, vec![SAMPLE.lines().last().unwrap().to_string()]);
+    }
+
+    #[test]
+    fn test_writer_writes_daily_files() {
+        let dir = tempdir().unwrap();
+        let mut writer = LogWriter::default();
+        // Dropped before the directory is known
+        writer.write_all(b"lost\n").unwrap();
+        writer.open(dir.path(), &LogConfig::default()).unwrap();
+        assert_eq!(writer.dir().as_deref(), Some(dir.path()));
+
+        writer.write_all(SAMPLE.as_bytes()).unwrap();
+        writer.flush().unwrap();
+        let files = log_files(dir.path());
+        assert_eq!(files.len(), 1);
+        let name = files[0].file_name().unwrap().to_str().unwrap();
+        assert!(
+            name.starts_with("app.") && name.ends_with(".log"),
+            "{}",
+            name
+        );
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn test_set_filter() {
+        let (layer, handle) = reload::Layer::new(directives(&["rust_backend"], LogLevel::Debug));
+        let handle = LogLevelHandle {
+            handle,
+            targets: vec!["rust_backend"],
+            from_env: true,
+        };
+        assert_eq!(handle.current(), "rust_backend=debug");
+
+        assert_eq!(
+            handle.set_filter(" rust_backend=trace ").unwrap(),
+            "rust_backend=trace"
+        );
+        assert!(matches!(
+            handle.set_filter("rust_backend=loud"),
+            Err(LogError::InvalidFilter(_))
+        ));
+        // A level setting does not override a filter chosen with RUST_LOG
+        handle.set_level(LogLevel::Warn);
+        assert_eq!(handle.current(), "rust_backend=trace");
+
+        drop(layer);
+        assert!(matches!(
+            handle.set_filter("info"),
+            Err(LogError::Reload(_))
+        ));
+    }
+
+    #[test]
+    fn test_log_files_in_age_order() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "app.2026-03-02.log",
+            LOG_FILE,
+            "app.log.2",
+            "app.2026-03-01.log",
+            "app.log.1",
+            "other.txt",
+        ] {
+            fs::write(dir.path().join(name), "x\n").unwrap();
+        }
+        let names: Vec<_> = log_files(dir.path())
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "app.log.2",
+                "app.log.1",
+                "app.log",
+                "app.2026-03-01.log",
+                "app.2026-03-02.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = tempdir().unwrap();
+        let day = Duration::from_secs(24 * 3600);
+        for (name, days_old) in [
+            ("app.2026-03-01.log", 20),
+            ("app.2026-03-10.log", 11),
+            ("app.2026-03-20.log", 1),
+            ("app.2026-03-21.log", 0),
+        ] {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![b'x'; 100]).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::now() - day * days_old)
+                .unwrap();
+        }
+
+        // Past 14 days, then the oldest until 200 bytes are left
+        assert_eq!(prune(dir.path(), day * 14, 200).unwrap(), 2);
+        let files = log_files(dir.path());
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("app.2026-03-20.log"));
+
+        // The file being written is kept however large it is
+        assert_eq!(prune(dir.path(), day * 14, 0).unwrap(), 1);
+        assert!(log_files(dir.path())[0].ends_with("app.2026-03-21.log"));
+    }
+
+    #[test]
+    fn test_zip_logs() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(LOG_FILE), SAMPLE).unwrap();
+        fs::write(dir.path().join("app.log.1"), "older\n").unwrap();
+
+        let bytes = zip_logs(dir.path()).unwrap();
+        let archive = zip::ZipArchive::new(io::Cursor::new(bytes)).unwrap();
+        let names: Vec<_> = archive.file_names().collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"app.log.1"));
+    }
+}
@@ -0,0 +1,190 @@
+//! Versioning and migration of the stored dataset.
+//!
+//! The dataset carries a `schemaVersion`; files written before versioning
+//! are version 0. [`migrate`] upgrades a dataset in place, one step at a
+//! time, and refuses data written by a newer release.
+
+use thiserror::Error;
+
+/// Schema version written by this release
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Name given to the child of a dataset migrated from a single-child version
+pub const DEFAULT_CHILD_NAME: &str = "子ども1";
+
+/// Errors that can occur while checking or migrating the dataset
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("Data schema version {found} is newer than supported version {supported}")]
+    TooNew { found: u32, supported: u32 },
+
+    #[error("Advisor supports data schema version {advisor}, but the data needs {required}")]
+    IncompatibleAdvisor { advisor: u32, required: u32 },
+}
+
+/// File the dataset is copied to before it is migrated from version `from`
+pub fn migration_copy_file(from: u32) -> String {
+    format!("data.v{}.json", from)
+}
+
+/// Whether `file_name` is a copy written by [`migration_copy_file`]
+pub fn is_migration_copy(file_name: &str) -> bool {
+    file_name
+        .strip_prefix("data.v")
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .is_some_and(|version| version.parse::<u32>().is_ok())
+}
+
+/// Schema version recorded in a dataset (0 if absent)
+pub fn schema_version(data: &serde_json::Value) -> u32 {
+    data.get("schemaVersion")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32
+}
+
+/// Version 0 → 1: fill in fields older frontends did not always write
+fn v0_to_v1(data: &mut serde_json::Value) {
+    let Some(schools) = data.get_mut("schools").and_then(|s| s.as_array_mut()) else {
+        return;
+    };
+    for school in schools.iter_mut().filter_map(|s| s.as_object_mut()) {
+        let defaults = [
+            ("priority", serde_json::json!(0)),
+            ("passStatus", serde_json::json!("notYetAnnounced")),
+            ("enrollmentFeePaid", serde_json::json!(false)),
+            ("tuitionPaid", serde_json::json!(false)),
+        ];
+        for (key, value) in defaults {
+            school.entry(key).or_insert(value);
+        }
+    }
+}
+
+/// Version 1 → 2: datasets held a single child's schools; make that child
+/// explicit and tag every school with it
+fn v1_to_v2(data: &mut serde_json::Value) {
+    let has_children = data
+        .get("children")
+        .and_then(|c| c.as_array())
+        .is_some_and(|c| !c.is_empty());
+    if has_children {
+        return;
+    }
+    data["children"] = serde_json::json!([{ "id": 1, "name": DEFAULT_CHILD_NAME }]);
+    let Some(schools) = data.get_mut("schools").and_then(|s| s.as_array_mut()) else {
+        return;
+    };
+    for school in schools.iter_mut().filter_map(|s| s.as_object_mut()) {
+        school.entry("childId").or_insert(1.into());
+    }
+}
+
+/// Migration steps, indexed by the version they upgrade from
+const MIGRATIONS: [fn(&mut serde_json::Value); CURRENT_SCHEMA_VERSION as usize] =
+    [v0_to_v1, v1_to_v2];
+
+/// Upgrade a dataset to [`CURRENT_SCHEMA_VERSION`].
+///
+/// Returns the version the data was at before migrating.
+pub fn migrate(data: &mut serde_json::Value) -> Result<u32, MigrationError> {
+    let from = schema_version(data);
+    if from > CURRENT_SCHEMA_VERSION {
+        return Err(MigrationError::TooNew {
+            found: from,
+            supported: CURRENT_SCHEMA_VERSION,
+        });
+    }
+    if !data.is_object() {
+        *data = serde_json::json!({ "schools": [] });
+    }
+
+    for step in &MIGRATIONS[from as usize..] {
+        step(data);
+    }
+    data["schemaVersion"] = CURRENT_SCHEMA_VERSION.into();
+    Ok(from)
+}
+
+/// Check that an advisor supporting `advisor_schema` can work with data at `data_schema`.
+///
+/// Used both for the running advisor and for the advisor bundled with an
+/// update; an advisor may be newer than the data (it will be migrated) but
+/// not older.
+pub fn check_advisor(advisor_schema: u32, data_schema: u32) -> Result<(), MigrationError> {
+    if advisor_schema < data_schema {
+        return Err(MigrationError::IncompatibleAdvisor {
+            advisor: advisor_schema,
+            required: data_schema,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v0() {
+        let mut data = serde_json::json!({
+            "schools": [{"id": 1, "name": "東京大学", "tuitionPaid": true}]
+        });
+        assert_eq!(migrate(&mut data).unwrap(), 0);
+        assert_eq!(schema_version(&data), CURRENT_SCHEMA_VERSION);
+
+        let school = &data["schools"][0];
+        assert_eq!(school["passStatus"], "notYetAnnounced");
+        assert_eq!(school["enrollmentFeePaid"], false);
+        assert_eq!(school["tuitionPaid"], true);
+        assert_eq!(school["childId"], 1);
+    }
+
+    #[test]
+    fn test_migrate_v1_adds_child() {
+        let mut data = serde_json::json!({
+            "schemaVersion": 1,
+            "schools": [{"id": 1, "name": "東京大学"}, {"id": 2, "name": "早稲田大学"}]
+        });
+        assert_eq!(migrate(&mut data).unwrap(), 1);
+        assert_eq!(
+            data["children"],
+            serde_json::json!([{ "id": 1, "name": DEFAULT_CHILD_NAME }])
+        );
+        assert_eq!(data["schools"][1]["childId"], 1);
+    }
+
+    #[test]
+    fn test_migrate_current_is_noop() {
+        let mut data = serde_json::json!({"schemaVersion": CURRENT_SCHEMA_VERSION, "schools": []});
+        let before = data.clone();
+        assert_eq!(migrate(&mut data).unwrap(), CURRENT_SCHEMA_VERSION);
+        assert_eq!(data, before);
+    }
+
+    #[test]
+    fn test_migrate_too_new() {
+        let mut data = serde_json::json!({"schemaVersion": CURRENT_SCHEMA_VERSION + 1});
+        assert!(matches!(
+            migrate(&mut data),
+            Err(MigrationError::TooNew { .. })
+        ));
+    }
+
+    #[test]
+    fn test_migration_copy_file() {
+        assert_eq!(migration_copy_file(1), "data.v1.json");
+        assert!(is_migration_copy(&migration_copy_file(0)));
+        assert!(!is_migration_copy("data.json"));
+        assert!(!is_migration_copy("data.vx.json"));
+    }
+
+    #[test]
+    fn test_check_advisor() {
+        assert!(check_advisor(1, 1).is_ok());
+        assert!(check_advisor(2, 1).is_ok());
+        assert!(matches!(
+            check_advisor(1, 2),
+            Err(MigrationError::IncompatibleAdvisor { .. })
+        ));
+    }
+}
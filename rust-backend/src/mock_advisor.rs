@@ -0,0 +1,646 @@
+//! Advisor stand-in for demo mode.
+//!
+//! [`MockAdvisor`] answers the same JSON-RPC methods as the Lean advisor
+//! with a plain Rust copy of its payment strategy, so the app can be
+//! explored, screenshotted and driven from frontend CI on machines where
+//! the advisor binary cannot run. Its answers are not covered by the
+//! proofs behind the real advisor and skip its input validation; it is
+//! only ever used in demo mode.
+
+use serde::Deserialize;
+
+use crate::date::{parse_day, to_day};
+use crate::format::format_yen;
+use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::migrations::CURRENT_SCHEMA_VERSION;
+
+/// Version reported by `getVersion`
+pub const MOCK_VERSION: &str = "demo";
+
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchoolInput {
+    id: u64,
+    name: String,
+    priority: u64,
+    result_date: u32,
+    enrollment_fee_deadline: u32,
+    tuition_deadline: u32,
+    enrollment_fee: u64,
+    tuition: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StateInput {
+    school_id: u64,
+    #[serde(default)]
+    pass_status: Option<String>,
+    #[serde(default)]
+    enrollment_fee_paid: bool,
+    #[serde(default)]
+    tuition_paid: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    NotYetAnnounced,
+    Passed,
+    Failed,
+    Cancelled,
+}
+
+impl Pass {
+    fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some("passed") => Self::Passed,
+            Some("failed") => Self::Failed,
+            Some("cancelled") => Self::Cancelled,
+            _ => Self::NotYetAnnounced,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::NotYetAnnounced => "notYetAnnounced",
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SchoolState {
+    school: SchoolInput,
+    pass: Pass,
+    fee_paid: bool,
+    tuition_paid: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatesParams {
+    schools: Vec<SchoolInput>,
+    #[serde(default)]
+    states: Vec<StateInput>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    PayEnrollmentFee(u64),
+    PayTuition(u64),
+    DoNothing,
+}
+
+impl Action {
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let school_id = value.get("schoolId").and_then(|v| v.as_u64());
+        match value.get("type")?.as_str()? {
+            "payEnrollmentFee" => Some(Self::PayEnrollmentFee(school_id?)),
+            "payTuition" => Some(Self::PayTuition(school_id?)),
+            "doNothing" => Some(Self::DoNothing),
+            _ => None,
+        }
+    }
+
+    fn to_json(self) -> serde_json::Value {
+        match self {
+            Self::PayEnrollmentFee(id) => {
+                serde_json::json!({ "type": "payEnrollmentFee", "schoolId": id })
+            }
+            Self::PayTuition(id) => serde_json::json!({ "type": "payTuition", "schoolId": id }),
+            Self::DoNothing => serde_json::json!({ "type": "doNothing" }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Recommendation {
+    action: Action,
+    reason: String,
+    urgency: u32,
+}
+
+impl Recommendation {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "action": self.action.to_json(),
+            "reason": self.reason,
+            "urgency": self.urgency,
+        })
+    }
+}
+
+fn build_states(params: StatesParams) -> Vec<SchoolState> {
+    params
+        .schools
+        .into_iter()
+        .map(|school| {
+            let state = params.states.iter().find(|s| s.school_id == school.id);
+            SchoolState {
+                pass: Pass::from_name(state.and_then(|s| s.pass_status.as_deref())),
+                fee_paid: state.is_some_and(|s| s.enrollment_fee_paid),
+                tuition_paid: state.is_some_and(|s| s.tuition_paid),
+                school,
+            }
+        })
+        .collect()
+}
+
+/// Cancel passes whose unpaid deadline has gone by
+fn apply_deadlines(states: &[SchoolState], today: u32) -> Vec<SchoolState> {
+    states
+        .iter()
+        .cloned()
+        .map(|mut s| {
+            if s.pass == Pass::Passed
+                && ((!s.fee_paid && today > s.school.enrollment_fee_deadline)
+                    || (!s.tuition_paid && today > s.school.tuition_deadline))
+            {
+                s.pass = Pass::Cancelled;
+            }
+            s
+        })
+        .collect()
+}
+
+fn higher<'a>(states: &'a [SchoolState], target: &SchoolState) -> Vec<&'a SchoolState> {
+    states
+        .iter()
+        .filter(|s| s.school.priority < target.school.priority)
+        .collect()
+}
+
+fn should_pay_fee(states: &[SchoolState], target: &SchoolState, today: u32) -> bool {
+    if target.pass != Pass::Passed
+        || target.fee_paid
+        || today > target.school.enrollment_fee_deadline
+    {
+        return false;
+    }
+    let higher = higher(states, target);
+    let higher_viable = higher.iter().any(|s| {
+        s.pass == Pass::Passed && (s.fee_paid || s.school.enrollment_fee_deadline >= today)
+    });
+    if higher_viable {
+        return false;
+    }
+    higher.is_empty()
+        || today == target.school.enrollment_fee_deadline
+        || higher.iter().all(|s| {
+            matches!(s.pass, Pass::Failed | Pass::Cancelled)
+                || (s.pass == Pass::Passed && s.tuition_paid)
+                || (s.pass == Pass::Passed
+                    && !s.fee_paid
+                    && s.school.enrollment_fee_deadline < today)
+        })
+}
+
+fn should_pay_tuition(states: &[SchoolState], target: &SchoolState, today: u32) -> bool {
+    if target.pass != Pass::Passed
+        || !target.fee_paid
+        || target.tuition_paid
+        || today > target.school.tuition_deadline
+    {
+        return false;
+    }
+    today == target.school.tuition_deadline
+        || higher(states, target)
+            .iter()
+            .all(|s| matches!(s.pass, Pass::Failed | Pass::Cancelled))
+}
+
+/// Days from `today` until `deadline`, counting the numbers as the advisor does
+fn days_until(deadline: u32, today: u32) -> u32 {
+    deadline.saturating_sub(today)
+}
+
+fn recommendation_for(
+    states: &[SchoolState],
+    state: &SchoolState,
+    today: u32,
+) -> Option<Recommendation> {
+    let school = &state.school;
+    if should_pay_fee(states, state, today) {
+        let urgency = days_until(school.enrollment_fee_deadline, today);
+        let amount = format_yen(school.enrollment_fee);
+        let reason = if urgency == 0 {
+            format!(
+                "{}の入学金支払期限です（{}）。支払わないと合格取り消しになります。",
+                school.name, amount
+            )
+        } else if higher(states, state).is_empty() {
+            format!(
+                "{}の入学金（{}）を支払う必要があります。第一志望に合格しました。",
+                school.name, amount
+            )
+        } else {
+            format!(
+                "{}の入学金（{}）を支払う必要があります。上位校の結果が全て出ました。",
+                school.name, amount
+            )
+        };
+        return Some(Recommendation {
+            action: Action::PayEnrollmentFee(school.id),
+            reason,
+            urgency,
+        });
+    }
+    if should_pay_tuition(states, state, today) {
+        let urgency = days_until(school.tuition_deadline, today);
+        let amount = format_yen(school.tuition);
+        let reason = if urgency == 0 {
+            format!(
+                "{}の授業料支払期限です（{}）。支払わないと合格取り消しになります。",
+                school.name, amount
+            )
+        } else {
+            format!(
+                "{}の授業料（{}）を支払う必要があります。入学が確定しています。",
+                school.name, amount
+            )
+        };
+        return Some(Recommendation {
+            action: Action::PayTuition(school.id),
+            reason,
+            urgency,
+        });
+    }
+    None
+}
+
+/// Recommendations for every school, most urgent first
+fn all_recommendations(states: &[SchoolState], today: u32) -> Vec<Recommendation> {
+    let mut recs: Vec<Recommendation> = states
+        .iter()
+        .filter_map(|s| recommendation_for(states, s, today))
+        .collect();
+    recs.sort_by_key(|r| r.urgency);
+    recs
+}
+
+fn top_recommendation(recs: &[Recommendation]) -> Recommendation {
+    recs.first().cloned().unwrap_or(Recommendation {
+        action: Action::DoNothing,
+        reason: "現時点で支払いが必要な学校はありません。".to_string(),
+        urgency: 999,
+    })
+}
+
+/// Passes cancelled by going from `before` to `after`
+fn state_updates(before: &[SchoolState], after: &[SchoolState]) -> Vec<serde_json::Value> {
+    before
+        .iter()
+        .zip(after)
+        .filter(|(old, new)| old.pass != new.pass)
+        .map(|(old, new)| {
+            let deadline = if old.fee_paid {
+                old.school.tuition_deadline
+            } else {
+                old.school.enrollment_fee_deadline
+            };
+            let label = if old.fee_paid {
+                "授業料"
+            } else {
+                "入学金"
+            };
+            serde_json::json!({
+                "schoolId": old.school.id,
+                "schoolName": old.school.name,
+                "oldStatus": old.pass.name(),
+                "newStatus": new.pass.name(),
+                "reason": format!("{}期限（{}）を過ぎたため取り消し", label, deadline),
+            })
+        })
+        .collect()
+}
+
+fn recommendation_result(states: &[SchoolState], today: u32) -> serde_json::Value {
+    let updated = apply_deadlines(states, today);
+    let recs = all_recommendations(&updated, today);
+    let top = top_recommendation(&recs);
+    serde_json::json!({
+        "action": top.action.to_json(),
+        "reason": top.reason,
+        "urgency": top.urgency,
+        "allRecommendations": recs.iter().map(Recommendation::to_json).collect::<Vec<_>>(),
+        "stateUpdates": state_updates(states, &updated),
+    })
+}
+
+fn apply_action(states: &mut [SchoolState], action: Action) {
+    for s in states.iter_mut() {
+        match action {
+            Action::PayEnrollmentFee(id) if s.school.id == id => s.fee_paid = true,
+            Action::PayTuition(id) if s.school.id == id => {
+                s.fee_paid = true;
+                s.tuition_paid = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn weekly_result(
+    states: Vec<SchoolState>,
+    start_day: u32,
+    days: u32,
+) -> Result<serde_json::Value, String> {
+    let start = parse_day(start_day).ok_or_else(|| format!("Invalid startDay: {}", start_day))?;
+    let day_at = |offset: u32| to_day(start + chrono::Duration::days(i64::from(offset)));
+
+    let mut recommendations = Vec::new();
+    let mut current = states.clone();
+    for offset in 0..days {
+        let today = day_at(offset);
+        let updated = apply_deadlines(&current, today);
+        let recs = all_recommendations(&updated, today);
+        let top = top_recommendation(&recs);
+        let urgency = offset;
+        recommendations.push(serde_json::json!({
+            "day": today,
+            "result": {
+                "action": top.action.to_json(),
+                "reason": top.reason,
+                "urgency": urgency,
+                "allRecommendations": recs
+                    .iter()
+                    .map(|r| Recommendation { urgency, ..r.clone() }.to_json())
+                    .collect::<Vec<_>>(),
+                "stateUpdates": state_updates(&current, &updated),
+            }
+        }));
+        current = updated;
+        apply_action(&mut current, top.action);
+    }
+
+    let end_day = day_at(days.saturating_sub(1));
+    let upcoming: Vec<serde_json::Value> = states
+        .iter()
+        .filter(|s| {
+            s.pass == Pass::NotYetAnnounced && (start_day..=end_day).contains(&s.school.result_date)
+        })
+        .map(|s| {
+            serde_json::json!({
+                "schoolId": s.school.id,
+                "schoolName": s.school.name,
+                "resultDay": s.school.result_date,
+            })
+        })
+        .collect();
+    let note = (!upcoming.is_empty()).then_some(
+        "この期間中に合格発表がある学校があります。発表結果により推奨アクションが変わる可能性があります。",
+    );
+    Ok(serde_json::json!({
+        "startDay": start_day,
+        "recommendations": recommendations,
+        "upcomingAnnouncements": upcoming,
+        "note": note,
+    }))
+}
+
+fn describe(state: &SchoolState, today: u32) -> String {
+    let name = &state.school.name;
+    match state.pass {
+        Pass::NotYetAnnounced => {
+            format!("{}: 発表待ち（発表日 {}）", name, state.school.result_date)
+        }
+        Pass::Failed => format!("{}: 不合格", name),
+        Pass::Cancelled => format!("{}: 合格取り消し", name),
+        Pass::Passed if state.tuition_paid => format!("{}: 合格・入学確定", name),
+        Pass::Passed if state.fee_paid => format!("{}: 合格・入学金支払済み", name),
+        Pass::Passed if state.school.enrollment_fee_deadline < today => {
+            format!("{}: 合格・入学金期限切れ", name)
+        }
+        Pass::Passed => format!(
+            "{}: 合格・入学金期限 {}",
+            name, state.school.enrollment_fee_deadline
+        ),
+    }
+}
+
+fn explain(
+    states: &[SchoolState],
+    action: Action,
+    today: u32,
+) -> Result<serde_json::Value, String> {
+    let states = apply_deadlines(states, today);
+    let (school_id, deadline_of): (u64, fn(&SchoolInput) -> u32) = match action {
+        Action::PayEnrollmentFee(id) => (id, |s| s.enrollment_fee_deadline),
+        Action::PayTuition(id) => (id, |s| s.tuition_deadline),
+        Action::DoNothing => {
+            let top = top_recommendation(&all_recommendations(&states, today));
+            return Ok(serde_json::json!({
+                "summary": top.reason,
+                "factors": states.iter().map(|s| describe(s, today)).collect::<Vec<_>>(),
+            }));
+        }
+    };
+    let target = states
+        .iter()
+        .find(|s| s.school.id == school_id)
+        .ok_or_else(|| format!("School not found: {}", school_id))?;
+    let summary = match recommendation_for(&states, target, today) {
+        Some(r) if r.action == action => r.reason,
+        _ => format!(
+            "{}は現時点でこの支払いの推奨対象ではありません。",
+            target.school.name
+        ),
+    };
+    let deadline = deadline_of(&target.school);
+    let mut factors = vec![
+        describe(target, today),
+        format!("期限 {}（あと{}日）", deadline, days_until(deadline, today)),
+    ];
+    if let Action::PayTuition(_) = action {
+        factors.push(
+            if target.fee_paid {
+                "入学金は支払済みです"
+            } else {
+                "入学金が未払いです"
+            }
+            .to_string(),
+        );
+    }
+    let higher = higher(&states, target);
+    if higher.is_empty() {
+        factors.push("第一志望の学校です".to_string());
+    } else {
+        factors.extend(higher.iter().map(|s| describe(s, today)));
+    }
+    Ok(serde_json::json!({ "summary": summary, "factors": factors }))
+}
+
+fn param<T: serde::de::DeserializeOwned>(
+    params: &serde_json::Value,
+    name: &str,
+) -> Result<T, String> {
+    let value = params
+        .get(name)
+        .ok_or_else(|| format!("Missing parameter: {}", name))?;
+    serde_json::from_value(value.clone()).map_err(|e| format!("Invalid {}: {}", name, e))
+}
+
+fn states_of(params: &serde_json::Value) -> Result<Vec<SchoolState>, String> {
+    let params: StatesParams = serde_json::from_value(params.clone()).map_err(|e| e.to_string())?;
+    Ok(build_states(params))
+}
+
+/// Answers advisor requests without the advisor binary
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockAdvisor;
+
+impl MockAdvisor {
+    /// Answer `request` as the advisor would
+    pub fn handle(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
+        let id = request.id.clone();
+        let params = &request.params_value();
+        let result = match request.method.as_str() {
+            "ping" => Ok(serde_json::json!("pong")),
+            "getVersion" => Ok(serde_json::json!({
+                "advisorVersion": MOCK_VERSION,
+                "schemaVersion": CURRENT_SCHEMA_VERSION,
+            })),
+            "getRecommendation" => param(params, "today")
+                .and_then(|today| Ok(recommendation_result(&states_of(params)?, today))),
+            "getWeeklyRecommendations" => param(params, "startDay").and_then(|start| {
+                let days = param(params, "days").unwrap_or(7);
+                weekly_result(states_of(params)?, start, days)
+            }),
+            "explain" => param(params, "today").and_then(|today| {
+                let action = params
+                    .get("action")
+                    .and_then(Action::from_json)
+                    .ok_or_else(|| "Invalid action".to_string())?;
+                explain(&states_of(params)?, action, today)
+            }),
+            method => {
+                return JsonRpcResponse::error(
+                    id,
+                    METHOD_NOT_FOUND,
+                    format!("Method not found: {}", method),
+                )
+            }
+        };
+        match result {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err(message) => JsonRpcResponse::error(id, INVALID_PARAMS, message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, params: serde_json::Value) -> JsonRpcRequest {
+        JsonRpcRequest::new(method, &params, serde_json::json!(1))
+    }
+
+    fn params(today_key: &str, today: u32, waseda_fee_paid: bool) -> serde_json::Value {
+        serde_json::json!({
+            today_key: today,
+            "schools": [
+                {
+                    "id": 1, "name": "東京大学", "priority": 1,
+                    "examDate": 20260225, "resultDate": 20260310,
+                    "enrollmentFeeDeadline": 20260317, "tuitionDeadline": 20260331,
+                    "enrollmentFee": 282000, "tuition": 535800
+                },
+                {
+                    "id": 2, "name": "早稲田大学", "priority": 2,
+                    "examDate": 20260215, "resultDate": 20260301,
+                    "enrollmentFeeDeadline": 20260306, "tuitionDeadline": 20260324,
+                    "enrollmentFee": 200000, "tuition": 800000
+                }
+            ],
+            "states": [
+                { "schoolId": 1, "passStatus": "notYetAnnounced" },
+                { "schoolId": 2, "passStatus": "passed", "enrollmentFeePaid": waseda_fee_paid }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_waits_until_the_deadline() {
+        let advisor = MockAdvisor;
+        let before = advisor.handle(&request(
+            "getRecommendation",
+            params("today", 20260302, false),
+        ));
+        assert_eq!(
+            before.result_value().unwrap()["action"]["type"],
+            "doNothing"
+        );
+
+        let due = advisor.handle(&request(
+            "getRecommendation",
+            params("today", 20260306, false),
+        ));
+        let result = due.result_value().unwrap();
+        assert_eq!(
+            result["action"],
+            serde_json::json!({ "type": "payEnrollmentFee", "schoolId": 2 })
+        );
+        assert_eq!(result["urgency"], 0);
+        assert!(result["reason"].as_str().unwrap().contains("¥200,000"));
+    }
+
+    #[test]
+    fn test_cancels_missed_deadlines() {
+        let response = MockAdvisor.handle(&request(
+            "getRecommendation",
+            params("today", 20260307, false),
+        ));
+        let updates = &response.result_value().unwrap()["stateUpdates"];
+        assert_eq!(updates[0]["schoolId"], 2);
+        assert_eq!(updates[0]["newStatus"], "cancelled");
+    }
+
+    #[test]
+    fn test_weekly_applies_actions() {
+        let response = MockAdvisor.handle(&request(
+            "getWeeklyRecommendations",
+            params("startDay", 20260305, false),
+        ));
+        let result = response.result_value().unwrap();
+        let days = result["recommendations"].as_array().unwrap();
+        assert_eq!(days.len(), 7);
+        assert_eq!(days[1]["day"], 20260306);
+        assert_eq!(days[1]["result"]["action"]["type"], "payEnrollmentFee");
+        // Paid on the 6th, so not cancelled on the 7th
+        assert!(days[2]["result"]["stateUpdates"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert_eq!(result["upcomingAnnouncements"][0]["resultDay"], 20260310);
+    }
+
+    #[test]
+    fn test_explain_and_errors() {
+        let mut explain = params("today", 20260320, true);
+        explain["action"] = serde_json::json!({ "type": "payTuition", "schoolId": 2 });
+        let result = MockAdvisor
+            .handle(&request("explain", explain))
+            .result_value()
+            .unwrap();
+        assert!(result["factors"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("入学金は支払済みです")));
+
+        let version = MockAdvisor.handle(&request("getVersion", serde_json::json!({})));
+        assert_eq!(
+            version.result_value().unwrap()["schemaVersion"],
+            CURRENT_SCHEMA_VERSION
+        );
+        let unknown = MockAdvisor.handle(&request("solve", serde_json::json!({})));
+        assert_eq!(unknown.error.unwrap().code, METHOD_NOT_FOUND);
+        let invalid = MockAdvisor.handle(&request("getRecommendation", serde_json::json!({})));
+        assert_eq!(invalid.error.unwrap().code, INVALID_PARAMS);
+    }
+}
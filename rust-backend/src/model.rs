@@ -0,0 +1,1089 @@
+//! Domain model shared with the frontend.
+//!
+//! These types are the contract between the Rust backend, the Lean advisor
+//! and the frontend. TypeScript definitions are generated from them into
+//! `frontend/src/types/generated/` by `cargo test` (see `.cargo/config.toml`),
+//! so the frontend cannot drift from what the backend accepts.
+//!
+//! Dates are YYYYMMDD integers, as in the Lean advisor. Amounts are in the
+//! minor unit of the school's [`Currency`], which is yen unless set.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Result status of a school's exam (Lean: PassStatus)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum PassStatus {
+    /// Older imports stored an empty string for this
+    #[default]
+    #[serde(alias = "")]
+    NotYetAnnounced,
+    Passed,
+    Failed,
+    Cancelled,
+}
+
+impl PassStatus {
+    pub const ALL: [PassStatus; 4] = [
+        Self::NotYetAnnounced,
+        Self::Passed,
+        Self::Failed,
+        Self::Cancelled,
+    ];
+
+    /// Name used in stored data and by the advisor
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NotYetAnnounced => "notYetAnnounced",
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether payments for the school may still be needed
+    pub fn is_open(self) -> bool {
+        !matches!(self, Self::Failed | Self::Cancelled)
+    }
+}
+
+/// ISO 4217 currency of a school's fees
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, TS,
+)]
+#[serde(rename_all = "UPPERCASE")]
+#[ts(export)]
+pub enum Currency {
+    #[default]
+    Jpy,
+    Usd,
+    Eur,
+    Gbp,
+    Aud,
+    Cad,
+    Nzd,
+    Chf,
+    Sgd,
+    Hkd,
+    Cny,
+    Krw,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 12] = [
+        Self::Jpy,
+        Self::Usd,
+        Self::Eur,
+        Self::Gbp,
+        Self::Aud,
+        Self::Cad,
+        Self::Nzd,
+        Self::Chf,
+        Self::Sgd,
+        Self::Hkd,
+        Self::Cny,
+        Self::Krw,
+    ];
+
+    /// ISO 4217 code
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Jpy => "JPY",
+            Self::Usd => "USD",
+            Self::Eur => "EUR",
+            Self::Gbp => "GBP",
+            Self::Aud => "AUD",
+            Self::Cad => "CAD",
+            Self::Nzd => "NZD",
+            Self::Chf => "CHF",
+            Self::Sgd => "SGD",
+            Self::Hkd => "HKD",
+            Self::Cny => "CNY",
+            Self::Krw => "KRW",
+        }
+    }
+
+    /// Look up a currency by its ISO code, case-insensitively
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Digits after the decimal point; amounts are stored in minor units
+    pub fn minor_digits(self) -> u32 {
+        match self {
+            Self::Jpy | Self::Krw => 0,
+            _ => 2,
+        }
+    }
+
+    /// Symbol shown before an amount
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Jpy => "¥",
+            Self::Usd => "US$",
+            Self::Eur => "€",
+            Self::Gbp => "£",
+            Self::Aud => "A$",
+            Self::Cad => "C$",
+            Self::Nzd => "NZ$",
+            Self::Chf => "CHF ",
+            Self::Sgd => "S$",
+            Self::Hkd => "HK$",
+            Self::Cny => "CN¥",
+            Self::Krw => "₩",
+        }
+    }
+}
+
+/// Kind of Japanese bank account (預金種目)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum AccountType {
+    /// 普通
+    #[default]
+    Ordinary,
+    /// 当座
+    Checking,
+    /// 貯蓄
+    Savings,
+}
+
+impl AccountType {
+    /// Code used in Zengin-format transfer files
+    pub fn zengin_code(self) -> char {
+        match self {
+            Self::Ordinary => '1',
+            Self::Checking => '2',
+            Self::Savings => '4',
+        }
+    }
+}
+
+/// A Japanese bank account, as needed for a bank transfer.
+///
+/// Names are in kana as registered with the bank; see
+/// [`to_bank_kana`](crate::format::to_bank_kana) for what is accepted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BankAccount {
+    /// 4-digit bank code
+    pub bank_code: String,
+    pub bank_name: String,
+    /// 3-digit branch code
+    pub branch_code: String,
+    pub branch_name: String,
+    #[serde(default)]
+    pub account_type: AccountType,
+    /// Up to 7 digits
+    pub account_number: String,
+    pub holder_name: String,
+}
+
+impl BankAccount {
+    /// The first field that a bank would reject, if any
+    pub fn invalid_field(&self) -> Option<&'static str> {
+        let digits = |s: &str, len: std::ops::RangeInclusive<usize>| {
+            len.contains(&s.len()) && s.bytes().all(|b| b.is_ascii_digit())
+        };
+        let kana = |s: &str| crate::format::to_bank_kana(s).is_ok();
+        if !digits(&self.bank_code, 4..=4) {
+            Some("bankCode")
+        } else if !kana(&self.bank_name) {
+            Some("bankName")
+        } else if !digits(&self.branch_code, 3..=3) {
+            Some("branchCode")
+        } else if !kana(&self.branch_name) {
+            Some("branchName")
+        } else if !digits(&self.account_number, 1..=7) {
+            Some("accountNumber")
+        } else if self.holder_name.trim().is_empty() || !kana(&self.holder_name) {
+            Some("holderName")
+        } else {
+            None
+        }
+    }
+}
+
+/// One payment of a tuition installment plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Installment {
+    pub due: u32,
+    #[ts(type = "number")]
+    pub amount: u64,
+    #[serde(default)]
+    pub paid: bool,
+}
+
+/// How to split tuition into installments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct InstallmentPlan {
+    #[ts(type = "number")]
+    pub total: u64,
+    /// Due date of the first payment, normally the tuition deadline
+    pub first_due: u32,
+    pub count: u32,
+    /// Months between payments
+    pub interval_months: u32,
+}
+
+impl InstallmentPlan {
+    /// Most installments a plan may have
+    pub const MAX_COUNT: u32 = 24;
+
+    /// Payments of the plan, equal but for the remainder added to the
+    /// first; `None` if the plan is empty, too long or has an invalid date.
+    ///
+    /// Later payments fall on the same day of the month as the first, or
+    /// the month's last day when it is shorter.
+    pub fn schedule(&self) -> Option<Vec<Installment>> {
+        if !(1..=Self::MAX_COUNT).contains(&self.count) {
+            return None;
+        }
+        let first = crate::date::parse_day(self.first_due)?;
+        let share = self.total / u64::from(self.count);
+        let remainder = self.total % u64::from(self.count);
+        (0..self.count)
+            .map(|i| {
+                let due =
+                    first.checked_add_months(chrono::Months::new(i * self.interval_months))?;
+                Some(Installment {
+                    due: crate::date::to_day(due),
+                    amount: if i == 0 { share + remainder } else { share },
+                    paid: false,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A fee due again at a fixed interval, such as monthly tuition or a
+/// facility fee each term
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RecurringFee {
+    /// What the fee is, such as `施設設備費`
+    pub label: String,
+    #[ts(type = "number")]
+    pub amount: u64,
+    /// Due date of the first payment
+    pub first_due: u32,
+    /// Months between payments: 1 for monthly, 3, 4 or 6 for each term
+    pub interval_months: u32,
+    /// Last day a payment may fall on; open-ended if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub until: Option<u32>,
+}
+
+impl RecurringFee {
+    /// Most payments generated from one fee
+    pub const MAX_PAYMENTS: u32 = 120;
+
+    /// Due dates of the payments falling on or before `horizon`; `None` if
+    /// the fee has no label, no interval or an invalid date.
+    ///
+    /// Payments fall on the same day of the month as the first, or the
+    /// month's last day when it is shorter, as installments do.
+    pub fn dues(&self, horizon: u32) -> Option<Vec<u32>> {
+        if self.label.trim().is_empty() || self.interval_months == 0 {
+            return None;
+        }
+        let first = crate::date::parse_day(self.first_due)?;
+        if let Some(until) = self.until {
+            crate::date::parse_day(until)?;
+        }
+        let last = self.until.map_or(horizon, |until| until.min(horizon));
+        let mut dues = Vec::new();
+        for i in 0..Self::MAX_PAYMENTS {
+            let due = first.checked_add_months(chrono::Months::new(i * self.interval_months))?;
+            let due = crate::date::to_day(due);
+            if due > last {
+                break;
+            }
+            dues.push(due);
+        }
+        Some(dues)
+    }
+}
+
+/// One payment of a recurring fee, generated from the fee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RecurringPayment {
+    /// Label of the fee it belongs to
+    pub label: String,
+    pub due: u32,
+    #[ts(type = "number")]
+    pub amount: u64,
+    #[serde(default)]
+    pub paid: bool,
+}
+
+/// Months ahead of today that recurring payments are generated for
+pub const RECURRING_HORIZON_MONTHS: u32 = 12;
+
+/// Last day recurring payments are generated for as of `today`
+pub fn recurring_horizon(today: u32) -> u32 {
+    crate::date::parse_day(today)
+        .and_then(|day| day.checked_add_months(chrono::Months::new(RECURRING_HORIZON_MONTHS)))
+        .map_or(today, crate::date::to_day)
+}
+
+/// Payments of `fees` due on or before `horizon`, by due date.
+///
+/// Whether a payment was paid is carried over from `previous` by label and
+/// due date, so changing a fee regenerates its unpaid payments only. Paid
+/// payments the fees no longer produce are kept, since they were made.
+/// Fees with an invalid rule produce no payments.
+pub fn expand_recurring(
+    fees: &[RecurringFee],
+    previous: &[RecurringPayment],
+    horizon: u32,
+) -> Vec<RecurringPayment> {
+    let was_paid = |label: &str, due: u32| {
+        previous
+            .iter()
+            .any(|p| p.paid && p.label == label && p.due == due)
+    };
+    let mut payments: Vec<RecurringPayment> = fees
+        .iter()
+        .flat_map(|fee| {
+            fee.dues(horizon)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|due| RecurringPayment {
+                    label: fee.label.clone(),
+                    due,
+                    amount: fee.amount,
+                    paid: was_paid(&fee.label, due),
+                })
+        })
+        .collect();
+    for paid in previous.iter().filter(|p| p.paid) {
+        if !payments
+            .iter()
+            .any(|p| p.label == paid.label && p.due == paid.due)
+        {
+            payments.push(paid.clone());
+        }
+    }
+    payments.sort_by(|a, b| a.due.cmp(&b.due).then_with(|| a.label.cmp(&b.label)));
+    payments
+}
+
+/// A fee due once the results are out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum FeeKind {
+    EnrollmentFee,
+    Tuition,
+}
+
+impl FeeKind {
+    /// Name used in stored data
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::EnrollmentFee => "enrollmentFee",
+            Self::Tuition => "tuition",
+        }
+    }
+}
+
+/// How a payment was made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum PaymentMethod {
+    /// 銀行振込
+    BankTransfer,
+    /// クレジットカード
+    CreditCard,
+    /// コンビニ払い
+    ConvenienceStore,
+    /// ペイジー
+    PayEasy,
+    Other,
+}
+
+impl PaymentMethod {
+    /// Name used in stored data
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::BankTransfer => "bankTransfer",
+            Self::CreditCard => "creditCard",
+            Self::ConvenienceStore => "convenienceStore",
+            Self::PayEasy => "payEasy",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// What paying by one method costs on top of the amount paid, such as a
+/// card surcharge or a convenience store handling fee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MethodFee {
+    pub method: PaymentMethod,
+    /// Charged on every payment, in yen
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub fixed: u64,
+    /// Share of the amount charged, in basis points (`150` is 1.5%)
+    #[serde(default)]
+    pub rate_bp: u32,
+    /// Most charged on one payment, in yen; uncapped if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub cap: Option<u64>,
+}
+
+impl MethodFee {
+    /// Charge for paying `amount` yen, with the share rounded half up
+    pub fn charge(&self, amount: u64) -> u64 {
+        let share = (amount as u128 * self.rate_bp as u128 + 5_000) / 10_000;
+        let charge = self
+            .fixed
+            .saturating_add(share.min(u64::MAX as u128) as u64);
+        self.cap.map_or(charge, |cap| charge.min(cap))
+    }
+}
+
+/// Charge for paying `amount` yen by `method` under `fees`; nothing for
+/// methods without a rule
+pub fn method_charge(fees: &[MethodFee], method: PaymentMethod, amount: u64) -> u64 {
+    fees.iter()
+        .find(|fee| fee.method == method)
+        .map_or(0, |fee| fee.charge(amount))
+}
+
+/// Record of a payment made, kept with the school
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Receipt {
+    pub fee: FeeKind,
+    /// 1-based installment number, for tuition paid in parts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub installment: Option<u32>,
+    pub paid_on: u32,
+    pub method: PaymentMethod,
+    /// Transfer or receipt number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub reference: Option<String>,
+    /// Link to a scan or confirmation of the payment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub attachment: Option<String>,
+}
+
+/// A free-text note on a school or one of its payments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Note {
+    pub text: String,
+    /// When the note was written (RFC 3339)
+    pub written_at: String,
+    /// Payment the note is about; about the school itself if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub fee: Option<FeeKind>,
+    /// 1-based installment number, for a note on tuition paid in parts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub installment: Option<u32>,
+}
+
+/// A scholarship or discount reducing one of a school's fees
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Adjustment {
+    /// What the reduction is, such as `特待生奨学金`
+    pub label: String,
+    pub fee: FeeKind,
+    #[ts(type = "number")]
+    pub amount: u64,
+}
+
+/// `amount` of `fee` less its adjustments, never below zero
+pub fn net_amount(amount: u64, fee: FeeKind, adjustments: &[Adjustment]) -> u64 {
+    adjustments
+        .iter()
+        .filter(|a| a.fee == fee)
+        .fold(amount, |net, a| net.saturating_sub(a.amount))
+}
+
+/// Payments of a school's tuition: its installments if it has any,
+/// otherwise `net_tuition` due on `tuition_deadline`.
+///
+/// With installments, `tuition_paid` marks the first one paid, since that
+/// is the payment securing the place.
+pub fn tuition_payments(
+    tuition_deadline: u32,
+    net_tuition: u64,
+    tuition_paid: bool,
+    installments: &[Installment],
+) -> Vec<Installment> {
+    if installments.is_empty() {
+        return vec![Installment {
+            due: tuition_deadline,
+            amount: net_tuition,
+            paid: tuition_paid,
+        }];
+    }
+    installments
+        .iter()
+        .enumerate()
+        .map(|(i, installment)| Installment {
+            paid: installment.paid || (i == 0 && tuition_paid),
+            ..*installment
+        })
+        .collect()
+}
+
+/// A school with its exam schedule, fees and payment state, as stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct School {
+    #[ts(type = "number")]
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub priority: u32,
+    pub exam_date: u32,
+    pub result_date: u32,
+    pub enrollment_fee_deadline: u32,
+    pub tuition_deadline: u32,
+    #[ts(type = "number")]
+    pub enrollment_fee: u64,
+    #[ts(type = "number")]
+    pub tuition: u64,
+    /// Exam application fee, paid by the exam date; unknown if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub application_fee: Option<u64>,
+    /// Currency of the fees, in its minor units
+    #[serde(default)]
+    pub currency: Currency,
+    #[serde(default)]
+    pub pass_status: PassStatus,
+    #[serde(default)]
+    pub enrollment_fee_paid: bool,
+    #[serde(default)]
+    pub tuition_paid: bool,
+    /// Account fees are transferred to, for bank transfer files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub payee: Option<BankAccount>,
+    /// Tuition paid in parts; the payments replace the single tuition
+    /// payment and already account for any tuition adjustments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<Installment>>", optional)]
+    pub installments: Vec<Installment>,
+    /// Scholarships and discounts taken off the fees
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<Adjustment>>", optional)]
+    pub adjustments: Vec<Adjustment>,
+    /// Child applying to the school, one of the dataset's children
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub child_id: Option<u64>,
+    /// Records of the payments made
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<Receipt>>", optional)]
+    pub receipts: Vec<Receipt>,
+    /// Fees due again and again once enrolled, such as monthly tuition
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<RecurringFee>>", optional)]
+    pub recurring_fees: Vec<RecurringFee>,
+    /// Payments generated from `recurring_fees`; see [`expand_recurring`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<RecurringPayment>>", optional)]
+    pub recurring_payments: Vec<RecurringPayment>,
+    /// On the waiting list; the pass status stays not yet announced until
+    /// the final result
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[ts(as = "Option<bool>", optional)]
+    pub waitlisted: bool,
+    /// How the school's fees will be paid, for their method charges;
+    /// recorded receipts take precedence for payments already made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub payment_method: Option<PaymentMethod>,
+    /// Notes on the school and its payments, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<Note>>", optional)]
+    pub notes: Vec<Note>,
+}
+
+impl School {
+    /// Enrollment fee after adjustments
+    pub fn net_enrollment_fee(&self) -> u64 {
+        net_amount(
+            self.enrollment_fee,
+            FeeKind::EnrollmentFee,
+            &self.adjustments,
+        )
+    }
+
+    /// Tuition after adjustments, the sum of the installments if any
+    pub fn net_tuition(&self) -> u64 {
+        if self.installments.is_empty() {
+            net_amount(self.tuition, FeeKind::Tuition, &self.adjustments)
+        } else {
+            self.installments.iter().map(|i| i.amount).sum()
+        }
+    }
+
+    /// Tuition paid to secure the place: the first installment, or all of it
+    pub fn securing_tuition(&self) -> u64 {
+        self.tuition_payments()[0].amount
+    }
+
+    /// Tuition payments; see [`tuition_payments`]
+    pub fn tuition_payments(&self) -> Vec<Installment> {
+        tuition_payments(
+            self.tuition_deadline,
+            self.net_tuition(),
+            self.tuition_paid,
+            &self.installments,
+        )
+    }
+
+    /// Regenerate `recurring_payments` from `recurring_fees` for
+    /// [`RECURRING_HORIZON_MONTHS`] from `today`. Schools that failed or
+    /// were cancelled keep only the payments already made. Returns whether
+    /// they changed.
+    pub fn regenerate_recurring(&mut self, today: u32) -> bool {
+        let horizon = recurring_horizon(today);
+        let fees: &[RecurringFee] = if self.pass_status.is_open() {
+            &self.recurring_fees
+        } else {
+            &[]
+        };
+        let payments = expand_recurring(fees, &self.recurring_payments, horizon);
+        let changed = payments != self.recurring_payments;
+        self.recurring_payments = payments;
+        changed
+    }
+}
+
+/// Spending limits for the season, in yen; absent limits are not checked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Budget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub application_fees: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub enrollment_fees: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub tuition: Option<u64>,
+}
+
+/// A child applying to schools; siblings applying in the same season
+/// share one dataset, with each school tagged by child
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Child {
+    #[ts(type = "number")]
+    pub id: u64,
+    pub name: String,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+/// The saved data file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Dataset {
+    /// Format version; missing in files written before versioning
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Raised by one on every save; see [`crate::revision`]
+    #[serde(default, skip_serializing_if = "is_zero")]
+    #[ts(as = "Option<u32>", optional)]
+    pub revision: u64,
+    #[serde(default)]
+    pub schools: Vec<School>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub budget: Option<Budget>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<Child>>", optional)]
+    pub children: Vec<Child>,
+    /// Charges of the payment methods, one rule per method
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<MethodFee>>", optional)]
+    pub method_fees: Vec<MethodFee>,
+}
+
+impl Dataset {
+    /// Read a dataset from its stored JSON form
+    pub fn from_value(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    /// The stored JSON form of this dataset
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Name of child `id`, if it is one of the dataset's children
+    pub fn child_name(&self, id: u64) -> Option<&str> {
+        self.children
+            .iter()
+            .find(|c| c.id == id)
+            .map(|c| c.name.as_str())
+    }
+
+    /// Regenerate the recurring payments of every school as of `today`.
+    /// Returns whether any changed.
+    pub fn regenerate_recurring(&mut self, today: u32) -> bool {
+        let mut changed = false;
+        for school in &mut self.schools {
+            changed |= school.regenerate_recurring(today);
+        }
+        changed
+    }
+
+    /// The dataset with only the schools of child `id`
+    pub fn for_child(&self, id: u64) -> Dataset {
+        Dataset {
+            schools: self
+                .schools
+                .iter()
+                .filter(|s| s.child_id == Some(id))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A school as sent to the advisor (Lean: SchoolInput)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SchoolInput {
+    #[ts(type = "number")]
+    pub id: u64,
+    pub name: String,
+    pub priority: u32,
+    pub exam_date: u32,
+    pub result_date: u32,
+    pub enrollment_fee_deadline: u32,
+    pub tuition_deadline: u32,
+    #[ts(type = "number")]
+    pub enrollment_fee: u64,
+    #[ts(type = "number")]
+    pub tuition: u64,
+}
+
+/// A school's payment state as sent to the advisor (Lean: StateInput)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StateInput {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub pass_status: PassStatus,
+    pub enrollment_fee_paid: bool,
+    pub tuition_paid: bool,
+}
+
+/// Kind of payment action (Lean: PaymentAction constructors)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum PaymentActionType {
+    PayEnrollmentFee,
+    PayTuition,
+    DoNothing,
+}
+
+impl PaymentActionType {
+    /// Name used by the advisor
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PayEnrollmentFee => "payEnrollmentFee",
+            Self::PayTuition => "payTuition",
+            Self::DoNothing => "doNothing",
+        }
+    }
+}
+
+/// What the advisor recommends doing (Lean: PaymentAction)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PaymentAction {
+    #[serde(rename = "type")]
+    pub action_type: PaymentActionType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub school_id: Option<u64>,
+}
+
+/// One recommended action with its reason (Lean: Recommendation)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Recommendation {
+    pub action: PaymentAction,
+    pub reason: String,
+    /// Days left until the action is due; 0 means today
+    pub urgency: u32,
+    /// Id to look up the explanation of this recommendation with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub explanation_id: Option<String>,
+}
+
+/// A status change the advisor inferred (Lean: StateUpdate)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StateUpdate {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    pub old_status: String,
+    pub new_status: String,
+    pub reason: String,
+}
+
+/// Result of `getRecommendation`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct GetRecommendationResult {
+    pub action: PaymentAction,
+    pub reason: String,
+    pub urgency: u32,
+    pub all_recommendations: Vec<Recommendation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub state_updates: Option<Vec<StateUpdate>>,
+    /// Id to look up the explanation of the top action with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub explanation_id: Option<String>,
+}
+
+/// Recommendation for one day of a weekly plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DailyRecommendation {
+    pub day: u32,
+    pub result: GetRecommendationResult,
+}
+
+/// A school whose results are due soon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UpcomingAnnouncement {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    pub result_day: u32,
+}
+
+/// Result of `getWeeklyRecommendations`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct GetWeeklyRecommendationsResult {
+    pub start_day: u32,
+    pub recommendations: Vec<DailyRecommendation>,
+    pub upcoming_announcements: Vec<UpcomingAnnouncement>,
+    pub note: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_reads_stored_json() {
+        let dataset = Dataset::from_value(serde_json::json!({
+            "schemaVersion": 2,
+            "schools": [{
+                "id": 1,
+                "name": "青葉大学",
+                "examDate": 20260201,
+                "resultDate": 20260210,
+                "enrollmentFeeDeadline": 20260215,
+                "tuitionDeadline": 20260301,
+                "enrollmentFee": 200000,
+                "tuition": 500000,
+                "passStatus": ""
+            }]
+        }))
+        .unwrap();
+
+        let school = &dataset.schools[0];
+        assert_eq!(school.pass_status, PassStatus::NotYetAnnounced);
+        assert_eq!(school.priority, 0);
+        assert!(!school.tuition_paid);
+        assert_eq!(
+            dataset.to_value()["schools"][0]["passStatus"],
+            "notYetAnnounced"
+        );
+    }
+
+    #[test]
+    fn test_recurring_fees_expand_and_keep_payments_made() {
+        let mut monthly = RecurringFee {
+            label: "授業料（月額）".to_string(),
+            amount: 50000,
+            first_due: 20260131,
+            interval_months: 1,
+            until: None,
+        };
+        assert_eq!(
+            monthly.dues(20260420),
+            Some(vec![20260131, 20260228, 20260331])
+        );
+        let term = RecurringFee {
+            label: "施設費".to_string(),
+            amount: 100000,
+            first_due: 20260410,
+            interval_months: 6,
+            until: Some(20270331),
+        };
+        assert_eq!(term.dues(20300101), Some(vec![20260410, 20261010]));
+        let broken = RecurringFee {
+            interval_months: 0,
+            ..term.clone()
+        };
+        assert_eq!(broken.dues(20300101), None);
+
+        let fees = vec![monthly.clone(), term];
+        let mut payments = expand_recurring(&fees, &[], 20260420);
+        assert_eq!(payments.len(), 4);
+        assert_eq!(payments[3].label, "施設費");
+        payments[0].paid = true;
+
+        // Raising the fee regenerates the payments not made yet
+        monthly.amount = 55000;
+        let regenerated = expand_recurring(&[monthly], &payments, 20260420);
+        let amounts: Vec<(u64, bool)> = regenerated.iter().map(|p| (p.amount, p.paid)).collect();
+        assert_eq!(amounts, vec![(55000, true), (55000, false), (55000, false)]);
+        // Dropping it keeps the payment already made
+        let dropped = expand_recurring(&[], &payments, 20260420);
+        assert_eq!(dropped, vec![payments[0].clone()]);
+    }
+
+    #[test]
+    fn test_method_fee_charge() {
+        let card = MethodFee {
+            method: PaymentMethod::CreditCard,
+            fixed: 0,
+            rate_bp: 150,
+            cap: Some(5000),
+        };
+        assert_eq!(card.charge(100_000), 1500);
+        assert_eq!(card.charge(1_000_000), 5000);
+        // Half a yen and up rounds up
+        assert_eq!(card.charge(1_000), 15);
+        assert_eq!(card.charge(100), 2);
+        let store = MethodFee {
+            method: PaymentMethod::ConvenienceStore,
+            fixed: 330,
+            rate_bp: 0,
+            cap: None,
+        };
+        let fees = [card, store];
+        assert_eq!(
+            method_charge(&fees, PaymentMethod::ConvenienceStore, 300_000),
+            330
+        );
+        assert_eq!(
+            method_charge(&fees, PaymentMethod::BankTransfer, 300_000),
+            0
+        );
+    }
+
+    #[test]
+    fn test_recommendation_matches_advisor_json() {
+        let json = serde_json::json!({
+            "action": { "type": "payTuition", "schoolId": 3 },
+            "reason": "期限が近い",
+            "urgency": 0,
+            "allRecommendations": [{
+                "action": { "type": "doNothing" },
+                "reason": "",
+                "urgency": 5
+            }]
+        });
+        let result: GetRecommendationResult = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(result.action.action_type, PaymentActionType::PayTuition);
+        assert_eq!(result.all_recommendations[0].action.school_id, None);
+        assert_eq!(serde_json::to_value(&result).unwrap(), json);
+    }
+
+    #[test]
+    fn test_currency_codes() {
+        for currency in Currency::ALL {
+            assert_eq!(serde_json::to_value(currency).unwrap(), currency.code());
+        }
+        assert_eq!(Currency::from_code("usd"), Some(Currency::Usd));
+        assert_eq!(Currency::from_code("XYZ"), None);
+    }
+
+    #[test]
+    fn test_bank_account_fields() {
+        let mut account = BankAccount {
+            bank_code: "0001".to_string(),
+            bank_name: "ミズホ".to_string(),
+            branch_code: "001".to_string(),
+            branch_name: "トウキヨウ".to_string(),
+            account_type: AccountType::Ordinary,
+            account_number: "1234567".to_string(),
+            holder_name: "早稲田大学".to_string(),
+        };
+        assert_eq!(account.invalid_field(), Some("holderName"));
+        account.holder_name = "ガク)ワセダダイガク".to_string();
+        assert_eq!(account.invalid_field(), None);
+        account.bank_code = "1".to_string();
+        assert_eq!(account.invalid_field(), Some("bankCode"));
+    }
+
+    #[test]
+    fn test_pass_status_names() {
+        for status in PassStatus::ALL {
+            assert_eq!(serde_json::to_value(status).unwrap(), status.as_str());
+        }
+    }
+}
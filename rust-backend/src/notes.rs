@@ -0,0 +1,189 @@
+//! Notes kept with schools and their payments.
+//!
+//! Each [`Note`] records what was heard or decided about a school, or about
+//! one of its payments, and when. Notes are only ever added or removed, so
+//! a school's notes read as a log of how things stood over the season.
+//! They are found again with [`crate::search`].
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::model::{Dataset, FeeKind, Note};
+
+/// Longest note accepted, in characters
+pub const MAX_NOTE_CHARS: usize = 2000;
+
+/// Errors that can occur when adding or removing a note
+#[derive(Debug, Error)]
+pub enum NoteError {
+    #[error("School not found: {0}")]
+    UnknownSchool(u64),
+
+    #[error("School {school_id} has no installment {installment}")]
+    UnknownInstallment { school_id: u64, installment: u32 },
+
+    #[error("School {school_id} has no note {index}")]
+    UnknownNote { school_id: u64, index: usize },
+
+    #[error("The note is empty")]
+    Empty,
+
+    #[error("Notes are limited to {MAX_NOTE_CHARS} characters")]
+    TooLong,
+}
+
+/// A note to add to a school
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct NewNote {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub text: String,
+    /// Payment the note is about; about the school itself if absent
+    #[serde(default)]
+    #[ts(optional)]
+    pub fee: Option<FeeKind>,
+    /// 1-based installment number, for tuition paid in parts
+    #[serde(default)]
+    #[ts(optional)]
+    pub installment: Option<u32>,
+}
+
+/// Add `note` to its school as written at `now`, returning its position
+/// in the school's notes
+pub fn add(
+    dataset: &mut Dataset,
+    note: NewNote,
+    now: DateTime<FixedOffset>,
+) -> Result<usize, NoteError> {
+    let text = note.text.trim();
+    if text.is_empty() {
+        return Err(NoteError::Empty);
+    }
+    if text.chars().count() > MAX_NOTE_CHARS {
+        return Err(NoteError::TooLong);
+    }
+    let school = dataset
+        .schools
+        .iter_mut()
+        .find(|s| s.id == note.school_id)
+        .ok_or(NoteError::UnknownSchool(note.school_id))?;
+
+    // An installment only makes sense for tuition paid in parts
+    let installment = match (note.fee, note.installment) {
+        (Some(FeeKind::Tuition), Some(number)) => {
+            if number == 0 || number as usize > school.installments.len() {
+                return Err(NoteError::UnknownInstallment {
+                    school_id: school.id,
+                    installment: number,
+                });
+            }
+            Some(number)
+        }
+        _ => None,
+    };
+    school.notes.push(Note {
+        text: text.to_string(),
+        written_at: now.to_rfc3339(),
+        fee: note.fee,
+        installment,
+    });
+    Ok(school.notes.len() - 1)
+}
+
+/// Remove the note at `index` of school `school_id`, returning it
+pub fn remove(dataset: &mut Dataset, school_id: u64, index: usize) -> Result<Note, NoteError> {
+    let school = dataset
+        .schools
+        .iter_mut()
+        .find(|s| s.id == school_id)
+        .ok_or(NoteError::UnknownSchool(school_id))?;
+    if index >= school.notes.len() {
+        return Err(NoteError::UnknownNote { school_id, index });
+    }
+    Ok(school.notes.remove(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset() -> Dataset {
+        Dataset::from_value(serde_json::json!({
+            "schools": [{
+                "id": 1,
+                "name": "東京大学",
+                "examDate": 20260225,
+                "resultDate": 20260310,
+                "enrollmentFeeDeadline": 20260315,
+                "tuitionDeadline": 20260331,
+                "enrollmentFee": 282000,
+                "tuition": 535800,
+                "installments": [
+                    { "due": 20260331, "amount": 267900 },
+                    { "due": 20260930, "amount": 267900 }
+                ]
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn note(text: &str, fee: Option<FeeKind>, installment: Option<u32>) -> NewNote {
+        NewNote {
+            school_id: 1,
+            text: text.to_string(),
+            fee,
+            installment,
+        }
+    }
+
+    #[test]
+    fn test_add_and_remove_notes() {
+        let mut dataset = dataset();
+        let now = DateTime::parse_from_rfc3339("2026-03-01T10:00:00+09:00").unwrap();
+
+        let index = add(
+            &mut dataset,
+            note(" 説明会で寮の話を聞いた ", None, None),
+            now,
+        )
+        .unwrap();
+        assert_eq!(index, 0);
+        let fee = Some(FeeKind::Tuition);
+        assert_eq!(
+            add(&mut dataset, note("後期分は口座振替", fee, Some(2)), now).unwrap(),
+            1
+        );
+
+        let notes = &dataset.schools[0].notes;
+        assert_eq!(notes[0].text, "説明会で寮の話を聞いた");
+        assert_eq!(notes[0].written_at, "2026-03-01T10:00:00+09:00");
+        assert_eq!(notes[1].installment, Some(2));
+
+        assert!(matches!(
+            add(&mut dataset, note("  ", None, None), now),
+            Err(NoteError::Empty)
+        ));
+        assert!(matches!(
+            add(&mut dataset, note("第3回", fee, Some(3)), now),
+            Err(NoteError::UnknownInstallment { installment: 3, .. })
+        ));
+        let mut other = note("別の学校", None, None);
+        other.school_id = 9;
+        assert!(matches!(
+            add(&mut dataset, other, now),
+            Err(NoteError::UnknownSchool(9))
+        ));
+
+        let removed = remove(&mut dataset, 1, 0).unwrap();
+        assert_eq!(removed.text, "説明会で寮の話を聞いた");
+        assert_eq!(dataset.schools[0].notes[0].text, "後期分は口座振替");
+        assert!(matches!(
+            remove(&mut dataset, 1, 1),
+            Err(NoteError::UnknownNote { index: 1, .. })
+        ));
+    }
+}
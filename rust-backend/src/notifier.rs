@@ -0,0 +1,388 @@
+//! Chat notifications (LINE Notify, Slack, Discord).
+//!
+//! Each service implements [`Notifier`]. Targets are configured under
+//! `[[notifiers]]` in `config.toml` with a per-target event filter, and
+//! receive the same events as webhooks rendered as a short Japanese message.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::date::format_day_with_weekday;
+use crate::format::format_yen;
+use crate::model::PaymentActionType;
+use crate::summary::PaymentCategory;
+use crate::webhook::{WebhookEvent, WebhookEventKind};
+
+/// LINE Notify API endpoint
+const LINE_NOTIFY_URL: &str = "https://notify-api.line.me/api/notify";
+
+/// Errors that can occur when sending a chat notification
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Notification rejected with status {0}")]
+    Status(u16),
+}
+
+/// A chat service that can deliver a text message
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Service name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Deliver a message
+    async fn send(&self, message: &str) -> Result<(), NotifyError>;
+}
+
+/// Check a response status, turning non-2xx into an error
+fn check_status(response: reqwest::Response) -> Result<(), NotifyError> {
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(NotifyError::Status(response.status().as_u16()))
+    }
+}
+
+/// LINE Notify personal or group token
+pub struct LineNotifier {
+    client: reqwest::Client,
+    token: String,
+}
+
+#[async_trait]
+impl Notifier for LineNotifier {
+    fn name(&self) -> &'static str {
+        "LINE"
+    }
+
+    async fn send(&self, message: &str) -> Result<(), NotifyError> {
+        let response = self
+            .client
+            .post(LINE_NOTIFY_URL)
+            .bearer_auth(&self.token)
+            .form(&[("message", message)])
+            .send()
+            .await?;
+        check_status(response)
+    }
+}
+
+/// Slack incoming webhook
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "Slack"
+    }
+
+    async fn send(&self, message: &str) -> Result<(), NotifyError> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&serde_json::json!({ "text": message }))
+            .send()
+            .await?;
+        check_status(response)
+    }
+}
+
+/// Discord channel webhook
+pub struct DiscordNotifier {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "Discord"
+    }
+
+    async fn send(&self, message: &str) -> Result<(), NotifyError> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&serde_json::json!({ "content": message }))
+            .send()
+            .await?;
+        check_status(response)
+    }
+}
+
+/// Which chat service a target uses
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ChatService {
+    Line { token: String },
+    Slack { webhook_url: String },
+    Discord { webhook_url: String },
+}
+
+/// A single chat notification target
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotifierConfig {
+    #[serde(flatten)]
+    pub service: ChatService,
+    /// Events to deliver; empty means all events
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+}
+
+impl NotifierConfig {
+    /// Build the notifier for this target
+    pub fn build(&self, client: reqwest::Client) -> Arc<dyn Notifier> {
+        match &self.service {
+            ChatService::Line { token } => Arc::new(LineNotifier {
+                client,
+                token: token.clone(),
+            }),
+            ChatService::Slack { webhook_url } => Arc::new(SlackNotifier {
+                client,
+                webhook_url: webhook_url.clone(),
+            }),
+            ChatService::Discord { webhook_url } => Arc::new(DiscordNotifier {
+                client,
+                webhook_url: webhook_url.clone(),
+            }),
+        }
+    }
+}
+
+/// Render an event as a human-readable chat message
+pub fn render_message(event: &WebhookEvent) -> String {
+    match event {
+        WebhookEvent::DeadlineApproaching(deadline) => {
+            let kind = deadline.fee_name();
+            let remaining = if deadline.days_left == 0 {
+                "本日期限".to_string()
+            } else {
+                format!("あと{}日", deadline.days_left)
+            };
+            let mut message = format!(
+                "【支払い期限】{} {} {} の期限は {} です（{}）",
+                deadline.school_name,
+                kind,
+                format_yen(deadline.amount),
+                format_day_with_weekday(deadline.deadline),
+                remaining
+            );
+            if deadline.pay_by != 0 && deadline.pay_by < deadline.deadline {
+                message.push_str(&format!(
+                    "。銀行振込は {} までに",
+                    format_day_with_weekday(deadline.pay_by)
+                ));
+            }
+            message
+        }
+        WebhookEvent::RecommendationChanged { current, .. } => {
+            let action = match current.get("type").and_then(|t| t.as_str()) {
+                Some("payEnrollmentFee") => "入学金を支払う",
+                Some("payTuition") => "授業料を支払う",
+                _ => "何もしない",
+            };
+            match current.get("schoolId").and_then(|id| id.as_u64()) {
+                Some(id) => format!("【推奨アクション変更】学校ID {} の{}", id, action),
+                None => format!("【推奨アクション変更】{}", action),
+            }
+        }
+        WebhookEvent::ReplCrashed { reason } => {
+            format!("【アドバイザー停止】{}", reason)
+        }
+        WebhookEvent::BudgetExceeded(alert) => {
+            let kind = match alert.action_type {
+                PaymentActionType::PayEnrollmentFee => "入学金",
+                _ => "授業料",
+            };
+            let budget = match alert.category {
+                Some(PaymentCategory::ApplicationFee) => "受験料の予算",
+                Some(PaymentCategory::EnrollmentFee) => "入学金の予算",
+                Some(PaymentCategory::Tuition) => "授業料の予算",
+                Some(PaymentCategory::Recurring) => "定期納付金の予算",
+                None => "全体の予算",
+            };
+            format!(
+                "【予算超過】{} {} {} を支払うと{} {} を {} 超えます",
+                alert.school_name,
+                kind,
+                format_yen(alert.amount),
+                budget,
+                format_yen(alert.limit),
+                format_yen(alert.excess())
+            )
+        }
+        WebhookEvent::BackupFailing {
+            last_success,
+            days,
+            error,
+            ..
+        } => {
+            let last = match last_success.as_deref().map(chrono::DateTime::parse_from_rfc3339) {
+                Some(Ok(time)) => time.format("%Y-%m-%d %H:%M").to_string(),
+                _ => "なし".to_string(),
+            };
+            format!(
+                "【バックアップ失敗】{}日間クラウドバックアップに失敗しています（最終成功: {}）: {}",
+                days, last, error
+            )
+        }
+    }
+}
+
+/// Routes events to configured chat notifiers
+#[derive(Default)]
+pub struct NotifierSet {
+    targets: Vec<(Arc<dyn Notifier>, Vec<WebhookEventKind>)>,
+}
+
+impl NotifierSet {
+    /// Build notifiers for the given targets
+    pub fn new(configs: &[NotifierConfig]) -> Self {
+        let client = reqwest::Client::new();
+        Self {
+            targets: configs
+                .iter()
+                .map(|c| (c.build(client.clone()), c.events.clone()))
+                .collect(),
+        }
+    }
+
+    /// Check whether any targets are configured
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Send an event to every matching notifier in the background
+    pub fn dispatch(&self, event: &WebhookEvent) {
+        let kind = event.kind();
+        let mut message = None;
+        for (notifier, events) in &self.targets {
+            if !events.is_empty() && !events.contains(&kind) {
+                continue;
+            }
+            let message = message.get_or_insert_with(|| render_message(event)).clone();
+            let notifier = notifier.clone();
+            tokio::spawn(async move {
+                if let Err(e) = notifier.send(&message).await {
+                    tracing::warn!("{} notification failed: {}", notifier.name(), e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::BudgetAlert;
+    use crate::reminders::{DeadlineKind, UpcomingDeadline};
+
+    #[test]
+    fn test_parse_config() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            notifiers: Vec<NotifierConfig>,
+        }
+
+        let wrapper: Wrapper = toml::from_str(
+            r#"
+            [[notifiers]]
+            kind = "line"
+            token = "abc"
+            events = ["deadlineApproaching"]
+
+            [[notifiers]]
+            kind = "slack"
+            webhook_url = "https://hooks.slack.com/services/x"
+
+            [[notifiers]]
+            kind = "discord"
+            webhook_url = "https://discord.com/api/webhooks/x"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(wrapper.notifiers.len(), 3);
+        assert!(matches!(
+            wrapper.notifiers[0].service,
+            ChatService::Line { .. }
+        ));
+        assert_eq!(
+            wrapper.notifiers[0].events,
+            vec![WebhookEventKind::DeadlineApproaching]
+        );
+        assert!(matches!(
+            wrapper.notifiers[2].service,
+            ChatService::Discord { .. }
+        ));
+
+        let set = NotifierSet::new(&wrapper.notifiers);
+        let names: Vec<_> = set.targets.iter().map(|(n, _)| n.name()).collect();
+        assert_eq!(names, vec!["LINE", "Slack", "Discord"]);
+    }
+
+    #[test]
+    fn test_render_deadline() {
+        let message = render_message(&WebhookEvent::DeadlineApproaching(UpcomingDeadline {
+            school_id: 2,
+            school_name: "早稲田大学".to_string(),
+            kind: DeadlineKind::EnrollmentFee,
+            deadline: 20260306,
+            amount: 200000,
+            days_left: 0,
+            pay_by: 20260306,
+            label: None,
+        }));
+        assert_eq!(
+            message,
+            "【支払い期限】早稲田大学 入学金 ¥200,000 の期限は 2026/03/06(金) です（本日期限）"
+        );
+
+        // Due on a holiday, so transfers have to be made the day before
+        let message = render_message(&WebhookEvent::DeadlineApproaching(UpcomingDeadline {
+            school_id: 2,
+            school_name: "早稲田大学".to_string(),
+            kind: DeadlineKind::Tuition,
+            deadline: 20260320,
+            amount: 800000,
+            days_left: 3,
+            pay_by: 20260319,
+            label: None,
+        }));
+        assert!(message.ends_with("（あと3日）。銀行振込は 2026/03/19(木) までに"));
+    }
+
+    #[test]
+    fn test_render_recommendation_changed() {
+        let message = render_message(&WebhookEvent::RecommendationChanged {
+            previous: serde_json::json!({"type": "doNothing"}),
+            current: serde_json::json!({"type": "payTuition", "schoolId": 1}),
+        });
+        assert_eq!(message, "【推奨アクション変更】学校ID 1 の授業料を支払う");
+    }
+
+    #[test]
+    fn test_render_budget_exceeded() {
+        let message = render_message(&WebhookEvent::BudgetExceeded(BudgetAlert {
+            category: Some(PaymentCategory::Tuition),
+            limit: 1000000,
+            committed: 500000,
+            school_id: 2,
+            school_name: "早稲田大学".to_string(),
+            action_type: PaymentActionType::PayTuition,
+            amount: 800000,
+        }));
+        assert_eq!(
+            message,
+            "【予算超過】早稲田大学 授業料 ¥800,000 を支払うと授業料の予算 ¥1,000,000 を ¥300,000 超えます"
+        );
+    }
+}
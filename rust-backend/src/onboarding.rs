@@ -0,0 +1,210 @@
+//! First-run setup progress.
+//!
+//! The setup wizard walks through the [`OnboardingStep`]s in order. Each
+//! completed step is saved in [`ONBOARDING_FILE`], so the wizard resumes
+//! where it left off after a restart, in the desktop app and in a browser
+//! alike. Completing a step again changes nothing; completing one before
+//! the steps ahead of it is refused.
+
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::storage::{Storage, StorageError};
+
+/// File holding the setup progress
+pub const ONBOARDING_FILE: &str = "onboarding.json";
+
+/// Errors that can occur while completing a setup step
+#[derive(Debug, Error)]
+pub enum OnboardingError {
+    #[error("Complete the {0} step first")]
+    OutOfOrder(OnboardingStep),
+}
+
+/// Setup steps, in the order the wizard shows them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum OnboardingStep {
+    /// A UI language was chosen
+    Locale,
+    /// Data was created, imported or started from a sample
+    Data,
+    /// Reminder timing was reviewed
+    Reminders,
+    /// The advisor answered a request
+    Advisor,
+}
+
+impl OnboardingStep {
+    pub const ORDER: [OnboardingStep; 4] = [
+        OnboardingStep::Locale,
+        OnboardingStep::Data,
+        OnboardingStep::Reminders,
+        OnboardingStep::Advisor,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Locale => "locale",
+            Self::Data => "data",
+            Self::Reminders => "reminders",
+            Self::Advisor => "advisor",
+        }
+    }
+}
+
+impl fmt::Display for OnboardingStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A step and when it was completed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CompletedStep {
+    pub step: OnboardingStep,
+    /// RFC 3339 time
+    pub completed_at: String,
+}
+
+/// How far the setup wizard has got
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export)]
+pub struct OnboardingState {
+    /// Steps done, in the order they were completed
+    pub completed: Vec<CompletedStep>,
+    /// Step to show next; `None` once setup is finished
+    pub next: Option<OnboardingStep>,
+}
+
+impl Default for OnboardingState {
+    fn default() -> Self {
+        Self {
+            completed: Vec::new(),
+            next: Some(OnboardingStep::ORDER[0]),
+        }
+    }
+}
+
+impl OnboardingState {
+    /// Load the saved progress, or a fresh start
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        let mut state: Self = match storage.load(ONBOARDING_FILE)? {
+            Some(value) => serde_json::from_value(value)?,
+            None => Self::default(),
+        };
+        state.next = state.first_missing();
+        Ok(state)
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(ONBOARDING_FILE, &serde_json::to_value(self)?)
+    }
+
+    pub fn is_done(&self, step: OnboardingStep) -> bool {
+        self.completed.iter().any(|c| c.step == step)
+    }
+
+    /// Whether every step is done
+    pub fn is_finished(&self) -> bool {
+        self.next.is_none()
+    }
+
+    fn first_missing(&self) -> Option<OnboardingStep> {
+        OnboardingStep::ORDER
+            .into_iter()
+            .find(|step| !self.is_done(*step))
+    }
+
+    /// Mark `step` done at `now`, returning whether it was not done before
+    pub fn complete(
+        &mut self,
+        step: OnboardingStep,
+        now: DateTime<FixedOffset>,
+    ) -> Result<bool, OnboardingError> {
+        if self.is_done(step) {
+            return Ok(false);
+        }
+        if let Some(missing) = OnboardingStep::ORDER
+            .into_iter()
+            .take_while(|s| *s != step)
+            .find(|s| !self.is_done(*s))
+        {
+            return Err(OnboardingError::OutOfOrder(missing));
+        }
+        self.completed.push(CompletedStep {
+            step,
+            completed_at: now.to_rfc3339(),
+        });
+        self.next = self.first_missing();
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn at(time: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(time).unwrap()
+    }
+
+    #[test]
+    fn test_steps_complete_in_order() {
+        let mut state = OnboardingState::default();
+        assert_eq!(state.next, Some(OnboardingStep::Locale));
+
+        let err = state
+            .complete(OnboardingStep::Reminders, at("2026-04-01T09:00:00+09:00"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OnboardingError::OutOfOrder(OnboardingStep::Locale)
+        ));
+
+        for step in OnboardingStep::ORDER {
+            let now = at("2026-04-01T09:00:00+09:00");
+            assert!(state.complete(step, now).unwrap());
+        }
+        assert!(state.is_finished());
+
+        // Completing a step again keeps the first time
+        let later = at("2026-04-02T09:00:00+09:00");
+        assert!(!state.complete(OnboardingStep::Data, later).unwrap());
+        assert_eq!(state.completed.len(), 4);
+        assert_eq!(state.completed[1].completed_at, "2026-04-01T09:00:00+09:00");
+    }
+
+    #[test]
+    fn test_progress_survives_restart() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        assert_eq!(
+            OnboardingState::load(&storage).unwrap(),
+            OnboardingState::default()
+        );
+
+        let mut state = OnboardingState::default();
+        let now = at("2026-04-01T09:00:00+09:00");
+        state.complete(OnboardingStep::Locale, now).unwrap();
+        state.complete(OnboardingStep::Data, now).unwrap();
+        state.save(&storage).unwrap();
+
+        let loaded = OnboardingState::load(&storage).unwrap();
+        assert_eq!(loaded.next, Some(OnboardingStep::Reminders));
+        assert!(loaded.is_done(OnboardingStep::Data));
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap()["completed"][0]["step"],
+            "locale"
+        );
+    }
+}
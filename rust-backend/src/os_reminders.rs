@@ -0,0 +1,493 @@
+//! Deadline reminders scheduled with the operating system.
+//!
+//! The reminder scheduler only runs while the app (or its tray icon) is
+//! running. For deadlines that must not be missed, [`register`] also hands
+//! one reminder per unpaid payment to the OS scheduler — a Task Scheduler
+//! task on Windows, a launchd agent on macOS, or a systemd user timer on
+//! Linux — which shows a native notification with no app process involved.
+//!
+//! Registered task names are kept in [`OS_REMINDERS_FILE`] so [`unregister`]
+//! can remove exactly what was added.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::date::{parse_day, to_day};
+use crate::notifier::render_message;
+use crate::reminders::{self, DeadlineKind, UpcomingDeadline};
+use crate::storage::{Storage, StorageError};
+use crate::webhook::WebhookEvent;
+
+/// File in the data directory listing the registered tasks
+pub const OS_REMINDERS_FILE: &str = "os-reminders.json";
+
+/// Prefix of every task name, so ours are recognizable in the OS tools
+pub const TASK_PREFIX: &str = "school-payment-reminder";
+
+/// Local time of day reminders fire at
+const REMINDER_HOUR: u32 = 9;
+
+/// Notification title
+const REMINDER_TITLE: &str = "支払い期限が近づいています";
+
+/// Folder grouping the tasks in the Windows Task Scheduler
+const WINDOWS_TASK_FOLDER: &str = "\\SchoolPayment\\";
+
+/// Errors that can occur while scheduling OS reminders
+#[derive(Debug, Error)]
+pub enum OsReminderError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error("{program} failed: {output}")]
+    Command { program: String, output: String },
+
+    #[error("Scheduled reminders are not supported on this platform")]
+    Unsupported,
+}
+
+/// OS scheduler a reminder is registered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux,
+}
+
+impl Platform {
+    /// Scheduler of the running OS
+    pub fn current() -> Option<Self> {
+        match std::env::consts::OS {
+            "windows" => Some(Self::Windows),
+            "macos" => Some(Self::MacOs),
+            "linux" => Some(Self::Linux),
+            _ => None,
+        }
+    }
+}
+
+/// One reminder to hand to the OS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReminderTask {
+    /// Task name, unique per school and payment
+    pub name: String,
+    /// YYYYMMDD day the reminder fires, at [`REMINDER_HOUR`]
+    pub fire_day: u32,
+    pub title: String,
+    pub body: String,
+}
+
+/// Tasks registered by the last [`register`], as saved in [`OS_REMINDERS_FILE`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredReminders {
+    pub platform: Option<Platform>,
+    pub tasks: Vec<String>,
+}
+
+fn task_name(deadline: &UpcomingDeadline) -> String {
+    let kind = match deadline.kind {
+        DeadlineKind::EnrollmentFee => "enrollment-fee",
+        DeadlineKind::Tuition => "tuition",
+        // One task per payment, as a fee falls due again and again
+        DeadlineKind::Recurring => {
+            return format!("{}-{}-recurring-{}", TASK_PREFIX, deadline.school_id, deadline.deadline)
+        }
+    };
+    format!("{}-{}-{}", TASK_PREFIX, deadline.school_id, kind)
+}
+
+/// Reminders for the unpaid deadlines in `data`.
+///
+/// Each fires `lead_days` before the last day banks are open on or before
+/// its deadline, or tomorrow if that has already passed; today is left to
+/// the running app. Deadlines due today get no task.
+pub fn reminder_tasks(data: &serde_json::Value, today: u32, lead_days: u32) -> Vec<ReminderTask> {
+    let Some(tomorrow) = parse_day(today).and_then(|d| d.checked_add_days(Days::new(1))) else {
+        return Vec::new();
+    };
+    reminders::upcoming_deadlines(data, today, i64::MAX)
+        .into_iter()
+        .filter_map(|mut deadline| {
+            let due = parse_day(deadline.deadline)?;
+            let pay_by = parse_day(deadline.pay_by).unwrap_or(due);
+            let fire = pay_by
+                .checked_sub_days(Days::new(u64::from(lead_days)))?
+                .max(tomorrow);
+            if fire > due {
+                return None;
+            }
+            deadline.days_left = (due - fire).num_days();
+            Some(ReminderTask {
+                name: task_name(&deadline),
+                fire_day: to_day(fire),
+                title: REMINDER_TITLE.to_string(),
+                body: render_message(&WebhookEvent::DeadlineApproaching(deadline)),
+            })
+        })
+        .collect()
+}
+
+fn fire_date(task: &ReminderTask) -> NaiveDate {
+    parse_day(task.fire_day).unwrap_or_default()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quote a systemd `ExecStart=` argument
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+/// systemd user service and timer units for a task
+pub fn systemd_units(task: &ReminderTask) -> (String, String) {
+    let service = format!(
+        "[Unit]\nDescription=School payment reminder\n\n\
+         [Service]\nType=oneshot\nExecStart=/usr/bin/env notify-send --urgency=critical {} {}\n",
+        systemd_quote(&task.title),
+        systemd_quote(&task.body)
+    );
+    let timer = format!(
+        "[Unit]\nDescription=School payment reminder\n\n\
+         [Timer]\nOnCalendar={} {:02}:00:00\nPersistent=true\n\n\
+         [Install]\nWantedBy=timers.target\n",
+        fire_date(task).format("%Y-%m-%d"),
+        REMINDER_HOUR
+    );
+    (service, timer)
+}
+
+/// launchd agent property list for a task.
+///
+/// launchd calendar intervals have no year, so the agent would fire again
+/// a year later; re-registering or unregistering removes it before then.
+pub fn launchd_plist(task: &ReminderTask) -> String {
+    let applescript_quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let script = format!(
+        "display notification \"{}\" with title \"{}\" sound name \"default\"",
+        applescript_quote(&task.body),
+        applescript_quote(&task.title)
+    );
+    let date = fire_date(task);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>/usr/bin/osascript</string>
+        <string>-e</string>
+        <string>{}</string>
+    </array>
+    <key>StartCalendarInterval</key>
+    <dict>
+        <key>Month</key>
+        <integer>{}</integer>
+        <key>Day</key>
+        <integer>{}</integer>
+        <key>Hour</key>
+        <integer>{}</integer>
+        <key>Minute</key>
+        <integer>0</integer>
+    </dict>
+</dict>
+</plist>
+"#,
+        xml_escape(&task.name),
+        xml_escape(&script),
+        date.format("%-m"),
+        date.format("%-d"),
+        REMINDER_HOUR
+    )
+}
+
+/// Task Scheduler definition for a task
+pub fn windows_task_xml(task: &ReminderTask) -> String {
+    // PowerShell string literal, with `"` escaped for the command line
+    let ps_quote = |s: &str| format!("'{}'", s.replace('\'', "''").replace('"', "\\\""));
+    let command = format!(
+        "Add-Type -AssemblyName System.Windows.Forms; \
+         [System.Windows.Forms.MessageBox]::Show({}, {}) | Out-Null",
+        ps_quote(&task.body),
+        ps_quote(&task.title)
+    );
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <Triggers>
+    <TimeTrigger>
+      <StartBoundary>{}T{:02}:00:00</StartBoundary>
+      <Enabled>true</Enabled>
+    </TimeTrigger>
+  </Triggers>
+  <Settings>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+  </Settings>
+  <Actions>
+    <Exec>
+      <Command>powershell.exe</Command>
+      <Arguments>-NoProfile -WindowStyle Hidden -Command "{}"</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        fire_date(task).format("%Y-%m-%d"),
+        REMINDER_HOUR,
+        xml_escape(&command)
+    )
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), OsReminderError> {
+    let output = Command::new(program).args(args).output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(OsReminderError::Command {
+        program: program.to_string(),
+        output: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    })
+}
+
+fn home_dir() -> Result<PathBuf, OsReminderError> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or(OsReminderError::Unsupported)
+}
+
+fn systemd_dir() -> Result<PathBuf, OsReminderError> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(config) => Ok(PathBuf::from(config).join("systemd/user")),
+        None => Ok(home_dir()?.join(".config/systemd/user")),
+    }
+}
+
+fn launch_agent_path(name: &str) -> Result<PathBuf, OsReminderError> {
+    Ok(home_dir()?
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", name)))
+}
+
+fn install(platform: Platform, task: &ReminderTask) -> Result<(), OsReminderError> {
+    match platform {
+        Platform::Linux => {
+            let dir = systemd_dir()?;
+            std::fs::create_dir_all(&dir)?;
+            let (service, timer) = systemd_units(task);
+            std::fs::write(dir.join(format!("{}.service", task.name)), service)?;
+            std::fs::write(dir.join(format!("{}.timer", task.name)), timer)?;
+            run("systemctl", &["--user", "daemon-reload"])?;
+            run(
+                "systemctl",
+                &["--user", "enable", "--now", &format!("{}.timer", task.name)],
+            )
+        }
+        Platform::MacOs => {
+            let path = launch_agent_path(&task.name)?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, launchd_plist(task))?;
+            run("launchctl", &["load", "-w", &path.to_string_lossy()])
+        }
+        Platform::Windows => {
+            let path = std::env::temp_dir().join(format!("{}.xml", task.name));
+            // schtasks only reliably reads UTF-16 with a byte order mark
+            let xml: Vec<u8> = [0xFEFF_u16]
+                .into_iter()
+                .chain(windows_task_xml(task).encode_utf16())
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            std::fs::write(&path, xml)?;
+            let result = run(
+                "schtasks",
+                &[
+                    "/Create",
+                    "/F",
+                    "/TN",
+                    &format!("{}{}", WINDOWS_TASK_FOLDER, task.name),
+                    "/XML",
+                    &path.to_string_lossy(),
+                ],
+            );
+            let _ = std::fs::remove_file(&path);
+            result
+        }
+    }
+}
+
+fn uninstall(platform: Platform, name: &str) -> Result<(), OsReminderError> {
+    match platform {
+        Platform::Linux => {
+            let timer = format!("{}.timer", name);
+            // The timer may already have been removed by hand
+            let _ = run("systemctl", &["--user", "disable", "--now", &timer]);
+            let dir = systemd_dir()?;
+            for unit in [timer, format!("{}.service", name)] {
+                remove_if_exists(&dir.join(unit))?;
+            }
+            run("systemctl", &["--user", "daemon-reload"])
+        }
+        Platform::MacOs => {
+            let path = launch_agent_path(name)?;
+            if path.exists() {
+                let _ = run("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+            }
+            remove_if_exists(&path)
+        }
+        Platform::Windows => {
+            let task = format!("{}{}", WINDOWS_TASK_FOLDER, name);
+            // Missing tasks are not an error; they may have been deleted by hand
+            let _ = run("schtasks", &["/Delete", "/F", "/TN", &task]);
+            Ok(())
+        }
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), OsReminderError> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Tasks recorded by the last registration
+pub fn registered(storage: &Storage) -> Result<RegisteredReminders, OsReminderError> {
+    Ok(storage
+        .load(OS_REMINDERS_FILE)?
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default())
+}
+
+/// Remove every task added by [`register`] and return how many there were
+pub fn unregister(storage: &Storage) -> Result<usize, OsReminderError> {
+    let previous = registered(storage)?;
+    if let Some(platform) = previous.platform {
+        for name in &previous.tasks {
+            uninstall(platform, name)?;
+        }
+    }
+    storage.delete(OS_REMINDERS_FILE)?;
+    Ok(previous.tasks.len())
+}
+
+/// Replace the registered tasks with `tasks`.
+///
+/// Tasks are recorded as they are installed, so a failure part-way leaves
+/// nothing that [`unregister`] does not know about.
+pub fn register(storage: &Storage, tasks: &[ReminderTask]) -> Result<usize, OsReminderError> {
+    let platform = Platform::current().ok_or(OsReminderError::Unsupported)?;
+    unregister(storage)?;
+
+    let mut record = RegisteredReminders {
+        platform: Some(platform),
+        tasks: Vec::new(),
+    };
+    for task in tasks {
+        install(platform, task)?;
+        record.tasks.push(task.name.clone());
+        storage.save(
+            OS_REMINDERS_FILE,
+            &serde_json::to_value(&record).unwrap_or_default(),
+        )?;
+    }
+    tracing::info!("Registered {} OS reminders", record.tasks.len());
+    Ok(record.tasks.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset() -> serde_json::Value {
+        serde_json::json!({
+            "schools": [
+                {
+                    "id": 1,
+                    "name": "\"青葉\" 大学 100%",
+                    "enrollmentFeeDeadline": 20260310,
+                    "tuitionDeadline": 20260302,
+                    "enrollmentFee": 200000,
+                    "tuition": 500000,
+                    "passStatus": "passed",
+                    "enrollmentFeePaid": false,
+                    "tuitionPaid": false
+                },
+                {
+                    "id": 2,
+                    "name": "桜大学",
+                    "enrollmentFeeDeadline": 20260301,
+                    "tuitionDeadline": 20260320,
+                    "enrollmentFee": 100000,
+                    "tuition": 300000,
+                    "passStatus": "passed",
+                    "enrollmentFeePaid": false,
+                    "tuitionPaid": true
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_reminder_tasks() {
+        let tasks = reminder_tasks(&dataset(), 20260301, 3);
+        let summary: Vec<_> = tasks
+            .iter()
+            .map(|t| (t.name.as_str(), t.fire_day))
+            .collect();
+        // Due today: left to the app. Within the lead window: tomorrow.
+        assert_eq!(
+            summary,
+            vec![
+                ("school-payment-reminder-1-tuition", 20260302),
+                ("school-payment-reminder-1-enrollment-fee", 20260307),
+            ]
+        );
+        assert!(tasks[0].body.contains("本日期限"));
+        assert!(tasks[1].body.contains("あと3日"));
+    }
+
+    #[test]
+    fn test_systemd_units_escape_arguments() {
+        let task = &reminder_tasks(&dataset(), 20260301, 3)[0];
+        let (service, timer) = systemd_units(task);
+        assert!(service.contains(r#"\"青葉\" 大学 100%%"#));
+        assert!(timer.contains("OnCalendar=2026-03-02 09:00:00"));
+    }
+
+    #[test]
+    fn test_launchd_and_windows_definitions_escape_text() {
+        let mut task = reminder_tasks(&dataset(), 20260301, 3)[1].clone();
+        task.body = "A & B's <fee>".to_string();
+
+        let plist = launchd_plist(&task);
+        assert!(plist.contains("<integer>7</integer>"));
+        assert!(plist.contains("A &amp; B's &lt;fee&gt;"));
+
+        let xml = windows_task_xml(&task);
+        assert!(xml.contains("<StartBoundary>2026-03-07T09:00:00</StartBoundary>"));
+        assert!(xml.contains("'A &amp; B''s &lt;fee&gt;'"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Recording exam results as they are announced.
+//!
+//! When a result comes in, [`apply`] sets the school's pass status and
+//! settles what depends on it: a rejected school's unpaid recurring
+//! payments are dropped, an accepted school's are generated, and with
+//! [`OutcomeRules::cancel_lower_choices`] the schools ranked below an
+//! accepted one are cancelled so their fees stop counting. A waitlisted
+//! school stays open until its final result.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::model::{Dataset, GetRecommendationResult, PassStatus, School};
+
+/// Errors recording an exam result
+#[derive(Debug, Error)]
+pub enum OutcomeError {
+    #[error("School not found: {0}")]
+    UnknownSchool(u64),
+}
+
+/// Result announced by a school
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum ExamOutcome {
+    Accepted,
+    Rejected,
+    Waitlisted,
+}
+
+impl ExamOutcome {
+    /// Pass status of a school with this result
+    pub fn pass_status(self) -> PassStatus {
+        match self {
+            Self::Accepted => PassStatus::Passed,
+            Self::Rejected => PassStatus::Failed,
+            Self::Waitlisted => PassStatus::NotYetAnnounced,
+        }
+    }
+}
+
+/// What else changes when a result is recorded
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutcomeRules {
+    /// On acceptance, cancel the schools ranked below it that are still
+    /// open and have nothing paid, such as safety schools
+    pub cancel_lower_choices: bool,
+}
+
+/// An exam result recorded for a school
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct OutcomeEntry {
+    /// RFC 3339 time the result was recorded
+    pub recorded_at: String,
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    pub outcome: ExamOutcome,
+    /// Schools cancelled by the outcome rules as a result
+    #[ts(type = "Array<number>")]
+    pub cancelled_school_ids: Vec<u64>,
+}
+
+/// An exam result recorded, with the advisor's recommendation after it
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RecordedOutcome {
+    pub entry: OutcomeEntry,
+    pub dataset: Dataset,
+    /// Today's recommendation for the updated data; `None` in a dry run or
+    /// while the advisor is unavailable
+    pub recommendation: Option<GetRecommendationResult>,
+}
+
+/// Whether any of `school`'s fees has been paid
+fn has_payments(school: &School) -> bool {
+    school.enrollment_fee_paid
+        || school.tuition_payments().iter().any(|p| p.paid)
+        || school.recurring_payments.iter().any(|p| p.paid)
+}
+
+/// Record `outcome` for school `school_id` and apply `rules` as of
+/// `today`. Returns the ids of the schools cancelled as a result.
+pub fn apply(
+    dataset: &mut Dataset,
+    school_id: u64,
+    outcome: ExamOutcome,
+    rules: &OutcomeRules,
+    today: u32,
+) -> Result<Vec<u64>, OutcomeError> {
+    let school = dataset
+        .schools
+        .iter_mut()
+        .find(|s| s.id == school_id)
+        .ok_or(OutcomeError::UnknownSchool(school_id))?;
+    school.pass_status = outcome.pass_status();
+    school.waitlisted = outcome == ExamOutcome::Waitlisted;
+    let rank = school.priority;
+
+    let mut cancelled = Vec::new();
+    // Priority 0 means the school was never ranked
+    if outcome == ExamOutcome::Accepted && rules.cancel_lower_choices && rank > 0 {
+        for other in &mut dataset.schools {
+            if other.id != school_id
+                && other.priority > rank
+                && other.pass_status.is_open()
+                && !has_payments(other)
+            {
+                other.pass_status = PassStatus::Cancelled;
+                other.waitlisted = false;
+                cancelled.push(other.id);
+            }
+        }
+    }
+    dataset.regenerate_recurring(today);
+    Ok(cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RecurringFee;
+
+    fn dataset() -> Dataset {
+        let mut dataset =
+            crate::sample::sample_dataset(crate::sample::SampleProfile::University, 20260301);
+        for (i, school) in dataset.schools.iter_mut().enumerate() {
+            school.priority = i as u32 + 1;
+            school.pass_status = PassStatus::NotYetAnnounced;
+            school.enrollment_fee_paid = false;
+            school.tuition_paid = false;
+            school.installments.clear();
+        }
+        dataset
+    }
+
+    #[test]
+    fn test_acceptance_cancels_lower_choices_by_rule() {
+        let mut data = dataset();
+        let rules = OutcomeRules {
+            cancel_lower_choices: true,
+        };
+        let first = data.schools[0].id;
+        let second = data.schools[1].id;
+        data.schools[2].enrollment_fee_paid = true;
+
+        let cancelled = apply(&mut data, second, ExamOutcome::Accepted, &rules, 20260301).unwrap();
+        assert_eq!(data.schools[1].pass_status, PassStatus::Passed);
+        // Higher choices and schools already paid for stay open
+        assert_eq!(data.schools[0].pass_status, PassStatus::NotYetAnnounced);
+        assert_eq!(data.schools[2].pass_status, PassStatus::NotYetAnnounced);
+        let expected: Vec<u64> = data.schools[3..].iter().map(|s| s.id).collect();
+        assert_eq!(cancelled, expected);
+        assert!(data.schools[3..]
+            .iter()
+            .all(|s| s.pass_status == PassStatus::Cancelled));
+
+        // Without the rule only the school itself changes
+        let mut data = dataset();
+        let cancelled = apply(
+            &mut data,
+            first,
+            ExamOutcome::Accepted,
+            &OutcomeRules::default(),
+            20260301,
+        )
+        .unwrap();
+        assert!(cancelled.is_empty());
+        assert!(data.schools[1..].iter().all(|s| s.pass_status.is_open()));
+        assert!(apply(&mut data, 999, ExamOutcome::Accepted, &rules, 20260301).is_err());
+    }
+
+    #[test]
+    fn test_rejection_drops_unpaid_recurring_payments() {
+        let mut data = dataset();
+        let id = data.schools[0].id;
+        data.schools[0].recurring_fees = vec![RecurringFee {
+            label: "施設設備費".to_string(),
+            amount: 30000,
+            first_due: 20260410,
+            interval_months: 1,
+            until: None,
+        }];
+        let rules = OutcomeRules::default();
+
+        apply(&mut data, id, ExamOutcome::Waitlisted, &rules, 20260301).unwrap();
+        assert!(data.schools[0].waitlisted);
+        assert_eq!(data.schools[0].pass_status, PassStatus::NotYetAnnounced);
+        assert!(!data.schools[0].recurring_payments.is_empty());
+
+        data.schools[0].recurring_payments[0].paid = true;
+        apply(&mut data, id, ExamOutcome::Rejected, &rules, 20260301).unwrap();
+        assert!(!data.schools[0].waitlisted);
+        assert_eq!(data.schools[0].pass_status, PassStatus::Failed);
+        assert_eq!(data.schools[0].recurring_payments.len(), 1);
+        assert!(data.schools[0].recurring_payments[0].paid);
+    }
+}
@@ -0,0 +1,321 @@
+//! Recording payments as made, and what is left to pay.
+//!
+//! [`mark_paid`] sets a payment's paid flag and keeps a [`Receipt`] of when
+//! and how it was made. Since reminders, summaries and the advisor all go
+//! by the paid flags, a payment marked here stops generating alerts.
+//! [`outstanding`] splits the enrollment fees and tuition payments falling
+//! in a range into outstanding and settled, with totals in yen.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::currency::ExchangeRates;
+use crate::date::parse_day;
+use crate::model::{Currency, Dataset, FeeKind, PaymentMethod, Receipt, School};
+use crate::summary::SummaryRange;
+
+/// Errors that can occur when recording a payment
+#[derive(Debug, Error)]
+pub enum PaymentError {
+    #[error("School not found: {0}")]
+    UnknownSchool(u64),
+
+    #[error("School {school_id} has no installment {installment}")]
+    UnknownInstallment { school_id: u64, installment: u32 },
+
+    #[error("Invalid payment date: {0}")]
+    InvalidDate(u32),
+}
+
+/// A payment to record as made
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PaymentRecord {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub fee: FeeKind,
+    /// 1-based installment number, for tuition paid in parts
+    #[serde(default)]
+    #[ts(optional)]
+    pub installment: Option<u32>,
+    pub paid_on: u32,
+    pub method: PaymentMethod,
+    #[serde(default)]
+    #[ts(optional)]
+    pub reference: Option<String>,
+    #[serde(default)]
+    #[ts(optional)]
+    pub attachment: Option<String>,
+}
+
+/// One enrollment fee or tuition payment of a school
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PaymentItem {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    pub fee: FeeKind,
+    /// 1-based installment number, for tuition paid in parts
+    pub installment: Option<u32>,
+    pub due: u32,
+    /// Amount after adjustments, in the school's currency
+    #[ts(type = "number")]
+    pub amount: u64,
+    pub currency: Currency,
+    /// How the payment was made, if it was recorded
+    pub receipt: Option<Receipt>,
+}
+
+/// Payments falling in a range, split by whether they were made
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct OutstandingPayments {
+    /// Unpaid payments of schools still open, earliest first
+    pub outstanding: Vec<PaymentItem>,
+    /// Payments made, earliest first
+    pub settled: Vec<PaymentItem>,
+    #[ts(type = "number")]
+    pub outstanding_total: u64,
+    #[ts(type = "number")]
+    pub settled_total: u64,
+    /// Schools left out of the totals because their currency has no
+    /// exchange rate
+    #[ts(type = "Array<number>")]
+    pub unconverted_school_ids: Vec<u64>,
+    /// Currencies of the schools whose rate is past its maximum age
+    pub stale_rates: Vec<Currency>,
+}
+
+/// Mark the payment in `record` as made and keep its receipt, replacing
+/// any earlier receipt for the same payment.
+///
+/// Paying the first tuition installment also marks the tuition paid, since
+/// it secures the place.
+pub fn mark_paid(dataset: &mut Dataset, record: PaymentRecord) -> Result<(), PaymentError> {
+    if parse_day(record.paid_on).is_none() {
+        return Err(PaymentError::InvalidDate(record.paid_on));
+    }
+    let school = dataset
+        .schools
+        .iter_mut()
+        .find(|s| s.id == record.school_id)
+        .ok_or(PaymentError::UnknownSchool(record.school_id))?;
+
+    let installment = match record.fee {
+        FeeKind::EnrollmentFee => {
+            school.enrollment_fee_paid = true;
+            None
+        }
+        FeeKind::Tuition if school.installments.is_empty() => {
+            school.tuition_paid = true;
+            None
+        }
+        FeeKind::Tuition => {
+            let number = record.installment.unwrap_or(1);
+            let installment = number
+                .checked_sub(1)
+                .and_then(|i| school.installments.get_mut(i as usize))
+                .ok_or(PaymentError::UnknownInstallment {
+                    school_id: school.id,
+                    installment: number,
+                })?;
+            installment.paid = true;
+            if number == 1 {
+                school.tuition_paid = true;
+            }
+            Some(number)
+        }
+    };
+
+    school
+        .receipts
+        .retain(|r| !(r.fee == record.fee && r.installment == installment));
+    school.receipts.push(Receipt {
+        fee: record.fee,
+        installment,
+        paid_on: record.paid_on,
+        method: record.method,
+        reference: record.reference.filter(|r| !r.trim().is_empty()),
+        attachment: record.attachment.filter(|a| !a.trim().is_empty()),
+    });
+    Ok(())
+}
+
+/// Enrollment fee and tuition payments of `school`, with their receipts
+/// and whether they were made
+pub(crate) fn items(school: &School) -> Vec<(PaymentItem, bool)> {
+    let receipt = |fee, installment| {
+        school
+            .receipts
+            .iter()
+            .find(|r| r.fee == fee && r.installment == installment)
+            .cloned()
+    };
+    let item = |fee, installment, due, amount| PaymentItem {
+        school_id: school.id,
+        school_name: school.name.clone(),
+        fee,
+        installment,
+        due,
+        amount,
+        currency: school.currency,
+        receipt: receipt(fee, installment),
+    };
+
+    let mut items = vec![(
+        item(
+            FeeKind::EnrollmentFee,
+            None,
+            school.enrollment_fee_deadline,
+            school.net_enrollment_fee(),
+        ),
+        school.enrollment_fee_paid,
+    )];
+    let in_parts = !school.installments.is_empty();
+    for (i, payment) in school.tuition_payments().into_iter().enumerate() {
+        let installment = in_parts.then_some(i as u32 + 1);
+        items.push((
+            item(FeeKind::Tuition, installment, payment.due, payment.amount),
+            payment.paid,
+        ));
+    }
+    items
+}
+
+/// Outstanding and settled payments of `dataset` due within `range`
+pub fn outstanding(
+    dataset: &Dataset,
+    range: SummaryRange,
+    rates: &ExchangeRates,
+) -> OutstandingPayments {
+    let mut payments = OutstandingPayments::default();
+    let schools = dataset
+        .schools
+        .iter()
+        .filter(|s| range.child_id.is_none() || s.child_id == range.child_id);
+    for school in schools {
+        for (item, paid) in items(school) {
+            let in_range = range.from.is_none_or(|from| item.due >= from)
+                && range.to.is_none_or(|to| item.due <= to);
+            if !in_range || !(paid || school.pass_status.is_open()) {
+                continue;
+            }
+            match rates.convert(item.amount, item.currency, Currency::Jpy) {
+                Some(yen) if paid => payments.settled_total += yen,
+                Some(yen) => payments.outstanding_total += yen,
+                None if !payments.unconverted_school_ids.contains(&school.id) => {
+                    payments.unconverted_school_ids.push(school.id)
+                }
+                None => {}
+            }
+            if paid {
+                payments.settled.push(item);
+            } else {
+                payments.outstanding.push(item);
+            }
+        }
+    }
+    payments.outstanding.sort_by_key(|i| (i.due, i.school_id));
+    payments.settled.sort_by_key(|i| (i.due, i.school_id));
+    payments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_dataset;
+    use crate::model::InstallmentPlan;
+
+    fn record(school_id: u64, fee: FeeKind, installment: Option<u32>) -> PaymentRecord {
+        PaymentRecord {
+            school_id,
+            fee,
+            installment,
+            paid_on: 20260305,
+            method: PaymentMethod::BankTransfer,
+            reference: Some("A-123".to_string()),
+            attachment: Some(String::new()),
+        }
+    }
+
+    #[test]
+    fn test_mark_paid() {
+        let mut dataset = sample_dataset();
+        mark_paid(&mut dataset, record(1, FeeKind::EnrollmentFee, None)).unwrap();
+
+        let tokyo = dataset.schools.iter().find(|s| s.id == 1).unwrap();
+        assert!(tokyo.enrollment_fee_paid);
+        assert_eq!(tokyo.receipts.len(), 1);
+        assert_eq!(tokyo.receipts[0].reference.as_deref(), Some("A-123"));
+        assert_eq!(tokyo.receipts[0].attachment, None);
+
+        // Recording it again replaces the receipt
+        mark_paid(&mut dataset, record(1, FeeKind::EnrollmentFee, None)).unwrap();
+        assert_eq!(dataset.schools[1].receipts.len(), 1);
+
+        assert!(matches!(
+            mark_paid(&mut dataset, record(9, FeeKind::Tuition, None)),
+            Err(PaymentError::UnknownSchool(9))
+        ));
+        let mut invalid = record(1, FeeKind::Tuition, None);
+        invalid.paid_on = 20260230;
+        assert!(matches!(
+            mark_paid(&mut dataset, invalid),
+            Err(PaymentError::InvalidDate(20260230))
+        ));
+    }
+
+    #[test]
+    fn test_mark_installment_paid() {
+        let mut dataset = sample_dataset();
+        dataset.schools[0].installments = InstallmentPlan {
+            total: 800000,
+            first_due: 20260320,
+            count: 2,
+            interval_months: 6,
+        }
+        .schedule()
+        .unwrap();
+
+        mark_paid(&mut dataset, record(2, FeeKind::Tuition, Some(2))).unwrap();
+        assert!(dataset.schools[0].installments[1].paid);
+        assert!(!dataset.schools[0].tuition_paid);
+        mark_paid(&mut dataset, record(2, FeeKind::Tuition, None)).unwrap();
+        assert!(dataset.schools[0].tuition_paid);
+        assert!(matches!(
+            mark_paid(&mut dataset, record(2, FeeKind::Tuition, Some(3))),
+            Err(PaymentError::UnknownInstallment { installment: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_outstanding() {
+        let mut dataset = sample_dataset();
+        mark_paid(&mut dataset, record(2, FeeKind::Tuition, None)).unwrap();
+        let payments = outstanding(&dataset, SummaryRange::default(), &ExchangeRates::default());
+
+        // Waseda is settled; Tokyo's fees are still to pay
+        assert_eq!(payments.settled_total, 200000 + 800000);
+        assert_eq!(payments.outstanding_total, 282000 + 535800);
+        assert_eq!(payments.outstanding[0].due, 20260317);
+        let tuition = payments
+            .settled
+            .iter()
+            .find(|i| i.fee == FeeKind::Tuition)
+            .unwrap();
+        assert_eq!(tuition.receipt.as_ref().unwrap().paid_on, 20260305);
+
+        let range = SummaryRange {
+            from: Some(20260318),
+            ..SummaryRange::default()
+        };
+        let payments = outstanding(&dataset, range, &ExchangeRates::default());
+        assert_eq!(payments.outstanding.len(), 1);
+        assert_eq!(payments.settled.len(), 1);
+    }
+}
@@ -0,0 +1,289 @@
+//! Advisor responses preloaded in the background after startup.
+//!
+//! Once the advisor is ready, [`spawn`] sends it the stored dataset with a
+//! `getWeeklyRecommendations` request for the current week, the first thing
+//! the main screen asks for, and keeps the response in a [`PreloadCache`].
+//! The frontend's matching request is then answered from the cache instead
+//! of waiting for the advisor.
+//!
+//! Preloading is turned off with the `advisor.preload` setting and is
+//! cancelled as soon as the user sends a request of their own; a request
+//! already being answered by the advisor is allowed to finish.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Map, Value};
+use tokio::task::AbortHandle;
+
+use crate::handlers::{self, AppState, HandlerError, WEEKLY_REPORT_DAYS};
+use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+
+/// Method whose responses are preloaded
+const PRELOADED_METHOD: &str = "getWeeklyRecommendations";
+
+/// School fields the advisor reads
+const SCHOOL_FIELDS: [&str; 9] = [
+    "id",
+    "name",
+    "priority",
+    "examDate",
+    "resultDate",
+    "enrollmentFeeDeadline",
+    "tuitionDeadline",
+    "enrollmentFee",
+    "tuition",
+];
+
+/// State fields the advisor reads
+const STATE_FIELDS: [&str; 4] = ["schoolId", "passStatus", "enrollmentFeePaid", "tuitionPaid"];
+
+/// Only `fields` of each object in `list`, leaving out nulls
+fn project(list: Option<&Value>, fields: &[&str]) -> Value {
+    let items = list
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    items
+        .iter()
+        .map(|item| {
+            let object: Map<String, Value> = fields
+                .iter()
+                .filter_map(|&field| {
+                    let value = item.get(field).filter(|v| !v.is_null())?;
+                    Some((field.to_string(), value.clone()))
+                })
+                .collect();
+            Value::Object(object)
+        })
+        .collect()
+}
+
+/// What the advisor reads from weekly `params` besides the first day,
+/// ignoring fields it does not read, so params built from the stored
+/// dataset match the frontend's
+pub(crate) fn advisor_inputs(params: &Value) -> Value {
+    serde_json::json!({
+        "days": params.get("days"),
+        "schools": project(params.get("schools"), &SCHOOL_FIELDS),
+        "states": project(params.get("states"), &STATE_FIELDS),
+    })
+}
+
+/// Key identifying what the advisor is asked
+fn cache_key(request: &JsonRpcRequest) -> Option<String> {
+    if request.method != PRELOADED_METHOD {
+        return None;
+    }
+    let params = request.params_value();
+    let key = serde_json::json!({
+        "startDay": params.get("startDay")?,
+        "inputs": advisor_inputs(&params),
+    });
+    Some(key.to_string())
+}
+
+/// Preloaded responses, each answered once, and the preload still running
+#[derive(Debug, Default)]
+pub struct PreloadCache {
+    responses: Mutex<HashMap<String, JsonRpcResponse>>,
+    task: Mutex<Option<AbortHandle>>,
+}
+
+impl PreloadCache {
+    /// Keep the advisor's `response` to `request`
+    pub fn insert(&self, request: &JsonRpcRequest, response: JsonRpcResponse) {
+        if let Some(key) = cache_key(request) {
+            self.responses
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, response);
+        }
+    }
+
+    /// The preloaded response to `request`, with its id; later requests go
+    /// to the advisor again
+    pub fn take(&self, request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+        let key = cache_key(request)?;
+        let response = self
+            .responses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&key)?;
+        Some(JsonRpcResponse {
+            id: request.id.clone(),
+            ..response
+        })
+    }
+
+    /// Stop the preload, if it is still waiting
+    pub fn cancel(&self) {
+        if let Some(task) = self.task.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            if !task.is_finished() {
+                tracing::debug!("Preload cancelled by a user request");
+                task.abort();
+            }
+        }
+    }
+
+    fn set_task(&self, task: AbortHandle) {
+        *self.task.lock().unwrap_or_else(|e| e.into_inner()) = Some(task);
+    }
+}
+
+/// Request for the recommendations of the week starting `today`
+fn weekly_request(data: &Value, today: u32) -> JsonRpcRequest {
+    let mut params = handlers::advisor_params(data);
+    params["startDay"] = today.into();
+    params["days"] = WEEKLY_REPORT_DAYS.into();
+    JsonRpcRequest::new(PRELOADED_METHOD, &params, serde_json::json!("preload"))
+}
+
+/// Ask the advisor for this week's recommendations on the stored dataset
+/// and keep the response
+async fn preload(state: Arc<AppState>, today: u32) {
+    let data = match state.load_dataset() {
+        Ok(data) => data,
+        Err(HandlerError::NoData) | Err(HandlerError::NoStorage) => return,
+        Err(e) => {
+            tracing::warn!("Skipping preload: {}", e);
+            return;
+        }
+    };
+    let request = weekly_request(&data, today);
+    let result = state.lean_repl.lock().await.send_request(&request);
+    match result {
+        Ok(response) if response.error.is_none() => {
+            tracing::info!("Preloaded this week's recommendations");
+            handlers::save_result(&state, &request, &response).await;
+            state.preload.insert(&request, response);
+        }
+        Ok(response) => tracing::debug!("Preload answered with an error: {:?}", response.error),
+        Err(e) => tracing::warn!("Preload failed: {}", e),
+    }
+}
+
+/// Start preloading in the background, unless turned off in the settings
+pub async fn spawn(state: Arc<AppState>, today: u32) {
+    if !state.settings.lock().await.advisor.preload {
+        return;
+    }
+    let task = tokio::spawn(preload(state.clone(), today));
+    state.preload.set_task(task.abort_handle());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_repl::LeanRepl;
+    use crate::settings::Settings;
+    use crate::storage::{Storage, SCHOOLS_DATA_FILE};
+
+    fn state_with_sample_data(dir: &std::path::Path) -> Arc<AppState> {
+        let storage = Storage::new(dir.to_path_buf());
+        storage
+            .save(SCHOOLS_DATA_FILE, &crate::export::sample_data())
+            .unwrap();
+        Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage))
+    }
+
+    /// The request the main screen sends, built like the frontend does
+    fn frontend_request(data: &Value, start_day: u32) -> JsonRpcRequest {
+        let schools = data["schools"].as_array().unwrap();
+        let pick = |fields: &[&str]| -> Vec<Value> {
+            schools
+                .iter()
+                .map(|s| {
+                    let object: Map<String, Value> = fields
+                        .iter()
+                        .filter_map(|&f| Some((f.to_string(), s.get(f)?.clone())))
+                        .collect();
+                    Value::Object(object)
+                })
+                .collect()
+        };
+        let states: Vec<Value> = schools
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "schoolId": s["id"],
+                    "passStatus": s.get("passStatus").cloned().unwrap_or("notYetAnnounced".into()),
+                    "enrollmentFeePaid": s.get("enrollmentFeePaid").cloned().unwrap_or(false.into()),
+                    "tuitionPaid": s.get("tuitionPaid").cloned().unwrap_or(false.into()),
+                })
+            })
+            .collect();
+        JsonRpcRequest::new(
+            PRELOADED_METHOD,
+            &serde_json::json!({
+                "startDay": start_day,
+                "days": 7,
+                "schools": pick(&SCHOOL_FIELDS),
+                "states": states,
+            }),
+            serde_json::json!(3),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_preloaded_response_answers_frontend_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_sample_data(dir.path());
+        preload(state.clone(), 20260201).await;
+
+        let data = crate::export::sample_data();
+        assert!(state
+            .preload
+            .take(&frontend_request(&data, 20260202))
+            .is_none());
+        let request = frontend_request(&data, 20260201);
+        let response = state.preload.take(&request).unwrap();
+        assert_eq!(response.id, serde_json::json!(3));
+        assert!(response.result.is_some());
+        // Each preloaded response is used once
+        assert!(state.preload.take(&request).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_relaunch_shows_last_computed_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_sample_data(dir.path());
+        preload(state, 20260201).await;
+
+        let relaunched = Arc::new(
+            AppState::new(LeanRepl::demo()).with_storage(Storage::new(dir.path().to_path_buf())),
+        );
+        let request = frontend_request(&crate::export::sample_data(), 20260201);
+        let cached = handlers::cached_result(relaunched, &request).await.unwrap();
+        assert!(cached.result["recommendations"].is_array());
+        assert!(cached.age_secs < 60);
+    }
+
+    #[tokio::test]
+    async fn test_preload_can_be_turned_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = Settings::default();
+        settings.advisor.preload = false;
+        let state = state_with_sample_data(dir.path());
+        *state.settings.lock().await = settings;
+
+        spawn(state.clone(), 20260201).await;
+        assert!(state.preload.task.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_user_request_cancels_preload() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_sample_data(dir.path());
+        // Hold the advisor so the preload cannot start its request
+        let repl = state.lean_repl.lock().await;
+        spawn(state.clone(), 20260201).await;
+        tokio::task::yield_now().await;
+        state.preload.cancel();
+        drop(repl);
+        // The lock is fair, so a preload still queued would run before this
+        drop(state.lean_repl.lock().await);
+
+        let request = frontend_request(&crate::export::sample_data(), 20260201);
+        assert!(state.preload.take(&request).is_none());
+    }
+}
@@ -0,0 +1,318 @@
+//! Opt-in latency profiling of advisor requests.
+//!
+//! When profiling is on, every request sent with
+//! [`send_rpc`](crate::handlers::send_rpc) records how long it spent in
+//! each [`Phase`]: waiting for the advisor, starting it, serializing the
+//! request, the advisor's own work, parsing the response and filling the
+//! explanation cache. [`Profiler::stats`] turns the recent samples into
+//! percentiles per method, which tells whether slowness comes from the
+//! advisor or from the app around it. Comparing the total with the round
+//! trip the frontend measures gives the time spent in the webview and IPC.
+//!
+//! Profiling is off unless `[profiling] enabled = true` is set in
+//! `config.toml`, and can be switched on and off while running.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Samples kept per method unless configured otherwise
+pub const DEFAULT_SAMPLES: usize = 500;
+
+/// `[profiling]` section of `config.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProfilingConfig {
+    /// Profile from startup
+    pub enabled: bool,
+    /// Most recent requests kept per method
+    pub samples: usize,
+}
+
+impl Default for ProfilingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples: DEFAULT_SAMPLES,
+        }
+    }
+}
+
+/// Where the time of a request goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum Phase {
+    /// Waiting for the advisor to finish earlier requests
+    Queueing,
+    /// Starting the advisor when it was not running
+    Spawn,
+    /// Turning the request into JSON
+    Serialization,
+    /// From sending the request until its response arrives, including the
+    /// pipes to and from the advisor
+    Compute,
+    /// Parsing the response JSON
+    Parsing,
+    /// Looking up and queueing explanations of the recommendations
+    Cache,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 6] = [
+        Phase::Queueing,
+        Phase::Spawn,
+        Phase::Serialization,
+        Phase::Compute,
+        Phase::Parsing,
+        Phase::Cache,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Time spent in each phase by one request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings([Duration; Phase::ALL.len()]);
+
+impl PhaseTimings {
+    /// Count `duration` towards `phase`
+    pub fn add(&mut self, phase: Phase, duration: Duration) {
+        self.0[phase.index()] += duration;
+    }
+
+    pub fn get(&self, phase: Phase) -> Duration {
+        self.0[phase.index()]
+    }
+
+    /// Time spent in all phases
+    pub fn total(&self) -> Duration {
+        self.0.iter().sum()
+    }
+}
+
+/// Percentiles of one phase, in milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PhaseStats {
+    /// The phase, or none for the whole request
+    pub phase: Option<Phase>,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl PhaseStats {
+    fn new(phase: Option<Phase>, mut durations: Vec<Duration>) -> Self {
+        durations.sort();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mean = match durations.len() {
+            0 => 0.0,
+            n => ms(durations.iter().sum::<Duration>()) / n as f64,
+        };
+        Self {
+            phase,
+            mean_ms: mean,
+            p50_ms: ms(percentile(&durations, 50)),
+            p90_ms: ms(percentile(&durations, 90)),
+            p99_ms: ms(percentile(&durations, 99)),
+            max_ms: ms(durations.last().copied().unwrap_or_default()),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted `durations`
+pub(crate) fn percentile(durations: &[Duration], p: usize) -> Duration {
+    if durations.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * durations.len()).div_ceil(100).max(1);
+    durations[rank - 1]
+}
+
+/// Timings of the recent requests of one method
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MethodStats {
+    pub method: String,
+    /// Requests the percentiles are taken over
+    pub count: usize,
+    /// The whole request
+    pub total: PhaseStats,
+    /// Each phase, in [`Phase::ALL`] order
+    pub phases: Vec<PhaseStats>,
+}
+
+/// Profiling state and the timings collected so far
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProfileStats {
+    pub enabled: bool,
+    /// Methods in name order
+    pub methods: Vec<MethodStats>,
+}
+
+/// Collects the phase timings of recent requests while enabled
+#[derive(Debug)]
+pub struct Profiler {
+    enabled: AtomicBool,
+    capacity: usize,
+    samples: Mutex<HashMap<String, VecDeque<PhaseTimings>>>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new(&ProfilingConfig::default())
+    }
+}
+
+impl Profiler {
+    pub fn new(config: &ProfilingConfig) -> Self {
+        Self {
+            enabled: AtomicBool::new(config.enabled),
+            capacity: config.samples.max(1),
+            samples: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Switch profiling on or off. Switching it on starts over with no
+    /// samples.
+    pub fn set_enabled(&self, enabled: bool) {
+        if enabled && !self.is_enabled() {
+            self.reset();
+        }
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Drop the samples collected so far
+    pub fn reset(&self) {
+        if let Ok(mut samples) = self.samples.lock() {
+            samples.clear();
+        }
+    }
+
+    /// Keep the timings of a `method` request, if profiling
+    pub fn record(&self, method: &str, timings: PhaseTimings) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Ok(mut samples) = self.samples.lock() {
+            let kept = samples.entry(method.to_string()).or_default();
+            if kept.len() == self.capacity {
+                kept.pop_front();
+            }
+            kept.push_back(timings);
+        }
+    }
+
+    /// Percentiles of the kept samples
+    pub fn stats(&self) -> ProfileStats {
+        let mut methods: Vec<MethodStats> = match self.samples.lock() {
+            Ok(samples) => samples
+                .iter()
+                .map(|(method, kept)| MethodStats {
+                    method: method.clone(),
+                    count: kept.len(),
+                    total: PhaseStats::new(None, kept.iter().map(PhaseTimings::total).collect()),
+                    phases: Phase::ALL
+                        .iter()
+                        .map(|&phase| {
+                            PhaseStats::new(
+                                Some(phase),
+                                kept.iter().map(|t| t.get(phase)).collect(),
+                            )
+                        })
+                        .collect(),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        methods.sort_by(|a, b| a.method.cmp(&b.method));
+        ProfileStats {
+            enabled: self.is_enabled(),
+            methods,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timings(compute_ms: u64, queueing_ms: u64) -> PhaseTimings {
+        let mut timings = PhaseTimings::default();
+        timings.add(Phase::Compute, Duration::from_millis(compute_ms));
+        timings.add(Phase::Queueing, Duration::from_millis(queueing_ms));
+        timings
+    }
+
+    #[test]
+    fn test_percentile() {
+        let durations: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&durations, 50), Duration::from_millis(5));
+        assert_eq!(percentile(&durations, 90), Duration::from_millis(9));
+        assert_eq!(percentile(&durations, 99), Duration::from_millis(10));
+        assert_eq!(percentile(&durations[..1], 50), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_records_only_while_enabled() {
+        let profiler = Profiler::default();
+        profiler.record("ping", timings(1, 0));
+        assert!(profiler.stats().methods.is_empty());
+
+        profiler.set_enabled(true);
+        for ms in 1..=4 {
+            profiler.record("getRecommendation", timings(ms * 10, ms));
+        }
+        profiler.record("ping", timings(1, 0));
+
+        let stats = profiler.stats();
+        assert!(stats.enabled);
+        assert_eq!(stats.methods.len(), 2);
+        let recommendation = &stats.methods[0];
+        assert_eq!(recommendation.method, "getRecommendation");
+        assert_eq!(recommendation.count, 4);
+        assert_eq!(recommendation.total.p50_ms, 22.0);
+        assert_eq!(recommendation.total.max_ms, 44.0);
+        let compute = &recommendation.phases[Phase::Compute.index()];
+        assert_eq!(compute.phase, Some(Phase::Compute));
+        assert_eq!(compute.mean_ms, 25.0);
+        assert_eq!(recommendation.phases[Phase::Parsing.index()].max_ms, 0.0);
+
+        // Switching back on starts over
+        profiler.set_enabled(false);
+        profiler.set_enabled(true);
+        assert!(profiler.stats().methods.is_empty());
+    }
+
+    #[test]
+    fn test_keeps_the_latest() {
+        let profiler = Profiler::new(&ProfilingConfig {
+            enabled: true,
+            samples: 2,
+        });
+        for ms in [100, 1, 2] {
+            profiler.record("ping", timings(ms, 0));
+        }
+        let stats = profiler.stats();
+        assert_eq!(stats.methods[0].count, 2);
+        assert_eq!(stats.methods[0].total.max_ms, 2.0);
+    }
+}
@@ -0,0 +1,250 @@
+//! Pagination, filtering, and sorting for list endpoints.
+//!
+//! [`ListQuery`] is parsed from query-string parameters:
+//!
+//! - `page` (1-based, default 1) and `per_page` (default 50, max 500)
+//! - `sort`: comma-separated field names, `-` prefix for descending
+//! - any other parameter is an equality filter on a top-level field
+//!
+//! It operates on JSON objects so the same code serves every list.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// Default page size
+pub const DEFAULT_PER_PAGE: usize = 50;
+
+/// Largest page size a client may request
+pub const MAX_PER_PAGE: usize = 500;
+
+/// Errors that can occur when parsing list parameters
+#[derive(Debug, Error)]
+pub enum QueryError {
+    #[error("Invalid value '{value}' for parameter '{name}'")]
+    InvalidParam { name: String, value: String },
+}
+
+/// A sort key parsed from `sort`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: String,
+    pub descending: bool,
+}
+
+/// Parsed list parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListQuery {
+    pub page: usize,
+    pub per_page: usize,
+    pub sort: Vec<SortKey>,
+    pub filters: Vec<(String, String)>,
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+            sort: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+}
+
+/// One page of results
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub total_pages: usize,
+}
+
+fn parse_positive(name: &str, value: &str) -> Result<usize, QueryError> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| QueryError::InvalidParam {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+}
+
+impl ListQuery {
+    /// Parse list parameters from a query-string map
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, QueryError> {
+        let mut query = Self::default();
+        for (name, value) in params {
+            match name.as_str() {
+                "page" => query.page = parse_positive(name, value)?,
+                "per_page" => query.per_page = parse_positive(name, value)?.min(MAX_PER_PAGE),
+                "sort" => {
+                    query.sort = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|f| !f.is_empty())
+                        .map(|f| match f.strip_prefix('-') {
+                            Some(field) => SortKey {
+                                field: field.to_string(),
+                                descending: true,
+                            },
+                            None => SortKey {
+                                field: f.to_string(),
+                                descending: false,
+                            },
+                        })
+                        .collect();
+                }
+                _ => query.filters.push((name.clone(), value.clone())),
+            }
+        }
+        query.filters.sort();
+        Ok(query)
+    }
+
+    /// Check whether an item passes every filter
+    fn matches(&self, item: &serde_json::Value) -> bool {
+        self.filters
+            .iter()
+            .all(|(field, expected)| match item.get(field) {
+                Some(serde_json::Value::String(s)) => s == expected,
+                Some(serde_json::Value::Null) | None => false,
+                Some(other) => {
+                    serde_json::from_str::<serde_json::Value>(expected).is_ok_and(|v| v == *other)
+                }
+            })
+    }
+
+    /// Filter and sort a list of JSON objects, ignoring the page
+    pub fn select(&self, items: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+        let mut items: Vec<_> = items.into_iter().filter(|i| self.matches(i)).collect();
+
+        if !self.sort.is_empty() {
+            items.sort_by(|a, b| {
+                self.sort
+                    .iter()
+                    .map(|key| {
+                        let ordering = compare_values(a.get(&key.field), b.get(&key.field));
+                        if key.descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    })
+                    .find(|o| *o != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        items
+    }
+
+    /// Filter, sort, and paginate a list of JSON objects
+    pub fn apply(&self, items: Vec<serde_json::Value>) -> Page<serde_json::Value> {
+        let items = self.select(items);
+        let total = items.len();
+        let start = (self.page - 1).saturating_mul(self.per_page).min(total);
+        let items: Vec<_> = items.into_iter().skip(start).take(self.per_page).collect();
+
+        Page {
+            items,
+            total,
+            page: self.page,
+            per_page: self.per_page,
+            total_pages: total.div_ceil(self.per_page),
+        }
+    }
+}
+
+/// Order JSON values: numbers numerically, strings lexically, missing values last
+fn compare_values(a: Option<&serde_json::Value>, b: Option<&serde_json::Value>) -> Ordering {
+    use serde_json::Value;
+    match (a, b) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => x
+            .as_f64()
+            .unwrap_or_default()
+            .total_cmp(&y.as_f64().unwrap_or_default()),
+        (Some(Value::String(x)), Some(Value::String(y))) => x.cmp(y),
+        (Some(Value::Bool(x)), Some(Value::Bool(y))) => x.cmp(y),
+        (None | Some(Value::Null), None | Some(Value::Null)) => Ordering::Equal,
+        (None | Some(Value::Null), _) => Ordering::Greater,
+        (_, None | Some(Value::Null)) => Ordering::Less,
+        (Some(x), Some(y)) => x.to_string().cmp(&y.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn items() -> Vec<serde_json::Value> {
+        vec![
+            serde_json::json!({"id": 1, "name": "東京大学", "priority": 1, "passStatus": "notYetAnnounced"}),
+            serde_json::json!({"id": 2, "name": "早稲田大学", "priority": 2, "passStatus": "passed"}),
+            serde_json::json!({"id": 3, "name": "慶應義塾大学", "priority": 3, "passStatus": "passed"}),
+            serde_json::json!({"id": 4, "name": "上智大学", "passStatus": "failed"}),
+        ]
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let query = ListQuery::from_params(&HashMap::new()).unwrap();
+        assert_eq!(query, ListQuery::default());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(ListQuery::from_params(&params(&[("page", "0")])).is_err());
+        assert!(ListQuery::from_params(&params(&[("per_page", "x")])).is_err());
+        let query = ListQuery::from_params(&params(&[("per_page", "10000")])).unwrap();
+        assert_eq!(query.per_page, MAX_PER_PAGE);
+    }
+
+    #[test]
+    fn test_filter_and_sort() {
+        let query =
+            ListQuery::from_params(&params(&[("passStatus", "passed"), ("sort", "-priority")]))
+                .unwrap();
+        let page = query.apply(items());
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items[0]["id"], 3);
+        assert_eq!(page.items[1]["id"], 2);
+    }
+
+    #[test]
+    fn test_numeric_filter() {
+        let query = ListQuery::from_params(&params(&[("id", "2")])).unwrap();
+        assert_eq!(query.apply(items()).items[0]["name"], "早稲田大学");
+    }
+
+    #[test]
+    fn test_missing_sorts_last() {
+        let query = ListQuery::from_params(&params(&[("sort", "priority")])).unwrap();
+        let page = query.apply(items());
+        assert_eq!(page.items[3]["id"], 4);
+    }
+
+    #[test]
+    fn test_pagination() {
+        let query = ListQuery::from_params(&params(&[("page", "2"), ("per_page", "3")])).unwrap();
+        let page = query.apply(items());
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.total, 4);
+        assert_eq!(page.total_pages, 2);
+
+        let query = ListQuery::from_params(&params(&[("page", "9")])).unwrap();
+        assert!(query.apply(items()).items.is_empty());
+    }
+}
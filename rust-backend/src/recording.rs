@@ -0,0 +1,351 @@
+//! Recorded advisor sessions for reproducing bug reports.
+//!
+//! While recording is on, every advisor request is kept with the response
+//! it got and the revision of the saved dataset it was answered against.
+//! Stopping writes the [`Session`] to `recordings/` under the data
+//! directory, from where the user can attach it to a report such as "the
+//! advisor told me to pay the wrong school".
+//!
+//! A developer starts the web server with [`REPLAY_ARG`] and the file. It
+//! then runs on a separate [`REPLAY_DATA_DIR`] seeded with the first data
+//! revision, and a [`ReplayAdvisor`] answers the recorded requests with the
+//! recorded responses, so the frontend shows exactly what the user saw.
+//! Session files are written in plain text even when fields are encrypted,
+//! so they can be shared.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::storage::{Storage, StorageError, SCHOOLS_DATA_FILE};
+
+/// Subdirectory of the data directory holding recorded sessions
+pub const RECORDING_DIR: &str = "recordings";
+
+/// Command-line flag, followed by a session file, that answers advisor
+/// requests from the recording
+pub const REPLAY_ARG: &str = "--replay";
+
+/// Directory inside the data directory used while replaying
+pub const REPLAY_DATA_DIR: &str = "replay";
+
+/// Format version of session files
+pub const SESSION_VERSION: u32 = 1;
+
+const NOT_RECORDED: i32 = -32000;
+
+/// Errors that can occur while saving or loading a session
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("Unsupported session file version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The saved dataset as it was during part of a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataRevision {
+    /// Numbered from 1 in the order the dataset changed
+    pub revision: u32,
+    /// When the change was first seen (RFC 3339, local time)
+    pub at: String,
+    pub data: serde_json::Value,
+}
+
+/// One request with the advisor's answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedExchange {
+    pub at: String,
+    pub request: JsonRpcRequest,
+    pub response: Option<JsonRpcResponse>,
+    /// Why no response was received
+    pub error: Option<String>,
+    /// Data revision saved when the request was sent; 0 when no data was
+    /// saved
+    pub revision: u32,
+}
+
+/// A recorded session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub version: u32,
+    pub app_version: String,
+    pub started_at: String,
+    pub stopped_at: Option<String>,
+    pub revisions: Vec<DataRevision>,
+    pub exchanges: Vec<RecordedExchange>,
+}
+
+impl Session {
+    pub fn start() -> Self {
+        Self {
+            version: SESSION_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: Local::now().to_rfc3339(),
+            stopped_at: None,
+            revisions: Vec::new(),
+            exchanges: Vec::new(),
+        }
+    }
+
+    /// Add an exchange, keeping `data` as a new revision when it differs
+    /// from the last one
+    pub fn record(
+        &mut self,
+        request: &JsonRpcRequest,
+        result: Result<&JsonRpcResponse, String>,
+        data: Option<&serde_json::Value>,
+    ) {
+        let at = Local::now().to_rfc3339();
+        if let Some(data) = data {
+            if self.revisions.last().is_none_or(|r| &r.data != data) {
+                self.revisions.push(DataRevision {
+                    revision: self.revisions.len() as u32 + 1,
+                    at: at.clone(),
+                    data: data.clone(),
+                });
+            }
+        }
+        let (response, error) = match result {
+            Ok(response) => (Some(response.clone()), None),
+            Err(e) => (None, Some(e)),
+        };
+        self.exchanges.push(RecordedExchange {
+            at,
+            request: request.clone(),
+            response,
+            error,
+            revision: self.revisions.len() as u32,
+        });
+    }
+
+    /// Mark the session as stopped and write it to `recordings/` under
+    /// `data_dir`, returning the file's path
+    pub fn finish(mut self, data_dir: &Path) -> Result<PathBuf, RecordingError> {
+        let now = Local::now();
+        self.stopped_at = Some(now.to_rfc3339());
+        let dir = data_dir.join(RECORDING_DIR);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("session-{}.json", now.format("%Y%m%d-%H%M%S")));
+        fs::write(&path, serde_json::to_vec_pretty(&self)?)?;
+        Ok(path)
+    }
+
+    /// Read a session file
+    pub fn load(path: &Path) -> Result<Self, RecordingError> {
+        let session: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if session.version > SESSION_VERSION {
+            return Err(RecordingError::UnsupportedVersion(session.version));
+        }
+        Ok(session)
+    }
+}
+
+/// Data directory to use while replaying
+pub fn replay_data_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(REPLAY_DATA_DIR)
+}
+
+/// Save the data the session started with, replacing what an earlier
+/// replay left behind. Returns whether the session had any data.
+pub fn seed(storage: &Storage, session: &Session) -> Result<bool, StorageError> {
+    let Some(first) = session.revisions.first() else {
+        return Ok(false);
+    };
+    storage.save(SCHOOLS_DATA_FILE, &first.data)?;
+    Ok(true)
+}
+
+/// Whether a session is being recorded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    pub started_at: Option<String>,
+    /// Requests recorded so far
+    pub exchanges: u32,
+}
+
+impl RecordingStatus {
+    pub fn of(session: Option<&Session>) -> Self {
+        Self {
+            recording: session.is_some(),
+            started_at: session.map(|s| s.started_at.clone()),
+            exchanges: session.map_or(0, |s| s.exchanges.len() as u32),
+        }
+    }
+}
+
+/// Advisor stand-in answering from a recorded session.
+///
+/// Each request gets the response recorded for the first exchange with the
+/// same method and params not replayed yet, so repeated requests are
+/// answered in the recorded order. Once those run out, the last one is
+/// repeated. Requests that were never recorded get an error response.
+#[derive(Debug, Clone)]
+pub struct ReplayAdvisor {
+    exchanges: Vec<RecordedExchange>,
+    replayed: Vec<bool>,
+}
+
+impl ReplayAdvisor {
+    pub fn new(session: Session) -> Self {
+        let replayed = vec![false; session.exchanges.len()];
+        Self {
+            exchanges: session.exchanges,
+            replayed,
+        }
+    }
+
+    /// Answer `request` as it was answered in the recording
+    pub fn handle(&mut self, request: &JsonRpcRequest) -> JsonRpcResponse {
+        let params = request.params_value();
+        let matching = |e: &RecordedExchange| {
+            e.request.method == request.method && e.request.params_value() == params
+        };
+        let next = (0..self.exchanges.len())
+            .find(|&i| !self.replayed[i] && matching(&self.exchanges[i]))
+            .or_else(|| self.exchanges.iter().rposition(matching));
+        let Some(index) = next else {
+            return JsonRpcResponse::error(
+                request.id.clone(),
+                NOT_RECORDED,
+                format!(
+                    "{} was not called with these params in the recording",
+                    request.method
+                ),
+            );
+        };
+        self.replayed[index] = true;
+        let exchange = &self.exchanges[index];
+        match &exchange.response {
+            Some(response) => JsonRpcResponse {
+                id: request.id.clone(),
+                ..response.clone()
+            },
+            None => JsonRpcResponse::internal_error(
+                request.id.clone(),
+                exchange.error.clone().unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(id: u64, method: &str, params: serde_json::Value) -> JsonRpcRequest {
+        JsonRpcRequest::new(method, &params, json!(id))
+    }
+
+    fn recommendation(school_id: u64) -> JsonRpcResponse {
+        JsonRpcResponse::success(json!(0), json!({ "schoolId": school_id }))
+    }
+
+    #[test]
+    fn test_record_keeps_data_revisions() {
+        let mut session = Session::start();
+        let data = json!({ "schools": [{ "id": 1 }] });
+        let params = json!({ "currentDay": 20260301 });
+        let req = request(1, "getRecommendation", params);
+
+        session.record(&req, Ok(&recommendation(1)), Some(&data));
+        session.record(&req, Ok(&recommendation(1)), Some(&data));
+        let changed = json!({ "schools": [{ "id": 1 }, { "id": 2 }] });
+        session.record(&req, Err("Timeout".to_string()), Some(&changed));
+
+        assert_eq!(session.revisions.len(), 2);
+        assert_eq!(session.revisions[1].data, changed);
+        let revisions: Vec<u32> = session.exchanges.iter().map(|e| e.revision).collect();
+        assert_eq!(revisions, vec![1, 1, 2]);
+        assert_eq!(session.exchanges[2].error.as_deref(), Some("Timeout"));
+        assert_eq!(RecordingStatus::of(Some(&session)).exchanges, 3);
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(replay_data_dir(dir.path()));
+        assert!(seed(&storage, &session).unwrap());
+        assert_eq!(storage.load(SCHOOLS_DATA_FILE).unwrap(), Some(data));
+        assert!(!seed(&storage, &Session::start()).unwrap());
+    }
+
+    #[test]
+    fn test_finish_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut session = Session::start();
+        session.record(
+            &request(1, "getVersion", json!({})),
+            Ok(&JsonRpcResponse::success(json!(1), json!("1.0.0"))),
+            None,
+        );
+        let path = session.finish(dir.path()).unwrap();
+        assert!(path.starts_with(dir.path().join(RECORDING_DIR)));
+
+        let loaded = Session::load(&path).unwrap();
+        assert!(loaded.stopped_at.is_some());
+        assert_eq!(loaded.exchanges.len(), 1);
+        assert_eq!(loaded.exchanges[0].revision, 0);
+
+        let mut newer = serde_json::to_value(&loaded).unwrap();
+        newer["version"] = json!(SESSION_VERSION + 1);
+        fs::write(&path, newer.to_string()).unwrap();
+        assert!(matches!(
+            Session::load(&path),
+            Err(RecordingError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_replay_answers_in_recorded_order() {
+        let mut session = Session::start();
+        let params = json!({ "currentDay": 20260301 });
+        session.record(
+            &request(1, "getRecommendation", params.clone()),
+            Ok(&recommendation(1)),
+            None,
+        );
+        session.record(
+            &request(2, "getRecommendation", params.clone()),
+            Ok(&recommendation(2)),
+            None,
+        );
+        let mut replay = ReplayAdvisor::new(session);
+
+        let school =
+            |response: JsonRpcResponse| response.result_value().unwrap()["schoolId"].clone();
+        let first = replay.handle(&request(7, "getRecommendation", params.clone()));
+        assert_eq!(first.id, json!(7));
+        assert_eq!(school(first), json!(1));
+        assert_eq!(
+            school(replay.handle(&request(8, "getRecommendation", params.clone()))),
+            json!(2)
+        );
+        // The last answer is repeated once the recording runs out
+        assert_eq!(
+            school(replay.handle(&request(9, "getRecommendation", params))),
+            json!(2)
+        );
+
+        let unknown = replay.handle(&request(
+            10,
+            "getRecommendation",
+            json!({ "currentDay": 1 }),
+        ));
+        assert_eq!(unknown.error.unwrap().code, NOT_RECORDED);
+    }
+}
@@ -0,0 +1,614 @@
+//! Upcoming payment deadline detection.
+//!
+//! Scans school data (either advisor request params or the stored dataset)
+//! for unpaid enrollment fees and tuition whose deadline falls within a lead
+//! window. Dates are YYYYMMDD integers, matching the Lean advisor.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::calendar::previous_business_day;
+use crate::date::{parse_day, today};
+use crate::handlers::AppState;
+use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::model::{
+    net_amount, tuition_payments, Adjustment, FeeKind, Installment, RecurringPayment,
+};
+use crate::storage::SCHOOLS_DATA_FILE;
+
+fn default_lead_days() -> i64 {
+    3
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_check_interval_minutes() -> u64 {
+    60
+}
+
+/// Reminder settings from `config.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReminderConfig {
+    /// How many days ahead of a deadline to start reminding, until changed
+    /// in the settings
+    #[serde(default = "default_lead_days")]
+    pub lead_days: i64,
+    /// Show OS notifications from the desktop app
+    #[serde(default = "default_true")]
+    pub desktop_notifications: bool,
+    /// How often the desktop scheduler re-reads the saved data
+    #[serde(default = "default_check_interval_minutes")]
+    pub check_interval_minutes: u64,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            lead_days: default_lead_days(),
+            desktop_notifications: default_true(),
+            check_interval_minutes: default_check_interval_minutes(),
+        }
+    }
+}
+
+/// Which payment a deadline belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeadlineKind {
+    EnrollmentFee,
+    Tuition,
+    /// A payment of one of the school's recurring fees
+    Recurring,
+}
+
+/// An unpaid payment whose deadline is within the lead window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpcomingDeadline {
+    pub school_id: u64,
+    pub school_name: String,
+    pub kind: DeadlineKind,
+    pub deadline: u32,
+    pub amount: u64,
+    pub days_left: i64,
+    /// Last day banks are open on or before the deadline
+    #[serde(default)]
+    pub pay_by: u32,
+    /// Label of the recurring fee, for recurring payments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl UpcomingDeadline {
+    /// Name of the fee due, as shown in reminders
+    pub fn fee_name(&self) -> &str {
+        match self.kind {
+            DeadlineKind::EnrollmentFee => "入学金",
+            DeadlineKind::Tuition => "授業料",
+            DeadlineKind::Recurring => self.label.as_deref().unwrap_or("定期納付金"),
+        }
+    }
+}
+
+/// The `key` array of `school`, or an empty list if absent or malformed
+fn list<T: serde::de::DeserializeOwned>(school: &serde_json::Value, key: &str) -> Vec<T> {
+    school
+        .get(key)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Find unpaid deadlines in `data` that fall within `lead_days` of `today`.
+///
+/// The lead time counts to the last day banks are open on or before the
+/// deadline, so a deadline after a weekend or Golden Week is announced as
+/// many days ahead of the day it has to be paid by.
+///
+/// `data` must contain a `schools` array. Payment and pass status are read
+/// from a parallel `states` array when present (advisor params), otherwise
+/// from the school entries themselves (stored dataset). Amounts are after
+/// adjustments, tuition paid in installments has a deadline for each
+/// unpaid installment, and so does each payment generated from the school's
+/// recurring fees. Schools that failed or were cancelled are skipped,
+/// as are deadlines already in the past.
+pub fn upcoming_deadlines(
+    data: &serde_json::Value,
+    today: u32,
+    lead_days: i64,
+) -> Vec<UpcomingDeadline> {
+    let Some(today_date) = parse_day(today) else {
+        return Vec::new();
+    };
+    unpaid_deadlines(data, today)
+        .into_iter()
+        .filter(|d| {
+            let pay_by_left =
+                parse_day(d.pay_by).map_or(d.days_left, |p| (p - today_date).num_days());
+            d.days_left >= 0 && pay_by_left <= lead_days
+        })
+        .collect()
+}
+
+/// Every unpaid deadline in `data`, read as [`upcoming_deadlines`] does,
+/// including those already past, which have negative `days_left`
+pub fn unpaid_deadlines(data: &serde_json::Value, today: u32) -> Vec<UpcomingDeadline> {
+    let Some(today_date) = parse_day(today) else {
+        return Vec::new();
+    };
+    let Some(schools) = data.get("schools").and_then(|s| s.as_array()) else {
+        return Vec::new();
+    };
+    let states = data.get("states").and_then(|s| s.as_array());
+
+    let mut deadlines = Vec::new();
+    for school in schools {
+        let Some(id) = school.get("id").and_then(|v| v.as_u64()) else {
+            continue;
+        };
+        let state = states
+            .and_then(|states| {
+                states
+                    .iter()
+                    .find(|st| st.get("schoolId").and_then(|v| v.as_u64()) == Some(id))
+            })
+            .unwrap_or(school);
+
+        let pass_status = state
+            .get("passStatus")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if pass_status == "failed" || pass_status == "cancelled" {
+            continue;
+        }
+
+        let name = school
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let flag = |key: &str| state.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        let number = |key: &str| school.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        let adjustments: Vec<Adjustment> = list(school, "adjustments");
+        let installments: Vec<Installment> = list(school, "installments");
+        let recurring: Vec<RecurringPayment> = list(school, "recurringPayments");
+
+        let mut items = vec![(
+            DeadlineKind::EnrollmentFee,
+            number("enrollmentFeeDeadline") as u32,
+            net_amount(
+                number("enrollmentFee"),
+                FeeKind::EnrollmentFee,
+                &adjustments,
+            ),
+            flag("enrollmentFeePaid"),
+            None,
+        )];
+        let tuition = net_amount(number("tuition"), FeeKind::Tuition, &adjustments);
+        items.extend(
+            tuition_payments(
+                number("tuitionDeadline") as u32,
+                tuition,
+                flag("tuitionPaid"),
+                &installments,
+            )
+            .into_iter()
+            .map(|p| (DeadlineKind::Tuition, p.due, p.amount, p.paid, None)),
+        );
+        items.extend(recurring.into_iter().map(|p| {
+            (DeadlineKind::Recurring, p.due, p.amount, p.paid, Some(p.label))
+        }));
+
+        for (kind, deadline, amount, paid, label) in items {
+            if paid {
+                continue;
+            }
+            let Some(deadline_date) = parse_day(deadline) else {
+                continue;
+            };
+            deadlines.push(UpcomingDeadline {
+                school_id: id,
+                school_name: name.to_string(),
+                kind,
+                deadline,
+                amount,
+                days_left: (deadline_date - today_date).num_days(),
+                pay_by: previous_business_day(deadline),
+                label,
+            });
+        }
+    }
+
+    deadlines.sort_by_key(|d| (d.deadline, d.school_id));
+    deadlines
+}
+
+/// Contents of a periodic reminder digest
+#[derive(Debug, Clone, Default)]
+pub struct Digest {
+    pub today: u32,
+    pub deadlines: Vec<UpcomingDeadline>,
+    /// The advisor's latest recommendation, if its action changed since the previous digest
+    pub new_recommendation: Option<serde_json::Value>,
+}
+
+impl Digest {
+    /// Check whether there is anything worth sending
+    pub fn is_empty(&self) -> bool {
+        self.deadlines.is_empty() && self.new_recommendation.is_none()
+    }
+}
+
+/// The earliest unpaid deadline in `data` on or after `today`, however far off
+pub fn next_deadline(data: &serde_json::Value, today: u32) -> Option<UpcomingDeadline> {
+    upcoming_deadlines(data, today, i64::MAX).into_iter().next()
+}
+
+/// What the reminder engine has learned from advisor traffic
+#[derive(Debug, Default)]
+pub struct ReminderState {
+    /// Params of the most recent advisor request that carried school data,
+    /// parsed only when a digest needs them
+    pub last_dataset: Option<Box<RawValue>>,
+    /// Result of the most recent `getRecommendation` call
+    pub last_recommendation: Option<serde_json::Value>,
+    digested_action: Option<serde_json::Value>,
+    notified_deadlines: HashSet<(u64, DeadlineKind, u32)>,
+}
+
+impl ReminderState {
+    /// Record an advisor exchange.
+    ///
+    /// Returns the previous and current action when a `getRecommendation`
+    /// call produced a different action than the one before it.
+    pub fn observe(
+        &mut self,
+        request: &JsonRpcRequest,
+        response: &JsonRpcResponse,
+    ) -> Option<(serde_json::Value, serde_json::Value)> {
+        #[derive(Deserialize)]
+        struct Schools {
+            schools: Option<IgnoredAny>,
+        }
+        if request
+            .parse_params::<Schools>()
+            .is_ok_and(|p| p.schools.is_some())
+        {
+            self.last_dataset = Some(request.params.clone());
+        }
+        if request.method != "getRecommendation" {
+            return None;
+        }
+        let result = response.result_value()?;
+        let action = result.get("action")?.clone();
+        let previous = self
+            .last_recommendation
+            .replace(result)
+            .and_then(|r| r.get("action").cloned())?;
+        (previous != action).then_some((previous, action))
+    }
+
+    /// Filter out deadlines that were already announced, remembering the rest
+    pub fn fresh_deadlines(&mut self, deadlines: Vec<UpcomingDeadline>) -> Vec<UpcomingDeadline> {
+        deadlines
+            .into_iter()
+            .filter(|d| {
+                self.notified_deadlines
+                    .insert((d.school_id, d.kind, d.deadline))
+            })
+            .collect()
+    }
+
+    /// Build a digest for `today`, marking the current recommendation as sent.
+    ///
+    /// `fallback` is used as the dataset when no advisor request has been seen yet.
+    pub fn take_digest(
+        &mut self,
+        today: u32,
+        lead_days: i64,
+        fallback: Option<&serde_json::Value>,
+    ) -> Digest {
+        let last_dataset: Option<serde_json::Value> = self
+            .last_dataset
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw.get()).ok());
+        let deadlines = last_dataset
+            .as_ref()
+            .or(fallback)
+            .map(|data| upcoming_deadlines(data, today, lead_days))
+            .unwrap_or_default();
+
+        let current_action = self
+            .last_recommendation
+            .as_ref()
+            .and_then(|r| r.get("action"))
+            .filter(|a| a.get("type").and_then(|t| t.as_str()) != Some("doNothing"));
+        let new_recommendation = match current_action {
+            Some(action) if self.digested_action.as_ref() != Some(action) => {
+                self.digested_action = Some(action.clone());
+                self.last_recommendation.clone()
+            }
+            _ => None,
+        };
+
+        Digest {
+            today,
+            deadlines,
+            new_recommendation,
+        }
+    }
+}
+
+/// Watch the saved data and call `notify` once for each deadline entering the lead window.
+///
+/// Runs forever, re-reading the stored data every `check_interval_minutes`,
+/// so reminders fire even when no advisor request is being made. The lead
+/// window is read from the settings on each check, so a change applies
+/// without a restart.
+pub async fn run_deadline_scheduler<F>(state: Arc<AppState>, notify: F)
+where
+    F: Fn(&UpcomingDeadline) + Send + 'static,
+{
+    let Some(storage) = state.storage.clone() else {
+        return;
+    };
+    let interval = Duration::from_secs(state.config.reminders.check_interval_minutes.max(1) * 60);
+    let mut announced = ReminderState::default();
+
+    loop {
+        let lead_days = i64::from(state.settings.lock().await.reminder_lead_days);
+        match storage.load(SCHOOLS_DATA_FILE) {
+            Ok(Some(data)) => {
+                let deadlines = upcoming_deadlines(&data, today(), lead_days);
+                for deadline in announced.fresh_deadlines(deadlines) {
+                    notify(&deadline);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Deadline scheduler could not read data: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::to_day;
+    use crate::settings::Settings;
+    use crate::storage::Storage;
+    use crate::LeanRepl;
+
+    fn params() -> serde_json::Value {
+        serde_json::json!({
+            "today": 20260301,
+            "schools": [
+                {
+                    "id": 1, "name": "東京大学",
+                    "enrollmentFeeDeadline": 20260317, "tuitionDeadline": 20260331,
+                    "enrollmentFee": 282000, "tuition": 535800
+                },
+                {
+                    "id": 2, "name": "早稲田大学",
+                    "enrollmentFeeDeadline": 20260303, "tuitionDeadline": 20260320,
+                    "enrollmentFee": 200000, "tuition": 800000
+                }
+            ],
+            "states": [
+                {"schoolId": 1, "passStatus": "notYetAnnounced", "enrollmentFeePaid": false, "tuitionPaid": false},
+                {"schoolId": 2, "passStatus": "passed", "enrollmentFeePaid": false, "tuitionPaid": false}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_day_roundtrip() {
+        let date = parse_day(20260229);
+        assert!(date.is_none());
+        assert_eq!(to_day(parse_day(20260301).unwrap()), 20260301);
+    }
+
+    #[test]
+    fn test_upcoming_within_window() {
+        let deadlines = upcoming_deadlines(&params(), 20260301, 3);
+        assert_eq!(deadlines.len(), 1);
+        assert_eq!(deadlines[0].school_id, 2);
+        assert_eq!(deadlines[0].kind, DeadlineKind::EnrollmentFee);
+        assert_eq!(deadlines[0].days_left, 2);
+        assert_eq!(deadlines[0].amount, 200000);
+    }
+
+    #[test]
+    fn test_lead_counts_to_last_business_day() {
+        // Due on Wednesday 5/6 at the end of Golden Week: transfers have to
+        // be made by Friday 5/1, so a 3-day reminder starts on 4/28
+        let data = serde_json::json!({
+            "schools": [{
+                "id": 1,
+                "name": "東京大学",
+                "enrollmentFeeDeadline": 20260506,
+                "enrollmentFee": 282000,
+                "tuitionDeadline": 20260930,
+                "tuition": 535800,
+                "passStatus": "passed"
+            }]
+        });
+        assert!(upcoming_deadlines(&data, 20260427, 3).is_empty());
+        let deadlines = upcoming_deadlines(&data, 20260428, 3);
+        assert_eq!(deadlines.len(), 1);
+        assert_eq!(deadlines[0].pay_by, 20260501);
+        assert_eq!(deadlines[0].days_left, 8);
+
+        // Still reported after the banks close, until the deadline itself
+        assert_eq!(upcoming_deadlines(&data, 20260505, 3).len(), 1);
+        assert!(upcoming_deadlines(&data, 20260507, 3).is_empty());
+    }
+
+    #[test]
+    fn test_paid_and_failed_are_skipped() {
+        let mut data = params();
+        data["states"][1]["enrollmentFeePaid"] = serde_json::json!(true);
+        data["states"][0]["passStatus"] = serde_json::json!("failed");
+        let deadlines = upcoming_deadlines(&data, 20260301, 30);
+        assert_eq!(deadlines.len(), 1);
+        assert_eq!(deadlines[0].kind, DeadlineKind::Tuition);
+        assert_eq!(deadlines[0].school_id, 2);
+    }
+
+    fn exchange(action: &str) -> (JsonRpcRequest, JsonRpcResponse) {
+        let request = JsonRpcRequest::new("getRecommendation", &params(), serde_json::json!(1));
+        let response = JsonRpcResponse::success(
+            serde_json::json!(1),
+            serde_json::json!({"action": {"type": action, "schoolId": 2}, "reason": "期限です"}),
+        );
+        (request, response)
+    }
+
+    #[test]
+    fn test_observe_detects_change() {
+        let mut state = ReminderState::default();
+        let (req, res) = exchange("doNothing");
+        assert!(state.observe(&req, &res).is_none());
+        assert!(state.observe(&req, &res).is_none());
+
+        let (req, res) = exchange("payEnrollmentFee");
+        let (previous, current) = state.observe(&req, &res).unwrap();
+        assert_eq!(previous["type"], "doNothing");
+        assert_eq!(current["type"], "payEnrollmentFee");
+        assert!(state.last_dataset.is_some());
+    }
+
+    #[test]
+    fn test_digest_reports_recommendation_once() {
+        let mut state = ReminderState::default();
+        let (req, res) = exchange("payEnrollmentFee");
+        state.observe(&req, &res);
+
+        let digest = state.take_digest(20260301, 3, None);
+        assert_eq!(digest.deadlines.len(), 1);
+        assert!(digest.new_recommendation.is_some());
+
+        let digest = state.take_digest(20260301, 3, None);
+        assert!(digest.new_recommendation.is_none());
+    }
+
+    #[test]
+    fn test_fresh_deadlines_deduplicated() {
+        let mut state = ReminderState::default();
+        let deadlines = upcoming_deadlines(&params(), 20260301, 3);
+        assert_eq!(state.fresh_deadlines(deadlines.clone()).len(), 1);
+        assert!(state.fresh_deadlines(deadlines).is_empty());
+    }
+
+    #[test]
+    fn test_digest_uses_fallback() {
+        let mut state = ReminderState::default();
+        let digest = state.take_digest(20260301, 3, Some(&params()));
+        assert_eq!(digest.deadlines.len(), 1);
+        assert!(state.take_digest(20260301, 3, None).is_empty());
+    }
+
+    #[test]
+    fn test_inline_state() {
+        let data = serde_json::json!({
+            "schools": [{
+                "id": 3, "name": "慶應義塾大学", "passStatus": "passed",
+                "enrollmentFeeDeadline": 20260305, "tuitionDeadline": 20260305,
+                "enrollmentFee": 200000, "tuition": 900000,
+                "enrollmentFeePaid": true, "tuitionPaid": false
+            }]
+        });
+        let deadlines = upcoming_deadlines(&data, 20260305, 0);
+        assert_eq!(deadlines.len(), 1);
+        assert_eq!(deadlines[0].kind, DeadlineKind::Tuition);
+        assert_eq!(deadlines[0].days_left, 0);
+    }
+
+    #[test]
+    fn test_installments_and_adjustments() {
+        let data = serde_json::json!({
+            "schools": [{
+                "id": 3, "name": "慶應義塾大学", "passStatus": "passed",
+                "enrollmentFeeDeadline": 20260305, "tuitionDeadline": 20260305,
+                "enrollmentFee": 200000, "tuition": 900000,
+                "enrollmentFeePaid": false, "tuitionPaid": true,
+                "adjustments": [{"label": "特待生", "fee": "enrollmentFee", "amount": 200000}],
+                "installments": [
+                    {"due": 20260305, "amount": 450000},
+                    {"due": 20260905, "amount": 450000}
+                ]
+            }]
+        });
+        let deadlines = upcoming_deadlines(&data, 20260301, i64::MAX);
+        assert_eq!(deadlines.len(), 2);
+        assert_eq!(deadlines[0].amount, 0);
+        assert_eq!(deadlines[1].kind, DeadlineKind::Tuition);
+        assert_eq!(deadlines[1].deadline, 20260905);
+        assert_eq!(deadlines[1].amount, 450000);
+    }
+
+    #[test]
+    fn test_recurring_payments() {
+        let data = serde_json::json!({
+            "schools": [{
+                "id": 3, "name": "慶應義塾大学", "passStatus": "passed",
+                "enrollmentFeeDeadline": 20260305, "tuitionDeadline": 20260305,
+                "enrollmentFee": 200000, "tuition": 900000,
+                "enrollmentFeePaid": true, "tuitionPaid": true,
+                "recurringPayments": [
+                    {"label": "施設設備費", "due": 20260410, "amount": 30000, "paid": true},
+                    {"label": "施設設備費", "due": 20260510, "amount": 30000}
+                ]
+            }]
+        });
+        let deadlines = upcoming_deadlines(&data, 20260401, i64::MAX);
+        assert_eq!(deadlines.len(), 1);
+        assert_eq!(deadlines[0].kind, DeadlineKind::Recurring);
+        assert_eq!(deadlines[0].deadline, 20260510);
+        assert_eq!(deadlines[0].fee_name(), "施設設備費");
+    }
+
+    #[test]
+    fn test_next_deadline() {
+        let next = next_deadline(&params(), 20260304).unwrap();
+        assert_eq!(next.school_id, 1);
+        assert_eq!(next.deadline, 20260317);
+        assert!(next_deadline(&params(), 20260401).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_uses_the_lead_days_setting() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let deadline = to_day(parse_day(today()).unwrap() + chrono::Days::new(10));
+        let data = serde_json::json!({
+            "schools": [
+                {"id": 1, "name": "東京大学", "enrollmentFeeDeadline": deadline, "enrollmentFee": 282000}
+            ],
+            "states": [
+                {"schoolId": 1, "passStatus": "passed", "enrollmentFeePaid": false, "tuitionPaid": false}
+            ]
+        });
+        storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+        // The config's 3 days would not reach the deadline
+        let settings = Settings {
+            reminder_lead_days: 14,
+            ..Settings::default()
+        };
+        let repl = LeanRepl::new(std::path::PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl).with_storage(storage).with_settings(settings));
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let scheduler = tokio::spawn(run_deadline_scheduler(state, move |deadline| {
+            sender.send(deadline.deadline).unwrap();
+        }));
+        let notified = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await;
+        scheduler.abort();
+        assert_eq!(notified.unwrap(), Some(deadline));
+    }
+}
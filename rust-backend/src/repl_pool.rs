@@ -0,0 +1,431 @@
+//! Extra advisor processes that come and go with the load.
+//!
+//! The advisor answers one request at a time, so ten what-if scenarios sent
+//! to the main process took ten times as long as one, and on the web server
+//! each user waited for everyone before them. A [`ReplPool`] starts more
+//! processes like the main one when there is more work than processes: the
+//! scenarios of a simulation are shared out between the main process and
+//! the pool through a [`WorkQueue`], and a request arriving while the main
+//! process is busy is answered by a free one.
+//!
+//! The pool never grows beyond [`PoolLimits::max`] processes, nor beyond
+//! what fits in [`PoolLimits::memory_mb`] at [`PROCESS_MEMORY_MB`] each.
+//! Processes left idle for [`PoolLimits::idle_timeout`] are stopped again,
+//! down to [`PoolLimits::min`]. With the default limits the desktop app
+//! runs a single process and bursts to two during a heavy simulation.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinSet;
+
+use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::lean_repl::{LeanRepl, LeanReplError};
+use crate::profiling::PhaseTimings;
+
+/// Most processes a pool can have
+pub const MAX_POOL_SIZE: usize = 8;
+
+/// Memory one advisor process is assumed to use, in megabytes
+pub const PROCESS_MEMORY_MB: u64 = 200;
+
+/// How often idle processes are looked for, at most
+const TRIM_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How far a pool may grow and shrink
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolLimits {
+    /// Processes kept even when idle
+    pub min: usize,
+    /// Most processes at once
+    pub max: usize,
+    /// Processes above `min` idle this long are stopped
+    pub idle_timeout: Duration,
+    /// Memory the processes may use together, in megabytes; 0 for no limit
+    pub memory_mb: u64,
+}
+
+impl PoolLimits {
+    /// Most processes allowed, by both the count and the memory budget
+    pub fn capacity(&self) -> usize {
+        let max = self.max.min(MAX_POOL_SIZE);
+        match self.memory_mb {
+            0 => max,
+            budget => max.min((budget / PROCESS_MEMORY_MB) as usize),
+        }
+    }
+}
+
+/// Requests waiting to be answered, with their positions
+pub type WorkQueue = Arc<Mutex<VecDeque<(usize, JsonRpcRequest)>>>;
+
+/// A queue of `requests`, numbered in order
+pub fn work_queue(requests: &[JsonRpcRequest]) -> WorkQueue {
+    Arc::new(Mutex::new(requests.iter().cloned().enumerate().collect()))
+}
+
+/// Take the next waiting request
+pub fn next(queue: &WorkQueue) -> Option<(usize, JsonRpcRequest)> {
+    queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front()
+}
+
+/// The answer to one request sent through the pool
+#[derive(Debug)]
+pub struct PooledResponse {
+    pub result: Result<JsonRpcResponse, LeanReplError>,
+    /// Time the answering process spent in each phase
+    pub timings: PhaseTimings,
+}
+
+struct Worker {
+    repl: Arc<Mutex<LeanRepl>>,
+    busy: bool,
+    idle_since: Instant,
+}
+
+/// Advisor processes answering requests side by side
+pub struct ReplPool {
+    /// Never started; new processes are made like it. `None` while
+    /// replaying a recorded session, whose answers have a single order.
+    template: Mutex<Option<LeanRepl>>,
+    limits: PoolLimits,
+    workers: Mutex<Vec<Worker>>,
+    /// Whether idle processes are being looked for, from the first growth
+    trimming: AtomicBool,
+}
+
+impl ReplPool {
+    /// A pool starting processes like `repl` within `limits`, with the
+    /// [`PoolLimits::min`] processes it always keeps already running
+    pub fn like(repl: &LeanRepl, limits: PoolLimits) -> Self {
+        let template = repl.sibling();
+        let mut workers = Vec::new();
+        if let Some(template) = &template {
+            let kept = limits.min.min(limits.capacity());
+            while workers.len() < kept {
+                let Some(repl) = template.sibling() else {
+                    break;
+                };
+                workers.push(Worker {
+                    repl: Arc::new(Mutex::new(repl)),
+                    busy: false,
+                    idle_since: Instant::now(),
+                });
+            }
+        }
+        if !workers.is_empty() {
+            tracing::info!("Advisor pool started with {} processes", workers.len());
+        }
+        Self {
+            template: Mutex::new(template),
+            limits,
+            workers: Mutex::new(workers),
+            trimming: AtomicBool::new(false),
+        }
+    }
+
+    pub fn limits(&self) -> PoolLimits {
+        self.limits
+    }
+
+    /// Most processes the pool will run; 0 when it never answers anything
+    pub fn capacity(&self) -> usize {
+        let template = self.template.lock().unwrap_or_else(|e| e.into_inner());
+        if template.is_some() {
+            self.limits.capacity()
+        } else {
+            0
+        }
+    }
+
+    /// Processes running or ready to run
+    pub fn size(&self) -> usize {
+        self.workers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// A free process, starting one if the limits allow
+    fn checkout(self: &Arc<Self>) -> Option<Checkout> {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(worker) = workers.iter_mut().find(|w| !w.busy) {
+            worker.busy = true;
+            return Some(Checkout {
+                pool: self.clone(),
+                repl: worker.repl.clone(),
+            });
+        }
+        if workers.len() >= self.limits.capacity() {
+            return None;
+        }
+        let repl = self
+            .template
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()?
+            .sibling()?;
+        let repl = Arc::new(Mutex::new(repl));
+        workers.push(Worker {
+            repl: repl.clone(),
+            busy: true,
+            idle_since: Instant::now(),
+        });
+        tracing::info!("Advisor pool grown to {} processes", workers.len());
+        if !self.trimming.swap(true, Ordering::Relaxed) {
+            spawn_trimming(self);
+        }
+        Some(Checkout {
+            pool: self.clone(),
+            repl,
+        })
+    }
+
+    /// Hand a process back after answering
+    fn checkin(&self, repl: &Arc<Mutex<LeanRepl>>) {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(worker) = workers.iter_mut().find(|w| Arc::ptr_eq(&w.repl, repl)) {
+            worker.busy = false;
+            worker.idle_since = Instant::now();
+        }
+    }
+
+    /// Stop processes idle longer than the idle timeout, keeping the minimum
+    pub fn trim(&self) {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        let before = workers.len();
+        let mut excess = before.saturating_sub(self.limits.min);
+        workers.retain(|worker| {
+            let stop = excess > 0
+                && !worker.busy
+                && worker.idle_since.elapsed() >= self.limits.idle_timeout;
+            if stop {
+                excess -= 1;
+            }
+            !stop
+        });
+        if workers.len() < before {
+            tracing::info!("Advisor pool shrunk to {} processes", workers.len());
+        }
+    }
+
+    /// Answer `request` in a free process, if there is one or one can be
+    /// started; otherwise the caller waits for the main process
+    pub async fn try_send(self: &Arc<Self>, request: &JsonRpcRequest) -> Option<PooledResponse> {
+        let checkout = self.checkout()?;
+        let request = request.clone();
+        // The process is handed back when the answer is in, even if the
+        // caller has stopped waiting for it
+        let answered = tokio::task::spawn_blocking(move || {
+            let mut timings = PhaseTimings::default();
+            let result = checkout
+                .repl
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .send_request_timed(&request, &mut timings);
+            PooledResponse { result, timings }
+        })
+        .await;
+        Some(answered.unwrap_or_else(|e| worker_failed(e.to_string())))
+    }
+
+    /// Answer requests from `queue` in up to `processes` free processes,
+    /// starting them as needed, until it is empty. Each takes the next
+    /// waiting request as soon as it has answered the last, so a slow
+    /// request does not hold up the others. Returns the answers with their
+    /// positions, none if no process was free.
+    pub async fn drain(
+        self: &Arc<Self>,
+        queue: &WorkQueue,
+        processes: usize,
+    ) -> Vec<(usize, PooledResponse)> {
+        // All taken before any starts, so none is handed out twice
+        let checkouts: Vec<_> = std::iter::from_fn(|| self.checkout())
+            .take(processes)
+            .collect();
+        let mut tasks = JoinSet::new();
+        for checkout in checkouts {
+            let queue = queue.clone();
+            tasks.spawn_blocking(move || {
+                let mut answered = Vec::new();
+                let mut worker = checkout.repl.lock().unwrap_or_else(|e| e.into_inner());
+                while let Some((index, request)) = next(&queue) {
+                    let mut timings = PhaseTimings::default();
+                    let result = worker.send_request_timed(&request, &mut timings);
+                    answered.push((index, PooledResponse { result, timings }));
+                }
+                answered
+            });
+        }
+
+        let mut answered = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(responses) => answered.extend(responses),
+                // The requests it had taken are reported as unanswered
+                Err(e) => tracing::error!("Advisor pool worker failed: {}", e),
+            }
+        }
+        answered
+    }
+}
+
+/// A process taken from the pool, handed back when dropped: after
+/// answering, when a worker panics, or when the caller is cancelled
+struct Checkout {
+    pool: Arc<ReplPool>,
+    repl: Arc<Mutex<LeanRepl>>,
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        self.pool.checkin(&self.repl);
+    }
+}
+
+fn worker_failed(reason: String) -> PooledResponse {
+    PooledResponse {
+        result: Err(LeanReplError::ReceiveFailed(reason)),
+        timings: PhaseTimings::default(),
+    }
+}
+
+/// Stop idle processes of `pool` in the background for as long as it exists
+fn spawn_trimming(pool: &Arc<ReplPool>) {
+    let interval = pool
+        .limits
+        .idle_timeout
+        .clamp(Duration::from_secs(1), TRIM_INTERVAL);
+    let pool: Weak<ReplPool> = Arc::downgrade(pool);
+    tokio::spawn(async move {
+        let first = tokio::time::Instant::now() + interval;
+        let mut ticks = tokio::time::interval_at(first, interval);
+        loop {
+            ticks.tick().await;
+            match pool.upgrade() {
+                Some(pool) => pool.trim(),
+                None => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::{ReplayAdvisor, Session};
+
+    fn limits(min: usize, max: usize) -> PoolLimits {
+        PoolLimits {
+            min,
+            max,
+            idle_timeout: Duration::from_secs(3600),
+            memory_mb: 0,
+        }
+    }
+
+    fn version(id: usize) -> JsonRpcRequest {
+        JsonRpcRequest::new("getVersion", &serde_json::json!({}), serde_json::json!(id))
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_drain_grows_and_answers_every_request() {
+        let pool = Arc::new(ReplPool::like(&LeanRepl::demo(), limits(0, 3)));
+        assert_eq!(pool.size(), 0);
+
+        let requests: Vec<JsonRpcRequest> = (0..10).map(version).collect();
+        let mut answered = pool.drain(&work_queue(&requests), 5).await;
+        assert_eq!(pool.size(), 3);
+        answered.sort_by_key(|(index, _)| *index);
+        assert_eq!(answered.len(), 10);
+        for (i, (index, response)) in answered.iter().enumerate() {
+            assert_eq!(*index, i);
+            let response = response.result.as_ref().unwrap();
+            assert_eq!(response.id, serde_json::json!(i));
+        }
+
+        // Processes already running are reused
+        pool.drain(&work_queue(&requests[..1]), 1).await;
+        assert_eq!(pool.size(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_idle_processes_are_stopped_down_to_min() {
+        let idle = PoolLimits {
+            idle_timeout: Duration::ZERO,
+            ..limits(1, 4)
+        };
+        let pool = Arc::new(ReplPool::like(&LeanRepl::demo(), idle));
+        let requests: Vec<JsonRpcRequest> = (0..4).map(version).collect();
+        pool.drain(&work_queue(&requests), 4).await;
+        assert_eq!(pool.size(), 4);
+
+        pool.trim();
+        assert_eq!(pool.size(), 1);
+        assert!(pool.try_send(&version(5)).await.unwrap().result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_min_processes_start_with_the_pool() {
+        let pool = Arc::new(ReplPool::like(&LeanRepl::demo(), limits(2, 4)));
+        assert_eq!(pool.size(), 2);
+        assert!(pool.try_send(&version(1)).await.unwrap().result.is_ok());
+        assert_eq!(pool.size(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_process_is_handed_back_on_cancel_and_panic() {
+        let pool = Arc::new(ReplPool::like(&LeanRepl::demo(), limits(1, 1)));
+        let repl = pool.workers.lock().unwrap()[0].repl.clone();
+
+        // A caller that stops waiting while the process is still answering
+        let (locked, is_locked) = std::sync::mpsc::channel();
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let answering = std::thread::spawn(move || {
+            let _busy = repl.lock().unwrap();
+            locked.send(()).unwrap();
+            released.recv().ok();
+        });
+        is_locked.recv().unwrap();
+        let request = version(1);
+        let cancelled = tokio::time::timeout(Duration::ZERO, pool.try_send(&request)).await;
+        assert!(cancelled.is_err());
+        assert!(pool.checkout().is_none());
+        release.send(()).unwrap();
+        answering.join().unwrap();
+        let checkout = loop {
+            match pool.checkout() {
+                Some(checkout) => break checkout,
+                None => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        assert!(pool.checkout().is_none());
+        drop(checkout);
+
+        // A worker that panics while holding the process
+        let held = pool.checkout().unwrap();
+        let failed = tokio::task::spawn_blocking(move || {
+            let _held = held;
+            panic!("advisor worker failed");
+        })
+        .await;
+        assert!(failed.is_err());
+        assert!(pool.try_send(&version(2)).await.unwrap().result.is_ok());
+        assert_eq!(pool.size(), 1);
+    }
+
+    #[test]
+    fn test_capacity_follows_limits_and_memory() {
+        let pool = ReplPool::like(&LeanRepl::demo(), limits(0, 100));
+        assert_eq!(pool.capacity(), MAX_POOL_SIZE);
+        let budget = PoolLimits {
+            memory_mb: 3 * PROCESS_MEMORY_MB + 1,
+            ..limits(0, 8)
+        };
+        assert_eq!(budget.capacity(), 3);
+
+        let replay = LeanRepl::replay(ReplayAdvisor::new(Session::start()));
+        let pool = Arc::new(ReplPool::like(&replay, limits(0, 4)));
+        assert_eq!(pool.capacity(), 0);
+        assert!(pool.checkout().is_none());
+    }
+}
@@ -0,0 +1,203 @@
+//! Advisor results kept across launches.
+//!
+//! Each week of recommendations the advisor computes is saved in
+//! [`RESULT_CACHE_FILE`] under the revision of the data it was computed
+//! from, a hash of the school and state fields the advisor reads, and the
+//! week's first day. When the app is launched again, the frontend shows the
+//! saved result for its request at once, marked with how long ago it was
+//! computed, while the advisor computes a fresh one in the background.
+//! The request parameters are kept with each result, so what the advisor
+//! was given can be looked at later.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use ts_rs::TS;
+
+use crate::handlers::WEEKLY_REPORT_DAYS;
+use crate::json_rpc::JsonRpcRequest;
+use crate::preload::advisor_inputs;
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding the saved results
+pub const RESULT_CACHE_FILE: &str = "result_cache.json";
+
+/// Results kept; the oldest are dropped first
+pub const MAX_ENTRIES: usize = 20;
+
+/// Method whose results are saved
+const CACHED_METHOD: &str = "getWeeklyRecommendations";
+
+/// Revision of the data and first day of the week `request` asks about, for
+/// week-long `getWeeklyRecommendations` requests
+fn key(request: &JsonRpcRequest) -> Option<(String, u32)> {
+    if request.method != CACHED_METHOD {
+        return None;
+    }
+    let params = request.params_value();
+    if params.get("days")?.as_u64()? != u64::from(WEEKLY_REPORT_DAYS) {
+        return None;
+    }
+    let week = u32::try_from(params.get("startDay")?.as_u64()?).ok()?;
+    let revision = hex::encode(Sha256::digest(advisor_inputs(&params).to_string()));
+    Some((revision, week))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedEntry {
+    revision: String,
+    week: u32,
+    /// RFC 3339
+    computed_at: String,
+    result: Value,
+    /// Parameters of the request; missing in files written before them
+    #[serde(default)]
+    params: Value,
+}
+
+/// A saved advisor result with how old it is
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CachedResult {
+    #[ts(type = "unknown")]
+    pub result: Value,
+    /// When the advisor computed the result (RFC 3339)
+    pub computed_at: String,
+    /// Seconds since then
+    #[ts(type = "number")]
+    pub age_secs: u64,
+    /// Parameters the advisor computed the result from
+    #[ts(type = "unknown")]
+    pub params: Value,
+}
+
+/// Saved results, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultCache {
+    entries: Vec<CachedEntry>,
+}
+
+impl ResultCache {
+    /// Load saved results, or none if nothing has been saved
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(RESULT_CACHE_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(RESULT_CACHE_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// The saved result of `request`, aged as of `now` (RFC 3339)
+    pub fn get(&self, request: &JsonRpcRequest, now: &str) -> Option<CachedResult> {
+        let (revision, week) = key(request)?;
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.revision == revision && e.week == week)?;
+        let age = match (
+            DateTime::parse_from_rfc3339(&entry.computed_at),
+            DateTime::parse_from_rfc3339(now),
+        ) {
+            (Ok(computed), Ok(now)) => (now - computed).num_seconds().max(0) as u64,
+            _ => 0,
+        };
+        Some(CachedResult {
+            result: entry.result.clone(),
+            computed_at: entry.computed_at.clone(),
+            age_secs: age,
+            params: entry.params.clone(),
+        })
+    }
+
+    /// Keep the advisor's `result` of `request`, computed at `computed_at`,
+    /// dropping the oldest beyond [`MAX_ENTRIES`]. Returns whether the
+    /// request's result is saved at all.
+    pub fn insert(&mut self, request: &JsonRpcRequest, result: Value, computed_at: &str) -> bool {
+        let Some((revision, week)) = key(request) else {
+            return false;
+        };
+        self.entries
+            .retain(|e| !(e.revision == revision && e.week == week));
+        self.entries.push(CachedEntry {
+            revision,
+            week,
+            computed_at: computed_at.to_string(),
+            result,
+            params: request.params_value(),
+        });
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn weekly(start_day: u32, paid: bool, id: u64) -> JsonRpcRequest {
+        let params = json!({
+            "startDay": start_day,
+            "days": 7,
+            "schools": [{ "id": 1, "name": "A", "tuition": 800000 }],
+            "states": [{ "schoolId": 1, "passStatus": "passed", "tuitionPaid": paid }],
+        });
+        JsonRpcRequest::new(CACHED_METHOD, &params, json!(id))
+    }
+
+    #[test]
+    fn test_results_are_keyed_by_revision_and_week() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let mut cache = ResultCache::default();
+        let result = json!({ "recommendations": [] });
+        assert!(cache.insert(
+            &weekly(20260301, false, 1),
+            result.clone(),
+            "2026-03-01T09:00:00+09:00"
+        ));
+        cache.save(&storage).unwrap();
+
+        let cache = ResultCache::load(&storage).unwrap();
+        let cached = cache
+            .get(&weekly(20260301, false, 2), "2026-03-01T12:00:00+09:00")
+            .unwrap();
+        assert_eq!(cached.result, result);
+        assert_eq!(cached.age_secs, 3 * 3600);
+        assert_eq!(cached.params, weekly(20260301, false, 1).params_value());
+        // Another week, or changed data, was never computed
+        assert!(cache
+            .get(&weekly(20260308, false, 3), "2026-03-01T12:00:00+09:00")
+            .is_none());
+        assert!(cache
+            .get(&weekly(20260301, true, 4), "2026-03-01T12:00:00+09:00")
+            .is_none());
+    }
+
+    #[test]
+    fn test_only_weekly_requests_are_kept() {
+        let mut cache = ResultCache::default();
+        let now = "2026-03-01T09:00:00+09:00";
+        let version = JsonRpcRequest::new("getVersion", &json!({}), json!(1));
+        assert!(!cache.insert(&version, json!("1.0.0"), now));
+
+        let mut long = weekly(20260301, false, 1).params_value();
+        long["days"] = json!(120);
+        let long = JsonRpcRequest::new(CACHED_METHOD, &long, json!(2));
+        assert!(!cache.insert(&long, json!({}), now));
+
+        for week in 0..MAX_ENTRIES as u32 + 5 {
+            cache.insert(&weekly(20260301 + week, false, 3), json!({}), now);
+        }
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert!(cache.get(&weekly(20260301, false, 4), now).is_none());
+    }
+}
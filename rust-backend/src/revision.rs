@@ -0,0 +1,115 @@
+//! Revision numbers of the saved datasets.
+//!
+//! Every dataset file carries a `revision` that [`Storage`] raises by one
+//! each time the file is written, by whichever window, server or command
+//! writes it. A client that loaded revision 7 sends 7 back as the expected
+//! revision when it saves; if the file has moved on since, the save fails
+//! with a [`RevisionConflict`] holding the current revision and what the
+//! stale save would have changed, instead of silently overwriting the other
+//! client's edits. Saves without an expected revision always go through.
+//!
+//! [`Storage`]: crate::storage::Storage
+
+use serde::Serialize;
+use serde_json::Value;
+use ts_rs::TS;
+
+use crate::diff::{self, DatasetDiff};
+
+/// Top-level field of a dataset file holding its revision
+pub const REVISION_KEY: &str = "revision";
+
+/// Revision of the stored dataset `data`; 0 for files written before
+/// revisions were kept
+pub fn of(data: &Value) -> u64 {
+    data.get(REVISION_KEY).and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// A save based on a revision that is no longer the latest
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RevisionConflict {
+    /// Revision the client loaded
+    #[ts(type = "number")]
+    pub expected: u64,
+    /// Revision saved now
+    #[ts(type = "number")]
+    pub current: u64,
+    /// What the stale save would change in the current data
+    pub diff: DatasetDiff,
+}
+
+impl RevisionConflict {
+    /// Conflict of saving `data` over `current`, which has moved past
+    /// `expected`
+    pub fn new(expected: u64, current: &Value, data: &Value) -> Self {
+        Self {
+            expected,
+            current: of(current),
+            diff: diff::diff(Some(current), data),
+        }
+    }
+}
+
+/// Apply a JSON merge patch (RFC 7386) to `target`: objects are merged
+/// key by key, `null` removes a key and anything else replaces the value
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target) = target else {
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.as_str()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_patch() {
+        let mut data = json!({
+            "revision": 3,
+            "budget": { "total": 1000000, "note": "貯金" },
+            "schools": [{ "id": 1 }]
+        });
+        merge_patch(
+            &mut data,
+            &json!({ "budget": { "total": 1200000, "note": null }, "schools": [] }),
+        );
+        assert_eq!(
+            data,
+            json!({ "revision": 3, "budget": { "total": 1200000 }, "schools": [] })
+        );
+        assert_eq!(of(&data), 3);
+        assert_eq!(of(&json!({})), 0);
+    }
+
+    #[test]
+    fn test_conflict_reports_the_stale_changes() {
+        let current = json!({
+            "revision": 5,
+            "schools": [{ "id": 1, "name": "東京大学", "tuition": 535800 }]
+        });
+        let stale = json!({
+            "revision": 4,
+            "schools": [{ "id": 1, "name": "東京大学", "tuition": 500000 }]
+        });
+        let conflict = RevisionConflict::new(4, &current, &stale);
+        assert_eq!((conflict.expected, conflict.current), (4, 5));
+        assert_eq!(conflict.diff.changed[0].fields[0].field, "tuition");
+    }
+}
@@ -0,0 +1,296 @@
+//! Synthetic school data for onboarding and demos.
+//!
+//! Dates are placed relative to the day the data is generated, so a fresh
+//! sample always has results pending and payment deadlines coming up for
+//! the advisor to reason about. School names are fictional.
+
+use chrono::Days;
+use serde::Deserialize;
+
+use crate::date::{parse_day, to_day};
+use crate::migrations::CURRENT_SCHEMA_VERSION;
+use crate::model::{Currency, Dataset, PassStatus, School};
+
+/// Kind of exam season to simulate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SampleProfile {
+    /// University entrance exams spread over a month
+    #[default]
+    University,
+    /// Junior high school exams packed into a few days
+    JuniorHigh,
+    /// Two schools, for trying the app out
+    Minimal,
+}
+
+impl SampleProfile {
+    const ALL: [SampleProfile; 3] = [Self::University, Self::JuniorHigh, Self::Minimal];
+
+    /// Short name used on the command line and in URLs
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::University => "university",
+            Self::JuniorHigh => "juniorHigh",
+            Self::Minimal => "minimal",
+        }
+    }
+
+    /// Look up a profile by name, case-insensitively
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// A sample school, with dates as day offsets from generation
+struct Template {
+    name: &'static str,
+    exam: i64,
+    result: i64,
+    enrollment_fee_deadline: i64,
+    tuition_deadline: i64,
+    enrollment_fee: u64,
+    tuition: u64,
+    pass_status: PassStatus,
+}
+
+const fn template(
+    name: &'static str,
+    [exam, result, enrollment_fee_deadline, tuition_deadline]: [i64; 4],
+    enrollment_fee: u64,
+    tuition: u64,
+    pass_status: PassStatus,
+) -> Template {
+    Template {
+        name,
+        exam,
+        result,
+        enrollment_fee_deadline,
+        tuition_deadline,
+        enrollment_fee,
+        tuition,
+        pass_status,
+    }
+}
+
+const UNIVERSITY: [Template; 5] = [
+    template(
+        "青葉国立大学",
+        [10, 25, 30, 45],
+        282000,
+        535800,
+        PassStatus::NotYetAnnounced,
+    ),
+    template(
+        "桜ヶ丘大学",
+        [-5, 3, 12, 26],
+        200000,
+        1000000,
+        PassStatus::NotYetAnnounced,
+    ),
+    template(
+        "港南工科大学",
+        [-8, -1, 9, 24],
+        250000,
+        900000,
+        PassStatus::Passed,
+    ),
+    template(
+        "緑川大学",
+        [-14, -6, 4, 20],
+        230000,
+        800000,
+        PassStatus::Passed,
+    ),
+    template(
+        "白浜学院大学",
+        [-12, -3, 7, 21],
+        220000,
+        750000,
+        PassStatus::Failed,
+    ),
+];
+
+const JUNIOR_HIGH: [Template; 6] = [
+    template(
+        "北杜学園中学校",
+        [7, 8, 10, 40],
+        320000,
+        480000,
+        PassStatus::NotYetAnnounced,
+    ),
+    template(
+        "若葉女子中学校",
+        [7, 7, 9, 30],
+        300000,
+        450000,
+        PassStatus::NotYetAnnounced,
+    ),
+    template(
+        "東雲中学校",
+        [8, 9, 11, 40],
+        280000,
+        500000,
+        PassStatus::NotYetAnnounced,
+    ),
+    template(
+        "朝霧学院中学校",
+        [9, 9, 12, 35],
+        250000,
+        420000,
+        PassStatus::NotYetAnnounced,
+    ),
+    template(
+        "星見台中学校",
+        [-20, -18, 5, 35],
+        200000,
+        400000,
+        PassStatus::Passed,
+    ),
+    template(
+        "川辺中学校",
+        [-25, -24, -10, 30],
+        150000,
+        360000,
+        PassStatus::Passed,
+    ),
+];
+
+const MINIMAL: [Template; 2] = [
+    template(
+        "青葉国立大学",
+        [10, 25, 30, 45],
+        282000,
+        535800,
+        PassStatus::NotYetAnnounced,
+    ),
+    template(
+        "桜ヶ丘大学",
+        [-5, 3, 12, 26],
+        200000,
+        1000000,
+        PassStatus::NotYetAnnounced,
+    ),
+];
+
+/// Shift a YYYYMMDD day by `offset` days
+fn offset_day(today: u32, offset: i64) -> u32 {
+    let Some(date) = parse_day(today) else {
+        return today;
+    };
+    let days = Days::new(offset.unsigned_abs());
+    let shifted = if offset < 0 {
+        date.checked_sub_days(days)
+    } else {
+        date.checked_add_days(days)
+    };
+    shifted.map(to_day).unwrap_or(today)
+}
+
+/// Sample schools for `profile`, with dates around `today`
+pub fn sample_schools(profile: SampleProfile, today: u32) -> Vec<School> {
+    let templates: &[Template] = match profile {
+        SampleProfile::University => &UNIVERSITY,
+        SampleProfile::JuniorHigh => &JUNIOR_HIGH,
+        SampleProfile::Minimal => &MINIMAL,
+    };
+    templates
+        .iter()
+        .enumerate()
+        .map(|(index, t)| {
+            // A school whose enrollment fee deadline has passed was enrolled in
+            let enrollment_fee_paid =
+                t.pass_status == PassStatus::Passed && t.enrollment_fee_deadline < 0;
+            School {
+                id: index as u64 + 1,
+                name: t.name.to_string(),
+                priority: index as u32 + 1,
+                exam_date: offset_day(today, t.exam),
+                result_date: offset_day(today, t.result),
+                enrollment_fee_deadline: offset_day(today, t.enrollment_fee_deadline),
+                tuition_deadline: offset_day(today, t.tuition_deadline),
+                enrollment_fee: t.enrollment_fee,
+                tuition: t.tuition,
+                application_fee: None,
+                currency: Currency::Jpy,
+                pass_status: t.pass_status,
+                enrollment_fee_paid,
+                tuition_paid: false,
+                payee: None,
+                installments: Vec::new(),
+                adjustments: Vec::new(),
+                child_id: None,
+                receipts: Vec::new(),
+                recurring_fees: Vec::new(),
+                recurring_payments: Vec::new(),
+                waitlisted: false,
+                payment_method: None,
+                notes: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// A dataset in the stored format with sample schools around `today`
+pub fn sample_dataset(profile: SampleProfile, today: u32) -> Dataset {
+    Dataset {
+        schema_version: CURRENT_SCHEMA_VERSION,
+        revision: 0,
+        schools: sample_schools(profile, today),
+        budget: None,
+        children: Vec::new(),
+        method_fees: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::schedule_entries;
+    use crate::import;
+
+    #[test]
+    fn test_offset_day() {
+        assert_eq!(offset_day(20260228, 1), 20260301);
+        assert_eq!(offset_day(20260301, -1), 20260228);
+        assert_eq!(offset_day(20261231, 0), 20261231);
+    }
+
+    #[test]
+    fn test_sample_dataset_is_valid() {
+        for profile in SampleProfile::ALL {
+            let data = sample_dataset(profile, 20260201).to_value();
+            let parsed = import::parse_file("sample.json", data.to_string().as_bytes()).unwrap();
+            assert!(
+                parsed.issues.is_empty(),
+                "{:?}: {:?}",
+                profile,
+                parsed.issues
+            );
+            assert_eq!(parsed.schools.len(), schedule_entries(&data).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_dates_are_staggered_around_today() {
+        let schools = sample_schools(SampleProfile::University, 20260201);
+        assert_eq!(schools[0].exam_date, 20260211);
+        assert!(schools.iter().any(|s| s.result_date > 20260201));
+        assert!(schools
+            .iter()
+            .all(|s| s.exam_date <= s.result_date && s.result_date <= s.tuition_deadline));
+    }
+
+    #[test]
+    fn test_profile_names() {
+        assert_eq!(
+            SampleProfile::from_name("JUNIORHIGH"),
+            Some(SampleProfile::JuniorHigh)
+        );
+        assert_eq!(SampleProfile::from_name("demo"), None);
+        let profile: SampleProfile = serde_json::from_str("\"minimal\"").unwrap();
+        assert_eq!(profile, SampleProfile::Minimal);
+    }
+}
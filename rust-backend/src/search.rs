@@ -0,0 +1,209 @@
+//! Full-text search over the saved schools.
+//!
+//! [`search`] looks for every word of a query in school names, notes and
+//! the memo fields kept with payments: receipt numbers, adjustment labels
+//! and recurring fee labels. Text is compared the way imports compare
+//! school names, without case, spacing, punctuation or full-width letters,
+//! so `ワセダ 寮` finds a note saying `ワセダ：寮の申込`.
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::import::normalize_name;
+use crate::model::{Dataset, FeeKind, School};
+
+/// Most hits returned for one query
+pub const MAX_HITS: usize = 100;
+
+/// Where a search hit was found, in the order hits are listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SearchField {
+    SchoolName,
+    Note,
+    /// Transfer or receipt number of a payment made
+    Reference,
+    /// Label of a scholarship or discount
+    Adjustment,
+    /// Label of a recurring fee
+    RecurringFee,
+}
+
+/// A school field matching a search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SearchHit {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    pub field: SearchField,
+    /// The matching text, as saved
+    pub text: String,
+    /// Position in the school's notes, for a note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub note: Option<usize>,
+    /// Payment the note, receipt or adjustment belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub fee: Option<FeeKind>,
+    /// 1-based installment number, for tuition paid in parts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub installment: Option<u32>,
+}
+
+/// Hits for `query` in `dataset`, school names first, then by field and
+/// school order; at most [`MAX_HITS`]. An empty query finds nothing.
+pub fn search(dataset: &Dataset, query: &str) -> Vec<SearchHit> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| normalize_name(term).into_iter().collect::<String>())
+        .filter(|term| !term.is_empty())
+        .collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let matches = |text: &str| {
+        let text: String = normalize_name(text).into_iter().collect();
+        terms.iter().all(|term| text.contains(term.as_str()))
+    };
+
+    let mut hits: Vec<SearchHit> = dataset
+        .schools
+        .iter()
+        .flat_map(fields)
+        .filter(|hit| matches(&hit.text))
+        .collect();
+    // Stable, so hits in one field stay in school order
+    hits.sort_by_key(|hit| hit.field);
+    hits.truncate(MAX_HITS);
+    hits
+}
+
+/// Every searchable field of `school`
+fn fields(school: &School) -> Vec<SearchHit> {
+    let hit = |field, text: &str| SearchHit {
+        school_id: school.id,
+        school_name: school.name.clone(),
+        field,
+        text: text.to_string(),
+        note: None,
+        fee: None,
+        installment: None,
+    };
+
+    let mut fields = vec![hit(SearchField::SchoolName, &school.name)];
+    for (index, note) in school.notes.iter().enumerate() {
+        fields.push(SearchHit {
+            note: Some(index),
+            fee: note.fee,
+            installment: note.installment,
+            ..hit(SearchField::Note, &note.text)
+        });
+    }
+    for receipt in &school.receipts {
+        if let Some(reference) = &receipt.reference {
+            fields.push(SearchHit {
+                fee: Some(receipt.fee),
+                installment: receipt.installment,
+                ..hit(SearchField::Reference, reference)
+            });
+        }
+    }
+    for adjustment in &school.adjustments {
+        fields.push(SearchHit {
+            fee: Some(adjustment.fee),
+            ..hit(SearchField::Adjustment, &adjustment.label)
+        });
+    }
+    for fee in &school.recurring_fees {
+        fields.push(hit(SearchField::RecurringFee, &fee.label));
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset() -> Dataset {
+        Dataset::from_value(serde_json::json!({
+            "schools": [{
+                "id": 1,
+                "name": "早稲田大学",
+                "examDate": 20260212,
+                "resultDate": 20260220,
+                "enrollmentFeeDeadline": 20260227,
+                "tuitionDeadline": 20260313,
+                "enrollmentFee": 200000,
+                "tuition": 800000,
+                "notes": [
+                    { "text": "寮の申込は3月", "writtenAt": "2026-02-20T10:00:00+09:00" },
+                    {
+                        "text": "振込は早稲田の窓口で確認済み",
+                        "writtenAt": "2026-02-21T10:00:00+09:00",
+                        "fee": "enrollmentFee"
+                    }
+                ],
+                "receipts": [{
+                    "fee": "enrollmentFee",
+                    "paidOn": 20260225,
+                    "method": "bankTransfer",
+                    "reference": "ＡＢＣ-123"
+                }]
+            }, {
+                "id": 2,
+                "name": "慶應義塾大学",
+                "examDate": 20260215,
+                "resultDate": 20260224,
+                "enrollmentFeeDeadline": 20260303,
+                "tuitionDeadline": 20260318,
+                "enrollmentFee": 200000,
+                "tuition": 900000,
+                "adjustments": [
+                    { "label": "特待生奨学金", "fee": "tuition", "amount": 300000 }
+                ],
+                "recurringFees": [{
+                    "label": "寮費",
+                    "amount": 50000,
+                    "firstDue": 20260425,
+                    "intervalMonths": 1
+                }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_search_fields() {
+        let dataset = dataset();
+
+        // School names come before notes
+        let hits = search(&dataset, "早稲田");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].field, SearchField::SchoolName);
+        assert_eq!(hits[1].field, SearchField::Note);
+        assert_eq!(hits[1].note, Some(1));
+        assert_eq!(hits[1].fee, Some(FeeKind::EnrollmentFee));
+
+        let hits = search(&dataset, "寮");
+        let found: Vec<_> = hits.iter().map(|h| (h.school_id, h.field)).collect();
+        assert_eq!(
+            found,
+            [(1, SearchField::Note), (2, SearchField::RecurringFee)]
+        );
+
+        // Every word must match, without case, spacing or full width
+        assert_eq!(search(&dataset, "寮 3月").len(), 1);
+        assert!(search(&dataset, "寮 4月").is_empty());
+        let hits = search(&dataset, "abc123");
+        assert_eq!(hits[0].field, SearchField::Reference);
+        assert_eq!(hits[0].text, "ＡＢＣ-123");
+        assert_eq!(search(&dataset, "奨学金")[0].school_name, "慶應義塾大学");
+
+        assert!(search(&dataset, "  ").is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+//! End-to-end self-test for the Help → Diagnostics screen.
+//!
+//! [`run`] goes through the whole stack the way a real request would:
+//! it pings the advisor, asks it for a recommendation on a canned scenario
+//! whose answer is known, and writes, reads back and deletes a probe file
+//! in the data directory. Each check is timed and reported on its own, so
+//! a failure points at the part that is broken. Nothing the user sees is
+//! touched: the canned request bypasses history and reminders, and the
+//! probe file is removed again.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::handlers::{self, AppState};
+use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::storage::Storage;
+
+/// Probe file written to the data directory
+pub const PROBE_FILE: &str = ".selftest.json";
+
+/// Day of the canned recommendation request: Waseda's enrollment fee
+/// deadline, while Tokyo has not announced its results
+const CANNED_TODAY: u32 = 20260306;
+
+/// Checks, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum SelfTestStep {
+    AdvisorPing,
+    Recommendation,
+    Storage,
+}
+
+impl SelfTestStep {
+    const ORDER: [SelfTestStep; 3] = [
+        SelfTestStep::AdvisorPing,
+        SelfTestStep::Recommendation,
+        SelfTestStep::Storage,
+    ];
+
+    /// Label shown on the diagnostics screen
+    fn label(self) -> &'static str {
+        match self {
+            Self::AdvisorPing => "アドバイザーとの接続",
+            Self::Recommendation => "推奨アクションの計算",
+            Self::Storage => "データフォルダへの読み書き",
+        }
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Not run, because an earlier check failed or it does not apply
+    Skipped,
+}
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SelfTestCheck {
+    pub step: SelfTestStep,
+    pub label: String,
+    pub status: CheckStatus,
+    /// What was checked, or why it failed or was skipped
+    pub detail: String,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+}
+
+/// Result of the whole self-test
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SelfTestReport {
+    /// Whether no check failed
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+/// `getRecommendation` request for the canned scenario, two of the
+/// sample schools on the day Waseda's enrollment fee is due
+fn canned_request() -> JsonRpcRequest {
+    let data = serde_json::json!({
+        "schools": [
+            {
+                "id": 1, "name": "東京大学", "priority": 1,
+                "examDate": 20260225, "resultDate": 20260310,
+                "enrollmentFeeDeadline": 20260317, "tuitionDeadline": 20260331,
+                "enrollmentFee": 282000, "tuition": 535800,
+                "passStatus": "notYetAnnounced", "enrollmentFeePaid": false, "tuitionPaid": false
+            },
+            {
+                "id": 2, "name": "早稲田大学", "priority": 2,
+                "examDate": 20260215, "resultDate": 20260301,
+                "enrollmentFeeDeadline": 20260306, "tuitionDeadline": 20260324,
+                "enrollmentFee": 200000, "tuition": 800000,
+                "passStatus": "passed", "enrollmentFeePaid": false, "tuitionPaid": false
+            }
+        ]
+    });
+    let mut params = handlers::advisor_params(&data);
+    params["today"] = CANNED_TODAY.into();
+    JsonRpcRequest::new("getRecommendation", &params, serde_json::json!("self-test"))
+}
+
+/// Action the advisor must recommend for [`canned_request`]: Waseda's
+/// fee is due today and Tokyo, the only school ranked above it, has not
+/// passed, so the fee cannot wait
+fn expected_action() -> serde_json::Value {
+    serde_json::json!({ "type": "payEnrollmentFee", "schoolId": 2 })
+}
+
+/// Check the advisor's answer to [`canned_request`]
+fn check_recommendation(response: &JsonRpcResponse) -> Result<String, String> {
+    if let Some(error) = &response.error {
+        return Err(format!("Advisor error: {}", error.message));
+    }
+    let action = response
+        .result_value()
+        .and_then(|r| r.get("action").cloned())
+        .ok_or_else(|| "Response has no action".to_string())?;
+    if action != expected_action() {
+        return Err(format!("Expected {} but got {}", expected_action(), action));
+    }
+    Ok(format!("Recommended {} as expected", action))
+}
+
+/// Write, read back and delete [`PROBE_FILE`]
+fn check_storage(storage: &Storage) -> Result<String, String> {
+    let probe = serde_json::json!({ "probe": crate::date::now().to_rfc3339() });
+    storage
+        .save(PROBE_FILE, &probe)
+        .map_err(|e| format!("Cannot write: {}", e))?;
+    let read = storage
+        .load(PROBE_FILE)
+        .map_err(|e| format!("Cannot read: {}", e));
+    let deleted = storage
+        .delete(PROBE_FILE)
+        .map_err(|e| format!("Cannot delete: {}", e));
+    if read? != Some(probe) {
+        return Err("Read back different contents".to_string());
+    }
+    deleted?;
+    Ok(format!(
+        "Read and write in {}",
+        storage.data_dir().display()
+    ))
+}
+
+/// Run one check; `Ok(None)` means it was skipped
+async fn run_step(
+    state: &Arc<AppState>,
+    step: SelfTestStep,
+    advisor_ok: bool,
+) -> Result<Option<String>, String> {
+    match step {
+        SelfTestStep::AdvisorPing => {
+            let response = handlers::ping(state.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            match (response.error, response.result) {
+                (Some(error), _) => Err(format!("Advisor error: {}", error.message)),
+                (None, Some(result)) => Ok(Some(format!("Advisor answered {}", result))),
+                (None, None) => Err("Advisor sent an empty response".to_string()),
+            }
+        }
+        SelfTestStep::Recommendation => {
+            if !advisor_ok {
+                return Ok(None);
+            }
+            // Sent directly, so that the canned day is not recorded as
+            // history or compared against the previous recommendation
+            let request = canned_request();
+            let state = state.clone();
+            let response = tokio::task::spawn_blocking(move || {
+                state.lean_repl.blocking_lock().send_request(&request)
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+            check_recommendation(&response).map(Some)
+        }
+        SelfTestStep::Storage => match &state.storage {
+            Some(storage) => check_storage(storage).map(Some),
+            None => Ok(None),
+        },
+    }
+}
+
+/// Run every check and report how each went
+pub async fn run(state: Arc<AppState>) -> SelfTestReport {
+    let mut checks = Vec::new();
+    let mut advisor_ok = false;
+    for step in SelfTestStep::ORDER {
+        let started = Instant::now();
+        let outcome = run_step(&state, step, advisor_ok).await;
+        let (status, detail) = match outcome {
+            Ok(Some(detail)) => (CheckStatus::Passed, detail),
+            Ok(None) if step == SelfTestStep::Storage => {
+                (CheckStatus::Skipped, "No data directory".to_string())
+            }
+            Ok(None) => (CheckStatus::Skipped, "Advisor is not available".to_string()),
+            Err(error) => {
+                tracing::warn!("Self-test {:?} failed: {}", step, error);
+                (CheckStatus::Failed, error)
+            }
+        };
+        if step == SelfTestStep::AdvisorPing {
+            advisor_ok = status == CheckStatus::Passed;
+        }
+        checks.push(SelfTestCheck {
+            step,
+            label: step.label().to_string(),
+            status,
+            detail,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+    SelfTestReport {
+        passed: checks.iter().all(|c| c.status != CheckStatus::Failed),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_rpc::JsonRpcError;
+    use crate::lean_repl::LeanRepl;
+    use std::path::PathBuf;
+
+    fn response(result: Option<serde_json::Value>) -> JsonRpcResponse {
+        JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: result.as_ref().map(crate::json_rpc::to_raw),
+            error: None,
+            id: serde_json::json!("self-test"),
+        }
+    }
+
+    #[test]
+    fn test_canned_request() {
+        let request = canned_request();
+        assert_eq!(request.params_value()["today"], 20260306);
+        assert_eq!(request.params_value()["states"][1]["passStatus"], "passed");
+        assert_eq!(
+            request.params_value()["schools"][1]["enrollmentFeeDeadline"],
+            CANNED_TODAY
+        );
+    }
+
+    #[test]
+    fn test_check_recommendation() {
+        let expected = response(Some(serde_json::json!({ "action": expected_action() })));
+        assert!(check_recommendation(&expected).is_ok());
+
+        let other = response(Some(serde_json::json!({
+            "action": { "type": "doNothing" }
+        })));
+        assert!(check_recommendation(&other)
+            .unwrap_err()
+            .contains("doNothing"));
+        assert!(check_recommendation(&response(None)).is_err());
+
+        let mut failed = response(None);
+        failed.error = Some(JsonRpcError {
+            code: -32603,
+            message: "boom".to_string(),
+            data: None,
+        });
+        assert!(check_recommendation(&failed).unwrap_err().contains("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_run_without_advisor() {
+        let dir = tempfile::tempdir().unwrap();
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state =
+            Arc::new(AppState::new(repl).with_storage(Storage::new(dir.path().to_path_buf())));
+
+        let report = run(state).await;
+        let statuses: Vec<CheckStatus> = report.checks.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                CheckStatus::Failed,
+                CheckStatus::Skipped,
+                CheckStatus::Passed
+            ]
+        );
+        assert!(!report.passed);
+        assert!(!dir.path().join(PROBE_FILE).exists());
+    }
+}
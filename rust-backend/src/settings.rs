@@ -0,0 +1,452 @@
+//! User settings edited in the app and persisted as `settings.json`.
+//!
+//! Unlike `config.toml`, which is written by hand and read once at startup,
+//! settings are changed from the settings screen and take effect
+//! immediately. Every update is validated before it is saved.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cloud_backup::{CloudBackupSettings, Schedule};
+use crate::config::AppConfig;
+use crate::i18n::Locale;
+use crate::logs::LogLevel;
+use crate::model::BankAccount;
+use crate::outcome::OutcomeRules;
+use crate::repl_pool::{PoolLimits, MAX_POOL_SIZE};
+use crate::startup::StartupMode;
+use crate::storage::{Storage, StorageError};
+
+/// Settings filename in the data directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Locales the frontend has translations for
+pub const SUPPORTED_LOCALES: [&str; 2] = ["ja", "en"];
+
+/// Errors that can occur while validating or saving settings
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Invalid setting {field}: {reason}")]
+    Invalid { field: &'static str, reason: String },
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+fn invalid(field: &'static str, reason: impl Into<String>) -> SettingsError {
+    SettingsError::Invalid {
+        field,
+        reason: reason.into(),
+    }
+}
+
+/// Color theme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the OS setting
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Advisor process limits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AdvisorSettings {
+    /// How long to wait for a response to one request
+    pub request_timeout_secs: u64,
+    /// When to start the advisor, see [`crate::startup::start`]
+    pub startup: StartupMode,
+    /// Ask for this week's recommendations in the background at startup,
+    /// see [`crate::preload`]
+    pub preload: bool,
+    /// Extra advisor processes kept running even when idle, see
+    /// [`crate::repl_pool`]
+    pub pool_min: usize,
+    /// Most extra advisor processes started when the main one is busy; 0
+    /// answers everything in the main process
+    pub pool_max: usize,
+    /// Extra processes idle this long are stopped, down to `pool_min`
+    pub pool_idle_secs: u64,
+    /// Memory the extra processes may use together, in megabytes; 0 for no
+    /// limit
+    pub pool_memory_mb: u64,
+}
+
+impl Default for AdvisorSettings {
+    fn default() -> Self {
+        Self {
+            request_timeout_secs: 30,
+            startup: StartupMode::Background,
+            preload: true,
+            pool_min: 0,
+            pool_max: 1,
+            pool_idle_secs: 120,
+            pool_memory_mb: 1024,
+        }
+    }
+}
+
+impl AdvisorSettings {
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn pool_limits(&self) -> PoolLimits {
+        PoolLimits {
+            min: self.pool_min,
+            max: self.pool_max,
+            idle_timeout: Duration::from_secs(self.pool_idle_secs),
+            memory_mb: self.pool_memory_mb,
+        }
+    }
+}
+
+/// Synchronization with a self-hosted web server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    /// Base URL of the web server, required when enabled
+    pub server_url: Option<String>,
+    pub interval_minutes: u32,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            server_url: None,
+            interval_minutes: 15,
+        }
+    }
+}
+
+/// The paying account for bank transfer files (振込依頼人)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankTransferSettings {
+    /// 10-digit requester code assigned by the bank for bulk transfers
+    pub requester_code: String,
+    /// Account the transfers are paid from; its holder is the requester
+    pub account: BankAccount,
+}
+
+/// User-editable settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// UI language, one of [`SUPPORTED_LOCALES`]
+    pub locale: String,
+    pub theme: Theme,
+    /// How many days before a deadline reminders start
+    pub reminder_lead_days: u32,
+    /// Seconds between automatic saves; 0 disables autosave
+    pub autosave_interval_secs: u32,
+    /// Start the desktop app in the tray when the user logs in
+    pub launch_at_login: bool,
+    /// Hide the desktop app to the tray instead of quitting when its window
+    /// is closed
+    pub close_to_tray: bool,
+    /// Least severe level logged: trace, debug, info, warn or error
+    pub log_level: String,
+    pub advisor: AdvisorSettings,
+    pub sync: SyncSettings,
+    /// Required for Zengin-format transfer exports
+    pub bank_transfer: Option<BankTransferSettings>,
+    /// Record advisor requests and responses for a bug report, from each
+    /// launch until turned off
+    pub record_sessions: bool,
+    /// What else changes when an exam result is recorded
+    pub outcome_rules: OutcomeRules,
+    /// Scheduled encrypted backups to the sync server
+    pub cloud_backup: CloudBackupSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            locale: "ja".to_string(),
+            theme: Theme::System,
+            reminder_lead_days: 3,
+            autosave_interval_secs: 30,
+            launch_at_login: false,
+            close_to_tray: false,
+            log_level: "debug".to_string(),
+            advisor: AdvisorSettings::default(),
+            sync: SyncSettings::default(),
+            bank_transfer: None,
+            record_sessions: false,
+            outcome_rules: OutcomeRules::default(),
+            cloud_backup: CloudBackupSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Defaults for a first launch, taking reminder timing and closing to
+    /// the tray from `config.toml`
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            reminder_lead_days: config.reminders.lead_days.clamp(0, 30) as u32,
+            close_to_tray: config.desktop.close_to_tray,
+            ..Self::default()
+        }
+    }
+
+    /// Locale for user-facing messages
+    pub fn locale(&self) -> Locale {
+        Locale::from_tag(&self.locale)
+    }
+
+    /// Level to log at, `debug` for an unknown name, which validation rejects
+    pub fn log_level(&self) -> LogLevel {
+        LogLevel::from_name(&self.log_level).unwrap_or(LogLevel::Debug)
+    }
+
+    /// Load saved settings, or `defaults` if none have been saved. Fields
+    /// saved before they existed are taken from `defaults`.
+    pub fn load(storage: &Storage, defaults: Settings) -> Result<Self, StorageError> {
+        let Some(saved) = storage.load(SETTINGS_FILE)? else {
+            return Ok(defaults);
+        };
+        let serde_json::Value::Object(saved) = saved else {
+            return Ok(serde_json::from_value(saved)?);
+        };
+        let mut value = serde_json::to_value(defaults)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.extend(saved);
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Validate and persist the settings
+    pub fn save(&self, storage: &Storage) -> Result<(), SettingsError> {
+        self.validate()?;
+        storage.save(
+            SETTINGS_FILE,
+            &serde_json::to_value(self).map_err(StorageError::from)?,
+        )?;
+        Ok(())
+    }
+
+    /// Check every field is within its allowed range
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if !SUPPORTED_LOCALES.contains(&self.locale.as_str()) {
+            return Err(invalid(
+                "locale",
+                format!("must be one of {}", SUPPORTED_LOCALES.join(", ")),
+            ));
+        }
+        if self.reminder_lead_days > 30 {
+            return Err(invalid("reminderLeadDays", "must be 30 days or less"));
+        }
+        if self.autosave_interval_secs != 0 && !(5..=3600).contains(&self.autosave_interval_secs) {
+            return Err(invalid(
+                "autosaveIntervalSecs",
+                "must be 0 (off) or between 5 and 3600",
+            ));
+        }
+        if LogLevel::from_name(&self.log_level).is_none() {
+            return Err(invalid(
+                "logLevel",
+                "must be one of trace, debug, info, warn, error",
+            ));
+        }
+        if !(5..=300).contains(&self.advisor.request_timeout_secs) {
+            return Err(invalid(
+                "advisor.requestTimeoutSecs",
+                "must be between 5 and 300",
+            ));
+        }
+        if self.advisor.pool_max > MAX_POOL_SIZE {
+            return Err(invalid(
+                "advisor.poolMax",
+                format!("must be {} or less", MAX_POOL_SIZE),
+            ));
+        }
+        if self.advisor.pool_min > self.advisor.pool_max {
+            return Err(invalid("advisor.poolMin", "must not exceed advisor.poolMax"));
+        }
+        if !(5..=3600).contains(&self.advisor.pool_idle_secs) {
+            return Err(invalid(
+                "advisor.poolIdleSecs",
+                "must be between 5 and 3600",
+            ));
+        }
+        if self.sync.enabled {
+            let url = self
+                .sync
+                .server_url
+                .as_deref()
+                .ok_or_else(|| invalid("sync.serverUrl", "is required when sync is enabled"))?;
+            match url::Url::parse(url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => return Err(invalid("sync.serverUrl", "must be an http(s) URL")),
+            }
+            if !(1..=1440).contains(&self.sync.interval_minutes) {
+                return Err(invalid(
+                    "sync.intervalMinutes",
+                    "must be between 1 and 1440",
+                ));
+            }
+        }
+        if self.cloud_backup.enabled {
+            match self.sync.server_url.as_deref().map(url::Url::parse) {
+                Some(Ok(url)) if matches!(url.scheme(), "http" | "https") => {}
+                _ => {
+                    return Err(invalid(
+                        "sync.serverUrl",
+                        "must be an http(s) URL when cloud backup is enabled",
+                    ))
+                }
+            }
+        }
+        if Schedule::parse(&self.cloud_backup.schedule).is_err() {
+            return Err(invalid(
+                "cloudBackup.schedule",
+                "must be five cron fields: minute hour day month weekday",
+            ));
+        }
+        if !(1..=30).contains(&self.cloud_backup.alert_after_days) {
+            return Err(invalid(
+                "cloudBackup.alertAfterDays",
+                "must be between 1 and 30",
+            ));
+        }
+        if let Some(transfer) = &self.bank_transfer {
+            if transfer.requester_code.len() != 10
+                || !transfer.requester_code.bytes().all(|b| b.is_ascii_digit())
+            {
+                return Err(invalid("bankTransfer.requesterCode", "must be 10 digits"));
+            }
+            if let Some(field) = transfer.account.invalid_field() {
+                return Err(invalid(
+                    "bankTransfer.account",
+                    format!("{} is not accepted by banks", field),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        Settings::default().validate().unwrap();
+        let config = AppConfig::parse("[reminders]\nlead_days = 7\n").unwrap();
+        assert_eq!(Settings::from_config(&config).reminder_lead_days, 7);
+        assert!(!Settings::from_config(&config).close_to_tray);
+        let config = AppConfig::parse("[desktop]\nclose_to_tray = true\n").unwrap();
+        assert!(Settings::from_config(&config).close_to_tray);
+    }
+
+    #[test]
+    fn test_validation() {
+        type Mutation = fn(&mut Settings);
+        let cases: [(&str, Mutation); 13] = [
+            ("locale", |s| s.locale = "fr".to_string()),
+            ("reminderLeadDays", |s| s.reminder_lead_days = 31),
+            ("autosaveIntervalSecs", |s| s.autosave_interval_secs = 1),
+            ("logLevel", |s| s.log_level = "verbose".to_string()),
+            ("advisor.requestTimeoutSecs", |s| {
+                s.advisor.request_timeout_secs = 0
+            }),
+            ("advisor.poolMax", |s| s.advisor.pool_max = 9),
+            ("advisor.poolMin", |s| s.advisor.pool_min = 2),
+            ("advisor.poolIdleSecs", |s| s.advisor.pool_idle_secs = 0),
+            ("sync.serverUrl", |s| s.sync.enabled = true),
+            ("bankTransfer.requesterCode", |s| {
+                s.bank_transfer = Some(BankTransferSettings::default())
+            }),
+            ("sync.serverUrl", |s| s.cloud_backup.enabled = true),
+            ("cloudBackup.schedule", |s| {
+                s.cloud_backup.schedule = "nightly".to_string()
+            }),
+            ("cloudBackup.alertAfterDays", |s| {
+                s.cloud_backup.alert_after_days = 0
+            }),
+        ];
+        for (expected, mutate) in cases {
+            let mut settings = Settings::default();
+            mutate(&mut settings);
+            match settings.validate() {
+                Err(SettingsError::Invalid { field, .. }) => assert_eq!(field, expected),
+                other => panic!("{}: expected invalid, got {:?}", expected, other),
+            }
+        }
+
+        let mut settings = Settings {
+            autosave_interval_secs: 0,
+            sync: SyncSettings {
+                enabled: true,
+                server_url: Some("ftp://nas.local".to_string()),
+                ..SyncSettings::default()
+            },
+            ..Settings::default()
+        };
+        assert!(settings.validate().is_err());
+        settings.sync.server_url = Some("http://nas.local:3000".to_string());
+        settings.validate().unwrap();
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        assert_eq!(
+            Settings::load(&storage, Settings::default()).unwrap(),
+            Settings::default()
+        );
+
+        let settings = Settings {
+            theme: Theme::Dark,
+            reminder_lead_days: 5,
+            ..Settings::default()
+        };
+        settings.save(&storage).unwrap();
+        assert_eq!(
+            Settings::load(&storage, Settings::default()).unwrap(),
+            settings
+        );
+
+        let stored = storage.load(SETTINGS_FILE).unwrap().unwrap();
+        assert_eq!(stored["theme"], "dark");
+        assert_eq!(stored["advisor"]["requestTimeoutSecs"], 30);
+    }
+
+    #[test]
+    fn test_fields_missing_from_saved_settings_take_the_defaults() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let saved = serde_json::json!({ "locale": "en", "reminderLeadDays": 5 });
+        storage.save(SETTINGS_FILE, &saved).unwrap();
+
+        let config = AppConfig::parse("[desktop]\nclose_to_tray = true\n").unwrap();
+        let settings = Settings::load(&storage, Settings::from_config(&config)).unwrap();
+        assert_eq!(settings.locale, "en");
+        assert_eq!(settings.reminder_lead_days, 5);
+        assert!(settings.close_to_tray);
+    }
+
+    #[test]
+    fn test_invalid_settings_not_saved() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let settings = Settings {
+            locale: "xx".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.save(&storage).is_err());
+        assert!(!storage.exists(SETTINGS_FILE));
+    }
+}
@@ -0,0 +1,375 @@
+//! Read-only links to a snapshot of the payment schedule.
+//!
+//! A share keeps the payments as they were when the link was made, so one
+//! parent can send the plan to the other without giving them an account or
+//! access to the data. The link's token is `<id>.<expiry>.<signature>`: the
+//! expiry is a Unix time and the signature an HMAC-SHA256 of both with the
+//! server's share key, so a token cannot be guessed, extended or pointed at
+//! another share. Snapshots are kept in [`SHARES_FILE`] and dropped once
+//! expired.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::field_crypto::FieldKey;
+use crate::format::{format_day, format_money, format_yen};
+use crate::i18n::Locale;
+use crate::model::{Currency, FeeKind};
+use crate::payments::{OutstandingPayments, PaymentItem};
+use crate::storage::{Storage, StorageError};
+use crate::token::{constant_time_eq, random_token};
+
+/// File in the data directory holding the shared snapshots
+pub const SHARES_FILE: &str = "shares.json";
+
+/// File in the data directory holding the signing key when none is
+/// configured, so links keep working after a restart
+pub const SHARE_KEY_FILE: &str = "share.key";
+
+/// Days a link lasts unless asked otherwise
+pub const DEFAULT_SHARE_DAYS: u32 = 7;
+
+/// Longest a link can last
+pub const MAX_SHARE_DAYS: u32 = 30;
+
+/// Hex characters of the signature kept in a token
+const SIGNATURE_LEN: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Errors that can occur while sharing or opening a shared schedule
+#[derive(Debug, Error)]
+pub enum ShareError {
+    #[error("A link can last 1 to {max} days, not {0}", max = MAX_SHARE_DAYS)]
+    InvalidDays(u32),
+
+    #[error("The share link is not valid")]
+    InvalidToken,
+
+    #[error("The share link expired at {0}")]
+    Expired(String),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// A new share link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ShareLink {
+    pub token: String,
+    /// RFC 3339 time the link stops working
+    pub expires_at: String,
+}
+
+/// The schedule as it was when shared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SharedSchedule {
+    /// RFC 3339 time the link was made
+    pub created_at: String,
+    pub expires_at: String,
+    /// Payments still to make and already made, without receipts
+    pub payments: SharedPayments,
+}
+
+/// Payments of a shared schedule
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SharedPayments {
+    pub outstanding: Vec<SharedPayment>,
+    pub settled: Vec<SharedPayment>,
+    /// Totals in yen, leaving out currencies without an exchange rate
+    #[ts(type = "number")]
+    pub outstanding_total: u64,
+    #[ts(type = "number")]
+    pub settled_total: u64,
+}
+
+/// One payment of a shared schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SharedPayment {
+    pub school_name: String,
+    pub fee: FeeKind,
+    pub installment: Option<u32>,
+    pub due: u32,
+    #[ts(type = "number")]
+    pub amount: u64,
+    pub currency: Currency,
+}
+
+impl From<PaymentItem> for SharedPayment {
+    fn from(item: PaymentItem) -> Self {
+        Self {
+            school_name: item.school_name,
+            fee: item.fee,
+            installment: item.installment,
+            due: item.due,
+            amount: item.amount,
+            currency: item.currency,
+        }
+    }
+}
+
+impl From<OutstandingPayments> for SharedPayments {
+    fn from(payments: OutstandingPayments) -> Self {
+        Self {
+            outstanding: payments.outstanding.into_iter().map(Into::into).collect(),
+            settled: payments.settled.into_iter().map(Into::into).collect(),
+            outstanding_total: payments.outstanding_total,
+            settled_total: payments.settled_total,
+        }
+    }
+}
+
+/// Snapshots by share id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shares {
+    #[serde(default)]
+    pub shares: BTreeMap<String, SharedSchedule>,
+}
+
+fn signature(key: &FieldKey, id: &str, expires: i64) -> String {
+    let mut mac = HmacSha256::new_from_slice(key.bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("share:{}.{}", id, expires).as_bytes());
+    let mut signature = hex::encode(mac.finalize().into_bytes());
+    signature.truncate(SIGNATURE_LEN);
+    signature
+}
+
+fn is_expired(schedule: &SharedSchedule, now: DateTime<FixedOffset>) -> bool {
+    DateTime::parse_from_rfc3339(&schedule.expires_at).map_or(true, |at| at <= now)
+}
+
+impl Shares {
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(SHARES_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(SHARES_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Keep `payments` for `days` from `now`, dropping expired snapshots,
+    /// and return the link to them
+    pub fn share(
+        &mut self,
+        key: &FieldKey,
+        payments: SharedPayments,
+        days: u32,
+        now: DateTime<FixedOffset>,
+    ) -> Result<ShareLink, ShareError> {
+        if !(1..=MAX_SHARE_DAYS).contains(&days) {
+            return Err(ShareError::InvalidDays(days));
+        }
+        self.shares.retain(|_, schedule| !is_expired(schedule, now));
+
+        let id = random_token(12);
+        let expires = now + TimeDelta::days(i64::from(days));
+        let token = format!(
+            "{}.{}.{}",
+            id,
+            expires.timestamp(),
+            signature(key, &id, expires.timestamp())
+        );
+        let expires_at = expires.to_rfc3339();
+        self.shares.insert(
+            id,
+            SharedSchedule {
+                created_at: now.to_rfc3339(),
+                expires_at: expires_at.clone(),
+                payments,
+            },
+        );
+        Ok(ShareLink { token, expires_at })
+    }
+
+    /// The snapshot `token` links to, if it was signed with `key` and has
+    /// not expired
+    pub fn open(
+        &self,
+        key: &FieldKey,
+        token: &str,
+        now: DateTime<FixedOffset>,
+    ) -> Result<&SharedSchedule, ShareError> {
+        let [id, expires, signed] = token.split('.').collect::<Vec<_>>()[..] else {
+            return Err(ShareError::InvalidToken);
+        };
+        let expires: i64 = expires.parse().map_err(|_| ShareError::InvalidToken)?;
+        if !constant_time_eq(signed, &signature(key, id, expires)) {
+            return Err(ShareError::InvalidToken);
+        }
+        let schedule = self.shares.get(id).ok_or(ShareError::InvalidToken)?;
+        if is_expired(schedule, now) {
+            return Err(ShareError::Expired(schedule.expires_at.clone()));
+        }
+        Ok(schedule)
+    }
+}
+
+/// Escape text for HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn payment_rows(payments: &[SharedPayment]) -> String {
+    if payments.is_empty() {
+        return "<tr><td colspan=\"4\">なし</td></tr>\n".to_string();
+    }
+    payments
+        .iter()
+        .map(|p| {
+            let fee = match (p.fee, p.installment) {
+                (FeeKind::EnrollmentFee, _) => "入学金".to_string(),
+                (FeeKind::Tuition, None) => "授業料".to_string(),
+                (FeeKind::Tuition, Some(number)) => format!("授業料（第{}回）", number),
+            };
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"amount\">{}</td></tr>\n",
+                format_day(p.due),
+                escape_html(&p.school_name),
+                fee,
+                format_money(p.amount, p.currency, Locale::Ja)
+            )
+        })
+        .collect()
+}
+
+/// A minimal page showing `schedule`, readable on a phone
+pub fn render_html(schedule: &SharedSchedule) -> String {
+    let expires = DateTime::parse_from_rfc3339(&schedule.expires_at)
+        .map(|at| at.format("%Y/%m/%d %H:%M").to_string())
+        .unwrap_or_default();
+    let payments = &schedule.payments;
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"ja\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<meta name=\"robots\" content=\"noindex\">
+<title>学費の支払い予定</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }}
+th, td {{ border-bottom: 1px solid #ccc; padding: 0.4em; text-align: left; }}
+.amount {{ text-align: right; }}
+</style>
+</head>
+<body>
+<h1>学費の支払い予定</h1>
+<p>{expires}まで閲覧できます。共有した時点の内容で、その後の変更は反映されません。</p>
+<h2>これからの支払い（合計 {outstanding_total}）</h2>
+<table>
+<tr><th>期限</th><th>学校</th><th>費目</th><th class=\"amount\">金額</th></tr>
+{outstanding}</table>
+<h2>支払い済み（合計 {settled_total}）</h2>
+<table>
+<tr><th>期限</th><th>学校</th><th>費目</th><th class=\"amount\">金額</th></tr>
+{settled}</table>
+</body>
+</html>
+",
+        expires = expires,
+        outstanding_total = format_yen(payments.outstanding_total),
+        outstanding = payment_rows(&payments.outstanding),
+        settled_total = format_yen(payments.settled_total),
+        settled = payment_rows(&payments.settled),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    fn payments() -> SharedPayments {
+        SharedPayments {
+            outstanding: vec![SharedPayment {
+                school_name: "<早稲田>".to_string(),
+                fee: FeeKind::Tuition,
+                installment: Some(1),
+                due: 20260310,
+                amount: 300000,
+                currency: Currency::Jpy,
+            }],
+            outstanding_total: 300000,
+            ..SharedPayments::default()
+        }
+    }
+
+    #[test]
+    fn test_links_are_signed_and_expire() {
+        let key = FieldKey::generate();
+        let now = at("2026-03-01T12:00:00+09:00");
+        let mut shares = Shares::default();
+        assert!(matches!(
+            shares.share(&key, payments(), 0, now),
+            Err(ShareError::InvalidDays(0))
+        ));
+        let link = shares.share(&key, payments(), 7, now).unwrap();
+        assert_eq!(at(&link.expires_at), at("2026-03-08T12:00:00+09:00"));
+
+        let schedule = shares.open(&key, &link.token, now).unwrap();
+        assert_eq!(schedule.payments, payments());
+
+        // Another key, a changed expiry or a truncated token are refused
+        assert!(matches!(
+            shares.open(&FieldKey::generate(), &link.token, now),
+            Err(ShareError::InvalidToken)
+        ));
+        let parts: Vec<&str> = link.token.split('.').collect();
+        let extended = format!("{}.{}.{}", parts[0], 4102444800u64, parts[2]);
+        assert!(matches!(
+            shares.open(&key, &extended, now),
+            Err(ShareError::InvalidToken)
+        ));
+        assert!(shares.open(&key, parts[0], now).is_err());
+
+        let later = at("2026-03-09T00:00:00+09:00");
+        assert!(matches!(
+            shares.open(&key, &link.token, later),
+            Err(ShareError::Expired(_))
+        ));
+        // Expired snapshots are dropped when the next link is made
+        shares.share(&key, payments(), 1, later).unwrap();
+        assert_eq!(shares.shares.len(), 1);
+    }
+
+    #[test]
+    fn test_render_html() {
+        let schedule = SharedSchedule {
+            created_at: "2026-03-01T12:00:00+09:00".to_string(),
+            expires_at: "2026-03-08T12:00:00+09:00".to_string(),
+            payments: payments(),
+        };
+        let html = render_html(&schedule);
+        assert!(html.contains("2026/03/08 12:00まで"));
+        assert!(html.contains("&lt;早稲田&gt;"));
+        assert!(html.contains("授業料（第1回）"));
+        assert!(html.contains("300,000円"));
+        assert!(html.contains("<td colspan=\"4\">なし</td>"));
+    }
+}
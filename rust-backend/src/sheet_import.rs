@@ -0,0 +1,438 @@
+//! Import of school lists kept in Google Sheets or any CSV on the web.
+//!
+//! Many families and cram schools keep their school list in a shared
+//! spreadsheet with their own column titles. A [`ColumnMapping`] saved in
+//! [`COLUMN_MAPPINGS_FILE`] tells which title holds which school field,
+//! so the same sheet can be imported again after it is edited. The fetched
+//! rows go through the same validation and preview as an imported file.
+//!
+//! Mappings also serve CSV files, such as a bank's monthly statement
+//! export. Each remembers the column titles of the sheet or file it was
+//! made for, and [`MappingCatalog::detect`] picks the saved mapping whose
+//! titles best match a new file's header, so a file laid out like last
+//! month's is read without choosing the mapping again.
+//!
+//! Google Sheets links as copied from the browser are turned into their
+//! CSV export URL; the sheet must be shared with anyone with the link or
+//! published to the web.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+use url::Url;
+
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding the saved column mappings
+pub const COLUMN_MAPPINGS_FILE: &str = "column_mappings.json";
+
+/// Largest CSV accepted, in bytes
+pub const MAX_CSV_BYTES: usize = 5 * 1024 * 1024;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SHEETS_HOST: &str = "docs.google.com";
+
+/// Errors that can occur while fetching a sheet or saving mappings
+#[derive(Debug, Error)]
+pub enum SheetImportError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("The sheet is larger than {MAX_CSV_BYTES} bytes")]
+    TooLarge,
+
+    #[error("The URL did not return CSV; check the sheet is shared or published")]
+    NotCsv,
+
+    #[error("Column mapping not found: {0}")]
+    UnknownMapping(String),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+}
+
+/// Where the rows read through a mapping come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum MappingSource {
+    /// A Google Sheet or CSV fetched from a URL
+    #[default]
+    Sheet,
+    /// A CSV file on disk
+    Csv,
+}
+
+/// Which spreadsheet column holds which school field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ColumnMapping {
+    pub name: String,
+    /// School field (`name`, `examDate`, ...) by column title
+    pub columns: BTreeMap<String, String>,
+    /// Sheet URL the mapping was last used with
+    #[serde(default)]
+    #[ts(optional)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub source: MappingSource,
+    /// Every column title of the sheet or file the mapping was last used
+    /// with, mapped or not; empty until it is first used
+    #[serde(default)]
+    pub headers: Vec<String>,
+}
+
+impl ColumnMapping {
+    /// Titles that identify the layout: the remembered header, or else the
+    /// mapped titles
+    fn signature(&self) -> BTreeSet<String> {
+        if self.headers.is_empty() {
+            header_signature(self.columns.keys())
+        } else {
+            header_signature(&self.headers)
+        }
+    }
+}
+
+/// A saved mapping found to fit a header row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MappingMatch {
+    pub name: String,
+    /// Share of the titles in the mapping and the header that both have,
+    /// in percent; 100 for the same layout
+    pub score: u32,
+}
+
+/// Column titles compared without case, spaces or their order
+pub fn header_signature<I, S>(titles: I) -> BTreeSet<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    titles
+        .into_iter()
+        .map(|t| {
+            t.as_ref()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Saved column mappings, in name order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingCatalog {
+    pub mappings: Vec<ColumnMapping>,
+}
+
+impl MappingCatalog {
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(COLUMN_MAPPINGS_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(COLUMN_MAPPINGS_FILE, &serde_json::to_value(self)?)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&ColumnMapping, SheetImportError> {
+        self.mappings
+            .iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| SheetImportError::UnknownMapping(name.to_string()))
+    }
+
+    /// Add `mapping`, replacing the one with the same name
+    pub fn upsert(&mut self, mapping: ColumnMapping) {
+        self.mappings.retain(|m| m.name != mapping.name);
+        self.mappings.push(mapping);
+        self.mappings.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Saved mapping for `source` that best fits `header`.
+    ///
+    /// A mapping fits only if every column it maps is in the header; among
+    /// those, the one sharing the most titles with the header wins, and
+    /// the first by name on a tie.
+    pub fn detect(&self, source: MappingSource, header: &[String]) -> Option<MappingMatch> {
+        let header = header_signature(header);
+        let mut best: Option<MappingMatch> = None;
+        for mapping in self.mappings.iter().filter(|m| m.source == source) {
+            let mapped = header_signature(mapping.columns.keys());
+            if mapped.is_empty() || !mapped.is_subset(&header) {
+                continue;
+            }
+            let signature = mapping.signature();
+            let shared = signature.intersection(&header).count();
+            let all = signature.union(&header).count();
+            let score = (shared * 100 / all) as u32;
+            if best.as_ref().is_none_or(|b| score > b.score) {
+                best = Some(MappingMatch {
+                    name: mapping.name.clone(),
+                    score,
+                });
+            }
+        }
+        best
+    }
+
+    /// Remember that `name` was used with `header` (and `url`), returning
+    /// whether anything changed
+    pub fn record_use(&mut self, name: &str, header: &[String], url: Option<&str>) -> bool {
+        let Some(mapping) = self.mappings.iter_mut().find(|m| m.name == name) else {
+            return false;
+        };
+        let url = url.map(str::to_string).or_else(|| mapping.url.clone());
+        if mapping.headers == header && mapping.url == url {
+            return false;
+        }
+        mapping.headers = header.to_vec();
+        mapping.url = url;
+        true
+    }
+
+    /// Remove the mapping called `name`, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.mappings.len();
+        self.mappings.retain(|m| m.name != name);
+        self.mappings.len() != before
+    }
+}
+
+/// URL to fetch CSV from for `url`.
+///
+/// A Google Sheets editor or share link becomes the CSV export of the same
+/// sheet tab; published (`/pub`) links are asked for CSV output. Other
+/// http(s) URLs are used as given.
+pub fn csv_url(url: &str) -> Result<Url, SheetImportError> {
+    let mut parsed =
+        Url::parse(url.trim()).map_err(|e| SheetImportError::InvalidUrl(e.to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(SheetImportError::InvalidUrl(url.to_string()));
+    }
+    if parsed.host_str() != Some(SHEETS_HOST) {
+        return Ok(parsed);
+    }
+
+    let segments: Vec<String> = parsed
+        .path_segments()
+        .map(|s| s.map(str::to_string).collect())
+        .unwrap_or_default();
+    // The sheet tab is `#gid=N` on editor links and `?gid=N` on others
+    let gid = parsed
+        .fragment()
+        .and_then(|f| f.strip_prefix("gid="))
+        .map(str::to_string)
+        .or_else(|| {
+            parsed
+                .query_pairs()
+                .find(|(k, _)| k == "gid")
+                .map(|(_, v)| v.into_owned())
+        });
+
+    match segments.as_slice() {
+        // Published to the web: /spreadsheets/d/e/{id}/pub
+        [s, d, e, id, ..] if s == "spreadsheets" && d == "d" && e == "e" => {
+            let mut path = format!("/spreadsheets/d/e/{}/pub", id);
+            path.push_str("?output=csv");
+            if let Some(gid) = gid {
+                path.push_str(&format!("&gid={}", gid));
+            }
+            parsed.set_fragment(None);
+            parsed
+                .join(&path)
+                .map_err(|e| SheetImportError::InvalidUrl(e.to_string()))
+        }
+        // Shared link: /spreadsheets/d/{id}/edit
+        [s, d, id, ..] if s == "spreadsheets" && d == "d" => {
+            let mut path = format!("/spreadsheets/d/{}/export?format=csv", id);
+            if let Some(gid) = gid {
+                path.push_str(&format!("&gid={}", gid));
+            }
+            parsed.set_fragment(None);
+            parsed
+                .join(&path)
+                .map_err(|e| SheetImportError::InvalidUrl(e.to_string()))
+        }
+        _ => Ok(parsed),
+    }
+}
+
+/// Download the CSV behind `url`
+pub async fn fetch_csv(url: &str) -> Result<String, SheetImportError> {
+    let url = csv_url(url)?;
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > MAX_CSV_BYTES)
+    {
+        return Err(SheetImportError::TooLarge);
+    }
+    // A sheet that is not shared redirects to the sign-in page
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if is_html {
+        return Err(SheetImportError::NotCsv);
+    }
+    let bytes = response.bytes().await?;
+    if bytes.len() > MAX_CSV_BYTES {
+        return Err(SheetImportError::TooLarge);
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_url() {
+        assert_eq!(
+            csv_url("https://docs.google.com/spreadsheets/d/abc123/edit#gid=42")
+                .unwrap()
+                .as_str(),
+            "https://docs.google.com/spreadsheets/d/abc123/export?format=csv&gid=42"
+        );
+        assert_eq!(
+            csv_url("https://docs.google.com/spreadsheets/d/abc123/edit?usp=sharing")
+                .unwrap()
+                .as_str(),
+            "https://docs.google.com/spreadsheets/d/abc123/export?format=csv"
+        );
+        assert_eq!(
+            csv_url("https://docs.google.com/spreadsheets/d/e/2PACX-x/pubhtml?gid=7")
+                .unwrap()
+                .as_str(),
+            "https://docs.google.com/spreadsheets/d/e/2PACX-x/pub?output=csv&gid=7"
+        );
+        assert_eq!(
+            csv_url(" https://example.com/schools.csv ")
+                .unwrap()
+                .as_str(),
+            "https://example.com/schools.csv"
+        );
+        assert!(csv_url("file:///etc/passwd").is_err());
+        assert!(csv_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_mapping_catalog() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let mapping = |name: &str, field: &str| ColumnMapping {
+            name: name.to_string(),
+            columns: [("学校名".to_string(), field.to_string())].into(),
+            url: None,
+            source: MappingSource::Sheet,
+            headers: Vec::new(),
+        };
+
+        let mut catalog = MappingCatalog::load(&storage).unwrap();
+        catalog.upsert(mapping("塾のリスト", "id"));
+        catalog.upsert(mapping("家族の表", "name"));
+        catalog.upsert(mapping("塾のリスト", "name"));
+        catalog.save(&storage).unwrap();
+
+        let loaded = MappingCatalog::load(&storage).unwrap();
+        assert_eq!(loaded.mappings.len(), 2);
+        assert_eq!(loaded.get("塾のリスト").unwrap().columns["学校名"], "name");
+        assert!(matches!(
+            loaded.get("none"),
+            Err(SheetImportError::UnknownMapping(_))
+        ));
+
+        let mut catalog = loaded;
+        assert!(catalog.remove("家族の表"));
+        assert!(!catalog.remove("家族の表"));
+    }
+
+    #[test]
+    fn test_detect_mapping() {
+        let titles =
+            |titles: &[&str]| -> Vec<String> { titles.iter().map(|t| t.to_string()).collect() };
+        let mapping =
+            |name: &str, source, columns: &[(&str, &str)], headers: &[&str]| ColumnMapping {
+                name: name.to_string(),
+                columns: columns
+                    .iter()
+                    .map(|(t, f)| (t.to_string(), f.to_string()))
+                    .collect(),
+                url: None,
+                source,
+                headers: titles(headers),
+            };
+        let mut catalog = MappingCatalog::default();
+        catalog.upsert(mapping(
+            "銀行",
+            MappingSource::Csv,
+            &[("摘要", "name"), ("お引出し", "tuition")],
+            &["日付", "摘要", "お引出し", "お預入れ", "残高"],
+        ));
+        catalog.upsert(mapping(
+            "銀行（旧）",
+            MappingSource::Csv,
+            &[("摘要", "name"), ("お引出し", "tuition")],
+            &["日付", "摘要", "お引出し", "残高"],
+        ));
+        catalog.upsert(mapping(
+            "塾のリスト",
+            MappingSource::Sheet,
+            &[("学校名", "name")],
+            &[],
+        ));
+
+        // Titles match without case, spaces or order
+        let header = titles(&["残高", "日付", "摘要 ", "お 引出し", "お預入れ"]);
+        let found = catalog.detect(MappingSource::Csv, &header).unwrap();
+        assert_eq!(found.name, "銀行");
+        assert_eq!(found.score, 100);
+
+        let header = titles(&["日付", "摘要", "お引出し", "残高"]);
+        let found = catalog.detect(MappingSource::Csv, &header).unwrap();
+        assert_eq!(found.name, "銀行（旧）");
+
+        // A header missing a mapped column, or from another source, fits nothing
+        assert!(catalog
+            .detect(MappingSource::Csv, &titles(&["日付", "摘要"]))
+            .is_none());
+        assert!(catalog
+            .detect(MappingSource::Csv, &titles(&["学校名", "試験日"]))
+            .is_none());
+        let found = catalog
+            .detect(MappingSource::Sheet, &titles(&["学校名", "試験日"]))
+            .unwrap();
+        assert_eq!((found.name.as_str(), found.score), ("塾のリスト", 50));
+
+        // Using a mapping remembers the full header
+        let header = titles(&["学校名", "試験日"]);
+        assert!(catalog.record_use("塾のリスト", &header, Some("https://example.com/a.csv")));
+        assert!(!catalog.record_use("塾のリスト", &header, None));
+        assert_eq!(catalog.get("塾のリスト").unwrap().headers, header);
+        let found = catalog.detect(MappingSource::Sheet, &header).unwrap();
+        assert_eq!(found.score, 100);
+    }
+}
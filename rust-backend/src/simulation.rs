@@ -0,0 +1,413 @@
+//! What-if simulation of the payment plan.
+//!
+//! Each [`Scenario`] is a list of [`Mutation`]s applied to a copy of the
+//! saved dataset, such as declining a school or a result coming a week
+//! late. The advisor plans every variant day by day until the last open
+//! deadline, and [`compare`] turns the plans into a [`ScenarioOutcome`] per
+//! scenario: what would be paid in total, how much of it is non-refundable,
+//! and which schools would lapse. The unchanged dataset is planned too, as
+//! the baseline the scenarios are compared with.
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::currency::ExchangeRates;
+use crate::date::{parse_day, to_day};
+use crate::json_rpc::{JsonRpcRequest, JsonRpcResponse};
+use crate::model::{
+    Currency, Dataset, GetWeeklyRecommendationsResult, PassStatus, PaymentActionType, School,
+};
+
+/// Most scenarios compared in one simulation
+pub const MAX_SCENARIOS: usize = 20;
+
+/// Longest plan asked of the advisor, in days
+pub const MAX_SIMULATION_DAYS: i64 = 120;
+
+/// Errors in a simulation request
+#[derive(Debug, Error)]
+pub enum SimulationError {
+    #[error("Scenario '{scenario}' refers to unknown school {school_id}")]
+    UnknownSchool { scenario: String, school_id: u64 },
+
+    #[error("At most {MAX_SCENARIOS} scenarios can be compared")]
+    TooManyScenarios,
+}
+
+/// A change to the dataset
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, TS)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[ts(export)]
+pub enum Mutation {
+    /// Give up a school's place; it is treated as cancelled
+    #[serde(rename_all = "camelCase")]
+    Decline {
+        #[ts(type = "number")]
+        school_id: u64,
+    },
+    /// Assume a school's exam result
+    #[serde(rename_all = "camelCase")]
+    SetResult {
+        #[ts(type = "number")]
+        school_id: u64,
+        status: PassStatus,
+    },
+    /// Move a school's result announcement; negative days bring it forward
+    #[serde(rename_all = "camelCase")]
+    DelayResult {
+        #[ts(type = "number")]
+        school_id: u64,
+        days: i32,
+    },
+}
+
+/// A named set of changes to compare
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Scenario {
+    pub name: String,
+    pub mutations: Vec<Mutation>,
+}
+
+/// A payment the advisor would recommend during the plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PlannedPayment {
+    /// First day the payment is recommended
+    pub day: u32,
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    pub action_type: PaymentActionType,
+    /// In yen
+    #[ts(type = "number")]
+    pub amount: u64,
+}
+
+/// How one scenario plays out
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ScenarioOutcome {
+    pub name: String,
+    /// Already paid plus planned payments, in yen
+    #[ts(type = "number")]
+    pub total_cost: u64,
+    /// Enrollment fees within the total, which are kept if a place is declined
+    #[ts(type = "number")]
+    pub non_refundable_cost: u64,
+    /// Total cost minus the baseline's
+    #[ts(type = "number")]
+    pub cost_difference: i64,
+    pub planned_payments: Vec<PlannedPayment>,
+    /// Schools the advisor expects to be cancelled by a missed deadline
+    pub lapsed_schools: Vec<String>,
+    /// Advisor error, if this scenario could not be planned
+    pub error: Option<String>,
+    /// How long the advisor took to plan this scenario, in milliseconds
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+}
+
+/// Outcomes of the baseline and each scenario, in request order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SimulationResult {
+    pub baseline: ScenarioOutcome,
+    pub scenarios: Vec<ScenarioOutcome>,
+    /// How long the whole simulation took, in milliseconds
+    #[ts(type = "number")]
+    pub elapsed_ms: u64,
+}
+
+impl SimulationResult {
+    /// Add how long each plan took, baseline first, and the whole run
+    pub fn with_timings(
+        mut self,
+        durations: &[std::time::Duration],
+        elapsed: std::time::Duration,
+    ) -> Self {
+        let outcomes = std::iter::once(&mut self.baseline).chain(self.scenarios.iter_mut());
+        for (outcome, duration) in outcomes.zip(durations) {
+            outcome.duration_ms = duration.as_millis() as u64;
+        }
+        self.elapsed_ms = elapsed.as_millis() as u64;
+        self
+    }
+}
+
+fn shift_day(day: u32, days: i32) -> u32 {
+    parse_day(day)
+        .map(|date| to_day(date + Duration::days(i64::from(days))))
+        .unwrap_or(day)
+}
+
+/// A copy of `dataset` with the scenario's mutations applied in order
+pub fn apply(dataset: &Dataset, scenario: &Scenario) -> Result<Dataset, SimulationError> {
+    let mut dataset = dataset.clone();
+    for mutation in &scenario.mutations {
+        let school_id = match mutation {
+            Mutation::Decline { school_id }
+            | Mutation::SetResult { school_id, .. }
+            | Mutation::DelayResult { school_id, .. } => *school_id,
+        };
+        let school = dataset
+            .schools
+            .iter_mut()
+            .find(|s| s.id == school_id)
+            .ok_or_else(|| SimulationError::UnknownSchool {
+                scenario: scenario.name.clone(),
+                school_id,
+            })?;
+        match mutation {
+            Mutation::Decline { .. } => school.pass_status = PassStatus::Cancelled,
+            Mutation::SetResult { status, .. } => school.pass_status = *status,
+            Mutation::DelayResult { days, .. } => {
+                school.result_date = shift_day(school.result_date, *days)
+            }
+        }
+    }
+    Ok(dataset)
+}
+
+/// Days from `today` through the last unpaid deadline of an open school
+pub fn horizon(dataset: &Dataset, today: u32) -> i64 {
+    let Some(start) = parse_day(today) else {
+        return 1;
+    };
+    let last = dataset
+        .schools
+        .iter()
+        .filter(|s| s.pass_status.is_open())
+        .flat_map(|s| {
+            [
+                (!s.enrollment_fee_paid).then_some(s.enrollment_fee_deadline),
+                (!s.tuition_paid).then_some(s.tuition_deadline),
+            ]
+        })
+        .flatten()
+        .filter_map(parse_day)
+        .max();
+    last.map_or(1, |last| (last - start).num_days() + 1)
+        .clamp(1, MAX_SIMULATION_DAYS)
+}
+
+/// Advisor request planning `dataset` from `today`
+pub fn request(dataset: &Dataset, today: u32, id: usize) -> JsonRpcRequest {
+    let mut params = crate::handlers::advisor_params(&dataset.to_value());
+    params["startDay"] = today.into();
+    params["days"] = horizon(dataset, today).into();
+    JsonRpcRequest::new(
+        "getWeeklyRecommendations",
+        &params,
+        serde_json::json!(format!("simulation-{}", id)),
+    )
+}
+
+fn to_yen(amount: u64, school: &School, rates: &ExchangeRates) -> u64 {
+    rates
+        .convert(amount, school.currency, Currency::Jpy)
+        .unwrap_or(amount)
+}
+
+/// Outcome of one scenario from the advisor's plan for it
+fn outcome(
+    name: &str,
+    dataset: &Dataset,
+    response: &JsonRpcResponse,
+    rates: &ExchangeRates,
+) -> ScenarioOutcome {
+    let mut outcome = ScenarioOutcome {
+        name: name.to_string(),
+        ..ScenarioOutcome::default()
+    };
+    for school in &dataset.schools {
+        if school.enrollment_fee_paid {
+            let fee = to_yen(school.net_enrollment_fee(), school, rates);
+            outcome.total_cost += fee;
+            outcome.non_refundable_cost += fee;
+        }
+        if school.tuition_paid {
+            outcome.total_cost += to_yen(school.net_tuition(), school, rates);
+        }
+    }
+
+    if let Some(error) = &response.error {
+        outcome.error = Some(error.message.clone());
+        return outcome;
+    }
+    let plan: GetWeeklyRecommendationsResult = match response.parse_result() {
+        Ok(plan) => plan,
+        Err(e) => {
+            outcome.error = Some(e.to_string());
+            return outcome;
+        }
+    };
+
+    for daily in &plan.recommendations {
+        for update in daily.result.state_updates.iter().flatten() {
+            if update.new_status == PassStatus::Cancelled.as_str()
+                && !outcome.lapsed_schools.contains(&update.school_name)
+            {
+                outcome.lapsed_schools.push(update.school_name.clone());
+            }
+        }
+
+        let action = &daily.result.action;
+        let Some(school) = action
+            .school_id
+            .and_then(|id| dataset.schools.iter().find(|s| s.id == id))
+        else {
+            continue;
+        };
+        let amount = match action.action_type {
+            PaymentActionType::PayEnrollmentFee => school.net_enrollment_fee(),
+            PaymentActionType::PayTuition => school.net_tuition(),
+            PaymentActionType::DoNothing => continue,
+        };
+        if outcome
+            .planned_payments
+            .iter()
+            .any(|p| p.school_id == school.id && p.action_type == action.action_type)
+        {
+            continue;
+        }
+        let amount = to_yen(amount, school, rates);
+        outcome.total_cost += amount;
+        if action.action_type == PaymentActionType::PayEnrollmentFee {
+            outcome.non_refundable_cost += amount;
+        }
+        outcome.planned_payments.push(PlannedPayment {
+            day: daily.day,
+            school_id: school.id,
+            school_name: school.name.clone(),
+            action_type: action.action_type,
+            amount,
+        });
+    }
+    outcome
+}
+
+/// Compare the advisor's plans: `variants[0]` is the baseline and the rest
+/// match `scenarios`, with `responses` in the same order
+pub fn compare(
+    scenarios: &[Scenario],
+    variants: &[Dataset],
+    responses: &[JsonRpcResponse],
+    rates: &ExchangeRates,
+) -> SimulationResult {
+    let names = std::iter::once("baseline").chain(scenarios.iter().map(|s| s.name.as_str()));
+    let mut outcomes: Vec<ScenarioOutcome> = names
+        .zip(variants.iter().zip(responses))
+        .map(|(name, (dataset, response))| outcome(name, dataset, response, rates))
+        .collect();
+    let baseline = outcomes.remove(0);
+    for outcome in &mut outcomes {
+        outcome.cost_difference = outcome.total_cost as i64 - baseline.total_cost as i64;
+    }
+    SimulationResult {
+        baseline,
+        scenarios: outcomes,
+        elapsed_ms: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_dataset;
+
+    fn scenario(mutations: serde_json::Value) -> Scenario {
+        serde_json::from_value(serde_json::json!({"name": "test", "mutations": mutations})).unwrap()
+    }
+
+    #[test]
+    fn test_apply_mutations() {
+        let changed = apply(
+            &sample_dataset(),
+            &scenario(serde_json::json!([
+                {"type": "decline", "schoolId": 2},
+                {"type": "delayResult", "schoolId": 1, "days": 7},
+                {"type": "setResult", "schoolId": 1, "status": "passed"}
+            ])),
+        )
+        .unwrap();
+        assert_eq!(changed.schools[0].pass_status, PassStatus::Cancelled);
+        assert_eq!(changed.schools[1].result_date, 20260317);
+        assert_eq!(changed.schools[1].pass_status, PassStatus::Passed);
+
+        assert!(matches!(
+            apply(
+                &sample_dataset(),
+                &scenario(serde_json::json!([{"type": "decline", "schoolId": 9}]))
+            ),
+            Err(SimulationError::UnknownSchool { school_id: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn test_horizon_ends_at_last_open_deadline() {
+        let dataset = sample_dataset();
+        // Tokyo's tuition deadline of 2026-03-31 is the last one
+        assert_eq!(horizon(&dataset, 20260301), 31);
+        assert_eq!(horizon(&Dataset::default(), 20260301), 1);
+    }
+
+    #[test]
+    fn test_compare_outcomes() {
+        let dataset = sample_dataset();
+        let declined = apply(
+            &dataset,
+            &scenario(serde_json::json!([{"type": "decline", "schoolId": 1}])),
+        )
+        .unwrap();
+        let plan = |actions: &[serde_json::Value]| {
+            let recommendations: Vec<_> = actions
+                .iter()
+                .enumerate()
+                .map(|(i, action)| {
+                    serde_json::json!({
+                        "day": 20260301 + i as u32,
+                        "result": {
+                            "action": action, "reason": "", "urgency": 0,
+                            "allRecommendations": []
+                        }
+                    })
+                })
+                .collect();
+            JsonRpcResponse::success(
+                serde_json::json!(0),
+                serde_json::json!({
+                    "startDay": 20260301,
+                    "recommendations": recommendations,
+                    "upcomingAnnouncements": [],
+                    "note": null
+                }),
+            )
+        };
+        let tuition = serde_json::json!({"type": "payTuition", "schoolId": 2});
+        let enrollment = serde_json::json!({"type": "payEnrollmentFee", "schoolId": 1});
+        let responses = [
+            plan(&[tuition.clone(), tuition.clone(), enrollment]),
+            plan(&[tuition]),
+        ];
+
+        let result = compare(
+            &[scenario(serde_json::json!([]))],
+            &[dataset, declined],
+            &responses,
+            &ExchangeRates::default(),
+        );
+        // Waseda's enrollment fee is already paid
+        assert_eq!(result.baseline.total_cost, 200000 + 800000 + 282000);
+        assert_eq!(result.baseline.non_refundable_cost, 200000 + 282000);
+        assert_eq!(result.baseline.planned_payments.len(), 2);
+        assert_eq!(result.scenarios[0].total_cost, 1000000);
+        assert_eq!(result.scenarios[0].cost_difference, -282000);
+    }
+}
@@ -0,0 +1,251 @@
+//! Advisor warm-up sequence with progress reporting.
+//!
+//! [`warm_up`] starts the advisor and prepares the stored data in separate
+//! stages, reporting each one so the desktop app can show a progress
+//! splash instead of a blank window. A failed stage is reported and ends
+//! the sequence; requests will still try to start the advisor on demand.
+//! Once ready, this week's recommendations are preloaded in the background
+//! (see [`crate::preload`]).
+//!
+//! [`start`] runs the sequence as the `advisor.startup` setting says: to
+//! completion before serving, in the background, or not at all so the
+//! advisor starts with the first request.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::date;
+use crate::handlers::{self, AppState, HandlerError};
+use crate::preload;
+
+/// When the advisor is started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupMode {
+    /// Wait until the advisor is ready before serving
+    Eager,
+    /// Serve at once and start the advisor alongside
+    #[default]
+    Background,
+    /// Start the advisor with the first request, saving its memory until then
+    Lazy,
+}
+
+/// Warm-up stages, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StartupStage {
+    LocatingBinary,
+    Spawning,
+    WaitingForReady,
+    Handshake,
+    PreloadingCache,
+    Ready,
+}
+
+impl StartupStage {
+    const ORDER: [StartupStage; 6] = [
+        StartupStage::LocatingBinary,
+        StartupStage::Spawning,
+        StartupStage::WaitingForReady,
+        StartupStage::Handshake,
+        StartupStage::PreloadingCache,
+        StartupStage::Ready,
+    ];
+
+    /// Message shown on the splash screen
+    fn message(self) -> &'static str {
+        match self {
+            Self::LocatingBinary => "アドバイザーを確認しています",
+            Self::Spawning => "アドバイザーを起動しています",
+            Self::WaitingForReady => "アドバイザーの準備を待っています",
+            Self::Handshake => "アドバイザーのバージョンを確認しています",
+            Self::PreloadingCache => "保存データを読み込んでいます",
+            Self::Ready => "準備完了",
+        }
+    }
+}
+
+/// One progress report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupProgress {
+    pub stage: StartupStage,
+    /// 1-based position of `stage`
+    pub step: usize,
+    pub total: usize,
+    pub message: String,
+    /// Set when `stage` failed; no further progress follows
+    pub error: Option<String>,
+}
+
+impl StartupProgress {
+    fn new(stage: StartupStage, error: Option<String>) -> Self {
+        Self {
+            stage,
+            step: StartupStage::ORDER
+                .iter()
+                .position(|s| *s == stage)
+                .unwrap_or(0)
+                + 1,
+            total: StartupStage::ORDER.len(),
+            message: stage.message().to_string(),
+            error,
+        }
+    }
+
+    /// Whether the sequence has finished, successfully or not
+    pub fn is_finished(&self) -> bool {
+        self.stage == StartupStage::Ready || self.error.is_some()
+    }
+}
+
+/// Run one stage, returning its error message on failure
+async fn run_stage(state: &Arc<AppState>, stage: StartupStage) -> Result<(), String> {
+    match stage {
+        StartupStage::LocatingBinary => {
+            let repl = state.lean_repl.lock().await;
+            let path = repl.advisor_path();
+            if !repl.is_demo() && !path.is_file() {
+                return Err(format!("Advisor binary not found: {}", path.display()));
+            }
+        }
+        StartupStage::Spawning => {
+            let state = state.clone();
+            // Spawning and waiting block, so keep them off the async workers
+            tokio::task::spawn_blocking(move || {
+                let mut repl = state.lean_repl.blocking_lock();
+                if repl.is_running() {
+                    return Ok(());
+                }
+                repl.spawn()
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        }
+        StartupStage::WaitingForReady => {
+            let state = state.clone();
+            tokio::task::spawn_blocking(move || state.lean_repl.blocking_lock().wait_ready())
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        StartupStage::Handshake => {
+            let advisor_schema = handlers::advisor_schema_version(state.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            tracing::info!("Advisor supports data schema v{}", advisor_schema);
+        }
+        StartupStage::PreloadingCache => {
+            handlers::migrate_dataset(state.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            handlers::check_advisor_compat(state.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            match state.load_dataset() {
+                Ok(_) | Err(HandlerError::NoData) | Err(HandlerError::NoStorage) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        StartupStage::Ready => {}
+    }
+    Ok(())
+}
+
+/// Run the warm-up sequence, calling `report` as each stage begins or fails.
+///
+/// Returns the final report.
+pub async fn warm_up<F>(state: Arc<AppState>, mut report: F) -> StartupProgress
+where
+    F: FnMut(&StartupProgress),
+{
+    let mut last = StartupProgress::new(StartupStage::LocatingBinary, None);
+    for stage in StartupStage::ORDER {
+        last = StartupProgress::new(stage, None);
+        report(&last);
+        if let Err(error) = run_stage(&state, stage).await {
+            tracing::error!("Startup failed at {:?}: {}", stage, error);
+            last.error = Some(error);
+            report(&last);
+            break;
+        }
+    }
+    if last.error.is_none() {
+        preload::spawn(state, date::today()).await;
+    }
+    last
+}
+
+/// Start the advisor as the `advisor.startup` setting says, calling
+/// `report` as [`warm_up`] does.
+///
+/// Returns once the advisor is ready when eager, at once otherwise. Lazy
+/// startup only migrates the stored data and reports ready.
+pub async fn start<F>(state: Arc<AppState>, mut report: F)
+where
+    F: FnMut(&StartupProgress) + Send + 'static,
+{
+    let mode = state.settings.lock().await.advisor.startup;
+    tracing::info!("Advisor startup: {:?}", mode);
+    match mode {
+        StartupMode::Eager => {
+            warm_up(state, report).await;
+        }
+        StartupMode::Background => {
+            tokio::spawn(warm_up(state, report));
+        }
+        StartupMode::Lazy => {
+            match handlers::migrate_dataset(state).await {
+                Ok(_) | Err(HandlerError::NoStorage) => {}
+                Err(e) => tracing::error!("Data migration failed: {}", e),
+            }
+            report(&StartupProgress::new(StartupStage::Ready, None));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lean_repl::LeanRepl;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_progress_steps() {
+        let first = StartupProgress::new(StartupStage::LocatingBinary, None);
+        assert_eq!((first.step, first.total), (1, 6));
+        assert!(!first.is_finished());
+        assert!(StartupProgress::new(StartupStage::Ready, None).is_finished());
+        assert!(StartupProgress::new(StartupStage::Spawning, Some("x".into())).is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_missing_binary_fails_first_stage() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl));
+
+        let mut reports = Vec::new();
+        let last = warm_up(state, |p| reports.push(p.clone())).await;
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(last.stage, StartupStage::LocatingBinary);
+        assert!(last.error.unwrap().contains("/nonexistent/advisor"));
+    }
+
+    #[tokio::test]
+    async fn test_lazy_start_leaves_advisor_stopped() {
+        let repl = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(repl));
+        state.settings.lock().await.advisor.startup = StartupMode::Lazy;
+
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        start(state.clone(), move |p| sink.lock().unwrap().push(p.clone())).await;
+
+        let stages: Vec<_> = reports.lock().unwrap().iter().map(|p| p.stage).collect();
+        assert_eq!(stages, vec![StartupStage::Ready]);
+        assert!(!state.lean_repl.lock().await.is_running());
+    }
+}
@@ -1,12 +1,21 @@
 //! File storage for persisting application data.
 //!
 //! Used primarily by the Tauri desktop application to save/load school data.
+//! Datasets are saved with [`Storage::save_dataset`], which raises their
+//! revision; see [`crate::revision`].
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
 use thiserror::Error;
 
+use crate::date;
+use crate::field_crypto::{self, FieldCryptoError};
+use crate::journal;
+use crate::revision::{self, RevisionConflict, REVISION_KEY};
+
 /// Errors that can occur during storage operations
 #[derive(Debug, Error)]
 pub enum StorageError {
@@ -18,36 +27,125 @@ pub enum StorageError {
 
     #[error("Failed to get app data directory")]
     NoDataDir,
+
+    #[error("Invalid dataset name: {0}")]
+    InvalidName(String),
+
+    #[error(transparent)]
+    FieldCrypto(#[from] FieldCryptoError),
+
+    #[error(
+        "The data was saved elsewhere since revision {}; it is now at revision {}",
+        .0.expected,
+        .0.current
+    )]
+    Conflict(Box<RevisionConflict>),
 }
 
-/// File-based storage for application data
+/// File-based storage for application data.
+///
+/// Clones share the data directory, so [`Storage::relocate`] moves them all.
+#[derive(Debug, Clone)]
 pub struct Storage {
-    data_dir: PathBuf,
+    data_dir: Arc<RwLock<PathBuf>>,
+    /// Held while a dataset's revision is checked and raised
+    revisions: Arc<Mutex<()>>,
 }
 
 impl Storage {
     /// Create a new Storage with the given data directory
     pub fn new(data_dir: PathBuf) -> Self {
-        Self { data_dir }
+        Self {
+            data_dir: Arc::new(RwLock::new(data_dir)),
+            revisions: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Directory holding the data files
+    pub fn data_dir(&self) -> PathBuf {
+        self.data_dir
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Read and write files in `data_dir` from now on, here and in every clone
+    pub fn relocate(&self, data_dir: PathBuf) {
+        *self.data_dir.write().unwrap_or_else(|e| e.into_inner()) = data_dir;
     }
 
     /// Get the path to a data file
     fn data_path(&self, filename: &str) -> PathBuf {
-        self.data_dir.join(filename)
+        self.data_dir().join(filename)
     }
 
     /// Ensure the data directory exists
     fn ensure_dir(&self) -> Result<(), StorageError> {
-        fs::create_dir_all(&self.data_dir)?;
+        fs::create_dir_all(self.data_dir())?;
         Ok(())
     }
 
+    /// Save dataset file `filename` with its revision raised by one, unless
+    /// it has moved on from the `expected` revision. `data` is given the new
+    /// revision, which is also returned.
+    pub fn save_dataset(
+        &self,
+        filename: &str,
+        data: &mut serde_json::Value,
+        expected: Option<u64>,
+    ) -> Result<u64, StorageError> {
+        let _guard = self.revisions.lock().unwrap_or_else(|e| e.into_inner());
+        let current = self.stored_revision(filename);
+        if let Some(expected) = expected.filter(|&e| e != current) {
+            let saved = self.load(filename)?.unwrap_or_default();
+            return Err(StorageError::Conflict(Box::new(RevisionConflict::new(
+                expected, &saved, data,
+            ))));
+        }
+        if let Some(fields) = data.as_object_mut() {
+            fields.insert(REVISION_KEY.to_string(), (current + 1).into());
+        }
+        self.save(filename, data)?;
+        if let Err(e) = journal::record(self, filename, data, date::now()) {
+            tracing::warn!("Could not add {} to the journal: {}", filename, e);
+        }
+        Ok(current + 1)
+    }
+
+    /// Revision of dataset file `filename` as saved, read without
+    /// decrypting so that an unreadable file can still be replaced
+    fn stored_revision(&self, filename: &str) -> u64 {
+        fs::read_to_string(self.data_path(filename))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .map_or(0, |data| revision::of(&data))
+    }
+
     /// Save data to a file
     pub fn save(&self, filename: &str, data: &serde_json::Value) -> Result<(), StorageError> {
         self.ensure_dir()?;
         let path = self.data_path(filename);
-        let content = serde_json::to_string_pretty(data)?;
-        fs::write(path, content)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = match field_crypto::current().filter(|_| field_crypto::covers(filename)) {
+            Some(cipher) => {
+                let mut data = data.clone();
+                cipher.encrypt(&mut data);
+                serde_json::to_string_pretty(&data)?
+            }
+            None => serde_json::to_string_pretty(data)?,
+        };
+        // Written aside and moved into place, so that a load running at the
+        // same time sees the old or the new file, never a partial one
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let mut partial = path.clone().into_os_string();
+        partial.push(format!(".{}.tmp", NEXT.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&partial, content)?;
+        if let Err(e) = fs::rename(&partial, &path) {
+            let _ = fs::remove_file(&partial);
+            return Err(e.into());
+        }
         Ok(())
     }
 
@@ -58,7 +156,8 @@ impl Storage {
             return Ok(None);
         }
         let content = fs::read_to_string(path)?;
-        let data: serde_json::Value = serde_json::from_str(&content)?;
+        let mut data: serde_json::Value = serde_json::from_str(&content)?;
+        field_crypto::decrypt(&mut data)?;
         Ok(Some(data))
     }
 
@@ -75,11 +174,73 @@ impl Storage {
         }
         Ok(())
     }
+
+    /// List named datasets saved with [`dataset_file`], sorted by name
+    pub fn list_datasets(&self) -> Result<Vec<String>, StorageError> {
+        let dir = self.data_dir().join(DATASETS_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                file_name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
 }
 
 /// Default data filename for school data
 pub const SCHOOLS_DATA_FILE: &str = "data.json";
 
+/// Subdirectory holding named datasets ("Save plan as...")
+pub const DATASETS_DIR: &str = "plans";
+
+/// Resolve an optional dataset name to a file name.
+///
+/// `None` is the default dataset. Names may not contain path separators,
+/// `..`, or characters that are invalid in Windows file names.
+pub fn dataset_file(name: Option<&str>) -> Result<String, StorageError> {
+    let Some(name) = name else {
+        return Ok(SCHOOLS_DATA_FILE.to_string());
+    };
+    let name = name.strip_suffix(".json").unwrap_or(name).trim();
+    let invalid = name.is_empty()
+        || name.len() > 100
+        || name.starts_with('.')
+        || name.contains("..")
+        || name
+            .chars()
+            .any(|c| c.is_control() || r#"/\:*?"<>|"#.contains(c));
+    if invalid {
+        return Err(StorageError::InvalidName(name.to_string()));
+    }
+    Ok(format!("{}/{}.json", DATASETS_DIR, name))
+}
+
+/// File beside the executable that turns on portable mode
+pub const PORTABLE_FLAG_FILE: &str = "portable.flag";
+
+/// Command-line flag that turns on portable mode
+pub const PORTABLE_ARG: &str = "--portable";
+
+/// Data directory beside the executable in portable mode
+pub const PORTABLE_DATA_DIR: &str = "data";
+
+/// Data directory for portable mode, or `None` to use the OS app-data directory.
+///
+/// Portable mode keeps everything in `data/` beside the executable at
+/// `exe`, so the app can run from a USB stick or a synced folder. It is on
+/// when `portable_arg` was given or a [`PORTABLE_FLAG_FILE`] exists there.
+pub fn portable_data_dir(exe: &Path, portable_arg: bool) -> Option<PathBuf> {
+    let exe_dir = exe.parent()?;
+    (portable_arg || exe_dir.join(PORTABLE_FLAG_FILE).is_file())
+        .then(|| exe_dir.join(PORTABLE_DATA_DIR))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,6 +284,59 @@ mod tests {
         assert!(storage.exists("test.json"));
     }
 
+    #[test]
+    fn test_dataset_file() {
+        assert_eq!(dataset_file(None).unwrap(), SCHOOLS_DATA_FILE);
+        assert_eq!(
+            dataset_file(Some("第一志望プラン")).unwrap(),
+            "plans/第一志望プラン.json"
+        );
+        assert_eq!(dataset_file(Some("plan-b.json")).unwrap(), "plans/plan-b.json");
+        for name in ["", "../data", "a/b", "a\\b", ".hidden", "c:evil", "x\u{0}"] {
+            assert!(dataset_file(Some(name)).is_err(), "{:?} accepted", name);
+        }
+    }
+
+    #[test]
+    fn test_named_datasets() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        assert!(storage.list_datasets().unwrap().is_empty());
+
+        let data = serde_json::json!({"schools": []});
+        for name in ["plan-b", "plan-a"] {
+            storage.save(&dataset_file(Some(name)).unwrap(), &data).unwrap();
+        }
+        assert_eq!(storage.list_datasets().unwrap(), vec!["plan-a", "plan-b"]);
+        let loaded = storage.load(&dataset_file(Some("plan-a")).unwrap()).unwrap();
+        assert_eq!(loaded, Some(data));
+    }
+
+    #[test]
+    fn test_dataset_revisions() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let mut data = serde_json::json!({"schools": [{"id": 1, "name": "A"}], "revision": 9});
+
+        // The stored revision counts, not the one sent
+        assert_eq!(storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, Some(0)).unwrap(), 1);
+        assert_eq!(data["revision"], 1);
+        storage.save_dataset(SCHOOLS_DATA_FILE, &mut data, None).unwrap();
+        let saved = storage.load(SCHOOLS_DATA_FILE).unwrap().unwrap();
+        assert_eq!(saved["revision"], 2);
+
+        let mut stale = serde_json::json!({"schools": [], "revision": 1});
+        match storage.save_dataset(SCHOOLS_DATA_FILE, &mut stale, Some(1)) {
+            Err(StorageError::Conflict(conflict)) => {
+                assert_eq!((conflict.expected, conflict.current), (1, 2));
+                assert_eq!(conflict.diff.removed[0].name, "A");
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+        assert_eq!(storage.load(SCHOOLS_DATA_FILE).unwrap(), Some(saved));
+        assert_eq!(storage.save_dataset(SCHOOLS_DATA_FILE, &mut stale, Some(2)).unwrap(), 3);
+    }
+
     #[test]
     fn test_delete() {
         let dir = tempdir().unwrap();
@@ -134,4 +348,22 @@ mod tests {
         storage.delete("test.json").unwrap();
         assert!(!storage.exists("test.json"));
     }
+
+    #[test]
+    fn test_portable_data_dir() {
+        let dir = tempdir().unwrap();
+        let exe = dir.path().join("school-payment.exe");
+
+        assert_eq!(portable_data_dir(&exe, false), None);
+        assert_eq!(
+            portable_data_dir(&exe, true),
+            Some(dir.path().join(PORTABLE_DATA_DIR))
+        );
+
+        fs::write(dir.path().join(PORTABLE_FLAG_FILE), "").unwrap();
+        assert_eq!(
+            portable_data_dir(&exe, false),
+            Some(dir.path().join(PORTABLE_DATA_DIR))
+        );
+    }
 }
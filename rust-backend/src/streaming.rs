@@ -0,0 +1,156 @@
+//! Large results sent in chunks.
+//!
+//! A year of weekly recommendations or a long recommendation history runs to
+//! thousands of items. Sent as one response, the frontend has to receive and
+//! parse all of it before drawing anything. [`Chunks`] splits such a result
+//! into [`ResultChunk`]s instead: the web server writes each as a line of an
+//! NDJSON response and the desktop app emits each as an event, so the first
+//! rows are shown while the rest are still on their way.
+//!
+//! The advisor is still asked once for the whole range, since each day's
+//! answer depends on the actions it recommended for the days before.
+
+use serde::Serialize;
+use serde_json::Value;
+use ts_rs::TS;
+
+/// Items per chunk unless the caller asks for another size
+pub const DEFAULT_CHUNK_SIZE: usize = 50;
+
+/// Largest chunk size a caller can ask for
+pub const MAX_CHUNK_SIZE: usize = 1000;
+
+/// Array field of the result that is sent in chunks, by advisor method.
+/// Results of other methods are chunked when they are arrays themselves.
+const STREAMED_FIELDS: [(&str, &str); 1] = [("getWeeklyRecommendations", "recommendations")];
+
+/// One part of a result sent in chunks
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ResultChunk {
+    /// Position of the chunk, from 0
+    pub seq: u32,
+    /// The rest of the result besides the chunked items; first chunk only
+    #[ts(type = "unknown")]
+    pub header: Option<Value>,
+    #[ts(type = "Array<unknown>")]
+    pub items: Vec<Value>,
+    /// Number of items in all chunks together
+    pub total: u32,
+    /// Whether this is the last chunk
+    pub done: bool,
+}
+
+/// A result split into chunks of at most `size` items.
+///
+/// There is always at least one chunk, so an empty result still delivers its
+/// header and a `done` chunk.
+#[derive(Debug)]
+pub struct Chunks {
+    header: Option<Value>,
+    items: std::vec::IntoIter<Value>,
+    size: usize,
+    total: u32,
+    seq: u32,
+    finished: bool,
+}
+
+impl Chunks {
+    /// `size` is clamped to 1..=[`MAX_CHUNK_SIZE`]
+    pub fn new(header: Option<Value>, items: Vec<Value>, size: usize) -> Self {
+        Self {
+            header,
+            total: items.len() as u32,
+            items: items.into_iter(),
+            size: size.clamp(1, MAX_CHUNK_SIZE),
+            seq: 0,
+            finished: false,
+        }
+    }
+
+    /// Split the result of advisor `method`: its streamed field, or the
+    /// result itself when it is an array. Anything else is sent whole as
+    /// the header.
+    pub fn from_result(method: &str, result: Value, size: usize) -> Self {
+        let field = STREAMED_FIELDS
+            .iter()
+            .find(|(m, _)| *m == method)
+            .map(|(_, field)| *field);
+        match (field, result) {
+            (Some(field), Value::Object(mut header)) => match header.remove(field) {
+                Some(Value::Array(items)) => Self::new(Some(Value::Object(header)), items, size),
+                Some(other) => {
+                    header.insert(field.to_string(), other);
+                    Self::new(Some(Value::Object(header)), Vec::new(), size)
+                }
+                None => Self::new(Some(Value::Object(header)), Vec::new(), size),
+            },
+            (_, Value::Array(items)) => Self::new(None, items, size),
+            (_, other) => Self::new(Some(other), Vec::new(), size),
+        }
+    }
+}
+
+impl Iterator for Chunks {
+    type Item = ResultChunk;
+
+    fn next(&mut self) -> Option<ResultChunk> {
+        if self.finished {
+            return None;
+        }
+        let items: Vec<Value> = self.items.by_ref().take(self.size).collect();
+        self.finished = self.items.as_slice().is_empty();
+        let chunk = ResultChunk {
+            seq: self.seq,
+            header: self.header.take(),
+            items,
+            total: self.total,
+            done: self.finished,
+        };
+        self.seq += 1;
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_weekly_recommendations_are_chunked() {
+        let days: Vec<Value> = (0..5).map(|d| json!({ "day": 20260301 + d })).collect();
+        let result = json!({ "recommendations": days, "summary": { "paid": 2 } });
+        let chunks: Vec<ResultChunk> =
+            Chunks::from_result("getWeeklyRecommendations", result, 2).collect();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].header, Some(json!({ "summary": { "paid": 2 } })));
+        assert!(chunks[1..].iter().all(|c| c.header.is_none()));
+        let sizes: Vec<usize> = chunks.iter().map(|c| c.items.len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        let seqs: Vec<u32> = chunks.iter().map(|c| c.seq).collect();
+        assert_eq!(seqs, vec![0, 1, 2]);
+        assert!(chunks.iter().all(|c| c.total == 5));
+        assert_eq!(chunks.iter().filter(|c| c.done).count(), 1);
+        assert!(chunks[2].done);
+        let items: Vec<Value> = chunks.into_iter().flat_map(|c| c.items).collect();
+        assert_eq!(items, days);
+    }
+
+    #[test]
+    fn test_other_results_are_sent_whole() {
+        let chunks: Vec<ResultChunk> =
+            Chunks::from_result("getVersion", json!({ "version": "1.0.0" }), 10).collect();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].done);
+        assert_eq!(chunks[0].header, Some(json!({ "version": "1.0.0" })));
+        assert!(chunks[0].items.is_empty());
+
+        let chunks: Vec<ResultChunk> =
+            Chunks::from_result("listSomething", json!([1, 2, 3]), 0).collect();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].header.is_none());
+    }
+}
@@ -0,0 +1,856 @@
+//! Payment totals over a period, computed without the advisor.
+//!
+//! Each school contributes its application fee on the exam date (taken as
+//! already paid), its enrollment fee and its tuition on their deadlines,
+//! with tuition split into its installments when paid in parts, and the
+//! payments generated from its recurring fees. Fees are counted after scholarships and discounts. Unpaid payments of schools that failed or were
+//! cancelled are left out, since they will never be made. Amounts are
+//! converted to yen with the saved exchange rates.
+//!
+//! When the dataset has children, totals are also broken down by child,
+//! month by month, for the household's combined cash flow; a range may
+//! also be limited to one child's schools.
+//!
+//! Payments are also totalled by the method they were or will be paid by,
+//! with the charges of each method kept apart from the fees themselves.
+//!
+//! Any figure of a summary can be [`explain`]ed as the payments that add
+//! up to it, each linked to its school.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::budget::BudgetStatus;
+use crate::currency::ExchangeRates;
+use crate::model::{method_charge, Currency, Dataset, FeeKind, PaymentMethod, School};
+
+/// Days to include, both ends inclusive and optional
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SummaryRange {
+    #[ts(optional)]
+    pub from: Option<u32>,
+    #[ts(optional)]
+    pub to: Option<u32>,
+    /// Only the schools of this child
+    #[ts(optional, type = "number")]
+    pub child_id: Option<u64>,
+}
+
+impl SummaryRange {
+    fn contains(&self, day: u32) -> bool {
+        self.from.is_none_or(|from| day >= from) && self.to.is_none_or(|to| day <= to)
+    }
+}
+
+/// Kind of payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum PaymentCategory {
+    ApplicationFee,
+    EnrollmentFee,
+    Tuition,
+    /// Fees due again and again, such as monthly tuition
+    Recurring,
+}
+
+impl PaymentCategory {
+    /// Whether the payment is returned if the place is declined.
+    ///
+    /// Universities refund tuition for places declined by the end of March;
+    /// application and enrollment fees are kept.
+    pub fn is_refundable(self) -> bool {
+        self == Self::Tuition
+    }
+}
+
+/// Totals for one month, with the running total up to its end
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MonthSummary {
+    /// YYYYMM
+    pub month: u32,
+    #[ts(type = "number")]
+    pub total: u64,
+    #[ts(type = "number")]
+    pub paid: u64,
+    #[ts(type = "number")]
+    pub cumulative: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SchoolSummary {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    #[ts(type = "number")]
+    pub total: u64,
+    #[ts(type = "number")]
+    pub paid: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CategorySummary {
+    pub category: PaymentCategory,
+    #[ts(type = "number")]
+    pub total: u64,
+    #[ts(type = "number")]
+    pub paid: u64,
+}
+
+/// Totals of the payments made or to be made by one method
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MethodSummary {
+    pub method: PaymentMethod,
+    #[ts(type = "number")]
+    pub total: u64,
+    #[ts(type = "number")]
+    pub paid: u64,
+    /// Charges of the method on top of `total`
+    #[ts(type = "number")]
+    pub fees: u64,
+}
+
+/// Totals for one child, with its share of each month
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ChildSummary {
+    /// `None` for schools not tagged with a child
+    #[ts(type = "number | null")]
+    pub child_id: Option<u64>,
+    pub child_name: Option<String>,
+    #[ts(type = "number")]
+    pub total: u64,
+    #[ts(type = "number")]
+    pub paid: u64,
+    pub by_month: Vec<MonthSummary>,
+}
+
+/// Payment totals over a range, in yen
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Summary {
+    pub currency: Currency,
+    #[ts(type = "number")]
+    pub total: u64,
+    #[ts(type = "number")]
+    pub paid: u64,
+    #[ts(type = "number")]
+    pub refundable: u64,
+    #[ts(type = "number")]
+    pub non_refundable: u64,
+    pub by_month: Vec<MonthSummary>,
+    pub by_school: Vec<SchoolSummary>,
+    pub by_category: Vec<CategorySummary>,
+    /// Totals per payment method, for the payments whose method is known
+    pub by_method: Vec<MethodSummary>,
+    /// Charges of the payment methods on top of `total`, so `total` plus
+    /// this is what comes out of pocket
+    #[ts(type = "number")]
+    pub method_fees: u64,
+    /// Totals per child, empty unless the dataset has children
+    pub by_child: Vec<ChildSummary>,
+    /// Schools left out because their currency has no exchange rate
+    #[ts(type = "Array<number>")]
+    pub unconverted_school_ids: Vec<u64>,
+    /// Currencies of the schools whose rate is past its maximum age, such
+    /// as the last known rate when the rate service could not be reached
+    pub stale_rates: Vec<Currency>,
+    /// Season spend against the budget, when one is set
+    pub budget: Option<BudgetStatus>,
+}
+
+/// One payment counted in a [`Summary`], in yen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SummaryItem {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    #[ts(type = "number | null")]
+    pub child_id: Option<u64>,
+    pub category: PaymentCategory,
+    /// Due date, or the exam date for application fees
+    pub day: u32,
+    #[ts(type = "number")]
+    pub amount: u64,
+    pub paid: bool,
+    pub method: Option<PaymentMethod>,
+    /// Charge of `method` on top of `amount`
+    #[ts(type = "number")]
+    pub method_fee: u64,
+    /// The school's currency, and the amount in it before conversion
+    pub currency: Currency,
+    #[ts(type = "number")]
+    pub original_amount: u64,
+}
+
+/// A figure of a [`Summary`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "figure", rename_all = "camelCase")]
+#[ts(export)]
+pub enum SummaryFigure {
+    Total,
+    Paid,
+    Refundable,
+    NonRefundable,
+    MethodFees,
+    /// A month's total; its running total is explained by the months up
+    /// to it
+    Month {
+        month: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    School {
+        #[ts(type = "number")]
+        school_id: u64,
+    },
+    Category {
+        category: PaymentCategory,
+    },
+    Method {
+        method: PaymentMethod,
+    },
+    /// A child's total; `None` for schools not tagged with a child
+    #[serde(rename_all = "camelCase")]
+    Child {
+        #[ts(type = "number | null")]
+        child_id: Option<u64>,
+    },
+}
+
+impl SummaryFigure {
+    /// Whether `item` adds to this figure
+    fn counts(self, item: &SummaryItem) -> bool {
+        match self {
+            Self::Total => true,
+            Self::Paid => item.paid,
+            Self::Refundable => item.category.is_refundable(),
+            Self::NonRefundable => !item.category.is_refundable(),
+            Self::MethodFees => item.method_fee > 0,
+            Self::Month { month } => item.day / 100 == month,
+            Self::School { school_id } => item.school_id == school_id,
+            Self::Category { category } => item.category == category,
+            Self::Method { method } => item.method == Some(method),
+            Self::Child { child_id } => item.child_id == child_id,
+        }
+    }
+
+    /// How much `item` adds to this figure
+    fn contribution(self, item: &SummaryItem) -> u64 {
+        match self {
+            Self::MethodFees => item.method_fee,
+            _ => item.amount,
+        }
+    }
+}
+
+/// The payments adding up to a figure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TotalExplanation {
+    pub figure: SummaryFigure,
+    /// The figure, as in the summary: the sum of the items' amounts, or of
+    /// their method fees for [`SummaryFigure::MethodFees`]
+    #[ts(type = "number")]
+    pub value: u64,
+    /// In date order
+    pub items: Vec<SummaryItem>,
+    /// Schools left out because their currency has no exchange rate
+    #[ts(type = "Array<number>")]
+    pub unconverted_school_ids: Vec<u64>,
+}
+
+/// One payment of a school, in the school's currency
+struct Payment {
+    category: PaymentCategory,
+    day: u32,
+    amount: u64,
+    paid: bool,
+    /// As recorded in its receipt, or the school's planned method
+    method: Option<PaymentMethod>,
+}
+
+fn payments(school: &School) -> Vec<Payment> {
+    let method = |fee: FeeKind, installment: Option<u32>| {
+        school
+            .receipts
+            .iter()
+            .find(|r| r.fee == fee && r.installment == installment)
+            .map(|r| r.method)
+            .or(school.payment_method)
+    };
+    let mut payments = Vec::new();
+    if let Some(amount) = school.application_fee {
+        payments.push(Payment {
+            category: PaymentCategory::ApplicationFee,
+            day: school.exam_date,
+            amount,
+            paid: true,
+            method: school.payment_method,
+        });
+    }
+    payments.push(Payment {
+        category: PaymentCategory::EnrollmentFee,
+        day: school.enrollment_fee_deadline,
+        amount: school.net_enrollment_fee(),
+        paid: school.enrollment_fee_paid,
+        method: method(FeeKind::EnrollmentFee, None),
+    });
+    let in_parts = !school.installments.is_empty();
+    payments.extend(
+        school
+            .tuition_payments()
+            .into_iter()
+            .zip(1..)
+            .map(|(i, number)| Payment {
+                category: PaymentCategory::Tuition,
+                day: i.due,
+                amount: i.amount,
+                paid: i.paid,
+                method: method(FeeKind::Tuition, in_parts.then_some(number)),
+            }),
+    );
+    payments.extend(school.recurring_payments.iter().map(|p| Payment {
+        category: PaymentCategory::Recurring,
+        day: p.due,
+        amount: p.amount,
+        paid: p.paid,
+        method: school.payment_method,
+    }));
+    payments.retain(|p| p.paid || school.pass_status.is_open());
+    payments
+}
+
+/// Monthly totals with running totals, in month order
+fn month_summaries(months: BTreeMap<u32, (u64, u64)>) -> Vec<MonthSummary> {
+    let mut cumulative = 0;
+    months
+        .into_iter()
+        .map(|(month, (total, paid))| {
+            cumulative += total;
+            MonthSummary {
+                month,
+                total,
+                paid,
+                cumulative,
+            }
+        })
+        .collect()
+}
+
+/// Payments in `dataset` falling within `range`, converted to yen, school
+/// by school, with the ids of the schools that could not be converted
+fn items(
+    dataset: &Dataset,
+    range: SummaryRange,
+    rates: &ExchangeRates,
+) -> (Vec<SummaryItem>, Vec<u64>) {
+    let mut items = Vec::new();
+    let mut unconverted = Vec::new();
+    let schools = dataset
+        .schools
+        .iter()
+        .filter(|s| range.child_id.is_none() || s.child_id == range.child_id);
+    for school in schools {
+        for payment in payments(school) {
+            if !range.contains(payment.day) {
+                continue;
+            }
+            let Some(amount) = rates.convert(payment.amount, school.currency, Currency::Jpy) else {
+                if !unconverted.contains(&school.id) {
+                    unconverted.push(school.id);
+                }
+                continue;
+            };
+            items.push(SummaryItem {
+                school_id: school.id,
+                school_name: school.name.clone(),
+                child_id: school.child_id,
+                category: payment.category,
+                day: payment.day,
+                amount,
+                paid: payment.paid,
+                method: payment.method,
+                method_fee: payment
+                    .method
+                    .map_or(0, |m| method_charge(&dataset.method_fees, m, amount)),
+                currency: school.currency,
+                original_amount: payment.amount,
+            });
+        }
+    }
+    (items, unconverted)
+}
+
+/// Totals of the payments in `dataset` falling within `range`
+pub fn summarize(dataset: &Dataset, range: SummaryRange, rates: &ExchangeRates) -> Summary {
+    let mut summary = Summary::default();
+    let mut months: BTreeMap<u32, (u64, u64)> = BTreeMap::new();
+    let mut categories: BTreeMap<PaymentCategory, (u64, u64)> = BTreeMap::new();
+    let mut methods: BTreeMap<PaymentMethod, (u64, u64, u64)> = BTreeMap::new();
+    let mut children: BTreeMap<Option<u64>, BTreeMap<u32, (u64, u64)>> = BTreeMap::new();
+
+    let (items, unconverted) = items(dataset, range, rates);
+    summary.unconverted_school_ids = unconverted;
+    for item in &items {
+        let amount = item.amount;
+        let paid = if item.paid { amount } else { 0 };
+
+        // Items come school by school
+        match summary.by_school.last_mut() {
+            Some(school) if school.school_id == item.school_id => {
+                school.total += amount;
+                school.paid += paid;
+            }
+            _ => summary.by_school.push(SchoolSummary {
+                school_id: item.school_id,
+                school_name: item.school_name.clone(),
+                total: amount,
+                paid,
+            }),
+        }
+        for totals in [
+            months.entry(item.day / 100).or_default(),
+            categories.entry(item.category).or_default(),
+            children
+                .entry(item.child_id)
+                .or_default()
+                .entry(item.day / 100)
+                .or_default(),
+        ] {
+            totals.0 += amount;
+            totals.1 += paid;
+        }
+        if let Some(method) = item.method {
+            let totals = methods.entry(method).or_default();
+            totals.0 += amount;
+            totals.1 += paid;
+            totals.2 += item.method_fee;
+            summary.method_fees += item.method_fee;
+        }
+        summary.total += amount;
+        summary.paid += paid;
+        if item.category.is_refundable() {
+            summary.refundable += amount;
+        } else {
+            summary.non_refundable += amount;
+        }
+    }
+    summary.by_school.retain(|school| school.total > 0);
+
+    summary.by_month = month_summaries(months);
+    summary.by_category = categories
+        .into_iter()
+        .map(|(category, (total, paid))| CategorySummary {
+            category,
+            total,
+            paid,
+        })
+        .collect();
+    summary.by_method = methods
+        .into_iter()
+        .map(|(method, (total, paid, fees))| MethodSummary {
+            method,
+            total,
+            paid,
+            fees,
+        })
+        .collect();
+    if !dataset.children.is_empty() {
+        summary.by_child = children
+            .into_iter()
+            .map(|(child_id, months)| ChildSummary {
+                child_id,
+                child_name: child_id
+                    .and_then(|id| dataset.child_name(id))
+                    .map(str::to_string),
+                total: months.values().map(|m| m.0).sum(),
+                paid: months.values().map(|m| m.1).sum(),
+                by_month: month_summaries(months),
+            })
+            .collect();
+    }
+    summary
+}
+
+/// The payments in `dataset` within `range` that add up to `figure` of its
+/// summary
+pub fn explain(
+    dataset: &Dataset,
+    range: SummaryRange,
+    rates: &ExchangeRates,
+    figure: SummaryFigure,
+) -> TotalExplanation {
+    let (items, unconverted_school_ids) = items(dataset, range, rates);
+    let mut items: Vec<_> = items
+        .into_iter()
+        .filter(|item| figure.counts(item))
+        .collect();
+    items.sort_by_key(|item| item.day);
+    TotalExplanation {
+        figure,
+        value: items.iter().map(|item| figure.contribution(item)).sum(),
+        items,
+        unconverted_school_ids,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        Adjustment, Child, InstallmentPlan, MethodFee, PassStatus, Receipt, RecurringFee,
+    };
+
+    fn school(id: u64, pass_status: PassStatus) -> School {
+        School {
+            id,
+            name: format!("学校{}", id),
+            priority: id as u32,
+            exam_date: 20260201,
+            result_date: 20260210,
+            enrollment_fee_deadline: 20260220,
+            tuition_deadline: 20260315,
+            enrollment_fee: 200000,
+            tuition: 500000,
+            application_fee: Some(35000),
+            currency: Currency::Jpy,
+            pass_status,
+            enrollment_fee_paid: false,
+            tuition_paid: false,
+            payee: None,
+            installments: Vec::new(),
+            adjustments: Vec::new(),
+            child_id: None,
+            receipts: Vec::new(),
+            recurring_fees: Vec::new(),
+            recurring_payments: Vec::new(),
+            waitlisted: false,
+            payment_method: None,
+            notes: Vec::new(),
+        }
+    }
+
+    fn dataset() -> Dataset {
+        let mut passed = school(1, PassStatus::Passed);
+        passed.enrollment_fee_paid = true;
+        let mut failed = school(2, PassStatus::Failed);
+        failed.application_fee = Some(30000);
+        let mut overseas = school(3, PassStatus::NotYetAnnounced);
+        overseas.currency = Currency::Usd;
+        overseas.application_fee = None;
+        Dataset {
+            schema_version: 2,
+            revision: 0,
+            schools: vec![passed, failed, overseas],
+            budget: None,
+            children: Vec::new(),
+            method_fees: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(
+            &dataset(),
+            SummaryRange::default(),
+            &ExchangeRates::default(),
+        );
+
+        // The failed school only contributes its application fee, and the
+        // dollar school has no rate
+        assert_eq!(summary.total, 35000 + 200000 + 500000 + 30000);
+        assert_eq!(summary.paid, 35000 + 200000 + 30000);
+        assert_eq!(summary.refundable, 500000);
+        assert_eq!(summary.non_refundable, 265000);
+        assert_eq!(summary.unconverted_school_ids, vec![3]);
+
+        let months: Vec<_> = summary
+            .by_month
+            .iter()
+            .map(|m| (m.month, m.total, m.cumulative))
+            .collect();
+        assert_eq!(
+            months,
+            vec![(202602, 265000, 265000), (202603, 500000, 765000)]
+        );
+        assert_eq!(summary.by_school.len(), 2);
+        assert_eq!(summary.by_school[1].total, 30000);
+        assert_eq!(
+            summary.by_category[0],
+            CategorySummary {
+                category: PaymentCategory::ApplicationFee,
+                total: 65000,
+                paid: 65000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_range_and_conversion() {
+        let mut rates = ExchangeRates::default();
+        rates.set_manual(Currency::Usd, Some(150.0)).unwrap();
+        let range = SummaryRange {
+            from: Some(20260301),
+            to: Some(20260331),
+            ..SummaryRange::default()
+        };
+        let summary = summarize(&dataset(), range, &rates);
+
+        // Tuition only: ¥500,000 plus US$5,000.00 at ¥150
+        assert_eq!(summary.total, 500000 + 750000);
+        assert_eq!(summary.by_month.len(), 1);
+        assert!(summary.unconverted_school_ids.is_empty());
+    }
+
+    #[test]
+    fn test_children() {
+        let mut dataset = dataset();
+        dataset.children = vec![
+            Child {
+                id: 1,
+                name: "太郎".to_string(),
+            },
+            Child {
+                id: 2,
+                name: "花子".to_string(),
+            },
+        ];
+        dataset.schools[0].child_id = Some(1);
+        dataset.schools[1].child_id = Some(2);
+        let rates = ExchangeRates::default();
+
+        let household = summarize(&dataset, SummaryRange::default(), &rates);
+        let children: Vec<_> = household
+            .by_child
+            .iter()
+            .map(|c| (c.child_id, c.child_name.as_deref(), c.total))
+            .collect();
+        assert_eq!(
+            children,
+            vec![
+                (Some(1), Some("太郎"), 35000 + 200000 + 500000),
+                (Some(2), Some("花子"), 30000),
+            ]
+        );
+        assert_eq!(household.by_child[0].by_month.len(), 2);
+
+        let range = SummaryRange {
+            child_id: Some(2),
+            ..SummaryRange::default()
+        };
+        let summary = summarize(&dataset, range, &rates);
+        assert_eq!(summary.total, 30000);
+        assert_eq!(summary.by_child.len(), 1);
+    }
+
+    #[test]
+    fn test_installments_and_adjustments() {
+        let mut school = school(1, PassStatus::Passed);
+        school.application_fee = None;
+        school.enrollment_fee_paid = true;
+        school.tuition_paid = true;
+        school.adjustments = vec![Adjustment {
+            label: "特待生奨学金".to_string(),
+            fee: FeeKind::EnrollmentFee,
+            amount: 50000,
+        }];
+        school.installments = InstallmentPlan {
+            total: 500000,
+            first_due: 20260315,
+            count: 2,
+            interval_months: 6,
+        }
+        .schedule()
+        .unwrap();
+        let dataset = Dataset {
+            schema_version: 2,
+            revision: 0,
+            schools: vec![school],
+            budget: None,
+            children: Vec::new(),
+            method_fees: Vec::new(),
+        };
+        let summary = summarize(&dataset, SummaryRange::default(), &ExchangeRates::default());
+
+        // The first installment is paid with the place secured
+        assert_eq!(summary.total, 150000 + 500000);
+        assert_eq!(summary.paid, 150000 + 250000);
+        let months: Vec<_> = summary.by_month.iter().map(|m| m.month).collect();
+        assert_eq!(months, vec![202602, 202603, 202609]);
+    }
+
+    #[test]
+    fn test_recurring_payments() {
+        let mut school = school(1, PassStatus::Passed);
+        school.application_fee = None;
+        school.enrollment_fee_paid = true;
+        school.tuition_paid = true;
+        school.recurring_fees = vec![RecurringFee {
+            label: "施設設備費".to_string(),
+            amount: 30000,
+            first_due: 20260410,
+            interval_months: 1,
+            until: None,
+        }];
+        school.regenerate_recurring(20260401);
+        school.recurring_payments[0].paid = true;
+        let dataset = Dataset {
+            schema_version: 2,
+            revision: 0,
+            schools: vec![school],
+            budget: None,
+            children: Vec::new(),
+            method_fees: Vec::new(),
+        };
+        let range = SummaryRange {
+            from: Some(20260401),
+            to: Some(20260630),
+            ..SummaryRange::default()
+        };
+        let summary = summarize(&dataset, range, &ExchangeRates::default());
+
+        let recurring = summary
+            .by_category
+            .iter()
+            .find(|c| c.category == PaymentCategory::Recurring)
+            .unwrap();
+        assert_eq!(recurring.total, 90000);
+        assert_eq!(recurring.paid, 30000);
+        let months: Vec<_> = summary.by_month.iter().map(|m| m.month).collect();
+        assert_eq!(months, vec![202604, 202605, 202606]);
+    }
+
+    #[test]
+    fn test_method_fees() {
+        let mut school = school(1, PassStatus::Passed);
+        school.application_fee = None;
+        school.payment_method = Some(PaymentMethod::CreditCard);
+        school.enrollment_fee_paid = true;
+        school.receipts = vec![Receipt {
+            fee: FeeKind::EnrollmentFee,
+            installment: None,
+            paid_on: 20260218,
+            method: PaymentMethod::ConvenienceStore,
+            reference: None,
+            attachment: None,
+        }];
+        let dataset = Dataset {
+            schema_version: 2,
+            revision: 0,
+            schools: vec![school],
+            budget: None,
+            children: Vec::new(),
+            method_fees: vec![
+                MethodFee {
+                    method: PaymentMethod::CreditCard,
+                    fixed: 0,
+                    rate_bp: 200,
+                    cap: Some(8000),
+                },
+                MethodFee {
+                    method: PaymentMethod::ConvenienceStore,
+                    fixed: 330,
+                    rate_bp: 0,
+                    cap: None,
+                },
+            ],
+        };
+        let summary = summarize(&dataset, SummaryRange::default(), &ExchangeRates::default());
+
+        // The receipt's method counts for the enrollment fee, the school's
+        // planned method for the unpaid tuition
+        assert_eq!(summary.total, 700000);
+        assert_eq!(summary.method_fees, 330 + 8000);
+        assert_eq!(
+            summary.by_method,
+            vec![
+                MethodSummary {
+                    method: PaymentMethod::CreditCard,
+                    total: 500000,
+                    paid: 0,
+                    fees: 8000,
+                },
+                MethodSummary {
+                    method: PaymentMethod::ConvenienceStore,
+                    total: 200000,
+                    paid: 200000,
+                    fees: 330,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_matches_summary() {
+        let mut dataset = dataset();
+        dataset.schools[0].payment_method = Some(PaymentMethod::CreditCard);
+        dataset.method_fees = vec![MethodFee {
+            method: PaymentMethod::CreditCard,
+            fixed: 0,
+            rate_bp: 100,
+            cap: None,
+        }];
+        let rates = ExchangeRates::default();
+        let range = SummaryRange::default();
+        let summary = summarize(&dataset, range, &rates);
+        let value = |figure| explain(&dataset, range, &rates, figure).value;
+
+        assert_eq!(value(SummaryFigure::Total), summary.total);
+        assert_eq!(value(SummaryFigure::Paid), summary.paid);
+        assert_eq!(value(SummaryFigure::Refundable), summary.refundable);
+        assert_eq!(value(SummaryFigure::NonRefundable), summary.non_refundable);
+        assert_eq!(value(SummaryFigure::MethodFees), summary.method_fees);
+        for month in &summary.by_month {
+            assert_eq!(
+                value(SummaryFigure::Month { month: month.month }),
+                month.total
+            );
+        }
+        for school in &summary.by_school {
+            let figure = SummaryFigure::School {
+                school_id: school.school_id,
+            };
+            assert_eq!(value(figure), school.total);
+        }
+        for category in &summary.by_category {
+            let figure = SummaryFigure::Category {
+                category: category.category,
+            };
+            assert_eq!(value(figure), category.total);
+        }
+
+        // Each item links back to its school, in date order
+        let explanation = explain(
+            &dataset,
+            range,
+            &rates,
+            SummaryFigure::Category {
+                category: PaymentCategory::ApplicationFee,
+            },
+        );
+        let schools: Vec<_> = explanation.items.iter().map(|i| i.school_id).collect();
+        assert_eq!(schools, vec![1, 2]);
+        assert_eq!(explanation.unconverted_school_ids, vec![3]);
+        assert_eq!(
+            serde_json::to_value(SummaryFigure::School { school_id: 1 }).unwrap(),
+            serde_json::json!({"figure": "school", "schoolId": 1})
+        );
+    }
+}
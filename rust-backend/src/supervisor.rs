@@ -0,0 +1,224 @@
+//! Advisor availability tracking and watchdog.
+//!
+//! The current [`AdvisorStatus`] is published on a watch channel held by
+//! `AppState`. It is updated as requests succeed or fail and by
+//! [`run_watchdog`], which pings the advisor periodically so failures are
+//! noticed before the user's next request runs into a timeout. Deployment
+//! scripts can instead [`wait_ready`] for the advisor to answer.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::handlers::{self, AppState};
+use crate::health_history::{HealthEvent, HealthEventKind};
+use crate::json_rpc::JsonRpcResponse;
+use crate::lean_repl::LeanReplError;
+use crate::startup::StartupMode;
+
+/// How often the watchdog pings the advisor
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Ping round trips slower than this mark the advisor as degraded
+pub const SLOW_PING: Duration = Duration::from_secs(5);
+
+/// Longest a health check waits for the advisor
+pub const MAX_WAIT_READY: Duration = Duration::from_secs(300);
+
+/// Pause between pings while waiting for the advisor
+const READY_POLL: Duration = Duration::from_millis(500);
+
+/// Coarse advisor availability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum AdvisorState {
+    Running,
+    Starting,
+    Degraded,
+    Stopped,
+}
+
+/// Advisor availability with the reason for anything other than running
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdvisorStatus {
+    pub state: AdvisorState,
+    pub reason: Option<String>,
+}
+
+impl AdvisorStatus {
+    pub fn new(state: AdvisorState, reason: impl Into<String>) -> Self {
+        Self {
+            state,
+            reason: Some(reason.into()),
+        }
+    }
+
+    pub fn running() -> Self {
+        Self {
+            state: AdvisorState::Running,
+            reason: None,
+        }
+    }
+
+    /// Status implied by a failed request
+    pub fn from_error(error: &LeanReplError) -> Self {
+        let state = match error {
+            LeanReplError::StartFailed(_)
+            | LeanReplError::NotRunning
+            | LeanReplError::ReceiveFailed(_) => AdvisorState::Stopped,
+            LeanReplError::Timeout
+            | LeanReplError::SendFailed(_)
+            | LeanReplError::InvalidJson(_)
+            | LeanReplError::Io(_) => AdvisorState::Degraded,
+        };
+        Self::new(state, error.to_string())
+    }
+}
+
+/// Classify the outcome of a watchdog ping
+pub fn classify_ping(
+    result: &Result<JsonRpcResponse, LeanReplError>,
+    elapsed: Duration,
+) -> AdvisorStatus {
+    match result {
+        Err(e) => AdvisorStatus::from_error(e),
+        Ok(response) => match &response.error {
+            Some(error) => AdvisorStatus::new(AdvisorState::Degraded, error.message.clone()),
+            None if elapsed > SLOW_PING => AdvisorStatus::new(
+                AdvisorState::Degraded,
+                format!("Advisor responded slowly ({} ms)", elapsed.as_millis()),
+            ),
+            None => AdvisorStatus::running(),
+        },
+    }
+}
+
+/// Ping the advisor forever, restarting it when it has stopped unless the
+/// advisor is started lazily
+pub async fn run_watchdog(state: Arc<AppState>, interval: Duration) {
+    loop {
+        let running = state.lean_repl.lock().await.is_running();
+        let lazy = state.settings.lock().await.advisor.startup == StartupMode::Lazy;
+        if !running && lazy {
+            // Left for the next request to start
+            tokio::time::sleep(interval).await;
+            continue;
+        }
+        if !running {
+            state.record_health(HealthEvent::new(
+                HealthEventKind::Restarted,
+                None,
+                Some("Advisor was not running".to_string()),
+            ));
+            state.set_advisor_status(AdvisorStatus::new(
+                AdvisorState::Starting,
+                "Starting advisor",
+            ));
+        }
+
+        let started = Instant::now();
+        let result = handlers::ping(state.clone()).await;
+        state.set_advisor_status(classify_ping(&result, started.elapsed()));
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Parse how long to wait: `500ms`, `30s`, `2m` or plain seconds
+pub fn parse_wait(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => value.split_at(at),
+        None => (value, "s"),
+    };
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+/// Ping the advisor, starting it if needed, until it answers or `timeout`
+/// (at most [`MAX_WAIT_READY`]) passes. Returns whether it answered.
+pub async fn wait_ready(state: Arc<AppState>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout.min(MAX_WAIT_READY);
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let ping = tokio::time::timeout(remaining, handlers::ping(state.clone())).await;
+        if let Ok(Ok(response)) = ping {
+            if response.error.is_none() {
+                return true;
+            }
+        }
+        if Instant::now() + READY_POLL >= deadline {
+            return false;
+        }
+        tokio::time::sleep(READY_POLL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pong() -> JsonRpcResponse {
+        JsonRpcResponse::success(serde_json::json!(1), serde_json::json!("pong"))
+    }
+
+    #[test]
+    fn test_classify_ping() {
+        let fast = Duration::from_millis(10);
+        assert_eq!(classify_ping(&Ok(pong()), fast), AdvisorStatus::running());
+        assert_eq!(
+            classify_ping(&Ok(pong()), SLOW_PING * 2).state,
+            AdvisorState::Degraded
+        );
+        assert_eq!(
+            classify_ping(&Err(LeanReplError::Timeout), fast).state,
+            AdvisorState::Degraded
+        );
+        assert_eq!(
+            classify_ping(&Err(LeanReplError::StartFailed("missing".into())), fast).state,
+            AdvisorState::Stopped
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let status = AdvisorStatus::new(AdvisorState::Stopped, "exited");
+        assert_eq!(
+            serde_json::to_value(status).unwrap(),
+            serde_json::json!({"state": "stopped", "reason": "exited"})
+        );
+    }
+
+    #[test]
+    fn test_parse_wait() {
+        assert_eq!(parse_wait("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_wait("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_wait("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_wait("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_wait("soon"), None);
+        assert_eq!(parse_wait("1h"), None);
+    }
+
+    #[tokio::test]
+    async fn test_wait_ready() {
+        use crate::lean_repl::LeanRepl;
+        use std::path::PathBuf;
+
+        let demo = Arc::new(AppState::new(LeanRepl::demo()));
+        assert!(wait_ready(demo, Duration::from_secs(5)).await);
+
+        let missing = LeanRepl::new(PathBuf::from("/nonexistent/advisor"));
+        let state = Arc::new(AppState::new(missing));
+        let started = Instant::now();
+        assert!(!wait_ready(state, Duration::from_millis(100)).await);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
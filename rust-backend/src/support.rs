@@ -0,0 +1,294 @@
+//! Support bundles for troubleshooting.
+//!
+//! A bundle is a zip archive holding a diagnostics report, the user's data
+//! with names and free text removed, and the recent log files, so a user
+//! can send everything needed to investigate a problem in one file.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::cloud_backup::CloudBackupStatus;
+use crate::crash::CrashReport;
+use crate::handlers::HealthResponse;
+use crate::health_history::HealthEvent;
+use crate::logs;
+use crate::settings::Settings;
+use crate::supervisor::AdvisorStatus;
+
+/// Suggested file name for a saved bundle
+pub const SUPPORT_BUNDLE_NAME: &str = "school-payment-support.zip";
+
+/// School fields kept as they are when anonymizing
+const KEPT_STRING_FIELDS: [&str; 1] = ["passStatus"];
+
+/// Errors that can occur while building a bundle
+#[derive(Debug, Error)]
+pub enum SupportError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+/// Operating system the app is running on
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub os: String,
+    pub family: String,
+    pub arch: String,
+}
+
+impl SystemInfo {
+    pub fn current() -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            family: std::env::consts::FAMILY.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+}
+
+/// The advisor binary and process
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdvisorInfo {
+    pub path: String,
+    /// SHA-256 of the binary, when it could be read
+    pub sha256: Option<String>,
+    /// Data schema version reported by the advisor, when it responded
+    pub schema_version: Option<u32>,
+    pub status: AdvisorStatus,
+}
+
+/// Diagnostics report included in a bundle as `report.json`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportReport {
+    pub app_version: String,
+    pub created_at: String,
+    pub system: SystemInfo,
+    pub advisor: AdvisorInfo,
+    pub health: HealthResponse,
+    /// Advisor status changes, crashes and restarts
+    pub health_history: Vec<HealthEvent>,
+    pub settings: Settings,
+    pub school_count: usize,
+    /// When the data was last backed up to the sync server
+    pub cloud_backup: CloudBackupStatus,
+}
+
+/// Hex SHA-256 of a file's contents
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Settings with anything identifying or unlocking the user's server removed
+pub fn redact_settings(settings: &Settings) -> Settings {
+    let mut settings = settings.clone();
+    if settings.sync.server_url.is_some() {
+        settings.sync.server_url = Some("<redacted>".to_string());
+    }
+    if settings.cloud_backup.server_token.is_some() {
+        settings.cloud_backup.server_token = Some("<redacted>".to_string());
+    }
+    settings
+}
+
+fn anonymize_school(index: usize, school: &serde_json::Value) -> serde_json::Value {
+    let serde_json::Value::Object(fields) = school else {
+        return serde_json::Value::Null;
+    };
+    let fields = fields
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                _ if key == "name" => format!("学校{}", index + 1).into(),
+                serde_json::Value::String(_) if !KEPT_STRING_FIELDS.contains(&key.as_str()) => {
+                    serde_json::Value::String(String::new())
+                }
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    serde_json::Value::Null
+                }
+                _ => value.clone(),
+            };
+            (key.clone(), value)
+        })
+        .collect();
+    serde_json::Value::Object(fields)
+}
+
+/// A copy of a dataset with school names replaced and free text removed.
+///
+/// Dates, amounts, statuses and top-level numbers such as the schema
+/// version are kept, since problems usually depend on them.
+pub fn anonymize(data: &serde_json::Value) -> serde_json::Value {
+    let serde_json::Value::Object(fields) = data else {
+        return serde_json::Value::Null;
+    };
+    let fields = fields
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::Array(schools) if key == "schools" => schools
+                    .iter()
+                    .enumerate()
+                    .map(|(index, school)| anonymize_school(index, school))
+                    .collect(),
+                serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.clone(),
+                _ => return None,
+            };
+            Some((key.clone(), value))
+        })
+        .collect();
+    serde_json::Value::Object(fields)
+}
+
+/// Package a report, the anonymized dataset, log files and optionally a
+/// crash report into a zip archive
+pub fn build_bundle(
+    report: &SupportReport,
+    data: Option<&serde_json::Value>,
+    log_dir: Option<&Path>,
+    crash: Option<&CrashReport>,
+) -> Result<Vec<u8>, SupportError> {
+    let mut archive = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    archive.start_file("report.json", options)?;
+    archive.write_all(&serde_json::to_vec_pretty(report)?)?;
+
+    if let Some(data) = data {
+        archive.start_file("data.json", options)?;
+        archive.write_all(&serde_json::to_vec_pretty(&anonymize(data))?)?;
+    }
+
+    if let Some(crash) = crash {
+        archive.start_file(format!("crashes/{}", crash.file_name), options)?;
+        archive.write_all(&serde_json::to_vec_pretty(crash)?)?;
+    }
+
+    for path in log_dir.map(logs::log_files).unwrap_or_default() {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        archive.start_file(format!("logs/{}", name), options)?;
+        archive.write_all(&fs::read(&path)?)?;
+    }
+    Ok(archive.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::supervisor::AdvisorState;
+    use tempfile::tempdir;
+
+    fn sample_report() -> SupportReport {
+        SupportReport {
+            app_version: "1.0.0".to_string(),
+            created_at: "2026-03-01T00:00:00+09:00".to_string(),
+            system: SystemInfo::current(),
+            advisor: AdvisorInfo {
+                path: "/opt/advisor".to_string(),
+                sha256: None,
+                schema_version: None,
+                status: AdvisorStatus::new(AdvisorState::Stopped, "not started"),
+            },
+            health: HealthResponse {
+                status: "ok".to_string(),
+                lean_repl: "stopped".to_string(),
+                ready: None,
+            },
+            health_history: Vec::new(),
+            settings: Settings::default(),
+            school_count: 1,
+            cloud_backup: CloudBackupStatus::default(),
+        }
+    }
+
+    #[test]
+    fn test_anonymize() {
+        let data = serde_json::json!({
+            "schemaVersion": 2,
+            "owner": "山田",
+            "schools": [{
+                "id": 7,
+                "name": "開成中学校",
+                "notes": "説明会で聞いた話",
+                "examDate": 20260201,
+                "tuition": 500000,
+                "passStatus": "passed",
+                "tuitionPaid": true
+            }]
+        });
+        let anonymized = anonymize(&data);
+
+        assert_eq!(anonymized["schemaVersion"], 2);
+        assert!(anonymized.get("owner").is_none());
+        let school = &anonymized["schools"][0];
+        assert_eq!(school["name"], "学校1");
+        assert_eq!(school["notes"], "");
+        assert_eq!(school["examDate"], 20260201);
+        assert_eq!(school["passStatus"], "passed");
+        assert_eq!(school["tuitionPaid"], true);
+        assert!(!anonymized.to_string().contains("開成"));
+    }
+
+    #[test]
+    fn test_redact_settings() {
+        let mut settings = Settings::default();
+        assert_eq!(redact_settings(&settings), settings);
+        settings.sync.server_url = Some("https://home.example".to_string());
+        settings.cloud_backup.server_token = Some("secret".to_string());
+        let redacted = redact_settings(&settings);
+        assert_eq!(redacted.sync.server_url.as_deref(), Some("<redacted>"));
+        assert_eq!(
+            redacted.cloud_backup.server_token.as_deref(),
+            Some("<redacted>")
+        );
+    }
+
+    #[test]
+    fn test_build_bundle() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(logs::LOG_FILE), "log line\n").unwrap();
+        let data = serde_json::json!({ "schools": [{ "id": 1, "name": "A" }] });
+
+        let bytes = build_bundle(&sample_report(), Some(&data), Some(dir.path()), None).unwrap();
+        let mut archive = zip::ZipArchive::new(io::Cursor::new(bytes)).unwrap();
+        let mut names: Vec<_> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, vec!["data.json", "logs/app.log", "report.json"]);
+
+        let report: serde_json::Value =
+            serde_json::from_reader(archive.by_name("report.json").unwrap()).unwrap();
+        assert_eq!(report["appVersion"], "1.0.0");
+        assert_eq!(report["advisor"]["path"], "/opt/advisor");
+    }
+
+    #[test]
+    fn test_file_sha256() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("advisor");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            file_sha256(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Year-end report of the school payments made in a calendar year.
+//!
+//! Filing for tax purposes and for education-support programs, such as the
+//! tax-free lump-sum gift of education funds, asks for what was paid to
+//! schools in the year, by kind of fee. [`tax_report`] collects the
+//! enrollment fees and tuition payments marked paid, dated by their receipt
+//! or, when none was recorded, by their deadline. Application fees are not
+//! tracked as paid, so they are left out.
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::currency::ExchangeRates;
+use crate::model::{Currency, Dataset, FeeKind};
+use crate::payments::{self, PaymentItem};
+
+/// One payment made in the year
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TaxReportLine {
+    /// Day the payment was made, or its deadline without a receipt
+    pub paid_on: u32,
+    /// Name of the child the school is for, if tagged
+    pub child_name: Option<String>,
+    pub payment: PaymentItem,
+    /// Amount in yen, `None` if the currency has no exchange rate
+    #[ts(type = "number | null")]
+    pub amount_yen: Option<u64>,
+}
+
+/// Total paid in the year for one kind of fee, in yen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct FeeTotal {
+    pub fee: FeeKind,
+    pub count: u32,
+    #[ts(type = "number")]
+    pub total: u64,
+}
+
+/// Payments made in a calendar year
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TaxReport {
+    pub year: u32,
+    /// Payments in date order
+    pub lines: Vec<TaxReportLine>,
+    /// Totals for the kinds of fee paid, enrollment fees first
+    pub by_fee: Vec<FeeTotal>,
+    #[ts(type = "number")]
+    pub total: u64,
+    /// Payments dated by their deadline because no receipt was recorded
+    pub without_receipt: u32,
+    /// Schools left out of the totals because their currency has no
+    /// exchange rate
+    #[ts(type = "Array<number>")]
+    pub unconverted_school_ids: Vec<u64>,
+}
+
+/// Payments of `dataset` made in `year`, with totals in yen
+pub fn tax_report(dataset: &Dataset, year: u32, rates: &ExchangeRates) -> TaxReport {
+    let mut report = TaxReport {
+        year,
+        ..TaxReport::default()
+    };
+    for school in &dataset.schools {
+        let child_name = school
+            .child_id
+            .and_then(|id| dataset.child_name(id))
+            .map(str::to_string);
+        for (payment, paid) in payments::items(school) {
+            let paid_on = payment.receipt.as_ref().map_or(payment.due, |r| r.paid_on);
+            if !paid || paid_on / 10000 != year {
+                continue;
+            }
+            if payment.receipt.is_none() {
+                report.without_receipt += 1;
+            }
+            let amount_yen = rates.convert(payment.amount, payment.currency, Currency::Jpy);
+            match amount_yen {
+                Some(yen) => {
+                    report.total += yen;
+                    match report.by_fee.iter_mut().find(|t| t.fee == payment.fee) {
+                        Some(total) => {
+                            total.count += 1;
+                            total.total += yen;
+                        }
+                        None => report.by_fee.push(FeeTotal {
+                            fee: payment.fee,
+                            count: 1,
+                            total: yen,
+                        }),
+                    }
+                }
+                None if !report.unconverted_school_ids.contains(&school.id) => {
+                    report.unconverted_school_ids.push(school.id)
+                }
+                None => {}
+            }
+            report.lines.push(TaxReportLine {
+                paid_on,
+                child_name: child_name.clone(),
+                payment,
+                amount_yen,
+            });
+        }
+    }
+    report
+        .lines
+        .sort_by_key(|l| (l.paid_on, l.payment.school_id));
+    report.by_fee.sort_by_key(|t| t.fee == FeeKind::Tuition);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_dataset;
+    use crate::model::{Child, PaymentMethod};
+    use crate::payments::{mark_paid, PaymentRecord};
+
+    #[test]
+    fn test_tax_report() {
+        let mut dataset = sample_dataset();
+        dataset.children = vec![Child {
+            id: 1,
+            name: "太郎".to_string(),
+        }];
+        dataset.schools[0].child_id = Some(1);
+        mark_paid(
+            &mut dataset,
+            PaymentRecord {
+                school_id: 2,
+                fee: FeeKind::Tuition,
+                installment: None,
+                paid_on: 20260318,
+                method: PaymentMethod::BankTransfer,
+                reference: None,
+                attachment: None,
+            },
+        )
+        .unwrap();
+
+        let report = tax_report(&dataset, 2026, &ExchangeRates::default());
+        assert_eq!(report.total, 200000 + 800000);
+        assert_eq!(report.lines.len(), 2);
+        // Waseda's enrollment fee has no receipt, so it is dated by its deadline
+        assert_eq!(report.lines[0].paid_on, 20260306);
+        assert_eq!(report.lines[1].paid_on, 20260318);
+        assert_eq!(report.lines[1].child_name.as_deref(), Some("太郎"));
+        assert_eq!(report.without_receipt, 1);
+        assert_eq!(report.by_fee[0].fee, FeeKind::EnrollmentFee);
+        assert_eq!(report.by_fee[1].total, 800000);
+
+        assert!(tax_report(&dataset, 2025, &ExchangeRates::default())
+            .lines
+            .is_empty());
+    }
+}
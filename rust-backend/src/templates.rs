@@ -0,0 +1,256 @@
+//! School templates: typical fee structures and deadlines by kind of school.
+//!
+//! A [`SchoolTemplate`] gives the fees of a kind of school and its result
+//! and payment deadlines as days after the exam, so entering a school comes
+//! down to picking a template and its exam date. A catalog of common
+//! templates is bundled with the app; users can import their own catalogs
+//! in the same JSON format, which are saved to [`TEMPLATES_FILE`] and
+//! replace bundled templates with the same id.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::date::{parse_day, to_day};
+use crate::model::{Currency, Dataset, PassStatus, School};
+use crate::storage::{Storage, StorageError};
+
+/// File in the data directory holding imported templates
+pub const TEMPLATES_FILE: &str = "templates.json";
+
+/// Catalog bundled with the app
+const BUNDLED_CATALOG: &str = include_str!("../templates/schools.json");
+
+/// Errors that can occur with templates
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error("Template not found: {0}")]
+    UnknownTemplate(String),
+
+    #[error("Invalid exam date: {0}")]
+    InvalidExamDate(u32),
+
+    #[error("Invalid template catalog: {0}")]
+    InvalidCatalog(String),
+}
+
+/// Fees and deadlines of a kind of school
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SchoolTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Days from the exam to the announcement of results
+    pub result_days: u32,
+    /// Days from the exam to the enrollment fee deadline
+    pub enrollment_fee_deadline_days: u32,
+    /// Days from the exam to the tuition deadline
+    pub tuition_deadline_days: u32,
+    #[ts(type = "number")]
+    pub enrollment_fee: u64,
+    #[ts(type = "number")]
+    pub tuition: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub application_fee: Option<u64>,
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+impl SchoolTemplate {
+    /// Describe the first problem with the template, if any
+    fn invalid_reason(&self) -> Option<String> {
+        if self.id.trim().is_empty() || self.name.trim().is_empty() {
+            return Some("id and name are required".to_string());
+        }
+        if self.enrollment_fee_deadline_days < self.result_days
+            || self.tuition_deadline_days < self.result_days
+        {
+            return Some(format!(
+                "{}: payment deadlines must not come before the results",
+                self.id
+            ));
+        }
+        None
+    }
+}
+
+/// Parse and check a catalog: a JSON array of templates
+pub fn parse_catalog(text: &str) -> Result<Vec<SchoolTemplate>, TemplateError> {
+    let templates: Vec<SchoolTemplate> = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|e| TemplateError::InvalidCatalog(e.to_string()))?;
+    if let Some(reason) = templates.iter().find_map(SchoolTemplate::invalid_reason) {
+        return Err(TemplateError::InvalidCatalog(reason));
+    }
+    Ok(templates)
+}
+
+/// Templates bundled with the app
+pub fn bundled() -> Vec<SchoolTemplate> {
+    parse_catalog(BUNDLED_CATALOG).expect("bundled catalog is valid")
+}
+
+/// Imported templates, or none
+fn load_imported(storage: &Storage) -> Result<Vec<SchoolTemplate>, StorageError> {
+    match storage.load(TEMPLATES_FILE)? {
+        Some(value) => Ok(serde_json::from_value(value)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Bundled templates followed by imported ones, which replace bundled
+/// templates of the same id
+pub fn catalog(storage: Option<&Storage>) -> Result<Vec<SchoolTemplate>, StorageError> {
+    let mut templates = bundled();
+    let imported = match storage {
+        Some(storage) => load_imported(storage)?,
+        None => Vec::new(),
+    };
+    for template in imported {
+        match templates.iter_mut().find(|t| t.id == template.id) {
+            Some(existing) => *existing = template,
+            None => templates.push(template),
+        }
+    }
+    Ok(templates)
+}
+
+/// Add the templates in a catalog file to the imported ones, replacing
+/// those with the same id. Returns the templates read.
+pub fn import_catalog(storage: &Storage, text: &str) -> Result<Vec<SchoolTemplate>, TemplateError> {
+    let added = parse_catalog(text)?;
+    let mut imported = load_imported(storage)?;
+    imported.retain(|t| !added.iter().any(|a| a.id == t.id));
+    imported.extend(added.iter().cloned());
+    storage.save(
+        TEMPLATES_FILE,
+        &serde_json::to_value(&imported).map_err(StorageError::from)?,
+    )?;
+    Ok(added)
+}
+
+fn after(exam_date: u32, days: u32) -> Option<u32> {
+    parse_day(exam_date)?
+        .checked_add_days(chrono::Days::new(u64::from(days)))
+        .map(to_day)
+}
+
+/// A new school from `template` with its exam on `exam_date`.
+///
+/// The school gets the next free id and the lowest priority in `dataset`,
+/// and is named `name` if given, otherwise after the template.
+pub fn apply(
+    template: &SchoolTemplate,
+    exam_date: u32,
+    name: Option<&str>,
+    dataset: &Dataset,
+) -> Result<School, TemplateError> {
+    let day = |days| after(exam_date, days).ok_or(TemplateError::InvalidExamDate(exam_date));
+    let name = name
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .unwrap_or(&template.name);
+    Ok(School {
+        id: dataset.schools.iter().map(|s| s.id).max().unwrap_or(0) + 1,
+        name: name.to_string(),
+        priority: dataset
+            .schools
+            .iter()
+            .map(|s| s.priority)
+            .max()
+            .unwrap_or(0)
+            + 1,
+        exam_date: day(0)?,
+        result_date: day(template.result_days)?,
+        enrollment_fee_deadline: day(template.enrollment_fee_deadline_days)?,
+        tuition_deadline: day(template.tuition_deadline_days)?,
+        enrollment_fee: template.enrollment_fee,
+        tuition: template.tuition,
+        application_fee: template.application_fee,
+        currency: template.currency,
+        pass_status: PassStatus::NotYetAnnounced,
+        enrollment_fee_paid: false,
+        tuition_paid: false,
+        payee: None,
+        installments: Vec::new(),
+        adjustments: Vec::new(),
+        child_id: None,
+        receipts: Vec::new(),
+        recurring_fees: Vec::new(),
+        recurring_payments: Vec::new(),
+        waitlisted: false,
+        payment_method: None,
+        notes: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_catalog() {
+        let templates = bundled();
+        assert!(templates.len() >= 4);
+        assert!(templates.iter().any(|t| t.id == "privateUniversity"));
+    }
+
+    #[test]
+    fn test_apply() {
+        let template = bundled()
+            .into_iter()
+            .find(|t| t.id == "publicUniversity")
+            .unwrap();
+        let dataset = crate::export::sample_dataset();
+        let school = apply(&template, 20260225, Some("  北海道大学 "), &dataset).unwrap();
+
+        assert_eq!(school.id, 3);
+        assert_eq!(school.priority, 3);
+        assert_eq!(school.name, "北海道大学");
+        assert_eq!(school.result_date, 20260308);
+        assert_eq!(school.enrollment_fee_deadline, 20260315);
+        assert_eq!(school.application_fee, Some(17000));
+
+        assert!(matches!(
+            apply(&template, 20260230, None, &dataset),
+            Err(TemplateError::InvalidExamDate(20260230))
+        ));
+    }
+
+    #[test]
+    fn test_catalog_import_replaces_by_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let text = r#"[
+            {"id": "privateUniversity", "name": "私立大学（改）", "resultDays": 7,
+             "enrollmentFeeDeadlineDays": 14, "tuitionDeadlineDays": 30,
+             "enrollmentFee": 300000, "tuition": 900000},
+            {"id": "musicCollege", "name": "音楽大学", "resultDays": 14,
+             "enrollmentFeeDeadlineDays": 21, "tuitionDeadlineDays": 40,
+             "enrollmentFee": 300000, "tuition": 1500000}
+        ]"#;
+        assert_eq!(import_catalog(&storage, text).unwrap().len(), 2);
+
+        let templates = catalog(Some(&storage)).unwrap();
+        assert_eq!(templates.len(), bundled().len() + 1);
+        let private = templates
+            .iter()
+            .find(|t| t.id == "privateUniversity")
+            .unwrap();
+        assert_eq!(private.enrollment_fee, 300000);
+
+        let invalid = r#"[{"id": "x", "name": "x", "resultDays": 10,
+            "enrollmentFeeDeadlineDays": 5, "tuitionDeadlineDays": 30,
+            "enrollmentFee": 1, "tuition": 1}]"#;
+        assert!(matches!(
+            import_catalog(&storage, invalid),
+            Err(TemplateError::InvalidCatalog(_))
+        ));
+    }
+}
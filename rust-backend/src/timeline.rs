@@ -0,0 +1,245 @@
+//! Timeline of each school's exam, results and payment deadlines.
+//!
+//! [`timeline`] lays the schools out as lanes for a calendar or Gantt view:
+//! bars for the wait from exam to results and the windows from results to
+//! each payment deadline, and markers for the days themselves. Deadlines on
+//! days banks are closed carry the last business day before them, as in
+//! [`analysis`](crate::analysis), so the frontend does no date math of its
+//! own.
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::calendar::{bank_holiday, previous_business_day};
+use crate::model::{Dataset, PassStatus, School};
+
+/// What a bar spans
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum BarKind {
+    /// From the exam to the announcement of results
+    AwaitingResult,
+    /// From the results to the enrollment fee deadline
+    EnrollmentFeeWindow,
+    /// From the results to the first tuition payment
+    TuitionWindow,
+}
+
+/// A span of days in a lane, both ends included
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TimelineBar {
+    pub kind: BarKind,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// What a marker marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum MarkerKind {
+    Exam,
+    Result,
+    EnrollmentFeeDeadline,
+    TuitionDeadline,
+}
+
+/// A single day in a lane
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TimelineMarker {
+    pub kind: MarkerKind,
+    pub day: u32,
+    /// 1-based installment number, for tuition paid in parts
+    pub installment: Option<u32>,
+    /// Whether the payment was made, for deadlines
+    pub paid: Option<bool>,
+    /// Last day banks are open, for deadlines on days they are closed
+    pub pay_by: Option<u32>,
+}
+
+/// Bars and markers of one school
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TimelineLane {
+    #[ts(type = "number")]
+    pub school_id: u64,
+    pub school_name: String,
+    #[ts(type = "number | null")]
+    pub child_id: Option<u64>,
+    pub priority: u32,
+    pub pass_status: PassStatus,
+    pub bars: Vec<TimelineBar>,
+    /// Markers in date order
+    pub markers: Vec<TimelineMarker>,
+}
+
+/// Lanes of all schools in priority order, with the days they cover
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Timeline {
+    /// Earliest day in any lane, `None` without schools
+    pub start: Option<u32>,
+    /// Latest day in any lane
+    pub end: Option<u32>,
+    pub lanes: Vec<TimelineLane>,
+}
+
+fn deadline(kind: MarkerKind, day: u32, installment: Option<u32>, paid: bool) -> TimelineMarker {
+    TimelineMarker {
+        kind,
+        day,
+        installment,
+        paid: Some(paid),
+        pay_by: bank_holiday(day).map(|_| previous_business_day(day)),
+    }
+}
+
+fn lane(school: &School) -> TimelineLane {
+    let day = |kind, day| TimelineMarker {
+        kind,
+        day,
+        installment: None,
+        paid: None,
+        pay_by: None,
+    };
+    let mut markers = vec![
+        day(MarkerKind::Exam, school.exam_date),
+        day(MarkerKind::Result, school.result_date),
+        deadline(
+            MarkerKind::EnrollmentFeeDeadline,
+            school.enrollment_fee_deadline,
+            None,
+            school.enrollment_fee_paid,
+        ),
+    ];
+    let payments = school.tuition_payments();
+    let in_parts = !school.installments.is_empty();
+    for (i, payment) in payments.iter().enumerate() {
+        let installment = in_parts.then_some(i as u32 + 1);
+        markers.push(deadline(
+            MarkerKind::TuitionDeadline,
+            payment.due,
+            installment,
+            payment.paid,
+        ));
+    }
+    markers.sort_by_key(|m| m.day);
+
+    // Bars whose dates are out of order are left out; analysis reports them
+    let first_tuition = payments.first().map_or(school.tuition_deadline, |p| p.due);
+    let bars = [
+        (
+            BarKind::AwaitingResult,
+            school.exam_date,
+            school.result_date,
+        ),
+        (
+            BarKind::EnrollmentFeeWindow,
+            school.result_date,
+            school.enrollment_fee_deadline,
+        ),
+        (BarKind::TuitionWindow, school.result_date, first_tuition),
+    ]
+    .into_iter()
+    .filter(|(_, start, end)| start <= end)
+    .map(|(kind, start, end)| TimelineBar { kind, start, end })
+    .collect();
+
+    TimelineLane {
+        school_id: school.id,
+        school_name: school.name.clone(),
+        child_id: school.child_id,
+        priority: school.priority,
+        pass_status: school.pass_status,
+        bars,
+        markers,
+    }
+}
+
+/// Timeline of every school in `dataset`
+pub fn timeline(dataset: &Dataset) -> Timeline {
+    let mut schools: Vec<&School> = dataset.schools.iter().collect();
+    schools.sort_by_key(|s| (s.priority, s.id));
+    let lanes: Vec<TimelineLane> = schools.into_iter().map(lane).collect();
+    let days = || lanes.iter().flat_map(|l| l.markers.iter().map(|m| m.day));
+    Timeline {
+        start: days().min(),
+        end: days().max(),
+        lanes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sample_dataset;
+    use crate::model::InstallmentPlan;
+
+    #[test]
+    fn test_sample_timeline() {
+        let timeline = timeline(&sample_dataset());
+        assert_eq!(timeline.start, Some(20260215));
+        assert_eq!(timeline.end, Some(20260331));
+
+        // Tokyo comes first by priority
+        let tokyo = &timeline.lanes[0];
+        assert_eq!(tokyo.school_id, 1);
+        assert_eq!(
+            tokyo.bars[0],
+            TimelineBar {
+                kind: BarKind::AwaitingResult,
+                start: 20260225,
+                end: 20260310,
+            }
+        );
+
+        let waseda = &timeline.lanes[1];
+        let tuition = waseda
+            .markers
+            .iter()
+            .find(|m| m.kind == MarkerKind::TuitionDeadline)
+            .unwrap();
+        // Due on 3/20, a holiday
+        assert_eq!(tuition.pay_by, Some(20260319));
+        assert_eq!(tuition.paid, Some(false));
+        assert_eq!(waseda.markers[2].paid, Some(true));
+    }
+
+    #[test]
+    fn test_installments_and_bad_dates() {
+        let mut dataset = sample_dataset();
+        dataset.schools[0].installments = InstallmentPlan {
+            total: 800000,
+            first_due: 20260325,
+            count: 2,
+            interval_months: 6,
+        }
+        .schedule()
+        .unwrap();
+        dataset.schools[1].result_date = 20260220;
+
+        let timeline = timeline(&dataset);
+        let waseda = &timeline.lanes[1];
+        let installments: Vec<_> = waseda
+            .markers
+            .iter()
+            .filter_map(|m| m.installment)
+            .collect();
+        assert_eq!(installments, vec![1, 2]);
+        assert_eq!(waseda.bars[2].end, 20260325);
+        assert_eq!(timeline.end, Some(20260925));
+
+        // Tokyo's results come before its exam, so there is no waiting bar
+        assert!(!timeline.lanes[0]
+            .bars
+            .iter()
+            .any(|b| b.kind == BarKind::AwaitingResult));
+    }
+}
@@ -0,0 +1,438 @@
+//! Moving data between devices over the local network.
+//!
+//! The sending device encrypts its dataset with a fresh one-time key and
+//! serves it once from a short-lived HTTP endpoint. It shows a QR code of
+//! [`TransferOffer::code`], a URL whose fragment carries the key, so the
+//! key never reaches the network in the clear. The receiving device fetches
+//! the URL, decrypts the bundle with the key, and imports it as usual.
+//!
+//! The bundle is sealed with ChaCha20-Poly1305 under a random nonce; see
+//! [`seal`]. Field encryption and cloud backups seal with the same
+//! function. Data sealed by earlier releases, with HMAC-SHA256 in counter
+//! mode and a separate HMAC tag, can still be opened but is no longer
+//! written.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use ring::aead::{self, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305};
+use serde::Serialize;
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::token::random_token;
+
+/// How long an offer stays open
+pub const TRANSFER_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Path prefix of the transfer endpoint
+const TRANSFER_PATH: &str = "/transfer/";
+
+/// Fragment parameter carrying the key in a transfer code
+const KEY_PARAM: &str = "#key=";
+
+/// Length of the keys [`seal`] takes
+pub const KEY_LEN: usize = 32;
+
+/// First byte of data sealed with ChaCha20-Poly1305
+const SEAL_VERSION: u8 = 2;
+
+const NONCE_LEN: usize = aead::NONCE_LEN;
+
+/// Nonce and tag lengths of the earlier HMAC construction
+const LEGACY_NONCE_LEN: usize = 16;
+const LEGACY_TAG_LEN: usize = 32;
+
+/// Longest wait for a client to send its request or take the bundle
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest request head accepted by the endpoint
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Errors that can occur while sending or receiving a transfer
+#[derive(Debug, Error)]
+pub enum TransferError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Invalid transfer code")]
+    InvalidCode,
+
+    #[error("Transfer data is damaged or the key does not match")]
+    Corrupt,
+}
+
+/// What the sending device shows to the receiving one
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferOffer {
+    /// Content of the QR code: the bundle URL with the key in its fragment
+    pub code: String,
+    /// Seconds until the endpoint closes
+    pub expires_in_secs: u64,
+}
+
+fn hmac(key: &[u8], parts: &[&[u8]]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+/// XOR `data` with the keystream for `nonce`
+fn apply_keystream(enc_key: &[u8], nonce: &[u8], data: &mut [u8]) {
+    for (counter, block) in data.chunks_mut(32).enumerate() {
+        let keystream = hmac(enc_key, &[nonce, &(counter as u64).to_be_bytes()])
+            .finalize()
+            .into_bytes();
+        for (byte, k) in block.iter_mut().zip(keystream) {
+            *byte ^= k;
+        }
+    }
+}
+
+/// Encryption and MAC keys of the legacy construction
+fn derive_keys(key: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let enc = hmac(key, &[b"school-payment transfer enc"])
+        .finalize()
+        .into_bytes();
+    let mac = hmac(key, &[b"school-payment transfer mac"])
+        .finalize()
+        .into_bytes();
+    (enc.into(), mac.into())
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, key).expect("keys are 32 bytes"))
+}
+
+/// Encrypt and authenticate `plaintext` with ChaCha20-Poly1305 as
+/// `version || nonce || ciphertext || tag`
+pub fn seal(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Vec<u8> {
+    let nonce = hex::decode(random_token(NONCE_LEN)).expect("random_token returns hex");
+    let nonce: [u8; NONCE_LEN] = nonce
+        .try_into()
+        .expect("random_token returns NONCE_LEN bytes");
+
+    let mut in_out = plaintext.to_vec();
+    aead_key(key)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            aead::Aad::empty(),
+            &mut in_out,
+        )
+        .expect("plaintext fits in one message");
+    let mut sealed = Vec::with_capacity(1 + NONCE_LEN + in_out.len());
+    sealed.push(SEAL_VERSION);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&in_out);
+    sealed
+}
+
+/// Verify and decrypt the output of [`seal`], or data sealed by earlier
+/// releases
+pub fn open(key: &[u8; KEY_LEN], sealed: &[u8]) -> Result<Vec<u8>, TransferError> {
+    // A legacy nonce starts with the version byte once in 256 times, so a
+    // failure falls back to the legacy format
+    if let Some(plaintext) = open_aead(key, sealed) {
+        return Ok(plaintext);
+    }
+    open_legacy(key, sealed)
+}
+
+fn open_aead(key: &[u8; KEY_LEN], sealed: &[u8]) -> Option<Vec<u8>> {
+    let (&version, rest) = sealed.split_first()?;
+    if version != SEAL_VERSION || rest.len() < NONCE_LEN + CHACHA20_POLY1305.tag_len() {
+        return None;
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = aead_key(key)
+        .open_in_place(nonce, aead::Aad::empty(), &mut in_out)
+        .ok()?;
+    Some(plaintext.to_vec())
+}
+
+/// Open `nonce || ciphertext || tag` written by the earlier HMAC
+/// construction
+fn open_legacy(key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, TransferError> {
+    if sealed.len() < LEGACY_NONCE_LEN + LEGACY_TAG_LEN {
+        return Err(TransferError::Corrupt);
+    }
+    let (enc_key, mac_key) = derive_keys(key);
+    let (body, tag) = sealed.split_at(sealed.len() - LEGACY_TAG_LEN);
+    hmac(&mac_key, &[body])
+        .verify_slice(tag)
+        .map_err(|_| TransferError::Corrupt)?;
+
+    let (nonce, ciphertext) = body.split_at(LEGACY_NONCE_LEN);
+    let mut plaintext = ciphertext.to_vec();
+    apply_keystream(&enc_key, nonce, &mut plaintext);
+    Ok(plaintext)
+}
+
+/// Address of this machine on the local network, or loopback if unknown.
+///
+/// Connecting a UDP socket sends nothing; it only selects the interface
+/// that would route to the given address.
+pub fn local_ip() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80))?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+/// Split a transfer code into the bundle URL and the key
+pub fn parse_code(code: &str) -> Result<(url::Url, [u8; KEY_LEN]), TransferError> {
+    let (url, key) = code
+        .trim()
+        .split_once(KEY_PARAM)
+        .ok_or(TransferError::InvalidCode)?;
+    let url = url::Url::parse(url).map_err(|_| TransferError::InvalidCode)?;
+    let key = hex::decode(key).map_err(|_| TransferError::InvalidCode)?;
+    let key: [u8; KEY_LEN] = key.try_into().map_err(|_| TransferError::InvalidCode)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(TransferError::InvalidCode);
+    }
+    Ok((url, key))
+}
+
+/// A bundle sealed and bound to a listener, ready to be served
+pub struct PendingTransfer {
+    listener: TcpListener,
+    path: String,
+    sealed: Vec<u8>,
+    pub offer: TransferOffer,
+}
+
+impl PendingTransfer {
+    /// Seal `plaintext` under a new key and listen on `ip`, on any free port
+    pub async fn bind(ip: IpAddr, plaintext: &[u8]) -> Result<Self, TransferError> {
+        let key: [u8; KEY_LEN] = hex::decode(random_token(KEY_LEN))
+            .expect("random_token returns hex")
+            .try_into()
+            .expect("random_token returns KEY_LEN bytes");
+        let listener = TcpListener::bind(SocketAddr::new(ip, 0)).await?;
+        let path = format!("{}{}", TRANSFER_PATH, random_token(16));
+        let code = format!(
+            "http://{}{}{}{}",
+            listener.local_addr()?,
+            path,
+            KEY_PARAM,
+            hex::encode(key)
+        );
+        Ok(Self {
+            listener,
+            path,
+            sealed: seal(&key, plaintext),
+            offer: TransferOffer {
+                code,
+                expires_in_secs: TRANSFER_TTL.as_secs(),
+            },
+        })
+    }
+
+    /// Serve the bundle to the first request for it, or give up after `ttl`.
+    ///
+    /// Returns whether the bundle was sent. Requests for other paths get a
+    /// 404 and do not end the transfer. A client that stalls is dropped
+    /// after [`REQUEST_TIMEOUT`], and never holds the offer past `ttl`.
+    pub async fn serve(self, ttl: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + ttl;
+        loop {
+            let accepted = tokio::time::timeout_at(deadline, self.listener.accept()).await;
+            let Ok(Ok((mut stream, peer))) = accepted else {
+                tracing::info!("Transfer offer expired");
+                return false;
+            };
+            let request_deadline = deadline.min(tokio::time::Instant::now() + REQUEST_TIMEOUT);
+            let read = read_request_path(&mut stream);
+            let path = match tokio::time::timeout_at(request_deadline, read).await {
+                Ok(Ok(path)) => path,
+                Ok(Err(e)) => {
+                    tracing::debug!("Bad transfer request from {}: {}", peer, e);
+                    continue;
+                }
+                Err(_) => {
+                    tracing::debug!("Transfer request from {} timed out", peer);
+                    continue;
+                }
+            };
+
+            let (status, body): (&str, &[u8]) = if path == self.path {
+                ("200 OK", &self.sealed)
+            } else {
+                ("404 Not Found", b"")
+            };
+            let head = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/octet-stream\r\n\
+                 Content-Length: {}\r\nAccess-Control-Allow-Origin: *\r\n\
+                 Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            );
+            let written = tokio::time::timeout_at(request_deadline, async {
+                stream.write_all(head.as_bytes()).await?;
+                stream.write_all(body).await?;
+                stream.shutdown().await
+            })
+            .await
+            .unwrap_or_else(|_| Err(std::io::ErrorKind::TimedOut.into()));
+
+            if path == self.path {
+                match written {
+                    Ok(()) => {
+                        tracing::info!("Sent transfer bundle to {}", peer);
+                        return true;
+                    }
+                    Err(e) => tracing::warn!("Failed to send transfer bundle to {}: {}", peer, e),
+                }
+            }
+        }
+    }
+}
+
+/// Read an HTTP request head and return the requested path
+async fn read_request_path(stream: &mut tokio::net::TcpStream) -> std::io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || head.len() + read > MAX_REQUEST_HEAD {
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(path)) => Ok(path.to_string()),
+        _ => Err(std::io::ErrorKind::InvalidData.into()),
+    }
+}
+
+/// Fetch and decrypt the bundle behind a transfer code
+pub async fn receive(code: &str) -> Result<Vec<u8>, TransferError> {
+    let (url, key) = parse_code(code)?;
+    let sealed = reqwest::Client::new()
+        .get(url)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    open(&key, &sealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let key = [7u8; KEY_LEN];
+        let plaintext = "{\"schools\": [\"東京大学\"]}".repeat(10);
+        let sealed = seal(&key, plaintext.as_bytes());
+
+        assert_eq!(sealed.len(), 1 + NONCE_LEN + plaintext.len() + 16);
+        assert!(!sealed.windows(6).any(|w| w == "東京".as_bytes()));
+        assert_eq!(open(&key, &sealed).unwrap(), plaintext.as_bytes());
+        assert_ne!(seal(&key, plaintext.as_bytes()), sealed);
+    }
+
+    #[test]
+    fn test_open_rejects_tampering() {
+        let key = [7u8; KEY_LEN];
+        let mut sealed = seal(&key, b"data");
+        assert!(matches!(
+            open(&[8u8; KEY_LEN], &sealed),
+            Err(TransferError::Corrupt)
+        ));
+        sealed[1 + NONCE_LEN] ^= 1;
+        assert!(matches!(open(&key, &sealed), Err(TransferError::Corrupt)));
+        assert!(matches!(open(&key, b"short"), Err(TransferError::Corrupt)));
+    }
+
+    /// Seal as releases before ChaCha20-Poly1305 did
+    fn seal_legacy(key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let (enc_key, mac_key) = derive_keys(key);
+        let nonce = [3u8; LEGACY_NONCE_LEN];
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(plaintext);
+        apply_keystream(&enc_key, &nonce, &mut sealed[LEGACY_NONCE_LEN..]);
+        let tag = hmac(&mac_key, &[&sealed]).finalize().into_bytes();
+        sealed.extend_from_slice(&tag);
+        sealed
+    }
+
+    #[test]
+    fn test_open_reads_legacy_data() {
+        let key = [7u8; KEY_LEN];
+        let sealed = seal_legacy(&key, b"{\"memo\": 1}");
+        assert_eq!(open(&key, &sealed).unwrap(), b"{\"memo\": 1}");
+        assert!(matches!(
+            open(&[8u8; KEY_LEN], &sealed),
+            Err(TransferError::Corrupt)
+        ));
+    }
+
+    #[test]
+    fn test_parse_code() {
+        let key = "ab".repeat(KEY_LEN);
+        let code = format!("http://192.168.1.5:5000/transfer/xyz#key={}", key);
+        let (url, parsed) = parse_code(&code).unwrap();
+        assert_eq!(url.path(), "/transfer/xyz");
+        assert_eq!(parsed, [0xab; KEY_LEN]);
+
+        assert!(parse_code("http://192.168.1.5:5000/transfer/xyz").is_err());
+        assert!(parse_code("file:///etc/passwd#key=00").is_err());
+        assert!(parse_code(&format!("ftp://host/x#key={}", key)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_over_loopback() {
+        let pending = PendingTransfer::bind(IpAddr::V4(Ipv4Addr::LOCALHOST), b"payload")
+            .await
+            .unwrap();
+        let code = pending.offer.code.clone();
+        let server = tokio::spawn(pending.serve(Duration::from_secs(10)));
+
+        // A wrong path does not use up the transfer
+        let (url, _) = parse_code(&code).unwrap();
+        let wrong = url.join("/transfer/other").unwrap();
+        let status = reqwest::get(wrong).await.unwrap().status();
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+
+        assert_eq!(receive(&code).await.unwrap(), b"payload");
+        assert!(server.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_client_does_not_hold_the_offer() {
+        let pending = PendingTransfer::bind(IpAddr::V4(Ipv4Addr::LOCALHOST), b"payload")
+            .await
+            .unwrap();
+        let (url, _) = parse_code(&pending.offer.code).unwrap();
+        let addr = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+        let server = tokio::spawn(pending.serve(Duration::from_millis(300)));
+
+        // Connects but never sends a request
+        let _stalled = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let served = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(!served.expect("serve ends at the deadline").unwrap());
+    }
+}
@@ -0,0 +1,603 @@
+//! Web Push notifications for browsers using the web version.
+//!
+//! A browser subscribes with the server's VAPID public key and hands the
+//! resulting [`PushSubscription`] over, labelled with the user it belongs
+//! to; subscriptions are kept in [`PUSH_SUBSCRIPTIONS_FILE`]. Deadline
+//! alerts from the reminder engine are then pushed to every subscription,
+//! so they arrive like the desktop app's notifications even when no tab is
+//! open.
+//!
+//! Messages are encrypted for each subscription as RFC 8291 describes
+//! (`aes128gcm`) and the push service is authorized with a VAPID token
+//! (RFC 8292) signed by the key pair in `[web.push]` of `config.toml`.
+//! Subscriptions the push service reports as gone are dropped.
+
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::{DateTime, FixedOffset};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use ring::{aead, agreement, hkdf};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+use url::Url;
+
+use crate::date;
+use crate::notifier::render_message;
+use crate::reminders::UpcomingDeadline;
+use crate::storage::{Storage, StorageError};
+use crate::webhook::{self, WebhookEvent};
+
+/// File in the data directory holding the push subscriptions
+pub const PUSH_SUBSCRIPTIONS_FILE: &str = "push_subscriptions.json";
+
+/// Record size announced in the encrypted message; messages fit in one
+const RECORD_SIZE: u32 = 4096;
+
+/// Largest message, leaving room for the padding delimiter and the tag
+pub const MAX_MESSAGE_BYTES: usize = RECORD_SIZE as usize - 17;
+
+/// How long the push service keeps a message for an offline browser
+const TTL_SECS: u32 = 24 * 60 * 60;
+
+/// How long a VAPID token is valid; at most a day is accepted
+const TOKEN_LIFETIME_SECS: i64 = 12 * 60 * 60;
+
+/// Errors that can occur while setting up or sending push messages
+#[derive(Debug, Error)]
+pub enum WebPushError {
+    #[error("Invalid VAPID key: {0}")]
+    InvalidKey(String),
+
+    #[error("Invalid push subscription: {0}")]
+    InvalidSubscription(String),
+
+    #[error("The message is larger than {MAX_MESSAGE_BYTES} bytes")]
+    TooLarge,
+
+    #[error("Encryption failed")]
+    Crypto,
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("The push service rejected the message with status {0}")]
+    Status(u16),
+
+    #[error("The subscription has expired or was cancelled")]
+    Gone,
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+}
+
+impl From<ring::error::Unspecified> for WebPushError {
+    fn from(_: ring::error::Unspecified) -> Self {
+        Self::Crypto
+    }
+}
+
+/// `[web.push]` in `config.toml`; push is unavailable when absent
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebPushConfig {
+    /// Public key of the VAPID key pair, base64url as printed by
+    /// `npx web-push generate-vapid-keys`
+    pub vapid_public_key: String,
+    /// Private key of the pair, base64url
+    pub vapid_private_key: String,
+    /// Contact for the push services, a `mailto:` or `https:` URL
+    pub subject: String,
+}
+
+/// Keys a browser created for a subscription, base64url
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PushKeys {
+    /// The browser's P-256 public key
+    pub p256dh: String,
+    /// Authentication secret
+    pub auth: String,
+}
+
+/// A subscription as the browser's `PushSubscription.toJSON()` gives it,
+/// with the user it belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct NewPushSubscription {
+    pub endpoint: String,
+    pub keys: PushKeys,
+    /// Who gets the notifications, such as `母`
+    #[serde(default)]
+    pub user: String,
+}
+
+/// A saved push subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    pub keys: PushKeys,
+    pub user: String,
+    /// When the browser subscribed (RFC 3339)
+    pub created_at: String,
+}
+
+/// Saved push subscriptions, one per endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushSubscriptions {
+    pub subscriptions: Vec<PushSubscription>,
+}
+
+impl PushSubscriptions {
+    pub fn load(storage: &Storage) -> Result<Self, StorageError> {
+        match storage.load(PUSH_SUBSCRIPTIONS_FILE)? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<(), StorageError> {
+        storage.save(PUSH_SUBSCRIPTIONS_FILE, &serde_json::to_value(self)?)
+    }
+
+    /// Add `subscription` as subscribed at `now`, replacing any with the
+    /// same endpoint
+    pub fn subscribe(
+        &mut self,
+        subscription: NewPushSubscription,
+        now: DateTime<FixedOffset>,
+    ) -> Result<&PushSubscription, WebPushError> {
+        validate(&subscription)?;
+        self.subscriptions
+            .retain(|s| s.endpoint != subscription.endpoint);
+        self.subscriptions.push(PushSubscription {
+            endpoint: subscription.endpoint,
+            keys: subscription.keys,
+            user: subscription.user.trim().to_string(),
+            created_at: now.to_rfc3339(),
+        });
+        Ok(self.subscriptions.last().expect("just pushed"))
+    }
+
+    /// Remove the subscription for `endpoint`, returning whether it existed
+    pub fn unsubscribe(&mut self, endpoint: &str) -> bool {
+        let before = self.subscriptions.len();
+        self.subscriptions.retain(|s| s.endpoint != endpoint);
+        self.subscriptions.len() != before
+    }
+
+    /// Subscriptions of `user`, or everyone's
+    pub fn of_user<'a>(
+        &'a self,
+        user: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a PushSubscription> + 'a {
+        self.subscriptions
+            .iter()
+            .filter(move |s| user.is_none_or(|user| s.user == user))
+    }
+}
+
+fn decode(value: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim().trim_end_matches('='))
+        .ok()
+}
+
+/// Check a subscription could be sent to
+fn validate(subscription: &NewPushSubscription) -> Result<(), WebPushError> {
+    let invalid = |detail: &str| WebPushError::InvalidSubscription(detail.to_string());
+    let endpoint = Url::parse(&subscription.endpoint).map_err(|e| invalid(&e.to_string()))?;
+    if endpoint.scheme() != "https" {
+        return Err(invalid("the endpoint must be an https URL"));
+    }
+    if decode(&subscription.keys.p256dh).is_none_or(|key| key.len() != 65) {
+        return Err(invalid("p256dh is not a P-256 public key"));
+    }
+    if decode(&subscription.keys.auth).is_none_or(|auth| auth.len() != 16) {
+        return Err(invalid("auth is not a 16-byte secret"));
+    }
+    Ok(())
+}
+
+/// Output length for HKDF expansion
+struct Len(usize);
+
+impl hkdf::KeyType for Len {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+fn expand(prk: &hkdf::Prk, info: &[&[u8]], out: &mut [u8]) -> Result<(), WebPushError> {
+    prk.expand(info, Len(out.len()))?.fill(out)?;
+    Ok(())
+}
+
+/// Content encryption key and nonce of a message, from the shared secret
+/// and the salt (RFC 8291 section 3.4)
+fn message_keys(
+    ecdh_secret: &[u8],
+    auth: &[u8],
+    ua_public: &[u8],
+    as_public: &[u8],
+    salt: &[u8],
+) -> Result<([u8; 16], [u8; 12]), WebPushError> {
+    let prk_key = hkdf::Salt::new(hkdf::HKDF_SHA256, auth).extract(ecdh_secret);
+    let mut ikm = [0u8; 32];
+    expand(
+        &prk_key,
+        &[b"WebPush: info\0", ua_public, as_public],
+        &mut ikm,
+    )?;
+
+    let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, salt).extract(&ikm);
+    let mut cek = [0u8; 16];
+    expand(&prk, &[b"Content-Encoding: aes128gcm\0"], &mut cek)?;
+    let mut nonce = [0u8; 12];
+    expand(&prk, &[b"Content-Encoding: nonce\0"], &mut nonce)?;
+    Ok((cek, nonce))
+}
+
+/// Encrypt `message` for the browser holding `keys`, as an `aes128gcm`
+/// body in one record
+pub fn encrypt(keys: &PushKeys, message: &[u8]) -> Result<Vec<u8>, WebPushError> {
+    if message.len() > MAX_MESSAGE_BYTES {
+        return Err(WebPushError::TooLarge);
+    }
+    let invalid = || WebPushError::InvalidSubscription("invalid keys".to_string());
+    let ua_public = decode(&keys.p256dh).ok_or_else(invalid)?;
+    let auth = decode(&keys.auth).ok_or_else(invalid)?;
+
+    let rng = SystemRandom::new();
+    let private_key = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng)?;
+    let as_public = private_key.compute_public_key()?;
+    let peer = agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, &ua_public);
+    let ecdh_secret = agreement::agree_ephemeral(private_key, &peer, |secret| secret.to_vec())?;
+    let mut salt = [0u8; 16];
+    rng.fill(&mut salt)?;
+    let (cek, nonce) = message_keys(&ecdh_secret, &auth, &ua_public, as_public.as_ref(), &salt)?;
+
+    // The last (and only) record ends with the delimiter 2
+    let mut record = message.to_vec();
+    record.push(2);
+    let key = aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_128_GCM, &cek)?);
+    key.seal_in_place_append_tag(
+        aead::Nonce::assume_unique_for_key(nonce),
+        aead::Aad::empty(),
+        &mut record,
+    )?;
+
+    let mut body = Vec::with_capacity(16 + 4 + 1 + 65 + record.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.as_ref().len() as u8);
+    body.extend_from_slice(as_public.as_ref());
+    body.extend_from_slice(&record);
+    Ok(body)
+}
+
+/// The server's VAPID identity
+pub struct Vapid {
+    key_pair: EcdsaKeyPair,
+    public_key: String,
+    subject: String,
+}
+
+impl Vapid {
+    pub fn from_config(config: &WebPushConfig) -> Result<Self, WebPushError> {
+        let invalid = |detail: &str| WebPushError::InvalidKey(detail.to_string());
+        let public_key = decode(&config.vapid_public_key).ok_or_else(|| invalid("public key"))?;
+        let private_key =
+            decode(&config.vapid_private_key).ok_or_else(|| invalid("private key"))?;
+        let key_pair = EcdsaKeyPair::from_private_key_and_public_key(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            &private_key,
+            &public_key,
+            &SystemRandom::new(),
+        )
+        .map_err(|e| invalid(&e.to_string()))?;
+        Ok(Self {
+            public_key: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+            key_pair,
+            subject: config.subject.clone(),
+        })
+    }
+
+    /// Public key browsers subscribe with (`applicationServerKey`),
+    /// base64url
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// `Authorization` header for a message to `endpoint` sent at `now`
+    pub fn authorization(
+        &self,
+        endpoint: &str,
+        now: DateTime<FixedOffset>,
+    ) -> Result<String, WebPushError> {
+        let audience = Url::parse(endpoint)
+            .map_err(|e| WebPushError::InvalidSubscription(e.to_string()))?
+            .origin()
+            .ascii_serialization();
+        let header = URL_SAFE_NO_PAD.encode(br#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": now.timestamp() + TOKEN_LIFETIME_SECS,
+            "sub": self.subject,
+        });
+        let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signed = format!("{}.{}", header, claims);
+        let signature = self
+            .key_pair
+            .sign(&SystemRandom::new(), signed.as_bytes())?;
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            signed,
+            URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            self.public_key
+        ))
+    }
+}
+
+/// What a notification shows, as the service worker receives it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    /// Replaces an earlier notification with the same tag
+    pub tag: String,
+}
+
+impl PushMessage {
+    /// Alert for a deadline entering the reminder window
+    pub fn deadline(deadline: &UpcomingDeadline) -> Self {
+        Self {
+            title: "支払い期限が近づいています".to_string(),
+            body: render_message(&WebhookEvent::DeadlineApproaching(deadline.clone())),
+            tag: format!(
+                "deadline-{}-{}-{}",
+                deadline.school_id,
+                deadline.fee_name(),
+                deadline.deadline
+            ),
+        }
+    }
+}
+
+/// Sends push messages as the server's VAPID identity
+pub struct WebPush {
+    vapid: Vapid,
+    client: reqwest::Client,
+}
+
+impl WebPush {
+    pub fn new(config: &WebPushConfig) -> Result<Self, WebPushError> {
+        Ok(Self {
+            vapid: Vapid::from_config(config)?,
+            client: webhook::http_client(),
+        })
+    }
+
+    pub fn public_key(&self) -> &str {
+        self.vapid.public_key()
+    }
+
+    /// Push `message` to one subscription
+    pub async fn send(
+        &self,
+        subscription: &PushSubscription,
+        message: &PushMessage,
+    ) -> Result<(), WebPushError> {
+        let message = serde_json::to_vec(message).map_err(StorageError::from)?;
+        let body = encrypt(&subscription.keys, &message)?;
+        let response = self
+            .client
+            .post(&subscription.endpoint)
+            .header(
+                reqwest::header::AUTHORIZATION,
+                self.vapid
+                    .authorization(&subscription.endpoint, date::now())?,
+            )
+            .header(reqwest::header::CONTENT_ENCODING, "aes128gcm")
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header("TTL", TTL_SECS.to_string())
+            .header("Urgency", "high")
+            .body(body)
+            .send()
+            .await?;
+        match response.status().as_u16() {
+            200..=299 => Ok(()),
+            404 | 410 => Err(WebPushError::Gone),
+            status => Err(WebPushError::Status(status)),
+        }
+    }
+
+    /// Push `message` to every subscription of `user`, or everyone's,
+    /// dropping those that are gone. Returns how many were delivered.
+    pub async fn send_all(
+        &self,
+        storage: &Storage,
+        user: Option<&str>,
+        message: &PushMessage,
+    ) -> Result<usize, WebPushError> {
+        let subscriptions = PushSubscriptions::load(storage)?;
+        let mut delivered = 0;
+        let mut gone = Vec::new();
+        for subscription in subscriptions.of_user(user) {
+            match self.send(subscription, message).await {
+                Ok(()) => delivered += 1,
+                Err(WebPushError::Gone) => gone.push(subscription.endpoint.clone()),
+                Err(e) => tracing::warn!("Push to {} failed: {}", subscription.user, e),
+            }
+        }
+        if !gone.is_empty() {
+            // Reload, so subscriptions added meanwhile are kept
+            let mut subscriptions = PushSubscriptions::load(storage)?;
+            for endpoint in &gone {
+                subscriptions.unsubscribe(endpoint);
+            }
+            subscriptions.save(storage)?;
+            tracing::info!("Dropped {} expired push subscriptions", gone.len());
+        }
+        Ok(delivered)
+    }
+
+    /// Push an alert for `deadline` to everyone in the background
+    pub fn notify_deadline(self: &Arc<Self>, storage: &Storage, deadline: &UpcomingDeadline) {
+        let push = self.clone();
+        let storage = storage.clone();
+        let message = PushMessage::deadline(deadline);
+        tokio::spawn(async move {
+            if let Err(e) = push.send_all(&storage, None, &message).await {
+                tracing::warn!("Deadline push failed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+
+    /// A browser's side of a subscription
+    fn browser() -> (agreement::EphemeralPrivateKey, PushKeys, [u8; 16]) {
+        let rng = SystemRandom::new();
+        let private_key =
+            agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng).unwrap();
+        let public_key = private_key.compute_public_key().unwrap();
+        let mut auth = [0u8; 16];
+        rng.fill(&mut auth).unwrap();
+        let keys = PushKeys {
+            p256dh: URL_SAFE_NO_PAD.encode(public_key.as_ref()),
+            auth: URL_SAFE_NO_PAD.encode(auth),
+        };
+        (private_key, keys, auth)
+    }
+
+    #[test]
+    fn test_browser_decrypts_message() {
+        let (private_key, keys, auth) = browser();
+        let body = encrypt(&keys, "期限が近づいています".as_bytes()).unwrap();
+
+        // Header: salt, record size, key id length and the server's key
+        let (salt, rest) = body.split_at(16);
+        assert_eq!(&rest[..4], &RECORD_SIZE.to_be_bytes());
+        assert_eq!(rest[4], 65);
+        let (as_public, record) = rest[5..].split_at(65);
+
+        let ua_public = decode(&keys.p256dh).unwrap();
+        let peer = agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, as_public);
+        let secret = agreement::agree_ephemeral(private_key, &peer, |s| s.to_vec()).unwrap();
+        let (cek, nonce) = message_keys(&secret, &auth, &ua_public, as_public, salt).unwrap();
+        let key = aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_128_GCM, &cek).unwrap());
+        let mut record = record.to_vec();
+        let plain = key
+            .open_in_place(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::empty(),
+                &mut record,
+            )
+            .unwrap();
+        assert_eq!(plain.last(), Some(&2));
+        assert_eq!(&plain[..plain.len() - 1], "期限が近づいています".as_bytes());
+
+        assert!(matches!(
+            encrypt(&keys, &[0; MAX_MESSAGE_BYTES + 1]),
+            Err(WebPushError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_vapid_token_is_signed() {
+        // A key pair in the form `npx web-push generate-vapid-keys` prints
+        let config = WebPushConfig {
+            vapid_public_key: "BGSOZmm1CqAPoAOFyD70i6o2NUi5bL5uJKgtjON9tkH2PPPRhQ_YlQzkpjepbw3fZQXjYfVvk2Y0HR8j2Lt6cnk"
+                .to_string(),
+            vapid_private_key: "WDZVjfvcqkP4QkUixi3iMM8RbGH0MV8Fn0eZpcsGZlY".to_string(),
+            subject: "mailto:admin@example.com".to_string(),
+        };
+        let vapid = Vapid::from_config(&config).unwrap();
+        assert_eq!(vapid.public_key(), config.vapid_public_key);
+
+        let now = DateTime::parse_from_rfc3339("2026-03-01T09:00:00+09:00").unwrap();
+        let header = vapid
+            .authorization("https://fcm.googleapis.com/fcm/send/abc", now)
+            .unwrap();
+        let token = header
+            .strip_prefix("vapid t=")
+            .and_then(|rest| rest.split(", k=").next())
+            .unwrap();
+        let (signed, signature) = token.rsplit_once('.').unwrap();
+        let public_key = decode(&config.vapid_public_key).unwrap();
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &public_key)
+            .verify(signed.as_bytes(), &decode(signature).unwrap())
+            .unwrap();
+
+        let claims = decode(signed.split('.').nth(1).unwrap()).unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(&claims).unwrap();
+        assert_eq!(claims["aud"], "https://fcm.googleapis.com");
+        assert_eq!(claims["exp"], now.timestamp() + TOKEN_LIFETIME_SECS);
+
+        let wrong = WebPushConfig {
+            vapid_private_key: URL_SAFE_NO_PAD.encode([7u8; 32]),
+            ..config
+        };
+        assert!(matches!(
+            Vapid::from_config(&wrong),
+            Err(WebPushError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_subscriptions_per_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let now = DateTime::parse_from_rfc3339("2026-03-01T09:00:00+09:00").unwrap();
+        let (_, keys, _) = browser();
+        let subscription = |endpoint: &str, user: &str| NewPushSubscription {
+            endpoint: endpoint.to_string(),
+            keys: keys.clone(),
+            user: user.to_string(),
+        };
+
+        let mut subscriptions = PushSubscriptions::load(&storage).unwrap();
+        subscriptions
+            .subscribe(subscription("https://push.example/1", "母"), now)
+            .unwrap();
+        subscriptions
+            .subscribe(subscription("https://push.example/2", "父"), now)
+            .unwrap();
+        // The same browser subscribing again replaces its subscription
+        subscriptions
+            .subscribe(subscription("https://push.example/1", " 母 "), now)
+            .unwrap();
+        subscriptions.save(&storage).unwrap();
+
+        let loaded = PushSubscriptions::load(&storage).unwrap();
+        assert_eq!(loaded.subscriptions.len(), 2);
+        let mothers: Vec<_> = loaded.of_user(Some("母")).collect();
+        assert_eq!(mothers.len(), 1);
+        assert_eq!(mothers[0].endpoint, "https://push.example/1");
+        assert_eq!(loaded.of_user(None).count(), 2);
+
+        assert!(matches!(
+            subscriptions.subscribe(subscription("http://push.example/3", "母"), now),
+            Err(WebPushError::InvalidSubscription(_))
+        ));
+        let mut bad_keys = subscription("https://push.example/3", "母");
+        bad_keys.keys.auth = "short".to_string();
+        assert!(subscriptions.subscribe(bad_keys, now).is_err());
+
+        let mut subscriptions = loaded;
+        assert!(subscriptions.unsubscribe("https://push.example/2"));
+        assert!(!subscriptions.unsubscribe("https://push.example/2"));
+    }
+}
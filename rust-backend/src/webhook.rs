@@ -0,0 +1,283 @@
+//! Outgoing webhook notifications.
+//!
+//! Events are POSTed as JSON to each configured target whose filter matches.
+//! When a secret is configured the body is signed with HMAC-SHA256 and sent
+//! in the `X-Webhook-Signature` header as `sha256=<hex>`. Failed deliveries
+//! are retried with exponential backoff in a background task.
+
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::budget::BudgetAlert;
+use crate::reminders::UpcomingDeadline;
+
+/// Header carrying the payload signature
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Header carrying the event kind
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Delay before the first retry; doubled on each subsequent attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait for a connection to a target
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest wait for a whole delivery attempt, so a target that never
+/// answers cannot hold a delivery task forever
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// HTTP client with [`CONNECT_TIMEOUT`] and [`REQUEST_TIMEOUT`] set, for
+/// requests to servers outside the user's control
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// Kinds of events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEventKind {
+    DeadlineApproaching,
+    RecommendationChanged,
+    ReplCrashed,
+    BudgetExceeded,
+    BackupFailing,
+}
+
+/// An event delivered to webhook targets
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "camelCase")]
+pub enum WebhookEvent {
+    DeadlineApproaching(UpcomingDeadline),
+    RecommendationChanged {
+        previous: serde_json::Value,
+        current: serde_json::Value,
+    },
+    ReplCrashed {
+        reason: String,
+    },
+    BudgetExceeded(BudgetAlert),
+    /// Scheduled cloud backups have kept failing
+    BackupFailing {
+        /// RFC 3339 time of the first failure
+        since: String,
+        last_success: Option<String>,
+        days: u32,
+        error: String,
+    },
+}
+
+impl WebhookEvent {
+    /// The kind of this event, used for filtering
+    pub fn kind(&self) -> WebhookEventKind {
+        match self {
+            Self::DeadlineApproaching(_) => WebhookEventKind::DeadlineApproaching,
+            Self::RecommendationChanged { .. } => WebhookEventKind::RecommendationChanged,
+            Self::ReplCrashed { .. } => WebhookEventKind::ReplCrashed,
+            Self::BudgetExceeded(_) => WebhookEventKind::BudgetExceeded,
+            Self::BackupFailing { .. } => WebhookEventKind::BackupFailing,
+        }
+    }
+
+    /// Serialize the event with a delivery timestamp
+    pub fn to_payload(&self) -> serde_json::Value {
+        let mut payload = serde_json::to_value(self).unwrap_or_default();
+        payload["timestamp"] = serde_json::json!(chrono::Utc::now().to_rfc3339());
+        payload
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// A single webhook target
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Shared secret used to sign payloads
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Events to deliver; empty means all events
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+impl WebhookConfig {
+    /// Check whether this target subscribes to the given event kind
+    pub fn accepts(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// Sign a payload body with HMAC-SHA256, returning the header value
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Delivers events to configured webhook targets
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    hooks: Vec<WebhookConfig>,
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self {
+            client: http_client(),
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher for the given targets
+    pub fn new(hooks: Vec<WebhookConfig>) -> Self {
+        Self {
+            hooks,
+            ..Self::default()
+        }
+    }
+
+    /// Check whether any targets are configured
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Send an event to every matching target in the background
+    pub fn dispatch(&self, event: WebhookEvent) {
+        let kind = event.kind();
+        let targets: Vec<_> = self
+            .hooks
+            .iter()
+            .filter(|h| h.accepts(kind))
+            .cloned()
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let body = event.to_payload().to_string();
+        for hook in targets {
+            let client = self.client.clone();
+            let body = body.clone();
+            tokio::spawn(async move { deliver(client, hook, kind, body).await });
+        }
+    }
+}
+
+/// POST a payload to a target, retrying with exponential backoff
+async fn deliver(
+    client: reqwest::Client,
+    hook: WebhookConfig,
+    kind: WebhookEventKind,
+    body: String,
+) {
+    let event_name = serde_json::to_value(kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let signature = hook
+        .secret
+        .as_deref()
+        .map(|secret| sign(secret, body.as_bytes()));
+
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 0..=hook.max_retries {
+        let mut request = client
+            .post(&hook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &event_name)
+            .body(body.clone());
+        if let Some(ref signature) = signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                tracing::debug!("Webhook {} delivered to {}", event_name, hook.url);
+                return;
+            }
+            Ok(response) => {
+                tracing::warn!(
+                    "Webhook {} to {} failed with status {} (attempt {})",
+                    event_name,
+                    hook.url,
+                    response.status(),
+                    attempt + 1
+                );
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Webhook {} to {} failed: {} (attempt {})",
+                    event_name,
+                    hook.url,
+                    e,
+                    attempt + 1
+                );
+            }
+        }
+
+        if attempt < hook.max_retries {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    tracing::error!("Giving up on webhook {} to {}", event_name, hook.url);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(events: Vec<WebhookEventKind>) -> WebhookConfig {
+        WebhookConfig {
+            url: "http://localhost/hook".to_string(),
+            secret: None,
+            events,
+            max_retries: 0,
+        }
+    }
+
+    #[test]
+    fn test_sign_known_vector() {
+        // RFC 4231 test case 2
+        let signature = sign("Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            signature,
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        assert!(hook(vec![]).accepts(WebhookEventKind::ReplCrashed));
+        let filtered = hook(vec![WebhookEventKind::DeadlineApproaching]);
+        assert!(filtered.accepts(WebhookEventKind::DeadlineApproaching));
+        assert!(!filtered.accepts(WebhookEventKind::ReplCrashed));
+    }
+
+    #[test]
+    fn test_payload_shape() {
+        let event = WebhookEvent::ReplCrashed {
+            reason: "exited".to_string(),
+        };
+        let payload = event.to_payload();
+        assert_eq!(payload["event"], "replCrashed");
+        assert_eq!(payload["data"]["reason"], "exited");
+        assert!(payload["timestamp"].is_string());
+    }
+}
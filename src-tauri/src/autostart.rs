@@ -0,0 +1,80 @@
+//! Launch at login, driven by the `launchAtLogin` setting.
+//!
+//! The OS entry (registry Run key, launchd agent or XDG autostart file) is
+//! managed by the autostart plugin and brought in line with the setting at
+//! every startup and whenever the setting changes. A login launch passes
+//! [`AUTOSTART_ARG`] so the app starts in the tray instead of opening its
+//! window.
+
+use std::sync::Arc;
+
+use tauri::{AppHandle, Manager, State};
+
+use rust_backend::{
+    handlers::{self, AppState},
+    i18n::LocalizedError,
+    settings::Settings,
+};
+
+/// Argument added to the command line registered with the OS
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+/// Whether this process was started by the OS at login
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+/// Register or remove the OS login entry to match `enabled`
+#[cfg(desktop)]
+pub fn apply(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    use tauri_plugin_autostart::ManagerExt;
+
+    let autolaunch = app.autolaunch();
+    if autolaunch.is_enabled().map_err(|e| e.to_string())? == enabled {
+        return Ok(());
+    }
+    if enabled {
+        autolaunch.enable().map_err(|e| e.to_string())?;
+    } else {
+        autolaunch.disable().map_err(|e| e.to_string())?;
+    }
+    tracing::info!("Launch at login {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+#[cfg(not(desktop))]
+pub fn apply(_app: &AppHandle, _enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+/// Turn launch at login on or off, saving it in the settings.
+///
+/// Returns the settings as applied.
+#[tauri::command]
+pub async fn set_autostart(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<Settings, LocalizedError> {
+    let locale = state.locale().await;
+    let mut settings = handlers::get_settings(state.inner().clone()).await;
+    settings.launch_at_login = enabled;
+    let settings = handlers::update_settings(state.inner().clone(), settings)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    apply(&app, enabled).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    Ok(settings)
+}
+
+/// Bring the OS entry in line with the saved setting and, on a login
+/// launch, keep the main window hidden in the tray
+pub fn setup(app: &AppHandle, settings: &Settings) {
+    if let Err(e) = apply(app, settings.launch_at_login) {
+        tracing::warn!("Could not update launch at login: {}", e);
+    }
+    if launched_at_login() {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.hide();
+        }
+    }
+}
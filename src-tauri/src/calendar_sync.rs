@@ -0,0 +1,149 @@
+//! Google Calendar sync commands, with the sign-in tokens kept in the OS
+//! keychain (Credential Manager, Keychain, or the Secret Service).
+
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+
+use rust_backend::{
+    date,
+    google_calendar::{
+        self, DeviceAuthorization, GoogleCalendarError, GoogleCalendarStatus, GoogleClient,
+        OAuthToken, SyncReport, TokenStore,
+    },
+    handlers::{AppState, HandlerError},
+    Storage,
+};
+
+use crate::windows;
+
+/// Keychain service name of the stored tokens
+const KEYCHAIN_SERVICE: &str = "school-payment";
+
+/// Keychain account name of the stored tokens
+const KEYCHAIN_ACCOUNT: &str = "google-calendar";
+
+/// OAuth tokens kept in the OS keychain
+pub struct KeychainTokenStore;
+
+impl KeychainTokenStore {
+    fn entry() -> Result<keyring::Entry, GoogleCalendarError> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+            .map_err(|e| GoogleCalendarError::Keychain(e.to_string()))
+    }
+}
+
+impl TokenStore for KeychainTokenStore {
+    fn load(&self) -> Result<Option<OAuthToken>, GoogleCalendarError> {
+        match Self::entry()?.get_password() {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(GoogleCalendarError::Keychain(e.to_string())),
+        }
+    }
+
+    fn save(&self, token: &OAuthToken) -> Result<(), GoogleCalendarError> {
+        Self::entry()?
+            .set_password(&serde_json::to_string(token)?)
+            .map_err(|e| GoogleCalendarError::Keychain(e.to_string()))
+    }
+
+    fn delete(&self) -> Result<(), GoogleCalendarError> {
+        match Self::entry()?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(GoogleCalendarError::Keychain(e.to_string())),
+        }
+    }
+}
+
+fn storage(state: &AppState) -> Result<&Storage, String> {
+    state
+        .storage
+        .as_ref()
+        .ok_or_else(|| HandlerError::NoStorage.to_string())
+}
+
+fn client(state: &AppState) -> Result<GoogleClient, String> {
+    state
+        .config
+        .google_calendar
+        .clone()
+        .map(GoogleClient::new)
+        .ok_or_else(|| GoogleCalendarError::NotConfigured.to_string())
+}
+
+/// Whether Google Calendar is configured and signed in, and which
+/// datasets are synced
+#[tauri::command]
+pub async fn get_google_calendar_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<GoogleCalendarStatus, String> {
+    google_calendar::status(
+        state.config.google_calendar.as_ref(),
+        &KeychainTokenStore,
+        storage(&state)?,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Start signing in to Google, returning the code for the user to enter
+#[tauri::command]
+pub async fn start_google_calendar_auth(
+    state: State<'_, Arc<AppState>>,
+) -> Result<DeviceAuthorization, String> {
+    client(&state)?
+        .start_device_flow()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Wait until the user has entered the code, then keep the tokens
+#[tauri::command]
+pub async fn finish_google_calendar_auth(
+    state: State<'_, Arc<AppState>>,
+    authorization: DeviceAuthorization,
+) -> Result<(), String> {
+    client(&state)?
+        .finish_device_flow(&authorization, &KeychainTokenStore)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Sign out of Google, forgetting the tokens. Synced events are kept.
+#[tauri::command]
+pub async fn disconnect_google_calendar() -> Result<(), String> {
+    KeychainTokenStore.delete().map_err(|e| e.to_string())
+}
+
+/// Turn sync of a dataset on or off; the default dataset if no name
+#[tauri::command]
+pub async fn set_google_calendar_sync(
+    state: State<'_, Arc<AppState>>,
+    profile: Option<String>,
+    enabled: bool,
+) -> Result<(), String> {
+    google_calendar::set_enabled(storage(&state)?, profile.as_deref(), enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// Sync a dataset with Google Calendar now; the default dataset if no name
+#[tauri::command]
+pub async fn sync_google_calendar(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    profile: Option<String>,
+) -> Result<SyncReport, String> {
+    let report = client(&state)?
+        .sync(
+            &KeychainTokenStore,
+            storage(&state)?,
+            profile.as_deref(),
+            date::today(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    if report.changed_data() {
+        windows::notify_data_changed(&app, None);
+    }
+    Ok(report)
+}
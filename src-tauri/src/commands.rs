@@ -1,15 +1,91 @@
 //! Tauri commands that expose rust-backend functionality to the frontend.
 
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Manager, State, Window};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_shell::ShellExt;
+
+use crate::{autostart, windows};
 
 use rust_backend::{
+    analysis::ScheduleWarning,
+    backup::{BackupInfo, EXIT_BACKUP},
+    bulk::BulkOp,
+    calendar::Holiday,
+    cloud_backup::CloudBackupStatus,
+    countdown::Countdown,
+    data_location::{self, DataLocation, MovedData},
+    diff::ChangeReport,
+    edit_lock::EditLockStatus,
+    explanations::Explanation,
+    export::{
+        self, ExportFormat, ExportTemplate, ReportType, TextFormat, ZenginExport, ZenginLayout,
+    },
     handlers::{self, AppState, HealthResponse},
+    history::{Decision, HistoryEntry, HistoryStats, RecommendationInputs},
+    i18n::LocalizedError,
+    import::{ImportPreview, MergeAction},
+    journal::PastVersion,
+    legacy_import::LegacyImportPreview,
+    logs::LogWriter,
+    query::{ListQuery, Page},
+    currency::ExchangeRates,
+    model::{
+        Currency, Dataset, Installment, InstallmentPlan, RecurringFee, RecurringPayment, School,
+    },
+    notes::NewNote,
+    onboarding::{OnboardingState, OnboardingStep},
+    outcome::{ExamOutcome, RecordedOutcome},
+    payments::{OutstandingPayments, PaymentRecord},
+    date,
+    health_history::HealthEvent,
+    sample::SampleProfile,
+    search::SearchHit,
+    settings::Settings,
+    sheet_import::{ColumnMapping, MappingMatch, MappingSource},
+    simulation::{Scenario, SimulationResult},
+    startup::StartupProgress,
     json_rpc::{JsonRpcRequest, JsonRpcResponse},
-    storage::{Storage, SCHOOLS_DATA_FILE},
+    result_cache::CachedResult,
+    storage::{dataset_file, Storage},
+    summary::{Summary, SummaryFigure, SummaryRange, TotalExplanation},
+    tax_report::TaxReport,
+    templates::SchoolTemplate,
+    timeline::Timeline,
+    supervisor::AdvisorStatus,
+    transfer::TransferOffer,
 };
 
+/// How the previous run of the app ended, detected at startup
+pub struct PreviousSession {
+    pub unclean: bool,
+}
+
+/// Latest advisor warm-up progress, for windows that load after it was emitted
+#[derive(Default)]
+pub struct StartupStatus(std::sync::Mutex<Option<StartupProgress>>);
+
+impl StartupStatus {
+    pub fn set(&self, progress: StartupProgress) {
+        if let Ok(mut latest) = self.0.lock() {
+            *latest = Some(progress);
+        }
+    }
+}
+
+/// Get the latest warm-up progress, or `None` before warm-up has begun
+#[tauri::command]
+pub async fn get_startup_progress(
+    status: State<'_, StartupStatus>,
+) -> Result<Option<StartupProgress>, String> {
+    Ok(status.0.lock().map_err(|e| e.to_string())?.clone())
+}
+
 /// Send an RPC request to the Lean REPL
 #[tauri::command]
 pub async fn send_rpc(
@@ -21,10 +97,56 @@ pub async fn send_rpc(
         .map_err(|e| e.to_string())
 }
 
-/// Check the health of the application
+/// Send several RPC requests to the Lean REPL in one call
+#[tauri::command]
+pub async fn send_rpc_batch(
+    state: State<'_, Arc<AppState>>,
+    requests: Vec<JsonRpcRequest>,
+) -> Result<Vec<JsonRpcResponse>, String> {
+    Ok(handlers::send_rpc_batch(
+        state.inner().clone(),
+        requests,
+        handlers::DEFAULT_BATCH_PARALLELISM,
+    )
+    .await)
+}
+
+/// Get the result last computed for an RPC request, with its age, to show
+/// while the advisor computes it again
+#[tauri::command]
+pub async fn get_cached_result(
+    state: State<'_, Arc<AppState>>,
+    request: JsonRpcRequest,
+) -> Result<Option<CachedResult>, String> {
+    Ok(handlers::cached_result(state.inner().clone(), &request).await)
+}
+
+/// Check the health of the application, first waiting up to
+/// `wait_ready_secs` for the advisor to answer if given
 #[tauri::command]
-pub async fn health_check(state: State<'_, Arc<AppState>>) -> Result<HealthResponse, String> {
-    Ok(handlers::health_check(state.inner().clone()).await)
+pub async fn health_check(
+    state: State<'_, Arc<AppState>>,
+    wait_ready_secs: Option<u64>,
+) -> Result<HealthResponse, String> {
+    let state = state.inner().clone();
+    Ok(match wait_ready_secs {
+        Some(secs) => handlers::health_check_ready(state, Duration::from_secs(secs)).await,
+        None => handlers::health_check(state).await,
+    })
+}
+
+/// Get the current advisor availability
+#[tauri::command]
+pub async fn get_advisor_status(state: State<'_, Arc<AppState>>) -> Result<AdvisorStatus, String> {
+    Ok(state.advisor_status())
+}
+
+/// Advisor status changes, crashes and restarts with their times
+#[tauri::command]
+pub async fn get_health_history(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<HealthEvent>, String> {
+    Ok(handlers::health_history(state.inner().clone()).await)
 }
 
 /// Restart the Lean REPL
@@ -35,28 +157,1227 @@ pub async fn restart_repl(state: State<'_, Arc<AppState>>) -> Result<(), String>
         .map_err(|e| e.to_string())
 }
 
-/// Save data to local storage
+/// Save data to local storage.
+///
+/// `name` selects a named dataset ("Save plan as..."); omit it for the default dataset.
+/// With `expected_revision`, the revision the data was loaded at, a save over
+/// changes made meanwhile elsewhere fails with `error.dataConflict` instead.
+/// Returns the saved data with its new revision.
+#[tauri::command]
+pub async fn save_data(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    data: Dataset,
+    name: Option<String>,
+    expected_revision: Option<u64>,
+) -> Result<Dataset, LocalizedError> {
+    let locale = state.locale().await;
+    let saved = handlers::save_data(
+        state.inner().clone(),
+        name.as_deref(),
+        data,
+        expected_revision,
+        date::today(),
+    )
+    .await
+    .map_err(|e| e.localize(locale))?;
+
+    windows::notify_data_changed(&app, Some(&window));
+    Ok(saved)
+}
+
+/// Change part of the saved data with a JSON merge patch, checking the
+/// expected revision like `save_data`
 #[tauri::command]
-pub async fn save_data(app: AppHandle, data: serde_json::Value) -> Result<(), String> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
+pub async fn patch_data(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    patch: serde_json::Value,
+    name: Option<String>,
+    expected_revision: Option<u64>,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<Dataset>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let report = handlers::patch_data(
+        state.inner().clone(),
+        name.as_deref(),
+        &patch,
+        expected_revision,
+        dry_run,
+        date::today(),
+    )
+    .await
+    .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(report)
+}
+
+/// Apply several changes to schools and payments as one save, all or
+/// none, checking the expected revision like `save_data`
+#[tauri::command]
+pub async fn bulk_update(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    ops: Vec<BulkOp>,
+    name: Option<String>,
+    expected_revision: Option<u64>,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<Dataset>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let report = handlers::bulk_update(
+        state.inner().clone(),
+        name.as_deref(),
+        ops,
+        expected_revision,
+        dry_run,
+        date::today(),
+    )
+    .await
+    .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(report)
+}
+
+/// A dataset as it was at `at` (RFC 3339, or a date for the end of that
+/// day), optionally a named dataset
+#[tauri::command]
+pub async fn get_data_as_of(
+    state: State<'_, Arc<AppState>>,
+    at: String,
+    name: Option<String>,
+) -> Result<PastVersion, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_data_as_of(state.inner().clone(), name.as_deref(), &at)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Load data from local storage, optionally a named dataset
+#[tauri::command]
+pub async fn load_data(
+    app: AppHandle,
+    name: Option<String>,
+) -> Result<Option<Dataset>, String> {
+    let file = dataset_file(name.as_deref()).map_err(|e| e.to_string())?;
+    let data_dir = crate::data_dir(&app)?;
 
     let storage = Storage::new(data_dir);
     storage
-        .save(SCHOOLS_DATA_FILE, &data)
+        .load(&file)
+        .map_err(|e| e.to_string())?
+        .map(Dataset::from_value)
+        .transpose()
         .map_err(|e| e.to_string())
 }
 
-/// Load data from local storage
+/// List the names of saved named datasets
 #[tauri::command]
-pub async fn load_data(app: AppHandle) -> Result<Option<serde_json::Value>, String> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
+pub async fn list_datasets(app: AppHandle) -> Result<Vec<String>, String> {
+    let data_dir = crate::data_dir(&app)?;
 
-    let storage = Storage::new(data_dir);
-    storage.load(SCHOOLS_DATA_FILE).map_err(|e| e.to_string())
+    Storage::new(data_dir)
+        .list_datasets()
+        .map_err(|e| e.to_string())
+}
+
+/// Export the saved data to a file chosen in the native save dialog.
+///
+/// Returns the saved path, or `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn export_data_dialog(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    format: String,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    let format = ExportFormat::from_name(&format).ok_or_else(|| {
+        LocalizedError::unexpected(format!("Unknown export format: {}", format), locale)
+    })?;
+    let bytes = handlers::export_data(state.inner().clone(), format, None)
+        .await
+        .map_err(|e| e.localize(locale))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter(format.extension().to_uppercase(), &[format.extension()])
+        .set_file_name(format!("school-payment.{}", format.extension()))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    std::fs::write(&path, bytes).map_err(|e| LocalizedError::unexpected(e, locale))?;
+
+    tracing::info!("Exported {} to {:?}", format.extension(), path);
+    Ok(Some(path.display().to_string()))
+}
+
+/// Save a Zengin-format bank transfer file for this week's payments.
+///
+/// `layout` is `fixed` (default) or `csv`. Returns what the file contains,
+/// or `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn export_zengin(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    layout: Option<String>,
+) -> Result<Option<ZenginExport>, LocalizedError> {
+    let locale = state.locale().await;
+    let layout = match layout {
+        Some(name) => ZenginLayout::from_name(&name).ok_or_else(|| {
+            LocalizedError::unexpected(format!("Unknown transfer file layout: {}", name), locale)
+        })?,
+        None => ZenginLayout::default(),
+    };
+    let export = handlers::export_zengin(state.inner().clone(), layout, date::today())
+        .await
+        .map_err(|e| e.localize(locale))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter(layout.extension().to_uppercase(), &[layout.extension()])
+        .set_file_name(format!("振込データ.{}", layout.extension()))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    std::fs::write(&path, &export.bytes).map_err(|e| LocalizedError::unexpected(e, locale))?;
+
+    tracing::info!("Exported {} bank transfers to {:?}", export.transfers, path);
+    Ok(Some(export))
+}
+
+/// Render a printable PDF report and save it where the user chooses.
+///
+/// With `open`, the saved file is shown in the default PDF viewer for
+/// printing. Returns the saved path, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn export_pdf(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    report_type: ReportType,
+    open: Option<bool>,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    let bytes = handlers::export_report(state.inner().clone(), report_type, date::today())
+        .await
+        .map_err(|e| e.localize(locale))?;
+    let file_name = match report_type {
+        ReportType::Schedule => "支払いスケジュール.pdf",
+        ReportType::Weekly => "週間推奨レポート.pdf",
+        ReportType::Plan => "支払い計画レポート.pdf",
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("PDF", &["pdf"])
+        .set_file_name(file_name)
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    std::fs::write(&path, bytes).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    tracing::info!("Exported {:?} report to {:?}", report_type, path);
+
+    if open.unwrap_or(false) {
+        // tauri-plugin-opener is not a dependency yet; the shell plugin's
+        // opener does the same when called from Rust
+        #[allow(deprecated)]
+        app.shell()
+            .open(path.display().to_string(), None)
+            .map_err(|e| LocalizedError::unexpected(e, locale))?;
+    }
+    Ok(Some(path.display().to_string()))
+}
+
+/// Export templates in the `templates` folder of the data directory
+#[tauri::command]
+pub async fn list_export_templates(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ExportTemplate>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::list_export_templates(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Render the saved data with an export template, with `report`'s advisor
+/// result if given, and save it where the user chooses. Returns the saved
+/// path, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn export_with_template(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    report: Option<ReportType>,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    let export = handlers::export_with_template(state.inner().clone(), &name, report, date::today())
+        .await
+        .map_err(|e| e.localize(locale))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let mut dialog = app.dialog().file().set_file_name(&export.template.name);
+    if !export.template.extension.is_empty() {
+        let extension = export.template.extension.as_str();
+        dialog = dialog.add_filter(extension.to_uppercase(), &[extension]);
+    }
+    dialog.save_file(move |path| {
+        let _ = tx.send(path);
+    });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    std::fs::write(&path, export.text).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    tracing::info!("Exported with template {} to {:?}", name, path);
+    Ok(Some(path.display().to_string()))
+}
+
+/// Copy this week's unpaid payments to the clipboard.
+///
+/// `format` is `text` (default) or `markdown`. Returns the copied text so
+/// the frontend can show what was copied.
+#[tauri::command]
+pub async fn copy_schedule_to_clipboard(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    format: Option<String>,
+) -> Result<String, LocalizedError> {
+    let locale = state.locale().await;
+    let format = match format {
+        Some(name) => TextFormat::from_name(&name).ok_or_else(|| {
+            LocalizedError::unexpected(format!("Unknown text format: {}", name), locale)
+        })?,
+        None => TextFormat::Plain,
+    };
+    let data = state.load_dataset().map_err(|e| e.localize(locale))?;
+    let text = export::upcoming_payments_text(&data, date::today(), format);
+
+    app.clipboard()
+        .write_text(text.clone())
+        .map_err(|e| LocalizedError::unexpected(e, locale))?;
+    Ok(text)
+}
+
+/// Generate sample school data for the onboarding wizard and demos.
+///
+/// Nothing is saved; the frontend saves it with `save_data` if kept.
+#[tauri::command]
+pub async fn generate_sample_data(
+    profile: Option<SampleProfile>,
+) -> Result<Dataset, String> {
+    Ok(handlers::generate_sample_data(profile.unwrap_or_default()))
+}
+
+/// Get the current user settings
+#[tauri::command]
+pub async fn get_settings(state: State<'_, Arc<AppState>>) -> Result<Settings, String> {
+    Ok(handlers::get_settings(state.inner().clone()).await)
+}
+
+/// Validate and save user settings, returning them as applied
+#[tauri::command]
+pub async fn set_settings(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    settings: Settings,
+) -> Result<Settings, LocalizedError> {
+    let locale = state.locale().await;
+    let settings = handlers::update_settings(state.inner().clone(), settings)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    autostart::apply(&app, settings.launch_at_login)
+        .map_err(|e| LocalizedError::unexpected(e, locale))?;
+    Ok(settings)
+}
+
+/// Pick a JSON or CSV file and preview what importing it would do.
+///
+/// A CSV file is read through the saved column mapping `mapping`, or else
+/// the one that best fits its header. Nothing is saved until
+/// `confirm_import` is called with the preview's token. Returns `None` if
+/// the user cancelled the dialog.
+#[tauri::command]
+pub async fn import_data_dialog(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    mapping: Option<String>,
+) -> Result<Option<ImportPreview>, LocalizedError> {
+    let locale = state.locale().await;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("JSON / CSV", &["json", "csv"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let bytes = std::fs::read(&path).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let state = state.inner().clone();
+    handlers::preview_import_with_mapping(state, &file_name, &bytes, mapping.as_deref())
+        .await
+        .map(Some)
+        .map_err(|e| e.localize(locale))
+}
+
+/// Preview data exported from the browser version, chosen in a file dialog.
+///
+/// The preview carries a report of how the old fields were converted.
+/// Nothing is saved until `confirm_import` is called with the preview's
+/// token. Returns `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn import_legacy_dialog(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<LegacyImportPreview>, LocalizedError> {
+    let locale = state.locale().await;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("JSON", &["json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let bytes = std::fs::read(&path).map_err(|e| LocalizedError::unexpected(e, locale))?;
+
+    handlers::preview_legacy_import(state.inner().clone(), &bytes)
+        .await
+        .map(Some)
+        .map_err(|e| e.localize(locale))
+}
+
+/// Preview the school list of a shared Google Sheet or other CSV URL.
+///
+/// With `mapping`, the sheet's columns are read through that saved column
+/// mapping; otherwise through the one that best fits the sheet's header.
+/// Nothing is saved until `confirm_import` is called.
+#[tauri::command]
+pub async fn import_from_url(
+    state: State<'_, Arc<AppState>>,
+    url: String,
+    mapping: Option<String>,
+) -> Result<ImportPreview, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::preview_url_import(state.inner().clone(), &url, mapping.as_deref())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Saved column mappings for importing spreadsheets
+#[tauri::command]
+pub async fn list_column_mappings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ColumnMapping>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::list_column_mappings(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Save a column mapping, replacing the one with the same name
+#[tauri::command]
+pub async fn save_column_mapping(
+    state: State<'_, Arc<AppState>>,
+    mapping: ColumnMapping,
+) -> Result<Vec<ColumnMapping>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::save_column_mapping(state.inner().clone(), mapping)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Saved column mapping that best fits a header row, if any
+#[tauri::command]
+pub async fn detect_column_mapping(
+    state: State<'_, Arc<AppState>>,
+    source: MappingSource,
+    header: Vec<String>,
+) -> Result<Option<MappingMatch>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::detect_column_mapping(state.inner().clone(), source, &header)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Delete a saved column mapping
+#[tauri::command]
+pub async fn delete_column_mapping(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<Vec<ColumnMapping>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::delete_column_mapping(state.inner().clone(), &name)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Apply a previewed import and return the updated data, or with `dry_run`
+/// only what it would change. `choices` overrides the suggested action for
+/// probable duplicates, by their position in the preview.
+#[tauri::command]
+pub async fn confirm_import(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    choices: Option<BTreeMap<usize, MergeAction>>,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<serde_json::Value>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let choices = choices.unwrap_or_default();
+    let applied = handlers::confirm_import(state.inner().clone(), &token, &choices, dry_run)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(applied)
+}
+
+/// Serve the saved data to another device and return the code for its QR code
+#[tauri::command]
+pub async fn start_transfer(state: State<'_, Arc<AppState>>) -> Result<TransferOffer, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::start_transfer(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Fetch data from another device's transfer code and preview it for import
+#[tauri::command]
+pub async fn receive_transfer(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+) -> Result<ImportPreview, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::receive_transfer(state.inner().clone(), &code)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Schedule OS reminders for unpaid deadlines, replacing earlier ones
+#[tauri::command]
+pub async fn register_os_reminders(state: State<'_, Arc<AppState>>) -> Result<usize, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::register_os_reminders(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Remove the OS reminders added by `register_os_reminders`
+#[tauri::command]
+pub async fn unregister_os_reminders(
+    state: State<'_, Arc<AppState>>,
+) -> Result<usize, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::unregister_os_reminders(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Unpaid deadlines by how soon they are due, for badges; later changes
+/// arrive as `deadline-countdown` events
+#[tauri::command]
+pub async fn get_deadline_countdown(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Countdown, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::deadline_countdown(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Payment totals by month, school and category, optionally limited to a range
+#[tauri::command]
+pub async fn get_summary(
+    state: State<'_, Arc<AppState>>,
+    range: Option<SummaryRange>,
+) -> Result<Summary, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_summary(state.inner().clone(), range.unwrap_or_default())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// The payments adding up to one figure of the summary, for clicking a
+/// number through to the schools behind it
+#[tauri::command]
+pub async fn explain_totals(
+    state: State<'_, Arc<AppState>>,
+    figure: SummaryFigure,
+    range: Option<SummaryRange>,
+) -> Result<TotalExplanation, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::explain_totals(state.inner().clone(), range.unwrap_or_default(), figure)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Compare what-if scenarios against the saved data, planned from today
+#[tauri::command]
+pub async fn simulate(
+    state: State<'_, Arc<AppState>>,
+    scenarios: Vec<Scenario>,
+) -> Result<SimulationResult, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::simulate(state.inner().clone(), scenarios, date::today())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Schedule conflicts in the saved data from today
+#[tauri::command]
+pub async fn analyze_schedule(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ScheduleWarning>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::analyze_schedule(state.inner().clone(), date::today())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Why the advisor recommends the action with explanation id `id`
+#[tauri::command]
+pub async fn get_explanation(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<Explanation, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_explanation(state.inner().clone(), id)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Exam, result and payment timeline of each school in the saved data
+#[tauri::command]
+pub async fn get_timeline(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Timeline, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_timeline(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Days banks are closed in `year` other than weekends
+#[tauri::command]
+pub async fn list_holidays(
+    state: State<'_, Arc<AppState>>,
+    year: u32,
+) -> Result<Vec<Holiday>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::list_holidays(state.inner().clone(), year)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Import a holiday calendar chosen in a file dialog.
+///
+/// Returns the days banks are closed in `year`, or `None` if the user
+/// cancelled.
+#[tauri::command]
+pub async fn import_holidays_dialog(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    year: u32,
+) -> Result<Option<Vec<Holiday>>, LocalizedError> {
+    let locale = state.locale().await;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("JSON", &["json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let bytes = std::fs::read(&path).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    handlers::import_holidays(state.inner().clone(), &bytes, year)
+        .await
+        .map(Some)
+        .map_err(|e| e.localize(locale))
+}
+
+/// Bundled and imported school templates
+#[tauri::command]
+pub async fn list_templates(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<SchoolTemplate>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::list_templates(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// New school from a template, for the user to review before saving.
+///
+/// With `dry_run` set to `false` the school is saved at once.
+#[tauri::command]
+pub async fn apply_template(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    template_id: String,
+    exam_date: u32,
+    name: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<School>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(true);
+    let applied = handlers::apply_template(
+        state.inner().clone(),
+        &template_id,
+        exam_date,
+        name.as_deref(),
+        dry_run,
+    )
+    .await
+    .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(applied)
+}
+
+/// Import a template catalog chosen in a file dialog.
+///
+/// Returns the imported templates, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn import_templates_dialog(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<Vec<SchoolTemplate>>, LocalizedError> {
+    let locale = state.locale().await;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("JSON", &["json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let bytes = std::fs::read(&path).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    handlers::import_templates(state.inner().clone(), &bytes)
+        .await
+        .map(Some)
+        .map_err(|e| e.localize(locale))
+}
+
+/// List recorded recommendations with the same parameters as school lists
+#[tauri::command]
+pub async fn list_history(
+    state: State<'_, Arc<AppState>>,
+    params: Option<HashMap<String, String>>,
+) -> Result<Page<serde_json::Value>, LocalizedError> {
+    let locale = state.locale().await;
+    let query = ListQuery::from_params(&params.unwrap_or_default())
+        .map_err(|e| LocalizedError::unexpected(e, locale))?;
+    handlers::list_history(state.inner().clone(), &query)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Record what the user did about a recommendation; no decision clears it
+#[tauri::command]
+pub async fn decide_recommendation(
+    state: State<'_, Arc<AppState>>,
+    id: u64,
+    decision: Option<Decision>,
+    note: Option<String>,
+) -> Result<HistoryEntry, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::decide_recommendation(state.inner().clone(), id, decision, note)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// The exact advisor parameters a recommendation was computed from
+#[tauri::command]
+pub async fn get_recommendation_inputs(
+    state: State<'_, Arc<AppState>>,
+    id: u64,
+) -> Result<RecommendationInputs, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_recommendation_inputs(state.inner().clone(), id)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Season totals of the recommendation history
+#[tauri::command]
+pub async fn get_history_stats(
+    state: State<'_, Arc<AppState>>,
+) -> Result<HistoryStats, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::history_stats(state.inner().clone(), date::today())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Save the recommendation history as JSON or CSV where the user chooses.
+///
+/// Returns the saved path, or `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn export_history(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    format: String,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    let format = ExportFormat::from_name(&format).ok_or_else(|| {
+        LocalizedError::unexpected(format!("Unknown export format: {}", format), locale)
+    })?;
+    let bytes = handlers::export_history(state.inner().clone(), format)
+        .await
+        .map_err(|e| e.localize(locale))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter(format.extension().to_uppercase(), &[format.extension()])
+        .set_file_name(format!("推奨履歴.{}", format.extension()))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    std::fs::write(&path, bytes).map_err(|e| LocalizedError::unexpected(e, locale))?;
+
+    tracing::info!("Exported recommendation history to {:?}", path);
+    Ok(Some(path.display().to_string()))
+}
+
+/// Payments made in `year`, for tax filing
+#[tauri::command]
+pub async fn get_tax_report(
+    state: State<'_, Arc<AppState>>,
+    year: u32,
+) -> Result<TaxReport, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_tax_report(state.inner().clone(), year)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Save the year-end report for `year` as CSV or PDF through a save dialog.
+///
+/// Returns the saved path, or `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn export_tax_report(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    year: u32,
+    format: String,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    let format = ExportFormat::from_name(&format).ok_or_else(|| {
+        LocalizedError::unexpected(format!("Unknown export format: {}", format), locale)
+    })?;
+    let bytes = handlers::export_tax_report(state.inner().clone(), year, format)
+        .await
+        .map_err(|e| e.localize(locale))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter(format.extension().to_uppercase(), &[format.extension()])
+        .set_file_name(format!("教育費支払い_{}年.{}", year, format.extension()))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    std::fs::write(&path, bytes).map_err(|e| LocalizedError::unexpected(e, locale))?;
+
+    tracing::info!("Exported the {} tax report to {:?}", year, path);
+    Ok(Some(path.display().to_string()))
+}
+
+/// Record a payment as made in the saved data, or with `dry_run` only show
+/// what would change
+#[tauri::command]
+pub async fn mark_paid(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    record: PaymentRecord,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<Dataset>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let paid = handlers::mark_paid(state.inner().clone(), record, dry_run)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(paid)
+}
+
+/// Add a note to a school in the saved data, or with `dry_run` only show
+/// what would change
+#[tauri::command]
+pub async fn add_note(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    note: NewNote,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<Dataset>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let report = handlers::add_note(state.inner().clone(), note, dry_run)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(report)
+}
+
+/// Delete a school's note by its position, or with `dry_run` only show
+/// what would change
+#[tauri::command]
+pub async fn delete_note(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    school_id: u64,
+    index: usize,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<Dataset>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let report = handlers::delete_note(state.inner().clone(), school_id, index, dry_run)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(report)
+}
+
+/// School names, notes and payment memos matching every word of `query`
+#[tauri::command]
+pub async fn search(
+    state: State<'_, Arc<AppState>>,
+    query: String,
+) -> Result<Vec<SearchHit>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::search(state.inner().clone(), &query)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Record an exam result in the saved data and recompute the
+/// recommendation, or with `dry_run` only show what would change
+#[tauri::command]
+pub async fn record_outcome(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    school_id: u64,
+    outcome: ExamOutcome,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<RecordedOutcome>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let recorded =
+        handlers::record_outcome(state.inner().clone(), school_id, outcome, dry_run, date::today())
+            .await
+            .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(recorded)
+}
+
+/// Outstanding and settled payments in the saved data, for the weekly view
+#[tauri::command]
+pub async fn get_outstanding_payments(
+    state: State<'_, Arc<AppState>>,
+    range: Option<SummaryRange>,
+) -> Result<OutstandingPayments, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_outstanding_payments(state.inner().clone(), range.unwrap_or_default())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Split tuition into installments
+#[tauri::command]
+pub async fn plan_installments(
+    state: State<'_, Arc<AppState>>,
+    plan: InstallmentPlan,
+) -> Result<Vec<Installment>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::plan_installments(plan)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Payments of recurring fees for the coming year, before saving them
+#[tauri::command]
+pub async fn expand_recurring_fees(
+    state: State<'_, Arc<AppState>>,
+    fees: Vec<RecurringFee>,
+) -> Result<Vec<RecurringPayment>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::expand_recurring_fees(fees, date::today())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Generate the upcoming recurring payments of the saved data
+#[tauri::command]
+pub async fn refresh_recurring_payments(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ChangeReport<Dataset>, LocalizedError> {
+    let locale = state.locale().await;
+    let refreshed = handlers::refresh_recurring_payments(state.inner().clone(), date::today())
+        .await
+        .map_err(|e| e.localize(locale))?;
+    if !refreshed.diff.is_empty() {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(refreshed)
+}
+
+/// Get the saved exchange rates
+#[tauri::command]
+pub async fn get_exchange_rates(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ExchangeRates, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::get_exchange_rates(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Set a rate by hand in yen per unit, or clear it by passing no rate
+#[tauri::command]
+pub async fn set_exchange_rate(
+    state: State<'_, Arc<AppState>>,
+    currency: Currency,
+    yen_per_unit: Option<f64>,
+) -> Result<ExchangeRates, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::set_exchange_rate(state.inner().clone(), currency, yen_per_unit)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Fetch current exchange rates, keeping rates set by hand
+#[tauri::command]
+pub async fn refresh_exchange_rates(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ExchangeRates, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::refresh_exchange_rates(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Offer the latest exit backup if the previous run did not shut down cleanly
+#[tauri::command]
+pub async fn get_restore_offer(
+    state: State<'_, Arc<AppState>>,
+    session: State<'_, PreviousSession>,
+) -> Result<Option<BackupInfo>, LocalizedError> {
+    let locale = state.locale().await;
+    if !session.unclean {
+        return Ok(None);
+    }
+    let backups = handlers::list_backups(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))?;
+    Ok(backups.into_iter().find(|b| b.kind == EXIT_BACKUP))
+}
+
+/// Restore a backup and return the restored data, or with `dry_run` only
+/// what it would change
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    file_name: String,
+    dry_run: Option<bool>,
+) -> Result<ChangeReport<serde_json::Value>, LocalizedError> {
+    let locale = state.locale().await;
+    let dry_run = dry_run.unwrap_or(false);
+    let restored = handlers::restore_backup(state.inner().clone(), &file_name, dry_run)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    if !dry_run {
+        windows::notify_data_changed(&app, Some(&window));
+    }
+    Ok(restored)
+}
+
+/// What the scheduled backups to the sync server last did
+#[tauri::command]
+pub async fn get_cloud_backup_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<CloudBackupStatus, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::cloud_backup_status(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Back up to the sync server now, returning the recorded outcome
+#[tauri::command]
+pub async fn run_cloud_backup(
+    state: State<'_, Arc<AppState>>,
+) -> Result<CloudBackupStatus, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::run_cloud_backup(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// How far the first-run setup wizard has got
+#[tauri::command]
+pub async fn get_onboarding_state(
+    state: State<'_, Arc<AppState>>,
+) -> Result<OnboardingState, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::onboarding_state(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Mark a setup wizard step done, returning the progress so far
+#[tauri::command]
+pub async fn complete_step(
+    state: State<'_, Arc<AppState>>,
+    step: OnboardingStep,
+) -> Result<OnboardingState, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::complete_onboarding_step(state.inner().clone(), step)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Where the data is kept, and where it is by default
+#[tauri::command]
+pub async fn get_data_location(app: AppHandle) -> Result<DataLocation, String> {
+    Ok(data_location::location(&crate::default_data_dir(&app)?))
+}
+
+/// Move the data, settings and logs to `new_path`, such as a Dropbox or
+/// NAS folder, and keep using them there
+#[tauri::command]
+pub async fn move_data_dir(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    log_writer: State<'_, LogWriter>,
+    new_path: String,
+) -> Result<MovedData, LocalizedError> {
+    let locale = state.locale().await;
+    if app.try_state::<crate::DemoMode>().is_some_and(|demo| demo.0) {
+        return Err(LocalizedError::unexpected("not available in demo mode", locale));
+    }
+    let default_dir =
+        crate::default_data_dir(&app).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let moved = handlers::move_data_dir(state.inner().clone(), &default_dir, Path::new(&new_path))
+        .await
+        .map_err(|e| e.localize(locale))?;
+
+    let logging = &state.config.logging;
+    let log_dir = logging.dir(&moved.data_dir);
+    if let Err(e) = log_writer.open(&log_dir, logging) {
+        tracing::warn!("Could not open log file in {:?}: {}", log_dir, e);
+    }
+    windows::notify_data_changed(&app, None);
+    Ok(moved)
+}
+
+/// Whether this app or the web version holds the edit lock
+#[tauri::command]
+pub async fn get_edit_lock_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<EditLockStatus, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::edit_lock_status(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Take or renew the edit lock; read-only while the web version holds it
+#[tauri::command]
+pub async fn acquire_edit_lock(
+    state: State<'_, Arc<AppState>>,
+) -> Result<EditLockStatus, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::acquire_edit_lock(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Give up the edit lock so the web version can edit
+#[tauri::command]
+pub async fn release_edit_lock(
+    state: State<'_, Arc<AppState>>,
+) -> Result<EditLockStatus, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::release_edit_lock(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
 }
@@ -0,0 +1,50 @@
+//! `school-payment://` deep-link handling.
+//!
+//! Links are parsed by `rust_backend::deep_link`, the main window is brought
+//! to the front, and the route is sent to it as a `DeepLink` event.
+
+use tauri::AppHandle;
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use rust_backend::deep_link;
+
+use crate::tray::show_main_window;
+use crate::windows;
+
+/// Route a deep link to the main window
+pub fn handle_url(app: &AppHandle, url: &str) {
+    let link = match deep_link::parse(url) {
+        Ok(link) => link,
+        Err(e) => {
+            tracing::warn!("Ignoring deep link: {}", e);
+            return;
+        }
+    };
+
+    tracing::info!("Opening deep link {}", url);
+    show_main_window(app);
+    windows::emit(app, link);
+}
+
+/// Register the URL scheme and start listening for links
+pub fn setup(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    // Installed builds register the scheme from the bundle; dev builds on
+    // Windows and Linux need to register at runtime
+    #[cfg(all(debug_assertions, any(windows, target_os = "linux")))]
+    app.deep_link().register_all()?;
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, url.as_str());
+        }
+    });
+
+    // A link that launched the app
+    if let Some(urls) = app.deep_link().get_current()? {
+        for url in urls {
+            handle_url(app, url.as_str());
+        }
+    }
+    Ok(())
+}
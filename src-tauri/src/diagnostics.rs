@@ -0,0 +1,269 @@
+//! Log access, support bundles and the advisor debug console for the
+//! Help → Diagnostics screen.
+
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::broadcast::error::RecvError;
+
+use rust_backend::{
+    call_stats::MethodCallStats,
+    crash::CrashReport,
+    events::AdvisorStderrLine,
+    handlers::{self, AppState, HandlerError},
+    i18n::LocalizedError,
+    log_buffer::{EventQuery, LogEvent},
+    logs::{self, LogLevel, LogWriter},
+    profiling::ProfileStats,
+    recording::RecordingStatus,
+    self_test::SelfTestReport,
+    support::SUPPORT_BUNDLE_NAME,
+};
+
+use crate::windows;
+
+/// Lines returned by `get_recent_logs` when the caller does not say
+const DEFAULT_LINES: usize = 200;
+
+/// Task forwarding advisor stderr to the frontend, while debugging is on
+#[derive(Default)]
+pub struct DebugConsole(std::sync::Mutex<Option<tauri::async_runtime::JoinHandle<()>>>);
+
+/// Report of a crash in the previous session, taken at startup
+pub struct LastCrash(pub Option<CrashReport>);
+
+fn log_dir(writer: &LogWriter) -> Result<std::path::PathBuf, String> {
+    writer
+        .dir()
+        .ok_or_else(|| "Log file is not available".to_string())
+}
+
+/// Get the most recent log lines at or above `level` (default `info`)
+#[tauri::command]
+pub async fn get_recent_logs(
+    writer: State<'_, LogWriter>,
+    lines: Option<usize>,
+    level: Option<String>,
+) -> Result<Vec<String>, String> {
+    let level = match level {
+        Some(name) => {
+            LogLevel::from_name(&name).ok_or_else(|| format!("Unknown log level: {}", name))?
+        }
+        None => LogLevel::Info,
+    };
+    logs::recent_lines(&log_dir(&writer)?, lines.unwrap_or(DEFAULT_LINES), level)
+        .map_err(|e| e.to_string())
+}
+
+/// Recent log events at or above `level`, from `module` and its
+/// submodules, at most `limit` of them; all by default
+#[tauri::command]
+pub async fn get_recent_events(
+    state: State<'_, Arc<AppState>>,
+    level: Option<String>,
+    module: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEvent>, LocalizedError> {
+    let locale = state.locale().await;
+    let query = EventQuery {
+        level,
+        module,
+        limit,
+    };
+    handlers::recent_events(state.inner().clone(), query)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Change the log filter (`RUST_LOG` syntax, such as `trace`) until the
+/// app restarts, returning the filter now in effect
+#[tauri::command]
+pub async fn set_log_level(
+    state: State<'_, Arc<AppState>>,
+    filter: String,
+) -> Result<String, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::set_log_filter(state.inner().clone(), &filter)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Save all log files as a zip archive where the user chooses.
+///
+/// Returns the saved path, or `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn export_logs_zip(
+    app: AppHandle,
+    writer: State<'_, LogWriter>,
+) -> Result<Option<String>, String> {
+    let bytes = logs::zip_logs(&log_dir(&writer)?).map_err(|e| e.to_string())?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("ZIP", &["zip"])
+        .set_file_name("school-payment-logs.zip")
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+    std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+
+    tracing::info!("Exported logs to {:?}", path);
+    Ok(Some(path.display().to_string()))
+}
+
+/// Get the crash report of the previous session, if it crashed
+#[tauri::command]
+pub async fn get_last_crash_report(
+    last_crash: State<'_, LastCrash>,
+) -> Result<Option<CrashReport>, String> {
+    Ok(last_crash.0.clone())
+}
+
+/// Calls, error rate and latency of each advisor method since startup
+#[tauri::command]
+pub async fn get_method_stats(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<MethodCallStats>, String> {
+    Ok(handlers::get_method_stats(state.inner().clone()).await)
+}
+
+/// Phase timings of recent advisor requests, by method
+#[tauri::command]
+pub async fn get_request_stats(state: State<'_, Arc<AppState>>) -> Result<ProfileStats, String> {
+    Ok(handlers::request_stats(state.inner().clone()).await)
+}
+
+/// Switch request latency profiling on or off until the app restarts
+#[tauri::command]
+pub async fn set_profiling(
+    state: State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<ProfileStats, String> {
+    Ok(handlers::set_profiling(state.inner().clone(), enabled).await)
+}
+
+/// Start recording advisor requests and responses for a bug report
+#[tauri::command]
+pub async fn start_recording(state: State<'_, Arc<AppState>>) -> Result<RecordingStatus, String> {
+    Ok(handlers::start_recording(state.inner().clone()).await)
+}
+
+/// Stop recording and save the session, returning the file's path
+#[tauri::command]
+pub async fn stop_recording(state: State<'_, Arc<AppState>>) -> Result<String, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::stop_recording(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Whether a session is being recorded
+#[tauri::command]
+pub async fn get_recording_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<RecordingStatus, String> {
+    Ok(handlers::recording_status(state.inner().clone()).await)
+}
+
+/// Check the advisor and the data directory end to end
+#[tauri::command]
+pub async fn run_self_test(state: State<'_, Arc<AppState>>) -> Result<SelfTestReport, String> {
+    Ok(handlers::run_self_test(state.inner().clone()).await)
+}
+
+/// Save a support bundle where the user chooses.
+///
+/// The zip holds a diagnostics report, the saved data with school names
+/// and free text removed, and the log files, plus the previous session's
+/// crash report with `include_crash_report`. Returns the saved path, or
+/// `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn create_support_bundle(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    writer: State<'_, LogWriter>,
+    last_crash: State<'_, LastCrash>,
+    include_crash_report: Option<bool>,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    let version = app.package_info().version.to_string();
+    let crash = last_crash
+        .0
+        .as_ref()
+        .filter(|_| include_crash_report.unwrap_or(false));
+    let bytes = handlers::create_support_bundle(
+        state.inner().clone(),
+        &version,
+        writer.dir().as_deref(),
+        crash,
+    )
+    .await
+    .map_err(|e| e.localize(locale))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("ZIP", &["zip"])
+        .set_file_name(SUPPORT_BUNDLE_NAME)
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| LocalizedError::unexpected(e, locale))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| LocalizedError::unexpected(e, locale))?;
+    std::fs::write(&path, bytes).map_err(|e| LocalizedError::unexpected(e, locale))?;
+
+    tracing::info!("Saved support bundle to {:?}", path);
+    Ok(Some(path.display().to_string()))
+}
+
+/// Turn the advisor debug console on or off.
+///
+/// Restarts the advisor with `--verbose` so it traces every request and
+/// response on stderr, and forwards each stderr line to the frontend as
+/// an `AdvisorStderrLine` event until turned off again.
+#[tauri::command]
+pub async fn debug_advisor(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    console: State<'_, DebugConsole>,
+    enable: bool,
+) -> Result<(), LocalizedError> {
+    let locale = state.locale().await;
+    if let Some(forwarder) = console.0.lock().ok().and_then(|mut task| task.take()) {
+        forwarder.abort();
+    }
+
+    if enable {
+        let mut lines = state.lean_repl.lock().await.subscribe_stderr();
+        let forwarder = tauri::async_runtime::spawn(async move {
+            loop {
+                match lines.recv().await {
+                    Ok(line) => {
+                        windows::emit(&app, AdvisorStderrLine(line));
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Debug console skipped {} advisor lines", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        if let Ok(mut task) = console.0.lock() {
+            *task = Some(forwarder);
+        }
+    }
+
+    handlers::set_advisor_debug(state.inner().clone(), enable)
+        .await
+        .map_err(|e| HandlerError::from(e).localize(locale))
+}
@@ -0,0 +1,181 @@
+//! Field encryption, audit log and cloud backup key commands, with the keys
+//! kept in the OS keychain so they never reach a cloud-synced data directory.
+//!
+//! The field key is read from the keychain at launch, so encrypted data
+//! opens without a password. With a launch password set, the key leaves
+//! the keychain and the data stays locked until `unlock_with_password`.
+
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+
+use rust_backend::{
+    audit::{AuditEntry, AuditVerification},
+    field_crypto::{FieldCryptoError, FieldEncryptionStatus, FieldKey, KeyStore, SensitiveField},
+    handlers::{self, AppState},
+    i18n::LocalizedError,
+};
+
+use crate::windows;
+
+/// Keychain service name of the keys
+const KEYCHAIN_SERVICE: &str = "school-payment";
+
+/// Key kept in the OS keychain under an account name
+pub struct KeychainKeyStore(&'static str);
+
+impl KeychainKeyStore {
+    /// Key of the encrypted fields
+    pub const FIELD_ENCRYPTION: Self = Self("field-encryption");
+
+    /// Key signing the audit log
+    pub const AUDIT: Self = Self("audit-log");
+
+    /// Key sealing the backups pushed to the sync server
+    pub const CLOUD_BACKUP: Self = Self("cloud-backup");
+
+    fn entry(&self) -> Result<keyring::Entry, FieldCryptoError> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, self.0)
+            .map_err(|e| FieldCryptoError::Keychain(e.to_string()))
+    }
+}
+
+impl KeyStore for KeychainKeyStore {
+    fn load(&self) -> Result<Option<FieldKey>, FieldCryptoError> {
+        match self.entry()?.get_password() {
+            Ok(code) => FieldKey::from_code(&code).map(Some),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(FieldCryptoError::Keychain(e.to_string())),
+        }
+    }
+
+    fn save(&self, key: &FieldKey) -> Result<(), FieldCryptoError> {
+        self.entry()?
+            .set_password(&key.code())
+            .map_err(|e| FieldCryptoError::Keychain(e.to_string()))
+    }
+
+    fn delete(&self) -> Result<(), FieldCryptoError> {
+        match self.entry()?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(FieldCryptoError::Keychain(e.to_string())),
+        }
+    }
+}
+
+/// Which fields are encrypted and whether the key is set up
+#[tauri::command]
+pub async fn get_field_encryption(
+    state: State<'_, Arc<AppState>>,
+) -> Result<FieldEncryptionStatus, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::field_encryption_status(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Encrypt the given fields from now on; none turns encryption off
+#[tauri::command]
+pub async fn set_encrypted_fields(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    fields: Vec<SensitiveField>,
+) -> Result<FieldEncryptionStatus, LocalizedError> {
+    let locale = state.locale().await;
+    let keys = KeychainKeyStore::FIELD_ENCRYPTION;
+    let status = handlers::set_encrypted_fields(state.inner().clone(), &keys, fields)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    windows::notify_data_changed(&app, None);
+    Ok(status)
+}
+
+/// Code of the encryption key, to enter on another device
+#[tauri::command]
+pub async fn export_field_key(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::export_encryption_key(&KeychainKeyStore::FIELD_ENCRYPTION)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Unlock the encrypted fields with the launch password
+#[tauri::command]
+pub async fn unlock_with_password(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    password: String,
+) -> Result<FieldEncryptionStatus, LocalizedError> {
+    let locale = state.locale().await;
+    let status = handlers::unlock_with_password(state.inner().clone(), &password)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    windows::notify_data_changed(&app, None);
+    Ok(status)
+}
+
+/// Ask for `password` on each launch instead of unlocking from the
+/// keychain; none unlocks from the keychain again
+#[tauri::command]
+pub async fn set_launch_password(
+    state: State<'_, Arc<AppState>>,
+    password: Option<String>,
+) -> Result<FieldEncryptionStatus, LocalizedError> {
+    let locale = state.locale().await;
+    let keys = KeychainKeyStore::FIELD_ENCRYPTION;
+    handlers::set_launch_password(state.inner().clone(), &keys, password.as_deref())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Set up the encryption key from another device's code
+#[tauri::command]
+pub async fn import_field_key(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    code: String,
+) -> Result<FieldEncryptionStatus, LocalizedError> {
+    let locale = state.locale().await;
+    let keys = KeychainKeyStore::FIELD_ENCRYPTION;
+    let status = handlers::import_field_key(state.inner().clone(), &keys, &code)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    windows::notify_data_changed(&app, None);
+    Ok(status)
+}
+
+/// Code of the cloud backup key, kept somewhere safe to read the backups
+/// if this computer is lost
+#[tauri::command]
+pub async fn export_cloud_backup_key(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<String>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::export_field_key(&KeychainKeyStore::CLOUD_BACKUP)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Entries of the audit log, oldest first
+#[tauri::command]
+pub async fn list_audit_log(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<AuditEntry>, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::list_audit_log(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Check the audit log for changed, removed or reordered entries
+#[tauri::command]
+pub async fn verify_audit_log(
+    state: State<'_, Arc<AppState>>,
+) -> Result<AuditVerification, LocalizedError> {
+    let locale = state.locale().await;
+    handlers::verify_audit_log(state.inner().clone())
+        .await
+        .map_err(|e| e.localize(locale))
+}
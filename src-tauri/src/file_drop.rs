@@ -0,0 +1,86 @@
+//! Import by dropping JSON or CSV files on a window.
+//!
+//! Each dropped file goes through the same validation as the import dialog.
+//! The result is sent to the window it was dropped on as a [`DroppedFile`]
+//! event; nothing is saved until the frontend calls `confirm_import` with
+//! the preview's token.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tauri::{AppHandle, Manager};
+
+use rust_backend::{
+    events::DroppedFile,
+    handlers::{self, AppState},
+    i18n::{Locale, LocalizedError, Message},
+    import::ImportPreview,
+};
+
+use crate::windows;
+
+/// Files larger than this many megabytes are rejected without being read
+const MAX_FILE_MB: u64 = 10;
+
+async fn preview_file(
+    state: Arc<AppState>,
+    path: &Path,
+    locale: Locale,
+) -> Result<ImportPreview, LocalizedError> {
+    let metadata = std::fs::metadata(path).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    if !metadata.is_file() {
+        let message = Message::new("error.importFolder");
+        return Err(LocalizedError::new(&message, locale, path.display()));
+    }
+    if metadata.len() > MAX_FILE_MB * 1024 * 1024 {
+        let message = Message::new("error.importTooLarge").arg("limit", MAX_FILE_MB);
+        return Err(LocalizedError::new(&message, locale, metadata.len()));
+    }
+    let bytes = std::fs::read(path).map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    handlers::preview_import(state, &file_name, &bytes)
+        .await
+        .map_err(|e| e.localize(locale))
+}
+
+/// Preview each dropped file and report it to the window it was dropped on
+pub fn handle_drop(app: &AppHandle, window_label: &str, paths: Vec<PathBuf>) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let state = state.inner().clone();
+    let app = app.clone();
+    let label = window_label.to_string();
+
+    tauri::async_runtime::spawn(async move {
+        let locale = state.locale().await;
+        for path in paths {
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let payload = match preview_file(state.clone(), &path, locale).await {
+                Ok(preview) => DroppedFile {
+                    file_name,
+                    preview: Some(preview),
+                    error: None,
+                    window: label.clone(),
+                },
+                Err(error) => {
+                    tracing::warn!("Dropped file {:?} rejected: {}", path, error.detail);
+                    DroppedFile {
+                        file_name,
+                        preview: None,
+                        error: Some(error),
+                        window: label.clone(),
+                    }
+                }
+            };
+            windows::emit(&app, payload);
+        }
+    });
+}
@@ -1,14 +1,53 @@
 //! Tauri desktop application for school-payment advisor.
 
+mod autostart;
+mod calendar_sync;
 mod commands;
+mod deep_link;
+mod diagnostics;
+mod field_encryption;
+mod file_drop;
+mod menu;
+mod reveal;
+mod streaming;
+mod tray;
+mod updater;
+mod windows;
 
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use tauri::Manager;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tauri::{DragDropEvent, Manager, RunEvent, WindowEvent};
+use tauri_plugin_notification::NotificationExt;
 
-use rust_backend::{handlers::AppState, LeanRepl};
+use rust_backend::{
+    audit,
+    backup,
+    cloud_backup,
+    config::CONFIG_FILE,
+    countdown,
+    crash::{self, CrashContext},
+    daily_check,
+    data_location,
+    date,
+    demo,
+    email,
+    field_crypto,
+    google_calendar::{self, GoogleClient},
+    handlers::{self, AppState},
+    i18n::Message,
+    log_buffer::LogBuffer,
+    logs::{self, LogLevel, LogWriter},
+    notifier::render_message,
+    reminders,
+    settings::Settings,
+    startup,
+    storage::{portable_data_dir, PORTABLE_ARG},
+    supervisor,
+    webhook::WebhookEvent,
+    AppConfig, LeanRepl, Storage,
+};
 
 /// Get the path to the advisor binary
 fn get_advisor_path(#[allow(unused)] app: &tauri::AppHandle) -> PathBuf {
@@ -35,49 +74,480 @@ fn get_advisor_path(#[allow(unused)] app: &tauri::AppHandle) -> PathBuf {
     }
 }
 
+/// Show an OS notification titled with catalog message `title` in the
+/// language of the settings
+fn show_notification(
+    handle: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    title: &'static str,
+    body: String,
+) {
+    let handle = handle.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        let title = Message::new(title).render(state.locale().await);
+        if let Err(e) = handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+        {
+            tracing::warn!("Failed to show notification: {}", e);
+        }
+    });
+}
+
+/// Whether the app runs in demo mode, decided once at startup
+pub(crate) struct DemoMode(pub bool);
+
+/// Directory for data, settings and logs unless the user moved them.
+///
+/// `data/` beside the executable in portable mode, otherwise the OS
+/// app-data directory.
+pub(crate) fn default_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let portable_arg = std::env::args().any(|arg| arg == PORTABLE_ARG);
+    let portable = std::env::current_exe()
+        .ok()
+        .and_then(|exe| portable_data_dir(&exe, portable_arg));
+    match portable {
+        Some(dir) => Ok(dir),
+        None => app.path().app_data_dir().map_err(|e| e.to_string()),
+    }
+}
+
+/// Directory for data, settings and logs: the one the user moved them to,
+/// if any, otherwise the default. In demo mode, its demo directory instead.
+pub(crate) fn data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = default_data_dir(app)?;
+    match app.try_state::<DemoMode>() {
+        Some(demo_mode) if demo_mode.0 => Ok(demo::data_dir(&dir)),
+        _ => Ok(data_location::resolve(&dir)),
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "school_payment=debug,rust_backend=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    tauri::Builder::default()
+    // Initialize tracing; the log file is opened once the data directory is known
+    let log_writer = LogWriter::default();
+    let log_buffer = LogBuffer::default();
+    let log_level = logs::init_tracing(
+        &["school_payment", "rust_backend"],
+        LogLevel::Debug,
+        log_writer.clone(),
+        log_buffer.clone(),
+    );
+
+    let mut builder = tauri::Builder::default();
+
+    // Must be registered first; forwards deep links from a second launch
+    #[cfg(desktop)]
+    {
+        builder = builder
+            .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+                tray::show_main_window(app);
+            }))
+            .plugin(tauri_plugin_autostart::init(
+                tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+                Some(vec![autostart::AUTOSTART_ARG]),
+            ));
+    }
+
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .menu(menu::build)
+        .on_menu_event(menu::handle_event)
+        .setup(move |app| {
             let advisor_path = get_advisor_path(app.handle());
             tracing::info!("Advisor binary path: {:?}", advisor_path);
 
-            // Initialize Lean REPL
-            let mut lean_repl = LeanRepl::new(advisor_path);
+            // Read before demo mode is decided, so from the real directory
+            let config_path = data_dir(app.handle())?.join(CONFIG_FILE);
+            let config = AppConfig::load(&config_path).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring config file {:?}: {}", config_path, e);
+                AppConfig::default()
+            });
 
-            match lean_repl.start() {
-                Ok(()) => tracing::info!("Lean REPL started successfully"),
-                Err(e) => {
-                    tracing::warn!("Could not start Lean REPL immediately: {}", e);
-                    tracing::info!("Will attempt to start on first request");
+            let demo_arg = std::env::args().any(|arg| arg == demo::DEMO_ARG);
+            let demo_mode = demo::is_enabled(&config, demo_arg);
+            app.manage(DemoMode(demo_mode));
+            let data_dir = data_dir(app.handle())?;
+
+            // Started by the warm-up task below so the window can show progress
+            let lean_repl = if demo_mode {
+                tracing::info!("Demo mode: using sample data and the mock advisor");
+                if let Err(e) = demo::seed(&Storage::new(data_dir.clone()), date::today()) {
+                    tracing::warn!("Could not write demo data: {}", e);
                 }
+                LeanRepl::demo()
+            } else {
+                LeanRepl::new(advisor_path)
+            };
+            tracing::info!("Data directory: {:?}", data_dir);
+
+            let log_dir = config.logging.dir(&data_dir);
+            if let Err(e) = log_writer.open(&log_dir, &config.logging) {
+                tracing::warn!("Could not open log file in {:?}: {}", log_dir, e);
+            }
+            app.manage(log_writer);
+
+            // Record panics for the next launch to offer in a support bundle
+            crash::install_panic_hook(CrashContext {
+                app_version: app.package_info().version.to_string(),
+                data_dir: data_dir.clone(),
+                log_dir,
+                advisor_path: lean_repl.advisor_path().to_path_buf(),
+            });
+            let last_crash = crash::take_last_crash(&data_dir);
+            if let Some(report) = &last_crash {
+                tracing::warn!("Previous session crashed: {}", report.message);
             }
+            app.manage(diagnostics::LastCrash(last_crash));
+            app.manage(diagnostics::DebugConsole::default());
+
+            // A leftover marker means the last run crashed or was killed
+            let unclean = backup::mark_running(&Storage::new(data_dir.clone()))
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Could not write running marker: {}", e);
+                    false
+                });
+            if unclean {
+                tracing::warn!("Previous session did not shut down cleanly");
+            }
+            app.manage(commands::PreviousSession { unclean });
+
+            let storage = Storage::new(data_dir);
+            // Before anything is loaded, so encrypted fields can be read
+            if !demo_mode {
+                let keys = field_encryption::KeychainKeyStore::FIELD_ENCRYPTION;
+                if let Err(e) = field_crypto::unlock_with_keychain(&storage, &keys) {
+                    tracing::warn!("Could not read the encryption key: {}", e);
+                }
+            }
+            let settings = Settings::load(&storage, Settings::from_config(&config))
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Ignoring saved settings: {}", e);
+                    Settings::from_config(&config)
+                });
+
+            autostart::setup(app.handle(), &settings);
 
             // Create shared state
-            let state = Arc::new(AppState::new(lean_repl));
+            let mut state = AppState::new(lean_repl)
+                .with_config(config)
+                .with_settings(settings)
+                .with_log_level(log_level)
+                .with_log_buffer(log_buffer)
+                .with_storage(storage.clone())
+                .with_edit_lock("desktop");
+            if !demo_mode {
+                match audit::load_or_create_key(&field_encryption::KeychainKeyStore::AUDIT) {
+                    Ok(key) => state = state.with_audit_key(key),
+                    Err(e) => tracing::warn!("Audit log disabled, no signing key: {}", e),
+                }
+                let keys = field_encryption::KeychainKeyStore::CLOUD_BACKUP;
+                match audit::load_or_create_key(&keys) {
+                    Ok(key) => state = state.with_backup_key(key),
+                    Err(e) => tracing::warn!("Cloud backup disabled, no key: {}", e),
+                }
+            }
+            let state = Arc::new(state);
+
+            // Start the advisor, migrate data written by an older release and
+            // check the advisor can read it, reporting each stage to the splash.
+            // Eager startup holds the window back until the advisor is ready.
+            app.manage(commands::StartupStatus::default());
+            let handle = app.handle().clone();
+            tauri::async_runtime::block_on(startup::start(state.clone(), move |progress| {
+                if let Some(status) = handle.try_state::<commands::StartupStatus>() {
+                    status.set(progress.clone());
+                }
+                windows::emit(&handle, progress.clone());
+            }));
+
+            // Daily email digest (no-op unless [email] is configured)
+            tauri::async_runtime::spawn(email::run_daily_digest(
+                state.clone(),
+                Some(storage.clone()),
+            ));
+
+            // Two-way Google Calendar sync of the datasets it is turned on for
+            // (no-op unless [google_calendar] is configured)
+            let sync_config = state
+                .config
+                .google_calendar
+                .clone()
+                .filter(|c| c.sync_interval_minutes > 0 && !demo_mode);
+            if let Some(config) = sync_config {
+                let interval = Duration::from_secs(config.sync_interval_minutes * 60);
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(google_calendar::run_periodic_sync(
+                    GoogleClient::new(config),
+                    Arc::new(calendar_sync::KeychainTokenStore),
+                    storage.clone(),
+                    interval,
+                    move |_| windows::notify_data_changed(&handle, None),
+                ));
+            }
+
+            // OS notifications for upcoming deadlines, independent of the window
+            if state.config.reminders.desktop_notifications {
+                let handle = app.handle().clone();
+                let notified = state.clone();
+                tauri::async_runtime::spawn(reminders::run_deadline_scheduler(
+                    state.clone(),
+                    move |deadline| {
+                        let body =
+                            render_message(&WebhookEvent::DeadlineApproaching(deadline.clone()));
+                        let title = "notification.deadlineApproaching";
+                        show_notification(&handle, &notified, title, body);
+                        windows::emit(&handle, deadline.clone());
+                    },
+                ));
+            }
+
+            // Once-a-day advisor check, kept running in the tray. Deadlines
+            // entering the window are already announced by the scheduler
+            // above, so only changed recommendations and budget alerts are
+            // shown here.
+            if state.config.reminders.desktop_notifications {
+                let handle = app.handle().clone();
+                let notified = state.clone();
+                tauri::async_runtime::spawn(daily_check::run_daily_check(
+                    state.clone(),
+                    move |event| {
+                        let title = match event {
+                            WebhookEvent::RecommendationChanged { .. } => {
+                                "notification.recommendationChanged"
+                            }
+                            WebhookEvent::BudgetExceeded(_) => "notification.budgetExceeded",
+                            _ => return,
+                        };
+                        show_notification(&handle, &notified, title, render_message(event));
+                    },
+                ));
+            }
+
+            // Scheduled backups to the sync server, alerting on the desktop
+            // as well as through the notifiers when they keep failing
+            if !demo_mode {
+                let handle = app.handle().clone();
+                let notified = state.clone();
+                let desktop = state.config.reminders.desktop_notifications;
+                tauri::async_runtime::spawn(cloud_backup::run_scheduler(
+                    state.clone(),
+                    move |event| {
+                        if desktop {
+                            let title = "notification.backupFailing";
+                            show_notification(&handle, &notified, title, render_message(event));
+                        }
+                    },
+                ));
+            }
+
+            // Watchdog, with status changes forwarded to the frontend
+            tauri::async_runtime::spawn(supervisor::run_watchdog(
+                state.clone(),
+                supervisor::WATCHDOG_INTERVAL,
+            ));
+            let mut status = state.subscribe_advisor_status();
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while status.changed().await.is_ok() {
+                    let current = status.borrow_and_update().clone();
+                    windows::emit(&handle, current);
+                }
+            });
+
+            // Deadline countdown for the badges, kept current across midnight
+            tauri::async_runtime::spawn(countdown::run_countdown(
+                state.clone(),
+                countdown::COUNTDOWN_INTERVAL,
+            ));
+            let mut countdown = state.subscribe_countdown();
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while countdown.changed().await.is_ok() {
+                    let current = countdown.borrow_and_update().clone();
+                    windows::emit(&handle, current);
+                }
+            });
+
             app.manage(state);
 
+            tray::create(app.handle())?;
+            deep_link::setup(app.handle())?;
+
             Ok(())
         })
+        .on_window_event(|window, event| match event {
+            WindowEvent::CloseRequested { api, .. } => {
+                // Read on each close, so a changed setting applies at once
+                let close_to_tray = window.try_state::<Arc<AppState>>().is_some_and(|state| {
+                    tauri::async_runtime::block_on(state.settings.lock()).close_to_tray
+                });
+                if close_to_tray {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+            WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => {
+                file_drop::handle_drop(window.app_handle(), window.label(), paths.clone());
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             commands::send_rpc,
+            commands::send_rpc_batch,
+            commands::get_cached_result,
+            streaming::stream_rpc,
+            streaming::stream_history,
             commands::health_check,
+            commands::get_advisor_status,
+            commands::get_health_history,
+            commands::get_startup_progress,
+            diagnostics::get_recent_logs,
+            diagnostics::set_log_level,
+            diagnostics::get_recent_events,
+            diagnostics::export_logs_zip,
+            diagnostics::run_self_test,
+            diagnostics::get_request_stats,
+            diagnostics::get_method_stats,
+            diagnostics::set_profiling,
+            diagnostics::start_recording,
+            diagnostics::stop_recording,
+            diagnostics::get_recording_status,
+            diagnostics::create_support_bundle,
+            diagnostics::get_last_crash_report,
+            diagnostics::debug_advisor,
             commands::restart_repl,
             commands::save_data,
+            commands::patch_data,
+            commands::bulk_update,
             commands::load_data,
+            commands::get_data_as_of,
+            commands::list_datasets,
+            commands::generate_sample_data,
+            commands::get_settings,
+            commands::set_settings,
+            autostart::set_autostart,
+            commands::export_data_dialog,
+            commands::export_pdf,
+            commands::list_export_templates,
+            commands::export_with_template,
+            commands::export_zengin,
+            commands::copy_schedule_to_clipboard,
+            commands::import_data_dialog,
+            commands::import_legacy_dialog,
+            commands::import_from_url,
+            commands::list_column_mappings,
+            commands::save_column_mapping,
+            commands::detect_column_mapping,
+            commands::delete_column_mapping,
+            commands::confirm_import,
+            commands::start_transfer,
+            commands::receive_transfer,
+            commands::register_os_reminders,
+            commands::unregister_os_reminders,
+            commands::get_deadline_countdown,
+            commands::get_summary,
+            commands::explain_totals,
+            commands::simulate,
+            commands::analyze_schedule,
+            commands::get_timeline,
+            commands::get_explanation,
+            commands::list_templates,
+            commands::apply_template,
+            commands::import_templates_dialog,
+            commands::list_holidays,
+            commands::import_holidays_dialog,
+            commands::list_history,
+            commands::decide_recommendation,
+            commands::get_recommendation_inputs,
+            commands::get_history_stats,
+            commands::export_history,
+            commands::plan_installments,
+            commands::expand_recurring_fees,
+            commands::refresh_recurring_payments,
+            commands::mark_paid,
+            commands::add_note,
+            commands::delete_note,
+            commands::search,
+            commands::record_outcome,
+            commands::get_outstanding_payments,
+            commands::get_tax_report,
+            commands::export_tax_report,
+            commands::get_exchange_rates,
+            commands::set_exchange_rate,
+            commands::refresh_exchange_rates,
+            updater::check_for_update,
+            updater::install_update,
+            commands::get_restore_offer,
+            commands::restore_backup,
+            commands::get_cloud_backup_status,
+            commands::run_cloud_backup,
+            commands::get_onboarding_state,
+            commands::complete_step,
+            commands::get_edit_lock_status,
+            commands::acquire_edit_lock,
+            commands::release_edit_lock,
+            commands::get_data_location,
+            commands::move_data_dir,
+            reveal::open_data_dir,
+            reveal::reveal_backup,
+            windows::open_calendar_window,
+            windows::open_school_window,
+            windows::emit_to_window,
+            calendar_sync::get_google_calendar_status,
+            calendar_sync::start_google_calendar_auth,
+            calendar_sync::finish_google_calendar_auth,
+            calendar_sync::disconnect_google_calendar,
+            calendar_sync::set_google_calendar_sync,
+            calendar_sync::sync_google_calendar,
+            field_encryption::get_field_encryption,
+            field_encryption::set_encrypted_fields,
+            field_encryption::export_field_key,
+            field_encryption::export_cloud_backup_key,
+            field_encryption::import_field_key,
+            field_encryption::unlock_with_password,
+            field_encryption::set_launch_password,
+            field_encryption::list_audit_log,
+            field_encryption::verify_audit_log,
         ])
-        .run(tauri::generate_context!())
-        .expect("Error while running Tauri application");
+        .build(tauri::generate_context!())
+        .expect("Error while building Tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                write_exit_backup(app);
+            }
+        });
+}
+
+/// Back up the saved data, release the edit lock and clear the running
+/// marker on clean exit
+fn write_exit_backup(app: &tauri::AppHandle) {
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let release = handlers::release_edit_lock(state.inner().clone());
+        if let Err(e) = tauri::async_runtime::block_on(release) {
+            tracing::warn!("Could not release the edit lock: {}", e);
+        }
+    }
+    let Ok(data_dir) = data_dir(app) else {
+        return;
+    };
+    let storage = Storage::new(data_dir);
+    match backup::write_backup(&storage, backup::EXIT_BACKUP) {
+        Ok(Some(info)) => tracing::info!("Wrote exit backup {}", info.file_name),
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to write exit backup: {}", e),
+    }
+    if let Err(e) = backup::clear_running(&storage) {
+        tracing::warn!("Could not clear running marker: {}", e);
+    }
 }
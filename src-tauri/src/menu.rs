@@ -0,0 +1,125 @@
+//! Native application menu with keyboard shortcuts.
+//!
+//! Actions that need no UI (backup, opening the calendar window,
+//! diagnostics) run directly in Rust. The rest are forwarded to the main
+//! window as a [`MenuAction`] event with the action name, as are the
+//! results of the direct actions.
+
+use std::sync::Arc;
+
+use tauri::{
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    AppHandle, Manager, Wry,
+};
+
+use rust_backend::{
+    backup,
+    events::MenuAction,
+    handlers::{self, AppState},
+};
+
+use crate::windows;
+
+fn item(
+    app: &AppHandle,
+    id: &str,
+    text: &str,
+    accelerator: Option<&str>,
+) -> tauri::Result<MenuItem<Wry>> {
+    MenuItem::with_id(app, id, text, true, accelerator)
+}
+
+/// Build the application menu
+pub fn build(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let file = Submenu::with_items(
+        app,
+        "ファイル",
+        true,
+        &[
+            &item(app, "import", "インポート…", Some("CmdOrCtrl+O"))?,
+            &item(app, "export", "エクスポート…", Some("CmdOrCtrl+E"))?,
+            &item(app, "backup", "今すぐバックアップ", Some("CmdOrCtrl+B"))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::quit(app, Some("終了"))?,
+        ],
+    )?;
+
+    let edit = Submenu::with_items(
+        app,
+        "編集",
+        true,
+        &[
+            &item(app, "undo", "元に戻す", Some("CmdOrCtrl+Z"))?,
+            &item(app, "redo", "やり直す", Some("CmdOrCtrl+Shift+Z"))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::cut(app, Some("切り取り"))?,
+            &PredefinedMenuItem::copy(app, Some("コピー"))?,
+            &PredefinedMenuItem::paste(app, Some("貼り付け"))?,
+            &PredefinedMenuItem::select_all(app, Some("すべて選択"))?,
+        ],
+    )?;
+
+    let view = Submenu::with_items(
+        app,
+        "表示",
+        true,
+        &[
+            &item(app, "calendar", "支払いカレンダー", Some("CmdOrCtrl+1"))?,
+            &item(app, "weekly", "週間推奨", Some("CmdOrCtrl+2"))?,
+        ],
+    )?;
+
+    let help = Submenu::with_items(
+        app,
+        "ヘルプ",
+        true,
+        &[&item(app, "diagnostics", "診断情報", None)?],
+    )?;
+
+    Menu::with_items(app, &[&file, &edit, &view, &help])
+}
+
+fn emit(app: &AppHandle, action: &str, result: Option<serde_json::Value>) {
+    let payload = MenuAction {
+        action: action.to_string(),
+        result,
+    };
+    windows::emit(app, payload);
+}
+
+/// Handle a menu selection
+pub fn handle_event(app: &AppHandle, event: MenuEvent) {
+    let action = event.id.as_ref();
+    match action {
+        "import" | "export" | "undo" | "redo" | "weekly" => emit(app, action, None),
+        "backup" => {
+            let state = app.state::<Arc<AppState>>().inner().clone();
+            let result = match state.storage.as_ref() {
+                Some(storage) => backup::write_backup(storage, backup::MANUAL_BACKUP)
+                    .map(|info| serde_json::json!(info))
+                    .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() })),
+                None => serde_json::json!({ "error": "Data storage is not configured" }),
+            };
+            emit(app, action, Some(result));
+        }
+        "calendar" => {
+            if let Err(e) = windows::open_calendar(app) {
+                tracing::error!("Failed to open calendar window: {}", e);
+            }
+        }
+        "diagnostics" => {
+            let app = app.clone();
+            let state = app.state::<Arc<AppState>>().inner().clone();
+            tauri::async_runtime::spawn(async move {
+                let health = handlers::health_check(state.clone()).await;
+                let result = serde_json::json!({
+                    "health": health,
+                    "advisor": state.advisor_status(),
+                    "version": app.package_info().version.to_string(),
+                });
+                emit(&app, "diagnostics", Some(result));
+            });
+        }
+        _ => {}
+    }
+}
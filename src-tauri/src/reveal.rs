@@ -0,0 +1,83 @@
+//! Show the data directory and backup files in the system file manager.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+use tauri_plugin_shell::ShellExt;
+
+use rust_backend::{
+    backup,
+    handlers::{AppState, HandlerError},
+    storage::Storage,
+};
+
+fn storage(state: &AppState) -> Result<&Storage, String> {
+    state
+        .storage
+        .as_ref()
+        .ok_or_else(|| HandlerError::NoStorage.to_string())
+}
+
+/// Open a directory in Explorer/Finder/the desktop's file manager
+fn open_dir(app: &AppHandle, dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    #[allow(deprecated)]
+    app.shell()
+        .open(dir.display().to_string(), None)
+        .map_err(|e| e.to_string())
+}
+
+/// Open the file manager with `path` selected.
+///
+/// Linux file managers have no common "select" flag, so the containing
+/// directory is opened instead.
+fn select_in_file_manager(app: &AppHandle, path: &Path) -> Result<(), String> {
+    if cfg!(target_os = "windows") {
+        let mut arg = std::ffi::OsString::from("/select,");
+        arg.push(path);
+        Command::new("explorer")
+            .arg(arg)
+            .spawn()
+            .map(drop)
+            .map_err(|e| e.to_string())
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+            .arg("-R")
+            .arg(path)
+            .spawn()
+            .map(drop)
+            .map_err(|e| e.to_string())
+    } else {
+        let dir = path.parent().unwrap_or(path);
+        open_dir(app, dir)
+    }
+}
+
+/// Open the app data directory in the file manager
+#[tauri::command]
+pub async fn open_data_dir(app: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    open_dir(&app, &storage(&state)?.data_dir())
+}
+
+/// Show a backup file, by the file name from `list_backups`, in the file manager
+#[tauri::command]
+pub async fn reveal_backup(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<(), String> {
+    let storage = storage(&state)?;
+    // Only bare backup file names are accepted, never arbitrary paths
+    let file_name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let backup_path = backup::backup_path(storage, &file_name)
+        .ok_or_else(|| HandlerError::UnknownBackup(path.clone()).to_string())?;
+    if Path::new(&path) != Path::new(&file_name) && Path::new(&path) != backup_path {
+        return Err(HandlerError::UnknownBackup(path).to_string());
+    }
+    select_in_file_manager(&app, &backup_path)
+}
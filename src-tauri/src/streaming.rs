@@ -0,0 +1,74 @@
+//! Large results sent to the frontend in chunks.
+//!
+//! The commands here return as soon as every chunk has been emitted as a
+//! [`StreamedChunk`] event, tagged with the stream id the frontend chose,
+//! so it can draw the first rows before the rest have arrived.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+
+use rust_backend::{
+    events::StreamedChunk,
+    handlers::{self, AppState},
+    i18n::{Locale, LocalizedError},
+    json_rpc::JsonRpcRequest,
+    query::ListQuery,
+    streaming::{Chunks, DEFAULT_CHUNK_SIZE},
+};
+
+use crate::windows;
+
+/// Emit each chunk in order, returning how many were emitted
+fn emit_chunks(
+    app: &AppHandle,
+    stream_id: &str,
+    chunks: Chunks,
+    locale: Locale,
+) -> Result<u32, LocalizedError> {
+    let mut emitted = 0;
+    for chunk in chunks {
+        if !windows::emit(app, StreamedChunk { stream_id, chunk }) {
+            return Err(LocalizedError::unexpected("the chunk was not delivered", locale));
+        }
+        emitted += 1;
+    }
+    Ok(emitted)
+}
+
+/// Send an RPC request and emit its result in chunks
+#[tauri::command]
+pub async fn stream_rpc(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    request: JsonRpcRequest,
+    stream_id: String,
+    chunk_size: Option<usize>,
+) -> Result<u32, LocalizedError> {
+    let locale = state.locale().await;
+    let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    let chunks = handlers::stream_rpc(state.inner().clone(), request, chunk_size)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    emit_chunks(&app, &stream_id, chunks, locale)
+}
+
+/// Emit every recorded recommendation matching `params` in chunks
+#[tauri::command]
+pub async fn stream_history(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    stream_id: String,
+    params: Option<HashMap<String, String>>,
+    chunk_size: Option<usize>,
+) -> Result<u32, LocalizedError> {
+    let locale = state.locale().await;
+    let query = ListQuery::from_params(&params.unwrap_or_default())
+        .map_err(|e| LocalizedError::unexpected(e, locale))?;
+    let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    let chunks = handlers::stream_history(state.inner().clone(), &query, chunk_size)
+        .await
+        .map_err(|e| e.localize(locale))?;
+    emit_chunks(&app, &stream_id, chunks, locale)
+}
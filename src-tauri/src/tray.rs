@@ -0,0 +1,131 @@
+//! System tray icon with advisor status and quick actions.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tauri::{
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    tray::TrayIconBuilder,
+    AppHandle, Manager, Wry,
+};
+
+use rust_backend::{
+    date,
+    events::RecomputeRequested,
+    format::{format_day, format_yen},
+    handlers::{self, AppState},
+    reminders,
+};
+
+use crate::windows;
+
+/// Tray icon identifier
+pub const TRAY_ID: &str = "main";
+
+/// How often the status lines are refreshed
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Show and focus the main window
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Create the tray icon and start refreshing its status lines
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, "status", "アドバイザー: 確認中…", false, None::<&str>)?;
+    let next_deadline =
+        MenuItem::with_id(app, "next-deadline", "次の期限: なし", false, None::<&str>)?;
+    let open = MenuItem::with_id(app, "open", "開く", true, None::<&str>)?;
+    let recompute = MenuItem::with_id(app, "recompute", "推奨を再計算", true, None::<&str>)?;
+    let restart = MenuItem::with_id(app, "restart", "アドバイザーを再起動", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "終了", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &next_deadline,
+            &PredefinedMenuItem::separator(app)?,
+            &open,
+            &recompute,
+            &restart,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip("志望校支払いアドバイザー")
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    tauri::async_runtime::spawn(refresh_status(app.clone(), status, next_deadline));
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "open" => show_main_window(app),
+        "recompute" => {
+            show_main_window(app);
+            windows::emit(app, RecomputeRequested);
+        }
+        "restart" => {
+            let state = app.state::<Arc<AppState>>().inner().clone();
+            tauri::async_runtime::spawn(async move {
+                match handlers::restart_repl(state).await {
+                    Ok(()) => tracing::info!("Advisor restarted from tray"),
+                    Err(e) => tracing::error!("Failed to restart advisor: {}", e),
+                }
+            });
+        }
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+/// Keep the status and next-deadline lines and the tooltip up to date
+async fn refresh_status(app: AppHandle, status: MenuItem<Wry>, next_deadline: MenuItem<Wry>) {
+    loop {
+        let state = app.state::<Arc<AppState>>().inner().clone();
+        let health = handlers::health_check(state.clone()).await;
+        let status_text = if health.lean_repl == "running" {
+            "アドバイザー: 稼働中"
+        } else {
+            "アドバイザー: 停止中"
+        };
+
+        let deadline_text = state
+            .load_dataset()
+            .ok()
+            .and_then(|data| reminders::next_deadline(&data, date::today()))
+            .map(|d| {
+                let kind = d.fee_name();
+                format!(
+                    "次の期限: {} {} {} {}（あと{}日）",
+                    format_day(d.deadline),
+                    d.school_name,
+                    kind,
+                    format_yen(d.amount),
+                    d.days_left
+                )
+            })
+            .unwrap_or_else(|| "次の期限: なし".to_string());
+
+        let _ = status.set_text(status_text);
+        let _ = next_deadline.set_text(&deadline_text);
+        if let Some(tray) = app.tray_by_id(TRAY_ID) {
+            let _ = tray.set_tooltip(Some(format!("{}\n{}", status_text, deadline_text)));
+        }
+
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
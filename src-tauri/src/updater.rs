@@ -0,0 +1,106 @@
+//! Application updates via tauri-plugin-updater.
+//!
+//! Releases advertise the data schema version their bundled advisor
+//! supports as `advisorSchemaVersion` in the update manifest. An update is
+//! only installed if that advisor can handle the stored data; the data is
+//! migrated on the first launch after the update.
+//!
+//! Updates stay off until the updater's `pubkey` in `tauri.conf.json` holds
+//! the public key releases are signed with: without it no update could be
+//! verified. Turn `createUpdaterArtifacts` back on together with the key.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use rust_backend::{
+    handlers::AppState,
+    migrations::{self, CURRENT_SCHEMA_VERSION},
+};
+
+/// An available update, as shown to the user
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub notes: Option<String>,
+    /// Whether the release's advisor can work with the stored data
+    pub compatible: bool,
+    /// Why the update cannot be installed, if it is not compatible
+    pub reason: Option<String>,
+}
+
+/// Check a release's advisor against the stored data schema
+fn check_compat(state: &AppState, update: &Update) -> Result<(), String> {
+    let advisor_schema = update
+        .raw_json
+        .get("advisorSchemaVersion")
+        .and_then(|v| v.as_u64())
+        .map_or(CURRENT_SCHEMA_VERSION, |v| v as u32);
+    let data_schema = state.load_dataset().map_or(CURRENT_SCHEMA_VERSION, |data| {
+        migrations::schema_version(&data)
+    });
+
+    migrations::check_advisor(advisor_schema, data_schema).map_err(|e| e.to_string())
+}
+
+/// Whether a key to verify releases with is configured
+fn is_configured(app: &AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str())
+        .is_some_and(|key| !key.trim().is_empty())
+}
+
+async fn fetch_update(app: &AppHandle) -> Result<Option<Update>, String> {
+    if !is_configured(app) {
+        tracing::debug!("Updates are off: no updater public key is configured");
+        return Ok(None);
+    }
+    app.updater()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Check for a newer release
+#[tauri::command]
+pub async fn check_for_update(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<UpdateInfo>, String> {
+    let Some(update) = fetch_update(&app).await? else {
+        return Ok(None);
+    };
+    let compat = check_compat(&state, &update);
+
+    Ok(Some(UpdateInfo {
+        version: update.version.clone(),
+        notes: update.body.clone(),
+        compatible: compat.is_ok(),
+        reason: compat.err(),
+    }))
+}
+
+/// Download and install the latest release, then restart
+#[tauri::command]
+pub async fn install_update(app: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let Some(update) = fetch_update(&app).await? else {
+        return Err("No update is available".to_string());
+    };
+    check_compat(&state, &update)?;
+
+    tracing::info!("Installing update {}", update.version);
+    update
+        .download_and_install(|_, _| {}, || {})
+        .await
+        .map_err(|e| e.to_string())?;
+
+    app.restart();
+}
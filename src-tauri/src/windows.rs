@@ -0,0 +1,122 @@
+//! Secondary windows and cross-window events.
+//!
+//! All windows share the managed `AppState`. The frontend picks the view to
+//! render from the `view` query parameter. Every event sent to the frontend
+//! goes through [`emit`]; the events and their payloads are defined in
+//! `rust_backend::events`. When data changes, every window receives
+//! [`DataChanged`] with the label of the window that caused it, so the
+//! sender can skip reloading.
+
+use std::sync::Arc;
+
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, Window};
+
+use rust_backend::countdown;
+use rust_backend::events::{DataChanged, Event};
+use rust_backend::handlers::AppState;
+
+/// Label of the detached payment calendar window
+pub const CALENDAR_WINDOW: &str = "calendar";
+
+/// Send `event` to its window, or to every window, returning whether it was
+/// delivered. Failures are logged.
+pub fn emit<E: Event>(app: &AppHandle, event: E) -> bool {
+    let sent = match event.target() {
+        Some(label) => app.emit_to(label, E::NAME, &event),
+        None => app.emit(E::NAME, &event),
+    };
+    if let Err(e) = &sent {
+        tracing::warn!("Failed to emit {}: {}", E::NAME, e);
+    }
+    sent.is_ok()
+}
+
+/// Tell every window that the saved data changed, and bring the deadline
+/// countdown up to date without waiting for its timer
+pub fn notify_data_changed(app: &AppHandle, source: Option<&Window>) {
+    emit(
+        app,
+        DataChanged {
+            source: source.map(|w| w.label().to_string()),
+        },
+    );
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        if let Err(e) = countdown::refresh(&state) {
+            tracing::warn!("Failed to refresh the deadline countdown: {}", e);
+        }
+    }
+}
+
+/// Focus the window with `label`, or create it showing `url`
+fn open_or_focus(
+    app: &AppHandle,
+    label: &str,
+    url: String,
+    title: &str,
+    size: (f64, f64),
+) -> Result<String, String> {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.unminimize();
+        window.set_focus().map_err(|e| e.to_string())?;
+        return Ok(label.to_string());
+    }
+
+    WebviewWindowBuilder::new(app, label, WebviewUrl::App(url.into()))
+        .title(title)
+        .inner_size(size.0, size.1)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(label.to_string())
+}
+
+/// Open or focus the detached payment calendar window
+pub fn open_calendar(app: &AppHandle) -> Result<String, String> {
+    open_or_focus(
+        app,
+        CALENDAR_WINDOW,
+        "index.html?view=calendar".to_string(),
+        "支払いカレンダー",
+        (900.0, 700.0),
+    )
+}
+
+/// Open the detached payment calendar window and return its label
+#[tauri::command]
+pub async fn open_calendar_window(app: AppHandle) -> Result<String, String> {
+    open_calendar(&app)
+}
+
+/// Open a detail window for one school and return its label
+#[tauri::command]
+pub async fn open_school_window(
+    app: AppHandle,
+    school_id: u64,
+    school_name: Option<String>,
+) -> Result<String, String> {
+    let title = match school_name {
+        Some(name) => format!("{} - 詳細", name),
+        None => "学校の詳細".to_string(),
+    };
+    open_or_focus(
+        &app,
+        &format!("school-{}", school_id),
+        format!("index.html?view=school&id={}", school_id),
+        &title,
+        (600.0, 700.0),
+    )
+}
+
+/// Send an event to a single window by label
+#[tauri::command]
+pub async fn emit_to_window(
+    app: AppHandle,
+    label: String,
+    event: String,
+    payload: serde_json::Value,
+) -> Result<(), String> {
+    if app.get_webview_window(&label).is_none() {
+        return Err(format!("No window labelled '{}'", label));
+    }
+    app.emit_to(label.as_str(), &event, payload)
+        .map_err(|e| e.to_string())
+}
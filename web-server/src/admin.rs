@@ -0,0 +1,80 @@
+//! Access control for the `/admin` routes.
+//!
+//! These routes change the log filter and record advisor requests, so they
+//! must not be open to anyone who can reach the server. With `admin_token`
+//! set in `[web]`, a request needs `Authorization: Bearer <token>`;
+//! without it, only requests from this machine are answered.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use rust_backend::token::constant_time_eq;
+
+/// Whether a request from `peer` with `headers` may use the admin routes
+fn is_authorized(token: Option<&str>, headers: &HeaderMap, peer: Option<SocketAddr>) -> bool {
+    match token {
+        Some(token) => headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|submitted| constant_time_eq(submitted.trim(), token)),
+        None => peer.is_some_and(|peer| peer.ip().is_loopback()),
+    }
+}
+
+/// Reject admin requests without the token, or from other machines
+pub async fn authorize(
+    State(token): State<Arc<Option<String>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    if is_authorized(token.as_deref(), request.headers(), peer) {
+        return next.run(request).await;
+    }
+    tracing::warn!(
+        "Rejected {} {} from {}",
+        request.method(),
+        request.uri().path(),
+        peer.map_or_else(|| "unknown peer".to_string(), |p| p.to_string())
+    );
+    let message = if token.is_some() {
+        "Missing or invalid admin token"
+    } else {
+        "Admin routes are only available from this machine"
+    };
+    (StatusCode::FORBIDDEN, message).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_or_loopback() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.20:50000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+
+        assert!(is_authorized(None, &headers, Some(local)));
+        assert!(!is_authorized(None, &headers, Some(remote)));
+        assert!(!is_authorized(None, &headers, None));
+
+        // With a token, where the request comes from does not matter
+        assert!(!is_authorized(Some("secret"), &headers, Some(local)));
+        headers.insert(header::AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert!(!is_authorized(Some("secret"), &headers, Some(local)));
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        assert!(is_authorized(Some("secret"), &headers, Some(remote)));
+    }
+}
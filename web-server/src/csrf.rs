@@ -0,0 +1,74 @@
+//! Double-submit CSRF protection for cookie-carrying requests.
+//!
+//! `GET /csrf-token` sets a random token in the `csrf_token` cookie and
+//! returns it in the body. Mutating requests that carry any cookie must echo
+//! the cookie value in the `X-CSRF-Token` header. A cross-site form post
+//! cannot read the cookie, so it cannot supply a matching header. Requests
+//! without cookies (scripts, the desktop app) are not affected.
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use rust_backend::token::{constant_time_eq, random_token};
+
+/// Cookie holding the CSRF token
+pub const CSRF_COOKIE: &str = "csrf_token";
+
+/// Header the client must echo the token in
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Find a cookie value in the request headers
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Issue a fresh CSRF token as both a cookie and a JSON body
+pub async fn issue_token() -> Response {
+    let token = random_token(32);
+    let cookie = format!("{}={}; Path=/; SameSite=Strict", CSRF_COOKIE, token);
+    (
+        [(header::SET_COOKIE, cookie)],
+        Json(serde_json::json!({ "token": token })),
+    )
+        .into_response()
+}
+
+/// Reject mutating cookie-carrying requests without a matching token
+pub async fn verify(request: Request, next: Next) -> Response {
+    let safe = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let headers = request.headers();
+    if safe || !headers.contains_key(header::COOKIE) {
+        return next.run(request).await;
+    }
+
+    let cookie = cookie_value(headers, CSRF_COOKIE);
+    let submitted = headers.get(CSRF_HEADER).and_then(|v| v.to_str().ok());
+    match (cookie, submitted) {
+        (Some(cookie), Some(submitted)) if constant_time_eq(cookie, submitted) => {
+            next.run(request).await
+        }
+        _ => {
+            tracing::warn!(
+                "Rejected {} {} without a valid CSRF token",
+                request.method(),
+                request.uri().path()
+            );
+            (StatusCode::FORBIDDEN, "Missing or invalid CSRF token").into_response()
+        }
+    }
+}
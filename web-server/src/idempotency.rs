@@ -0,0 +1,252 @@
+//! `Idempotency-Key` handling for mutating requests.
+//!
+//! A request carrying the header runs once; retries with the same key get
+//! the first response back with `Idempotent-Replayed: true`. See
+//! [`rust_backend::idempotency`] for what is kept and for how long.
+//!
+//! Streamed responses, such as those of `/rpc/stream`, and responses over
+//! [`MAX_KEPT_RESPONSE`] are sent on without being kept, so a retry of
+//! one runs again.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use rust_backend::idempotency::{
+    self, Claim, IdempotencyStore, StoredResponse, IDEMPOTENCY_HEADER, REPLAYED_HEADER,
+};
+
+/// Largest request body buffered to identify a retry
+const MAX_REQUEST_BODY: usize = 16 * 1024 * 1024;
+
+/// Largest response kept for retries
+const MAX_KEPT_RESPONSE: u64 = 1024 * 1024;
+
+/// A claimed key, released unless a response was kept for it: after a
+/// server error, or when the client goes away before the handler is done
+struct ClaimGuard {
+    store: Arc<IdempotencyStore>,
+    key: String,
+    kept: bool,
+}
+
+impl ClaimGuard {
+    fn complete(mut self, response: StoredResponse) {
+        self.store.complete(&self.key, response);
+        self.kept = true;
+    }
+}
+
+impl Drop for ClaimGuard {
+    fn drop(&mut self) {
+        if !self.kept {
+            self.store.release(&self.key);
+        }
+    }
+}
+
+fn replay(response: StoredResponse) -> Response {
+    let mut builder = Response::builder()
+        .status(response.status)
+        .header(REPLAYED_HEADER, "true");
+    if let Some(content_type) = response.content_type {
+        builder = builder.header(header::CONTENT_TYPE, content_type);
+    }
+    builder
+        .body(Body::from(response.body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Run a keyed mutating request once, replaying its response to retries
+pub async fn deduplicate(
+    State(store): State<Arc<IdempotencyStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let safe = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let key = request
+        .headers()
+        .get(IDEMPOTENCY_HEADER)
+        .map(|v| v.to_str().unwrap_or_default().to_string());
+    let key = match key {
+        Some(key) if !safe => key,
+        _ => return next.run(request).await,
+    };
+    if !idempotency::is_valid_key(&key) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Idempotency-Key must be 1 to 255 printable ASCII characters",
+        )
+            .into_response();
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, MAX_REQUEST_BODY).await else {
+        return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response();
+    };
+    let uri = parts.uri.path_and_query().map_or("", |p| p.as_str());
+    let fingerprint = idempotency::fingerprint(parts.method.as_str(), uri, &body);
+    match store.claim(&key, &fingerprint, Instant::now()) {
+        Claim::New => {}
+        Claim::Replay(response) => {
+            tracing::info!("Replayed {} {} for a retried request", parts.method, uri);
+            return replay(response);
+        }
+        Claim::InProgress => {
+            return (
+                StatusCode::CONFLICT,
+                "A request with this Idempotency-Key is still running",
+            )
+                .into_response()
+        }
+        Claim::Mismatch => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used for a different request",
+            )
+                .into_response()
+        }
+    }
+
+    let claim = ClaimGuard {
+        store,
+        key,
+        kept: false,
+    };
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    let kept_size = response.body().size_hint().exact();
+    if response.status().is_server_error() || kept_size.is_none_or(|n| n > MAX_KEPT_RESPONSE) {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_KEPT_RESPONSE as usize).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!("Failed to read the response to keep for retries: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    claim.complete(StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        body: body.to_vec(),
+    });
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use axum::{middleware, routing::post, Router};
+    use tower::ServiceExt;
+
+    fn app(calls: Arc<AtomicUsize>) -> Router {
+        let counted = move || {
+            let calls = calls.clone();
+            async move { calls.fetch_add(1, Ordering::SeqCst).to_string() }
+        };
+        Router::new()
+            .route("/paid", post(counted.clone()))
+            .route("/slow", post(std::future::pending::<()>))
+            .route(
+                "/stream",
+                post(move || async move {
+                    counted().await;
+                    let chunks = futures_util::stream::iter([Ok::<_, std::io::Error>("{}\n")]);
+                    Body::from_stream(chunks)
+                }),
+            )
+            .layer(middleware::from_fn_with_state(
+                Arc::new(IdempotencyStore::default()),
+                deduplicate,
+            ))
+    }
+
+    fn keyed(path: &str, key: &str, body: &'static str) -> Request {
+        Request::post(path)
+            .header(IDEMPOTENCY_HEADER, key)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replay_and_conflicts() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(calls.clone());
+
+        let first = app
+            .clone()
+            .oneshot(keyed("/paid", "k1", "a"))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert!(first.headers().get(REPLAYED_HEADER).is_none());
+        let retry = app
+            .clone()
+            .oneshot(keyed("/paid", "k1", "a"))
+            .await
+            .unwrap();
+        assert_eq!(retry.headers()[REPLAYED_HEADER], "true");
+        assert_eq!(to_bytes(retry.into_body(), 100).await.unwrap(), "0");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let other = app
+            .clone()
+            .oneshot(keyed("/paid", "k1", "b"))
+            .await
+            .unwrap();
+        assert_eq!(other.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Streamed responses are not kept
+        for _ in 0..2 {
+            let streamed = app
+                .clone()
+                .oneshot(keyed("/stream", "k2", "a"))
+                .await
+                .unwrap();
+            assert!(streamed.headers().get(REPLAYED_HEADER).is_none());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_request_releases_its_key() {
+        let app = app(Arc::new(AtomicUsize::new(0)));
+        let slow = tokio::spawn(app.clone().oneshot(keyed("/slow", "k1", "a")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let running = app
+            .clone()
+            .oneshot(keyed("/slow", "k1", "a"))
+            .await
+            .unwrap();
+        assert_eq!(running.status(), StatusCode::CONFLICT);
+
+        // The client went away, so a retry runs again rather than being
+        // refused as still running
+        slow.abort();
+        assert!(slow.await.unwrap_err().is_cancelled());
+        let retry = tokio::time::timeout(
+            Duration::from_millis(50),
+            app.oneshot(keyed("/slow", "k1", "a")),
+        )
+        .await;
+        assert!(retry.is_err());
+    }
+}
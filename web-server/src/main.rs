@@ -2,36 +2,106 @@
 //!
 //! This server wraps the rust-backend library and exposes HTTP endpoints.
 
+mod admin;
+mod csrf;
+mod idempotency;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::env;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{get, post},
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
     Json, Router,
 };
-use tower_http::cors::{Any, CorsLayer};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
 use rust_backend::{
-    handlers::{self, AppState, HealthResponse},
+    analysis::ScheduleWarning,
+    audit::{self, AuditEntry, AuditVerification},
+    bulk::BulkOp,
+    call_stats::MethodCallStats,
+    calendar::{CalendarError, Holiday},
+    countdown::{self, Countdown},
+    events::Event as _,
+    diff::ChangeReport,
+    edit_lock::EditLockStatus,
+    email,
+    explanations::Explanation,
+    export::{ExportError, ExportFormat, ExportTemplate, ReportType, ZenginLayout},
+    field_crypto::{self, FileKeyStore},
+    handlers::{self, AppState, HandlerError, HealthResponse},
+    health_history::HealthEvent,
+    history::{Decision, HistoryEntry, HistoryError, HistoryStats, RecommendationInputs},
+    idempotency::IdempotencyStore,
+    journal::PastVersion,
     json_rpc::{JsonRpcRequest, JsonRpcResponse},
-    LeanRepl,
+    log_buffer::{EventQuery, LogBuffer, LogEvent},
+    logs::{self, LogError, LogLevel, LogWriter},
+    model::{
+        Dataset, FeeKind, Installment, InstallmentPlan, RecurringFee, RecurringPayment, School,
+    },
+    notes::{NewNote, NoteError},
+    onboarding::{OnboardingState, OnboardingStep},
+    outcome::{ExamOutcome, RecordedOutcome},
+    payments::{OutstandingPayments, PaymentError, PaymentRecord},
+    startup,
+    profiling::ProfileStats,
+    query::{ListQuery, Page},
+    recording::{self, RecordingStatus, ReplayAdvisor, Session},
+    reminders,
+    date,
+    demo,
+    sample::SampleProfile,
+    search::SearchHit,
+    self_test::SelfTestReport,
+    summary::{Summary, SummaryFigure, SummaryRange, TotalExplanation},
+    supervisor,
+    templates::{SchoolTemplate, TemplateError},
+    timeline::Timeline,
+    result_cache::CachedResult,
+    settings::Settings,
+    share::{self, ShareError, ShareLink, DEFAULT_SHARE_DAYS},
+    storage::StorageError,
+    simulation::{Scenario, SimulationResult},
+    streaming::{Chunks, DEFAULT_CHUNK_SIZE},
+    web_push::{NewPushSubscription, PushSubscription, WebPush, WebPushError},
+    AppConfig, LeanRepl, Storage,
 };
 
+/// Origins of the frontend dev and preview servers, allowed when
+/// `[web] allowed_origins` is empty
+const DEV_ORIGINS: [&str; 4] = [
+    "http://localhost:5173",
+    "http://127.0.0.1:5173",
+    "http://localhost:4173",
+    "http://127.0.0.1:4173",
+];
+
 #[tokio::main]
 async fn main() {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "web_server=debug,rust_backend=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Initialize tracing; the log file is opened once the config is loaded
+    let log_writer = LogWriter::default();
+    let log_buffer = LogBuffer::default();
+    let log_level = logs::init_tracing(
+        &["web_server", "rust_backend"],
+        LogLevel::Debug,
+        log_writer.clone(),
+        log_buffer.clone(),
+    );
 
     // Get configuration from environment
     let port: u16 = env::var("PORT")
@@ -50,46 +120,361 @@ async fn main() {
 
     tracing::info!("Advisor binary path: {:?}", advisor_path);
 
-    // Initialize Lean REPL
-    let mut lean_repl = LeanRepl::new(advisor_path);
+    let config_path = env::var("CONFIG_PATH").unwrap_or_else(|_| "config.toml".to_string());
+    let config = AppConfig::load(&PathBuf::from(&config_path)).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring config file {}: {}", config_path, e);
+        AppConfig::default()
+    });
 
-    match lean_repl.start() {
-        Ok(()) => tracing::info!("Lean REPL started successfully"),
+    let mut data_dir = PathBuf::from(env::var("DATA_DIR").unwrap_or_else(|_| "data".to_string()));
+    let demo_mode = demo::is_enabled(&config, env::args().any(|arg| arg == demo::DEMO_ARG));
+    if demo_mode {
+        // Keep the real data out of reach of the demo
+        data_dir = demo::data_dir(&data_dir);
+    }
+    let replay_path = env::args().skip_while(|arg| arg != recording::REPLAY_ARG).nth(1);
+    let replay = replay_path.map(|path| match Session::load(&PathBuf::from(&path)) {
+        Ok(session) => session,
         Err(e) => {
-            tracing::warn!("Could not start Lean REPL immediately: {}", e);
-            tracing::info!("Will attempt to start on first request");
+            tracing::error!("Cannot load session {}: {}", path, e);
+            std::process::exit(1);
         }
+    });
+    if replay.is_some() {
+        data_dir = recording::replay_data_dir(&data_dir);
+    }
+    let log_dir = config.logging.dir(&data_dir);
+    match log_writer.open(&log_dir, &config.logging) {
+        Ok(()) => tracing::info!("Log directory: {:?}", log_dir),
+        Err(e) => tracing::warn!("Could not open log file in {:?}: {}", log_dir, e),
     }
 
+    // Initialize Lean REPL
+    let lean_repl = if let Some(session) = &replay {
+        tracing::info!(
+            "Replaying a session recorded {} ({} requests)",
+            session.started_at,
+            session.exchanges.len()
+        );
+        LeanRepl::replay(ReplayAdvisor::new(session.clone()))
+    } else if demo_mode {
+        tracing::info!("Demo mode: using sample data and the mock advisor");
+        LeanRepl::demo()
+    } else {
+        LeanRepl::new(advisor_path)
+    };
+
     // Create shared state
-    let state = Arc::new(AppState::new(lean_repl));
+    tracing::info!("Data directory: {:?}", data_dir);
+
+    let storage = Storage::new(data_dir);
+    if demo_mode {
+        if let Err(e) = demo::seed(&storage, date::today()) {
+            tracing::warn!("Could not write demo data: {}", e);
+        }
+    }
+    if let Some(session) = &replay {
+        if let Err(e) = recording::seed(&storage, session) {
+            tracing::warn!("Could not write the recorded data: {}", e);
+        }
+    }
+    if let Some(path) = &config.web.field_key_file {
+        match field_crypto::unlock(&storage, &FileKeyStore::new(path.clone())) {
+            Ok(true) => tracing::info!("Encrypted fields unlocked"),
+            Ok(false) => tracing::warn!("No encryption key in {:?}", path),
+            Err(e) => tracing::warn!("Could not read the encryption key: {}", e),
+        }
+    }
+    let settings = Settings::load(&storage, Settings::from_config(&config)).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring saved settings: {}", e);
+        Settings::from_config(&config)
+    });
+
+    let audit_key = config.web.audit_key_file.clone().and_then(|path| {
+        audit::load_or_create_key(&FileKeyStore::new(path))
+            .inspect_err(|e| tracing::warn!("Audit log disabled, no signing key: {}", e))
+            .ok()
+    });
+    let share_key_file = config
+        .web
+        .share_key_file
+        .clone()
+        .unwrap_or_else(|| storage.data_dir().join(share::SHARE_KEY_FILE));
+    let share_key = audit::load_or_create_key(&FileKeyStore::new(share_key_file))
+        .inspect_err(|e| tracing::warn!("Sharing disabled, no signing key: {}", e))
+        .ok();
+    let web_push = config.web.push.as_ref().and_then(|push| {
+        WebPush::new(push)
+            .inspect_err(|e| tracing::warn!("Web Push disabled: {}", e))
+            .ok()
+    });
+    let mut state = AppState::new(lean_repl)
+        .with_config(config)
+        .with_settings(settings)
+        .with_log_level(log_level)
+        .with_log_buffer(log_buffer)
+        .with_storage(storage.clone())
+        .with_edit_lock("web");
+    if let Some(key) = audit_key {
+        state = state.with_audit_key(key);
+    }
+    if let Some(key) = share_key {
+        state = state.with_share_key(key);
+    }
+    if let Some(push) = web_push {
+        state = state.with_web_push(push);
+    }
+    let state = Arc::new(state);
+
+    // Start the advisor as the settings say, migrating data written by an
+    // older release
+    startup::start(state.clone(), |progress| match &progress.error {
+        Some(e) => {
+            tracing::warn!("Could not start the advisor: {}", e);
+            tracing::info!("Will attempt to start on first request");
+        }
+        None => tracing::debug!("Advisor startup: {}", progress.message),
+    })
+    .await;
+
+    // Daily email digest (no-op unless [email] is configured)
+    tokio::spawn(email::run_daily_digest(state.clone(), None));
+
+    // Deadline countdown for the badges, kept current across midnight
+    tokio::spawn(countdown::run_countdown(
+        state.clone(),
+        countdown::COUNTDOWN_INTERVAL,
+    ));
 
-    // Configure CORS
+    // Deadline alerts to subscribed browsers, even with the tab closed
+    if let Some(push) = state.web_push.clone() {
+        tokio::spawn(reminders::run_deadline_scheduler(
+            state.clone(),
+            move |deadline| push.notify_deadline(&storage, deadline),
+        ));
+    }
+
+    // Configure CORS: only the configured origins, or the frontend dev
+    // server on this machine
+    let origins = if state.config.web.allowed_origins.is_empty() {
+        DEV_ORIGINS.iter().map(|origin| origin.to_string()).collect()
+    } else {
+        state.config.web.allowed_origins.clone()
+    };
+    let origins: Vec<HeaderValue> = origins
+        .iter()
+        .filter_map(|origin| match origin.parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid allowed origin {:?}", origin);
+                None
+            }
+        })
+        .collect();
     let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
         .allow_headers(Any);
 
+    let csrf_enabled = state.config.web.csrf;
+
     // Build router
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/rpc", post(rpc_handler))
+        .route("/rpc/batch", post(rpc_batch_handler))
+        .route("/rpc/stream", post(rpc_stream_handler))
+        .route("/rpc/cached", post(rpc_cached_handler))
         .route("/health", get(health_handler))
+        .route("/health/history", get(health_history_handler))
         .route("/ping", get(ping_handler))
-        .layer(cors)
-        .with_state(state);
+        .route("/selftest", get(self_test_handler))
+        .route("/export", get(export_handler).post(export_body_handler))
+        .route("/export/zengin", get(zengin_handler))
+        .route("/export/templates", get(list_export_templates_handler))
+        .route("/export/templates/{name}", get(export_template_handler))
+        .route("/data", get(load_data_handler).put(save_data_handler).patch(patch_data_handler))
+        .route("/data/schools", get(list_schools_handler))
+        .route("/data/as-of", get(data_as_of_handler))
+        .route("/data/bulk", post(bulk_update_handler))
+        .route("/summary", get(summary_handler))
+        .route("/deadlines/countdown", get(countdown_handler))
+        .route("/deadlines/countdown/events", get(countdown_events_handler))
+        .route("/summary/explain", post(explain_totals_handler))
+        .route("/simulate", post(simulate_handler))
+        .route("/analysis", get(analysis_handler))
+        .route("/timeline", get(timeline_handler))
+        .route("/payments/outstanding", get(outstanding_payments_handler))
+        .route("/payments/paid", post(mark_paid_handler))
+        .route("/schools/{id}/outcome", post(record_outcome_handler))
+        .route("/schools/{id}/notes", post(add_note_handler))
+        .route("/schools/{id}/notes/{index}", delete(delete_note_handler))
+        .route("/search", get(search_handler))
+        .route("/push/key", get(push_key_handler))
+        .route(
+            "/push/subscriptions",
+            post(subscribe_push_handler).delete(unsubscribe_push_handler),
+        )
+        .route("/audit", get(audit_log_handler))
+        .route("/audit/verify", get(verify_audit_handler))
+        .route("/share", post(create_share_handler))
+        .route("/shared/{token}", get(shared_schedule_handler))
+        .route("/onboarding", get(onboarding_state_handler))
+        .route("/onboarding/{step}", post(complete_onboarding_step_handler))
+        .route(
+            "/edit-lock",
+            get(edit_lock_handler)
+                .post(acquire_edit_lock_handler)
+                .delete(release_edit_lock_handler),
+        )
+        .route("/installments/plan", post(plan_installments_handler))
+        .route("/recurring/expand", post(expand_recurring_handler))
+        .route("/recurring/refresh", post(refresh_recurring_handler))
+        .route("/templates", get(list_templates_handler).post(import_templates_handler))
+        .route("/templates/{id}/apply", post(apply_template_handler))
+        .route("/holidays/{year}", get(list_holidays_handler).post(import_holidays_handler))
+        .route("/explanations/{id}", get(explanation_handler))
+        .route("/history", get(list_history_handler))
+        .route("/history/stats", get(history_stats_handler))
+        .route("/history/export", get(export_history_handler))
+        .route("/history/stream", get(stream_history_handler))
+        .route("/reports/tax/{year}", get(tax_report_handler))
+        .route("/history/{id}/decision", post(decide_handler))
+        .route("/history/{id}/inputs", get(recommendation_inputs_handler))
+        .route("/stats/methods", get(method_stats_handler));
+
+    // Admin routes need the admin token, or come from this machine. Push
+    // subscriptions hold every browser's keys, so only browsers add and
+    // remove their own without it; backups are written to the data folder.
+    let admin_routes = Router::new()
+        .route("/push/subscriptions", get(list_push_subscriptions_handler))
+        .route("/push/test", post(test_push_handler))
+        .route("/backups/cloud", post(receive_cloud_backup_handler))
+        .route("/admin/log-level", post(log_level_handler))
+        .route("/admin/log-events", get(log_events_handler))
+        .route("/admin/profile", get(profile_handler).post(set_profiling_handler))
+        .route("/admin/recording", get(recording_status_handler))
+        .route("/admin/recording/start", post(start_recording_handler))
+        .route("/admin/recording/stop", post(stop_recording_handler))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::new(state.config.web.admin_token.clone()),
+            admin::authorize,
+        ));
+    app = app.merge(admin_routes);
+
+    // Sample data for the frontend dev server; not part of release builds
+    if cfg!(debug_assertions) {
+        app = app.route("/dev/sample-data", get(sample_data_handler));
+    }
+
+    // Retries of keyed requests get the first response back; inside the
+    // CSRF check so a rejected request is never kept
+    app = app.layer(middleware::from_fn_with_state(
+        Arc::new(IdempotencyStore::default()),
+        idempotency::deduplicate,
+    ));
+
+    if csrf_enabled {
+        app = app
+            .route("/csrf-token", get(csrf::issue_token))
+            .layer(middleware::from_fn(csrf::verify));
+    }
+
+    let app = app.layer(cors).with_state(state.clone());
 
     // Start server
     let addr = format!("0.0.0.0:{}", port);
     tracing::info!("API Server running on http://{}", addr);
     tracing::info!("  - POST /rpc - JSON-RPC endpoint");
-    tracing::info!("  - GET /health - Health check");
+    tracing::info!("  - POST /rpc/batch - Batched JSON-RPC endpoint");
+    tracing::info!("  - POST /rpc/stream - JSON-RPC with the result as NDJSON chunks (?chunkSize)");
+    tracing::info!("  - POST /rpc/cached - Result last computed for a JSON-RPC request, with its age");
+    tracing::info!("  - GET /health - Health check (?wait_ready=30s waits for the advisor, 503 if it does not answer)");
+    tracing::info!("  - GET /health/history - Advisor status changes, crashes and restarts");
     tracing::info!("  - GET /ping - Test Lean REPL connection");
+    tracing::info!("  - GET /selftest - Check the advisor and data directory end to end");
+    tracing::info!("  - GET|POST /export - Export schedule (json/csv/xlsx/ics/pdf)");
+    tracing::info!("  - GET /export/zengin - Bank transfer file for this week's payments (?layout=fixed|csv)");
+    tracing::info!("  - GET /export/templates - List export templates in the data directory");
+    tracing::info!("  - GET /export/templates/{{name}} - Export with a template (?report=schedule|weekly|plan)");
+    tracing::info!("  - GET|PUT|PATCH /data - Saved data with its revision; PUT saves it, PATCH merges a JSON merge patch (?name, ?expectedRevision, ?dryRun)");
+    tracing::info!("  - GET /data/schools - List saved schools (?page, ?per_page, ?sort, field filters)");
+    tracing::info!("  - POST /data/bulk - Apply a list of school and payment changes as one save, all or none (?name, ?expectedRevision, ?dryRun)");
+    tracing::info!("  - GET /data/as-of - Saved data as it was at a past time (?at=RFC 3339 or date, ?name)");
+    tracing::info!(
+        "  - GET /summary - Payment totals by month, school, category and child (?from, ?to, ?childId)"
+    );
+    tracing::info!("  - GET /deadlines/countdown - Unpaid deadlines: overdue, today, within 3 and 7 days");
+    tracing::info!("  - GET /deadlines/countdown/events - The countdown and its changes as server-sent events");
+    tracing::info!("  - POST /summary/explain - The payments adding up to one figure of the summary");
+    tracing::info!("  - POST /simulate - Compare what-if scenarios against the saved data");
+    tracing::info!("  - GET /analysis - Deadline conflicts and bank holidays in the saved data");
+    tracing::info!("  - GET /timeline - Exam, result and payment timeline of each school");
+    tracing::info!("  - POST /installments/plan - Split tuition into installments");
+    tracing::info!("  - POST /recurring/expand - Preview the payments of recurring fees");
+    tracing::info!("  - POST /recurring/refresh - Generate the upcoming recurring payments of the saved data");
+    tracing::info!("  - GET /payments/outstanding - Outstanding and settled payments (?from, ?to, ?childId)");
+    tracing::info!("  - POST /payments/paid - Record a payment as made (?dryRun=true to only show the diff)");
+    tracing::info!("  - POST /schools/{{id}}/outcome - Record an exam result and recompute the recommendation (?dryRun=true to only show the diff)");
+    tracing::info!("  - POST /schools/{{id}}/notes - Add a note to a school or one of its payments (?dryRun=true to only show the diff)");
+    tracing::info!("  - DELETE /schools/{{id}}/notes/{{index}} - Delete a school's note (?dryRun=true to only show the diff)");
+    tracing::info!("  - GET /search - Search school names, notes and payment memos (?q)");
+    tracing::info!("  - GET /push/key - Public VAPID key for browsers to subscribe with");
+    tracing::info!("  - POST|DELETE /push/subscriptions - Add or remove (?endpoint) a push subscription");
+    tracing::info!("  - GET /audit - Audit log of payments, imports and restores");
+    tracing::info!("  - GET /audit/verify - Check the audit log for tampering");
+    tracing::info!("  - POST /share - Make a read-only link to a snapshot of the payment schedule (?days, 7 by default)");
+    tracing::info!("  - GET /shared/{{token}} - The shared snapshot, as JSON or a web page (?format=json|html)");
+    tracing::info!("  - GET /onboarding - Progress through the first-run setup wizard");
+    tracing::info!("  - POST /onboarding/{{step}} - Mark a setup step done (locale, data, reminders, advisor)");
+    tracing::info!("  - GET|POST|DELETE /edit-lock - Edit lock shared with the desktop app: check, take or renew, release");
+    tracing::info!("  - GET|POST /templates - List school templates, or import a catalog");
+    tracing::info!("  - POST /templates/{{id}}/apply - New school from a template and exam date");
+    tracing::info!("  - GET|POST /holidays/{{year}} - List bank holidays, or import a holiday file");
+    tracing::info!("  - GET /explanations/{{id}} - Why the advisor recommends an action (id from explanationId)");
+    tracing::info!("  - GET /history - Recorded recommendations (same parameters as /data/schools)");
+    tracing::info!("  - GET /history/stream - All matching recommendations as NDJSON chunks (?chunkSize)");
+    tracing::info!("  - POST /history/{{id}}/decision - Record what was done about a recommendation");
+    tracing::info!("  - GET /history/{{id}}/inputs - Advisor parameters a recommendation was computed from");
+    tracing::info!("  - GET /history/stats - Season totals of the history");
+    tracing::info!("  - GET /history/export - Export the history (?format=json|csv)");
+    tracing::info!("  - GET /reports/tax/{{year}} - Payments made in a year for tax filing (?format=json|csv|pdf)");
+    tracing::info!("  - POST /admin/log-level - Change the log filter until restart");
+    tracing::info!("  - GET /admin/log-events - Recent log events (?level=&module=&limit=)");
+    tracing::info!("  - GET|POST /admin/profile - Request latency by phase, or switch profiling on/off");
+    tracing::info!("  - GET /stats/methods - Calls, error rate and p50/p95 latency of each advisor method");
+    tracing::info!("  - GET /admin/recording - Whether advisor requests are being recorded");
+    tracing::info!("  - POST /admin/recording/start|stop - Record advisor requests, or save the session");
+    tracing::info!("  - GET /push/subscriptions - Saved push subscriptions (?user, admin)");
+    tracing::info!("  - POST /push/test - Push a test notification (?user, everyone by default, admin)");
+    tracing::info!("  - POST /backups/cloud - Keep an encrypted backup uploaded by the desktop app's schedule (admin)");
+    if cfg!(debug_assertions) {
+        tracing::info!("  - GET /dev/sample-data - Generate sample data (?profile=university|juniorHigh|minimal)");
+    }
+    tracing::info!("  Mutating requests accept an Idempotency-Key header; retries within 24 hours get the first response");
+    if state.config.web.admin_token.is_some() {
+        tracing::info!("  Admin routes require Authorization: Bearer <admin_token>");
+    } else {
+        tracing::info!("  Admin routes only answer requests from this machine");
+    }
+    if csrf_enabled {
+        tracing::info!("  - GET /csrf-token - Issue CSRF token (required on mutating requests with cookies)");
+    }
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
+
+    if let Err(e) = handlers::release_edit_lock(state).await {
+        tracing::warn!("Could not release the edit lock: {}", e);
+    }
 }
 
 /// Handle JSON-RPC requests
@@ -107,9 +492,179 @@ async fn rpc_handler(
     }
 }
 
-/// Handle health check requests
-async fn health_handler(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
-    Json(handlers::health_check(state).await)
+/// Handle batched JSON-RPC requests
+///
+/// Always answers 200 with one response per request, in request order;
+/// individual failures are reported as JSON-RPC error objects.
+async fn rpc_batch_handler(
+    State(state): State<Arc<AppState>>,
+    Json(requests): Json<Vec<JsonRpcRequest>>,
+) -> Json<Vec<JsonRpcResponse>> {
+    Json(handlers::send_rpc_batch(state, requests, handlers::DEFAULT_BATCH_PARALLELISM).await)
+}
+
+/// The result last computed for a request, shown while it is computed again
+async fn rpc_cached_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<JsonRpcRequest>,
+) -> Json<Option<CachedResult>> {
+    Json(handlers::cached_result(state, &request).await)
+}
+
+/// Chunk size of a streamed response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamParams {
+    chunk_size: Option<usize>,
+}
+
+/// Write chunks as an NDJSON body, one chunk per line
+fn ndjson(chunks: Chunks) -> Response {
+    let lines = chunks.map(|chunk| {
+        serde_json::to_vec(&chunk).map(|mut line| {
+            line.push(b'\n');
+            line
+        })
+    });
+    let body = Body::from_stream(futures_util::stream::iter(lines));
+    ([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response()
+}
+
+/// Handle a JSON-RPC request whose result is streamed as NDJSON chunks
+async fn rpc_stream_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<StreamParams>,
+    Json(request): Json<JsonRpcRequest>,
+) -> Result<Response, (StatusCode, String)> {
+    let chunk_size = params.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    handlers::stream_rpc(state, request, chunk_size)
+        .await
+        .map(ndjson)
+        .map_err(|e| {
+            tracing::error!("RPC error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct HealthQuery {
+    /// Wait this long for the advisor to answer, such as `30s`
+    wait_ready: Option<String>,
+}
+
+/// Handle health check requests, answering 503 if the advisor did not
+/// become ready within `wait_ready`
+async fn health_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HealthQuery>,
+) -> Result<(StatusCode, Json<HealthResponse>), (StatusCode, String)> {
+    let Some(wait) = query.wait_ready else {
+        return Ok((StatusCode::OK, Json(handlers::health_check(state).await)));
+    };
+    let timeout = supervisor::parse_wait(&wait).ok_or((
+        StatusCode::BAD_REQUEST,
+        format!("Invalid wait_ready: {} (use 500ms, 30s or 2m)", wait),
+    ))?;
+    let health = handlers::health_check_ready(state, timeout).await;
+    let status = if health.ready == Some(true) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((status, Json(health)))
+}
+
+/// Advisor status changes, crashes and restarts with their times
+async fn health_history_handler(State(state): State<Arc<AppState>>) -> Json<Vec<HealthEvent>> {
+    Json(handlers::health_history(state).await)
+}
+
+/// Run the end-to-end self-test
+async fn self_test_handler(State(state): State<Arc<AppState>>) -> Json<SelfTestReport> {
+    Json(handlers::run_self_test(state).await)
+}
+
+/// Body and response of a log filter change
+#[derive(Debug, Deserialize, Serialize)]
+struct LogFilterBody {
+    /// Filter in `RUST_LOG` syntax, such as `rust_backend=trace,info`
+    filter: String,
+}
+
+/// Change the log filter without restarting
+async fn log_level_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<LogFilterBody>,
+) -> Result<Json<LogFilterBody>, (StatusCode, String)> {
+    handlers::set_log_filter(state, &body.filter)
+        .await
+        .map(|filter| Json(LogFilterBody { filter }))
+        .map_err(|e| match e {
+            HandlerError::Log(LogError::InvalidFilter(_)) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Recent log events, filtered by `?level=`, `?module=` and `?limit=`
+async fn log_events_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EventQuery>,
+) -> Result<Json<Vec<LogEvent>>, (StatusCode, String)> {
+    handlers::recent_events(state, query)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Log(LogError::UnknownLevel(_)) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Whether a session is being recorded
+async fn recording_status_handler(State(state): State<Arc<AppState>>) -> Json<RecordingStatus> {
+    Json(handlers::recording_status(state).await)
+}
+
+/// Start recording advisor requests and responses
+async fn start_recording_handler(State(state): State<Arc<AppState>>) -> Json<RecordingStatus> {
+    Json(handlers::start_recording(state).await)
+}
+
+/// Stop recording and save the session, returning the file's path
+async fn stop_recording_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<String, (StatusCode, String)> {
+    handlers::stop_recording(state).await.map_err(|e| match e {
+        HandlerError::NotRecording => (StatusCode::CONFLICT, e.to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })
+}
+
+/// Body of a profiling switch
+#[derive(Debug, Deserialize)]
+struct ProfilingBody {
+    enabled: bool,
+}
+
+/// Calls, error rate and latency of each advisor method since startup
+async fn method_stats_handler(State(state): State<Arc<AppState>>) -> Json<Vec<MethodCallStats>> {
+    Json(handlers::get_method_stats(state).await)
+}
+
+/// Percentiles of the phase timings of recent advisor requests
+async fn profile_handler(State(state): State<Arc<AppState>>) -> Json<ProfileStats> {
+    Json(handlers::request_stats(state).await)
+}
+
+/// Switch request profiling on or off
+async fn set_profiling_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<ProfilingBody>,
+) -> Json<ProfileStats> {
+    Json(handlers::set_profiling(state, body.enabled).await)
 }
 
 /// Handle ping requests
@@ -122,6 +677,1086 @@ async fn ping_handler(
     }
 }
 
+/// Query parameters for export requests
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+}
+
+/// Pick an export format from `?format=` or, failing that, the `Accept` header
+fn negotiate_format(
+    query: &ExportQuery,
+    headers: &HeaderMap,
+) -> Result<ExportFormat, (StatusCode, String)> {
+    if let Some(name) = &query.format {
+        return ExportFormat::from_name(name).ok_or_else(|| {
+            (StatusCode::BAD_REQUEST, format!("Unknown export format: {}", name))
+        });
+    }
+
+    match headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) {
+        None => Ok(ExportFormat::Json),
+        Some(accept) => ExportFormat::from_accept(accept).ok_or_else(|| {
+            (
+                StatusCode::NOT_ACCEPTABLE,
+                "No supported export format is acceptable".to_string(),
+            )
+        }),
+    }
+}
+
+/// Render an export and wrap it in a download response
+async fn export_response(
+    state: Arc<AppState>,
+    query: ExportQuery,
+    headers: HeaderMap,
+    data: Option<serde_json::Value>,
+) -> Response {
+    let format = match negotiate_format(&query, &headers) {
+        Ok(format) => format,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    match handlers::export_data(state, format, data).await {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"school-payment.{}\"", format.extension()),
+                ),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => {
+            let status = match e {
+                HandlerError::NoData => StatusCode::NOT_FOUND,
+                HandlerError::Export(ExportError::InvalidData(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+                HandlerError::Export(ExportError::Unsupported(_)) => StatusCode::NOT_ACCEPTABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Export the stored dataset
+async fn export_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Response {
+    export_response(state, query, headers, None).await
+}
+
+/// Export a dataset supplied in the request body
+async fn export_body_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+    Json(data): Json<serde_json::Value>,
+) -> Response {
+    export_response(state, query, headers, Some(data)).await
+}
+
+/// Query parameters for bank transfer exports
+#[derive(Debug, Deserialize)]
+struct ZenginQuery {
+    layout: Option<String>,
+}
+
+/// Zengin-format transfer file for the payments due this week
+async fn zengin_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ZenginQuery>,
+) -> Response {
+    let layout = match query.layout.as_deref().map(ZenginLayout::from_name) {
+        None => ZenginLayout::default(),
+        Some(Some(layout)) => layout,
+        Some(None) => {
+            return (StatusCode::BAD_REQUEST, "Unknown transfer file layout").into_response()
+        }
+    };
+
+    match handlers::export_zengin(state, layout, date::today()).await {
+        Ok(export) => (
+            [
+                (header::CONTENT_TYPE, "text/plain; charset=Shift_JIS".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"zengin.{}\"", layout.extension()),
+                ),
+            ],
+            export.bytes,
+        )
+            .into_response(),
+        Err(e) => {
+            let status = match e {
+                HandlerError::NoData => StatusCode::NOT_FOUND,
+                HandlerError::Export(
+                    ExportError::NoBankTransferSettings | ExportError::InvalidAccount { .. },
+                ) => StatusCode::UNPROCESSABLE_ENTITY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Export templates the user has put in the data directory
+async fn list_export_templates_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ExportTemplate>>, (StatusCode, String)> {
+    handlers::list_export_templates(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Query parameters for template exports
+#[derive(Debug, Deserialize)]
+struct TemplateExportQuery {
+    report: Option<ReportType>,
+}
+
+/// Render the stored dataset with an export template
+async fn export_template_handler(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<TemplateExportQuery>,
+) -> Response {
+    match handlers::export_with_template(state, &name, query.report, date::today()).await {
+        Ok(export) => (
+            [
+                (header::CONTENT_TYPE, export.template.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", export.template.name),
+                ),
+            ],
+            export.text,
+        )
+            .into_response(),
+        Err(e) => {
+            let status = match e {
+                HandlerError::NoData | HandlerError::Export(ExportError::UnknownTemplate(_)) => {
+                    StatusCode::NOT_FOUND
+                }
+                HandlerError::Export(ExportError::Template(_) | ExportError::InvalidData(_)) => {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Query parameters for reading and writing the saved data
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataQuery {
+    /// Named dataset; the default dataset when missing
+    name: Option<String>,
+    /// Revision the client loaded; a save over a later one is refused
+    expected_revision: Option<u64>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Response to a failed save or change of the saved data; a stale one gets
+/// 409 with the conflict as JSON
+fn data_error(e: HandlerError) -> Response {
+    let status = match &e {
+        HandlerError::Storage(StorageError::Conflict(conflict)) => {
+            return (StatusCode::CONFLICT, Json(conflict)).into_response();
+        }
+        HandlerError::NoData => StatusCode::NOT_FOUND,
+        HandlerError::Storage(StorageError::InvalidName(_))
+        | HandlerError::InvalidPatch(_)
+        | HandlerError::Bulk(_) => StatusCode::BAD_REQUEST,
+        HandlerError::ReadOnly(_) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string()).into_response()
+}
+
+/// The saved data, with the revision to send back when saving it
+async fn load_data_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DataQuery>,
+) -> Response {
+    match handlers::load_data(state, query.name.as_deref()).await {
+        Ok(data) => Json(data).into_response(),
+        Err(e) => data_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AsOfQuery {
+    at: String,
+    name: Option<String>,
+}
+
+/// The saved data as it was at `?at=`, from the journal or a backup
+async fn data_as_of_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AsOfQuery>,
+) -> Result<Json<PastVersion>, (StatusCode, String)> {
+    handlers::get_data_as_of(state, query.name.as_deref(), &query.at)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::InvalidAsOf(_) | HandlerError::Storage(StorageError::InvalidName(_)) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            HandlerError::NoDataAsOf(_) => (StatusCode::NOT_FOUND, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Replace the saved data, unless it has changed since `?expectedRevision=`
+async fn save_data_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DataQuery>,
+    Json(data): Json<Dataset>,
+) -> Response {
+    let name = query.name.as_deref();
+    match handlers::save_data(state, name, data, query.expected_revision, date::today()).await {
+        Ok(saved) => Json(saved).into_response(),
+        Err(e) => data_error(e),
+    }
+}
+
+/// Change part of the saved data with a JSON merge patch, checking
+/// `?expectedRevision=` like PUT
+async fn patch_data_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DataQuery>,
+    Json(patch): Json<serde_json::Value>,
+) -> Response {
+    let report = handlers::patch_data(
+        state,
+        query.name.as_deref(),
+        &patch,
+        query.expected_revision,
+        query.dry_run,
+        date::today(),
+    )
+    .await;
+    match report {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => data_error(e),
+    }
+}
+
+/// Apply a list of school and payment changes as one save; if any fails,
+/// nothing is saved
+async fn bulk_update_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DataQuery>,
+    Json(ops): Json<Vec<BulkOp>>,
+) -> Response {
+    let report = handlers::bulk_update(
+        state,
+        query.name.as_deref(),
+        ops,
+        query.expected_revision,
+        query.dry_run,
+        date::today(),
+    )
+    .await;
+    match report {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => data_error(e),
+    }
+}
+
+/// List saved schools with pagination, sorting, and field filters
+async fn list_schools_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, (StatusCode, String)> {
+    let query =
+        ListQuery::from_params(&params).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    handlers::list_schools(state, &query)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Unpaid deadlines by how soon they are due, computed now
+async fn countdown_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Countdown>, (StatusCode, String)> {
+    handlers::deadline_countdown(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// The deadline countdown as server-sent events: the current one, then
+/// each change, such as at midnight
+async fn countdown_events_handler(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut countdown = state.subscribe_countdown();
+    countdown.mark_changed();
+    let events = futures_util::stream::unfold(countdown, |mut countdown| async move {
+        countdown.changed().await.ok()?;
+        let current = countdown.borrow_and_update().clone();
+        let event = Event::default()
+            .event(Countdown::NAME)
+            .json_data(&current)
+            .unwrap_or_default();
+        Some((Ok(event), countdown))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Summarize saved payments, optionally between `?from=` and `?to=` (YYYYMMDD)
+/// and for one child with `?childId=`
+async fn summary_handler(
+    State(state): State<Arc<AppState>>,
+    Query(range): Query<SummaryRange>,
+) -> Result<Json<Summary>, (StatusCode, String)> {
+    handlers::get_summary(state, range)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+struct ExplainRequest {
+    figure: SummaryFigure,
+    #[serde(default)]
+    range: SummaryRange,
+}
+
+/// The payments adding up to one figure of the summary
+async fn explain_totals_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ExplainRequest>,
+) -> Result<Json<TotalExplanation>, (StatusCode, String)> {
+    handlers::explain_totals(state, request.range, request.figure)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Compare what-if scenarios planned from today
+async fn simulate_handler(
+    State(state): State<Arc<AppState>>,
+    Json(scenarios): Json<Vec<Scenario>>,
+) -> Result<Json<SimulationResult>, (StatusCode, String)> {
+    handlers::simulate(state, scenarios, date::today())
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Simulation(_) => (StatusCode::BAD_REQUEST, e.to_string()),
+            HandlerError::NoData => (StatusCode::NOT_FOUND, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Schedule conflicts in the saved data from today
+async fn analysis_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ScheduleWarning>>, (StatusCode, String)> {
+    handlers::analyze_schedule(state, date::today())
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Why the advisor recommends the action with explanation id `id`
+async fn explanation_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Explanation>, (StatusCode, String)> {
+    handlers::get_explanation(state, id)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::UnknownExplanation(_) => (StatusCode::NOT_FOUND, e.to_string()),
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Exam, result and payment timeline of each school
+async fn timeline_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Timeline>, (StatusCode, String)> {
+    handlers::get_timeline(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Outstanding and settled payments, optionally between `?from=` and `?to=`
+/// and for one child with `?childId=`
+async fn outstanding_payments_handler(
+    State(state): State<Arc<AppState>>,
+    Query(range): Query<SummaryRange>,
+) -> Result<Json<OutstandingPayments>, (StatusCode, String)> {
+    handlers::get_outstanding_payments(state, range)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Whether a change is only checked and its diff returned
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRunQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Record a payment as made, or with `?dryRun=true` show what would change
+async fn mark_paid_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DryRunQuery>,
+    Json(record): Json<PaymentRecord>,
+) -> Result<Json<ChangeReport<Dataset>>, Response> {
+    handlers::mark_paid(state, record, query.dry_run)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Payment(PaymentError::UnknownSchool(_)) => {
+                (StatusCode::NOT_FOUND, e.to_string()).into_response()
+            }
+            HandlerError::Payment(_) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            _ => data_error(e),
+        })
+}
+
+/// Body of an exam result to record
+#[derive(Debug, Deserialize)]
+struct OutcomeBody {
+    outcome: ExamOutcome,
+}
+
+/// Record an exam result, or with `?dryRun=true` show what would change
+async fn record_outcome_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Query(query): Query<DryRunQuery>,
+    Json(body): Json<OutcomeBody>,
+) -> Result<Json<ChangeReport<RecordedOutcome>>, Response> {
+    handlers::record_outcome(state, id, body.outcome, query.dry_run, date::today())
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Outcome(_) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+            _ => data_error(e),
+        })
+}
+
+/// Body of a note to add to the school in the path
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NoteBody {
+    text: String,
+    #[serde(default)]
+    fee: Option<FeeKind>,
+    #[serde(default)]
+    installment: Option<u32>,
+}
+
+fn note_error(e: HandlerError) -> Response {
+    match e {
+        HandlerError::Note(NoteError::UnknownSchool(_) | NoteError::UnknownNote { .. }) => {
+            (StatusCode::NOT_FOUND, e.to_string()).into_response()
+        }
+        HandlerError::Note(_) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        _ => data_error(e),
+    }
+}
+
+/// Add a note to a school, or with `?dryRun=true` show what would change
+async fn add_note_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Query(query): Query<DryRunQuery>,
+    Json(body): Json<NoteBody>,
+) -> Result<Json<ChangeReport<Dataset>>, Response> {
+    let note = NewNote {
+        school_id: id,
+        text: body.text,
+        fee: body.fee,
+        installment: body.installment,
+    };
+    handlers::add_note(state, note, query.dry_run)
+        .await
+        .map(Json)
+        .map_err(note_error)
+}
+
+/// Delete a school's note by its position, or with `?dryRun=true` show
+/// what would change
+async fn delete_note_handler(
+    State(state): State<Arc<AppState>>,
+    Path((id, index)): Path<(u64, usize)>,
+    Query(query): Query<DryRunQuery>,
+) -> Result<Json<ChangeReport<Dataset>>, Response> {
+    handlers::delete_note(state, id, index, query.dry_run)
+        .await
+        .map(Json)
+        .map_err(note_error)
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+}
+
+/// School names, notes and payment memos matching every word of `?q`
+async fn search_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchHit>>, (StatusCode, String)> {
+    handlers::search(state, &query.q)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+fn push_error(e: HandlerError) -> (StatusCode, String) {
+    match e {
+        HandlerError::WebPush(WebPushError::InvalidSubscription(_)) => {
+            (StatusCode::BAD_REQUEST, e.to_string())
+        }
+        HandlerError::PushUnavailable => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PushKey {
+    public_key: String,
+}
+
+/// Public VAPID key, for `pushManager.subscribe({ applicationServerKey })`
+async fn push_key_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PushKey>, (StatusCode, String)> {
+    handlers::push_public_key(state)
+        .await
+        .map(|public_key| Json(PushKey { public_key }))
+        .map_err(push_error)
+}
+
+#[derive(Deserialize)]
+struct PushUserQuery {
+    user: Option<String>,
+}
+
+/// Saved push subscriptions of `?user`, or everyone's
+async fn list_push_subscriptions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PushUserQuery>,
+) -> Result<Json<Vec<PushSubscription>>, (StatusCode, String)> {
+    handlers::list_push_subscriptions(state, query.user.as_deref())
+        .await
+        .map(Json)
+        .map_err(push_error)
+}
+
+/// Save the subscription a browser made with the public key
+async fn subscribe_push_handler(
+    State(state): State<Arc<AppState>>,
+    Json(subscription): Json<NewPushSubscription>,
+) -> Result<(StatusCode, Json<PushSubscription>), (StatusCode, String)> {
+    handlers::subscribe_push(state, subscription)
+        .await
+        .map(|saved| (StatusCode::CREATED, Json(saved)))
+        .map_err(push_error)
+}
+
+#[derive(Deserialize)]
+struct PushEndpointQuery {
+    endpoint: String,
+}
+
+/// Forget the subscription for `?endpoint`, after the browser unsubscribed
+async fn unsubscribe_push_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PushEndpointQuery>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match handlers::unsubscribe_push(state, &query.endpoint).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((StatusCode::NOT_FOUND, "Push subscription not found".to_string())),
+        Err(e) => Err(push_error(e)),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PushDelivery {
+    delivered: usize,
+}
+
+/// Push a test notification to `?user`, or everyone
+async fn test_push_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PushUserQuery>,
+) -> Result<Json<PushDelivery>, (StatusCode, String)> {
+    handlers::send_test_push(state, query.user.as_deref())
+        .await
+        .map(|delivered| Json(PushDelivery { delivered }))
+        .map_err(push_error)
+}
+
+/// Whether this server or the desktop app holds the edit lock
+async fn edit_lock_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EditLockStatus>, (StatusCode, String)> {
+    handlers::edit_lock_status(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Take or renew the edit lock; read-only if the desktop app holds it
+async fn acquire_edit_lock_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EditLockStatus>, (StatusCode, String)> {
+    handlers::acquire_edit_lock(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Give up the edit lock
+async fn release_edit_lock_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EditLockStatus>, (StatusCode, String)> {
+    handlers::release_edit_lock(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Entries of the audit log, oldest first
+async fn audit_log_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    handlers::list_audit_log(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Check the audit log for changed, removed or reordered entries
+async fn verify_audit_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AuditVerification>, (StatusCode, String)> {
+    handlers::verify_audit_log(state)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::AuditUnavailable => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// File name under which an uploaded backup was kept
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReceivedBackup {
+    file_name: String,
+}
+
+/// Keep a sealed backup pushed by a client; the server cannot read it
+async fn receive_cloud_backup_handler(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ReceivedBackup>), (StatusCode, String)> {
+    handlers::receive_cloud_backup(state, &body)
+        .await
+        .map(|file_name| (StatusCode::CREATED, Json(ReceivedBackup { file_name })))
+        .map_err(|e| match e {
+            HandlerError::EmptyBackup => (StatusCode::BAD_REQUEST, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// How far the first-run setup wizard has got
+async fn onboarding_state_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<OnboardingState>, (StatusCode, String)> {
+    handlers::onboarding_state(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Mark a setup wizard step done, returning the progress so far
+async fn complete_onboarding_step_handler(
+    State(state): State<Arc<AppState>>,
+    Path(step): Path<OnboardingStep>,
+) -> Result<Json<OnboardingState>, (StatusCode, String)> {
+    handlers::complete_onboarding_step(state, step)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Onboarding(_) | HandlerError::NoData => {
+                (StatusCode::CONFLICT, e.to_string())
+            }
+            HandlerError::Repl(_) | HandlerError::Advisor(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, e.to_string())
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Split tuition into installments
+async fn plan_installments_handler(
+    Json(plan): Json<InstallmentPlan>,
+) -> Result<Json<Vec<Installment>>, (StatusCode, String)> {
+    handlers::plan_installments(plan)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Payments of recurring fees for the coming year
+async fn expand_recurring_handler(
+    Json(fees): Json<Vec<RecurringFee>>,
+) -> Result<Json<Vec<RecurringPayment>>, (StatusCode, String)> {
+    handlers::expand_recurring_fees(fees, date::today())
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Generate the upcoming recurring payments of the saved data
+async fn refresh_recurring_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChangeReport<Dataset>>, Response> {
+    handlers::refresh_recurring_payments(state, date::today())
+        .await
+        .map(Json)
+        .map_err(data_error)
+}
+
+/// Bundled and imported school templates
+async fn list_templates_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SchoolTemplate>>, (StatusCode, String)> {
+    handlers::list_templates(state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Import a template catalog sent as the JSON body
+async fn import_templates_handler(
+    State(state): State<Arc<AppState>>,
+    body: String,
+) -> Result<Json<Vec<SchoolTemplate>>, (StatusCode, String)> {
+    handlers::import_templates(state, body.as_bytes())
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Template(TemplateError::InvalidCatalog(_)) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Days banks are closed in `year` other than weekends
+async fn list_holidays_handler(
+    State(state): State<Arc<AppState>>,
+    Path(year): Path<u32>,
+) -> Result<Json<Vec<Holiday>>, (StatusCode, String)> {
+    handlers::list_holidays(state, year)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Import a holiday calendar sent as the JSON body, returning the days
+/// banks are closed in `year`
+async fn import_holidays_handler(
+    State(state): State<Arc<AppState>>,
+    Path(year): Path<u32>,
+    body: String,
+) -> Result<Json<Vec<Holiday>>, (StatusCode, String)> {
+    handlers::import_holidays(state, body.as_bytes(), year)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Calendar(CalendarError::InvalidCalendar(_)) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Body of a template application
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApplyTemplateBody {
+    exam_date: u32,
+    name: Option<String>,
+    /// `false` to save the school; by default it is only returned
+    dry_run: Option<bool>,
+}
+
+/// New school from a template, for the client to review, or saved
+async fn apply_template_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<ApplyTemplateBody>,
+) -> Result<Json<ChangeReport<School>>, Response> {
+    let dry_run = body.dry_run.unwrap_or(true);
+    handlers::apply_template(state, &id, body.exam_date, body.name.as_deref(), dry_run)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::Template(TemplateError::UnknownTemplate(_)) => {
+                (StatusCode::NOT_FOUND, e.to_string()).into_response()
+            }
+            HandlerError::Template(TemplateError::InvalidExamDate(_)) => {
+                (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+            }
+            _ => data_error(e),
+        })
+}
+
+/// List recorded recommendations, newest first unless sorted
+async fn list_history_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, (StatusCode, String)> {
+    let query =
+        ListQuery::from_params(&params).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    handlers::list_history(state, &query)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Stream every matching history entry as NDJSON chunks
+async fn stream_history_handler(
+    State(state): State<Arc<AppState>>,
+    Query(mut params): Query<HashMap<String, String>>,
+) -> Result<Response, (StatusCode, String)> {
+    let chunk_size = match params.remove("chunkSize") {
+        Some(size) => size
+            .parse()
+            .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid chunkSize: {}", size)))?,
+        None => DEFAULT_CHUNK_SIZE,
+    };
+    let query =
+        ListQuery::from_params(&params).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    handlers::stream_history(state, &query, chunk_size)
+        .await
+        .map(ndjson)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Body of a decision request
+#[derive(Debug, Deserialize)]
+struct DecisionBody {
+    decision: Option<Decision>,
+    note: Option<String>,
+}
+
+/// Record what the user did about a recommendation
+async fn decide_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(body): Json<DecisionBody>,
+) -> Result<Json<HistoryEntry>, (StatusCode, String)> {
+    handlers::decide_recommendation(state, id, body.decision, body.note)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::History(HistoryError::UnknownEntry(_)) => (StatusCode::NOT_FOUND, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// The exact advisor parameters a recommendation was computed from
+async fn recommendation_inputs_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<RecommendationInputs>, (StatusCode, String)> {
+    handlers::get_recommendation_inputs(state, id)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            HandlerError::History(HistoryError::UnknownEntry(_) | HistoryError::NoInputs(_)) => {
+                (StatusCode::NOT_FOUND, e.to_string())
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// Season totals of the recommendation history
+async fn history_stats_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HistoryStats>, (StatusCode, String)> {
+    handlers::history_stats(state, date::today())
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Payments made in `year`, as JSON or downloaded as CSV or PDF
+async fn tax_report_handler(
+    State(state): State<Arc<AppState>>,
+    Path(year): Path<u32>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let format = match negotiate_format(&query, &headers) {
+        Ok(format) => format,
+        Err(rejection) => return rejection.into_response(),
+    };
+    match handlers::export_tax_report(state, year, format).await {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"tax-report-{}.{}\"", year, format.extension()),
+                ),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(HandlerError::Export(e @ ExportError::Unsupported(_))) => {
+            (StatusCode::NOT_ACCEPTABLE, e.to_string()).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Query parameters for making a share link
+#[derive(Debug, Deserialize)]
+struct ShareQuery {
+    days: Option<u32>,
+}
+
+/// Make a read-only link to a snapshot of the payment schedule
+async fn create_share_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ShareQuery>,
+) -> Result<(StatusCode, Json<ShareLink>), (StatusCode, String)> {
+    handlers::create_share(state, query.days.unwrap_or(DEFAULT_SHARE_DAYS))
+        .await
+        .map(|link| (StatusCode::CREATED, Json(link)))
+        .map_err(|e| match e {
+            HandlerError::Share(ShareError::InvalidDays(_)) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            HandlerError::SharingUnavailable => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })
+}
+
+/// The snapshot behind a share link, as a web page when a browser opens it
+/// and as JSON otherwise. Needs no account; the token is the only secret.
+async fn shared_schedule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let html = match query.format.as_deref() {
+        Some("html") => true,
+        Some("json") => false,
+        Some(name) => {
+            return (StatusCode::BAD_REQUEST, format!("Unknown format: {}", name)).into_response()
+        }
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/html")),
+    };
+    let schedule = match handlers::shared_schedule(state, &token).await {
+        Ok(schedule) => schedule,
+        Err(e @ HandlerError::Share(ShareError::InvalidToken)) => {
+            return (StatusCode::NOT_FOUND, e.to_string()).into_response()
+        }
+        Err(e @ HandlerError::Share(ShareError::Expired(_))) => {
+            return (StatusCode::GONE, e.to_string()).into_response()
+        }
+        Err(e @ HandlerError::SharingUnavailable) => {
+            return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response()
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    // Keep the token out of caches and of the Referer sent to other sites
+    let private = [
+        (header::CACHE_CONTROL, "no-store"),
+        (header::REFERRER_POLICY, "no-referrer"),
+    ];
+    if html {
+        (private, axum::response::Html(share::render_html(&schedule))).into_response()
+    } else {
+        (private, Json(schedule)).into_response()
+    }
+}
+
+/// Download the recommendation history
+async fn export_history_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let format = match negotiate_format(&query, &headers) {
+        Ok(format) => format,
+        Err(rejection) => return rejection.into_response(),
+    };
+    match handlers::export_history(state, format).await {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"history.{}\"", format.extension()),
+                ),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(HandlerError::Export(e @ ExportError::Unsupported(_))) => {
+            (StatusCode::NOT_ACCEPTABLE, e.to_string()).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Query parameters for sample data requests
+#[derive(Debug, Deserialize)]
+struct SampleQuery {
+    profile: Option<String>,
+}
+
+/// Generate a sample dataset (debug builds only)
+async fn sample_data_handler(
+    Query(query): Query<SampleQuery>,
+) -> Result<Json<Dataset>, (StatusCode, String)> {
+    let profile = match query.profile.as_deref() {
+        Some(name) => SampleProfile::from_name(name).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Unknown sample profile: {}", name),
+            )
+        })?,
+        None => SampleProfile::default(),
+    };
+    Ok(Json(handlers::generate_sample_data(profile)))
+}
+
 /// Graceful shutdown signal handler
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -148,3 +1783,63 @@ async fn shutdown_signal() {
 
     tracing::info!("Shutting down...");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use rust_backend::{
+        lean_repl::LeanRepl,
+        storage::{Storage, SCHOOLS_DATA_FILE},
+    };
+
+    fn unpaid_school(id: u64) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "name": format!("School {}", id), "priority": id,
+            "examDate": 20260215, "resultDate": 20260301,
+            "enrollmentFeeDeadline": 20260306, "tuitionDeadline": 20260320,
+            "enrollmentFee": 200000, "tuition": 800000,
+            "passStatus": "passed", "enrollmentFeePaid": false, "tuitionPaid": false
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 3)]
+    async fn test_stale_payment_gets_409_with_the_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf());
+        let state = Arc::new(AppState::new(LeanRepl::demo()).with_storage(storage.clone()));
+        let unpaid = [(1, "enrollmentFee"), (1, "tuition"), (2, "tuition")];
+
+        // Payments recorded at once from several devices save over each
+        // other's revision until one of them is refused
+        for _ in 0..50 {
+            let data = serde_json::json!({ "schools": [unpaid_school(1), unpaid_school(2)] });
+            storage.save(SCHOOLS_DATA_FILE, &data).unwrap();
+            let tasks = unpaid.map(|(school_id, fee)| {
+                let record: PaymentRecord = serde_json::from_value(serde_json::json!({
+                    "schoolId": school_id, "fee": fee,
+                    "paidOn": 20260312, "method": "bankTransfer"
+                }))
+                .unwrap();
+                let query = Query(DryRunQuery { dry_run: false });
+                tokio::spawn(mark_paid_handler(State(state.clone()), query, Json(record)))
+            });
+            let mut refused = None;
+            for task in tasks {
+                if let Err(response) = task.await.unwrap() {
+                    refused = Some(response);
+                }
+            }
+            let Some(response) = refused else {
+                continue;
+            };
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let conflict: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let (expected, current) = (&conflict["expected"], &conflict["current"]);
+            assert!(current.as_u64().unwrap() > expected.as_u64().unwrap());
+            return;
+        }
+        panic!("no payment was refused as stale");
+    }
+}